@@ -137,8 +137,8 @@ const FileDetailsModal: React.FC<FileDetailsModalProps> = ({ isOpen, onClose, fi
 
                 {isSingleFile && (
                     <>
-                        <DetailRow label="Created:" value={file.created_at} />
-                        <DetailRow label="Modified:" value={file.updated_at} />
+                        <DetailRow label="Created:" value={new Date(file.created_at).toLocaleString()} />
+                        <DetailRow label="Modified:" value={new Date(file.updated_at).toLocaleString()} />
                     </>
                 )}
             </div>
@@ -5,18 +5,141 @@ use std::path::PathBuf;
 // Default configuration constants
 const DEFAULT_MAX_CONNECTIONS: u32 = 5;
 const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+const DEFAULT_DB_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_DB_IDLE_TIMEOUT_SECS: u64 = 10 * 60;
+const DEFAULT_DB_SLOW_STATEMENT_MS: u64 = 1000;
+const DEFAULT_SQLITE_JOURNAL_MODE: &str = "wal";
+const DEFAULT_SQLITE_SYNCHRONOUS: &str = "normal";
+const DEFAULT_SQLITE_BUSY_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_SQLITE_CACHE_SIZE: i64 = -64 * 1024; // 64MB, negative values are KiB
 const DEFAULT_JWT_EXPIRATION_HOURS: i64 = 24;
+const DEFAULT_JWT_ALGORITHM: &str = "HS256";
 const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_LOG_FORMAT: &str = "text";
 const DEFAULT_STORAGE_DIR: &str = "storage";
+const DEFAULT_STORAGE_PLACEMENT: &str = "most_free_space";
+const DEFAULT_STORAGE_RESERVE_BYTES: u64 = 512 * 1024 * 1024; // 512MB
+const DEFAULT_STORAGE_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+const DEFAULT_FOLDER_SIZE_RECONCILE_INTERVAL_SECS: u64 = 3600;
 const DEFAULT_MAX_UPLOAD_SIZE: usize = 5 * 1024 * 1024 * 1024; // 5GB
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_SLOW_REQUEST_MS: u64 = 2000;
+const DEFAULT_MAX_IN_FLIGHT: usize = 256;
+const DEFAULT_MAX_QUEUED: usize = 512;
+const DEFAULT_QUEUE_TIMEOUT_MS: u64 = 2000;
+const DEFAULT_SHED_RETRY_AFTER_SECS: u64 = 1;
 const DEFAULT_MAX_BATCH_DOWNLOAD_SIZE: usize = 5 * 1024 * 1024 * 1024; // 5GB
 const DEFAULT_COMPRESSION_THRESHOLD: usize = 256 * 1024 * 1024; // 256MB
+const DEFAULT_ARCHIVE_TTL_SECS: i64 = 24 * 60 * 60;
+const DEFAULT_ARCHIVE_SWEEP_INTERVAL_SECS: u64 = 5 * 60;
+const DEFAULT_ARGON2_MEMORY_KIB: u32 = 19 * 1024; // OWASP recommended minimum
+const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
+const DEFAULT_USERNAME_MIN_LENGTH: usize = 3;
+const DEFAULT_USERNAME_MAX_LENGTH: usize = 32;
+const DEFAULT_USERNAME_ALLOWED_SYMBOLS: &str = "_-.";
+const DEFAULT_PASSWORD_MIN_LENGTH: usize = 8;
+const DEFAULT_PASSWORD_MAX_LENGTH: usize = 128;
+const DEFAULT_PASSWORD_MIN_SCORE: u8 = 2;
+const DEFAULT_FILENAME_MAX_LENGTH: usize = 255;
+const DEFAULT_SESSION_COOKIE_NAME: &str = "cd_session";
+const DEFAULT_CSRF_COOKIE_NAME: &str = "cd_csrf";
+const DEFAULT_SESSION_SAME_SITE: &str = "Strict";
+const DEFAULT_SMTP_PORT: u16 = 587;
+const DEFAULT_SMTP_TLS: &str = "starttls";
+const DEFAULT_SMTP_FROM: &str = "Cloud Drive <noreply@localhost>";
+const DEFAULT_OUTBOX_POLL_INTERVAL_SECS: u64 = 15;
+const DEFAULT_OUTBOX_MAX_ATTEMPTS: i32 = 6;
+const DEFAULT_STAGING_DIR_NAME: &str = ".staging";
+const DEFAULT_STAGING_MAX_BYTES: u64 = 10 * 1024 * 1024 * 1024; // 10GB
+const DEFAULT_STAGING_MIN_FREE_BYTES: u64 = 1024 * 1024 * 1024; // 1GB
+const DEFAULT_STAGING_STALE_AFTER_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_STAGING_SWEEP_INTERVAL_SECS: u64 = 10 * 60;
+const DEFAULT_MOUNT_INDEX_INTERVAL_SECS: u64 = 15 * 60;
+const DEFAULT_DELETION_GRACE_PERIOD_DAYS: i64 = 14;
+const DEFAULT_DELETION_SWEEP_INTERVAL_SECS: u64 = 60 * 60;
+const DEFAULT_CLEANUP_INTERVAL_SECS: u64 = 60 * 60;
+const DEFAULT_CLEANUP_NOTICE_HOURS: i64 = 24;
+const DEFAULT_JOBS_MAX_CONCURRENT: usize = 4;
+const DEFAULT_QUOTA_WARN_PERCENTS: [u64; 2] = [90, 100];
+const DEFAULT_USAGE_HISTORY_INTERVAL_SECS: u64 = 60 * 60;
+const DEFAULT_USAGE_HISTORY_RETENTION_DAYS: i64 = 400;
+const DEFAULT_BACKUP_INTERVAL_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_BACKUP_FULL_EVERY_RUNS: u64 = 30;
+const DEFAULT_REPLICATION_INTERVAL_SECS: u64 = 60;
+const DEFAULT_REPLICATION_BATCH_SIZE: u64 = 100;
+const DEFAULT_PROCESSING_MAX_FILE_SIZE: u64 = 32 * 1024 * 1024; // 32MB
+const DEFAULT_PROCESSING_HOOK_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_SEARCH_BACKEND: &str = "database";
+const DEFAULT_SEARCH_INDEX: &str = "cloud_drive_files";
+const DEFAULT_SEARCH_SYNC_INTERVAL_SECS: u64 = 10;
+const DEFAULT_GZIP_MIN_SIZE: u64 = 1024; // 1KB
+const DEFAULT_GZIP_MAX_SIZE: u64 = 64 * 1024 * 1024; // 64MB
+const DEFAULT_CACHE_CONTROL: &str = "private, no-store";
+const DEFAULT_PRESIGNED_TTL_SECS: i64 = 900; // 15 minutes
+const DEFAULT_PRESIGNED_MAX_TTL_SECS: i64 = 86400; // 1 day
+const DEFAULT_SHARE_LINK_MAX_HOURS: i64 = 24 * 365; // 1 year
+const DEFAULT_IMMUTABLE_CACHE_CONTROL: &str = "private, max-age=31536000, immutable";
+const DEFAULT_MEDIA_CACHE_CONTROL: &str = "private, max-age=86400";
+const DEFAULT_ACTIVE_MIME_TYPES: [&str; 7] = [
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/xml",
+    "application/xml",
+    "text/javascript",
+    "application/javascript",
+];
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     pub address: String,
     #[serde(default = "default_max_upload_size")]
     pub max_upload_size: usize,
+    /// Upload size per role, e.g. `{ role = "admin", max_bytes = 0 }` for unlimited admin uploads
+    /// Roles without a rule are held to `max_upload_size`
+    #[serde(default)]
+    pub upload_limits: Vec<UploadLimit>,
+    /// Take client IPs from the last `X-Forwarded-For` entry (only behind a single reverse proxy)
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+    /// Abort requests after this long, 0 disables it (transfers and storage scans are exempt)
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Requests slower than this are logged as warnings, 0 disables it
+    #[serde(default = "default_slow_request_ms")]
+    pub slow_request_ms: u64,
+    /// Base URL clients reach the server at, used for links in emails and share previews
+    #[serde(default)]
+    pub public_url: Option<String>,
+}
+
+impl ServerConfig {
+    /// Base URL for links handed out to clients, without a trailing slash
+    pub fn public_base_url(&self) -> String {
+        match &self.public_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("http://{}", self.address),
+        }
+    }
+
+    /// Largest upload in bytes a user with `role` may send, `None` when unlimited
+    pub fn max_upload_size_for(&self, role: &str) -> Option<u64> {
+        let max_bytes = self
+            .upload_limits
+            .iter()
+            .find(|l| l.role == role)
+            .map_or(self.max_upload_size as u64, |l| l.max_bytes);
+        (max_bytes > 0).then_some(max_bytes)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadLimit {
+    pub role: String,
+    /// Maximum upload size in bytes, 0 for unlimited
+    pub max_bytes: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -26,13 +149,100 @@ pub struct DatabaseConfig {
     pub max_connections: u32,
     #[serde(default = "default_min_connections")]
     pub min_connections: u32,
+    /// Give up opening a new connection after this long
+    #[serde(default = "default_db_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Fail a query that waited this long for a free connection
+    #[serde(default = "default_db_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+    /// Close connections idle for longer than this, down to `min_connections`
+    #[serde(default = "default_db_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// Log every statement at debug level
+    #[serde(default)]
+    pub log_statements: bool,
+    /// Statements slower than this are logged as warnings, 0 disables it
+    #[serde(default = "default_db_slow_statement_ms")]
+    pub slow_statement_ms: u64,
+    #[serde(default = "default_sqlite_config")]
+    pub sqlite: SqliteConfig,
+}
+
+/// Pragmas applied to every SQLite connection
+#[derive(Debug, Clone, Deserialize)]
+pub struct SqliteConfig {
+    /// "wal" (default), "delete", "truncate", "persist", "memory" or "off"
+    #[serde(default = "default_sqlite_journal_mode")]
+    pub journal_mode: String,
+    /// "normal" (default), "full", "extra" or "off"
+    #[serde(default = "default_sqlite_synchronous")]
+    pub synchronous: String,
+    /// Wait this long for a locked database before failing with SQLITE_BUSY
+    #[serde(default = "default_sqlite_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+    /// Page cache per connection, in pages when positive or KiB when negative
+    #[serde(default = "default_sqlite_cache_size")]
+    pub cache_size: i64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct SecurityConfig {
+    /// Default signing secret, identified by the `default` kid
     pub jwt_secret: String,
+    /// Tokens signed with `jwt_secret` are rejected after this instant
+    #[serde(default)]
+    pub jwt_secret_retire_at: Option<chrono::DateTime<chrono::Utc>>,
     #[serde(default = "default_jwt_expiration_hours")]
     pub jwt_expiration_hours: i64,
+    /// Additional signing keys for key rotation and asymmetric signing
+    #[serde(default)]
+    pub jwt_keys: Vec<JwtKeyConfig>,
+    /// Kid of the key used to sign new tokens (defaults to `jwt_secret`)
+    #[serde(default)]
+    pub jwt_active_kid: Option<String>,
+    /// Optional HttpOnly cookie sessions for the browser UI
+    #[serde(default = "default_session_cookie_config")]
+    pub session_cookie: SessionCookieConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionCookieConfig {
+    /// Allow clients to log in with a session cookie instead of a Bearer token
+    #[serde(default)]
+    pub enabled: bool,
+    /// Name of the HttpOnly cookie holding the session token
+    #[serde(default = "default_session_cookie_name")]
+    pub cookie_name: String,
+    /// Name of the script-readable cookie holding the CSRF token
+    #[serde(default = "default_csrf_cookie_name")]
+    pub csrf_cookie_name: String,
+    /// SameSite attribute: Strict, Lax or None
+    #[serde(default = "default_session_same_site")]
+    pub same_site: String,
+    /// Only send cookies over HTTPS (disable for plain-HTTP local setups)
+    #[serde(default = "default_true")]
+    pub secure: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtKeyConfig {
+    /// Key identifier written to the token `kid` header
+    pub kid: String,
+    /// Signing algorithm: HS256, RS256 or EdDSA
+    #[serde(default = "default_jwt_algorithm")]
+    pub algorithm: String,
+    /// Shared secret (HS256 only)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// PEM private key file (RS256/EdDSA), only needed for the active key
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+    /// PEM public key file (RS256/EdDSA), published at `/.well-known/jwks.json`
+    #[serde(default)]
+    pub public_key_path: Option<String>,
+    /// Tokens signed with this key are rejected after this instant
+    #[serde(default)]
+    pub retire_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -43,12 +253,56 @@ pub struct LoggingConfig {
     pub log_dir: Option<String>,
     #[serde(default)]
     pub log_to_file: bool,
+    /// "text" or "json", one object per line with the fields of the event and its spans
+    #[serde(default = "default_log_format")]
+    pub format: String,
+    /// Also send every log line as JSON to this syslog server over UDP, e.g. "127.0.0.1:514"
+    #[serde(default)]
+    pub syslog: Option<String>,
+    /// Also POST log lines as newline-delimited JSON batches to this http:// URL
+    #[serde(default)]
+    pub http_endpoint: Option<String>,
+}
+
+impl LoggingConfig {
+    pub fn is_json(&self) -> bool {
+        self.format.eq_ignore_ascii_case("json")
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct StorageConfig {
     #[serde(default = "default_storage_dir")]
     pub dir: String,
+    /// Free space kept on the storage volume, writes that would use it are rejected
+    #[serde(default = "default_storage_reserve_bytes")]
+    pub reserve_bytes: u64,
+    /// Additional storage roots, usually on other disks
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    /// How a new user's volume is chosen: "most_free_space", "round_robin" or "per_user"
+    /// (only pinned users leave the primary volume)
+    #[serde(default = "default_storage_placement")]
+    pub placement: String,
+    /// Users assigned to a fixed volume, applied when the user is first placed
+    #[serde(default)]
+    pub pins: Vec<VolumePin>,
+    /// How often each volume is probed with a canary write, read and delete
+    #[serde(default = "default_storage_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+    /// Place new users on the next healthy volume while their volume is down
+    /// Users already placed on a failed volume get 503 on writes until it recovers
+    #[serde(default)]
+    pub failover: bool,
+    /// How often cached folder sizes are recomputed from the files they contain
+    #[serde(default = "default_folder_size_reconcile_interval_secs")]
+    pub folder_size_reconcile_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VolumePin {
+    pub user_id: i32,
+    pub volume: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -59,6 +313,431 @@ pub struct BatchDownloadConfig {
     /// Size threshold above which compression is enabled
     #[serde(default = "default_compression_threshold")]
     pub compression_threshold: usize,
+    /// How long a prepared archive stays downloadable
+    #[serde(default = "default_archive_ttl_secs")]
+    pub archive_ttl_secs: i64,
+    /// How often expired archives are removed
+    #[serde(default = "default_archive_sweep_interval_secs")]
+    pub archive_sweep_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordHashingConfig {
+    /// Argon2id memory cost in KiB
+    #[serde(default = "default_argon2_memory_kib")]
+    pub argon2_memory_kib: u32,
+    /// Argon2id number of iterations
+    #[serde(default = "default_argon2_iterations")]
+    pub argon2_iterations: u32,
+    /// Argon2id degree of parallelism
+    #[serde(default = "default_argon2_parallelism")]
+    pub argon2_parallelism: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidationConfig {
+    /// Minimum username length in characters
+    #[serde(default = "default_username_min_length")]
+    pub username_min_length: usize,
+    /// Maximum username length in characters
+    #[serde(default = "default_username_max_length")]
+    pub username_max_length: usize,
+    /// Symbols allowed in usernames in addition to ASCII letters and digits
+    #[serde(default = "default_username_allowed_symbols")]
+    pub username_allowed_symbols: String,
+    /// Reject emails that don't look like `local@domain.tld`
+    #[serde(default = "default_true")]
+    pub check_email_format: bool,
+    /// Minimum password length in characters
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+    /// Maximum password length in characters
+    #[serde(default = "default_password_max_length")]
+    pub password_max_length: usize,
+    /// Minimum password strength score (0 = anything goes, 4 = very strong)
+    #[serde(default = "default_password_min_score")]
+    pub password_min_score: u8,
+    /// Maximum file and folder name length in bytes
+    #[serde(default = "default_filename_max_length")]
+    pub filename_max_length: usize,
+    /// Reject names Windows cannot store (`CON`, `NUL`, `COM1`, names ending in a dot or space)
+    #[serde(default = "default_true")]
+    pub filename_windows_compatible: bool,
+    /// Characters rejected in file and folder names besides path separators and control characters
+    #[serde(default)]
+    pub filename_forbidden_chars: String,
+    /// Additional names that cannot be used for files or folders, compared case-insensitively
+    #[serde(default)]
+    pub filename_reserved_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmtpConfig {
+    /// Send emails; when disabled, emails are only logged
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Transport security: starttls, tls or none
+    #[serde(default = "default_smtp_tls")]
+    pub tls: String,
+    /// Sender address, e.g. `Cloud Drive <noreply@example.com>`
+    #[serde(default = "default_smtp_from")]
+    pub from: String,
+    /// How often the outbox worker looks for emails to send
+    #[serde(default = "default_outbox_poll_interval_secs")]
+    pub outbox_poll_interval_secs: u64,
+    /// Delivery attempts before an email is marked failed
+    #[serde(default = "default_outbox_max_attempts")]
+    pub outbox_max_attempts: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StagingConfig {
+    /// Temp directory for chunked uploads, generated archives and conversions
+    /// Defaults to `.staging` inside the storage directory
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Total bytes temporary data may occupy
+    #[serde(default = "default_staging_max_bytes")]
+    pub max_bytes: u64,
+    /// Disk space that must stay free after writing temporary data
+    #[serde(default = "default_staging_min_free_bytes")]
+    pub min_free_bytes: u64,
+    /// Artifacts not modified for this long are removed by the sweeper
+    #[serde(default = "default_staging_stale_after_secs")]
+    pub stale_after_secs: u64,
+    /// How often the sweeper runs
+    #[serde(default = "default_staging_sweep_interval_secs")]
+    pub sweep_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadSheddingConfig {
+    /// Refuse requests with 503 once the limits below are reached
+    /// In-flight requests are counted either way
+    #[serde(default)]
+    pub enabled: bool,
+    /// Requests handled at the same time, later ones wait in the queue
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Requests that may wait for a free slot, beyond that they are refused right away
+    #[serde(default = "default_max_queued")]
+    pub max_queued: usize,
+    /// Give up waiting for a free slot after this long
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// Per-route limits, e.g. `{ route = "/api/files/search", max_in_flight = 8 }`
+    /// Requests over a route limit are refused without waiting
+    #[serde(default)]
+    pub routes: Vec<RouteLimit>,
+    /// Sent as `Retry-After` on refused requests
+    #[serde(default = "default_shed_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl LoadSheddingConfig {
+    /// Limit of concurrent requests on `route`, if any
+    pub fn route_limit(&self, route: &str) -> Option<usize> {
+        self.routes
+            .iter()
+            .find(|r| r.route == route)
+            .map(|r| r.max_in_flight)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteLimit {
+    /// Route as declared, e.g. "/api/files/:id/stat"
+    pub route: String,
+    pub max_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MountsConfig {
+    /// How often external folder mounts are re-indexed
+    #[serde(default = "default_mount_index_interval_secs")]
+    pub index_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountDeletionConfig {
+    /// Days between a deletion request and the purge, during which it can be cancelled
+    #[serde(default = "default_deletion_grace_period_days")]
+    pub grace_period_days: i64,
+    /// How often accounts past their grace period are purged
+    #[serde(default = "default_deletion_sweep_interval_secs")]
+    pub sweep_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CleanupConfig {
+    /// How often the users' cleanup rules are run
+    #[serde(default = "default_cleanup_interval_secs")]
+    pub interval_secs: u64,
+    /// Hours between the notice listing the files due and their deletion
+    #[serde(default = "default_cleanup_notice_hours")]
+    pub notice_hours: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageHistoryConfig {
+    /// How often today's storage snapshot of every user is taken again
+    #[serde(default = "default_usage_history_interval_secs")]
+    pub interval_secs: u64,
+    /// Snapshots older than this are removed
+    #[serde(default = "default_usage_history_retention_days")]
+    pub retention_days: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeaturesConfig {
+    /// Defaults per feature, e.g. `{ name = "public_sharing", enabled = false, roles = ["admin"] }`
+    /// Features not listed are on for everyone, admins can override them at runtime
+    #[serde(default)]
+    pub flags: Vec<FeatureFlagConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeatureFlagConfig {
+    pub name: String,
+    /// Whether the feature is on for roles not listed in `roles`
+    #[serde(default)]
+    pub enabled: bool,
+    /// Roles the feature is on for even when `enabled` is false
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuotaConfig {
+    /// Bytes each account may store, 0 for unlimited
+    #[serde(default)]
+    pub default_bytes: u64,
+    /// Quota per role, e.g. `{ role = "admin", max_bytes = 0 }` for unlimited admin storage
+    #[serde(default)]
+    pub roles: Vec<RoleQuota>,
+    /// Usage in percent of the quota that notifies the owner when an upload crosses it
+    #[serde(default = "default_quota_warn_percents")]
+    pub warn_percents: Vec<u64>,
+    /// Overage in percent of the quota still accepted before uploads are rejected
+    #[serde(default)]
+    pub grace_percent: u64,
+}
+
+impl QuotaConfig {
+    /// Bytes a user with `role` may store, `None` when unlimited
+    pub fn limit_for(&self, role: &str) -> Option<u64> {
+        let max_bytes = self
+            .roles
+            .iter()
+            .find(|q| q.role == role)
+            .map_or(self.default_bytes, |q| q.max_bytes);
+        (max_bytes > 0).then_some(max_bytes)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleQuota {
+    pub role: String,
+    /// Storage quota in bytes, 0 for unlimited
+    pub max_bytes: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobsConfig {
+    /// Background tasks running at the same time over all classes
+    /// When they are all busy, waiting tasks of higher priority classes go first
+    #[serde(default = "default_jobs_max_concurrent")]
+    pub max_concurrent: usize,
+    /// Per-class settings, e.g. `{ class = "replication", max_concurrent = 1, priority = "low" }`
+    /// Classes are hashing, thumbnailing, replication, processing and archive
+    #[serde(default)]
+    pub classes: Vec<JobClassConfig>,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        default_jobs_config()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobClassConfig {
+    pub class: String,
+    pub max_concurrent: Option<usize>,
+    /// low, normal or high
+    pub priority: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplicationConfig {
+    /// Mirror stored files to `target` in the background
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory on another disk receiving the replicas, stored by content hash
+    #[serde(default)]
+    pub target: Option<String>,
+    /// How often the worker looks for files not yet replicated
+    #[serde(default = "default_replication_interval_secs")]
+    pub interval_secs: u64,
+    /// Files copied per round
+    #[serde(default = "default_replication_batch_size")]
+    pub batch_size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupConfig {
+    /// Back up user files to `target` on a schedule
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory receiving the backups, e.g. an external disk or an rclone mount
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Time between two runs
+    #[serde(default = "default_backup_interval_secs")]
+    pub interval_secs: u64,
+    /// Every this many runs is a full one, the others only copy what changed
+    #[serde(default = "default_backup_full_every_runs")]
+    pub full_every_runs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingConfig {
+    /// Processors run on each uploaded file whose MIME type they match
+    #[serde(default)]
+    pub hooks: Vec<ProcessingHook>,
+    /// Larger files are not handed to processors
+    #[serde(default = "default_processing_max_file_size")]
+    pub max_file_size: u64,
+}
+
+/// External processor deriving labels and metadata from file content
+///
+/// It answers with JSON like `{"labels": ["cat"], "metadata": {"score": 0.9}}`,
+/// either on stdout for a command or as the response body for a webhook.
+/// A scanner adds `"threat": "<name>"` to quarantine the file
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingHook {
+    /// Recorded as the source of what the hook produced
+    pub name: String,
+    /// MIME type or class, e.g. `application/pdf` or `image/*`
+    pub mime: String,
+    /// Local program and its arguments, run with the stored file's path appended
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// `http://` endpoint the file content is POSTed to
+    pub webhook: Option<String>,
+    #[serde(default = "default_processing_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchConfig {
+    /// "database" for SQL name matching, "meilisearch" or "elasticsearch" for an external engine
+    #[serde(default = "default_search_backend")]
+    pub backend: String,
+    /// `http://` address of the search engine, e.g. `http://127.0.0.1:7700`
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+    #[serde(default = "default_search_index")]
+    pub index: String,
+    /// How often file changes are pushed to the search engine
+    #[serde(default = "default_search_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadConfig {
+    /// Gzip text-like files on the fly when the client sends `Accept-Encoding: gzip`
+    #[serde(default = "default_true")]
+    pub gzip_enabled: bool,
+    /// Smaller files are sent as is, the savings don't pay for the overhead
+    #[serde(default = "default_gzip_min_size")]
+    pub gzip_min_size: u64,
+    /// Larger files are sent as is to bound the CPU spent per download
+    #[serde(default = "default_gzip_max_size")]
+    pub gzip_max_size: u64,
+    /// Cache-Control per MIME class, the first matching rule wins
+    #[serde(default = "default_cache_rules")]
+    pub cache_rules: Vec<CacheRule>,
+    /// Cache-Control for files no rule matches
+    #[serde(default = "default_cache_control")]
+    pub default_cache_control: String,
+    /// Cache-Control for downloads pinned to a version, whose content can never change
+    #[serde(default = "default_immutable_cache_control")]
+    pub immutable_cache_control: String,
+    /// MIME types a browser may run scripts from, sent as attachments unless on `content_domain`
+    #[serde(default = "default_active_mime_types")]
+    pub active_mime_types: Vec<String>,
+    /// Host serving nothing but user content, where active content is shown inline in a sandbox
+    #[serde(default)]
+    pub content_domain: Option<String>,
+    /// Lifetime of presigned download URLs when the request doesn't set one
+    #[serde(default = "default_presigned_ttl_secs")]
+    pub presigned_ttl_secs: i64,
+    /// Longest lifetime a presigned download URL may be given
+    #[serde(default = "default_presigned_max_ttl_secs")]
+    pub presigned_max_ttl_secs: i64,
+    /// Longest lifetime a public share link may be given
+    #[serde(default = "default_share_link_max_hours")]
+    pub share_link_max_hours: i64,
+}
+
+impl DownloadConfig {
+    /// Cache-Control for a download of the given MIME type
+    pub fn cache_control_for(&self, mime_type: &str, immutable: bool) -> &str {
+        if immutable {
+            return &self.immutable_cache_control;
+        }
+        self.cache_rules
+            .iter()
+            .find(|r| crate::utils::http_cache::mime_matches(&r.mime, mime_type))
+            .map_or(&self.default_cache_control, |r| &r.cache_control)
+    }
+
+    /// Whether a browser could run scripts from a file of the given MIME type
+    pub fn is_active_content(&self, mime_type: &str) -> bool {
+        self.active_mime_types
+            .iter()
+            .any(|m| crate::utils::http_cache::mime_matches(m, mime_type))
+    }
+
+    /// Whether a request for `host` reached the dedicated content domain
+    pub fn is_content_domain(&self, host: Option<&str>) -> bool {
+        // Ports are ignored, bracketed IPv6 literals keep their colons
+        let strip_port = |h: &str| {
+            let h = h.trim();
+            match h.rsplit_once(':') {
+                Some((name, port))
+                    if port.chars().all(|c| c.is_ascii_digit())
+                        && (!name.contains(':') || name.ends_with(']')) =>
+                {
+                    name.to_string()
+                }
+                _ => h.to_string(),
+            }
+        };
+        match (&self.content_domain, host) {
+            (Some(domain), Some(host)) => {
+                strip_port(domain).eq_ignore_ascii_case(&strip_port(host))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheRule {
+    /// MIME type or class, e.g. `application/pdf` or `image/*`
+    pub mime: String,
+    pub cache_control: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -70,6 +749,40 @@ pub struct Config {
     pub storage: StorageConfig,
     #[serde(default = "default_batch_download_config")]
     pub batch_download: BatchDownloadConfig,
+    #[serde(default = "default_validation_config")]
+    pub validation: ValidationConfig,
+    #[serde(default = "default_password_hashing_config")]
+    pub password_hashing: PasswordHashingConfig,
+    #[serde(default = "default_smtp_config")]
+    pub smtp: SmtpConfig,
+    #[serde(default = "default_staging_config")]
+    pub staging: StagingConfig,
+    #[serde(default = "default_mounts_config")]
+    pub mounts: MountsConfig,
+    #[serde(default = "default_account_deletion_config")]
+    pub account_deletion: AccountDeletionConfig,
+    #[serde(default = "default_cleanup_config")]
+    pub cleanup: CleanupConfig,
+    #[serde(default = "default_jobs_config")]
+    pub jobs: JobsConfig,
+    #[serde(default = "default_quota_config")]
+    pub quota: QuotaConfig,
+    #[serde(default = "default_features_config")]
+    pub features: FeaturesConfig,
+    #[serde(default = "default_usage_history_config")]
+    pub usage_history: UsageHistoryConfig,
+    #[serde(default = "default_download_config")]
+    pub download: DownloadConfig,
+    #[serde(default = "default_replication_config")]
+    pub replication: ReplicationConfig,
+    #[serde(default = "default_backup_config")]
+    pub backup: BackupConfig,
+    #[serde(default = "default_processing_config")]
+    pub processing: ProcessingConfig,
+    #[serde(default = "default_search_config")]
+    pub search: SearchConfig,
+    #[serde(default = "default_load_shedding_config")]
+    pub load_shedding: LoadSheddingConfig,
 }
 
 // Default value functions (required by serde)
@@ -81,22 +794,117 @@ fn default_min_connections() -> u32 {
     DEFAULT_MIN_CONNECTIONS
 }
 
+fn default_db_connect_timeout_secs() -> u64 {
+    DEFAULT_DB_CONNECT_TIMEOUT_SECS
+}
+
+fn default_db_acquire_timeout_secs() -> u64 {
+    DEFAULT_DB_ACQUIRE_TIMEOUT_SECS
+}
+
+fn default_db_idle_timeout_secs() -> u64 {
+    DEFAULT_DB_IDLE_TIMEOUT_SECS
+}
+
+fn default_db_slow_statement_ms() -> u64 {
+    DEFAULT_DB_SLOW_STATEMENT_MS
+}
+
+fn default_sqlite_journal_mode() -> String {
+    DEFAULT_SQLITE_JOURNAL_MODE.to_string()
+}
+
+fn default_sqlite_synchronous() -> String {
+    DEFAULT_SQLITE_SYNCHRONOUS.to_string()
+}
+
+fn default_sqlite_busy_timeout_ms() -> u64 {
+    DEFAULT_SQLITE_BUSY_TIMEOUT_MS
+}
+
+fn default_sqlite_cache_size() -> i64 {
+    DEFAULT_SQLITE_CACHE_SIZE
+}
+
+fn default_sqlite_config() -> SqliteConfig {
+    SqliteConfig {
+        journal_mode: default_sqlite_journal_mode(),
+        synchronous: default_sqlite_synchronous(),
+        busy_timeout_ms: DEFAULT_SQLITE_BUSY_TIMEOUT_MS,
+        cache_size: DEFAULT_SQLITE_CACHE_SIZE,
+    }
+}
+
 fn default_jwt_expiration_hours() -> i64 {
     DEFAULT_JWT_EXPIRATION_HOURS
 }
 
+fn default_jwt_algorithm() -> String {
+    DEFAULT_JWT_ALGORITHM.to_string()
+}
+
+fn default_session_cookie_name() -> String {
+    DEFAULT_SESSION_COOKIE_NAME.to_string()
+}
+
+fn default_csrf_cookie_name() -> String {
+    DEFAULT_CSRF_COOKIE_NAME.to_string()
+}
+
+fn default_session_same_site() -> String {
+    DEFAULT_SESSION_SAME_SITE.to_string()
+}
+
+fn default_session_cookie_config() -> SessionCookieConfig {
+    SessionCookieConfig {
+        enabled: false,
+        cookie_name: DEFAULT_SESSION_COOKIE_NAME.to_string(),
+        csrf_cookie_name: DEFAULT_CSRF_COOKIE_NAME.to_string(),
+        same_site: DEFAULT_SESSION_SAME_SITE.to_string(),
+        secure: true,
+    }
+}
+
 fn default_log_level() -> String {
     DEFAULT_LOG_LEVEL.to_string()
 }
 
+fn default_log_format() -> String {
+    DEFAULT_LOG_FORMAT.to_string()
+}
+
 fn default_storage_dir() -> String {
     DEFAULT_STORAGE_DIR.to_string()
 }
 
+fn default_storage_reserve_bytes() -> u64 {
+    DEFAULT_STORAGE_RESERVE_BYTES
+}
+
+fn default_storage_health_check_interval_secs() -> u64 {
+    DEFAULT_STORAGE_HEALTH_CHECK_INTERVAL_SECS
+}
+
+fn default_folder_size_reconcile_interval_secs() -> u64 {
+    DEFAULT_FOLDER_SIZE_RECONCILE_INTERVAL_SECS
+}
+
+fn default_storage_placement() -> String {
+    DEFAULT_STORAGE_PLACEMENT.to_string()
+}
+
 fn default_max_upload_size() -> usize {
     DEFAULT_MAX_UPLOAD_SIZE
 }
 
+fn default_request_timeout_secs() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_SECS
+}
+
+fn default_slow_request_ms() -> u64 {
+    DEFAULT_SLOW_REQUEST_MS
+}
+
 fn default_max_batch_download_size() -> usize {
     DEFAULT_MAX_BATCH_DOWNLOAD_SIZE
 }
@@ -105,10 +913,390 @@ fn default_compression_threshold() -> usize {
     DEFAULT_COMPRESSION_THRESHOLD
 }
 
+fn default_archive_ttl_secs() -> i64 {
+    DEFAULT_ARCHIVE_TTL_SECS
+}
+
+fn default_archive_sweep_interval_secs() -> u64 {
+    DEFAULT_ARCHIVE_SWEEP_INTERVAL_SECS
+}
+
 fn default_batch_download_config() -> BatchDownloadConfig {
     BatchDownloadConfig {
         max_total_size: DEFAULT_MAX_BATCH_DOWNLOAD_SIZE,
         compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+        archive_ttl_secs: DEFAULT_ARCHIVE_TTL_SECS,
+        archive_sweep_interval_secs: DEFAULT_ARCHIVE_SWEEP_INTERVAL_SECS,
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_username_min_length() -> usize {
+    DEFAULT_USERNAME_MIN_LENGTH
+}
+
+fn default_username_max_length() -> usize {
+    DEFAULT_USERNAME_MAX_LENGTH
+}
+
+fn default_username_allowed_symbols() -> String {
+    DEFAULT_USERNAME_ALLOWED_SYMBOLS.to_string()
+}
+
+fn default_password_min_length() -> usize {
+    DEFAULT_PASSWORD_MIN_LENGTH
+}
+
+fn default_password_max_length() -> usize {
+    DEFAULT_PASSWORD_MAX_LENGTH
+}
+
+fn default_password_min_score() -> u8 {
+    DEFAULT_PASSWORD_MIN_SCORE
+}
+
+fn default_filename_max_length() -> usize {
+    DEFAULT_FILENAME_MAX_LENGTH
+}
+
+fn default_validation_config() -> ValidationConfig {
+    ValidationConfig {
+        username_min_length: DEFAULT_USERNAME_MIN_LENGTH,
+        username_max_length: DEFAULT_USERNAME_MAX_LENGTH,
+        username_allowed_symbols: DEFAULT_USERNAME_ALLOWED_SYMBOLS.to_string(),
+        check_email_format: true,
+        password_min_length: DEFAULT_PASSWORD_MIN_LENGTH,
+        password_max_length: DEFAULT_PASSWORD_MAX_LENGTH,
+        password_min_score: DEFAULT_PASSWORD_MIN_SCORE,
+        filename_max_length: DEFAULT_FILENAME_MAX_LENGTH,
+        filename_windows_compatible: true,
+        filename_forbidden_chars: String::new(),
+        filename_reserved_names: Vec::new(),
+    }
+}
+
+fn default_argon2_memory_kib() -> u32 {
+    DEFAULT_ARGON2_MEMORY_KIB
+}
+
+fn default_argon2_iterations() -> u32 {
+    DEFAULT_ARGON2_ITERATIONS
+}
+
+fn default_argon2_parallelism() -> u32 {
+    DEFAULT_ARGON2_PARALLELISM
+}
+
+fn default_password_hashing_config() -> PasswordHashingConfig {
+    PasswordHashingConfig {
+        argon2_memory_kib: DEFAULT_ARGON2_MEMORY_KIB,
+        argon2_iterations: DEFAULT_ARGON2_ITERATIONS,
+        argon2_parallelism: DEFAULT_ARGON2_PARALLELISM,
+    }
+}
+
+fn default_smtp_port() -> u16 {
+    DEFAULT_SMTP_PORT
+}
+
+fn default_smtp_tls() -> String {
+    DEFAULT_SMTP_TLS.to_string()
+}
+
+fn default_smtp_from() -> String {
+    DEFAULT_SMTP_FROM.to_string()
+}
+
+fn default_outbox_poll_interval_secs() -> u64 {
+    DEFAULT_OUTBOX_POLL_INTERVAL_SECS
+}
+
+fn default_outbox_max_attempts() -> i32 {
+    DEFAULT_OUTBOX_MAX_ATTEMPTS
+}
+
+fn default_smtp_config() -> SmtpConfig {
+    SmtpConfig {
+        enabled: false,
+        host: String::new(),
+        port: DEFAULT_SMTP_PORT,
+        username: None,
+        password: None,
+        tls: DEFAULT_SMTP_TLS.to_string(),
+        from: DEFAULT_SMTP_FROM.to_string(),
+        outbox_poll_interval_secs: DEFAULT_OUTBOX_POLL_INTERVAL_SECS,
+        outbox_max_attempts: DEFAULT_OUTBOX_MAX_ATTEMPTS,
+    }
+}
+
+fn default_staging_max_bytes() -> u64 {
+    DEFAULT_STAGING_MAX_BYTES
+}
+
+fn default_staging_min_free_bytes() -> u64 {
+    DEFAULT_STAGING_MIN_FREE_BYTES
+}
+
+fn default_staging_stale_after_secs() -> u64 {
+    DEFAULT_STAGING_STALE_AFTER_SECS
+}
+
+fn default_staging_sweep_interval_secs() -> u64 {
+    DEFAULT_STAGING_SWEEP_INTERVAL_SECS
+}
+
+fn default_mount_index_interval_secs() -> u64 {
+    DEFAULT_MOUNT_INDEX_INTERVAL_SECS
+}
+
+fn default_mounts_config() -> MountsConfig {
+    MountsConfig {
+        index_interval_secs: DEFAULT_MOUNT_INDEX_INTERVAL_SECS,
+    }
+}
+
+fn default_deletion_grace_period_days() -> i64 {
+    DEFAULT_DELETION_GRACE_PERIOD_DAYS
+}
+
+fn default_deletion_sweep_interval_secs() -> u64 {
+    DEFAULT_DELETION_SWEEP_INTERVAL_SECS
+}
+
+fn default_cleanup_interval_secs() -> u64 {
+    DEFAULT_CLEANUP_INTERVAL_SECS
+}
+
+fn default_cleanup_notice_hours() -> i64 {
+    DEFAULT_CLEANUP_NOTICE_HOURS
+}
+
+fn default_cleanup_config() -> CleanupConfig {
+    CleanupConfig {
+        interval_secs: DEFAULT_CLEANUP_INTERVAL_SECS,
+        notice_hours: DEFAULT_CLEANUP_NOTICE_HOURS,
+    }
+}
+
+fn default_usage_history_interval_secs() -> u64 {
+    DEFAULT_USAGE_HISTORY_INTERVAL_SECS
+}
+
+fn default_usage_history_retention_days() -> i64 {
+    DEFAULT_USAGE_HISTORY_RETENTION_DAYS
+}
+
+fn default_usage_history_config() -> UsageHistoryConfig {
+    UsageHistoryConfig {
+        interval_secs: DEFAULT_USAGE_HISTORY_INTERVAL_SECS,
+        retention_days: DEFAULT_USAGE_HISTORY_RETENTION_DAYS,
+    }
+}
+
+fn default_features_config() -> FeaturesConfig {
+    FeaturesConfig { flags: Vec::new() }
+}
+
+fn default_quota_warn_percents() -> Vec<u64> {
+    DEFAULT_QUOTA_WARN_PERCENTS.to_vec()
+}
+
+fn default_quota_config() -> QuotaConfig {
+    QuotaConfig {
+        default_bytes: 0,
+        roles: Vec::new(),
+        warn_percents: default_quota_warn_percents(),
+        grace_percent: 0,
+    }
+}
+
+fn default_jobs_max_concurrent() -> usize {
+    DEFAULT_JOBS_MAX_CONCURRENT
+}
+
+fn default_jobs_config() -> JobsConfig {
+    JobsConfig {
+        max_concurrent: DEFAULT_JOBS_MAX_CONCURRENT,
+        classes: Vec::new(),
+    }
+}
+
+fn default_account_deletion_config() -> AccountDeletionConfig {
+    AccountDeletionConfig {
+        grace_period_days: DEFAULT_DELETION_GRACE_PERIOD_DAYS,
+        sweep_interval_secs: DEFAULT_DELETION_SWEEP_INTERVAL_SECS,
+    }
+}
+
+fn default_backup_interval_secs() -> u64 {
+    DEFAULT_BACKUP_INTERVAL_SECS
+}
+
+fn default_backup_full_every_runs() -> u64 {
+    DEFAULT_BACKUP_FULL_EVERY_RUNS
+}
+
+fn default_backup_config() -> BackupConfig {
+    BackupConfig {
+        enabled: false,
+        target: None,
+        interval_secs: DEFAULT_BACKUP_INTERVAL_SECS,
+        full_every_runs: DEFAULT_BACKUP_FULL_EVERY_RUNS,
+    }
+}
+
+fn default_replication_interval_secs() -> u64 {
+    DEFAULT_REPLICATION_INTERVAL_SECS
+}
+
+fn default_replication_batch_size() -> u64 {
+    DEFAULT_REPLICATION_BATCH_SIZE
+}
+
+fn default_replication_config() -> ReplicationConfig {
+    ReplicationConfig {
+        enabled: false,
+        target: None,
+        interval_secs: DEFAULT_REPLICATION_INTERVAL_SECS,
+        batch_size: DEFAULT_REPLICATION_BATCH_SIZE,
+    }
+}
+
+fn default_processing_max_file_size() -> u64 {
+    DEFAULT_PROCESSING_MAX_FILE_SIZE
+}
+
+fn default_processing_hook_timeout_secs() -> u64 {
+    DEFAULT_PROCESSING_HOOK_TIMEOUT_SECS
+}
+
+fn default_processing_config() -> ProcessingConfig {
+    ProcessingConfig {
+        hooks: Vec::new(),
+        max_file_size: DEFAULT_PROCESSING_MAX_FILE_SIZE,
+    }
+}
+
+fn default_search_backend() -> String {
+    DEFAULT_SEARCH_BACKEND.to_string()
+}
+
+fn default_search_index() -> String {
+    DEFAULT_SEARCH_INDEX.to_string()
+}
+
+fn default_search_sync_interval_secs() -> u64 {
+    DEFAULT_SEARCH_SYNC_INTERVAL_SECS
+}
+
+fn default_search_config() -> SearchConfig {
+    SearchConfig {
+        backend: DEFAULT_SEARCH_BACKEND.to_string(),
+        url: None,
+        api_key: None,
+        index: DEFAULT_SEARCH_INDEX.to_string(),
+        sync_interval_secs: DEFAULT_SEARCH_SYNC_INTERVAL_SECS,
+    }
+}
+
+fn default_gzip_min_size() -> u64 {
+    DEFAULT_GZIP_MIN_SIZE
+}
+
+fn default_gzip_max_size() -> u64 {
+    DEFAULT_GZIP_MAX_SIZE
+}
+
+fn default_cache_rules() -> Vec<CacheRule> {
+    ["image/*", "video/*", "audio/*", "font/*"]
+        .into_iter()
+        .map(|mime| CacheRule {
+            mime: mime.to_string(),
+            cache_control: DEFAULT_MEDIA_CACHE_CONTROL.to_string(),
+        })
+        .collect()
+}
+
+fn default_cache_control() -> String {
+    DEFAULT_CACHE_CONTROL.to_string()
+}
+
+fn default_immutable_cache_control() -> String {
+    DEFAULT_IMMUTABLE_CACHE_CONTROL.to_string()
+}
+
+fn default_active_mime_types() -> Vec<String> {
+    DEFAULT_ACTIVE_MIME_TYPES
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_presigned_ttl_secs() -> i64 {
+    DEFAULT_PRESIGNED_TTL_SECS
+}
+
+fn default_presigned_max_ttl_secs() -> i64 {
+    DEFAULT_PRESIGNED_MAX_TTL_SECS
+}
+
+fn default_share_link_max_hours() -> i64 {
+    DEFAULT_SHARE_LINK_MAX_HOURS
+}
+
+fn default_download_config() -> DownloadConfig {
+    DownloadConfig {
+        gzip_enabled: true,
+        gzip_min_size: DEFAULT_GZIP_MIN_SIZE,
+        gzip_max_size: DEFAULT_GZIP_MAX_SIZE,
+        cache_rules: default_cache_rules(),
+        default_cache_control: DEFAULT_CACHE_CONTROL.to_string(),
+        immutable_cache_control: DEFAULT_IMMUTABLE_CACHE_CONTROL.to_string(),
+        active_mime_types: default_active_mime_types(),
+        content_domain: None,
+        presigned_ttl_secs: DEFAULT_PRESIGNED_TTL_SECS,
+        presigned_max_ttl_secs: DEFAULT_PRESIGNED_MAX_TTL_SECS,
+        share_link_max_hours: DEFAULT_SHARE_LINK_MAX_HOURS,
+    }
+}
+
+fn default_max_in_flight() -> usize {
+    DEFAULT_MAX_IN_FLIGHT
+}
+
+fn default_max_queued() -> usize {
+    DEFAULT_MAX_QUEUED
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    DEFAULT_QUEUE_TIMEOUT_MS
+}
+
+fn default_shed_retry_after_secs() -> u64 {
+    DEFAULT_SHED_RETRY_AFTER_SECS
+}
+
+fn default_load_shedding_config() -> LoadSheddingConfig {
+    LoadSheddingConfig {
+        enabled: false,
+        max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+        max_queued: DEFAULT_MAX_QUEUED,
+        queue_timeout_ms: DEFAULT_QUEUE_TIMEOUT_MS,
+        routes: Vec::new(),
+        retry_after_secs: DEFAULT_SHED_RETRY_AFTER_SECS,
+    }
+}
+
+fn default_staging_config() -> StagingConfig {
+    StagingConfig {
+        dir: None,
+        max_bytes: DEFAULT_STAGING_MAX_BYTES,
+        min_free_bytes: DEFAULT_STAGING_MIN_FREE_BYTES,
+        stale_after_secs: DEFAULT_STAGING_STALE_AFTER_SECS,
+        sweep_interval_secs: DEFAULT_STAGING_SWEEP_INTERVAL_SECS,
     }
 }
 
@@ -154,6 +1342,22 @@ impl Config {
         PathBuf::from(&self.storage.dir)
     }
 
+    /// All storage roots, the primary `storage.dir` first
+    pub fn get_storage_volumes(&self) -> Vec<PathBuf> {
+        std::iter::once(&self.storage.dir)
+            .chain(&self.storage.volumes)
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Get temp/staging directory path
+    pub fn get_staging_dir(&self) -> PathBuf {
+        match &self.staging.dir {
+            Some(dir) => PathBuf::from(dir),
+            None => self.get_storage_dir().join(DEFAULT_STAGING_DIR_NAME),
+        }
+    }
+
     pub fn ensure_directories(&self) -> std::io::Result<()> {
         // Create database directory if it doesn't exist
         if let Some(db_dir) = self.get_database_dir() {
@@ -169,9 +1373,14 @@ impl Config {
             }
         }
 
-        let storage_dir = self.get_storage_dir();
-        std::fs::create_dir_all(&storage_dir)?;
-        tracing::info!("Storage directory ensured: {:?}", storage_dir);
+        for storage_dir in self.get_storage_volumes() {
+            std::fs::create_dir_all(&storage_dir)?;
+            tracing::info!("Storage directory ensured: {:?}", storage_dir);
+        }
+
+        let staging_dir = self.get_staging_dir();
+        std::fs::create_dir_all(&staging_dir)?;
+        tracing::info!("Staging directory ensured: {:?}", staging_dir);
 
         Ok(())
     }
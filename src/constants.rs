@@ -5,6 +5,12 @@ pub const FILE_TYPE_FOLDER: &str = "folder";
 // User role constants
 pub const ROLE_ADMIN: &str = "admin";
 pub const ROLE_USER: &str = "user";
+/// Account owning the Shared Library, it cannot log in
+pub const ROLE_LIBRARY: &str = "library";
+
+// Reserved paths
+/// Top-level folders kept for the system, users cannot create, change or shadow them
+pub const SYSTEM_PATHS: &[&str] = &["/.trash"];
 
 // Buffer sizes
 pub const HASH_BUFFER_SIZE: usize = 8192; // 8KB for hash calculation
@@ -1,68 +1,306 @@
-use sea_orm::{Database, DatabaseConnection, DbErr};
+use crate::config::{DatabaseConfig, PasswordHashingConfig};
+use sea_orm::{ConnectOptions, DatabaseConnection, DbErr, RuntimeErr, SqlxSqliteConnector};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::{ConnectOptions as _, Sqlite};
+use std::str::FromStr;
+use std::time::Duration;
 
 const DEFAULT_ADMIN_USERNAME: &str = "admin";
 const DEFAULT_ADMIN_PASSWORD: &str = "Tomy0331.";
 const DEFAULT_ADMIN_EMAIL: &str = "andresromeralito@gmail.com";
 
-pub async fn create_connection(database_url: &str) -> Result<DatabaseConnection, DbErr> {
-    let db = Database::connect(database_url).await?;
-    tracing::info!("Database connected successfully");
-    Ok(db)
+pub async fn create_connection(config: &DatabaseConfig) -> Result<DatabaseConnection, DbErr> {
+    let mut options = ConnectOptions::new(config.url.clone());
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.idle_timeout_secs));
+
+    let pool = options
+        .pool_options::<Sqlite>()
+        .connect_with(sqlite_options(config)?)
+        .await
+        .map_err(|e| DbErr::Conn(RuntimeErr::SqlxError(e)))?;
+
+    tracing::info!(
+        max_connections = config.max_connections,
+        min_connections = config.min_connections,
+        journal_mode = %config.sqlite.journal_mode,
+        synchronous = %config.sqlite.synchronous,
+        "Database connected successfully"
+    );
+    Ok(SqlxSqliteConnector::from_sqlx_sqlite_pool(pool))
 }
 
-pub async fn init_database(db: &DatabaseConnection) -> Result<(), DbErr> {
-    use crate::entities::user;
-    use crate::utils::password;
-    use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, PaginatorTrait, Schema, Set};
+/// Connection options with the configured pragmas and statement logging
+fn sqlite_options(config: &DatabaseConfig) -> Result<SqliteConnectOptions, DbErr> {
+    let invalid = |e: sqlx::Error| DbErr::Custom(format!("Invalid SQLite setting: {}", e));
+    let pragmas = &config.sqlite;
 
-    let schema = Schema::new(sea_orm::DatabaseBackend::Sqlite);
-    let stmt = schema.create_table_from_entity(user::Entity);
+    let mut options = SqliteConnectOptions::from_str(&config.url)
+        .map_err(invalid)?
+        .journal_mode(SqliteJournalMode::from_str(&pragmas.journal_mode).map_err(invalid)?)
+        .synchronous(SqliteSynchronous::from_str(&pragmas.synchronous).map_err(invalid)?)
+        .busy_timeout(Duration::from_millis(pragmas.busy_timeout_ms))
+        .pragma("cache_size", pragmas.cache_size.to_string());
 
-    match db.execute(db.get_database_backend().build(&stmt)).await {
-        Ok(_) => {
-            tracing::info!("Users table created successfully");
-        }
-        Err(e) => {
-            if e.to_string().contains("already exists") {
-                tracing::debug!("Users table already exists");
-            } else {
-                return Err(e);
-            }
-        }
+    options = if config.log_statements {
+        options.log_statements(log::LevelFilter::Debug)
+    } else {
+        options.log_statements(log::LevelFilter::Off)
+    };
+    if config.slow_statement_ms > 0 {
+        options = options.log_slow_statements(
+            log::LevelFilter::Warn,
+            Duration::from_millis(config.slow_statement_ms),
+        );
+    } else {
+        options = options.log_slow_statements(log::LevelFilter::Off, Duration::default());
     }
+    Ok(options)
+}
 
-    // Create files table
-    let stmt = schema.create_table_from_entity(crate::entities::file::Entity);
-    match db.execute(db.get_database_backend().build(&stmt)).await {
-        Ok(_) => tracing::info!("Files table created successfully"),
-        Err(e) => {
-            if e.to_string().contains("already exists") {
-                tracing::debug!("Files table already exists");
-            } else {
-                return Err(e);
-            }
-        }
-    }
+pub async fn init_database(
+    db: &DatabaseConnection,
+    password_hashing: &PasswordHashingConfig,
+) -> Result<(), DbErr> {
+    use crate::entities::user;
+    use crate::utils::password;
+    use sea_orm::{ActiveModelTrait, EntityTrait, PaginatorTrait, QuerySelect, Schema, Set};
 
-    // Create file_permissions table
-    let stmt = schema.create_table_from_entity(crate::entities::file_permission::Entity);
-    match db.execute(db.get_database_backend().build(&stmt)).await {
-        Ok(_) => tracing::info!("File permissions table created successfully"),
-        Err(e) => {
-            if e.to_string().contains("already exists") {
-                tracing::debug!("File permissions table already exists");
-            } else {
-                return Err(e);
-            }
-        }
-    }
+    let schema = Schema::new(sea_orm::DatabaseBackend::Sqlite);
+
+    create_table_if_missing(db, &schema, user::Entity, "Users").await?;
+    create_table_if_missing(db, &schema, crate::entities::file::Entity, "Files").await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_permission::Entity,
+        "File permissions",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::audit_log::Entity,
+        "Audit logs",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::email_outbox::Entity,
+        "Email outbox",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::notification::Entity,
+        "Notifications",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::notification_preference::Entity,
+        "Notification preferences",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::share_link::Entity,
+        "Share links",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::folder_default_permission::Entity,
+        "Folder default permissions",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::permission_template::Entity,
+        "Permission templates",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_stat::Entity,
+        "File stats",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::daily_download::Entity,
+        "Daily downloads",
+    )
+    .await?;
+    create_table_if_missing(db, &schema, crate::entities::job::Entity, "Jobs").await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_change::Entity,
+        "File changes",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::backup_run::Entity,
+        "Backup runs",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::audio_metadata::Entity,
+        "Audio metadata",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::audio_cover::Entity,
+        "Audio covers",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::photo_location::Entity,
+        "Photo locations",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_label::Entity,
+        "File labels",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_metadata::Entity,
+        "File metadata",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::custom_metadata::Entity,
+        "Custom metadata",
+    )
+    .await?;
+    create_table_if_missing(db, &schema, crate::entities::mount::Entity, "Mounts").await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::folder_style::Entity,
+        "Folder styles",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::file_star::Entity,
+        "File stars",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::upload_session::Entity,
+        "Upload sessions",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::upload_chunk::Entity,
+        "Upload chunks",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::announcement::Entity,
+        "Announcements",
+    )
+    .await?;
+    create_table_if_missing(db, &schema, crate::entities::group::Entity, "Groups").await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::group_member::Entity,
+        "Group members",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::library_grant::Entity,
+        "Library grants",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::organize_rule::Entity,
+        "Organize rules",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::cleanup_rule::Entity,
+        "Cleanup rules",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::feature_override::Entity,
+        "Feature overrides",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::usage_snapshot::Entity,
+        "Usage snapshots",
+    )
+    .await?;
+    create_table_if_missing(
+        db,
+        &schema,
+        crate::entities::download_token::Entity,
+        "Download tokens",
+    )
+    .await?;
+    create_table_if_missing(db, &schema, crate::entities::takedown::Entity, "Takedowns").await?;
 
-    let user_count = user::Entity::find().count(db).await?;
+    // Only the id is selected: columns added by `migrate_database` may not exist yet
+    let user_count = user::Entity::find()
+        .select_only()
+        .column(user::Column::Id)
+        .count(db)
+        .await?;
 
     if user_count == 0 {
         tracing::info!("Initializing default admin account...");
 
-        let password_hash = password::hash_password(DEFAULT_ADMIN_PASSWORD)
+        let password_hash = password::hash_password(DEFAULT_ADMIN_PASSWORD, password_hashing)
+            .await
             .map_err(|e| DbErr::Custom(format!("Failed to hash password: {}", e)))?;
 
         let now = chrono::Utc::now().naive_utc();
@@ -71,6 +309,8 @@ pub async fn init_database(db: &DatabaseConnection) -> Result<(), DbErr> {
             email: Set(DEFAULT_ADMIN_EMAIL.to_string()),
             password_hash: Set(password_hash),
             role: Set("admin".to_string()),
+            token_version: Set(0),
+            login_alerts_enabled: Set(true),
             created_at: Set(now),
             updated_at: Set(now),
             ..Default::default()
@@ -85,49 +325,154 @@ pub async fn init_database(db: &DatabaseConnection) -> Result<(), DbErr> {
 
 /// Migrate database schema to add new columns
 pub async fn migrate_database(db: &DatabaseConnection) -> Result<(), DbErr> {
-    use sea_orm::{ConnectionTrait, Statement};
+    add_column_if_missing(db, "files", "file_hash", "TEXT").await;
+    add_column_if_missing(db, "files", "ref_count", "INTEGER DEFAULT 1").await;
+    add_column_if_missing(db, "files", "version", "INTEGER NOT NULL DEFAULT 1").await;
+    add_column_if_missing(db, "files", "replication_status", "TEXT").await;
+    add_column_if_missing(db, "files", "replicated_at", "TIMESTAMP").await;
+    add_column_if_missing(db, "files", "replica_path", "TEXT").await;
+    add_column_if_missing(db, "files", "pinned", "BOOLEAN NOT NULL DEFAULT 0").await;
+    add_column_if_missing(db, "files", "total_size_bytes", "INTEGER").await;
+    add_column_if_missing(db, "files", "client_modified", "TIMESTAMP").await;
+    add_column_if_missing(db, "files", "legal_hold", "BOOLEAN NOT NULL DEFAULT 0").await;
+    add_column_if_missing(db, "files", "legal_hold_reason", "TEXT").await;
+    add_column_if_missing(db, "files", "quarantined_at", "TIMESTAMP").await;
+    add_column_if_missing(db, "files", "quarantine_reason", "TEXT").await;
+    add_column_if_missing(db, "files", "taken_down", "BOOLEAN NOT NULL DEFAULT 0").await;
+    add_column_if_missing(db, "file_changes", "client_modified", "TIMESTAMP").await;
+    add_column_if_missing(db, "upload_sessions", "client_modified", "TIMESTAMP").await;
+    add_column_if_missing(db, "users", "token_version", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column_if_missing(
+        db,
+        "users",
+        "login_alerts_enabled",
+        "BOOLEAN NOT NULL DEFAULT 1",
+    )
+    .await;
+    add_column_if_missing(db, "users", "storage_root", "TEXT").await;
+    add_column_if_missing(db, "users", "display_name", "TEXT").await;
+    add_column_if_missing(db, "users", "locale", "TEXT").await;
+    add_column_if_missing(db, "users", "timezone", "TEXT").await;
+    add_column_if_missing(db, "users", "pending_email", "TEXT").await;
+    add_column_if_missing(db, "users", "email_token", "TEXT").await;
+    add_column_if_missing(db, "users", "email_token_expires_at", "TIMESTAMP").await;
+    add_column_if_missing(db, "users", "deletion_scheduled_at", "TIMESTAMP").await;
+    add_column_if_missing(db, "audit_logs", "file_id", "INTEGER").await;
 
-    // Add file_hash column if not exists
-    let add_hash_sql = "ALTER TABLE files ADD COLUMN file_hash TEXT";
-    match db
-        .execute(Statement::from_string(
-            db.get_database_backend(),
-            add_hash_sql.to_string(),
-        ))
-        .await
-    {
-        Ok(_) => tracing::info!("Added file_hash column"),
+    normalize_file_names(db).await?;
+
+    Ok(())
+}
+
+/// Rewrite names and paths stored before they were normalized to NFC
+/// A row whose NFC path is already taken keeps its name, and its subtree with it
+async fn normalize_file_names(db: &DatabaseConnection) -> Result<(), DbErr> {
+    use crate::{entities::file, utils::file_utils::normalize_name};
+    use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter};
+    use unicode_normalization::is_nfc;
+
+    // Plain ASCII paths are always normalized
+    let mut pending: Vec<file::Model> = file::Entity::find()
+        .filter(Expr::cust("path GLOB '*[^ -~]*'"))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|f| !is_nfc(&f.path) || !is_nfc(&f.name) || !is_nfc(&f.parent_path))
+        .collect();
+    // Parents before their children
+    pending.sort_by_key(|f| f.path.len());
+
+    let mut kept: Vec<(i32, String)> = Vec::new();
+    let mut normalized = 0;
+    for f in pending {
+        if kept
+            .iter()
+            .any(|(owner, prefix)| *owner == f.user_id && f.path.starts_with(prefix))
+        {
+            continue;
+        }
+
+        let path = normalize_name(&f.path);
+        let taken = file::Entity::find()
+            .filter(file::Column::UserId.eq(f.user_id))
+            .filter(file::Column::Path.eq(&path))
+            .filter(file::Column::Id.ne(f.id))
+            .one(db)
+            .await?
+            .is_some();
+        if taken {
+            tracing::warn!(file_id = f.id, path = %f.path, "Name collides with its NFC form, left as is");
+            kept.push((f.user_id, format!("{}/", f.path)));
+            continue;
+        }
+
+        file::Entity::update_many()
+            .col_expr(file::Column::Name, Expr::value(normalize_name(&f.name)))
+            .col_expr(file::Column::Path, Expr::value(path))
+            .col_expr(
+                file::Column::ParentPath,
+                Expr::value(normalize_name(&f.parent_path)),
+            )
+            .filter(file::Column::Id.eq(f.id))
+            .exec(db)
+            .await?;
+        normalized += 1;
+    }
+
+    if normalized > 0 {
+        tracing::info!(files = normalized, "Normalized file names to NFC");
+    }
+
+    Ok(())
+}
+
+/// Create the table for `entity`, ignoring "already exists" errors
+async fn create_table_if_missing<E: sea_orm::EntityTrait>(
+    db: &DatabaseConnection,
+    schema: &sea_orm::Schema,
+    entity: E,
+    label: &str,
+) -> Result<(), DbErr> {
+    use sea_orm::ConnectionTrait;
+
+    let stmt = schema.create_table_from_entity(entity);
+    match db.execute(db.get_database_backend().build(&stmt)).await {
+        Ok(_) => tracing::info!("{} table created successfully", label),
         Err(e) => {
-            if e.to_string().contains("duplicate column")
-                || e.to_string().contains("already exists")
-            {
-                tracing::debug!("file_hash column already exists");
+            if e.to_string().contains("already exists") {
+                tracing::debug!("{} table already exists", label);
             } else {
-                tracing::warn!("Failed to add file_hash column: {:?}", e);
+                return Err(e);
             }
         }
     }
 
-    // Add ref_count column if not exists
-    let add_ref_count_sql = "ALTER TABLE files ADD COLUMN ref_count INTEGER DEFAULT 1";
+    Ok(())
+}
+
+/// Add a column to an existing table, ignoring "already exists" errors
+async fn add_column_if_missing(
+    db: &DatabaseConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) {
+    use sea_orm::{ConnectionTrait, Statement};
+
+    let sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
     match db
-        .execute(Statement::from_string(
-            db.get_database_backend(),
-            add_ref_count_sql.to_string(),
-        ))
+        .execute(Statement::from_string(db.get_database_backend(), sql))
         .await
     {
-        Ok(_) => tracing::info!("Added ref_count column"),
+        Ok(_) => tracing::info!("Added {} column to {}", column, table),
         Err(e) => {
             if e.to_string().contains("duplicate column")
                 || e.to_string().contains("already exists")
             {
-                tracing::debug!("ref_count column already exists");
+                tracing::debug!("{}.{} column already exists", table, column);
             } else {
-                tracing::warn!("Failed to add ref_count column: {:?}", e);
+                tracing::warn!("Failed to add {} column to {}: {:?}", column, table, e);
             }
         }
     }
-
-    Ok(())
 }
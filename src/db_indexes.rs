@@ -5,6 +5,21 @@ use std::time::Instant;
 const TABLE_FILES: &str = "files";
 const TABLE_FILE_PERMISSIONS: &str = "file_permissions";
 const TABLE_USERS: &str = "users";
+const TABLE_AUDIT_LOGS: &str = "audit_logs";
+const TABLE_EMAIL_OUTBOX: &str = "email_outbox";
+const TABLE_NOTIFICATIONS: &str = "notifications";
+const TABLE_NOTIFICATION_PREFERENCES: &str = "notification_preferences";
+const TABLE_FOLDER_DEFAULT_PERMISSIONS: &str = "folder_default_permissions";
+const TABLE_FILE_STATS: &str = "file_stats";
+const TABLE_FILE_CHANGES: &str = "file_changes";
+const TABLE_AUDIO_METADATA: &str = "audio_metadata";
+const TABLE_PHOTO_LOCATIONS: &str = "photo_locations";
+const TABLE_FILE_LABELS: &str = "file_labels";
+const TABLE_FILE_METADATA: &str = "file_metadata";
+const TABLE_CUSTOM_METADATA: &str = "custom_metadata";
+const TABLE_FOLDER_STYLES: &str = "folder_styles";
+const TABLE_FILE_STARS: &str = "file_stars";
+const TABLE_USAGE_SNAPSHOTS: &str = "usage_snapshots";
 
 const FIELD_USER_ID: &str = "user_id";
 const FIELD_PARENT_PATH: &str = "parent_path";
@@ -14,6 +29,27 @@ const FIELD_FILE_ID: &str = "file_id";
 const INDEX_FILES_USER_PARENT: &str = "idx_files_user_parent";
 const INDEX_FILES_USER_PATH: &str = "idx_files_user_path";
 const INDEX_PERMISSIONS_FILE_USER: &str = "idx_permissions_file_user";
+const INDEX_AUDIT_LOGS_USER_CREATED: &str = "idx_audit_logs_user_created";
+const INDEX_AUDIT_LOGS_EVENT_CREATED: &str = "idx_audit_logs_event_created";
+const INDEX_AUDIT_LOGS_FILE_CREATED: &str = "idx_audit_logs_file_created";
+const INDEX_EMAIL_OUTBOX_PENDING: &str = "idx_email_outbox_pending";
+const INDEX_NOTIFICATIONS_USER_CREATED: &str = "idx_notifications_user_created";
+const INDEX_NOTIFICATION_PREFS_USER_CATEGORY: &str = "idx_notification_prefs_user_category";
+const INDEX_FOLDER_DEFAULTS_FOLDER_USER: &str = "idx_folder_defaults_folder_user";
+const INDEX_FILE_STATS_DOWNLOADS: &str = "idx_file_stats_downloads";
+const INDEX_FILE_CHANGES_USER_ID: &str = "idx_file_changes_user_id";
+const INDEX_AUDIO_METADATA_USER_ARTIST_ALBUM: &str = "idx_audio_metadata_user_artist_album";
+const INDEX_PHOTO_LOCATIONS_USER_LAT_LON: &str = "idx_photo_locations_user_lat_lon";
+const INDEX_FILE_LABELS_USER_LABEL: &str = "idx_file_labels_user_label";
+const INDEX_FILE_LABELS_FILE_ID: &str = "idx_file_labels_file_id";
+const INDEX_FILE_METADATA_FILE_SOURCE_KEY: &str = "idx_file_metadata_file_source_key";
+const INDEX_FILE_METADATA_USER_KEY: &str = "idx_file_metadata_user_key";
+const INDEX_CUSTOM_METADATA_FILE_KEY: &str = "idx_custom_metadata_file_key";
+const INDEX_CUSTOM_METADATA_USER_KEY_VALUE: &str = "idx_custom_metadata_user_key_value";
+const INDEX_FOLDER_STYLES_FILE_USER: &str = "idx_folder_styles_file_user";
+const INDEX_FILE_STARS_FILE_USER: &str = "idx_file_stars_file_user";
+const INDEX_FILE_STARS_USER_CREATED: &str = "idx_file_stars_user_created";
+const INDEX_USAGE_SNAPSHOTS_USER_DAY: &str = "idx_usage_snapshots_user_day";
 
 #[derive(Debug)]
 struct IndexInfo {
@@ -102,8 +138,221 @@ pub async fn create_composite_indexes(db: &DatabaseConnection) -> Result<(), DbE
         ),
     );
 
+    // Per-user security log, newest first
+    let mut audit_indexes = HashMap::new();
+    audit_indexes.insert(
+        INDEX_AUDIT_LOGS_USER_CREATED.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, created_at DESC)",
+            INDEX_AUDIT_LOGS_USER_CREATED, TABLE_AUDIT_LOGS, FIELD_USER_ID
+        ),
+    );
+    // Admin audit queries filter by action or file within a date range
+    audit_indexes.insert(
+        INDEX_AUDIT_LOGS_EVENT_CREATED.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}(event, created_at)",
+            INDEX_AUDIT_LOGS_EVENT_CREATED, TABLE_AUDIT_LOGS
+        ),
+    );
+    audit_indexes.insert(
+        INDEX_AUDIT_LOGS_FILE_CREATED.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, created_at)",
+            INDEX_AUDIT_LOGS_FILE_CREATED, TABLE_AUDIT_LOGS, FIELD_FILE_ID
+        ),
+    );
+
+    // Outbox worker polls for due pending emails
+    let mut outbox_indexes = HashMap::new();
+    outbox_indexes.insert(
+        INDEX_EMAIL_OUTBOX_PENDING.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}(status, next_attempt_at)",
+            INDEX_EMAIL_OUTBOX_PENDING, TABLE_EMAIL_OUTBOX
+        ),
+    );
+
+    // In-app notification list, newest first
+    let mut notification_indexes = HashMap::new();
+    notification_indexes.insert(
+        INDEX_NOTIFICATIONS_USER_CREATED.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, created_at DESC)",
+            INDEX_NOTIFICATIONS_USER_CREATED, TABLE_NOTIFICATIONS, FIELD_USER_ID
+        ),
+    );
+
+    // One preference row per user and category
+    let mut notification_pref_indexes = HashMap::new();
+    notification_pref_indexes.insert(
+        INDEX_NOTIFICATION_PREFS_USER_CATEGORY.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, category)",
+            INDEX_NOTIFICATION_PREFS_USER_CATEGORY, TABLE_NOTIFICATION_PREFERENCES, FIELD_USER_ID
+        ),
+    );
+
+    // One default policy per folder and user
+    let mut folder_default_indexes = HashMap::new();
+    folder_default_indexes.insert(
+        INDEX_FOLDER_DEFAULTS_FOLDER_USER.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}(folder_id, {})",
+            INDEX_FOLDER_DEFAULTS_FOLDER_USER, TABLE_FOLDER_DEFAULT_PERMISSIONS, FIELD_USER_ID
+        ),
+    );
+
+    // Most downloaded files report
+    let mut file_stats_indexes = HashMap::new();
+    file_stats_indexes.insert(
+        INDEX_FILE_STATS_DOWNLOADS.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}(download_count DESC)",
+            INDEX_FILE_STATS_DOWNLOADS, TABLE_FILE_STATS
+        ),
+    );
+
+    // Changes feed, read forward from a cursor
+    let mut file_changes_indexes = HashMap::new();
+    file_changes_indexes.insert(
+        INDEX_FILE_CHANGES_USER_ID.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, id)",
+            INDEX_FILE_CHANGES_USER_ID, TABLE_FILE_CHANGES, FIELD_USER_ID
+        ),
+    );
+
+    // Music library browsing by artist and album
+    let mut audio_indexes = HashMap::new();
+    audio_indexes.insert(
+        INDEX_AUDIO_METADATA_USER_ARTIST_ALBUM.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, artist, album)",
+            INDEX_AUDIO_METADATA_USER_ARTIST_ALBUM, TABLE_AUDIO_METADATA, FIELD_USER_ID
+        ),
+    );
+
+    // Photo map lookups within a bounding box
+    let mut photo_location_indexes = HashMap::new();
+    photo_location_indexes.insert(
+        INDEX_PHOTO_LOCATIONS_USER_LAT_LON.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, latitude, longitude)",
+            INDEX_PHOTO_LOCATIONS_USER_LAT_LON, TABLE_PHOTO_LOCATIONS, FIELD_USER_ID
+        ),
+    );
+
+    // Search by label, cleanup by file
+    let mut label_indexes = HashMap::new();
+    label_indexes.insert(
+        INDEX_FILE_LABELS_USER_LABEL.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, label)",
+            INDEX_FILE_LABELS_USER_LABEL, TABLE_FILE_LABELS, FIELD_USER_ID
+        ),
+    );
+    label_indexes.insert(
+        INDEX_FILE_LABELS_FILE_ID.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({})",
+            INDEX_FILE_LABELS_FILE_ID, TABLE_FILE_LABELS, FIELD_FILE_ID
+        ),
+    );
+
+    // One entry per file, source and key; search by key
+    let mut metadata_indexes = HashMap::new();
+    metadata_indexes.insert(
+        INDEX_FILE_METADATA_FILE_SOURCE_KEY.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, source, key)",
+            INDEX_FILE_METADATA_FILE_SOURCE_KEY, TABLE_FILE_METADATA, FIELD_FILE_ID
+        ),
+    );
+    metadata_indexes.insert(
+        INDEX_FILE_METADATA_USER_KEY.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, key)",
+            INDEX_FILE_METADATA_USER_KEY, TABLE_FILE_METADATA, FIELD_USER_ID
+        ),
+    );
+
+    // One entry per file and key; search by key and value
+    let mut custom_metadata_indexes = HashMap::new();
+    custom_metadata_indexes.insert(
+        INDEX_CUSTOM_METADATA_FILE_KEY.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, key)",
+            INDEX_CUSTOM_METADATA_FILE_KEY, TABLE_CUSTOM_METADATA, FIELD_FILE_ID
+        ),
+    );
+    custom_metadata_indexes.insert(
+        INDEX_CUSTOM_METADATA_USER_KEY_VALUE.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, key, value)",
+            INDEX_CUSTOM_METADATA_USER_KEY_VALUE, TABLE_CUSTOM_METADATA, FIELD_USER_ID
+        ),
+    );
+
+    // One style per folder and user
+    let mut folder_style_indexes = HashMap::new();
+    folder_style_indexes.insert(
+        INDEX_FOLDER_STYLES_FILE_USER.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, {})",
+            INDEX_FOLDER_STYLES_FILE_USER, TABLE_FOLDER_STYLES, FIELD_FILE_ID, FIELD_USER_ID
+        ),
+    );
+
+    // One star per file and user, listed newest first
+    let mut star_indexes = HashMap::new();
+    star_indexes.insert(
+        INDEX_FILE_STARS_FILE_USER.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, {})",
+            INDEX_FILE_STARS_FILE_USER, TABLE_FILE_STARS, FIELD_FILE_ID, FIELD_USER_ID
+        ),
+    );
+    star_indexes.insert(
+        INDEX_FILE_STARS_USER_CREATED.to_string(),
+        format!(
+            "CREATE INDEX {} ON {}({}, created_at)",
+            INDEX_FILE_STARS_USER_CREATED, TABLE_FILE_STARS, FIELD_USER_ID
+        ),
+    );
+
+    // One snapshot per user and day, read as a time range
+    let mut usage_snapshot_indexes = HashMap::new();
+    usage_snapshot_indexes.insert(
+        INDEX_USAGE_SNAPSHOTS_USER_DAY.to_string(),
+        format!(
+            "CREATE UNIQUE INDEX {} ON {}({}, day)",
+            INDEX_USAGE_SNAPSHOTS_USER_DAY, TABLE_USAGE_SNAPSHOTS, FIELD_USER_ID
+        ),
+    );
+
     manage_table_indexes(db, TABLE_FILES, files_indexes).await?;
     manage_table_indexes(db, TABLE_FILE_PERMISSIONS, permissions_indexes).await?;
+    manage_table_indexes(db, TABLE_AUDIT_LOGS, audit_indexes).await?;
+    manage_table_indexes(db, TABLE_EMAIL_OUTBOX, outbox_indexes).await?;
+    manage_table_indexes(db, TABLE_NOTIFICATIONS, notification_indexes).await?;
+    manage_table_indexes(
+        db,
+        TABLE_NOTIFICATION_PREFERENCES,
+        notification_pref_indexes,
+    )
+    .await?;
+    manage_table_indexes(db, TABLE_FOLDER_DEFAULT_PERMISSIONS, folder_default_indexes).await?;
+    manage_table_indexes(db, TABLE_FILE_STATS, file_stats_indexes).await?;
+    manage_table_indexes(db, TABLE_FILE_CHANGES, file_changes_indexes).await?;
+    manage_table_indexes(db, TABLE_AUDIO_METADATA, audio_indexes).await?;
+    manage_table_indexes(db, TABLE_PHOTO_LOCATIONS, photo_location_indexes).await?;
+    manage_table_indexes(db, TABLE_FILE_LABELS, label_indexes).await?;
+    manage_table_indexes(db, TABLE_FILE_METADATA, metadata_indexes).await?;
+    manage_table_indexes(db, TABLE_CUSTOM_METADATA, custom_metadata_indexes).await?;
+    manage_table_indexes(db, TABLE_FOLDER_STYLES, folder_style_indexes).await?;
+    manage_table_indexes(db, TABLE_FILE_STARS, star_indexes).await?;
+    manage_table_indexes(db, TABLE_USAGE_SNAPSHOTS, usage_snapshot_indexes).await?;
 
     tracing::info!("Database index management completed");
     Ok(())
@@ -217,6 +466,23 @@ pub async fn drop_composite_indexes(db: &DatabaseConnection) -> Result<(), DbErr
     drop_index(db, INDEX_FILES_USER_PARENT).await?;
     drop_index(db, INDEX_FILES_USER_PATH).await?;
     drop_index(db, INDEX_PERMISSIONS_FILE_USER).await?;
+    drop_index(db, INDEX_AUDIT_LOGS_USER_CREATED).await?;
+    drop_index(db, INDEX_AUDIT_LOGS_EVENT_CREATED).await?;
+    drop_index(db, INDEX_AUDIT_LOGS_FILE_CREATED).await?;
+    drop_index(db, INDEX_EMAIL_OUTBOX_PENDING).await?;
+    drop_index(db, INDEX_NOTIFICATIONS_USER_CREATED).await?;
+    drop_index(db, INDEX_NOTIFICATION_PREFS_USER_CATEGORY).await?;
+    drop_index(db, INDEX_FOLDER_DEFAULTS_FOLDER_USER).await?;
+    drop_index(db, INDEX_FILE_STATS_DOWNLOADS).await?;
+    drop_index(db, INDEX_FILE_CHANGES_USER_ID).await?;
+    drop_index(db, INDEX_AUDIO_METADATA_USER_ARTIST_ALBUM).await?;
+    drop_index(db, INDEX_PHOTO_LOCATIONS_USER_LAT_LON).await?;
+    drop_index(db, INDEX_FILE_LABELS_USER_LABEL).await?;
+    drop_index(db, INDEX_FILE_LABELS_FILE_ID).await?;
+    drop_index(db, INDEX_FILE_METADATA_FILE_SOURCE_KEY).await?;
+    drop_index(db, INDEX_FILE_METADATA_USER_KEY).await?;
+    drop_index(db, INDEX_CUSTOM_METADATA_FILE_KEY).await?;
+    drop_index(db, INDEX_CUSTOM_METADATA_USER_KEY_VALUE).await?;
 
     drop_index(db, "idx_files_user_parent_type").await?;
     drop_index(db, "idx_files_name_search").await?;
@@ -233,7 +499,23 @@ pub async fn drop_composite_indexes(db: &DatabaseConnection) -> Result<(), DbErr
 pub async fn verify_indexes(db: &DatabaseConnection) -> Result<(), DbErr> {
     tracing::info!("Verifying database indexes...");
 
-    let tables = vec![TABLE_FILES, TABLE_FILE_PERMISSIONS, TABLE_USERS];
+    let tables = vec![
+        TABLE_FILES,
+        TABLE_FILE_PERMISSIONS,
+        TABLE_USERS,
+        TABLE_AUDIT_LOGS,
+        TABLE_EMAIL_OUTBOX,
+        TABLE_NOTIFICATIONS,
+        TABLE_NOTIFICATION_PREFERENCES,
+        TABLE_FOLDER_DEFAULT_PERMISSIONS,
+        TABLE_FILE_STATS,
+        TABLE_FILE_CHANGES,
+        TABLE_AUDIO_METADATA,
+        TABLE_PHOTO_LOCATIONS,
+        TABLE_FILE_LABELS,
+        TABLE_FILE_METADATA,
+        TABLE_CUSTOM_METADATA,
+    ];
     let mut total_indexes = 0;
 
     for table in tables {
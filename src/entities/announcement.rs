@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const SEVERITY_INFO: &str = "info";
+pub const SEVERITY_WARNING: &str = "warning";
+pub const SEVERITY_CRITICAL: &str = "critical";
+
+/// Banner an administrator publishes to every user, e.g. a maintenance window
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "announcements")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    pub title: String,
+
+    pub body: String,
+
+    /// "info", "warning" or "critical"
+    pub severity: String,
+
+    /// Shown from this time on, immediately when unset
+    pub starts_at: Option<DateTime>,
+
+    /// Hidden after this time, shown until deleted when unset
+    pub ends_at: Option<DateTime>,
+
+    /// Admin who published it
+    pub created_by: i32,
+
+    pub created_at: DateTime,
+}
+
+impl Model {
+    /// Whether the banner should be shown at `now`
+    pub fn is_active(&self, now: DateTime) -> bool {
+        self.starts_at.is_none_or(|t| t <= now) && self.ends_at.is_none_or(|t| t > now)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
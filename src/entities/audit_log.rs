@@ -0,0 +1,48 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_logs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// User the event belongs to (None for failed logins of unknown users)
+    pub user_id: Option<i32>,
+
+    /// Event type, see `services::audit::AuditEvent`
+    pub event: String,
+
+    /// File the event concerns, if any
+    pub file_id: Option<i32>,
+
+    /// Client IP address
+    pub ip_address: Option<String>,
+
+    /// Client User-Agent header
+    pub user_agent: Option<String>,
+
+    /// Event specific details as JSON
+    pub details: Option<String>,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// One run of the scheduled file backup
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "backup_runs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// running, completed or failed
+    pub status: String,
+
+    /// Full runs copy every file, incremental ones only what changed since the last run
+    pub full: bool,
+
+    /// Last change feed entry covered, the next incremental run starts after it
+    pub cursor: i32,
+
+    /// Entries written to the manifest and bytes copied to the target
+    pub entries: i64,
+    pub bytes: i64,
+
+    /// Reason the run failed
+    pub error: Option<String>,
+
+    pub started_at: DateTime,
+    pub finished_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
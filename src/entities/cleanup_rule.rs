@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Retention rule deleting old files of a user's folder, e.g. items of `/Downloads`
+/// older than 30 days
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "cleanup_rules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    #[sea_orm(indexed)]
+    pub user_id: i32,
+
+    /// Folder cleaned up, files in its subfolders included
+    pub folder_path: String,
+
+    /// Comma separated lowercase extensions without the dot, e.g. `png,jpg`
+    pub extensions: Option<String>,
+
+    /// Lowercase text the file name has to contain, e.g. `screenshot`
+    pub name_contains: Option<String>,
+
+    /// Files unchanged for longer than this are deleted
+    pub max_age_days: i32,
+
+    /// When the user was told which files are due, they are deleted a notice period later
+    pub warned_at: Option<DateTime>,
+
+    pub last_run_at: Option<DateTime>,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "download_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    /// Random token used in the presigned URL
+    #[sea_orm(unique, indexed)]
+    pub token: String,
+    /// File the URL downloads
+    pub file_id: i32,
+    /// User who requested the URL, their permissions apply on download
+    pub user_id: i32,
+    /// Only requests from this IP may use the URL (None = any)
+    pub bound_ip: Option<String>,
+    /// The URL stops working after its first download
+    #[sea_orm(default_value = false)]
+    pub single_use: bool,
+    /// Set when a single-use URL has been used
+    pub used_at: Option<DateTime>,
+    pub expires_at: DateTime,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::file::Entity",
+        from = "Column::FileId",
+        to = "super::file::Column::Id"
+    )]
+    File,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
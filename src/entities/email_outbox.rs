@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "email_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Recipient address
+    pub to_address: String,
+
+    pub subject: String,
+
+    /// Plain-text body
+    pub body: String,
+
+    /// pending, sent or failed
+    pub status: String,
+
+    /// Delivery attempts so far
+    pub attempts: i32,
+
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+
+    /// Earliest time of the next delivery attempt
+    pub next_attempt_at: DateTime,
+
+    pub created_at: DateTime,
+    pub sent_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Admin decision turning a feature on or off, for one user, one role or everyone
+/// A user override wins over a role override, which wins over one for everyone
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "feature_overrides")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    /// Feature name, e.g. `public_sharing`
+    #[sea_orm(indexed)]
+    pub feature: String,
+
+    /// Role the override applies to, unset together with `user_id` for everyone
+    pub role: Option<String>,
+
+    /// User the override applies to
+    #[sea_orm(indexed)]
+    pub user_id: Option<i32>,
+
+    pub enabled: bool,
+
+    /// Administrator who set the override
+    pub updated_by: i32,
+
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -31,6 +31,11 @@ pub struct Model {
     #[sea_orm(nullable)]
     pub size_bytes: Option<i64>,
 
+    /// Total size of the files below a folder (folders only), kept up to date
+    /// incrementally and recomputed periodically
+    #[sea_orm(nullable)]
+    pub total_size_bytes: Option<i64>,
+
     /// Physical storage path
     pub storage_path: String,
 
@@ -42,7 +47,61 @@ pub struct Model {
     #[sea_orm(default_value = 1)]
     pub ref_count: i32,
 
+    /// Incremented on every change, exposed as the ETag for optimistic concurrency
+    #[sea_orm(default_value = 1)]
+    pub version: i32,
+
+    /// Replication to the secondary target: replicated or failed, NULL while not attempted
+    #[sea_orm(nullable)]
+    pub replication_status: Option<String>,
+
+    /// When the current content was last copied to the secondary target
+    #[sea_orm(nullable)]
+    #[serde(serialize_with = "crate::utils::timestamp::serialize_option")]
+    pub replicated_at: Option<DateTime>,
+
+    /// Location of the replica on the secondary target
+    #[sea_orm(nullable)]
+    pub replica_path: Option<String>,
+
+    /// Kept available offline by sync clients, a pinned folder covers its contents
+    /// Cleanup and tiering jobs must leave pinned content where it is
+    #[sea_orm(default_value = false)]
+    pub pinned: bool,
+
+    /// Modification time reported by the client that wrote the file, kept apart
+    /// from the server timestamps so sync clients can restore it
+    #[sea_orm(nullable)]
+    #[serde(serialize_with = "crate::utils::timestamp::serialize_option")]
+    pub client_modified: Option<DateTime>,
+
+    /// Set by administrators to keep a file, or a folder with its contents, from being
+    /// deleted, moved or overwritten until the hold is lifted
+    #[sea_orm(default_value = false)]
+    pub legal_hold: bool,
+
+    /// Why the hold was placed, e.g. a case reference
+    #[sea_orm(nullable)]
+    pub legal_hold_reason: Option<String>,
+
+    /// Set when a scanner flagged the content, nobody but administrators can download
+    /// the file until one of them releases or deletes it
+    #[sea_orm(nullable)]
+    #[serde(serialize_with = "crate::utils::timestamp::serialize_option")]
+    pub quarantined_at: Option<DateTime>,
+
+    /// Threat the scanner reported and the hook that found it
+    #[sea_orm(nullable)]
+    pub quarantine_reason: Option<String>,
+
+    /// Taken down by an administrator, the file is kept but cannot be downloaded or shared
+    /// until the takedown is lifted
+    #[sea_orm(default_value = false)]
+    pub taken_down: bool,
+
+    #[serde(serialize_with = "crate::utils::timestamp::serialize")]
     pub created_at: DateTime,
+    #[serde(serialize_with = "crate::utils::timestamp::serialize")]
     pub updated_at: DateTime,
 }
 
@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// One entry of the changes feed sync clients poll, the ID is the cursor
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "file_changes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Owner of the tree the change happened in
+    pub user_id: i32,
+
+    pub file_id: i32,
+
+    /// Change type, see `services::changes`
+    pub change: String,
+
+    /// Path after the change (last path for deletions)
+    pub path: String,
+
+    /// Path before a move or rename, for conflicts the path the upload was meant for
+    #[sea_orm(nullable)]
+    pub previous_path: Option<String>,
+
+    /// Client modification time of the file when the change was made
+    #[sea_orm(nullable)]
+    pub client_modified: Option<DateTime>,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
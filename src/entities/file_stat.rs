@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Usage counters kept per file
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "file_stats")]
+pub struct Model {
+    /// File the counters belong to
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub file_id: i32,
+
+    /// Number of times the file was downloaded
+    #[sea_orm(default_value = 0)]
+    pub download_count: i64,
+
+    /// Time of the most recent download
+    pub last_downloaded_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::file::Entity",
+        from = "Column::FileId",
+        to = "super::file::Column::Id"
+    )]
+    File,
+}
+
+impl Related<super::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,59 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Long-running background operation started by a user
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// User who started the job
+    pub user_id: i32,
+
+    /// Job type, e.g. batch_download
+    pub kind: String,
+
+    /// pending, running, completed, failed or expired
+    pub status: String,
+
+    /// Items to process and items done so far
+    pub total_items: i64,
+    pub processed_items: i64,
+
+    /// Bytes to process and bytes done so far
+    pub total_bytes: i64,
+    pub processed_bytes: i64,
+
+    /// Generated artifact (e.g. ZIP archive) while it is kept
+    pub result_path: Option<String>,
+    pub result_size: Option<i64>,
+
+    /// Reason the job failed
+    pub error: Option<String>,
+
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+
+    /// The result is deleted after this time
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,60 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Access to a Shared Library folder and everything below it, for a role or a group
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "library_grants")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Library folder path, "/" for the whole library
+    pub path: String,
+
+    /// Role the grant applies to (exactly one of role and group_id is set)
+    #[sea_orm(nullable)]
+    pub role: Option<String>,
+
+    /// Group the grant applies to
+    #[sea_orm(nullable)]
+    pub group_id: Option<i32>,
+
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+
+    /// Granter ID (usually admin)
+    pub granted_by: i32,
+
+    pub created_at: DateTime,
+}
+
+impl Model {
+    /// Whether the grant covers `path`
+    pub fn covers(&self, path: &str) -> bool {
+        self.path == "/"
+            || path == self.path
+            || path
+                .strip_prefix(&self.path)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::group::Entity",
+        from = "Column::GroupId",
+        to = "super::group::Column::Id"
+    )]
+    Group,
+}
+
+impl Related<super::group::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Group.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
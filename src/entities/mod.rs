@@ -1,3 +1,36 @@
+pub mod announcement;
+pub mod audio_cover;
+pub mod audio_metadata;
+pub mod audit_log;
+pub mod backup_run;
+pub mod cleanup_rule;
+pub mod custom_metadata;
+pub mod daily_download;
+pub mod download_token;
+pub mod email_outbox;
+pub mod feature_override;
 pub mod file;
+pub mod file_change;
+pub mod file_label;
+pub mod file_metadata;
 pub mod file_permission;
+pub mod file_star;
+pub mod file_stat;
+pub mod folder_default_permission;
+pub mod folder_style;
+pub mod group;
+pub mod group_member;
+pub mod job;
+pub mod library_grant;
+pub mod mount;
+pub mod notification;
+pub mod notification_preference;
+pub mod organize_rule;
+pub mod permission_template;
+pub mod photo_location;
+pub mod share_link;
+pub mod takedown;
+pub mod upload_chunk;
+pub mod upload_session;
+pub mod usage_snapshot;
 pub mod user;
@@ -0,0 +1,60 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Host directory shown as a folder in a user's tree, its bytes stay where they are
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "mounts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// User whose tree shows the mount
+    pub user_id: i32,
+
+    /// Folder name at the root of the tree, e.g. "Media"
+    pub name: String,
+
+    /// Absolute directory on the host
+    pub host_path: String,
+
+    /// Users may only browse and download
+    pub read_only: bool,
+
+    pub last_indexed_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+impl Model {
+    /// Path of the mount folder in the user's tree
+    pub fn mount_point(&self) -> String {
+        format!("/{}", self.name)
+    }
+
+    /// Whether `path` is the mount folder or lies inside it
+    pub fn contains(&self, path: &str) -> bool {
+        let mount_point = self.mount_point();
+        path == mount_point
+            || path
+                .strip_prefix(&mount_point)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Where a user's new uploads of a folder are filed, e.g. images uploaded to `/`
+/// go to `/Photos/{YYYY}/{MM}`
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "organize_rules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    #[sea_orm(indexed)]
+    pub user_id: i32,
+
+    /// Folder whose uploads the rule applies to, subfolders are not covered
+    pub folder_path: String,
+
+    /// Comma separated lowercase extensions without the dot, e.g. `jpg,png`
+    pub extensions: Option<String>,
+
+    /// Start of the MIME type, e.g. `image/`
+    pub mime_prefix: Option<String>,
+
+    /// Destination folder, `{YYYY}`, `{MM}` and `{DD}` take the date of the file
+    pub destination: String,
+
+    /// Rules are tried in ascending order, the first match wins
+    pub position: i32,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,50 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Named, reusable set of permission flags (e.g. "reviewers: read-only")
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "permission_templates")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Template name
+    #[sea_orm(unique, indexed)]
+    pub name: String,
+
+    /// Optional description shown to administrators
+    pub description: Option<String>,
+
+    /// Read permission
+    pub can_read: bool,
+
+    /// Write permission
+    pub can_write: bool,
+
+    /// Delete permission
+    pub can_delete: bool,
+
+    /// Admin who created the template
+    pub created_by: i32,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedBy",
+        to = "super::user::Column::Id"
+    )]
+    Creator,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Creator.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
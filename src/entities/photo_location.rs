@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// GPS position read from the EXIF data of an uploaded image
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "photo_locations")]
+pub struct Model {
+    /// Image the position belongs to
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub file_id: i32,
+
+    /// Owner of the file
+    pub user_id: i32,
+
+    /// Decimal degrees, negative south of the equator
+    pub latitude: f64,
+
+    /// Decimal degrees, negative west of Greenwich
+    pub longitude: f64,
+
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::file::Entity",
+        from = "Column::FileId",
+        to = "super::file::Column::Id"
+    )]
+    File,
+}
+
+impl Related<super::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
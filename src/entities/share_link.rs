@@ -0,0 +1,60 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "share_links")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Random public token used in the share URL
+    #[sea_orm(unique, indexed)]
+    pub token: String,
+
+    /// Shared file ID
+    pub file_id: i32,
+
+    /// User who created the link
+    pub created_by: i32,
+
+    /// Maximum number of downloads (None = unlimited)
+    pub max_downloads: Option<i32>,
+
+    /// Downloads served so far
+    #[sea_orm(default_value = 0)]
+    pub download_count: i32,
+
+    /// Link stops working after this time (None = never)
+    pub expires_at: Option<DateTime>,
+
+    /// Set when the owner revokes the link
+    pub revoked_at: Option<DateTime>,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::file::Entity",
+        from = "Column::FileId",
+        to = "super::file::Column::Id"
+    )]
+    File,
+
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedBy",
+        to = "super::user::Column::Id"
+    )]
+    Creator,
+}
+
+impl Related<super::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Takedown of a file by an administrator, e.g. after a DMCA notice
+/// The row stays as the record of the notice, the counter-notice and the outcome
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "takedowns")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    #[sea_orm(indexed)]
+    pub file_id: i32,
+
+    /// Owner of the file when it was taken down
+    #[sea_orm(indexed)]
+    pub owner_id: i32,
+
+    /// Why the file was taken down, e.g. the notice it answers
+    pub reason: String,
+
+    /// Administrator who took the file down
+    pub issued_by: i32,
+
+    /// active, countered, restored or upheld
+    pub status: String,
+
+    /// Statement of the owner disputing the takedown
+    pub counter_notice: Option<String>,
+    pub countered_at: Option<DateTime>,
+
+    /// Administrator who restored the file or upheld the takedown
+    pub resolved_by: Option<i32>,
+    pub resolution_note: Option<String>,
+    pub resolved_at: Option<DateTime>,
+
+    pub created_at: DateTime,
+}
+
+/// No foreign key to the file, the record outlives it
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
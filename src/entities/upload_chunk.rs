@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Chunk stored for an upload session, checked again when the session is completed
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "upload_chunks")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    #[sea_orm(indexed)]
+    pub session_id: i32,
+
+    /// Position of the chunk's first byte in the file
+    pub offset: i64,
+
+    pub size: i64,
+
+    /// SHA-256 of the chunk's bytes as received
+    pub checksum: String,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::upload_session::Entity",
+        from = "Column::SessionId",
+        to = "super::upload_session::Column::Id"
+    )]
+    UploadSession,
+}
+
+impl Related<super::upload_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UploadSession.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,64 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Chunked upload in progress, its bytes are staged until it is completed
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "upload_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+
+    /// Uploader, the file is stored in their tree
+    pub user_id: i32,
+
+    /// Folder the file goes to
+    pub path: String,
+
+    pub file_name: String,
+
+    #[sea_orm(nullable)]
+    pub mime_type: Option<String>,
+
+    /// Conflict mode applied on completion
+    pub on_conflict: String,
+
+    /// Server hash the sync client last saw for the target file
+    #[sea_orm(nullable)]
+    pub base_hash: Option<String>,
+
+    /// Client modification time stored with the file on completion
+    #[sea_orm(nullable)]
+    pub client_modified: Option<DateTime>,
+
+    /// Declared size and bytes received so far
+    pub total_bytes: i64,
+    pub received_bytes: i64,
+
+    /// Partial file in the uploads staging area
+    pub staging_path: String,
+
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+
+    /// Sessions without a chunk until then are dropped with their bytes
+    pub expires_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
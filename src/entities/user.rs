@@ -19,6 +19,40 @@ pub struct Model {
 
     pub role: String,
 
+    /// Incremented to invalidate all previously issued tokens
+    #[serde(skip)]
+    #[sea_orm(default_value = 0)]
+    pub token_version: i32,
+
+    /// Email the user when a login comes from a new device or location
+    #[sea_orm(default_value = true)]
+    pub login_alerts_enabled: bool,
+
+    /// Storage root holding this user's files, assigned on first write
+    #[serde(skip)]
+    pub storage_root: Option<String>,
+
+    /// Name shown instead of the username
+    pub display_name: Option<String>,
+
+    /// Preferred language tag, e.g. `en` or `zh-CN`
+    pub locale: Option<String>,
+
+    /// IANA time zone, e.g. `Asia/Shanghai`
+    pub timezone: Option<String>,
+
+    /// New email awaiting confirmation, `email` is kept until then
+    pub pending_email: Option<String>,
+
+    #[serde(skip)]
+    pub email_token: Option<String>,
+
+    #[serde(skip)]
+    pub email_token_expires_at: Option<DateTime>,
+
+    /// When the account and its data will be purged, set by a deletion request
+    pub deletion_scheduled_at: Option<DateTime>,
+
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
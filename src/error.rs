@@ -1,14 +1,74 @@
-use crate::utils::{request_id, response::error_resp};
+use crate::utils::{
+    i18n::{self, Message, Text},
+    request_id,
+    response::error_code_resp,
+};
 use axum::{http::StatusCode, response::Response};
+use serde::Serialize;
 use thiserror::Error;
 
+/// Machine-readable reason of a failed request, stable across releases and languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidRequest,
+    ValidationFailed,
+    Unauthenticated,
+    NoPermission,
+    NotFound,
+    /// An item with the same name already exists
+    NameConflict,
+    /// The request conflicts with the current state, e.g. work still in progress
+    Conflict,
+    Gone,
+    PreconditionFailed,
+    PayloadTooLarge,
+    RangeNotSatisfiable,
+    /// Not enough storage quota or free disk space
+    QuotaExceeded,
+    RequestTimeout,
+    DatabaseError,
+    InternalError,
+    NotImplemented,
+    Unavailable,
+    /// Too many requests are in progress, retry after the `Retry-After` delay
+    Overloaded,
+    /// The file is under legal hold and cannot be deleted, moved or overwritten
+    LegalHold,
+}
+
+impl ErrorCode {
+    /// Code implied by a status when the handler gives no more specific one
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthenticated,
+            StatusCode::FORBIDDEN => ErrorCode::NoPermission,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::CONFLICT => ErrorCode::NameConflict,
+            StatusCode::GONE => ErrorCode::Gone,
+            StatusCode::LOCKED => ErrorCode::LegalHold,
+            StatusCode::PRECONDITION_FAILED => ErrorCode::PreconditionFailed,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::RANGE_NOT_SATISFIABLE => ErrorCode::RangeNotSatisfiable,
+            StatusCode::INSUFFICIENT_STORAGE => ErrorCode::QuotaExceeded,
+            StatusCode::NOT_IMPLEMENTED => ErrorCode::NotImplemented,
+            StatusCode::SERVICE_UNAVAILABLE => ErrorCode::Unavailable,
+            s if s.is_server_error() => ErrorCode::InternalError,
+            _ => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] sea_orm::DbErr),
 
     #[error("Authentication error: {0}")]
-    Auth(String),
+    Auth(&'static Message),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(&'static Message),
 
     #[error("Validation error: {0}")]
     Validation(String),
@@ -24,29 +84,51 @@ impl AppError {
     pub fn into_response(self) -> Response {
         let req_id = request_id::generate_request_id();
 
+        let code = self.code();
         let (status, message) = match self {
             AppError::Database(err) => {
                 tracing::error!(request_id = %req_id, error = ?err, "Database error");
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error occurred")
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Text::Catalog(&i18n::COMMON_DATABASE_ERROR),
+                )
             }
-            AppError::Auth(ref msg) => {
+            AppError::Auth(msg) => {
                 tracing::warn!(request_id = %req_id, message = %msg, "Authentication error");
-                (StatusCode::UNAUTHORIZED, msg.as_str())
+                (StatusCode::UNAUTHORIZED, Text::Catalog(msg))
+            }
+            AppError::Forbidden(msg) => {
+                tracing::warn!(request_id = %req_id, message = %msg, "Forbidden");
+                (StatusCode::FORBIDDEN, Text::Catalog(msg))
             }
-            AppError::Validation(ref msg) => {
+            AppError::Validation(msg) => {
                 tracing::warn!(request_id = %req_id, message = %msg, "Validation error");
-                (StatusCode::BAD_REQUEST, msg.as_str())
+                (StatusCode::BAD_REQUEST, Text::Plain(msg))
             }
-            AppError::NotFound(ref msg) => {
+            AppError::NotFound(msg) => {
                 tracing::warn!(request_id = %req_id, message = %msg, "Not found");
-                (StatusCode::NOT_FOUND, msg.as_str())
+                (StatusCode::NOT_FOUND, Text::Plain(msg))
             }
             AppError::Internal(err) => {
                 tracing::error!(request_id = %req_id, error = ?err, "Internal error");
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Text::Catalog(&i18n::COMMON_INTERNAL_ERROR),
+                )
             }
         };
 
-        error_resp(status, req_id, message)
+        error_code_resp(status, code, req_id, message)
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Database(_) => ErrorCode::DatabaseError,
+            AppError::Auth(_) => ErrorCode::Unauthenticated,
+            AppError::Forbidden(_) => ErrorCode::NoPermission,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Internal(_) => ErrorCode::InternalError,
+        }
     }
 }
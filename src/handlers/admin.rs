@@ -0,0 +1,1175 @@
+use crate::{
+    entities::{audit_log, file, file_stat, job},
+    error::ErrorCode,
+    models::admin::{
+        AuditLogEntry, AuditLogPage, AuditLogQuery, BackupRunItem, ConsistencyQuery,
+        ConsistencyRepairRequest, LegalHoldItem, MostDownloadedFile, ReplicaItem,
+        ReplicationReport, ReportQuery, SetLegalHoldRequest, StatsQuery, StorageMigrationRequest,
+        UpdateJobLimitsRequest,
+    },
+    services::{
+        admin_stats,
+        audit::{self, AuditEvent},
+        backup, consistency, disk_space,
+        job_scheduler::{self, JobClass, Priority},
+        jobs, legal_hold, mounts, replication, search, staging, storage_migration, volumes,
+    },
+    utils::{
+        client::ClientInfo,
+        export, file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_code_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+    Extension,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+
+const DEFAULT_AUDIT_LIMIT: u64 = 50;
+const MAX_AUDIT_LIMIT: u64 = 500;
+const MAX_AUDIT_EXPORT_ROWS: u64 = 50_000;
+const DEFAULT_REPORT_LIMIT: u64 = 20;
+const MAX_REPORT_LIMIT: u64 = 100;
+const DEFAULT_CONSISTENCY_LIMIT: u64 = 100;
+const MAX_CONSISTENCY_LIMIT: u64 = 1000;
+const DEFAULT_STATS_DAYS: i64 = 30;
+const MAX_STATS_DAYS: i64 = 365;
+
+/// Aggregated usage numbers for the admin dashboard (admin only)
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_STATS_ONLY);
+    }
+
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_STATS_DAYS)
+        .clamp(1, MAX_STATS_DAYS);
+
+    match admin_stats::collect(&state.db, days).await {
+        Ok(stats) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ADMIN_STATS_RETRIEVED,
+            Some(stats),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to aggregate statistics");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Query the audit log with filters, newest first (admin only)
+pub async fn query_audit_log(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AuditLogQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_AUDIT_ONLY);
+    }
+
+    let condition = match audit_condition(&query) {
+        Ok(c) => c,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    let total = match audit_log::Entity::find()
+        .filter(condition.clone())
+        .count(&state.db)
+        .await
+    {
+        Ok(n) => n,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let entries = match audit_log::Entity::find()
+        .filter(condition)
+        .order_by_desc(audit_log::Column::CreatedAt)
+        .order_by_desc(audit_log::Column::Id)
+        .offset(offset)
+        .limit(limit)
+        .all(&state.db)
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::ADMIN_AUDIT_RETRIEVED,
+        Some(AuditLogPage {
+            total,
+            limit,
+            offset,
+            entries: entries.into_iter().map(to_entry).collect(),
+        }),
+    )
+}
+
+/// Export the filtered audit log as CSV or JSON Lines, oldest first (admin only)
+pub async fn export_audit_log(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AuditLogQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_AUDIT_EXPORT_ONLY,
+        );
+    }
+
+    let format = query.format.as_deref().unwrap_or("csv");
+    let (content_type, extension) = match format {
+        "csv" => ("text/csv; charset=utf-8", "csv"),
+        "jsonl" => ("application/x-ndjson", "jsonl"),
+        _ => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Unsupported export format, use csv or jsonl",
+            );
+        }
+    };
+
+    let condition = match audit_condition(&query) {
+        Ok(c) => c,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(MAX_AUDIT_EXPORT_ROWS)
+        .clamp(1, MAX_AUDIT_EXPORT_ROWS);
+
+    let entries = match audit_log::Entity::find()
+        .filter(condition)
+        .order_by_asc(audit_log::Column::CreatedAt)
+        .order_by_asc(audit_log::Column::Id)
+        .offset(query.offset.unwrap_or(0))
+        .limit(limit)
+        .all(&state.db)
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let row_count = entries.len();
+    let mut body = String::new();
+    if extension == "csv" {
+        body.push_str(&export::csv_row(&[
+            "id",
+            "created_at",
+            "user_id",
+            "event",
+            "file_id",
+            "ip_address",
+            "user_agent",
+            "details",
+        ]));
+        for e in entries {
+            body.push_str(&export::csv_row(&[
+                e.id.to_string(),
+                timestamp::format(e.created_at),
+                e.user_id.map(|id| id.to_string()).unwrap_or_default(),
+                e.event,
+                e.file_id.map(|id| id.to_string()).unwrap_or_default(),
+                e.ip_address.unwrap_or_default(),
+                e.user_agent.unwrap_or_default(),
+                e.details.unwrap_or_default(),
+            ]));
+        }
+    } else {
+        for e in entries {
+            match serde_json::to_string(&to_entry(e)) {
+                Ok(line) => {
+                    body.push_str(&line);
+                    body.push('\n');
+                }
+                Err(e) => {
+                    tracing::error!(request_id = %request_id, error = %e, "Failed to serialize audit entry");
+                    return error_resp(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        request_id,
+                        &i18n::COMMON_INTERNAL_ERROR,
+                    );
+                }
+            }
+        }
+    }
+
+    tracing::info!(request_id = %request_id, rows = row_count, format = format, "Audit log exported");
+
+    let filename = format!(
+        "audit-log-{}.{}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        extension
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(axum::body::Body::from(body))
+        .unwrap()
+}
+
+/// Report the most downloaded files across all users (admin only)
+pub async fn most_downloaded_files(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_REPORTS_ONLY);
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_REPORT_LIMIT)
+        .clamp(1, MAX_REPORT_LIMIT);
+
+    let rows = match file_stat::Entity::find()
+        .find_also_related(file::Entity)
+        .order_by_desc(file_stat::Column::DownloadCount)
+        .order_by_desc(file_stat::Column::LastDownloadedAt)
+        .limit(limit)
+        .all(&state.db)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let report: Vec<MostDownloadedFile> = rows
+        .into_iter()
+        .filter_map(|(stat, f)| {
+            let f = f?;
+            Some(MostDownloadedFile {
+                file_id: f.id,
+                name: f.name,
+                path: f.path,
+                owner_id: f.user_id,
+                download_count: stat.download_count,
+                last_downloaded_at: stat.last_downloaded_at.map(timestamp::format),
+            })
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        "Most downloaded files retrieved",
+        Some(report),
+    )
+}
+
+/// Build the filter shared by the query and export endpoints
+fn audit_condition(query: &AuditLogQuery) -> Result<Condition, &'static str> {
+    let mut condition = Condition::all();
+
+    if let Some(user_id) = query.user_id {
+        condition = condition.add(audit_log::Column::UserId.eq(user_id));
+    }
+    if let Some(action) = query.action.as_deref().filter(|a| !a.is_empty()) {
+        condition = condition.add(audit_log::Column::Event.eq(action));
+    }
+    if let Some(file_id) = query.file_id {
+        condition = condition.add(audit_log::Column::FileId.eq(file_id));
+    }
+    if let Some(from) = query.from.as_deref() {
+        let from = export::parse_time_bound(from, false).ok_or("Invalid 'from' date")?;
+        condition = condition.add(audit_log::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = query.to.as_deref() {
+        let to = export::parse_time_bound(to, true).ok_or("Invalid 'to' date")?;
+        condition = condition.add(audit_log::Column::CreatedAt.lte(to));
+    }
+
+    Ok(condition)
+}
+
+fn to_entry(e: audit_log::Model) -> AuditLogEntry {
+    AuditLogEntry {
+        id: e.id,
+        user_id: e.user_id,
+        event: e.event,
+        file_id: e.file_id,
+        ip_address: e.ip_address,
+        user_agent: e.user_agent,
+        details: e.details.and_then(|d| serde_json::from_str(&d).ok()),
+        created_at: timestamp::format(e.created_at),
+    }
+}
+
+/// Size of the temporary staging directory per area and the free disk space (admin only)
+pub async fn get_staging_usage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            "Only administrators can view staging usage",
+        );
+    }
+
+    let config = state.config.clone();
+    match tokio::task::spawn_blocking(move || staging::usage(&config)).await {
+        Ok(Ok(usage)) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            "Staging usage retrieved",
+            Some(usage),
+        ),
+        Ok(Err(e)) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to measure staging usage");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Staging usage task failed");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Move stored files to a new storage root, for all users or a single one (admin only)
+/// Runs as a `storage_migration` job, the moved users keep storing new files at the destination
+pub async fn migrate_storage(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<StorageMigrationRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_MIGRATION_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let destination = std::path::PathBuf::from(req.destination.trim());
+    if !destination.is_absolute() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Destination must be an absolute path",
+        );
+    }
+
+    // A single user moves off their own volume, otherwise the primary root is moved
+    let root = match req.user_id {
+        Some(user_id) => match volumes::user_root(&state.db, &state.config, user_id).await {
+            Ok(root) => root,
+            Err(DbErr::RecordNotFound(_)) => {
+                return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND);
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        },
+        None => state.config.get_storage_dir(),
+    };
+    let overlaps = match tokio::fs::canonicalize(&root).await {
+        Ok(canonical_root) => {
+            destination.starts_with(&canonical_root)
+                || destination.starts_with(&root)
+                || canonical_root.starts_with(&destination)
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to access storage directory");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::STORAGE_ACCESS_FAILED,
+            );
+        }
+    };
+    if overlaps {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Destination must not overlap the current storage directory",
+        );
+    }
+
+    let running = job::Entity::find()
+        .filter(job::Column::Kind.eq(jobs::KIND_STORAGE_MIGRATION))
+        .filter(job::Column::Status.is_in([jobs::STATUS_PENDING, jobs::STATUS_RUNNING]))
+        .count(&state.db)
+        .await;
+    match running {
+        Ok(0) => {}
+        Ok(_) => {
+            return error_code_resp(
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+                request_id,
+                &i18n::ADMIN_MIGRATION_RUNNING,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let plan = match storage_migration::plan(&state.db, &root, &destination, req.user_id).await {
+        Ok(p) if p.is_empty() => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::ADMIN_NOTHING_TO_MIGRATE,
+            );
+        }
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to collect files");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if let Err(e) = tokio::fs::create_dir_all(&destination).await {
+        tracing::error!(request_id = %request_id, error = %e, "Failed to create destination");
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!("Cannot create destination: {}", e),
+        );
+    }
+    if let Err(msg) = disk_space::ensure_free_space(
+        &destination,
+        plan.total_bytes as u64,
+        state.config.storage.reserve_bytes,
+    )
+    .await
+    {
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
+    }
+
+    match storage_migration::start(&state.db, &state.events, admin_id, plan).await {
+        Ok(job) => {
+            tracing::info!(
+                request_id = %request_id,
+                job_id = job.id,
+                destination = ?destination,
+                user_id = ?req.user_id,
+                "Storage migration started"
+            );
+            do_json_detail_resp(
+                StatusCode::ACCEPTED,
+                request_id,
+                &i18n::ADMIN_MIGRATION_STARTED,
+                Some(jobs::job_info(job)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create job");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+fn to_replica_item(f: file::Model) -> ReplicaItem {
+    ReplicaItem {
+        file_id: f.id,
+        owner_id: f.user_id,
+        path: f.path,
+        status: f.replication_status,
+        updated_at: timestamp::format(f.updated_at),
+        replicated_at: f.replicated_at.map(timestamp::format),
+        replica_path: f.replica_path,
+    }
+}
+
+/// Replication progress with the files lagging behind and replicas missing on the target (admin only)
+pub async fn replication_report(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_REPORTS_ONLY);
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_REPORT_LIMIT)
+        .clamp(1, MAX_REPORT_LIMIT);
+
+    let report = async {
+        let files = || file::Entity::find().filter(file::Column::FileType.eq("file"));
+        let replicated = files()
+            .filter(file::Column::ReplicationStatus.eq(replication::STATUS_REPLICATED))
+            .filter(replication::lagging_condition().not())
+            .count(&state.db)
+            .await?;
+        let failed = files()
+            .filter(file::Column::ReplicationStatus.eq(replication::STATUS_FAILED))
+            .count(&state.db)
+            .await?;
+        let lagging_count = files()
+            .filter(replication::lagging_condition())
+            .count(&state.db)
+            .await?;
+        let lagging = files()
+            .filter(replication::lagging_condition())
+            .order_by_asc(file::Column::UpdatedAt)
+            .limit(limit)
+            .all(&state.db)
+            .await?;
+        let (missing_count, missing) =
+            replication::missing_replicas(&state.db, limit as usize).await?;
+
+        Ok::<_, DbErr>(ReplicationReport {
+            enabled: state.config.replication.enabled,
+            target: state.config.replication.target.clone(),
+            replicated,
+            lagging_count,
+            failed,
+            missing_count,
+            lagging: lagging.into_iter().map(to_replica_item).collect(),
+            missing: missing.into_iter().map(to_replica_item).collect(),
+        })
+    }
+    .await;
+
+    match report {
+        Ok(report) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            "Replication report retrieved",
+            Some(report),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Recent file backup runs (admin only)
+pub async fn list_backup_runs(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_REPORTS_ONLY);
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_REPORT_LIMIT)
+        .clamp(1, MAX_REPORT_LIMIT);
+
+    match backup::list_runs(&state.db, limit).await {
+        Ok(runs) => {
+            let items: Vec<BackupRunItem> = runs
+                .into_iter()
+                .map(|r| BackupRunItem {
+                    id: r.id,
+                    status: r.status,
+                    full: r.full,
+                    entries: r.entries,
+                    bytes: r.bytes,
+                    error: r.error,
+                    started_at: timestamp::format(r.started_at),
+                    finished_at: r.finished_at.map(timestamp::format),
+                })
+                .collect();
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                "Backup runs retrieved",
+                Some(items),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Requests in progress per route and requests refused by load shedding (admin only)
+pub async fn get_load(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_STATS_ONLY);
+    }
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::ADMIN_LOAD_RETRIEVED,
+        Some(state.load.snapshot()),
+    )
+}
+
+/// Rebuild the search engine index from the files table as a background job (admin only)
+pub async fn rebuild_search_index(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            "Only administrators can rebuild the search index",
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let Some(backend) = state.search.clone() else {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "No search engine is configured, file search runs in the database",
+        );
+    };
+
+    let running = job::Entity::find()
+        .filter(job::Column::Kind.eq(jobs::KIND_SEARCH_REINDEX))
+        .filter(job::Column::Status.is_in([jobs::STATUS_PENDING, jobs::STATUS_RUNNING]))
+        .count(&state.db)
+        .await;
+    match running {
+        Ok(0) => {}
+        Ok(_) => {
+            return error_code_resp(
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+                request_id,
+                "The search index is already being rebuilt",
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    match search::start_rebuild(&state.db, &state.events, backend, admin_id).await {
+        Ok(job) => {
+            tracing::info!(
+                request_id = %request_id,
+                job_id = job.id,
+                files = job.total_items,
+                "Search index rebuild started"
+            );
+            do_json_detail_resp(
+                StatusCode::ACCEPTED,
+                request_id,
+                "Search index rebuild started",
+                Some(jobs::job_info(job)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create job");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Background task limits per class with running and waiting counts (admin only)
+pub async fn get_job_limits(Extension(claims): Extension<Claims>) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_STATS_ONLY);
+    }
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        "Job limits retrieved",
+        Some(job_scheduler::snapshot()),
+    )
+}
+
+/// Change background task limits and priorities until the next restart (admin only)
+pub async fn update_job_limits(
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<UpdateJobLimitsRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            "Only administrators can change job limits",
+        );
+    }
+
+    if req.max_concurrent == Some(0) || req.classes.iter().any(|c| c.max_concurrent == Some(0)) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Limits must be at least 1",
+        );
+    }
+
+    let mut classes = Vec::with_capacity(req.classes.len());
+    for entry in &req.classes {
+        let Some(class) = JobClass::parse(&entry.class) else {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Invalid job class, use hashing, thumbnailing, replication, processing or archive",
+            );
+        };
+        let priority = match entry.priority.as_deref() {
+            Some(value) => match Priority::parse(value) {
+                Some(priority) => Some(priority),
+                None => {
+                    return error_resp(
+                        StatusCode::BAD_REQUEST,
+                        request_id,
+                        "Invalid priority, use low, normal or high",
+                    )
+                }
+            },
+            None => None,
+        };
+        classes.push((class, entry.max_concurrent, priority));
+    }
+
+    job_scheduler::update(req.max_concurrent, &classes);
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        "Job limits changed"
+    );
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        "Job limits updated",
+        Some(job_scheduler::snapshot()),
+    )
+}
+
+/// Cross-check file records against physical storage (admin only)
+pub async fn consistency_report(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ConsistencyQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_REPORTS_ONLY);
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONSISTENCY_LIMIT)
+        .clamp(1, MAX_CONSISTENCY_LIMIT);
+
+    match consistency::check(&state.db, &state.config, limit as usize, None).await {
+        Ok(report) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ADMIN_CONSISTENCY_RETRIEVED,
+            Some(report),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Consistency check failed");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Check consistency again and repair the issues covered by the requested actions (admin only)
+pub async fn repair_consistency(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ConsistencyQuery>,
+    Json(req): Json<ConsistencyRepairRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_REPAIR_ONLY);
+    }
+
+    if req.actions.is_empty()
+        || req
+            .actions
+            .iter()
+            .any(|a| !consistency::REPAIR_ACTIONS.contains(&a.as_str()))
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ADMIN_INVALID_REPAIR_ACTION,
+        );
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONSISTENCY_LIMIT)
+        .clamp(1, MAX_CONSISTENCY_LIMIT);
+
+    match consistency::check(&state.db, &state.config, limit as usize, Some(&req.actions)).await {
+        Ok(report) => {
+            tracing::info!(
+                request_id = %request_id,
+                admin = %claims.sub,
+                actions = ?req.actions,
+                repaired = ?report.repaired,
+                "Storage consistency repaired"
+            );
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::ADMIN_REPAIRS_APPLIED,
+                Some(report),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Consistency repair failed");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Files and folders kept in place by a legal hold (admin only)
+pub async fn list_legal_holds(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_LEGAL_HOLD_ONLY,
+        );
+    }
+
+    match legal_hold::list(&state.db).await {
+        Ok(held) => {
+            let items: Vec<LegalHoldItem> = held
+                .into_iter()
+                .map(|f| LegalHoldItem {
+                    file_id: f.id,
+                    owner_id: f.user_id,
+                    path: f.path,
+                    file_type: f.file_type,
+                    reason: f.legal_hold_reason,
+                })
+                .collect();
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::ADMIN_LEGAL_HOLDS_RETRIEVED,
+                Some(items),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to list legal holds");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Place or lift a legal hold, a held file or folder cannot be deleted, moved,
+/// renamed or overwritten until the hold is lifted (admin only)
+pub async fn set_legal_hold(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(req): Json<SetLegalHoldRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_LEGAL_HOLD_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let f = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to load file");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    if req.held {
+        if file_utils::is_protected_path(&f.path) {
+            return error_resp(
+                StatusCode::FORBIDDEN,
+                request_id,
+                &i18n::FILE_PROTECTED_PATH,
+            );
+        }
+        // Mounted host folders change outside of the drive, a hold could not be kept
+        match mounts::find_for_path(&state.db, f.user_id, &f.path).await {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                return error_resp(
+                    StatusCode::BAD_REQUEST,
+                    request_id,
+                    &i18n::ADMIN_LEGAL_HOLD_MOUNTED,
+                );
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Failed to look up mounts");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_INTERNAL_ERROR,
+                );
+            }
+        }
+    }
+
+    let reason = req
+        .reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    let previous_reason = f.legal_hold_reason.clone();
+    let mut active: file::ActiveModel = f.into();
+    active.legal_hold = Set(req.held);
+    active.legal_hold_reason = Set(if req.held { reason.clone() } else { None });
+    let updated = match active.update(&state.db).await {
+        Ok(updated) => updated,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to update legal hold");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let (event, details) = if req.held {
+        (
+            AuditEvent::LegalHoldPlaced,
+            serde_json::json!({ "owner_id": updated.user_id, "path": updated.path, "reason": reason }),
+        )
+    } else {
+        (
+            AuditEvent::LegalHoldLifted,
+            serde_json::json!({ "owner_id": updated.user_id, "path": updated.path, "reason": previous_reason }),
+        )
+    };
+    audit::record_file(
+        &state.db,
+        Some(admin_id),
+        event,
+        &client,
+        Some(updated.id),
+        Some(details),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        file_id = updated.id,
+        held = req.held,
+        "Legal hold updated"
+    );
+
+    let message = if req.held {
+        &i18n::ADMIN_LEGAL_HOLD_PLACED
+    } else {
+        &i18n::ADMIN_LEGAL_HOLD_LIFTED
+    };
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        message,
+        Some(LegalHoldItem {
+            file_id: updated.id,
+            owner_id: updated.user_id,
+            path: updated.path,
+            file_type: updated.file_type,
+            reason: updated.legal_hold_reason,
+        }),
+    )
+}
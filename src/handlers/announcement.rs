@@ -0,0 +1,320 @@
+use crate::{
+    entities::announcement::{self, SEVERITY_CRITICAL, SEVERITY_INFO, SEVERITY_WARNING},
+    models::announcement::{AnnouncementItem, CreateAnnouncementRequest},
+    services::{
+        audit::{self, AuditEvent},
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+    utils::{
+        client::ClientInfo,
+        export, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, Set};
+
+const MAX_TITLE_LENGTH: usize = 200;
+
+fn to_item(a: announcement::Model) -> AnnouncementItem {
+    let active = a.is_active(Utc::now().naive_utc());
+    AnnouncementItem {
+        id: a.id,
+        title: a.title,
+        body: a.body,
+        severity: a.severity,
+        starts_at: a.starts_at.map(timestamp::format),
+        ends_at: a.ends_at.map(timestamp::format),
+        active,
+        created_at: timestamp::format(a.created_at),
+    }
+}
+
+/// Publish an announcement and deliver it to every user's notification center (admin only)
+pub async fn create_announcement(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateAnnouncementRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ANNOUNCEMENT_ADMIN_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let title = payload.title.trim().to_string();
+    let body = payload.body.trim().to_string();
+    if title.is_empty() || body.is_empty() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ANNOUNCEMENT_REQUIRED_FIELDS,
+        );
+    }
+    if title.chars().count() > MAX_TITLE_LENGTH {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ANNOUNCEMENT_TITLE_TOO_LONG,
+        );
+    }
+
+    let severity = payload
+        .severity
+        .as_deref()
+        .unwrap_or(SEVERITY_INFO)
+        .to_string();
+    if ![SEVERITY_INFO, SEVERITY_WARNING, SEVERITY_CRITICAL].contains(&severity.as_str()) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ANNOUNCEMENT_INVALID_SEVERITY,
+        );
+    }
+
+    let parse = |value: &Option<String>, upper| match value {
+        Some(v) => export::parse_time_bound(v, upper).map(Some).ok_or(()),
+        None => Ok(None),
+    };
+    let (starts_at, ends_at) = match (
+        parse(&payload.starts_at, false),
+        parse(&payload.ends_at, true),
+    ) {
+        (Ok(s), Ok(e)) => (s, e),
+        _ => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::ANNOUNCEMENT_INVALID_WINDOW,
+            );
+        }
+    };
+
+    // The window has to end after it starts and in the future
+    let now = Utc::now().naive_utc();
+    let earliest_end = starts_at.map_or(now, |s| s.max(now));
+    if ends_at.is_some_and(|e| e <= earliest_end) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ANNOUNCEMENT_INVALID_WINDOW,
+        );
+    }
+
+    let created = announcement::ActiveModel {
+        title: Set(title),
+        body: Set(body),
+        severity: Set(severity),
+        starts_at: Set(starts_at),
+        ends_at: Set(ends_at),
+        created_by: Set(admin_id),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await;
+
+    let created = match created {
+        Ok(a) => a,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create announcement");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    audit::record(
+        &state.db,
+        Some(admin_id),
+        AuditEvent::AnnouncementPublished,
+        &client,
+        Some(serde_json::json!({ "announcement_id": created.id, "title": created.title })),
+    )
+    .await;
+
+    // Delivering to every user can take a while, the admin does not wait for it
+    let db = state.db.clone();
+    let smtp = state.config.smtp.clone();
+    let (announcement_id, title, body) = (created.id, created.title.clone(), created.body.clone());
+    tokio::spawn(async move {
+        let result = notifications::broadcast(&db, &smtp, |recipient| Notification {
+            category: NotificationCategory::Announcement,
+            title: title.clone(),
+            body: body.clone(),
+            link: None,
+            email: Some(EmailTemplate::Announcement {
+                username: recipient.username.clone(),
+                title: title.clone(),
+                body: body.clone(),
+            }),
+        })
+        .await;
+        match result {
+            Ok(recipients) => {
+                tracing::info!(
+                    announcement_id = announcement_id,
+                    recipients = recipients,
+                    "Announcement delivered"
+                );
+            }
+            Err(e) => {
+                tracing::error!(announcement_id = announcement_id, error = %e, "Failed to deliver announcement");
+            }
+        }
+    });
+
+    tracing::info!(request_id = %request_id, announcement_id = created.id, "Announcement published");
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        &i18n::ANNOUNCEMENT_PUBLISHED,
+        Some(to_item(created)),
+    )
+}
+
+/// List every announcement, newest first (admin only)
+pub async fn list_all_announcements(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ANNOUNCEMENT_ADMIN_ONLY,
+        );
+    }
+
+    match announcement::Entity::find()
+        .order_by_desc(announcement::Column::CreatedAt)
+        .order_by_desc(announcement::Column::Id)
+        .all(&state.db)
+        .await
+    {
+        Ok(items) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ANNOUNCEMENT_LIST_RETRIEVED,
+            Some(items.into_iter().map(to_item).collect::<Vec<_>>()),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Remove an announcement, notifications already delivered are kept (admin only)
+pub async fn delete_announcement(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(announcement_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ANNOUNCEMENT_ADMIN_ONLY,
+        );
+    }
+
+    match announcement::Entity::delete_by_id(announcement_id)
+        .exec(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::ANNOUNCEMENT_NOT_FOUND,
+        ),
+        Ok(_) => {
+            tracing::info!(request_id = %request_id, announcement_id = announcement_id, "Announcement removed");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::ANNOUNCEMENT_REMOVED,
+                None,
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Announcements to show as banners right now, newest first
+pub async fn list_announcements(State(state): State<AppState>) -> Response {
+    let request_id = request_id::generate_request_id();
+    let now = Utc::now().naive_utc();
+
+    match announcement::Entity::find()
+        .order_by_desc(announcement::Column::CreatedAt)
+        .order_by_desc(announcement::Column::Id)
+        .all(&state.db)
+        .await
+    {
+        Ok(items) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ANNOUNCEMENT_LIST_RETRIEVED,
+            Some(
+                items
+                    .into_iter()
+                    .filter(|a| a.is_active(now))
+                    .map(to_item)
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
@@ -1,14 +1,34 @@
 use crate::{
+    constants::ROLE_LIBRARY,
     entities::user,
-    models::auth::{LoginRequest, LoginResponse, RegisterRequest},
+    error::ErrorCode,
+    middleware::auth,
+    models::auth::{LoginRequest, LoginResponse, RegisterRequest, VerifyEmailRequest},
+    services::{
+        audit::{self, AuditEvent},
+        features::{self, Feature},
+        login_alert, user_cache,
+    },
     utils::{
-        jwt, password, request_id,
-        response::{do_json_detail_resp, error_resp},
+        client::ClientInfo,
+        cookie,
+        i18n::{self, Text},
+        jwt::{self, Claims},
+        password, request_id,
+        response::{do_json_detail_resp, error_code_resp, error_resp, EmptyData},
+        validation,
     },
     AppState,
 };
-use axum::{extract::State, http::StatusCode, response::Response, Json};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+    Extension, Json,
+};
+use jsonwebtoken::jwk::JwkSet;
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::json;
 
 pub async fn register(
     State(state): State<AppState>,
@@ -23,26 +43,55 @@ pub async fn register(
         "Register request received"
     );
 
-    if payload.username.trim().is_empty() {
-        tracing::warn!(request_id = %request_id, "Validation failed: empty username");
+    if !features::enabled(
+        &state.db,
+        &state.config.features,
+        Feature::Registration,
+        None,
+    )
+    .await
+    {
+        tracing::warn!(request_id = %request_id, "Registration rejected, the feature is disabled");
         return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::AUTH_REGISTRATION_DISABLED,
+        );
+    }
+
+    let rules = &state.config.validation;
+
+    if let Err(e) = validation::validate_username(&payload.username, rules) {
+        tracing::warn!(request_id = %request_id, error = %e, "Validation failed: username");
+        return error_code_resp(
             StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
             request_id,
-            "Username cannot be empty",
+            e.to_string(),
         );
     }
 
-    if payload.email.trim().is_empty() {
-        tracing::warn!(request_id = %request_id, "Validation failed: empty email");
-        return error_resp(StatusCode::BAD_REQUEST, request_id, "Email cannot be empty");
+    if let Err(e) = validation::validate_email(&payload.email, rules) {
+        tracing::warn!(request_id = %request_id, error = %e, "Validation failed: email");
+        return error_code_resp(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
+            request_id,
+            e.to_string(),
+        );
     }
 
-    if payload.password.len() < 6 {
-        tracing::warn!(request_id = %request_id, "Validation failed: password too short");
-        return error_resp(
+    if let Err(e) = validation::validate_password(
+        &payload.password,
+        &[&payload.username, &payload.email],
+        rules,
+    ) {
+        tracing::warn!(request_id = %request_id, error = %e, "Validation failed: password");
+        return error_code_resp(
             StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
             request_id,
-            "Password must be at least 6 characters",
+            e.to_string(),
         );
     }
 
@@ -57,17 +106,18 @@ pub async fn register(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
     if existing_username.is_some() {
         tracing::warn!(request_id = %request_id, username = %payload.username, "Username already exists");
-        return error_resp(
+        return error_code_resp(
             StatusCode::BAD_REQUEST,
+            ErrorCode::NameConflict,
             request_id,
-            "Username already exists",
+            &i18n::AUTH_USERNAME_TAKEN,
         );
     }
 
@@ -82,27 +132,33 @@ pub async fn register(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
     if existing_email.is_some() {
         tracing::warn!(request_id = %request_id, email = %payload.email, "Email already exists");
-        return error_resp(StatusCode::BAD_REQUEST, request_id, "Email already exists");
+        return error_code_resp(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::NameConflict,
+            request_id,
+            &i18n::AUTH_EMAIL_TAKEN,
+        );
     }
 
-    let password_hash = match password::hash_password(&payload.password) {
-        Ok(h) => h,
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Password hashing error");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Internal server error",
-            );
-        }
-    };
+    let password_hash =
+        match password::hash_password(&payload.password, &state.config.password_hashing).await {
+            Ok(h) => h,
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Password hashing error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_INTERNAL_ERROR,
+                );
+            }
+        };
 
     let now = chrono::Utc::now().naive_utc();
     let new_user = user::ActiveModel {
@@ -110,6 +166,8 @@ pub async fn register(
         email: Set(payload.email.clone()),
         password_hash: Set(password_hash),
         role: Set("user".to_string()),
+        token_version: Set(0),
+        login_alerts_enabled: Set(true),
         created_at: Set(now),
         updated_at: Set(now),
         ..Default::default()
@@ -122,7 +180,7 @@ pub async fn register(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
@@ -134,36 +192,23 @@ pub async fn register(
         "User created successfully"
     );
 
-    let token = match jwt::create_token(user.id, &user.username, state.config.jwt_secret()) {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Token creation error");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Internal server error",
-            );
-        }
-    };
-
-    let response = LoginResponse {
-        token,
-        user_id: user.id,
-        username: user.username.clone(),
-        role: user.role,
-    };
-
     tracing::info!(request_id = %request_id, user_id = user.id, "Registration completed successfully");
 
-    do_json_detail_resp(
-        StatusCode::CREATED,
+    token_response(
+        &state,
         request_id,
+        StatusCode::CREATED,
         "Registration completed successfully",
-        Some(response),
+        user,
+        false,
     )
 }
 
-pub async fn login(State(state): State<AppState>, Json(payload): Json<LoginRequest>) -> Response {
+pub async fn login(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(payload): Json<LoginRequest>,
+) -> Response {
     let request_id = request_id::generate_request_id();
 
     tracing::info!(
@@ -178,13 +223,22 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<LoginReque
         .await;
 
     let user = match user_result {
-        Ok(Some(u)) => u,
-        Ok(None) => {
+        // The Shared Library account has no password and never logs in
+        Ok(Some(u)) if u.role != ROLE_LIBRARY => u,
+        Ok(_) => {
             tracing::warn!(request_id = %request_id, username = %payload.username, "User not found");
+            audit::record(
+                &state.db,
+                None,
+                AuditEvent::LoginFailed,
+                &client,
+                Some(json!({ "username": payload.username, "reason": "unknown_user" })),
+            )
+            .await;
             return error_resp(
                 StatusCode::UNAUTHORIZED,
                 request_id,
-                "Invalid username or password",
+                &i18n::AUTH_INVALID_CREDENTIALS,
             );
         }
         Err(e) => {
@@ -192,29 +246,37 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<LoginReque
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
-    let valid = match password::verify_password(&payload.password, &user.password_hash) {
+    let valid = match password::verify_password(&payload.password, &user.password_hash).await {
         Ok(v) => v,
         Err(e) => {
             tracing::error!(request_id = %request_id, error = %e, "Password verification error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
     if !valid {
         tracing::warn!(request_id = %request_id, username = %payload.username, "Invalid password");
+        audit::record(
+            &state.db,
+            Some(user.id),
+            AuditEvent::LoginFailed,
+            &client,
+            Some(json!({ "reason": "invalid_password" })),
+        )
+        .await;
         return error_resp(
             StatusCode::UNAUTHORIZED,
             request_id,
-            "Invalid username or password",
+            &i18n::AUTH_INVALID_CREDENTIALS,
         );
     }
 
@@ -226,31 +288,380 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<LoginReque
         "User authenticated successfully"
     );
 
-    let token = match jwt::create_token(user.id, &user.username, state.config.jwt_secret()) {
+    // Transparently upgrade legacy bcrypt or outdated Argon2 hashes
+    if password::needs_rehash(&user.password_hash, &state.config.password_hashing) {
+        match password::hash_password(&payload.password, &state.config.password_hashing).await {
+            Ok(new_hash) => {
+                let mut active: user::ActiveModel = user.clone().into();
+                active.password_hash = Set(new_hash);
+                active.updated_at = Set(chrono::Utc::now().naive_utc());
+                match active.update(&state.db).await {
+                    Ok(_) => {
+                        user_cache::invalidate(user.id);
+                        tracing::info!(request_id = %request_id, user_id = user.id, "Password hash upgraded")
+                    }
+                    Err(e) => {
+                        tracing::warn!(request_id = %request_id, error = %e, "Failed to store upgraded password hash")
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!(request_id = %request_id, error = %e, "Failed to rehash password")
+            }
+        }
+    }
+
+    if payload.use_cookie && !state.config.security.session_cookie.enabled {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::AUTH_COOKIE_SESSIONS_DISABLED,
+        );
+    }
+
+    login_alert::check_login(&state, &user, &client).await;
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::Login,
+        &client,
+        Some(json!({ "session": if payload.use_cookie { "cookie" } else { "bearer" } })),
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Login completed successfully");
+
+    token_response(
+        &state,
+        request_id,
+        StatusCode::OK,
+        &i18n::AUTH_LOGIN_SUCCESS,
+        user,
+        payload.use_cookie,
+    )
+}
+
+/// End a session: expire the session cookies and record the logout
+/// Bearer clients simply discard their token, `logout_all` revokes every token of the user
+pub async fn logout(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    headers: HeaderMap,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let session_config = &state.config.security.session_cookie;
+
+    // Logging out never fails; an invalid token just isn't attributed to a user
+    let user_id = auth::extract_token(&headers, session_config)
+        .ok()
+        .flatten()
+        .and_then(|(token, _)| jwt::validate_token(&token, &state.jwt_keys).ok())
+        .and_then(|claims| claims.sub.parse::<i32>().ok());
+
+    if let Some(user_id) = user_id {
+        audit::record(&state.db, Some(user_id), AuditEvent::Logout, &client, None).await;
+        tracing::info!(request_id = %request_id, user_id = user_id, "User logged out");
+    }
+
+    let mut resp = do_json_detail_resp::<EmptyData>(
+        StatusCode::OK,
+        request_id,
+        &i18n::AUTH_LOGOUT_SUCCESS,
+        None,
+    );
+
+    if session_config.enabled {
+        for value in cookie::clear_session_cookies(session_config) {
+            resp.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    resp
+}
+
+/// End every session of the user, cookie and Bearer alike, by bumping their token version
+pub async fn logout_all(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    client: ClientInfo,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if let Err(e) = user::Entity::update_many()
+        .col_expr(
+            user::Column::TokenVersion,
+            Expr::col(user::Column::TokenVersion).add(1),
+        )
+        .filter(user::Column::Id.eq(user_id))
+        .exec(&state.db)
+        .await
+    {
+        tracing::error!(request_id = %request_id, error = %e, "Database update error");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_INTERNAL_ERROR,
+        );
+    }
+    user_cache::invalidate(user_id);
+
+    audit::record(
+        &state.db,
+        Some(user_id),
+        AuditEvent::LogoutEverywhere,
+        &client,
+        None,
+    )
+    .await;
+    tracing::info!(request_id = %request_id, user_id = user_id, "User logged out of all sessions");
+
+    let session_config = &state.config.security.session_cookie;
+    let mut resp = do_json_detail_resp::<EmptyData>(
+        StatusCode::OK,
+        request_id,
+        &i18n::AUTH_LOGOUT_ALL_SUCCESS,
+        None,
+    );
+    if session_config.enabled {
+        for value in cookie::clear_session_cookies(session_config) {
+            resp.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    resp
+}
+
+/// Confirm an email change with the token mailed to the new address
+pub async fn verify_email(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user = match user::Entity::find()
+        .filter(user::Column::EmailToken.eq(payload.token.trim()))
+        .one(&state.db)
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let now = chrono::Utc::now().naive_utc();
+    let pending = user.and_then(|u| {
+        let email = u.pending_email.clone()?;
+        let valid = u.email_token_expires_at.is_some_and(|t| t > now);
+        valid.then_some((u, email))
+    });
+    let (user, new_email) = match pending {
+        Some(p) => p,
+        None => {
+            tracing::warn!(request_id = %request_id, "Invalid or expired email verification token");
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::USER_INVALID_EMAIL_TOKEN,
+            );
+        }
+    };
+
+    // The address may have been taken since the change was requested
+    let existing = match user::Entity::find()
+        .filter(user::Column::Email.eq(&new_email))
+        .filter(user::Column::Id.ne(user.id))
+        .one(&state.db)
+        .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    if existing.is_some() {
+        tracing::warn!(request_id = %request_id, email = %new_email, "Email already exists");
+        return error_code_resp(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::NameConflict,
+            request_id,
+            &i18n::AUTH_EMAIL_TAKEN,
+        );
+    }
+
+    let old_email = user.email.clone();
+    let mut active: user::ActiveModel = user.into();
+    active.email = Set(new_email.clone());
+    active.pending_email = Set(None);
+    active.email_token = Set(None);
+    active.email_token_expires_at = Set(None);
+    active.updated_at = Set(now);
+
+    let user = match active.update(&state.db).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database update error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    user_cache::invalidate(user.id);
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::EmailChanged,
+        &client,
+        Some(json!({ "old_email": old_email, "new_email": new_email })),
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Email address changed");
+
+    do_json_detail_resp::<EmptyData>(StatusCode::OK, request_id, &i18n::USER_EMAIL_VERIFIED, None)
+}
+
+/// Exchange a valid token for a fresh one of the same kind
+pub async fn refresh(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    // Reload the user so the new token carries the current role
+    let user = match user::Entity::find_by_id(user_id).one(&state.db).await {
+        Ok(Some(u)) => u,
+        Ok(None) => {
+            return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::USER_NOT_FOUND);
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::TokenRefreshed,
+        &client,
+        None,
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Token refreshed");
+
+    token_response(
+        &state,
+        request_id,
+        StatusCode::OK,
+        "Token refreshed successfully",
+        user,
+        claims.csrf.is_some(),
+    )
+}
+
+/// Publish public token verification keys (JWKS) for other services
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+    Json(state.jwt_keys.jwks().clone())
+}
+
+/// Issue a token for `user` and wrap it in a login-style response
+/// Cookie sessions get the token in an HttpOnly cookie instead of the body
+pub(crate) fn token_response(
+    state: &AppState,
+    request_id: String,
+    status: StatusCode,
+    message: impl Into<Text>,
+    user: user::Model,
+    use_cookie: bool,
+) -> Response {
+    let security = &state.config.security;
+
+    let issued = if use_cookie {
+        jwt::create_session_token(&user, &state.jwt_keys, security.jwt_expiration_hours)
+            .map(|(token, csrf)| (token, Some(csrf)))
+    } else {
+        jwt::create_token(&user, &state.jwt_keys, security.jwt_expiration_hours)
+            .map(|token| (token, None))
+    };
+
+    let (token, csrf_token) = match issued {
         Ok(t) => t,
         Err(e) => {
             tracing::error!(request_id = %request_id, error = %e, "Token creation error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
+    let cookies = csrf_token.as_ref().map(|csrf| {
+        cookie::session_cookies(
+            &security.session_cookie,
+            &token,
+            csrf,
+            security.jwt_expiration_hours * 3600,
+        )
+    });
+
     let response = LoginResponse {
-        token,
+        token: if use_cookie { None } else { Some(token) },
+        csrf_token,
         user_id: user.id,
-        username: user.username.clone(),
+        username: user.username,
         role: user.role,
     };
 
-    tracing::info!(request_id = %request_id, user_id = user.id, "Login completed successfully");
-
-    do_json_detail_resp(
-        StatusCode::OK,
-        request_id,
-        "Login completed successfully",
-        Some(response),
-    )
+    let mut resp = do_json_detail_resp(status, request_id, message, Some(response));
+    for value in cookies.into_iter().flatten() {
+        resp.headers_mut().append(header::SET_COOKIE, value);
+    }
+    resp
 }
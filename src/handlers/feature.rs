@@ -0,0 +1,367 @@
+use crate::{
+    constants::{ROLE_ADMIN, ROLE_USER},
+    entities::{feature_override, user},
+    models::feature::{
+        FeatureFlagItem, FeatureOverrideItem, FeatureState, SetFeatureOverrideRequest,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        features::{self, Feature},
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+
+fn to_override_item(o: feature_override::Model) -> FeatureOverrideItem {
+    FeatureOverrideItem {
+        id: o.id,
+        role: o.role,
+        user_id: o.user_id,
+        enabled: o.enabled,
+        updated_by: o.updated_by,
+        updated_at: timestamp::format(o.updated_at),
+    }
+}
+
+/// Features and whether each is on for the caller, for clients to hide what is off
+pub async fn my_features(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let states: Vec<FeatureState> =
+        features::for_user(&state.db, &state.config.features, user_id, &claims.role)
+            .await
+            .into_iter()
+            .map(|(feature, enabled)| FeatureState {
+                name: feature.as_str().to_string(),
+                enabled,
+            })
+            .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FEATURES_RETRIEVED,
+        Some(states),
+    )
+}
+
+/// Every feature with its configured defaults and overrides (admin only)
+pub async fn list_features(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_FEATURES_ONLY,
+        );
+    }
+
+    let overrides = match feature_override::Entity::find()
+        .order_by_asc(feature_override::Column::Id)
+        .all(&state.db)
+        .await
+    {
+        Ok(o) => o,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let items: Vec<FeatureFlagItem> = Feature::ALL
+        .into_iter()
+        .map(|feature| {
+            let configured = state
+                .config
+                .features
+                .flags
+                .iter()
+                .find(|f| f.name == feature.as_str());
+            FeatureFlagItem {
+                name: feature.as_str().to_string(),
+                enabled: configured.is_none_or(|f| f.enabled),
+                roles: configured.map(|f| f.roles.clone()).unwrap_or_default(),
+                overrides: overrides
+                    .iter()
+                    .filter(|o| o.feature == feature.as_str())
+                    .cloned()
+                    .map(to_override_item)
+                    .collect(),
+            }
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FEATURES_RETRIEVED,
+        Some(items),
+    )
+}
+
+/// Turn a feature on or off for a user, a role or everyone, replacing an earlier override (admin only)
+pub async fn set_feature_override(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(name): Path<String>,
+    Json(req): Json<SetFeatureOverrideRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_FEATURES_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let Some(feature) = Feature::parse(&name) else {
+        return error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::ADMIN_FEATURE_NOT_FOUND,
+        );
+    };
+
+    let role = req.role.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    match (role, req.user_id) {
+        (Some(_), Some(_)) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Set either role or user_id, not both",
+            );
+        }
+        (Some(role), None) if role != ROLE_ADMIN && role != ROLE_USER => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Invalid role, use admin or user",
+            );
+        }
+        (None, Some(user_id)) => match user::Entity::find_by_id(user_id).one(&state.db).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND)
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        },
+        _ => {}
+    }
+
+    let mut existing = feature_override::Entity::find()
+        .filter(feature_override::Column::Feature.eq(feature.as_str()));
+    existing = match req.user_id {
+        Some(user_id) => existing.filter(feature_override::Column::UserId.eq(user_id)),
+        None => existing.filter(feature_override::Column::UserId.is_null()),
+    };
+    existing = match role {
+        Some(role) => existing.filter(feature_override::Column::Role.eq(role)),
+        None => existing.filter(feature_override::Column::Role.is_null()),
+    };
+
+    let now = Utc::now().naive_utc();
+    let saved = match existing.one(&state.db).await {
+        Ok(Some(o)) => {
+            let mut active: feature_override::ActiveModel = o.into();
+            active.enabled = Set(req.enabled);
+            active.updated_by = Set(admin_id);
+            active.updated_at = Set(now);
+            active.update(&state.db).await
+        }
+        Ok(None) => {
+            feature_override::ActiveModel {
+                feature: Set(feature.as_str().to_string()),
+                role: Set(role.map(str::to_string)),
+                user_id: Set(req.user_id),
+                enabled: Set(req.enabled),
+                updated_by: Set(admin_id),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&state.db)
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match saved {
+        Ok(o) => {
+            audit::record(
+                &state.db,
+                Some(admin_id),
+                AuditEvent::FeatureOverrideSet,
+                &client,
+                Some(serde_json::json!({
+                    "feature": o.feature,
+                    "role": o.role,
+                    "user_id": o.user_id,
+                    "enabled": o.enabled,
+                })),
+            )
+            .await;
+            tracing::info!(
+                request_id = %request_id,
+                admin = %claims.sub,
+                feature = %o.feature,
+                role = ?o.role,
+                user_id = ?o.user_id,
+                enabled = o.enabled,
+                "Feature override set"
+            );
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::ADMIN_FEATURE_OVERRIDE_SAVED,
+                Some(to_override_item(o)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to save feature override");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Remove an override, the feature falls back to the next one or the configuration (admin only)
+pub async fn delete_feature_override(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path((name, id)): Path<(String, i32)>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_FEATURES_ONLY,
+        );
+    }
+
+    let admin_id = claims.sub.parse::<i32>().ok();
+
+    let found = feature_override::Entity::find_by_id(id)
+        .filter(feature_override::Column::Feature.eq(name))
+        .one(&state.db)
+        .await;
+    let o = match found {
+        Ok(Some(o)) => o,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::ADMIN_FEATURE_OVERRIDE_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if let Err(e) = feature_override::Entity::delete_by_id(o.id)
+        .exec(&state.db)
+        .await
+    {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to delete feature override");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    audit::record(
+        &state.db,
+        admin_id,
+        AuditEvent::FeatureOverrideRemoved,
+        &client,
+        Some(serde_json::json!({
+            "feature": o.feature,
+            "role": o.role,
+            "user_id": o.user_id,
+        })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        override_id = o.id,
+        "Feature override removed"
+    );
+
+    do_json_detail_resp::<EmptyData>(
+        StatusCode::OK,
+        request_id,
+        &i18n::ADMIN_FEATURE_OVERRIDE_REMOVED,
+        None,
+    )
+}
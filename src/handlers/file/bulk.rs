@@ -0,0 +1,328 @@
+use crate::{
+    entities::file,
+    models::file::{BulkFileReport, BulkFileResult, BulkStarRequest, BulkTagRequest},
+    services::{stars, tags},
+    utils::{
+        i18n::{self, Text},
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{extract::State, http::StatusCode, response::Response, Extension, Json};
+use sea_orm::{
+    ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
+};
+use std::collections::{BTreeSet, HashSet};
+
+use super::{permission::get_file_permissions, search::query_condition};
+
+const MAX_BULK_FILES: usize = 1000;
+
+const STATUS_UPDATED: &str = "updated";
+const STATUS_UNCHANGED: &str = "unchanged";
+const STATUS_SKIPPED: &str = "skipped";
+
+impl BulkFileReport {
+    fn new() -> Self {
+        BulkFileReport {
+            updated: 0,
+            unchanged: 0,
+            skipped: 0,
+            results: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, file_id: i32, status: &str, reason: Option<String>) {
+        match status {
+            STATUS_UPDATED => self.updated += 1,
+            STATUS_UNCHANGED => self.unchanged += 1,
+            _ => self.skipped += 1,
+        }
+        self.results.push(BulkFileResult {
+            file_id,
+            status: status.to_string(),
+            reason,
+        });
+    }
+}
+
+/// Attach and detach tags on many files in one transaction
+/// Tags change the file for everyone, so shared files need write access
+pub async fn bulk_tag_files(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<BulkTagRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let (add, remove) = match (tags::normalize(&req.add), tags::normalize(&req.remove)) {
+        (Ok(add), Ok(remove)) => (add, remove),
+        (Err(msg), _) | (_, Err(msg)) => {
+            return error_resp(StatusCode::BAD_REQUEST, request_id, msg);
+        }
+    };
+    if add.is_empty() && remove.is_empty() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_BULK_TAGS_EMPTY,
+        );
+    }
+
+    let mut report = BulkFileReport::new();
+    let files = match select_targets(
+        &state,
+        user_id,
+        &claims.role,
+        &req.file_ids,
+        req.query.as_deref(),
+        true,
+        &mut report,
+    )
+    .await
+    {
+        Ok(files) => files,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+
+    let result = match state.db.begin().await {
+        Ok(txn) => apply_tags(txn, &files, &add, &remove, &mut report).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!(request_id = %request_id, error = ?e, "Bulk tag failed, rolled back");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    tracing::info!(
+        request_id = %request_id,
+        updated = report.updated,
+        unchanged = report.unchanged,
+        skipped = report.skipped,
+        "Bulk tag completed"
+    );
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_BULK_TAG_COMPLETED,
+        Some(report),
+    )
+}
+
+async fn apply_tags(
+    txn: DatabaseTransaction,
+    files: &[file::Model],
+    add: &BTreeSet<String>,
+    remove: &BTreeSet<String>,
+    report: &mut BulkFileReport,
+) -> Result<(), DbErr> {
+    for f in files {
+        match tags::apply(&txn, f, add, remove).await? {
+            Ok(true) => report.push(f.id, STATUS_UPDATED, None),
+            Ok(false) => report.push(f.id, STATUS_UNCHANGED, None),
+            Err(reason) => report.push(f.id, STATUS_SKIPPED, Some(reason)),
+        }
+    }
+    // Dropping the transaction on an error rolls back the files tagged so far
+    txn.commit().await
+}
+
+/// Star or unstar many files for the current user in one transaction
+pub async fn bulk_star_files(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<BulkStarRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let mut report = BulkFileReport::new();
+    let files = match select_targets(
+        &state,
+        user_id,
+        &claims.role,
+        &req.file_ids,
+        req.query.as_deref(),
+        false,
+        &mut report,
+    )
+    .await
+    {
+        Ok(files) => files,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+
+    let result = match state.db.begin().await {
+        Ok(txn) => apply_stars(txn, user_id, &files, req.starred, &mut report).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!(request_id = %request_id, error = ?e, "Bulk star failed, rolled back");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    tracing::info!(
+        request_id = %request_id,
+        starred = req.starred,
+        updated = report.updated,
+        unchanged = report.unchanged,
+        skipped = report.skipped,
+        "Bulk star completed"
+    );
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_BULK_STAR_COMPLETED,
+        Some(report),
+    )
+}
+
+async fn apply_stars(
+    txn: DatabaseTransaction,
+    user_id: i32,
+    files: &[file::Model],
+    starred: bool,
+    report: &mut BulkFileReport,
+) -> Result<(), DbErr> {
+    let ids: Vec<i32> = files.iter().map(|f| f.id).collect();
+    let current = stars::starred_among(&txn, user_id, &ids).await?;
+    for &file_id in &ids {
+        if current.contains(&file_id) == starred {
+            report.push(file_id, STATUS_UNCHANGED, None);
+            continue;
+        }
+        stars::set(&txn, user_id, file_id, starred).await?;
+        report.push(file_id, STATUS_UPDATED, None);
+    }
+    txn.commit().await
+}
+
+/// Files a bulk request targets, either listed by ID or matched by a search query
+/// Listed files the caller can't use are recorded as skipped in `report`
+async fn select_targets(
+    state: &AppState,
+    user_id: i32,
+    role: &str,
+    file_ids: &[i32],
+    query: Option<&str>,
+    needs_write: bool,
+    report: &mut BulkFileReport,
+) -> Result<Vec<file::Model>, (StatusCode, Text)> {
+    let query = query.map(str::trim).filter(|q| !q.is_empty());
+    let db_error = |e: DbErr| {
+        tracing::error!(error = ?e, "Failed to load bulk operation files");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Text::Catalog(&i18n::COMMON_DATABASE_ERROR),
+        )
+    };
+
+    let ids = match (file_ids.is_empty(), query) {
+        (true, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Text::Catalog(&i18n::FILE_BULK_TARGET_MISSING),
+            ));
+        }
+        (false, Some(_)) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Text::Catalog(&i18n::FILE_BULK_TARGET_BOTH),
+            ));
+        }
+        (true, Some(q)) => {
+            let condition =
+                query_condition(q, user_id).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let files = file::Entity::find()
+                .filter(condition)
+                .order_by_asc(file::Column::Path)
+                .limit(MAX_BULK_FILES as u64 + 1)
+                .all(&state.db)
+                .await
+                .map_err(db_error)?;
+            if files.len() > MAX_BULK_FILES {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Text::Plain(format!(
+                        "The query matches more than {} files, narrow it down",
+                        MAX_BULK_FILES
+                    )),
+                ));
+            }
+            return Ok(files);
+        }
+        (false, None) => {
+            let mut seen = HashSet::new();
+            let ids: Vec<i32> = file_ids
+                .iter()
+                .copied()
+                .filter(|id| seen.insert(*id))
+                .collect();
+            if ids.len() > MAX_BULK_FILES {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Text::Plain(format!(
+                        "Too many files in one request (maximum {})",
+                        MAX_BULK_FILES
+                    )),
+                ));
+            }
+            ids
+        }
+    };
+
+    let mut found = file::Entity::find()
+        .filter(file::Column::Id.is_in(ids.iter().copied()))
+        .all(&state.db)
+        .await
+        .map_err(db_error)?;
+    let mut files = Vec::with_capacity(ids.len());
+    for id in ids {
+        let Some(pos) = found.iter().position(|f| f.id == id) else {
+            report.push(id, STATUS_SKIPPED, Some("File not found".to_string()));
+            continue;
+        };
+        let f = found.swap_remove(pos);
+        let (can_read, can_write, _) = get_file_permissions(&state.db, user_id, role, &f).await;
+        if !can_read {
+            report.push(id, STATUS_SKIPPED, Some("File not found".to_string()));
+        } else if needs_write && !can_write {
+            report.push(id, STATUS_SKIPPED, Some("Permission denied".to_string()));
+        } else {
+            files.push(f);
+        }
+    }
+    Ok(files)
+}
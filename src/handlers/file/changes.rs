@@ -0,0 +1,128 @@
+use crate::{
+    models::file::{FileChangeItem, FileChangesQuery, FileChangesResponse},
+    services::changes,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+
+const DEFAULT_CHANGES_LIMIT: u64 = 500;
+const MAX_CHANGES_LIMIT: u64 = 1000;
+
+/// Read the changes feed of a tree from a cursor, sync clients poll this to catch up
+pub async fn list_changes(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FileChangesQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let owner_id = query.owner_id.unwrap_or(user_id);
+    if claims.role != "admin" && owner_id != user_id {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_OWN_CHANGES_ONLY,
+        );
+    }
+
+    let by_modified = match query.order.as_deref() {
+        None | Some("cursor") => false,
+        Some("modified") => true,
+        Some(_) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::FILE_INVALID_CHANGES_ORDER,
+            );
+        }
+    };
+
+    let since = query.since.unwrap_or(0);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+
+    // One extra row tells whether another page follows
+    let mut rows = match changes::list(&state.db, owner_id, since, limit + 1).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to query changes");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+    let has_more = rows.len() as u64 > limit;
+    rows.truncate(limit as usize);
+
+    let cursor = rows.last().map(|c| c.id).unwrap_or(since);
+
+    let file_ids: Vec<i32> = rows.iter().map(|c| c.file_id).collect();
+    let pinned = match changes::pinned_among(&state.db, &file_ids).await {
+        Ok(pinned) => pinned,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to query pinned files");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    // The cursor still moves by ID, only the entries within the page are reordered
+    if by_modified {
+        rows.sort_by_key(|c| c.client_modified.unwrap_or(c.created_at));
+    }
+
+    let changes = rows
+        .into_iter()
+        .map(|c| FileChangeItem {
+            id: c.id,
+            file_id: c.file_id,
+            change: c.change,
+            path: c.path,
+            previous_path: c.previous_path,
+            pinned: pinned.contains(&c.file_id),
+            client_modified: c.client_modified.map(timestamp::format),
+            created_at: timestamp::format(c.created_at),
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_CHANGES_RETRIEVED,
+        Some(FileChangesResponse {
+            changes,
+            cursor,
+            has_more,
+        }),
+    )
+}
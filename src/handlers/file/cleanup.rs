@@ -0,0 +1,320 @@
+use crate::{
+    entities::cleanup_rule,
+    models::file::{CleanupRule, CleanupRuleRequest},
+    services::cleanup,
+    utils::{
+        file_utils,
+        i18n::{self, Text},
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+
+use super::organize::{folder_path, parse_extensions};
+
+const MAX_AGE_DAYS: i32 = 3650;
+const MAX_NAME_FILTER_LENGTH: usize = 100;
+
+fn to_response(state: &AppState, r: cleanup_rule::Model) -> CleanupRule {
+    CleanupRule {
+        id: r.id,
+        folder_path: r.folder_path,
+        extensions: r
+            .extensions
+            .map(|list| list.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        name_contains: r.name_contains,
+        max_age_days: r.max_age_days,
+        deletion_due_at: r
+            .warned_at
+            .map(|t| timestamp::format(cleanup::deletion_time(&state.config, t))),
+        last_run_at: r.last_run_at.map(timestamp::format),
+        created_at: timestamp::format(r.created_at),
+    }
+}
+
+/// Checked and normalized fields of a rule: folder, extensions and name filter
+fn parse_rule(req: &CleanupRuleRequest) -> Result<(String, Option<String>, Option<String>), Text> {
+    let folder = folder_path(&req.folder_path)?;
+    if file_utils::is_protected_path(&folder) {
+        return Err(Text::Catalog(&i18n::FILE_PROTECTED_PATH));
+    }
+    if !(1..=MAX_AGE_DAYS).contains(&req.max_age_days) {
+        return Err(Text::Plain(format!(
+            "Maximum age must be between 1 and {} days",
+            MAX_AGE_DAYS
+        )));
+    }
+
+    let name_contains = req
+        .name_contains
+        .as_deref()
+        .map(|n| n.trim().to_lowercase())
+        .filter(|n| !n.is_empty());
+    if name_contains
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_NAME_FILTER_LENGTH)
+    {
+        return Err(Text::Catalog(&i18n::CLEANUP_NAME_FILTER_TOO_LONG));
+    }
+
+    Ok((folder, parse_extensions(&req.extensions)?, name_contains))
+}
+
+/// List the current user's cleanup rules
+pub async fn list_cleanup_rules(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    match cleanup_rule::Entity::find()
+        .filter(cleanup_rule::Column::UserId.eq(user_id))
+        .order_by_asc(cleanup_rule::Column::FolderPath)
+        .order_by_asc(cleanup_rule::Column::Id)
+        .all(&state.db)
+        .await
+    {
+        Ok(rules) => {
+            let rules: Vec<CleanupRule> =
+                rules.into_iter().map(|r| to_response(&state, r)).collect();
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::CLEANUP_RULES_RETRIEVED,
+                Some(rules),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Add a cleanup rule, the files it covers are announced before the first deletion
+pub async fn create_cleanup_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CleanupRuleRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let (folder, extensions, name_contains) = match parse_rule(&req) {
+        Ok(fields) => fields,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    match cleanup_rule::Entity::find()
+        .filter(cleanup_rule::Column::UserId.eq(user_id))
+        .count(&state.db)
+        .await
+    {
+        Ok(count) if count >= cleanup::MAX_RULES => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                format!("At most {} cleanup rules are allowed", cleanup::MAX_RULES),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let active = cleanup_rule::ActiveModel {
+        user_id: Set(user_id),
+        folder_path: Set(folder),
+        extensions: Set(extensions),
+        name_contains: Set(name_contains),
+        max_age_days: Set(req.max_age_days),
+        warned_at: Set(None),
+        last_run_at: Set(None),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    match active.insert(&state.db).await {
+        Ok(r) => {
+            tracing::info!(request_id = %request_id, rule_id = r.id, "Cleanup rule created");
+            do_json_detail_resp(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::CLEANUP_RULE_CREATED,
+                Some(to_response(&state, r)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to create cleanup rule");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Replace a cleanup rule, a pending deletion is called off and announced again
+pub async fn update_cleanup_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<i32>,
+    Json(req): Json<CleanupRuleRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let (folder, extensions, name_contains) = match parse_rule(&req) {
+        Ok(fields) => fields,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let existing = match cleanup_rule::Entity::find_by_id(rule_id)
+        .filter(cleanup_rule::Column::UserId.eq(user_id))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::CLEANUP_RULE_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let mut active: cleanup_rule::ActiveModel = existing.into();
+    active.folder_path = Set(folder);
+    active.extensions = Set(extensions);
+    active.name_contains = Set(name_contains);
+    active.max_age_days = Set(req.max_age_days);
+    active.warned_at = Set(None);
+
+    match active.update(&state.db).await {
+        Ok(r) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::CLEANUP_RULE_UPDATED,
+            Some(to_response(&state, r)),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to update cleanup rule");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Delete a cleanup rule, a pending deletion is called off
+pub async fn delete_cleanup_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    match cleanup_rule::Entity::delete_many()
+        .filter(cleanup_rule::Column::Id.eq(rule_id))
+        .filter(cleanup_rule::Column::UserId.eq(user_id))
+        .exec(&state.db)
+        .await
+    {
+        Ok(res) if res.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::CLEANUP_RULE_NOT_FOUND,
+        ),
+        Ok(_) => do_json_detail_resp::<()>(
+            StatusCode::OK,
+            request_id,
+            &i18n::CLEANUP_RULE_DELETED,
+            None,
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
@@ -1,23 +1,51 @@
 use crate::{
-    entities::{file, user},
-    utils::{jwt, request_id, response::error_resp},
+    entities::file,
+    models::file::{BatchDownloadRequest, DownloadFolderQuery, GetFileQuery},
+    services::{
+        download::{self, CollectedFiles},
+        features::{self, Feature},
+        file_stats, jobs, staging,
+    },
+    utils::{
+        archive::ArchiveFormat,
+        content_encoding, i18n, jwt, request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
     AppState,
 };
 use axum::{
-    extract::{Query, Request, State},
-    http::StatusCode,
+    extract::{Json, Query, Request, State},
+    http::{header, StatusCode},
     response::Response,
+    Extension,
 };
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use sea_orm::EntityTrait;
 use std::path::PathBuf;
-
-use super::permission::{check_permission, Permission};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tokio_util::io::ReaderStream;
+
+use super::helpers::with_cache_headers;
+use super::permission::{check_permission, get_file_permissions, Permission};
+
+/// Buffer between the archive being built and the response body
+const ARCHIVE_PIPE_SIZE: usize = 64 * 1024;
+
+/// Why the file may not be served, `None` when nothing holds it back
+pub(crate) fn unavailable_reason(f: &file::Model) -> Option<&'static i18n::Message> {
+    if f.taken_down {
+        Some(&i18n::DOWNLOAD_TAKEN_DOWN)
+    } else if f.quarantined_at.is_some() {
+        Some(&i18n::DOWNLOAD_QUARANTINED)
+    } else {
+        None
+    }
+}
 
 /// Download single file
 pub async fn get_file(
     State(state): State<AppState>,
-    Query(query): Query<crate::models::file::DeleteQuery>,
+    Query(query): Query<GetFileQuery>,
     request: Request,
 ) -> Response {
     let request_id = request_id::generate_request_id();
@@ -26,11 +54,7 @@ pub async fn get_file(
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
         None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            );
+            return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED);
         }
     };
 
@@ -40,32 +64,18 @@ pub async fn get_file(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    // Get user role
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
-        }
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error",
-            );
-        }
-    };
+    let user_role = claims.role.clone();
 
     // Check read permission
     let has_permission = match check_permission(
         &state.db,
         user_id,
-        &user_entity.role,
+        &user_role,
         query.file_id,
         Permission::Read,
     )
@@ -77,7 +87,7 @@ pub async fn get_file(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Permission check failed",
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
             );
         }
     };
@@ -86,7 +96,7 @@ pub async fn get_file(
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You don't have permission to download this file",
+            &i18n::FILE_NO_PERMISSION_DOWNLOAD,
         );
     }
 
@@ -94,27 +104,90 @@ pub async fn get_file(
     let file_entity = match file::Entity::find_by_id(query.file_id).one(&state.db).await {
         Ok(Some(f)) => f,
         Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "File not found");
+            return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
         }
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
     // Don't allow downloading folders
     if file_entity.file_type == "folder" {
-        return error_resp(
-            StatusCode::BAD_REQUEST,
-            request_id,
-            "Cannot download a folder",
+        return error_resp(StatusCode::BAD_REQUEST, request_id, &i18n::DOWNLOAD_FOLDER);
+    }
+
+    if let Some(reason) = unavailable_reason(&file_entity) {
+        return error_resp(StatusCode::FORBIDDEN, request_id, reason);
+    }
+
+    // Compress text-like files in the configured size window when the client accepts gzip
+    let download_config = &state.config.download;
+    let size = file_entity.size_bytes.unwrap_or(0).max(0) as u64;
+    let negotiable = download_config.gzip_enabled
+        && (download_config.gzip_min_size..=download_config.gzip_max_size).contains(&size)
+        && file_entity
+            .mime_type
+            .as_deref()
+            .is_some_and(content_encoding::is_compressible);
+    let gzip = negotiable
+        && request
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(content_encoding::accepts_gzip);
+
+    // Content a browser could run scripts from is only shown on a domain holding nothing else
+    let mime_type = file_entity
+        .mime_type
+        .as_deref()
+        .unwrap_or("application/octet-stream");
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok());
+    let active = download_config.is_active_content(mime_type);
+    let sandboxed = active && download_config.is_content_domain(host);
+    let disposition = if active && !sandboxed {
+        "attachment"
+    } else {
+        "inline"
+    };
+
+    let mut response = stream_file(&file_entity, request_id, disposition, gzip).await;
+    if sandboxed {
+        response.headers_mut().insert(
+            header::CONTENT_SECURITY_POLICY,
+            header::HeaderValue::from_static("sandbox"),
+        );
+    }
+    if negotiable {
+        response.headers_mut().insert(
+            header::VARY,
+            header::HeaderValue::from_static("Accept-Encoding"),
         );
     }
+    if response.status().is_success() {
+        file_stats::record_downloads(&state.db, &[file_entity.id]).await;
+        // A URL pinned to the current version always serves the same bytes
+        let immutable = query.version == Some(file_entity.version);
+        response = with_cache_headers(response, download_config, &file_entity, immutable);
+    }
+    response
+}
 
+/// Stream a stored file with download headers
+/// `disposition` is `inline` or `attachment`, `gzip` sends it with `Content-Encoding: gzip`
+pub(crate) async fn stream_file(
+    file_entity: &file::Model,
+    request_id: String,
+    disposition: &str,
+    gzip: bool,
+) -> Response {
     // Open file for streaming
     let physical_path = PathBuf::from(&file_entity.storage_path);
     let file = match tokio::fs::File::open(&physical_path).await {
@@ -124,7 +197,7 @@ pub async fn get_file(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Failed to read file",
+                &i18n::FILE_READ_FAILED,
             );
         }
     };
@@ -134,19 +207,22 @@ pub async fn get_file(
 
     tracing::info!(
         request_id = %request_id,
-        file_id = query.file_id,
+        file_id = file_entity.id,
         filename = %file_entity.name,
         size_bytes = file_size,
+        gzip = gzip,
         "Streaming file download"
     );
 
     // Create streaming body
-    use tokio_util::io::ReaderStream;
-    let stream = ReaderStream::new(file);
-    let body = axum::body::Body::from_stream(stream);
+    let body = if gzip {
+        drop(file);
+        axum::body::Body::from_stream(content_encoding::gzip_file_stream(physical_path))
+    } else {
+        axum::body::Body::from_stream(ReaderStream::new(file))
+    };
 
     // Return file with appropriate headers
-    use axum::http::header;
     let content_type = file_entity
         .mime_type
         .as_ref()
@@ -159,22 +235,30 @@ pub async fn get_file(
     // Sanitize filename for legacy field
     let safe_filename = file_entity.name.replace(['\"', '\r', '\n'], "");
 
-    Response::builder()
+    let builder = Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, content_type)
-        .header(header::CONTENT_LENGTH, file_size)
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    // The compressed length is unknown up front, the body is sent chunked
+    let builder = if gzip {
+        builder.header(header::CONTENT_ENCODING, "gzip")
+    } else {
+        builder.header(header::CONTENT_LENGTH, file_size)
+    };
+
+    builder
         .header(
             header::CONTENT_DISPOSITION,
             format!(
-                "inline; filename=\"{}\"; filename*=UTF-8''{}",
-                safe_filename, encoded_filename
+                "{}; filename=\"{}\"; filename*=UTF-8''{}",
+                disposition, safe_filename, encoded_filename
             ),
         )
         .body(body)
         .unwrap()
 }
 
-/// Batch download files and folders as ZIP archive
+/// Batch download files and folders as a ZIP or TAR archive, streamed while it is built
 pub async fn batch_download_files(State(state): State<AppState>, request: Request) -> Response {
     let request_id = request_id::generate_request_id();
 
@@ -185,23 +269,34 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
             return error_resp(
                 StatusCode::UNAUTHORIZED,
                 request_id.clone(),
-                "Authentication required",
+                &i18n::AUTH_REQUIRED,
+            );
+        }
+    };
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    let user_entity = match crate::services::batch_download::extract_user_from_request(
+    let user_role = claims.role.clone();
+
+    if !features::enabled(
         &state.db,
-        claims,
-        &request_id,
+        &state.config.features,
+        Feature::BatchDownload,
+        Some((user_id, &user_role)),
     )
     .await
     {
-        Ok(user) => user,
-        Err((status, _, msg)) => return error_resp(status, request_id, &msg),
-    };
-
-    let user_id = user_entity.id;
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::FEATURES_DISABLED);
+    }
 
     // Parse request body
     let bytes = match axum::body::to_bytes(request.into_body(), usize::MAX).await {
@@ -211,19 +306,19 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Failed to read request",
+                &i18n::COMMON_READ_REQUEST_FAILED,
             );
         }
     };
 
-    let req: crate::models::file::BatchDownloadRequest = match serde_json::from_slice(&bytes) {
+    let req: BatchDownloadRequest = match serde_json::from_slice(&bytes) {
         Ok(r) => r,
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to parse request");
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
@@ -232,16 +327,24 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
         return error_resp(
             StatusCode::BAD_REQUEST,
             request_id,
-            "No files specified for download",
+            &i18n::DOWNLOAD_NO_FILES,
         );
     }
 
+    let Some(format) = ArchiveFormat::parse(req.format.as_deref()) else {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::DOWNLOAD_INVALID_FORMAT,
+        );
+    };
+
     // Try single file optimization
     match crate::services::batch_download::try_single_file_download(
         &state.db,
         &req.file_ids,
         user_id,
-        &user_entity.role,
+        &user_role,
     )
     .await
     {
@@ -261,10 +364,11 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
                     return error_resp(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         request_id,
-                        "Failed to read file",
+                        &i18n::FILE_READ_FAILED,
                     );
                 }
             };
+            file_stats::record_downloads(&state.db, &[file_entity.id]).await;
 
             let content_type = file_entity
                 .mime_type
@@ -272,7 +376,6 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
                 .unwrap_or(&"application/octet-stream".to_string())
                 .clone();
 
-            use axum::http::header;
             let encoded_filename =
                 utf8_percent_encode(&file_entity.name, NON_ALPHANUMERIC).to_string();
             let safe_filename = file_entity.name.replace(['"', '\r', '\n'], "");
@@ -280,6 +383,7 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
             return Response::builder()
                 .status(StatusCode::OK)
                 .header(header::CONTENT_TYPE, content_type)
+                .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
                 .header(
                     header::CONTENT_DISPOSITION,
                     format!(
@@ -298,141 +402,365 @@ pub async fn batch_download_files(State(state): State<AppState>, request: Reques
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     }
 
-    // Collect all files to download
-    let collected_result = match crate::services::download::collect_files_to_download(
-        &state.db,
+    let (collected_result, should_compress) = match collect_batch(
+        &state,
         req.file_ids.clone(),
         user_id,
+        &user_role,
+        &request_id,
     )
     .await
     {
-        Ok(result) => result,
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+
+    tracing::info!(
+        request_id = %request_id,
+        file_count = collected_result.files.len(),
+        compressed = should_compress,
+        format = format.extension(),
+        "Batch download started"
+    );
+
+    let downloaded_ids: Vec<i32> = collected_result.files.iter().map(|f| f.id).collect();
+    file_stats::record_downloads(&state.db, &downloaded_ids).await;
+
+    // The archive is written into a pipe and sent while it is being built
+    // A failure halfway ends the body with an error, so the client sees an aborted transfer
+    let (writer, reader) = tokio::io::duplex(ARCHIVE_PIPE_SIZE);
+    let (error_tx, error_rx) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move {
+        match download::write_archive(&collected_result, format, should_compress, writer).await {
+            Ok((_, size)) => {
+                tracing::info!(request_id = %request_id, archive_size = size, "Batch download successful");
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Failed to create archive");
+                let _ = error_tx
+                    .send(Err(std::io::Error::other(e.to_string())))
+                    .await;
+            }
+        }
+    });
+    let body = ReaderStream::new(reader).chain(ReceiverStream::new(error_rx));
+
+    // Generate archive filename with timestamp
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let archive_filename = format!("files_{}.{}", timestamp, format.extension());
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", archive_filename),
+        )
+        .body(axum::body::Body::from_stream(body))
+        .unwrap()
+}
+
+/// Download a folder with everything the caller can read in it as a ZIP or TAR archive
+/// The archive is written to the staging area and streamed from there
+pub async fn download_folder(
+    State(state): State<AppState>,
+    Extension(claims): Extension<jwt::Claims>,
+    Query(query): Query<DownloadFolderQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let folder = match file::Entity::find_by_id(query.folder_id)
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(f)) => f,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::FILE_FOLDER_NOT_FOUND,
+            );
+        }
         Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Failed to collect files");
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
-                StatusCode::BAD_REQUEST,
+                StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Failed to collect files",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
-    if collected_result.files.is_empty() {
+    // Folders the caller cannot read are not revealed
+    let (can_read, _, _) = get_file_permissions(&state.db, user_id, &claims.role, &folder).await;
+    if !can_read {
         return error_resp(
             StatusCode::NOT_FOUND,
             request_id,
-            "No files found to download",
+            &i18n::FILE_FOLDER_NOT_FOUND,
         );
     }
+    if folder.file_type != "folder" {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::DOWNLOAD_NOT_FOLDER,
+        );
+    }
+    let Some(format) = ArchiveFormat::parse(query.format.as_deref()) else {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::DOWNLOAD_INVALID_FORMAT,
+        );
+    };
 
-    // Calculate total size and determine compression strategy
-    let total_size = crate::services::download::calculate_total_size(&collected_result.files);
-    let max_size = state.config.batch_download.max_total_size;
-    let compression_threshold = state.config.batch_download.compression_threshold;
-    let should_compress = total_size as usize > compression_threshold;
+    let (collected, should_compress) =
+        match collect_batch(&state, vec![folder.id], user_id, &claims.role, &request_id).await {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+
+    let total_size = download::calculate_total_size(&collected.files) as u64;
+    if let Err(msg) = staging::ensure_space(&state.config, total_size).await {
+        tracing::warn!(request_id = %request_id, total_size = total_size, reason = %msg, "Not enough staging space");
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
+    }
+
+    let archive_dir = staging::area_dir(&state.config, staging::AREA_ARCHIVES);
+    let archive_path = archive_dir.join(format!("folder_{}.{}", request_id, format.extension()));
+    let result = async {
+        tokio::fs::create_dir_all(&archive_dir).await?;
+        let dest = tokio::io::BufWriter::new(tokio::fs::File::create(&archive_path).await?);
+        download::write_archive(&collected, format, should_compress, dest).await
+    }
+    .await;
+
+    let archive_size = match result {
+        Ok((_, size)) => size,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&archive_path).await;
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create archive");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::DOWNLOAD_ARCHIVE_FAILED,
+            );
+        }
+    };
+
+    // The open handle keeps the bytes readable once the staging entry is gone
+    let archive = tokio::fs::File::open(&archive_path).await;
+    let _ = tokio::fs::remove_file(&archive_path).await;
+    let archive = match archive {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to open archive");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                "Failed to read archive",
+            );
+        }
+    };
 
     tracing::info!(
         request_id = %request_id,
-        total_size = total_size,
-        max_size = max_size,
-        compression_threshold = compression_threshold,
-        should_compress = should_compress,
-        file_count = collected_result.files.len(),
-        "Batch download size check"
+        folder_id = folder.id,
+        file_count = collected.files.len(),
+        archive_size = archive_size,
+        compressed = should_compress,
+        format = format.extension(),
+        "Folder download successful"
     );
 
-    // Verify size limit
-    if let Err(e) = crate::services::download::verify_size_limit(total_size, max_size) {
-        tracing::warn!(request_id = %request_id, error = %e, "Size limit exceeded");
+    let downloaded_ids: Vec<i32> = collected.files.iter().map(|f| f.id).collect();
+    file_stats::record_downloads(&state.db, &downloaded_ids).await;
+
+    let archive_name = format!("{}.{}", folder.name, format.extension());
+    let encoded_filename = utf8_percent_encode(&archive_name, NON_ALPHANUMERIC).to_string();
+    let safe_filename = archive_name.replace(['"', '\r', '\n'], "");
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::CONTENT_LENGTH, archive_size)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+                safe_filename, encoded_filename
+            ),
+        )
+        .body(axum::body::Body::from_stream(ReaderStream::new(archive)))
+        .unwrap()
+}
+
+/// Prepare a batch download in the background and return the job to poll
+/// Meant for selections too large to zip within a single request
+pub async fn prepare_batch_download(
+    State(state): State<AppState>,
+    Extension(claims): Extension<jwt::Claims>,
+    Json(req): Json<BatchDownloadRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if !features::enabled(
+        &state.db,
+        &state.config.features,
+        Feature::BatchDownload,
+        Some((user_id, &claims.role)),
+    )
+    .await
+    {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::FEATURES_DISABLED);
+    }
+
+    if req.file_ids.is_empty() {
         return error_resp(
-            StatusCode::PAYLOAD_TOO_LARGE,
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::DOWNLOAD_NO_FILES,
+        );
+    }
+
+    let Some(format) = ArchiveFormat::parse(req.format.as_deref()) else {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
             request_id,
-            &format!("Total download size exceeds limit: {}", e),
+            &i18n::DOWNLOAD_INVALID_FORMAT,
         );
+    };
+
+    let (collected, should_compress) =
+        match collect_batch(&state, req.file_ids, user_id, &claims.role, &request_id).await {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+
+    let total_size = download::calculate_total_size(&collected.files) as u64;
+    if let Err(msg) = staging::ensure_space(&state.config, total_size).await {
+        tracing::warn!(request_id = %request_id, total_size = total_size, reason = %msg, "Not enough staging space");
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
     }
 
-    // Verify permissions for all files
-    match crate::services::download::verify_download_permissions(
+    match jobs::start_batch_download(
         &state.db,
-        &collected_result.files,
+        &state.config,
+        &state.events,
         user_id,
-        &user_entity.role,
+        collected,
+        format,
+        should_compress,
     )
     .await
     {
-        Ok(true) => {}
-        Ok(false) => {
-            return error_resp(
-                StatusCode::FORBIDDEN,
+        Ok(job) => {
+            tracing::info!(request_id = %request_id, job_id = job.id, "Batch download job queued");
+            do_json_detail_resp(
+                StatusCode::ACCEPTED,
                 request_id,
-                "Permission denied for one or more files",
-            );
+                &i18n::DOWNLOAD_PREPARING,
+                Some(jobs::job_info(job)),
+            )
         }
         Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Permission check failed");
-            return error_resp(StatusCode::FORBIDDEN, request_id, "Permission denied");
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create job");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
         }
     }
+}
 
-    // Create ZIP archive with dynamic compression
-    // Use spawn_blocking to prevent blocking the async runtime during file I/O and compression
-    // Clone collected_files for the logging after ZIP creation
-    let files_for_zip = collected_result.files.clone();
-    let folder_roots = collected_result.folder_roots.clone();
-    let zip_data = match tokio::task::spawn_blocking(move || {
-        crate::services::download::create_batch_download_zip(
-            &files_for_zip,
-            &folder_roots,
-            should_compress,
-        )
-    })
+/// Collect the selected files the caller can read and check the size limit
+/// Returns the files and whether the archive should be compressed
+async fn collect_batch(
+    state: &AppState,
+    file_ids: Vec<i32>,
+    user_id: i32,
+    user_role: &str,
+    request_id: &str,
+) -> Result<(CollectedFiles, bool), Response> {
+    // Collect all files to download
+    let collected_result = match crate::services::download::collect_files_to_download(
+        &state.db, file_ids, user_id, user_role,
+    )
     .await
     {
-        Ok(Ok(data)) => data,
-        Ok(Err(e)) => {
-            tracing::error!(request_id = %request_id, error = %e, "Failed to create ZIP");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                &format!("Failed to create ZIP archive"),
-            );
-        }
+        Ok(result) => result,
         Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Task join error");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Failed to process download",
-            );
+            tracing::error!(request_id = %request_id, error = %e, "Failed to collect files");
+            return Err(error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id.to_string(),
+                &i18n::DOWNLOAD_COLLECT_FAILED,
+            ));
         }
     };
 
+    if collected_result.is_empty() {
+        return Err(error_resp(
+            StatusCode::NOT_FOUND,
+            request_id.to_string(),
+            &i18n::DOWNLOAD_NOTHING_FOUND,
+        ));
+    }
+
+    // Calculate total size and determine compression strategy
+    let total_size = crate::services::download::calculate_total_size(&collected_result.files);
+    let max_size = state.config.batch_download.max_total_size;
+    let compression_threshold = state.config.batch_download.compression_threshold;
+    let should_compress = total_size as usize > compression_threshold;
+
     tracing::info!(
         request_id = %request_id,
+        total_size = total_size,
+        max_size = max_size,
+        compression_threshold = compression_threshold,
+        should_compress = should_compress,
         file_count = collected_result.files.len(),
-        zip_size = zip_data.len(),
-        compressed = should_compress,
-        "Batch download successful"
+        "Batch download size check"
     );
 
-    // Generate ZIP filename with timestamp
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let zip_filename = format!("files_{}.zip", timestamp);
+    // Verify size limit
+    if let Err(e) = crate::services::download::verify_size_limit(total_size, max_size) {
+        tracing::warn!(request_id = %request_id, error = %e, "Size limit exceeded");
+        return Err(error_resp(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            request_id.to_string(),
+            format!("Total download size exceeds limit: {}", e),
+        ));
+    }
 
-    // Return ZIP file
-    use axum::http::header;
-    Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "application/zip")
-        .header(
-            header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{}\"", zip_filename),
-        )
-        .body(axum::body::Body::from(zip_data))
-        .unwrap()
+    Ok((collected_result, should_compress))
 }
@@ -0,0 +1,367 @@
+use crate::{
+    entities::{download_token, file, user},
+    models::file::{CreateDownloadUrlRequest, DownloadUrl},
+    services::{
+        audit::{self, AuditEvent},
+        file_stats,
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use chrono::{Duration, Utc};
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+
+use super::download::{stream_file, unavailable_reason};
+use super::permission::{check_permission, Permission};
+
+fn download_url(state: &AppState, token: &str) -> String {
+    format!(
+        "{}/api/public/downloads/{}",
+        state.config.server.public_base_url(),
+        token
+    )
+}
+
+/// Create a presigned URL that downloads a file without authentication
+/// The URL can be bound to the caller's IP and limited to one download
+pub async fn create_download_url(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CreateDownloadUrlRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let config = &state.config.download;
+    let ttl_secs = req.expires_in_secs.unwrap_or(config.presigned_ttl_secs);
+    if !(1..=config.presigned_max_ttl_secs).contains(&ttl_secs) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "expires_in_secs must be between 1 and {}",
+                config.presigned_max_ttl_secs
+            ),
+        );
+    }
+
+    let bound_ip = if req.bind_ip {
+        match &client.ip {
+            Some(ip) => Some(ip.clone()),
+            None => {
+                return error_resp(
+                    StatusCode::BAD_REQUEST,
+                    request_id,
+                    &i18n::DOWNLOAD_IP_UNKNOWN,
+                );
+            }
+        }
+    } else {
+        None
+    };
+
+    match check_permission(
+        &state.db,
+        user_id,
+        &claims.role,
+        req.file_id,
+        Permission::Read,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return error_resp(
+                StatusCode::FORBIDDEN,
+                request_id,
+                &i18n::FILE_NO_PERMISSION_DOWNLOAD,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Permission check failed");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
+            );
+        }
+    }
+
+    match file::Entity::find_by_id(req.file_id).one(&state.db).await {
+        Ok(Some(f)) if f.file_type == "folder" => {
+            return error_resp(StatusCode::BAD_REQUEST, request_id, &i18n::DOWNLOAD_FOLDER);
+        }
+        Ok(Some(f)) => {
+            if let Some(reason) = unavailable_reason(&f) {
+                return error_resp(StatusCode::FORBIDDEN, request_id, reason);
+            }
+        }
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+
+    // Expired tokens can never be used again, drop them while we are here
+    if let Err(e) = download_token::Entity::delete_many()
+        .filter(download_token::Column::ExpiresAt.lte(now))
+        .exec(&state.db)
+        .await
+    {
+        tracing::warn!(request_id = %request_id, error = %e, "Failed to remove expired download tokens");
+    }
+
+    let token = download_token::ActiveModel {
+        token: Set(uuid::Uuid::new_v4().simple().to_string()),
+        file_id: Set(req.file_id),
+        user_id: Set(user_id),
+        bound_ip: Set(bound_ip),
+        single_use: Set(req.single_use),
+        used_at: Set(None),
+        expires_at: Set(now + Duration::seconds(ttl_secs)),
+        created_at: Set(now),
+        ..Default::default()
+    };
+    let token = match token.insert(&state.db).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database insert error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::DOWNLOAD_URL_CREATE_FAILED,
+            );
+        }
+    };
+
+    audit::record_file(
+        &state.db,
+        Some(user_id),
+        AuditEvent::DownloadUrlCreated,
+        &client,
+        Some(token.file_id),
+        Some(json!({
+            "token_id": token.id,
+            "bound_ip": token.bound_ip,
+            "single_use": token.single_use,
+        })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        user_id = user_id,
+        file_id = token.file_id,
+        token_id = token.id,
+        bound = token.bound_ip.is_some(),
+        single_use = token.single_use,
+        "Download URL created"
+    );
+
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        &i18n::DOWNLOAD_URL_CREATED,
+        Some(DownloadUrl {
+            url: download_url(&state, &token.token),
+            token: token.token,
+            file_id: token.file_id,
+            bound_ip: token.bound_ip,
+            single_use: token.single_use,
+            expires_at: timestamp::format(token.expires_at),
+        }),
+    )
+}
+
+/// Download a file through a presigned URL
+/// The requester of the URL must still be allowed to read the file
+pub async fn download_with_token(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Path(token): Path<String>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let now = Utc::now().naive_utc();
+
+    let link = match download_token::Entity::find()
+        .filter(download_token::Column::Token.eq(&token))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::DOWNLOAD_URL_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if link.expires_at <= now {
+        return error_resp(StatusCode::GONE, request_id, &i18n::DOWNLOAD_URL_EXPIRED);
+    }
+
+    if link
+        .bound_ip
+        .as_ref()
+        .is_some_and(|ip| client.ip.as_ref() != Some(ip))
+    {
+        tracing::warn!(
+            request_id = %request_id,
+            token_id = link.id,
+            ip = ?client.ip,
+            "Download URL used from another address"
+        );
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::DOWNLOAD_URL_WRONG_IP,
+        );
+    }
+
+    let role = match user::Entity::find_by_id(link.user_id).one(&state.db).await {
+        Ok(Some(u)) => u.role,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::GONE,
+                request_id,
+                &i18n::DOWNLOAD_URL_UNAVAILABLE,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+    match check_permission(
+        &state.db,
+        link.user_id,
+        &role,
+        link.file_id,
+        Permission::Read,
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return error_resp(
+                StatusCode::GONE,
+                request_id,
+                &i18n::DOWNLOAD_URL_UNAVAILABLE,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Permission check failed");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
+            );
+        }
+    }
+
+    let file_entity = match file::Entity::find_by_id(link.file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => {
+            return error_resp(StatusCode::GONE, request_id, &i18n::DOWNLOAD_URL_FILE_GONE);
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if let Some(reason) = unavailable_reason(&file_entity) {
+        return error_resp(StatusCode::FORBIDDEN, request_id, reason);
+    }
+
+    // Claim a single-use token atomically so two concurrent requests can't both use it
+    if link.single_use {
+        let claimed = download_token::Entity::update_many()
+            .col_expr(download_token::Column::UsedAt, Expr::value(now))
+            .filter(download_token::Column::Id.eq(link.id))
+            .filter(download_token::Column::UsedAt.is_null())
+            .exec(&state.db)
+            .await;
+        match claimed {
+            Ok(r) if r.rows_affected > 0 => {}
+            Ok(_) => {
+                return error_resp(StatusCode::GONE, request_id, &i18n::DOWNLOAD_URL_USED);
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        }
+    }
+
+    tracing::info!(
+        request_id = %request_id,
+        token_id = link.id,
+        file_id = file_entity.id,
+        "Presigned file download"
+    );
+
+    let response = stream_file(&file_entity, request_id, "attachment", false).await;
+    if response.status().is_success() {
+        file_stats::record_downloads(&state.db, &[file_entity.id]).await;
+    } else if link.single_use {
+        // Give the token back if nothing was served
+        let mut active: download_token::ActiveModel = link.into();
+        active.used_at = Set(None);
+        let _ = active.update(&state.db).await;
+    }
+    response
+}
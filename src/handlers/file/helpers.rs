@@ -1,5 +1,31 @@
-use crate::entities::file;
-use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use crate::{
+    config::DownloadConfig,
+    constants::ROLE_ADMIN,
+    entities::{
+        download_token, file, file_permission, file_stat, folder_default_permission, mount,
+        share_link,
+    },
+    models::file::{FileItem, FileType},
+    services::{
+        custom_metadata, file_stats, folder_styles, legal_hold, library, mounts, music,
+        permission_cache, photos, processing, stars, storage_health, volumes,
+    },
+    utils::{
+        file_utils, http_cache,
+        i18n::{self, Text},
+        jwt,
+        response::error_resp,
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, TransactionTrait};
+use std::path::{Path, PathBuf};
 
 /// Maximum number of duplicate files before erroring
 pub const MAX_DUPLICATE_FILES: u32 = 1000;
@@ -7,6 +33,18 @@ pub const MAX_DUPLICATE_FILES: u32 = 1000;
 /// Error message for too many duplicates
 pub const ERR_TOO_MANY_DUPLICATES: &str = "Too many duplicate files";
 
+/// Id of the authenticated user, a 500 response when the token subject is malformed
+#[allow(clippy::result_large_err)]
+pub fn parse_user_id(claims: &jwt::Claims, request_id: &str) -> Result<i32, Response> {
+    claims.sub.parse::<i32>().map_err(|_| {
+        error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id.to_string(),
+            &i18n::COMMON_INVALID_USER_ID,
+        )
+    })
+}
+
 /// Generate a unique filename by appending (1), (2), etc. if needed
 pub async fn generate_unique_filename(
     original_filename: &str,
@@ -67,3 +105,371 @@ pub fn calculate_folder_size(files: &[file::Model]) -> i64 {
         .map(|f| f.size_bytes.unwrap_or(0))
         .sum()
 }
+
+/// Delete a file record together with the rows that reference it
+/// (grants, share links, folder default policies and stats) in one transaction
+/// Records under legal hold are refused, whichever cleanup asks for it
+pub async fn delete_file_record(db: &DatabaseConnection, file_id: i32) -> Result<(), DbErr> {
+    delete_record(db, file_id, None).await
+}
+
+/// Like `delete_file_record`, but only while the record is still at `version`
+/// Fails with `DbErr::RecordNotUpdated` once someone else changed or removed it
+pub async fn delete_file_record_at(
+    db: &DatabaseConnection,
+    file_id: i32,
+    version: i32,
+) -> Result<(), DbErr> {
+    delete_record(db, file_id, Some(version)).await
+}
+
+async fn delete_record(
+    db: &DatabaseConnection,
+    file_id: i32,
+    version: Option<i32>,
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+
+    if let Some(f) = file::Entity::find_by_id(file_id).one(&txn).await? {
+        if legal_hold::holding(&txn, &f).await?.is_some() {
+            return Err(DbErr::Custom(i18n::FILE_LEGAL_HOLD.to_string()));
+        }
+    }
+
+    file_permission::Entity::delete_many()
+        .filter(file_permission::Column::FileId.eq(file_id))
+        .exec(&txn)
+        .await?;
+    share_link::Entity::delete_many()
+        .filter(share_link::Column::FileId.eq(file_id))
+        .exec(&txn)
+        .await?;
+    download_token::Entity::delete_many()
+        .filter(download_token::Column::FileId.eq(file_id))
+        .exec(&txn)
+        .await?;
+    folder_default_permission::Entity::delete_many()
+        .filter(folder_default_permission::Column::FolderId.eq(file_id))
+        .exec(&txn)
+        .await?;
+    file_stat::Entity::delete_by_id(file_id).exec(&txn).await?;
+    music::remove(&txn, file_id).await?;
+    photos::remove(&txn, file_id).await?;
+    processing::remove(&txn, file_id).await?;
+    custom_metadata::remove(&txn, file_id).await?;
+    folder_styles::remove(&txn, file_id).await?;
+    stars::remove(&txn, file_id).await?;
+    let mut delete = file::Entity::delete_by_id(file_id);
+    if let Some(v) = version {
+        delete = delete.filter(file::Column::Version.eq(v));
+    }
+    if delete.exec(&txn).await?.rows_affected == 0 && version.is_some() {
+        // Dropping the transaction rolls back the related rows removed above
+        return Err(DbErr::RecordNotUpdated);
+    }
+
+    txn.commit().await?;
+    permission_cache::invalidate_file(file_id);
+    Ok(())
+}
+
+/// Convert file records into response items with permissions and download stats
+/// Items the user cannot read are left out
+pub async fn build_file_items(
+    db: &DatabaseConnection,
+    files: Vec<file::Model>,
+    user_id: i32,
+    user_role: &str,
+    request_id: &str,
+) -> Vec<FileItem> {
+    let file_ids: Vec<i32> = files.iter().map(|f| f.id).collect();
+    let stats = file_stats::for_files(db, &file_ids)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load file stats");
+            Default::default()
+        });
+    let mut metadata = custom_metadata::for_files(db, &file_ids)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load file metadata");
+            Default::default()
+        });
+
+    let folder_ids: Vec<i32> = files
+        .iter()
+        .filter(|f| f.file_type == "folder")
+        .map(|f| f.id)
+        .collect();
+    let mut styles = folder_styles::for_files(db, user_id, &folder_ids)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load folder styles");
+            Default::default()
+        });
+
+    let starred = stars::starred_among(db, user_id, &file_ids)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load stars");
+            Default::default()
+        });
+
+    let permissions =
+        super::permission::get_files_permissions(db, user_id, user_role, &files).await;
+
+    let mut file_items = Vec::with_capacity(files.len());
+    for f in files {
+        let (can_read, can_write, can_delete) = permissions
+            .get(&f.id)
+            .copied()
+            .unwrap_or((false, false, false));
+
+        // Only return files user has read permission for
+        // Drop-only folders are listed so files can be added to them, without what they hold
+        let drop_only = !can_read && can_write && f.file_type == "folder";
+        if !can_read && !drop_only {
+            continue;
+        }
+
+        let file_type = if f.file_type == "folder" {
+            FileType::Folder
+        } else {
+            FileType::File
+        };
+
+        file_items.push(FileItem {
+            id: f.id,
+            name: f.name,
+            path: f.path,
+            file_type,
+            size_bytes: f.size_bytes,
+            total_size_bytes: f.total_size_bytes.filter(|_| !drop_only),
+            mime_type: f.mime_type,
+            created_at: timestamp::format(f.created_at),
+            updated_at: timestamp::format(f.updated_at),
+            client_modified: f.client_modified.map(timestamp::format),
+            version: f.version,
+            pinned: f.pinned,
+            legal_hold: f.legal_hold,
+            quarantined: f.quarantined_at.is_some(),
+            taken_down: f.taken_down,
+            starred: starred.contains(&f.id),
+            download_count: stats.get(&f.id).map_or(0, |s| s.download_count),
+            last_downloaded_at: stats
+                .get(&f.id)
+                .and_then(|s| s.last_downloaded_at)
+                .map(timestamp::format),
+            metadata: metadata.remove(&f.id).unwrap_or_default(),
+            style: styles.remove(&f.id),
+            can_read,
+            can_write,
+            can_delete,
+            is_owner: f.user_id == user_id,
+        });
+    }
+
+    file_items
+}
+
+/// If-Match header of a mutating request, read before the body is consumed
+pub fn if_match(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Whether the client's If-Match (if any) still matches the stored version
+pub fn precondition_met(if_match: Option<&str>, f: &file::Model) -> bool {
+    if_match
+        .is_none_or(|h| file_utils::if_match_satisfied(h, &file_utils::file_etag(f.id, f.version)))
+}
+
+/// Attach the ETag of the file's current version to a response
+pub fn with_etag(mut response: Response, f: &file::Model) -> Response {
+    if let Ok(value) = HeaderValue::from_str(&file_utils::file_etag(f.id, f.version)) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+/// Attach Cache-Control and Expires for a file download
+pub fn with_cache_headers(
+    mut response: Response,
+    config: &DownloadConfig,
+    f: &file::Model,
+    immutable: bool,
+) -> Response {
+    let mime_type = f.mime_type.as_deref().unwrap_or("application/octet-stream");
+    let cache_control = config.cache_control_for(mime_type, immutable);
+    let expires = http_cache::expires(cache_control, chrono::Utc::now());
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&expires) {
+        headers.insert(header::EXPIRES, value);
+    }
+    response
+}
+
+/// Storage root of the volume holding `user_id`'s files
+/// Writes to a volume failing its health check are refused
+pub async fn user_storage_root(
+    state: &AppState,
+    user_id: i32,
+    request_id: &str,
+) -> Result<PathBuf, Response> {
+    let root = volumes::user_root(&state.db, &state.config, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to resolve storage volume");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::STORAGE_VOLUME_RESOLVE_FAILED,
+            )
+        })?;
+
+    if !storage_health::is_healthy(&root) {
+        tracing::warn!(request_id = %request_id, user_id = user_id, root = ?root, "Write to unavailable storage volume refused");
+        return Err(error_resp(
+            StatusCode::SERVICE_UNAVAILABLE,
+            request_id.to_string(),
+            &i18n::STORAGE_VOLUME_UNAVAILABLE,
+        ));
+    }
+    Ok(root)
+}
+
+/// Refuse deleting, moving or overwriting `f` while a legal hold keeps it in place
+pub async fn not_on_hold(
+    db: &DatabaseConnection,
+    f: &file::Model,
+    request_id: &str,
+) -> Result<(), (StatusCode, Text)> {
+    match legal_hold::holding(db, f).await {
+        Ok(None) => Ok(()),
+        Ok(Some(held)) => {
+            tracing::warn!(request_id = %request_id, file_id = f.id, held_id = held.id, "Change refused by legal hold");
+            Err((StatusCode::LOCKED, Text::Catalog(&i18n::FILE_LEGAL_HOLD)))
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to check legal hold");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Text::Catalog(&i18n::COMMON_DATABASE_ERROR),
+            ))
+        }
+    }
+}
+
+/// Mount containing `path` when it may be changed
+/// Read-only mounts and the mount folders themselves are refused
+pub async fn writable_mount(
+    db: &DatabaseConnection,
+    user_id: i32,
+    path: &str,
+    request_id: &str,
+) -> Result<Option<mount::Model>, (StatusCode, Text)> {
+    let mount = mounts::find_for_path(db, user_id, path)
+        .await
+        .map_err(|e| {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to look up mounts");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Text::Catalog(&i18n::COMMON_DATABASE_ERROR),
+            )
+        })?;
+
+    match mount {
+        Some(m) if m.read_only => Err((
+            StatusCode::FORBIDDEN,
+            Text::Plain(format!("'{}' is a read-only mount", m.mount_point())),
+        )),
+        Some(m) if m.mount_point() == path => Err((
+            StatusCode::FORBIDDEN,
+            Text::Plain("Mount folders are managed by administrators".to_string()),
+        )),
+        other => Ok(other),
+    }
+}
+
+/// Check that `path` in `owner_id`'s tree is an existing folder `user_id` can write to
+/// The root always exists and is writable by its owner
+/// `denied` is the message returned when the folder exists but the user cannot write to it
+/// Returns whether the user may also read the folder, drop-only folders only take new files
+pub async fn writable_destination(
+    db: &DatabaseConnection,
+    owner_id: i32,
+    user_id: i32,
+    user_role: &str,
+    path: &str,
+    denied: &'static i18n::Message,
+    request_id: &str,
+) -> Result<bool, (StatusCode, Text)> {
+    let db_error = |e: DbErr| {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to look up destination folder");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Text::Catalog(&i18n::COMMON_DATABASE_ERROR),
+        )
+    };
+
+    let (can_read, can_write, _) = if path == "/" {
+        if library::is_library(owner_id) {
+            library::permissions(db, user_id, user_role, path)
+                .await
+                .map_err(db_error)?
+        } else {
+            let owned = owner_id == user_id || user_role == ROLE_ADMIN;
+            (owned, owned, owned)
+        }
+    } else {
+        let folder = file::Entity::find()
+            .filter(file::Column::UserId.eq(owner_id))
+            .filter(file::Column::Path.eq(path))
+            .one(db)
+            .await
+            .map_err(db_error)?;
+        match folder {
+            None => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    Text::Catalog(&i18n::FILE_DESTINATION_NOT_FOUND),
+                ))
+            }
+            Some(f) if f.file_type != "folder" => {
+                return Err((
+                    StatusCode::CONFLICT,
+                    Text::Catalog(&i18n::FILE_DESTINATION_NOT_FOLDER),
+                ))
+            }
+            Some(f) => super::permission::get_file_permissions(db, user_id, user_role, &f).await,
+        }
+    };
+
+    if !can_write {
+        return Err((StatusCode::FORBIDDEN, Text::Catalog(denied)));
+    }
+    Ok(can_read)
+}
+
+/// Physical location of `path`, in the mount's host directory or the user's storage
+/// The result uses the OS-specific separator
+pub fn physical_path(
+    mount: Option<&mount::Model>,
+    storage_root: &Path,
+    user_id: i32,
+    path: &str,
+) -> PathBuf {
+    match mount {
+        Some(m) => mounts::host_path(m, path),
+        None => file_utils::get_user_storage_path(storage_root, user_id).join(
+            path.trim_start_matches('/')
+                .replace('/', std::path::MAIN_SEPARATOR_STR),
+        ),
+    }
+}
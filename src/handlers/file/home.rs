@@ -0,0 +1,159 @@
+use crate::{
+    entities::{file, file_permission},
+    models::file::{FileItem, HomeFeedQuery, HomeFeedResponse},
+    services::stars,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::helpers::build_file_items;
+
+const DEFAULT_HOME_LIMIT: u64 = 20;
+const MAX_HOME_LIMIT: u64 = 100;
+
+/// Recently changed, starred and newly shared items in one response for the landing page
+pub async fn get_home_feed(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<HomeFeedQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HOME_LIMIT)
+        .clamp(1, MAX_HOME_LIMIT);
+
+    let sections = match load_sections(&state.db, user_id, limit).await {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to load home feed");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    // Items of all sections are resolved together, an item in several sections is built once
+    let mut seen = HashSet::new();
+    let files: Vec<file::Model> = sections
+        .files
+        .into_iter()
+        .filter(|f| seen.insert(f.id))
+        .collect();
+    let items: HashMap<i32, FileItem> =
+        build_file_items(&state.db, files, user_id, &claims.role, &request_id)
+            .await
+            .into_iter()
+            .map(|item| (item.id, item))
+            .collect();
+    let pick = |ids: &[i32]| -> Vec<FileItem> {
+        ids.iter().filter_map(|id| items.get(id).cloned()).collect()
+    };
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_HOME_RETRIEVED,
+        Some(HomeFeedResponse {
+            recent: pick(&sections.recent),
+            starred: pick(&sections.starred),
+            shared: pick(&sections.shared),
+        }),
+    )
+}
+
+/// File records of the feed with the IDs of each section in display order
+struct HomeSections {
+    files: Vec<file::Model>,
+    recent: Vec<i32>,
+    starred: Vec<i32>,
+    shared: Vec<i32>,
+}
+
+async fn load_sections(
+    db: &DatabaseConnection,
+    user_id: i32,
+    limit: u64,
+) -> Result<HomeSections, DbErr> {
+    let recent_files = file::Entity::find()
+        .filter(file::Column::UserId.eq(user_id))
+        .filter(file::Column::FileType.eq("file"))
+        .order_by_desc(file::Column::UpdatedAt)
+        .order_by_desc(file::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+    let recent: Vec<i32> = recent_files.iter().map(|f| f.id).collect();
+
+    let starred = stars::recent(db, user_id, limit).await?;
+
+    // Drop-only grants count as shared too, such folders are listed without their content
+    let shared: Vec<i32> = file_permission::Entity::find()
+        .select_only()
+        .column(file_permission::Column::FileId)
+        .filter(file_permission::Column::UserId.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(file_permission::Column::CanRead.eq(true))
+                .add(file_permission::Column::CanWrite.eq(true)),
+        )
+        .order_by_desc(file_permission::Column::CreatedAt)
+        .order_by_desc(file_permission::Column::Id)
+        .limit(limit)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let others: Vec<i32> = starred
+        .iter()
+        .chain(&shared)
+        .copied()
+        .filter(|id| !recent.contains(id))
+        .collect();
+    let mut files = recent_files;
+    if !others.is_empty() {
+        files.extend(
+            file::Entity::find()
+                .filter(file::Column::Id.is_in(others))
+                .all(db)
+                .await?,
+        );
+    }
+
+    Ok(HomeSections {
+        files,
+        recent,
+        starred,
+        shared,
+    })
+}
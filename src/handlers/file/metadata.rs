@@ -0,0 +1,129 @@
+use crate::{
+    entities::file,
+    models::file::FileMetadataResponse,
+    services::custom_metadata::{self, Metadata},
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::EntityTrait;
+use std::collections::BTreeSet;
+
+use super::permission::get_file_permissions;
+
+/// Merge key-value metadata into a file, a `null` value removes the key
+pub async fn update_file_metadata(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(patch): Json<Metadata>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let file_entity = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let (can_read, can_write, _) =
+        get_file_permissions(&state.db, user_id, &claims.role, &file_entity).await;
+    if !can_read {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
+    }
+    if !can_write {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_NO_WRITE_PERMISSION,
+        );
+    }
+
+    for (key, value) in &patch {
+        let checked = custom_metadata::validate_key(key).and_then(|_| {
+            if value.is_null() {
+                Ok(())
+            } else {
+                custom_metadata::encode(key, value).map(|_| ())
+            }
+        });
+        if let Err(e) = checked {
+            return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+        }
+    }
+
+    let existing = match custom_metadata::for_files(&state.db, &[file_id]).await {
+        Ok(mut m) => m.remove(&file_id).unwrap_or_default(),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+    let mut keys: BTreeSet<&String> = existing.keys().collect();
+    for (key, value) in &patch {
+        if value.is_null() {
+            keys.remove(key);
+        } else {
+            keys.insert(key);
+        }
+    }
+    if keys.len() > custom_metadata::MAX_ENTRIES {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "A file can have at most {} metadata entries",
+                custom_metadata::MAX_ENTRIES
+            ),
+        );
+    }
+
+    match custom_metadata::apply(&state.db, &file_entity, patch).await {
+        Ok(metadata) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::FILE_METADATA_UPDATED,
+            Some(FileMetadataResponse { file_id, metadata }),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to update file metadata");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
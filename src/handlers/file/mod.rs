@@ -1,24 +1,95 @@
 // Module declarations
+mod bulk;
+mod changes;
+mod cleanup;
 mod download;
+mod download_url;
 mod helpers;
+mod home;
+mod metadata;
 mod operations;
+mod organize;
 mod permission;
+mod permission_copy;
+mod permission_template;
+mod pin;
+mod precheck;
+mod search;
+mod star;
+mod stat;
+mod style;
+mod tree;
 mod upload;
+mod upload_session;
 
 // Re-export all public handlers
 pub use permission::{
     check_permission,
+    explicit_grants,
+    get_file_permissions,
+    get_files_permissions,
     grant_permission,
+    list_folder_default_permissions,
     list_user_permissions,
+    remove_folder_default_permission,
     revoke_permission,
+    set_folder_default_permission,
     // Export types and functions used by other modules
     Permission,
 };
 
-pub use upload::upload_file;
+pub use permission_copy::copy_permissions;
 
-pub use download::{batch_download_files, get_file};
+pub use permission_template::{
+    bulk_grant_permissions, create_permission_template, delete_permission_template,
+    list_permission_templates,
+};
+
+pub use changes::list_changes;
+
+pub use cleanup::{
+    create_cleanup_rule, delete_cleanup_rule, list_cleanup_rules, update_cleanup_rule,
+};
+
+pub use metadata::update_file_metadata;
+
+pub use search::search_files;
+
+pub use stat::stat_file;
+
+pub use organize::{
+    create_organize_rule, delete_organize_rule, list_organize_rules, preview_organize_rules,
+    update_organize_rule,
+};
+
+pub use pin::set_file_pinned;
+
+pub use star::set_file_starred;
+
+pub use bulk::{bulk_star_files, bulk_tag_files};
+
+pub use home::get_home_feed;
+
+pub use precheck::precheck_upload;
+
+pub use style::set_folder_style;
+
+pub use tree::list_tree;
+
+pub use upload::{upload_file, upload_library_file};
+
+pub use upload_session::{
+    cancel_upload_session, complete_upload_session, list_upload_sessions, start_upload_session,
+    upload_chunk,
+};
+
+pub use download_url::{create_download_url, download_with_token};
+
+pub use download::{batch_download_files, download_folder, get_file, prepare_batch_download};
+pub(crate) use download::{stream_file, unavailable_reason};
+pub(crate) use helpers::{build_file_items, delete_file_record, parse_user_id, with_cache_headers};
 
+pub(crate) use operations::create_folder_in;
 pub use operations::{
     calculate_size, copy_file, create_folder, delete_file, list_files, move_file, rename_file,
 };
@@ -1,12 +1,18 @@
 use crate::{
-    entities::{file, user},
+    entities::file,
     models::file::{
         CalculateSizeRequest, CalculateSizeResponse, CopyRequest, CreateFolderRequest, DeleteQuery,
-        FileItem, FileListQuery, FileListResponse, FileType, MoveRequest,
+        FileListQuery, FileListResponse, MoveRequest,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        changes, disk_space, folder_defaults, folder_sizes, jobs, quota,
     },
     utils::{
-        file_utils, jwt, request_id,
+        client::ClientInfo,
+        export, file_utils, i18n, jwt, request_id,
         response::{do_json_detail_resp, error_resp},
+        validation,
     },
     AppState,
 };
@@ -16,11 +22,23 @@ use axum::{
     response::Response,
     Extension,
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-use std::path::PathBuf;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use super::helpers::{
+    if_match, not_on_hold, physical_path, precondition_met, with_etag, writable_destination,
+    writable_mount,
+};
 use super::permission::{check_permission, get_file_permissions, Permission};
 
+/// Files copied at once when copying a folder
+const COPY_WORKERS: usize = 4;
+/// Rows per INSERT when recording the contents of a copied folder
+const INSERT_CHUNK: usize = 500;
+
 /// List files in a directory
 pub async fn list_files(
     State(state): State<AppState>,
@@ -35,7 +53,7 @@ pub async fn list_files(
             return error_resp(
                 StatusCode::UNAUTHORIZED,
                 request_id.clone(),
-                "Authentication required",
+                &i18n::AUTH_REQUIRED,
             );
         }
     };
@@ -46,35 +64,21 @@ pub async fn list_files(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    // Get user role
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
-        }
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error",
-            );
-        }
-    };
+    let user_role = claims.role.clone();
 
     let path = query.path.unwrap_or_else(|| "/".to_string());
     let owner_id = query.owner_id.unwrap_or(user_id);
 
-    if user_entity.role != "admin" && owner_id != user_id {
+    if user_role != "admin" && owner_id != user_id {
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You can only view your own files",
+            &i18n::FILE_OWN_FILES_ONLY,
         );
     }
 
@@ -82,7 +86,7 @@ pub async fn list_files(
     let clean_path = match file_utils::sanitize_path(&path) {
         Ok(p) => p,
         Err(e) => {
-            return error_resp(StatusCode::BAD_REQUEST, request_id, &e.to_string());
+            return error_resp(StatusCode::BAD_REQUEST, request_id, e);
         }
     };
 
@@ -107,43 +111,13 @@ pub async fn list_files(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
-    // Convert to response format with permissions
-    let mut file_items = Vec::new();
-    for f in files {
-        let (can_read, can_write, can_delete) =
-            get_file_permissions(&state.db, user_id, &user_entity.role, &f).await;
-
-        // Only return files user has read permission for
-        if !can_read {
-            continue;
-        }
-
-        let file_type = if f.file_type == "folder" {
-            FileType::Folder
-        } else {
-            FileType::File
-        };
-
-        file_items.push(FileItem {
-            id: f.id,
-            name: f.name,
-            path: f.path,
-            file_type,
-            size_bytes: f.size_bytes,
-            mime_type: f.mime_type,
-            created_at: f.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-            updated_at: f.updated_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-            can_read,
-            can_write,
-            can_delete,
-            is_owner: f.user_id == user_id,
-        });
-    }
+    let file_items =
+        super::helpers::build_file_items(&state.db, files, user_id, &user_role, &request_id).await;
 
     let response = FileListResponse {
         files: file_items,
@@ -153,7 +127,7 @@ pub async fn list_files(
     do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "Files retrieved successfully",
+        &i18n::FILE_LISTED,
         Some(response),
     )
 }
@@ -166,11 +140,7 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
         None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            );
+            return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED);
         }
     };
 
@@ -180,11 +150,13 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
+    let user_role = claims.role.clone();
+
     // Parse request body
     let bytes = match axum::body::to_bytes(request.into_body(), usize::MAX).await {
         Ok(b) => b,
@@ -193,7 +165,7 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Failed to read request",
+                &i18n::COMMON_READ_REQUEST_FAILED,
             );
         }
     };
@@ -205,44 +177,218 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
 
-    let parent_path = match file_utils::sanitize_path(&req.path) {
-        Ok(p) => p,
+    // A parent given by id may be a folder shared with the caller, or any folder for admins
+    let (owner_id, parent_path) = match req.parent_id {
+        Some(parent_id) => {
+            let parent = match file::Entity::find_by_id(parent_id).one(&state.db).await {
+                Ok(Some(f)) => f,
+                Ok(None) => {
+                    return error_resp(
+                        StatusCode::NOT_FOUND,
+                        request_id,
+                        &i18n::FILE_DESTINATION_NOT_FOUND,
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                    return error_resp(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        request_id,
+                        &i18n::COMMON_DATABASE_ERROR,
+                    );
+                }
+            };
+            let (can_read, can_write, _) =
+                get_file_permissions(&state.db, user_id, &user_role, &parent).await;
+            if !can_read {
+                return error_resp(
+                    StatusCode::NOT_FOUND,
+                    request_id,
+                    &i18n::FILE_DESTINATION_NOT_FOUND,
+                );
+            }
+            if parent.file_type != "folder" {
+                return error_resp(
+                    StatusCode::CONFLICT,
+                    request_id,
+                    &i18n::FILE_DESTINATION_NOT_FOLDER,
+                );
+            }
+            if !can_write {
+                return error_resp(
+                    StatusCode::FORBIDDEN,
+                    request_id,
+                    &i18n::FILE_NO_PERMISSION_CREATE_FOLDER,
+                );
+            }
+            (parent.user_id, parent.path)
+        }
+        None => match file_utils::sanitize_path(&req.path) {
+            Ok(p) => (user_id, p),
+            Err(e) => {
+                return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+            }
+        },
+    };
+
+    create_folder_in(
+        &state,
+        owner_id,
+        parent_path,
+        &req.name,
+        req.exist_ok,
+        request_id,
+    )
+    .await
+}
+
+/// Create a folder in the tree of `owner_id`, after the caller's access has been checked
+/// The folders above it have to exist, unless `exist_ok` asks to create them like `mkdir -p`
+/// With `exist_ok` an existing folder of that name is returned instead of a conflict
+pub(crate) async fn create_folder_in(
+    state: &AppState,
+    owner_id: i32,
+    parent_path: String,
+    name: &str,
+    exist_ok: bool,
+    request_id: String,
+) -> Response {
+    let name = file_utils::normalize_name(name);
+    let folder_path = format!("{}/{}", parent_path.trim_end_matches('/'), name);
+
+    let ancestors = file_utils::ancestor_paths(&parent_path);
+    let mut lookup = ancestors.clone();
+    lookup.push(folder_path.clone());
+    let existing: HashMap<String, file::Model> = match file::Entity::find()
+        .filter(file::Column::UserId.eq(owner_id))
+        .filter(file::Column::Path.is_in(lookup))
+        .all(&state.db)
+        .await
+    {
+        Ok(rows) => rows.into_iter().map(|f| (f.path.clone(), f)).collect(),
         Err(e) => {
-            return error_resp(StatusCode::BAD_REQUEST, request_id, &e.to_string());
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
         }
     };
 
-    let folder_path = format!("{}/{}", parent_path.trim_end_matches('/'), req.name);
+    if let Some(found) = existing.get(&folder_path) {
+        if exist_ok && found.file_type == "folder" {
+            return do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::FILE_FOLDER_EXISTS,
+                Some(found),
+            );
+        }
+        return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_NAME_CONFLICT);
+    }
 
-    let storage_root = state.config.get_storage_dir();
-    let _ = file_utils::ensure_user_directory(&storage_root, user_id);
+    for path in &ancestors {
+        match existing.get(path) {
+            Some(f) if f.file_type == "folder" => {}
+            Some(_) => {
+                return error_resp(
+                    StatusCode::CONFLICT,
+                    request_id,
+                    &i18n::FILE_DESTINATION_NOT_FOLDER,
+                );
+            }
+            None if exist_ok => {}
+            None => {
+                return error_resp(
+                    StatusCode::NOT_FOUND,
+                    request_id,
+                    &i18n::FILE_DESTINATION_NOT_FOUND,
+                );
+            }
+        }
+    }
+
+    // Check every name before anything is created
+    let missing: Vec<&String> = ancestors
+        .iter()
+        .filter(|p| !existing.contains_key(*p))
+        .chain(std::iter::once(&folder_path))
+        .collect();
+    for path in &missing {
+        let segment = path.rsplit('/').next().unwrap_or_default();
+        if let Err(e) = validation::validate_filename(segment, &state.config.validation) {
+            return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+        }
+        if file_utils::is_protected_path(path) {
+            return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_RESERVED_PATH);
+        }
+    }
+
+    let storage_root = match super::helpers::user_storage_root(state, owner_id, &request_id).await {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+    let _ = file_utils::ensure_user_directory(&storage_root, owner_id).await;
+
+    let mut created = None;
+    for path in missing {
+        match insert_folder(state, owner_id, &storage_root, path, &request_id).await {
+            Ok(folder) => created = Some(folder),
+            Err(resp) => return resp,
+        }
+    }
 
-    let physical_path = file_utils::get_user_storage_path(&storage_root, user_id)
-        .join(folder_path.trim_start_matches('/'));
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        &i18n::FILE_FOLDER_CREATED,
+        created,
+    )
+}
+
+/// Create the directory and record of one folder whose parent exists
+async fn insert_folder(
+    state: &AppState,
+    owner_id: i32,
+    storage_root: &Path,
+    folder_path: &str,
+    request_id: &str,
+) -> Result<file::Model, Response> {
+    let (parent_path, name) = match folder_path.rfind('/') {
+        Some(0) | None => ("/", folder_path.trim_start_matches('/')),
+        Some(idx) => (&folder_path[..idx], &folder_path[idx + 1..]),
+    };
 
-    if let Err(e) = std::fs::create_dir_all(&physical_path) {
+    let mount = writable_mount(&state.db, owner_id, folder_path, request_id)
+        .await
+        .map_err(|(status, msg)| error_resp(status, request_id.to_string(), msg))?;
+    let physical_path = physical_path(mount.as_ref(), storage_root, owner_id, folder_path);
+
+    if let Err(e) = tokio::fs::create_dir_all(&physical_path).await {
         tracing::error!(request_id = %request_id, error = ?e, "Failed to create directory");
-        return error_resp(
+        return Err(error_resp(
             StatusCode::INTERNAL_SERVER_ERROR,
-            request_id,
-            "Failed to create folder",
-        );
+            request_id.to_string(),
+            &i18n::FILE_CREATE_FOLDER_FAILED,
+        ));
     }
 
     let now = chrono::Utc::now().naive_utc();
     let new_folder = file::ActiveModel {
-        user_id: Set(user_id),
-        name: Set(req.name.clone()),
-        path: Set(folder_path.clone()),
-        parent_path: Set(parent_path),
+        user_id: Set(owner_id),
+        name: Set(name.to_string()),
+        path: Set(folder_path.to_string()),
+        parent_path: Set(parent_path.to_string()),
         file_type: Set("folder".to_string()),
         mime_type: Set(None),
         size_bytes: Set(None),
+        total_size_bytes: Set(Some(0)),
         storage_path: Set(physical_path.to_string_lossy().to_string()),
         created_at: Set(now),
         updated_at: Set(now),
@@ -252,20 +398,19 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
     match new_folder.insert(&state.db).await {
         Ok(folder) => {
             tracing::info!(request_id = %request_id, folder_id = folder.id, "Folder created successfully");
-            do_json_detail_resp(
-                StatusCode::CREATED,
-                request_id,
-                "Folder created successfully",
-                Some(folder),
-            )
+            changes::record(&state.db, &folder, changes::CHANGE_CREATED, None).await;
+            if let Err(e) = folder_defaults::apply(&state.db, &folder).await {
+                tracing::warn!(request_id = %request_id, error = ?e, "Failed to apply folder default permissions");
+            }
+            Ok(folder)
         }
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
-            error_resp(
+            Err(error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            )
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ))
         }
     }
 }
@@ -273,6 +418,7 @@ pub async fn create_folder(State(state): State<AppState>, request: Request) -> R
 /// Delete a file or folder
 pub async fn delete_file(
     State(state): State<AppState>,
+    client: ClientInfo,
     Query(query): Query<DeleteQuery>,
     request: Request,
 ) -> Response {
@@ -282,11 +428,7 @@ pub async fn delete_file(
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
         None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            );
+            return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED);
         }
     };
 
@@ -296,31 +438,17 @@ pub async fn delete_file(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    // Get user role
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
-        }
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            );
-        }
-    };
+    let user_role = claims.role.clone();
 
     let has_permission = match check_permission(
         &state.db,
         user_id,
-        &user_entity.role,
+        &user_role,
         query.file_id,
         Permission::Delete,
     )
@@ -332,7 +460,7 @@ pub async fn delete_file(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Permission check failed",
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
             );
         }
     };
@@ -341,7 +469,7 @@ pub async fn delete_file(
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You don't have permission to delete this file",
+            &i18n::FILE_NO_PERMISSION_DELETE,
         );
     }
 
@@ -349,35 +477,92 @@ pub async fn delete_file(
     let file_entity = match file::Entity::find_by_id(query.file_id).one(&state.db).await {
         Ok(Some(f)) => f,
         Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "File not found");
+            return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
         }
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
+    if !precondition_met(if_match(&request).as_deref(), &file_entity) {
+        return error_resp(
+            StatusCode::PRECONDITION_FAILED,
+            request_id,
+            &i18n::FILE_MODIFIED,
+        );
+    }
+
+    if file_utils::is_protected_path(&file_entity.path) {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_PROTECTED_PATH,
+        );
+    }
+
+    if let Err((status, msg)) = not_on_hold(&state.db, &file_entity, &request_id).await {
+        return error_resp(status, request_id, msg);
+    }
+
+    if let Err((status, msg)) = writable_mount(
+        &state.db,
+        file_entity.user_id,
+        &file_entity.path,
+        &request_id,
+    )
+    .await
+    {
+        return error_resp(status, request_id, msg);
+    }
+
     // Store the storage path before deleting the record
     let storage_path = file_entity.storage_path.clone();
     let file_type = file_entity.file_type.clone();
+    let freed_bytes = folder_sizes::item_size(&state.db, &file_entity)
+        .await
+        .unwrap_or(0);
 
     // Delete database record first
-    if let Err(e) = file::Entity::delete_by_id(query.file_id)
-        .exec(&state.db)
-        .await
+    // Conditional on the version checked above, so a concurrent change is not deleted unseen
+    match super::helpers::delete_file_record_at(&state.db, query.file_id, file_entity.version).await
     {
-        tracing::error!(request_id = %request_id, error = ?e, "Failed to delete from database");
-        return error_resp(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            request_id,
-            "Database error occurred",
-        );
+        Ok(()) => {}
+        Err(DbErr::RecordNotUpdated) => {
+            return error_resp(
+                StatusCode::PRECONDITION_FAILED,
+                request_id,
+                &i18n::FILE_MODIFIED,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to delete from database");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
     }
 
+    audit::record_file(
+        &state.db,
+        Some(user_id),
+        AuditEvent::FileDeleted,
+        &client,
+        Some(query.file_id),
+        Some(serde_json::json!({
+            "name": file_entity.name,
+            "path": file_entity.path,
+            "file_type": file_type,
+        })),
+    )
+    .await;
+
     // After deleting the record, check if any other files still reference this physical file
     let should_delete_physical = if file_type == "file" {
         // Normalize storage_path for comparison (database uses forward slashes)
@@ -428,11 +613,11 @@ pub async fn delete_file(
         } else {
             PathBuf::from(&storage_path)
         };
-        if physical_path.exists() {
+        if tokio::fs::try_exists(&physical_path).await.unwrap_or(false) {
             let delete_result = if file_type == "folder" {
-                std::fs::remove_dir_all(&physical_path)
+                tokio::fs::remove_dir_all(&physical_path).await
             } else {
-                std::fs::remove_file(&physical_path)
+                tokio::fs::remove_file(&physical_path).await
             };
 
             if let Err(e) = delete_result {
@@ -450,6 +635,15 @@ pub async fn delete_file(
         );
     }
 
+    folder_sizes::adjust(
+        &state.db,
+        file_entity.user_id,
+        &file_entity.parent_path,
+        -freed_bytes,
+    )
+    .await;
+    changes::record(&state.db, &file_entity, changes::CHANGE_DELETED, None).await;
+
     tracing::info!(request_id = %request_id, file_id = query.file_id, "File deleted successfully");
     do_json_detail_resp::<()>(
         StatusCode::OK,
@@ -465,13 +659,7 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
 
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
-        None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            )
-        }
+        None => return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED),
     };
 
     let user_id = match claims.sub.parse::<i32>() {
@@ -480,11 +668,15 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             )
         }
     };
 
+    let user_role = claims.role.clone();
+
+    let if_match = if_match(&request);
+
     let bytes = match axum::body::to_bytes(request.into_body(), usize::MAX).await {
         Ok(b) => b,
         Err(e) => {
@@ -492,48 +684,32 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Failed to read request",
+                &i18n::COMMON_READ_REQUEST_FAILED,
             );
         }
     };
 
-    let req: crate::models::file::RenameRequest = match serde_json::from_slice(&bytes) {
+    let mut req: crate::models::file::RenameRequest = match serde_json::from_slice(&bytes) {
         Ok(r) => r,
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to parse request");
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
 
-    if req.new_name.contains('/') || req.new_name.contains('\\') {
-        return error_resp(
-            StatusCode::BAD_REQUEST,
-            request_id,
-            "File name cannot contain path separators",
-        );
+    req.new_name = file_utils::normalize_name(&req.new_name);
+    if let Err(e) = validation::validate_filename(&req.new_name, &state.config.validation) {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, e);
     }
 
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "User not found"),
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            );
-        }
-    };
-
     let has_permission = match check_permission(
         &state.db,
         user_id,
-        &user_entity.role,
+        &user_role,
         req.file_id,
         Permission::Write,
     )
@@ -545,7 +721,7 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Permission check failed",
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
             );
         }
     };
@@ -554,53 +730,80 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You don't have permission to rename this file",
+            &i18n::FILE_NO_PERMISSION_RENAME,
         );
     }
 
     let file_entity = match file::Entity::find_by_id(req.file_id).one(&state.db).await {
         Ok(Some(f)) => f,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "File not found"),
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
+    if !precondition_met(if_match.as_deref(), &file_entity) {
+        return error_resp(
+            StatusCode::PRECONDITION_FAILED,
+            request_id,
+            &i18n::FILE_MODIFIED,
+        );
+    }
+
+    // Renames stay in the tree the file belongs to, e.g. the Shared Library
+    let owner_id = file_entity.user_id;
     let old_path = file_entity.path.clone();
     let parent_path = file_entity.parent_path.clone();
     let new_path = format!("{}/{}", parent_path.trim_end_matches('/'), req.new_name);
 
+    if file_utils::is_protected_path(&old_path) {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_PROTECTED_PATH,
+        );
+    }
+    if file_utils::is_protected_path(&new_path) {
+        return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_RESERVED_PATH);
+    }
+    if let Err((status, msg)) = not_on_hold(&state.db, &file_entity, &request_id).await {
+        return error_resp(status, request_id, msg);
+    }
+
     if new_path != old_path {
         if let Ok(Some(_)) = file::Entity::find()
-            .filter(file::Column::UserId.eq(user_id))
+            .filter(file::Column::UserId.eq(owner_id))
             .filter(file::Column::Path.eq(&new_path))
             .one(&state.db)
             .await
         {
-            return error_resp(
-                StatusCode::CONFLICT,
-                request_id,
-                "A file with this name already exists",
-            );
+            return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_NAME_CONFLICT);
         }
     }
 
-    let storage_root = state.config.get_storage_dir();
+    let storage_root = match super::helpers::user_storage_root(&state, owner_id, &request_id).await
+    {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+    let mount = match writable_mount(&state.db, owner_id, &old_path, &request_id).await {
+        Ok(m) => m,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
     let old_physical = PathBuf::from(&file_entity.storage_path);
-    let new_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-        .join(new_path.trim_start_matches('/'));
+    let new_physical = physical_path(mount.as_ref(), &storage_root, owner_id, &new_path);
 
-    if let Err(e) = std::fs::rename(&old_physical, &new_physical) {
+    if let Err(e) = tokio::fs::rename(&old_physical, &new_physical).await {
         tracing::error!(request_id = %request_id, error = ?e, "Failed to rename physical file");
         return error_resp(
             StatusCode::INTERNAL_SERVER_ERROR,
             request_id,
-            "Failed to rename file",
+            &i18n::FILE_RENAME_FAILED,
         );
     }
 
@@ -610,15 +813,29 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
     active_model.storage_path = Set(new_physical.to_string_lossy().to_string());
     active_model.updated_at = Set(chrono::Utc::now().naive_utc());
 
-    let updated_file = match active_model.update(&state.db).await {
+    // Only update the version we checked, a concurrent change wins
+    active_model.version = Set(file_entity.version + 1);
+    let updated_file = match file::Entity::update(active_model)
+        .filter(file::Column::Version.eq(file_entity.version))
+        .exec(&state.db)
+        .await
+    {
         Ok(f) => f,
+        Err(DbErr::RecordNotUpdated) => {
+            let _ = tokio::fs::rename(&new_physical, &old_physical).await;
+            return error_resp(
+                StatusCode::PRECONDITION_FAILED,
+                request_id,
+                &i18n::FILE_MODIFIED,
+            );
+        }
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to update database");
-            let _ = std::fs::rename(&new_physical, &old_physical);
+            let _ = tokio::fs::rename(&new_physical, &old_physical).await;
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
@@ -626,7 +843,7 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
     // Update child paths for folders
     if file_entity.file_type == "folder" {
         if let Ok(children) =
-            super::helpers::get_folder_files_recursive(&state.db, &old_path, user_id).await
+            super::helpers::get_folder_files_recursive(&state.db, &old_path, owner_id).await
         {
             for child in children {
                 if child.id == updated_file.id {
@@ -634,10 +851,12 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
                 }
 
                 let new_child_path = child.path.replacen(&old_path, &new_path, 1);
-                let new_child_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-                    .join(new_child_path.trim_start_matches('/'));
+                let new_child_physical =
+                    physical_path(mount.as_ref(), &storage_root, owner_id, &new_child_path);
 
+                let child_version = child.version;
                 let mut child_active: file::ActiveModel = child.into();
+                child_active.version = Set(child_version + 1);
                 child_active.path = Set(new_child_path);
                 child_active.storage_path = Set(new_child_physical.to_string_lossy().to_string());
                 child_active.updated_at = Set(chrono::Utc::now().naive_utc());
@@ -647,13 +866,22 @@ pub async fn rename_file(State(state): State<AppState>, request: Request) -> Res
         }
     }
 
+    changes::record(
+        &state.db,
+        &updated_file,
+        changes::CHANGE_MOVED,
+        Some(&old_path),
+    )
+    .await;
+
     tracing::info!(request_id = %request_id, file_id = updated_file.id, "File renamed successfully");
-    do_json_detail_resp(
+    let response = do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "File renamed successfully",
-        Some(updated_file),
-    )
+        &i18n::FILE_RENAMED,
+        Some(&updated_file),
+    );
+    with_etag(response, &updated_file)
 }
 
 /// Move a file or folder to a different directory
@@ -662,13 +890,7 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
 
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
-        None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            )
-        }
+        None => return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED),
     };
 
     let user_id = match claims.sub.parse::<i32>() {
@@ -677,11 +899,15 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             )
         }
     };
 
+    let user_role = claims.role.clone();
+
+    let if_match = if_match(&request);
+
     let bytes = match axum::body::to_bytes(request.into_body(), usize::MAX).await {
         Ok(b) => b,
         Err(e) => {
@@ -689,7 +915,7 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Failed to read request",
+                &i18n::COMMON_READ_REQUEST_FAILED,
             );
         }
     };
@@ -701,33 +927,33 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
 
     let dest_path = match file_utils::sanitize_path(&req.destination_path) {
         Ok(p) => p,
-        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, &e.to_string()),
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
     };
-
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "User not found"),
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            );
-        }
+    let client_modified = match req.client_modified.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(value) => match export::parse_time_bound(value, false) {
+            Some(t) => Some(t),
+            None => {
+                return error_resp(
+                    StatusCode::BAD_REQUEST,
+                    request_id,
+                    &i18n::FILE_INVALID_CLIENT_MODIFIED,
+                );
+            }
+        },
     };
 
     let has_permission = match check_permission(
         &state.db,
         user_id,
-        &user_entity.role,
+        &user_role,
         req.file_id,
         Permission::Write,
     )
@@ -739,7 +965,7 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Permission check failed",
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
             );
         }
     };
@@ -748,28 +974,82 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You don't have permission to move this file",
+            &i18n::FILE_NO_PERMISSION_MOVE,
         );
     }
 
     let file_entity = match file::Entity::find_by_id(req.file_id).one(&state.db).await {
         Ok(Some(f)) => f,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "File not found"),
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
+    if !precondition_met(if_match.as_deref(), &file_entity) {
+        return error_resp(
+            StatusCode::PRECONDITION_FAILED,
+            request_id,
+            &i18n::FILE_MODIFIED,
+        );
+    }
+
+    if file_entity.file_type == "folder" && file_utils::in_subtree(&dest_path, &file_entity.path) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_MOVE_INTO_ITSELF,
+        );
+    }
+
+    // Names from before the current rules have to be fixed with a rename first
+    if let Err(e) = validation::validate_filename(&file_entity.name, &state.config.validation) {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+    }
+
+    // Moves stay in the tree the file belongs to, e.g. the Shared Library
+    let owner_id = file_entity.user_id;
     let old_path = file_entity.path.clone();
     let new_path = format!("{}/{}", dest_path.trim_end_matches('/'), file_entity.name);
 
+    if file_utils::is_protected_path(&old_path) {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_PROTECTED_PATH,
+        );
+    }
+    if file_utils::is_protected_path(&new_path) {
+        return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_RESERVED_PATH);
+    }
+    if let Err((status, msg)) = not_on_hold(&state.db, &file_entity, &request_id).await {
+        return error_resp(status, request_id, msg);
+    }
+    let moved_bytes = folder_sizes::item_size(&state.db, &file_entity)
+        .await
+        .unwrap_or(0);
+
+    if let Err((status, msg)) = writable_destination(
+        &state.db,
+        owner_id,
+        user_id,
+        &user_role,
+        &dest_path,
+        &i18n::FILE_NO_PERMISSION_DESTINATION,
+        &request_id,
+    )
+    .await
+    {
+        return error_resp(status, request_id, msg);
+    }
+
     if let Ok(Some(_)) = file::Entity::find()
-        .filter(file::Column::UserId.eq(user_id))
+        .filter(file::Column::UserId.eq(owner_id))
         .filter(file::Column::Path.eq(&new_path))
         .one(&state.db)
         .await
@@ -777,32 +1057,51 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
         return error_resp(
             StatusCode::CONFLICT,
             request_id,
-            "A file with this name already exists in destination",
+            &i18n::FILE_NAME_CONFLICT_DESTINATION,
+        );
+    }
+
+    let storage_root = match super::helpers::user_storage_root(&state, owner_id, &request_id).await
+    {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+    let source_mount = match writable_mount(&state.db, owner_id, &old_path, &request_id).await {
+        Ok(m) => m,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+    let mount = match writable_mount(&state.db, owner_id, &new_path, &request_id).await {
+        Ok(m) => m,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+    if source_mount.as_ref().map(|m| m.id) != mount.as_ref().map(|m| m.id) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_MOVE_ACROSS_MOUNT,
         );
     }
 
-    let storage_root = state.config.get_storage_dir();
     let old_physical = PathBuf::from(&file_entity.storage_path);
-    let new_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-        .join(new_path.trim_start_matches('/'));
+    let new_physical = physical_path(mount.as_ref(), &storage_root, owner_id, &new_path);
 
     if let Some(parent) = new_physical.parent() {
-        if let Err(e) = std::fs::create_dir_all(parent) {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to create destination directory");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Failed to create destination directory",
+                &i18n::FILE_CREATE_DIRECTORY_FAILED,
             );
         }
     }
 
-    if let Err(e) = std::fs::rename(&old_physical, &new_physical) {
+    if let Err(e) = tokio::fs::rename(&old_physical, &new_physical).await {
         tracing::error!(request_id = %request_id, error = ?e, "Failed to move physical file");
         return error_resp(
             StatusCode::INTERNAL_SERVER_ERROR,
             request_id,
-            "Failed to move file",
+            &i18n::FILE_MOVE_FAILED,
         );
     }
 
@@ -810,17 +1109,34 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
     active_model.path = Set(new_path.clone());
     active_model.parent_path = Set(dest_path.clone());
     active_model.storage_path = Set(new_physical.to_string_lossy().to_string());
+    if client_modified.is_some() {
+        active_model.client_modified = Set(client_modified);
+    }
     active_model.updated_at = Set(chrono::Utc::now().naive_utc());
 
-    let updated_file = match active_model.update(&state.db).await {
+    // Only update the version we checked, a concurrent change wins
+    active_model.version = Set(file_entity.version + 1);
+    let updated_file = match file::Entity::update(active_model)
+        .filter(file::Column::Version.eq(file_entity.version))
+        .exec(&state.db)
+        .await
+    {
         Ok(f) => f,
+        Err(DbErr::RecordNotUpdated) => {
+            let _ = tokio::fs::rename(&new_physical, &old_physical).await;
+            return error_resp(
+                StatusCode::PRECONDITION_FAILED,
+                request_id,
+                &i18n::FILE_MODIFIED,
+            );
+        }
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to update database");
-            let _ = std::fs::rename(&new_physical, &old_physical);
+            let _ = tokio::fs::rename(&new_physical, &old_physical).await;
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
@@ -828,7 +1144,7 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
     // Update child paths for folders
     if file_entity.file_type == "folder" {
         if let Ok(children) =
-            super::helpers::get_folder_files_recursive(&state.db, &old_path, user_id).await
+            super::helpers::get_folder_files_recursive(&state.db, &old_path, owner_id).await
         {
             for child in children {
                 if child.id == updated_file.id {
@@ -841,10 +1157,12 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
                 } else {
                     "/".to_string()
                 };
-                let new_child_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-                    .join(new_child_path.trim_start_matches('/'));
+                let new_child_physical =
+                    physical_path(mount.as_ref(), &storage_root, owner_id, &new_child_path);
 
+                let child_version = child.version;
                 let mut child_active: file::ActiveModel = child.into();
+                child_active.version = Set(child_version + 1);
                 child_active.path = Set(new_child_path);
                 child_active.parent_path = Set(new_child_parent);
                 child_active.storage_path = Set(new_child_physical.to_string_lossy().to_string());
@@ -855,13 +1173,30 @@ pub async fn move_file(State(state): State<AppState>, request: Request) -> Respo
         }
     }
 
+    folder_sizes::adjust(&state.db, owner_id, &file_entity.parent_path, -moved_bytes).await;
+    folder_sizes::adjust(&state.db, owner_id, &dest_path, moved_bytes).await;
+
+    // Pick up the default permissions of the destination folder
+    if let Err(e) = folder_defaults::apply(&state.db, &updated_file).await {
+        tracing::warn!(request_id = %request_id, error = ?e, "Failed to apply folder default permissions");
+    }
+
+    changes::record(
+        &state.db,
+        &updated_file,
+        changes::CHANGE_MOVED,
+        Some(&old_path),
+    )
+    .await;
+
     tracing::info!(request_id = %request_id, file_id = updated_file.id, "File moved successfully");
-    do_json_detail_resp(
+    let response = do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "File moved successfully",
-        Some(updated_file),
-    )
+        &i18n::FILE_MOVED,
+        Some(&updated_file),
+    );
+    with_etag(response, &updated_file)
 }
 
 /// Copy a file or folder to a different directory
@@ -870,13 +1205,7 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
 
     let claims = match request.extensions().get::<jwt::Claims>() {
         Some(c) => c,
-        None => {
-            return error_resp(
-                StatusCode::UNAUTHORIZED,
-                request_id,
-                "Authentication required",
-            )
-        }
+        None => return error_resp(StatusCode::UNAUTHORIZED, request_id, &i18n::AUTH_REQUIRED),
     };
 
     let user_id = match claims.sub.parse::<i32>() {
@@ -885,11 +1214,13 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             )
         }
     };
 
+    let user_role = claims.role.clone();
+
     let bytes = match axum::body::to_bytes(request.into_body(), usize::MAX).await {
         Ok(b) => b,
         Err(e) => {
@@ -897,7 +1228,7 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Failed to read request",
+                &i18n::COMMON_READ_REQUEST_FAILED,
             );
         }
     };
@@ -909,33 +1240,20 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
 
     let dest_path = match file_utils::sanitize_path(&req.destination_path) {
         Ok(p) => p,
-        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, &e.to_string()),
-    };
-
-    let user_entity = match user::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "User not found"),
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            );
-        }
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
     };
 
     let has_permission = match check_permission(
         &state.db,
         user_id,
-        &user_entity.role,
+        &user_role,
         req.file_id,
         Permission::Read,
     )
@@ -947,7 +1265,7 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Permission check failed",
+                &i18n::COMMON_PERMISSION_CHECK_FAILED,
             );
         }
     };
@@ -956,28 +1274,89 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "You don't have permission to copy this file",
+            &i18n::FILE_NO_PERMISSION_COPY,
         );
     }
 
     let file_entity = match file::Entity::find_by_id(req.file_id).one(&state.db).await {
         Ok(Some(f)) => f,
-        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "File not found"),
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
         Err(e) => {
             tracing::error!(request_id = %request_id, error = ?e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
-    let storage_root = state.config.get_storage_dir();
+    // Shared content is copied next to the original unless the caller asks for their own drive
+    let owner_id = if req.to_my_drive {
+        user_id
+    } else {
+        file_entity.user_id
+    };
+
+    if let Err((status, msg)) = writable_destination(
+        &state.db,
+        owner_id,
+        user_id,
+        &user_role,
+        &dest_path,
+        &i18n::FILE_NO_PERMISSION_DESTINATION,
+        &request_id,
+    )
+    .await
+    {
+        return error_resp(status, request_id, msg);
+    }
+
+    // Only a copy within the same tree can land inside the folder being copied
+    if file_entity.file_type == "folder"
+        && file_entity.user_id == owner_id
+        && file_utils::in_subtree(&dest_path, &file_entity.path)
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_COPY_INTO_ITSELF,
+        );
+    }
+
+    let storage_root = match super::helpers::user_storage_root(&state, owner_id, &request_id).await
+    {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+
+    let copy_size = if file_entity.file_type == "folder" {
+        folder_sizes::compute(&state.db, file_entity.user_id, &file_entity.path)
+            .await
+            .map(|(size, _)| size)
+            .unwrap_or(0)
+    } else {
+        file_entity.size_bytes.unwrap_or(0)
+    };
+    if let Err(msg) = disk_space::ensure_free_space(
+        &storage_root,
+        copy_size as u64,
+        state.config.storage.reserve_bytes,
+    )
+    .await
+    {
+        tracing::warn!(request_id = %request_id, bytes = copy_size, "Copy rejected, insufficient storage");
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
+    }
+    if let Err(msg) = quota::ensure_room(&state.db, &state.config.quota, owner_id, copy_size).await
+    {
+        tracing::warn!(request_id = %request_id, bytes = copy_size, "Copy rejected, storage quota exceeded");
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
+    }
 
     let unique_filename = match super::helpers::generate_unique_filename(
         &file_entity.name,
-        user_id,
+        owner_id,
         &dest_path,
         &state.db,
     )
@@ -989,31 +1368,40 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Failed to generate unique filename",
+                &i18n::FILE_UNIQUE_NAME_FAILED,
             );
         }
     };
 
     let new_path = format!("{}/{}", dest_path.trim_end_matches('/'), unique_filename);
+    if file_utils::is_protected_path(&new_path) {
+        return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_RESERVED_PATH);
+    }
     let src_physical = PathBuf::from(&file_entity.storage_path);
-    let dest_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-        .join(new_path.trim_start_matches('/'));
+    let mount = match writable_mount(&state.db, owner_id, &new_path, &request_id).await {
+        Ok(m) => m,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+    let dest_physical = physical_path(mount.as_ref(), &storage_root, owner_id, &new_path);
 
     if let Some(parent) = dest_physical.parent() {
-        if let Err(e) = std::fs::create_dir_all(parent) {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
             tracing::error!(request_id = %request_id, error = ?e, "Failed to create destination directory");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Failed to create destination directory",
+                &i18n::FILE_CREATE_DIRECTORY_FAILED,
             );
         }
     }
 
     let copy_result = if file_entity.file_type == "folder" {
-        copy_dir_recursive(&src_physical, &dest_physical)
+        copy_folder_with_progress(&state, user_id, &file_entity, &src_physical, &dest_physical)
+            .await
     } else {
-        std::fs::copy(&src_physical, &dest_physical).map(|_| ())
+        tokio::fs::copy(&src_physical, &dest_physical)
+            .await
+            .map(|_| ())
     };
 
     if let Err(e) = copy_result {
@@ -1021,106 +1409,219 @@ pub async fn copy_file(State(state): State<AppState>, request: Request) -> Respo
         return error_resp(
             StatusCode::INTERNAL_SERVER_ERROR,
             request_id,
-            "Failed to copy file",
+            &i18n::FILE_COPY_FAILED,
         );
     }
 
-    let now = chrono::Utc::now().naive_utc();
-    let new_file = file::ActiveModel {
-        user_id: Set(user_id),
-        name: Set(unique_filename.clone()),
-        path: Set(new_path.clone()),
-        parent_path: Set(dest_path.clone()),
-        file_type: Set(file_entity.file_type.clone()),
-        mime_type: Set(file_entity.mime_type.clone()),
-        size_bytes: Set(file_entity.size_bytes),
-        storage_path: Set(dest_physical.to_string_lossy().to_string()),
-        created_at: Set(now),
-        updated_at: Set(now),
-        ..Default::default()
-    };
-
-    let created_file = match new_file.insert(&state.db).await {
+    let created_file = match record_copy(
+        &state,
+        owner_id,
+        &file_entity,
+        &unique_filename,
+        &dest_path,
+        &new_path,
+        |path| physical_path(mount.as_ref(), &storage_root, owner_id, path),
+    )
+    .await
+    {
         Ok(f) => f,
         Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to create database record");
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to create database records");
             let _ = if file_entity.file_type == "folder" {
-                std::fs::remove_dir_all(&dest_physical)
+                tokio::fs::remove_dir_all(&dest_physical).await
             } else {
-                std::fs::remove_file(&dest_physical)
+                tokio::fs::remove_file(&dest_physical).await
             };
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             );
         }
     };
 
-    // Copy child records for folders
-    if file_entity.file_type == "folder" {
-        if let Ok(children) =
-            super::helpers::get_folder_files_recursive(&state.db, &file_entity.path, user_id).await
-        {
-            for child in children {
-                if child.id == file_entity.id {
-                    continue;
-                }
-
-                let relative_path = child.path.replacen(&file_entity.path, "", 1);
-                let new_child_path = format!("{}{}", new_path, relative_path);
-                let new_child_parent = if let Some(idx) = new_child_path.rfind('/') {
-                    new_child_path[..idx].to_string()
-                } else {
-                    "/".to_string()
-                };
-                let new_child_physical = file_utils::get_user_storage_path(&storage_root, user_id)
-                    .join(new_child_path.trim_start_matches('/'));
-
-                let new_child = file::ActiveModel {
-                    user_id: Set(user_id),
-                    name: Set(child.name.clone()),
-                    path: Set(new_child_path),
-                    parent_path: Set(new_child_parent),
-                    file_type: Set(child.file_type.clone()),
-                    mime_type: Set(child.mime_type.clone()),
-                    size_bytes: Set(child.size_bytes),
-                    storage_path: Set(new_child_physical.to_string_lossy().to_string()),
-                    created_at: Set(now),
-                    updated_at: Set(now),
-                    ..Default::default()
-                };
-
-                let _ = new_child.insert(&state.db).await;
-            }
-        }
+    if let Err(e) = folder_defaults::apply(&state.db, &created_file).await {
+        tracing::warn!(request_id = %request_id, error = ?e, "Failed to apply folder default permissions");
     }
 
+    folder_sizes::adjust(&state.db, owner_id, &dest_path, copy_size).await;
+    changes::record(&state.db, &created_file, changes::CHANGE_CREATED, None).await;
+    quota::warn_if_crossed(&state.db, &state.config, owner_id, copy_size).await;
+
     tracing::info!(request_id = %request_id, file_id = created_file.id, "File copied successfully");
     do_json_detail_resp(
         StatusCode::CREATED,
         request_id,
-        "File copied successfully",
+        &i18n::FILE_COPIED,
         Some(created_file),
     )
 }
 
-/// Recursively copy a directory and all its contents
-fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
+/// Insert the records of a copy and, for folders, of everything inside it in one transaction
+/// `storage_path` maps a logical path of the copy to where it is stored
+async fn record_copy(
+    state: &AppState,
+    user_id: i32,
+    source: &file::Model,
+    name: &str,
+    parent_path: &str,
+    new_path: &str,
+    storage_path: impl Fn(&str) -> PathBuf,
+) -> Result<file::Model, DbErr> {
+    let children = if source.file_type == "folder" {
+        super::helpers::get_folder_files_recursive(&state.db, &source.path, source.user_id).await?
+    } else {
+        Vec::new()
+    };
+
+    let now = chrono::Utc::now().naive_utc();
+    let txn = state.db.begin().await?;
+
+    let created = file::ActiveModel {
+        user_id: Set(user_id),
+        name: Set(name.to_string()),
+        path: Set(new_path.to_string()),
+        parent_path: Set(parent_path.to_string()),
+        file_type: Set(source.file_type.clone()),
+        mime_type: Set(source.mime_type.clone()),
+        size_bytes: Set(source.size_bytes),
+        total_size_bytes: Set(source.total_size_bytes),
+        storage_path: Set(storage_path(new_path).to_string_lossy().to_string()),
+        client_modified: Set(source.client_modified),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let rows: Vec<file::ActiveModel> = children
+        .into_iter()
+        .filter(|child| child.id != source.id)
+        .map(|child| {
+            let relative_path = child.path.replacen(&source.path, "", 1);
+            let child_path = format!("{}{}", new_path, relative_path);
+            let child_parent = match child_path.rfind('/') {
+                Some(idx) => child_path[..idx].to_string(),
+                None => "/".to_string(),
+            };
+            file::ActiveModel {
+                user_id: Set(user_id),
+                name: Set(child.name),
+                storage_path: Set(storage_path(&child_path).to_string_lossy().to_string()),
+                path: Set(child_path),
+                parent_path: Set(child_parent),
+                file_type: Set(child.file_type),
+                mime_type: Set(child.mime_type),
+                size_bytes: Set(child.size_bytes),
+                total_size_bytes: Set(child.total_size_bytes),
+                client_modified: Set(child.client_modified),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    for chunk in rows.chunks(INSERT_CHUNK) {
+        file::Entity::insert_many(chunk.iter().cloned())
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+    Ok(created)
+}
+
+/// Copy a folder on disk as a tracked `copy` job so clients can follow its progress
+async fn copy_folder_with_progress(
+    state: &AppState,
+    user_id: i32,
+    folder: &file::Model,
+    src: &Path,
+    dst: &Path,
+) -> std::io::Result<()> {
+    let files: Vec<file::Model> =
+        super::helpers::get_folder_files_recursive(&state.db, &folder.path, folder.user_id)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|f| f.file_type == "file")
+            .collect();
+
+    let job = match jobs::create(
+        &state.db,
+        &state.events,
+        user_id,
+        jobs::KIND_COPY,
+        jobs::STATUS_RUNNING,
+        files.len() as i64,
+        super::helpers::calculate_folder_size(&files),
+    )
+    .await
+    {
+        Ok(job) => job,
+        Err(e) => {
+            // Progress is best effort, the copy itself goes ahead
+            tracing::warn!(error = ?e, "Failed to create copy job");
+            return copy_dir_parallel(src, dst, &mut |_| {}).await;
+        }
+    };
+
+    let mut progress = jobs::ProgressReporter::new(&state.events, job);
+    let result = copy_dir_parallel(src, dst, &mut |bytes| progress.advance(1, bytes as i64)).await;
+    progress
+        .finish(
+            &state.db,
+            result.as_ref().map(|_| ()).map_err(|e| e.to_string()),
+        )
+        .await;
+    result
+}
+
+/// Copy a directory and all its contents, up to `COPY_WORKERS` files at a time
+/// `on_file` is called with the size of every copied file
+async fn copy_dir_parallel(
+    src: &Path,
+    dst: &Path,
+    on_file: &mut (dyn FnMut(u64) + Send),
+) -> std::io::Result<()> {
+    let (src, dst) = (src.to_path_buf(), dst.to_path_buf());
+    let files = tokio::task::spawn_blocking(move || plan_dir_copy(&src, &dst))
+        .await
+        .map_err(std::io::Error::other)??;
+
+    let mut pending = files.into_iter();
+    let mut workers = tokio::task::JoinSet::new();
+    loop {
+        while workers.len() < COPY_WORKERS {
+            let Some((from, to)) = pending.next() else {
+                break;
+            };
+            workers.spawn_blocking(move || std::fs::copy(from, to));
+        }
+
+        match workers.join_next().await {
+            Some(copied) => on_file(copied.map_err(std::io::Error::other)??),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Create the directory tree of `src` under `dst` and list the files to copy into it
+fn plan_dir_copy(src: &Path, dst: &Path) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
     std::fs::create_dir_all(dst)?;
+    let mut files = Vec::new();
     for entry in std::fs::read_dir(src)? {
         let entry = entry?;
-        let file_type = entry.file_type()?;
-        let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
-
-        if file_type.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
+        if entry.file_type()?.is_dir() {
+            files.extend(plan_dir_copy(&entry.path(), &dst_path)?);
         } else {
-            std::fs::copy(&src_path, &dst_path)?;
+            files.push((entry.path(), dst_path));
         }
     }
-    Ok(())
+    Ok(files)
 }
 
 /// Calculate total size of selected files/folders
@@ -1137,27 +1638,14 @@ pub async fn calculate_size(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             )
         }
     };
 
-    let db = &state.db;
+    let user_role = claims.role.clone();
 
-    let user_entity = match user::Entity::find_by_id(user_id).one(db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
-        }
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
-            );
-        }
-    };
+    let db = &state.db;
 
     let mut total_size: i64 = 0;
     let mut file_count: usize = 0;
@@ -1172,15 +1660,14 @@ pub async fn calculate_size(
                 return error_resp(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     request_id,
-                    "Database error occurred",
+                    &i18n::COMMON_DATABASE_ERROR,
                 );
             }
         };
 
         // Skip files without read permission
         if file.user_id != user_id {
-            match check_permission(db, user_id, &user_entity.role, file.id, Permission::Read).await
-            {
+            match check_permission(db, user_id, &user_role, file.id, Permission::Read).await {
                 Ok(false) | Err(_) => continue,
                 Ok(true) => {}
             }
@@ -1191,19 +1678,18 @@ pub async fn calculate_size(
             file_count += 1;
         } else {
             folder_count += 1;
-            match super::helpers::get_folder_files_recursive(db, &file.path, user_id).await {
-                Ok(files) => {
-                    let size = super::helpers::calculate_folder_size(&files);
-                    let count = files.iter().filter(|f| f.file_type == "file").count();
+            // Descendants live in the folder owner's tree
+            match folder_sizes::compute(db, file.user_id, &file.path).await {
+                Ok((size, count)) => {
                     total_size += size;
-                    file_count += count;
+                    file_count += count as usize;
                 }
                 Err(e) => {
                     tracing::error!(request_id = %request_id, error = ?e, "Failed to get folder contents");
                     return error_resp(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         request_id,
-                        "Failed to calculate folder size",
+                        &i18n::FILE_SIZE_FAILED,
                     );
                 }
             }
@@ -1213,7 +1699,7 @@ pub async fn calculate_size(
     do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "Size calculated successfully",
+        &i18n::FILE_SIZE_CALCULATED,
         Some(CalculateSizeResponse {
             total_size_bytes: total_size,
             file_count,
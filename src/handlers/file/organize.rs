@@ -0,0 +1,461 @@
+use crate::{
+    config::ValidationConfig,
+    entities::{file, organize_rule},
+    models::file::{
+        OrganizePreviewItem, OrganizePreviewRequest, OrganizeRule, OrganizeRuleRequest,
+    },
+    services::organize,
+    utils::{
+        file_utils,
+        i18n::{self, Text},
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp, validation,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+
+const MAX_MIME_PREFIX_LENGTH: usize = 100;
+const MAX_PREVIEW_FILES: u64 = 1000;
+
+fn to_response(r: organize_rule::Model) -> OrganizeRule {
+    OrganizeRule {
+        id: r.id,
+        folder_path: r.folder_path,
+        extensions: r
+            .extensions
+            .map(|list| list.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+        mime_prefix: r.mime_prefix,
+        destination: r.destination,
+        position: r.position,
+        created_at: timestamp::format(r.created_at),
+    }
+}
+
+/// Folder path without a trailing slash, the root stays "/"
+pub(super) fn folder_path(path: &str) -> Result<String, String> {
+    let path = file_utils::sanitize_path(path).map_err(|e| format!("Invalid path: {}", e))?;
+    Ok(match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    })
+}
+
+/// Lowercase extensions without the dot, comma separated; `None` when the list is empty
+pub(super) fn parse_extensions(list: &[String]) -> Result<Option<String>, String> {
+    let mut extensions: Vec<String> = Vec::new();
+    for ext in list {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        if ext.is_empty() {
+            continue;
+        }
+        if !ext.chars().all(|c| c.is_alphanumeric()) {
+            return Err(format!("Invalid extension '{}'", ext));
+        }
+        if !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    Ok((!extensions.is_empty()).then(|| extensions.join(",")))
+}
+
+/// Check a rule and turn it into an unsaved record of `user_id`
+fn parse_rule(
+    req: OrganizeRuleRequest,
+    user_id: i32,
+    rules: &ValidationConfig,
+) -> Result<organize_rule::Model, Text> {
+    let source = folder_path(&req.folder_path)?;
+    let extensions = parse_extensions(&req.extensions)?;
+
+    let mime_prefix = req
+        .mime_prefix
+        .map(|m| m.trim().to_lowercase())
+        .filter(|m| !m.is_empty());
+    if mime_prefix
+        .as_ref()
+        .is_some_and(|m| m.len() > MAX_MIME_PREFIX_LENGTH)
+    {
+        return Err(Text::Catalog(&i18n::ORGANIZE_MIME_PREFIX_TOO_LONG));
+    }
+
+    let destination = req.destination.trim().to_string();
+    if !destination.starts_with('/') {
+        return Err(Text::Catalog(&i18n::ORGANIZE_INVALID_DESTINATION));
+    }
+    // Placeholders are checked with a sample date, they expand to plain digits
+    let sample = organize::expand(&destination, chrono::Utc::now().naive_utc());
+    if sample.contains(['{', '}']) {
+        return Err(Text::Catalog(&i18n::ORGANIZE_INVALID_PLACEHOLDER));
+    }
+    if folder_path(&sample)? != sample {
+        return Err(Text::Catalog(&i18n::ORGANIZE_INVALID_DESTINATION));
+    }
+    if file_utils::is_protected_path(&sample) {
+        return Err(Text::Catalog(&i18n::FILE_PROTECTED_PATH));
+    }
+    for segment in sample.split('/').filter(|s| !s.is_empty()) {
+        validation::validate_filename(segment, rules).map_err(Text::from)?;
+    }
+    if destination == source {
+        return Err(Text::Catalog(&i18n::ORGANIZE_SAME_DESTINATION));
+    }
+
+    Ok(organize_rule::Model {
+        id: 0,
+        user_id,
+        folder_path: source,
+        extensions,
+        mime_prefix,
+        destination,
+        position: 0,
+        created_at: chrono::Utc::now().naive_utc(),
+    })
+}
+
+/// List the current user's upload organize rules, in the order they are tried
+pub async fn list_organize_rules(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    match organize::for_user(&state.db, user_id).await {
+        Ok(rules) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ORGANIZE_RULES_RETRIEVED,
+            Some(rules.into_iter().map(to_response).collect::<Vec<_>>()),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Add an upload organize rule, tried after the existing ones
+pub async fn create_organize_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<OrganizeRuleRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let rule = match parse_rule(req, user_id, &state.config.validation) {
+        Ok(rule) => rule,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let existing = organize_rule::Entity::find()
+        .filter(organize_rule::Column::UserId.eq(user_id))
+        .count(&state.db)
+        .await;
+    let last: Result<Option<i32>, _> = organize_rule::Entity::find()
+        .select_only()
+        .column(organize_rule::Column::Position)
+        .filter(organize_rule::Column::UserId.eq(user_id))
+        .order_by_desc(organize_rule::Column::Position)
+        .into_tuple()
+        .one(&state.db)
+        .await;
+    let position = match (existing, last) {
+        (Ok(count), _) if count >= organize::MAX_RULES => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                format!("At most {} organize rules are allowed", organize::MAX_RULES),
+            );
+        }
+        (Ok(_), Ok(last)) => last.map_or(0, |p| p + 1),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let active = organize_rule::ActiveModel {
+        user_id: Set(user_id),
+        folder_path: Set(rule.folder_path),
+        extensions: Set(rule.extensions),
+        mime_prefix: Set(rule.mime_prefix),
+        destination: Set(rule.destination),
+        position: Set(position),
+        created_at: Set(rule.created_at),
+        ..Default::default()
+    };
+
+    match active.insert(&state.db).await {
+        Ok(r) => {
+            tracing::info!(request_id = %request_id, rule_id = r.id, "Organize rule created");
+            do_json_detail_resp(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::ORGANIZE_RULE_CREATED,
+                Some(to_response(r)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to create organize rule");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Replace the conditions and destination of an organize rule, its position is kept
+pub async fn update_organize_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<i32>,
+    Json(req): Json<OrganizeRuleRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let rule = match parse_rule(req, user_id, &state.config.validation) {
+        Ok(rule) => rule,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let existing = match organize_rule::Entity::find_by_id(rule_id)
+        .filter(organize_rule::Column::UserId.eq(user_id))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::ORGANIZE_RULE_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let mut active: organize_rule::ActiveModel = existing.into();
+    active.folder_path = Set(rule.folder_path);
+    active.extensions = Set(rule.extensions);
+    active.mime_prefix = Set(rule.mime_prefix);
+    active.destination = Set(rule.destination);
+
+    match active.update(&state.db).await {
+        Ok(r) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::ORGANIZE_RULE_UPDATED,
+            Some(to_response(r)),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to update organize rule");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Delete an organize rule, files it already filed stay where they are
+pub async fn delete_organize_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(rule_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    match organize_rule::Entity::delete_many()
+        .filter(organize_rule::Column::Id.eq(rule_id))
+        .filter(organize_rule::Column::UserId.eq(user_id))
+        .exec(&state.db)
+        .await
+    {
+        Ok(res) if res.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::ORGANIZE_RULE_NOT_FOUND,
+        ),
+        Ok(_) => do_json_detail_resp::<()>(
+            StatusCode::OK,
+            request_id,
+            &i18n::ORGANIZE_RULE_DELETED,
+            None,
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Dry run: where the files of a folder would be filed if they were uploaded now
+/// Nothing is moved, files no rule takes are left out
+pub async fn preview_organize_rules(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<OrganizePreviewRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let path = match folder_path(&req.folder_path) {
+        Ok(p) => p,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let (rules, saved) = match req.rule {
+        Some(rule) => match parse_rule(rule, user_id, &state.config.validation) {
+            // The unsaved rule is tried on the previewed folder whatever folder it names
+            Ok(rule) => (
+                vec![organize_rule::Model {
+                    folder_path: path.clone(),
+                    ..rule
+                }],
+                false,
+            ),
+            Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+        },
+        None => match organize::for_user(&state.db, user_id).await {
+            Ok(rules) => (rules, true),
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        },
+    };
+
+    let files = match file::Entity::find()
+        .filter(file::Column::UserId.eq(user_id))
+        .filter(file::Column::ParentPath.eq(&path))
+        .filter(file::Column::FileType.eq("file"))
+        .order_by_asc(file::Column::Name)
+        .limit(MAX_PREVIEW_FILES)
+        .all(&state.db)
+        .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let items: Vec<OrganizePreviewItem> = files
+        .into_iter()
+        .filter_map(|f| {
+            let time = f.client_modified.unwrap_or(f.created_at);
+            let (rule, destination) =
+                organize::destination(&rules, &path, &f.name, f.mime_type.as_deref(), time)?;
+            Some(OrganizePreviewItem {
+                file_id: f.id,
+                rule_id: saved.then_some(rule.id),
+                name: f.name,
+                destination,
+            })
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::ORGANIZE_PREVIEW_GENERATED,
+        Some(items),
+    )
+}
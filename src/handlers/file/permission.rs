@@ -1,11 +1,29 @@
 use crate::{
-    entities::{file, file_permission},
+    entities::{file, file_permission, folder_default_permission},
+    models::file::{
+        FolderDefaultPermission, FolderDefaultPermissionQuery, FolderDefaultPermissionRequest,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        folder_defaults, library, permission_cache, share_alert,
+    },
+    utils::client::ClientInfo,
+    utils::i18n,
     utils::request_id,
-    utils::response::error_resp,
+    utils::response::{do_json_detail_resp, error_resp},
+    utils::timestamp,
     AppState,
 };
-use axum::{extract::State, http::StatusCode, response::Response, Extension};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use std::collections::HashMap;
 
 /// Permission types
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -36,23 +54,22 @@ pub async fn check_permission(
         return Ok(true);
     }
 
-    let perm = file_permission::Entity::find()
-        .filter(file_permission::Column::FileId.eq(file_id))
-        .filter(file_permission::Column::UserId.eq(user_id))
-        .one(db)
-        .await?;
-
-    match perm {
-        Some(p) => {
-            let has_perm = match permission {
-                Permission::Read => p.can_read,
-                Permission::Write => p.can_write,
-                Permission::Delete => p.can_delete,
-            };
-            Ok(has_perm)
-        }
-        None => Ok(false),
+    if library::is_library(file_entity.user_id) {
+        let (read, write, delete) =
+            library::permissions(db, user_id, user_role, &file_entity.path).await?;
+        return Ok(match permission {
+            Permission::Read => read,
+            Permission::Write => write,
+            Permission::Delete => delete,
+        });
     }
+
+    let (read, write, delete) = direct_permissions(db, user_id, file_id).await?;
+    Ok(match permission {
+        Permission::Read => read,
+        Permission::Write => write,
+        Permission::Delete => delete,
+    })
 }
 
 /// Get file permissions for a user (read, write, delete)
@@ -70,20 +87,121 @@ pub async fn get_file_permissions(
         return (true, true, true);
     }
 
-    match file_permission::Entity::find()
-        .filter(file_permission::Column::FileId.eq(file_entity.id))
+    if library::is_library(file_entity.user_id) {
+        return library::permissions(db, user_id, user_role, &file_entity.path)
+            .await
+            .unwrap_or((false, false, false));
+    }
+
+    direct_permissions(db, user_id, file_entity.id)
+        .await
+        .unwrap_or((false, false, false))
+}
+
+/// Permissions for many files at once, keyed by file id
+/// Direct grants not already cached are fetched in a single query
+pub async fn get_files_permissions(
+    db: &sea_orm::DatabaseConnection,
+    user_id: i32,
+    user_role: &str,
+    files: &[file::Model],
+) -> HashMap<i32, permission_cache::Grant> {
+    let mut resolved = HashMap::with_capacity(files.len());
+    let mut uncached = Vec::new();
+
+    for f in files {
+        if user_role == "admin" || f.user_id == user_id {
+            resolved.insert(f.id, (true, true, true));
+        } else if library::is_library(f.user_id) {
+            let grant = library::permissions(db, user_id, user_role, &f.path)
+                .await
+                .unwrap_or((false, false, false));
+            resolved.insert(f.id, grant);
+        } else if let Some(grant) = permission_cache::get(user_id, f.id) {
+            resolved.insert(f.id, grant);
+        } else {
+            uncached.push(f.id);
+        }
+    }
+
+    if uncached.is_empty() {
+        return resolved;
+    }
+
+    let granted: HashMap<i32, permission_cache::Grant> = match file_permission::Entity::find()
         .filter(file_permission::Column::UserId.eq(user_id))
-        .one(db)
+        .filter(file_permission::Column::FileId.is_in(uncached.iter().copied()))
+        .all(db)
         .await
     {
-        Ok(Some(perm)) => (perm.can_read, perm.can_write, perm.can_delete),
-        _ => (false, false, false),
+        Ok(rows) => rows
+            .into_iter()
+            .map(|p| (p.file_id, (p.can_read, p.can_write, p.can_delete)))
+            .collect(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load file permissions");
+            for file_id in uncached {
+                resolved.insert(file_id, (false, false, false));
+            }
+            return resolved;
+        }
+    };
+
+    for file_id in uncached {
+        let grant = granted
+            .get(&file_id)
+            .copied()
+            .unwrap_or((false, false, false));
+        permission_cache::insert(user_id, file_id, grant);
+        resolved.insert(file_id, grant);
     }
+
+    resolved
+}
+
+/// Grants recorded for a user on some of `file_ids`, files without a record are left out
+/// Unlike `get_files_permissions` this tells a missing grant apart from one without access
+pub async fn explicit_grants(
+    db: &sea_orm::DatabaseConnection,
+    user_id: i32,
+    file_ids: impl IntoIterator<Item = i32>,
+) -> Result<HashMap<i32, permission_cache::Grant>, sea_orm::DbErr> {
+    Ok(file_permission::Entity::find()
+        .filter(file_permission::Column::UserId.eq(user_id))
+        .filter(file_permission::Column::FileId.is_in(file_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.file_id, (p.can_read, p.can_write, p.can_delete)))
+        .collect())
+}
+
+/// Permissions granted to a user on a file, served from the permission cache when possible
+async fn direct_permissions(
+    db: &sea_orm::DatabaseConnection,
+    user_id: i32,
+    file_id: i32,
+) -> Result<permission_cache::Grant, sea_orm::DbErr> {
+    if let Some(grant) = permission_cache::get(user_id, file_id) {
+        return Ok(grant);
+    }
+
+    let grant = file_permission::Entity::find()
+        .filter(file_permission::Column::FileId.eq(file_id))
+        .filter(file_permission::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .map_or((false, false, false), |p| {
+            (p.can_read, p.can_write, p.can_delete)
+        });
+    permission_cache::insert(user_id, file_id, grant);
+    Ok(grant)
 }
 
 /// Grant permission to a user for a file (admin only)
 pub async fn grant_permission(
     State(state): State<AppState>,
+    client: ClientInfo,
     Extension(claims): Extension<crate::utils::jwt::Claims>,
     body: axum::body::Bytes,
 ) -> Response {
@@ -95,35 +213,16 @@ pub async fn grant_permission(
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
-            );
-        }
-    };
-
-    // Check if admin
-    let user_entity = match crate::entities::user::Entity::find_by_id(user_id)
-        .one(&state.db)
-        .await
-    {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
-        }
-        Err(e) => {
-            tracing::error!(request_id = %request_id, error = ?e, "Database error");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Database error occurred",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    if user_entity.role != "admin" {
+    if claims.role != "admin" {
         return error_resp(
             StatusCode::FORBIDDEN,
             request_id,
-            "Only administrators can grant permissions",
+            &i18n::PERMISSION_ADMIN_ONLY_GRANT,
         );
     }
 
@@ -135,13 +234,19 @@ pub async fn grant_permission(
             return error_resp(
                 StatusCode::BAD_REQUEST,
                 request_id,
-                "Invalid request format",
+                &i18n::COMMON_INVALID_REQUEST,
             );
         }
     };
 
     // Create or update permission record
     let now = chrono::Utc::now().naive_utc();
+    let audit_details = serde_json::json!({
+        "user_id": req.user_id,
+        "can_read": req.can_read,
+        "can_write": req.can_write,
+        "can_delete": req.can_delete,
+    });
 
     // Try to find existing permission
     let existing = file_permission::Entity::find()
@@ -160,18 +265,30 @@ pub async fn grant_permission(
             active.granted_by = Set(user_id);
 
             match active.update(&state.db).await {
-                Ok(_) => crate::utils::response::do_json_detail_resp::<()>(
-                    StatusCode::OK,
-                    request_id,
-                    "Permission updated successfully",
-                    None,
-                ),
+                Ok(_) => {
+                    permission_cache::invalidate(req.user_id, req.file_id);
+                    audit::record_file(
+                        &state.db,
+                        Some(user_id),
+                        AuditEvent::PermissionGranted,
+                        &client,
+                        Some(req.file_id),
+                        Some(audit_details),
+                    )
+                    .await;
+                    crate::utils::response::do_json_detail_resp::<()>(
+                        StatusCode::OK,
+                        request_id,
+                        "Permission updated successfully",
+                        None,
+                    )
+                }
                 Err(e) => {
                     tracing::error!(request_id = %request_id, error = ?e, "Failed to update permission");
                     error_resp(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         request_id,
-                        "Database error occurred",
+                        &i18n::COMMON_DATABASE_ERROR,
                     )
                 }
             }
@@ -190,18 +307,38 @@ pub async fn grant_permission(
             };
 
             match new_perm.insert(&state.db).await {
-                Ok(_) => crate::utils::response::do_json_detail_resp::<()>(
-                    StatusCode::CREATED,
-                    request_id,
-                    "Permission granted successfully",
-                    None,
-                ),
+                Ok(_) => {
+                    permission_cache::invalidate(req.user_id, req.file_id);
+                    audit::record_file(
+                        &state.db,
+                        Some(user_id),
+                        AuditEvent::PermissionGranted,
+                        &client,
+                        Some(req.file_id),
+                        Some(audit_details),
+                    )
+                    .await;
+                    share_alert::notify_granted(
+                        &state,
+                        user_id,
+                        req.user_id,
+                        req.file_id,
+                        (req.can_read, req.can_write, req.can_delete),
+                    )
+                    .await;
+                    crate::utils::response::do_json_detail_resp::<()>(
+                        StatusCode::CREATED,
+                        request_id,
+                        "Permission granted successfully",
+                        None,
+                    )
+                }
                 Err(e) => {
                     tracing::error!(request_id = %request_id, error = ?e, "Failed to create permission");
                     error_resp(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         request_id,
-                        "Database error occurred",
+                        &i18n::COMMON_DATABASE_ERROR,
                     )
                 }
             }
@@ -211,19 +348,253 @@ pub async fn grant_permission(
             error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Database error occurred",
+                &i18n::COMMON_DATABASE_ERROR,
             )
         }
     }
 }
 
+/// Set the default permissions a user receives on everything inside a folder (admin only)
+pub async fn set_folder_default_permission(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::utils::jwt::Claims>,
+    Json(req): Json<FolderDefaultPermissionRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_DEFAULTS,
+        );
+    }
+
+    let folder = match file::Entity::find_by_id(req.folder_id).one(&state.db).await {
+        Ok(Some(f)) if f.file_type == "folder" => f,
+        Ok(Some(_)) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::PERMISSION_DEFAULTS_FOLDERS_ONLY,
+            );
+        }
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::FILE_FOLDER_NOT_FOUND,
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let now = chrono::Utc::now().naive_utc();
+    let policy = folder_default_permission::ActiveModel {
+        folder_id: Set(req.folder_id),
+        user_id: Set(req.user_id),
+        can_read: Set(req.can_read),
+        can_write: Set(req.can_write),
+        can_delete: Set(req.can_delete),
+        created_by: Set(admin_id),
+        created_at: Set(now),
+        ..Default::default()
+    };
+
+    if let Err(e) = folder_default_permission::Entity::insert(policy)
+        .on_conflict(
+            OnConflict::columns([
+                folder_default_permission::Column::FolderId,
+                folder_default_permission::Column::UserId,
+            ])
+            .update_columns([
+                folder_default_permission::Column::CanRead,
+                folder_default_permission::Column::CanWrite,
+                folder_default_permission::Column::CanDelete,
+                folder_default_permission::Column::CreatedBy,
+            ])
+            .to_owned(),
+        )
+        .exec(&state.db)
+        .await
+    {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to save folder default permission");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    if req.apply_to_existing {
+        let children = match file::Entity::find()
+            .filter(file::Column::UserId.eq(folder.user_id))
+            .filter(file::Column::ParentPath.eq(&folder.path))
+            .all(&state.db)
+            .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        };
+
+        for child in &children {
+            if let Err(e) = folder_defaults::apply(&state.db, child).await {
+                tracing::error!(request_id = %request_id, error = ?e, "Failed to apply folder default permission");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        }
+    }
+
+    tracing::info!(
+        request_id = %request_id,
+        folder_id = req.folder_id,
+        user_id = req.user_id,
+        "Folder default permission saved"
+    );
+
+    do_json_detail_resp::<()>(
+        StatusCode::OK,
+        request_id,
+        "Folder default permission saved",
+        None,
+    )
+}
+
+/// Remove a folder default permission policy (admin only)
+/// Permissions already granted through the policy are kept
+pub async fn remove_folder_default_permission(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::utils::jwt::Claims>,
+    Query(query): Query<FolderDefaultPermissionQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_DEFAULTS,
+        );
+    }
+
+    match folder_default_permission::Entity::delete_many()
+        .filter(folder_default_permission::Column::FolderId.eq(query.folder_id))
+        .filter(folder_default_permission::Column::UserId.eq(query.user_id))
+        .exec(&state.db)
+        .await
+    {
+        Ok(res) if res.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::PERMISSION_DEFAULT_NOT_FOUND,
+        ),
+        Ok(_) => do_json_detail_resp::<()>(
+            StatusCode::OK,
+            request_id,
+            "Folder default permission removed",
+            None,
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// List the default permission policies of a folder (admin only)
+pub async fn list_folder_default_permissions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::utils::jwt::Claims>,
+    Path(folder_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_DEFAULTS,
+        );
+    }
+
+    let policies = match folder_default_permission::Entity::find()
+        .filter(folder_default_permission::Column::FolderId.eq(folder_id))
+        .order_by_asc(folder_default_permission::Column::UserId)
+        .all(&state.db)
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let policies: Vec<FolderDefaultPermission> = policies
+        .into_iter()
+        .map(|p| FolderDefaultPermission {
+            folder_id: p.folder_id,
+            user_id: p.user_id,
+            can_read: p.can_read,
+            can_write: p.can_write,
+            can_delete: p.can_delete,
+            created_by: p.created_by,
+            created_at: timestamp::format(p.created_at),
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::PERMISSION_DEFAULTS_RETRIEVED,
+        Some(policies),
+    )
+}
+
 /// Revoke permission (coming soon)
 pub async fn revoke_permission(State(_state): State<AppState>) -> Response {
     let request_id = request_id::generate_request_id();
     error_resp(
         StatusCode::NOT_IMPLEMENTED,
         request_id,
-        "Revoke permission feature coming soon",
+        &i18n::COMMON_COMING_SOON_REVOKE_PERMISSION,
     )
 }
 
@@ -233,6 +604,6 @@ pub async fn list_user_permissions(State(_state): State<AppState>) -> Response {
     error_resp(
         StatusCode::NOT_IMPLEMENTED,
         request_id,
-        "List permissions feature coming soon",
+        &i18n::COMMON_COMING_SOON_LIST_PERMISSIONS,
     )
 }
@@ -0,0 +1,252 @@
+use crate::{
+    entities::{file, file_permission, share_link},
+    handlers::share::is_active,
+    models::file::{CopyPermissionsReport, CopyPermissionsRequest},
+    services::{
+        audit::{self, AuditEvent},
+        permission_cache,
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{extract::State, http::StatusCode, response::Response, Extension, Json};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+
+/// Clone the permission set (and optionally share links) of one file or folder onto another (admin only)
+/// Grants the target already has for the same user are overwritten, other grants are kept
+pub async fn copy_permissions(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CopyPermissionsRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_COPY,
+        );
+    }
+
+    if req.source_file_id == req.target_file_id {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::PERMISSION_COPY_SAME_FILE,
+        );
+    }
+
+    let (source, target) = match (
+        file::Entity::find_by_id(req.source_file_id)
+            .one(&state.db)
+            .await,
+        file::Entity::find_by_id(req.target_file_id)
+            .one(&state.db)
+            .await,
+    ) {
+        (Ok(Some(s)), Ok(Some(t))) => (s, t),
+        (Ok(None), _) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::PERMISSION_COPY_SOURCE_NOT_FOUND,
+            );
+        }
+        (_, Ok(None)) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::PERMISSION_COPY_TARGET_NOT_FOUND,
+            );
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if req.include_share_links && target.file_type != "file" {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::PERMISSION_COPY_SHARE_TO_FOLDER,
+        );
+    }
+
+    let txn = match state.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to start transaction");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let report = match copy_all(&txn, &source, &target, req.include_share_links, admin_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Permission copy failed, rolled back");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if let Err(e) = txn.commit().await {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to commit permission copy");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+    permission_cache::invalidate_file(target.id);
+
+    audit::record_file(
+        &state.db,
+        Some(admin_id),
+        AuditEvent::PermissionsCopied,
+        &client,
+        Some(target.id),
+        Some(serde_json::json!({
+            "source_file_id": source.id,
+            "permissions_copied": report.permissions_copied,
+            "share_links_copied": report.share_links_copied,
+        })),
+    )
+    .await;
+
+    tracing::info!(
+        request_id = %request_id,
+        source_file_id = source.id,
+        target_file_id = target.id,
+        permissions_copied = report.permissions_copied,
+        share_links_copied = report.share_links_copied,
+        "Permissions copied"
+    );
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::PERMISSION_COPIED,
+        Some(report),
+    )
+}
+
+async fn copy_all(
+    txn: &DatabaseTransaction,
+    source: &file::Model,
+    target: &file::Model,
+    include_share_links: bool,
+    admin_id: i32,
+) -> Result<CopyPermissionsReport, DbErr> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut report = CopyPermissionsReport {
+        source_file_id: source.id,
+        target_file_id: target.id,
+        permissions_copied: 0,
+        share_links_copied: 0,
+    };
+
+    let permissions = file_permission::Entity::find()
+        .filter(file_permission::Column::FileId.eq(source.id))
+        .all(txn)
+        .await?;
+
+    for perm in permissions {
+        // The target's owner already has full access
+        if perm.user_id == target.user_id {
+            continue;
+        }
+
+        let existing = file_permission::Entity::find()
+            .filter(file_permission::Column::FileId.eq(target.id))
+            .filter(file_permission::Column::UserId.eq(perm.user_id))
+            .one(txn)
+            .await?;
+
+        match existing {
+            Some(existing) => {
+                let mut active: file_permission::ActiveModel = existing.into();
+                active.can_read = Set(perm.can_read);
+                active.can_write = Set(perm.can_write);
+                active.can_delete = Set(perm.can_delete);
+                active.granted_by = Set(admin_id);
+                active.update(txn).await?;
+            }
+            None => {
+                file_permission::ActiveModel {
+                    file_id: Set(target.id),
+                    user_id: Set(perm.user_id),
+                    can_read: Set(perm.can_read),
+                    can_write: Set(perm.can_write),
+                    can_delete: Set(perm.can_delete),
+                    granted_by: Set(admin_id),
+                    created_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(txn)
+                .await?;
+            }
+        }
+        report.permissions_copied += 1;
+    }
+
+    if include_share_links {
+        let links = share_link::Entity::find()
+            .filter(share_link::Column::FileId.eq(source.id))
+            .all(txn)
+            .await?;
+
+        // Only links that still work are worth cloning, each copy gets a fresh token and counter
+        for link in links.into_iter().filter(|l| is_active(l, now)) {
+            share_link::ActiveModel {
+                token: Set(uuid::Uuid::new_v4().simple().to_string()),
+                file_id: Set(target.id),
+                created_by: Set(link.created_by),
+                max_downloads: Set(link.max_downloads),
+                download_count: Set(0),
+                expires_at: Set(link.expires_at),
+                revoked_at: Set(None),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+            report.share_links_copied += 1;
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,500 @@
+use crate::{
+    entities::{file, file_permission, permission_template, user},
+    models::file::{
+        BulkGrantReport, BulkGrantRequest, BulkGrantResult, CreatePermissionTemplateRequest,
+        PermissionTemplate,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        permission_cache, share_alert,
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use std::collections::HashSet;
+
+const MAX_TEMPLATE_NAME_LENGTH: usize = 100;
+const MAX_BULK_GRANT_PAIRS: usize = 5000;
+
+/// Create a named permission template (admin only)
+pub async fn create_permission_template(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CreatePermissionTemplateRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_TEMPLATES,
+        );
+    }
+
+    let name = req.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_TEMPLATE_NAME_LENGTH {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "Template name must be between 1 and {} characters",
+                MAX_TEMPLATE_NAME_LENGTH
+            ),
+        );
+    }
+
+    match permission_template::Entity::find()
+        .filter(permission_template::Column::Name.eq(&name))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(_)) => {
+            return error_resp(
+                StatusCode::CONFLICT,
+                request_id,
+                &i18n::PERMISSION_TEMPLATE_EXISTS,
+            );
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let template = permission_template::ActiveModel {
+        name: Set(name),
+        description: Set(req.description.filter(|d| !d.trim().is_empty())),
+        can_read: Set(req.can_read),
+        can_write: Set(req.can_write),
+        can_delete: Set(req.can_delete),
+        created_by: Set(admin_id),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    match template.insert(&state.db).await {
+        Ok(t) => {
+            tracing::info!(request_id = %request_id, template_id = t.id, name = %t.name, "Permission template created");
+            do_json_detail_resp(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::PERMISSION_TEMPLATE_CREATED,
+                Some(to_response(t)),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to create permission template");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// List all permission templates (admin only)
+pub async fn list_permission_templates(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_TEMPLATES,
+        );
+    }
+
+    match permission_template::Entity::find()
+        .order_by_asc(permission_template::Column::Name)
+        .all(&state.db)
+        .await
+    {
+        Ok(templates) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::PERMISSION_TEMPLATES_RETRIEVED,
+            Some(
+                templates
+                    .into_iter()
+                    .map(to_response)
+                    .collect::<Vec<PermissionTemplate>>(),
+            ),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Delete a permission template (admin only)
+/// Permissions already granted from the template are kept
+pub async fn delete_permission_template(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(template_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_TEMPLATES,
+        );
+    }
+
+    match permission_template::Entity::delete_by_id(template_id)
+        .exec(&state.db)
+        .await
+    {
+        Ok(res) if res.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::PERMISSION_TEMPLATE_NOT_FOUND,
+        ),
+        Ok(_) => do_json_detail_resp::<()>(
+            StatusCode::OK,
+            request_id,
+            "Permission template deleted",
+            None,
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Apply a permission template to many files and users at once (admin only)
+/// All grants are written in a single transaction, unknown files or users are skipped
+pub async fn bulk_grant_permissions(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<BulkGrantRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::PERMISSION_ADMIN_ONLY_GRANT,
+        );
+    }
+
+    let file_ids = dedup(&req.file_ids);
+    let user_ids = dedup(&req.user_ids);
+
+    if file_ids.is_empty() || user_ids.is_empty() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::PERMISSION_BULK_GRANT_EMPTY,
+        );
+    }
+
+    if file_ids.len() * user_ids.len() > MAX_BULK_GRANT_PAIRS {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "Too many grants in one request (maximum {} file/user pairs)",
+                MAX_BULK_GRANT_PAIRS
+            ),
+        );
+    }
+
+    let template = match permission_template::Entity::find_by_id(req.template_id)
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::PERMISSION_TEMPLATE_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let txn = match state.db.begin().await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to start transaction");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let report = match apply_template(&txn, &template, &file_ids, &user_ids, admin_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            // Dropping the transaction rolls back any grants written so far
+            tracing::error!(request_id = %request_id, error = ?e, "Bulk grant failed, rolled back");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if let Err(e) = txn.commit().await {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to commit bulk grant");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+    for result in &report.results {
+        permission_cache::invalidate(result.user_id, result.file_id);
+    }
+
+    // One audit entry per file so the log can be searched by file
+    for &file_id in &file_ids {
+        let user_ids: Vec<i32> = report
+            .results
+            .iter()
+            .filter(|r| r.file_id == file_id && r.status != "skipped")
+            .map(|r| r.user_id)
+            .collect();
+        if user_ids.is_empty() {
+            continue;
+        }
+        audit::record_file(
+            &state.db,
+            Some(admin_id),
+            AuditEvent::PermissionGranted,
+            &client,
+            Some(file_id),
+            Some(serde_json::json!({
+                "template_id": template.id,
+                "user_ids": user_ids,
+            })),
+        )
+        .await;
+    }
+
+    // Only new grants are announced, sent in the background since a batch can be large
+    let granted: Vec<(i32, i32)> = report
+        .results
+        .iter()
+        .filter(|r| r.status == "granted")
+        .map(|r| (r.user_id, r.file_id))
+        .collect();
+    if !granted.is_empty() {
+        let state = state.clone();
+        let flags = (template.can_read, template.can_write, template.can_delete);
+        tokio::spawn(async move {
+            for (user_id, file_id) in granted {
+                share_alert::notify_granted(&state, admin_id, user_id, file_id, flags).await;
+            }
+        });
+    }
+
+    tracing::info!(
+        request_id = %request_id,
+        template_id = template.id,
+        granted = report.granted,
+        updated = report.updated,
+        skipped = report.skipped,
+        "Bulk grant completed"
+    );
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::PERMISSION_BULK_GRANT_COMPLETED,
+        Some(report),
+    )
+}
+
+/// Write the template's flags for every file/user pair inside the transaction
+async fn apply_template(
+    txn: &DatabaseTransaction,
+    template: &permission_template::Model,
+    file_ids: &[i32],
+    user_ids: &[i32],
+    admin_id: i32,
+) -> Result<BulkGrantReport, DbErr> {
+    let existing_files: HashSet<i32> = file::Entity::find()
+        .filter(file::Column::Id.is_in(file_ids.iter().copied()))
+        .all(txn)
+        .await?
+        .into_iter()
+        .map(|f| f.id)
+        .collect();
+    let existing_users: HashSet<i32> = user::Entity::find()
+        .filter(user::Column::Id.is_in(user_ids.iter().copied()))
+        .all(txn)
+        .await?
+        .into_iter()
+        .map(|u| u.id)
+        .collect();
+
+    let mut report = BulkGrantReport {
+        template_id: template.id,
+        granted: 0,
+        updated: 0,
+        skipped: 0,
+        results: Vec::with_capacity(file_ids.len() * user_ids.len()),
+    };
+    let now = chrono::Utc::now().naive_utc();
+
+    for &file_id in file_ids {
+        for &user_id in user_ids {
+            let reason = if !existing_files.contains(&file_id) {
+                Some("File not found")
+            } else if !existing_users.contains(&user_id) {
+                Some("User not found")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                report.skipped += 1;
+                report.results.push(BulkGrantResult {
+                    file_id,
+                    user_id,
+                    status: "skipped".to_string(),
+                    reason: Some(reason.to_string()),
+                });
+                continue;
+            }
+
+            let existing = file_permission::Entity::find()
+                .filter(file_permission::Column::FileId.eq(file_id))
+                .filter(file_permission::Column::UserId.eq(user_id))
+                .one(txn)
+                .await?;
+
+            let status = match existing {
+                Some(perm) => {
+                    let mut active: file_permission::ActiveModel = perm.into();
+                    active.can_read = Set(template.can_read);
+                    active.can_write = Set(template.can_write);
+                    active.can_delete = Set(template.can_delete);
+                    active.granted_by = Set(admin_id);
+                    active.update(txn).await?;
+                    report.updated += 1;
+                    "updated"
+                }
+                None => {
+                    file_permission::ActiveModel {
+                        file_id: Set(file_id),
+                        user_id: Set(user_id),
+                        can_read: Set(template.can_read),
+                        can_write: Set(template.can_write),
+                        can_delete: Set(template.can_delete),
+                        granted_by: Set(admin_id),
+                        created_at: Set(now),
+                        ..Default::default()
+                    }
+                    .insert(txn)
+                    .await?;
+                    report.granted += 1;
+                    "granted"
+                }
+            };
+
+            report.results.push(BulkGrantResult {
+                file_id,
+                user_id,
+                status: status.to_string(),
+                reason: None,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Drop duplicate IDs while keeping the caller's order
+fn dedup(ids: &[i32]) -> Vec<i32> {
+    let mut seen = HashSet::new();
+    ids.iter().copied().filter(|id| seen.insert(*id)).collect()
+}
+
+fn to_response(t: permission_template::Model) -> PermissionTemplate {
+    PermissionTemplate {
+        id: t.id,
+        name: t.name,
+        description: t.description,
+        can_read: t.can_read,
+        can_write: t.can_write,
+        can_delete: t.can_delete,
+        created_by: t.created_by,
+        created_at: timestamp::format(t.created_at),
+    }
+}
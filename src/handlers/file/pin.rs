@@ -0,0 +1,106 @@
+use crate::{
+    entities::file,
+    models::file::{FilePinResponse, SetPinnedRequest},
+    services::changes,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+use super::permission::get_file_permissions;
+
+/// Pin or unpin a file or folder so sync clients keep it available offline
+/// The content and its version are unchanged, the feed reports a pinned or unpinned change
+pub async fn set_file_pinned(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(payload): Json<SetPinnedRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let file_entity = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let (can_read, can_write, _) =
+        get_file_permissions(&state.db, user_id, &claims.role, &file_entity).await;
+    if !can_read {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
+    }
+    if !can_write {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_NO_WRITE_PERMISSION,
+        );
+    }
+
+    let message = if payload.pinned {
+        &i18n::FILE_PINNED
+    } else {
+        &i18n::FILE_UNPINNED
+    };
+    let response = FilePinResponse {
+        file_id,
+        pinned: payload.pinned,
+    };
+    if file_entity.pinned == payload.pinned {
+        return do_json_detail_resp(StatusCode::OK, request_id, message, Some(response));
+    }
+
+    let mut active: file::ActiveModel = file_entity.into();
+    active.pinned = Set(payload.pinned);
+    let updated = match active.update(&state.db).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to update pin state");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let change = if updated.pinned {
+        changes::CHANGE_PINNED
+    } else {
+        changes::CHANGE_UNPINNED
+    };
+    changes::record(&state.db, &updated, change, None).await;
+
+    tracing::info!(request_id = %request_id, file_id = file_id, pinned = updated.pinned, "Pin state updated");
+    do_json_detail_resp(StatusCode::OK, request_id, message, Some(response))
+}
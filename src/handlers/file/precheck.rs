@@ -0,0 +1,228 @@
+use crate::{
+    entities::file,
+    models::file::{PrecheckResult, UploadPrecheckRequest, UploadPrecheckResponse},
+    services::{disk_space, quota},
+    utils::{
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+
+/// Files one pre-check may cover
+const MAX_PRECHECK_FILES: usize = 1000;
+
+/// Paths or hashes per IN query, below SQLite's variable limit
+const LOOKUP_CHUNK: usize = 500;
+
+/// A requested file after normalization, `path` holds the reason it cannot be checked
+struct Planned {
+    requested: String,
+    path: Result<String, String>,
+    size: i64,
+    hash: Option<String>,
+}
+
+/// Report for each file of a planned upload whether it conflicts, fits the free space and quota,
+/// or can be copied from content already stored, without transferring anything
+pub async fn precheck_upload(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UploadPrecheckRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if payload.files.len() > MAX_PRECHECK_FILES {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "At most {} files can be checked at once",
+                MAX_PRECHECK_FILES
+            ),
+        );
+    }
+
+    // Normalize first so the lookups below can be batched
+    let files: Vec<Planned> = payload
+        .files
+        .into_iter()
+        .map(|f| {
+            let path = file_utils::sanitize_path(&f.path)
+                .map_err(|e| format!("Invalid path: {}", e))
+                .and_then(|p| match p.as_str() {
+                    "/" => Err("Invalid path: missing file name".to_string()),
+                    _ if f.size < 0 => Err("Invalid file size".to_string()),
+                    _ => Ok(p),
+                });
+            let hash = f
+                .hash
+                .map(|h| h.trim().to_lowercase())
+                .filter(|h| !h.is_empty());
+            Planned {
+                requested: f.path,
+                path,
+                size: f.size,
+                hash,
+            }
+        })
+        .collect();
+
+    let paths: Vec<String> = files
+        .iter()
+        .filter_map(|f| f.path.as_ref().ok().cloned())
+        .collect();
+    let hashes: Vec<String> = files.iter().filter_map(|f| f.hash.clone()).collect();
+
+    let lookups = async {
+        let existing = find_by(&state.db, user_id, file::Column::Path, &paths).await?;
+        let stored = find_by(&state.db, user_id, file::Column::FileHash, &hashes).await?;
+        Ok::<_, DbErr>((existing, stored))
+    };
+    let (existing, stored) = match lookups.await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+    let existing: HashMap<&str, &file::Model> =
+        existing.iter().map(|f| (f.path.as_str(), f)).collect();
+    let mut by_hash: HashMap<&str, &file::Model> = HashMap::new();
+    for f in stored.iter().filter(|f| f.file_type == "file") {
+        if let Some(hash) = f.file_hash.as_deref() {
+            by_hash.entry(hash).or_insert(f);
+        }
+    }
+
+    let storage_root = match super::helpers::user_storage_root(&state, user_id, &request_id).await {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+    let reserve = state.config.storage.reserve_bytes;
+    let available_bytes = disk_space::available_space(&storage_root)
+        .await
+        .map(|a| a.saturating_sub(reserve));
+    // The quota of the account counts like the free space of its volume
+    let quota_bytes = match quota::remaining_bytes(&state.db, &state.config.quota, user_id).await {
+        Ok(remaining) => remaining,
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to measure quota usage");
+            None
+        }
+    };
+    let available_bytes = match (available_bytes, quota_bytes) {
+        (Some(disk), Some(quota)) => Some(disk.min(quota)),
+        (disk, quota) => disk.or(quota),
+    };
+    let role_limit = state.config.server.max_upload_size_for(&claims.role);
+
+    let mut upload_bytes: i64 = 0;
+    let mut results = Vec::with_capacity(files.len());
+    for planned in files {
+        let path = match planned.path {
+            Ok(p) => p,
+            Err(error) => {
+                results.push(PrecheckResult {
+                    path: planned.requested,
+                    conflict: false,
+                    identical: false,
+                    existing_file_id: None,
+                    exceeds_quota: false,
+                    instant_upload: false,
+                    source_file_id: None,
+                    error: Some(error),
+                });
+                continue;
+            }
+        };
+        let hash = planned.hash.as_deref();
+
+        let current = existing.get(path.as_str()).copied();
+        let identical = current.is_some_and(|f| {
+            f.file_type == "file" && hash.is_some() && f.file_hash.as_deref() == hash
+        });
+        let source = match hash {
+            Some(h) if !identical => by_hash.get(h).copied(),
+            _ => None,
+        };
+
+        // Files that will not be uploaded leave their space to the ones after them
+        let mut exceeds_quota = planned.size > crate::constants::MAX_FILE_SIZE_BYTES
+            || role_limit.is_some_and(|limit| planned.size as u64 > limit);
+        if !exceeds_quota && !identical && source.is_none() {
+            exceeds_quota =
+                available_bytes.is_some_and(|a| (upload_bytes + planned.size) as u64 > a);
+            if !exceeds_quota {
+                upload_bytes += planned.size;
+            }
+        }
+
+        results.push(PrecheckResult {
+            path,
+            conflict: current.is_some() && !identical,
+            identical,
+            existing_file_id: current.map(|f| f.id),
+            exceeds_quota,
+            instant_upload: source.is_some(),
+            source_file_id: source.map(|f| f.id),
+            error: None,
+        });
+    }
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::UPLOAD_PRECHECK_COMPLETED,
+        Some(UploadPrecheckResponse {
+            results,
+            upload_bytes,
+            available_bytes,
+        }),
+    )
+}
+
+/// Files in the user's tree whose `column` is one of `values`
+async fn find_by(
+    db: &DatabaseConnection,
+    user_id: i32,
+    column: file::Column,
+    values: &[String],
+) -> Result<Vec<file::Model>, DbErr> {
+    let mut found = Vec::new();
+    for chunk in values.chunks(LOOKUP_CHUNK) {
+        found.extend(
+            file::Entity::find()
+                .filter(file::Column::UserId.eq(user_id))
+                .filter(column.is_in(chunk.iter().cloned()))
+                .all(db)
+                .await?,
+        );
+    }
+    Ok(found)
+}
@@ -0,0 +1,266 @@
+use crate::{
+    entities::{custom_metadata, file, file_label},
+    models::file::FileSearchQuery,
+    services::search::SearchBackend,
+    utils::{
+        file_utils,
+        i18n::{self, Text},
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        search_query::{self, Comparison, Filter, Term},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use chrono::NaiveDate;
+use sea_orm::{
+    sea_query::Query as SubQuery, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+
+use super::helpers::build_file_items;
+
+const DEFAULT_SEARCH_LIMIT: u64 = 100;
+const MAX_SEARCH_LIMIT: u64 = 500;
+
+/// Search the caller's files with the query language in `q` and the single-field parameters
+/// All filters must match, most recently changed files come first unless a search engine ranks them
+pub async fn search_files(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FileSearchQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let filters = match collect_filters(&query) {
+        Ok(f) if f.is_empty() => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::SEARCH_CRITERIA_REQUIRED,
+            );
+        }
+        Ok(f) => f,
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+    };
+
+    // Name terms go to the search engine when there is one, the rest stays in the database
+    let ranked = match &state.search {
+        Some(backend) => rank_by_name(backend.as_ref(), &filters, user_id, &request_id).await,
+        None => None,
+    };
+
+    let mut condition = Condition::all().add(file::Column::UserId.eq(user_id));
+    for filter in &filters {
+        if ranked.is_some() && !filter.negated && matches!(filter.term, Term::Name(_)) {
+            continue;
+        }
+        let term = match term_condition(&filter.term, user_id) {
+            Ok(c) => c,
+            Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+        };
+        condition = condition.add(if filter.negated { term.not() } else { term });
+    }
+    if let Some(ids) = &ranked {
+        condition = condition.add(file::Column::Id.is_in(ids.iter().copied()));
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let mut select = file::Entity::find()
+        .filter(condition)
+        .order_by_desc(file::Column::UpdatedAt);
+    if ranked.is_none() {
+        select = select.limit(limit);
+    }
+    let files = match select.all(&state.db).await {
+        Ok(mut files) => {
+            if let Some(ids) = &ranked {
+                files.sort_by_key(|f| ids.iter().position(|id| *id == f.id));
+                files.truncate(limit as usize);
+            }
+            files
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to search files");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let items = build_file_items(&state.db, files, user_id, &claims.role, &request_id).await;
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::SEARCH_COMPLETED,
+        Some(items),
+    )
+}
+
+/// File IDs best matching the name terms according to the search engine, best first
+/// `None` without name terms or when the engine fails, the database search is used then
+async fn rank_by_name(
+    backend: &dyn SearchBackend,
+    filters: &[Filter],
+    user_id: i32,
+    request_id: &str,
+) -> Option<Vec<i32>> {
+    let names: Vec<&str> = filters
+        .iter()
+        .filter(|f| !f.negated)
+        .filter_map(|f| match &f.term {
+            Term::Name(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+
+    match backend
+        .search(user_id, &names.join(" "), MAX_SEARCH_LIMIT as usize)
+        .await
+    {
+        Ok(ids) => Some(ids),
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = %e, "Search engine failed, using the database");
+            None
+        }
+    }
+}
+
+/// Filters of the `q` query followed by those of the single-field parameters
+fn collect_filters(query: &FileSearchQuery) -> Result<Vec<Filter>, String> {
+    let mut filters = match &query.q {
+        Some(q) => search_query::parse(&file_utils::normalize_name(q))?,
+        None => Vec::new(),
+    };
+
+    let param = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let mut add = |term| {
+        filters.push(Filter {
+            negated: false,
+            term,
+        })
+    };
+
+    if let Some(name) = param(&query.name) {
+        add(Term::Name(file_utils::normalize_name(&name)));
+    }
+    if let Some(label) = param(&query.label) {
+        add(Term::Tag(label.to_lowercase()));
+    }
+    match (param(&query.meta_key), &query.meta_value) {
+        (Some(key), value) => add(Term::Meta(key, value.clone())),
+        (None, Some(_)) => return Err("meta_value requires meta_key".to_string()),
+        (None, None) => {}
+    }
+
+    Ok(filters)
+}
+
+/// Condition matching the caller's files the query language in `q` selects, without a search engine
+pub(super) fn query_condition(q: &str, user_id: i32) -> Result<Condition, Text> {
+    let filters = search_query::parse(&file_utils::normalize_name(q))?;
+    if filters.is_empty() {
+        return Err(Text::Catalog(&i18n::SEARCH_QUERY_EMPTY));
+    }
+
+    let mut condition = Condition::all().add(file::Column::UserId.eq(user_id));
+    for filter in &filters {
+        let term = term_condition(&filter.term, user_id)?;
+        condition = condition.add(if filter.negated { term.not() } else { term });
+    }
+    Ok(condition)
+}
+
+fn term_condition(term: &Term, user_id: i32) -> Result<Condition, String> {
+    let condition = Condition::all();
+    Ok(match term {
+        Term::Name(name) => condition.add(file::Column::Name.contains(name)),
+        Term::Type(t) => match t.as_str() {
+            "file" | "folder" => condition.add(file::Column::FileType.eq(t)),
+            mime if mime.contains('/') => condition.add(file::Column::MimeType.eq(mime)),
+            class => condition.add(file::Column::MimeType.starts_with(format!("{}/", class))),
+        },
+        Term::Extension(ext) => condition.add(file::Column::Name.ends_with(format!(".{}", ext))),
+        Term::Size(cmp, size) => condition.add(match cmp {
+            Comparison::Lt => file::Column::SizeBytes.lt(*size),
+            Comparison::Le => file::Column::SizeBytes.lte(*size),
+            Comparison::Eq => file::Column::SizeBytes.eq(*size),
+            Comparison::Ge => file::Column::SizeBytes.gte(*size),
+            Comparison::Gt => file::Column::SizeBytes.gt(*size),
+        }),
+        Term::Modified(cmp, date) => date_condition(file::Column::UpdatedAt, *cmp, *date),
+        Term::Created(cmp, date) => date_condition(file::Column::CreatedAt, *cmp, *date),
+        Term::Tag(label) => condition.add(
+            file::Column::Id.in_subquery(
+                SubQuery::select()
+                    .column(file_label::Column::FileId)
+                    .from(file_label::Entity)
+                    .and_where(file_label::Column::UserId.eq(user_id))
+                    .and_where(file_label::Column::Label.eq(label))
+                    .to_owned(),
+            ),
+        ),
+        Term::Meta(key, value) => {
+            let mut entries = SubQuery::select()
+                .column(custom_metadata::Column::FileId)
+                .from(custom_metadata::Entity)
+                .and_where(custom_metadata::Column::UserId.eq(user_id))
+                .and_where(custom_metadata::Column::Key.eq(key))
+                .to_owned();
+            if let Some(value) = value {
+                entries.and_where(custom_metadata::Column::Value.eq(value));
+            }
+            condition.add(file::Column::Id.in_subquery(entries))
+        }
+        Term::In(path) => {
+            let path = file_utils::sanitize_path(path).map_err(|e| e.to_string())?;
+            let prefix = format!("{}/", path.trim_end_matches('/'));
+            condition.add(file::Column::Path.starts_with(prefix))
+        }
+    })
+}
+
+/// Dates match whole days, `>2024-01-01` starts on January 2nd
+fn date_condition(column: file::Column, cmp: Comparison, date: NaiveDate) -> Condition {
+    let start = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let next = start + chrono::Duration::days(1);
+    match cmp {
+        Comparison::Lt => Condition::all().add(column.lt(start)),
+        Comparison::Le => Condition::all().add(column.lt(next)),
+        Comparison::Eq => Condition::all().add(column.gte(start)).add(column.lt(next)),
+        Comparison::Ge => Condition::all().add(column.gte(start)),
+        Comparison::Gt => Condition::all().add(column.gte(next)),
+    }
+}
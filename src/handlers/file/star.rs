@@ -0,0 +1,87 @@
+use crate::{
+    entities::file,
+    models::file::{FileStarResponse, SetStarredRequest},
+    services::stars,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::EntityTrait;
+
+use super::permission::get_file_permissions;
+
+/// Star or unstar a file or folder for the current user
+/// Only needs read access, other users don't see the star
+pub async fn set_file_starred(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(payload): Json<SetStarredRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let file_entity = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let (can_read, _, _) =
+        get_file_permissions(&state.db, user_id, &claims.role, &file_entity).await;
+    if !can_read {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
+    }
+
+    if let Err(e) = stars::set(&state.db, user_id, file_id, payload.starred).await {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to update star");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    let message = if payload.starred {
+        &i18n::FILE_STARRED
+    } else {
+        &i18n::FILE_UNSTARRED
+    };
+    tracing::info!(request_id = %request_id, file_id = file_id, starred = payload.starred, "Star updated");
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        message,
+        Some(FileStarResponse {
+            file_id,
+            starred: payload.starred,
+        }),
+    )
+}
@@ -0,0 +1,152 @@
+use crate::{
+    entities::file,
+    models::file::{FileStat, FileStatQuery, FileType},
+    services::{custom_metadata, file_stats, processing},
+    utils::{
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use super::{helpers::with_etag, permission::get_file_permissions};
+
+/// Get size, hash, MIME type, timestamps and effective permissions of one file in a single call
+pub async fn stat_file(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FileStatQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let lookup = match (query.file_id, query.path.as_deref()) {
+        (Some(file_id), _) => file::Entity::find_by_id(file_id).one(&state.db).await,
+        (None, Some(path)) => {
+            let path = match file_utils::sanitize_path(path) {
+                Ok(p) => p,
+                Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+            };
+            file::Entity::find()
+                .filter(file::Column::UserId.eq(query.owner_id.unwrap_or(user_id)))
+                .filter(file::Column::Path.eq(path.trim_end_matches('/')))
+                .one(&state.db)
+                .await
+        }
+        (None, None) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::FILE_ID_OR_PATH_REQUIRED,
+            );
+        }
+    };
+
+    let file_entity = match lookup {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let (can_read, can_write, can_delete) =
+        get_file_permissions(&state.db, user_id, &claims.role, &file_entity).await;
+
+    // Unreadable files look the same as missing ones
+    if !can_read {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
+    }
+
+    let stats = match file_stats::for_files(&state.db, &[file_entity.id]).await {
+        Ok(mut s) => s.remove(&file_entity.id),
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load file stats");
+            None
+        }
+    };
+
+    let metadata = match custom_metadata::for_files(&state.db, &[file_entity.id]).await {
+        Ok(mut m) => m.remove(&file_entity.id).unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load file metadata");
+            Default::default()
+        }
+    };
+
+    let (labels, derived_metadata) = match processing::for_file(&state.db, file_entity.id).await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = ?e, "Failed to load file labels");
+            Default::default()
+        }
+    };
+
+    let file_type = if file_entity.file_type == "folder" {
+        FileType::Folder
+    } else {
+        FileType::File
+    };
+
+    let etag_source = file_entity.clone();
+    let stat = FileStat {
+        id: file_entity.id,
+        owner_id: file_entity.user_id,
+        name: file_entity.name,
+        path: file_entity.path,
+        parent_path: file_entity.parent_path,
+        file_type,
+        size_bytes: file_entity.size_bytes,
+        mime_type: file_entity.mime_type,
+        file_hash: file_entity.file_hash,
+        ref_count: file_entity.ref_count,
+        created_at: timestamp::format(file_entity.created_at),
+        updated_at: timestamp::format(file_entity.updated_at),
+        client_modified: file_entity.client_modified.map(timestamp::format),
+        version: file_entity.version,
+        download_count: stats.as_ref().map_or(0, |s| s.download_count),
+        last_downloaded_at: stats
+            .and_then(|s| s.last_downloaded_at)
+            .map(timestamp::format),
+        metadata,
+        labels,
+        derived_metadata,
+        can_read,
+        can_write,
+        can_delete,
+        is_owner: file_entity.user_id == user_id,
+    };
+
+    let response = do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_STAT_RETRIEVED,
+        Some(stat),
+    );
+    with_etag(response, &etag_source)
+}
@@ -0,0 +1,107 @@
+use crate::{
+    entities::file,
+    models::file::FolderStyle,
+    services::folder_styles,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        validation,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::EntityTrait;
+
+use super::permission::get_file_permissions;
+
+/// Set the color and icon a folder is shown with for the current user
+/// Only needs read access, other users keep seeing the folder unchanged
+pub async fn set_folder_style(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(payload): Json<FolderStyle>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let file_entity = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let (can_read, _, _) =
+        get_file_permissions(&state.db, user_id, &claims.role, &file_entity).await;
+    if !can_read {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND);
+    }
+    if file_entity.file_type != "folder" {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_STYLE_FOLDERS_ONLY,
+        );
+    }
+
+    let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let style = FolderStyle {
+        color: non_empty(payload.color).map(|c| c.to_ascii_lowercase()),
+        icon: non_empty(payload.icon),
+    };
+
+    let checked = style
+        .color
+        .as_deref()
+        .map_or(Ok(()), validation::validate_hex_color)
+        .and_then(|_| {
+            style
+                .icon
+                .as_deref()
+                .map_or(Ok(()), validation::validate_icon)
+        });
+    if let Err(e) = checked {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+    }
+
+    if let Err(e) = folder_styles::set(&state.db, user_id, file_id, &style).await {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to update folder style");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    tracing::info!(request_id = %request_id, file_id = file_id, "Folder style updated");
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_STYLE_UPDATED,
+        Some(style),
+    )
+}
@@ -0,0 +1,211 @@
+use crate::{
+    entities::file,
+    models::file::{
+        FileItem, FileTreeNode, FileTreeQuery, FileTreeResponse, FlatFileItem, FlatFileListResponse,
+    },
+    utils::{
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use std::collections::HashMap;
+
+use super::helpers::build_file_items;
+
+const DEFAULT_FLAT_LIMIT: u64 = 200;
+const MAX_FLAT_LIMIT: u64 = 1000;
+/// Nested trees are returned in one response, larger folders must use the flat format
+const MAX_TREE_NODES: usize = 5000;
+
+/// List all descendants of a folder as a nested tree or a flat, paginated list
+pub async fn list_tree(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<FileTreeQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let owner_id = query.owner_id.unwrap_or(user_id);
+    if claims.role != "admin" && owner_id != user_id {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::FILE_OWN_FILES_ONLY,
+        );
+    }
+
+    let flat = match query.format.as_deref() {
+        None | Some("nested") => false,
+        Some("flat") => true,
+        Some(_) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::FILE_UNSUPPORTED_TREE_FORMAT,
+            );
+        }
+    };
+
+    let root_path = match file_utils::sanitize_path(query.path.as_deref().unwrap_or("/")) {
+        Ok(p) => {
+            let trimmed = p.trim_end_matches('/');
+            if trimmed.is_empty() {
+                "/".to_string()
+            } else {
+                trimmed.to_string()
+            }
+        }
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+    };
+
+    let mut select = file::Entity::find().filter(file::Column::UserId.eq(owner_id));
+    if root_path != "/" {
+        select = select.filter(file::Column::Path.starts_with(format!("{}/", root_path)));
+    }
+
+    let files = match select.order_by_asc(file::Column::Path).all(&state.db).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to query files");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    // Keep descendants within the requested depth
+    let root_depth = file_utils::path_depth(&root_path);
+    let files: Vec<(file::Model, u32)> = files
+        .into_iter()
+        .map(|f| {
+            let depth = (file_utils::path_depth(&f.path) - root_depth) as u32;
+            (f, depth)
+        })
+        .filter(|(_, depth)| query.depth.is_none_or(|max| *depth <= max))
+        .collect();
+
+    tracing::info!(
+        request_id = %request_id,
+        owner_id = owner_id,
+        path = %root_path,
+        count = files.len(),
+        flat = flat,
+        "List file tree request"
+    );
+
+    if flat {
+        let total = files.len();
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_FLAT_LIMIT)
+            .clamp(1, MAX_FLAT_LIMIT);
+        let offset = query.offset.unwrap_or(0);
+
+        let (page, depths): (Vec<file::Model>, Vec<u32>) = files
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .unzip();
+        let depth_by_id: HashMap<i32, u32> = page.iter().map(|f| f.id).zip(depths).collect();
+
+        let items = build_file_items(&state.db, page, user_id, &claims.role, &request_id).await;
+        let files = items
+            .into_iter()
+            .map(|item| FlatFileItem {
+                depth: depth_by_id.get(&item.id).copied().unwrap_or(0),
+                item,
+            })
+            .collect();
+
+        return do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::FILE_TREE_RETRIEVED,
+            Some(FlatFileListResponse {
+                root_path,
+                total,
+                limit,
+                offset,
+                files,
+            }),
+        );
+    }
+
+    if files.len() > MAX_TREE_NODES {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "Folder has more than {} entries, use format=flat with pagination",
+                MAX_TREE_NODES
+            ),
+        );
+    }
+
+    let parents: HashMap<i32, String> = files
+        .iter()
+        .map(|(f, _)| (f.id, f.parent_path.clone()))
+        .collect();
+    let models = files.into_iter().map(|(f, _)| f).collect();
+    let items = build_file_items(&state.db, models, user_id, &claims.role, &request_id).await;
+    let total = items.len();
+
+    let mut children: HashMap<String, Vec<FileItem>> = HashMap::new();
+    for item in items {
+        let parent = parents.get(&item.id).cloned().unwrap_or_default();
+        children.entry(parent).or_default().push(item);
+    }
+    let tree = build_tree(&root_path, &mut children);
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_TREE_RETRIEVED,
+        Some(FileTreeResponse {
+            root_path,
+            total,
+            tree,
+        }),
+    )
+}
+
+/// Attach every item to its parent folder, starting at `parent_path`
+fn build_tree(
+    parent_path: &str,
+    children: &mut HashMap<String, Vec<FileItem>>,
+) -> Vec<FileTreeNode> {
+    let items = children.remove(parent_path).unwrap_or_default();
+    items
+        .into_iter()
+        .map(|item| {
+            let nested = build_tree(&item.path, children);
+            FileTreeNode {
+                item,
+                children: nested,
+            }
+        })
+        .collect()
+}
@@ -1,41 +1,168 @@
 use crate::{
+    config::QuotaConfig,
     entities::file,
-    utils::{file_utils, jwt, request_id, response::error_resp},
+    models::file::{ConflictMode, UploadQuery},
+    services::{
+        changes, disk_space, folder_defaults, folder_sizes, library, music, organize, photos,
+        processing, quota,
+    },
+    utils::{
+        export, file_utils,
+        i18n::{self, Text},
+        jwt, request_id,
+        response::error_resp,
+        validation,
+    },
     AppState,
 };
 use axum::{
     body::Bytes,
-    extract::{Multipart, State},
-    http::StatusCode,
+    extract::{Multipart, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::Response,
     Extension,
 };
-use sea_orm::{ActiveModelTrait, Set};
-use std::path::PathBuf;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set};
+use std::path::{Path, PathBuf};
 
-use super::helpers::generate_unique_filename;
+use super::helpers::{
+    generate_unique_filename, not_on_hold, parse_user_id, physical_path, writable_destination,
+    writable_mount,
+};
+use super::permission::get_file_permissions;
 
-struct UploadContext {
+pub(super) struct UploadContext {
     request_id: String,
+    /// Owner of the tree the file is stored in
     user_id: i32,
+    /// Uploader, named in conflicted copies
+    username: String,
+    /// The uploader stores into their own tree, only then their organize rules apply
+    own_tree: bool,
     storage_root: PathBuf,
+    reserve_bytes: u64,
+    quota: QuotaConfig,
 }
 
-struct FileUploadData {
-    file_name: String,
-    content_type: Option<String>,
-    data: Bytes,
-    upload_path: String,
+pub(super) struct FileUploadData {
+    pub(super) file_name: String,
+    pub(super) content_type: Option<String>,
+    pub(super) data: UploadBody,
+    pub(super) upload_path: String,
+    pub(super) on_conflict: ConflictMode,
+    /// Server hash the sync client last saw for the target file
+    pub(super) base_hash: Option<String>,
+    /// Modification time of the file on the client
+    pub(super) client_modified: Option<chrono::NaiveDateTime>,
 }
 
-fn parse_user_id(claims: &jwt::Claims, request_id: &str) -> Result<i32, Response> {
-    claims.sub.parse::<i32>().map_err(|_| {
-        error_resp(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            request_id.to_string(),
-            "Invalid user ID",
-        )
-    })
+/// Content of an upload
+pub(super) enum UploadBody {
+    /// File field of a multipart form, read into memory
+    Memory(Bytes),
+    /// File assembled in staging by a chunked upload, moved into place when stored
+    Staged {
+        path: PathBuf,
+        size: u64,
+        /// SHA-256 computed while the staged file was verified
+        hash: String,
+    },
+}
+
+impl UploadBody {
+    fn len(&self) -> u64 {
+        match self {
+            UploadBody::Memory(data) => data.len() as u64,
+            UploadBody::Staged { size, .. } => *size,
+        }
+    }
+
+    async fn hash(&self) -> Result<String, (StatusCode, Text)> {
+        match self {
+            UploadBody::Memory(data) => {
+                let data = data.clone();
+                tokio::task::spawn_blocking(move || {
+                    crate::services::deduplication::calculate_hash_from_bytes(&data)
+                })
+                .await
+                .map_err(|_| internal("Failed to hash file".to_string()))
+            }
+            UploadBody::Staged { hash, .. } => Ok(hash.clone()),
+        }
+    }
+
+    /// Put the content at `dest`, replacing what is there
+    async fn write_to(&self, dest: &Path) -> std::io::Result<()> {
+        match self {
+            UploadBody::Memory(data) => tokio::fs::write(dest, data).await,
+            UploadBody::Staged { path, .. } => move_file(path, dest).await,
+        }
+    }
+
+    /// Undo `write_to` after the upload could not be recorded
+    /// Staged content goes back to staging so the session can be completed again
+    async fn discard(&self, dest: &Path) {
+        let result = match self {
+            UploadBody::Memory(_) => tokio::fs::remove_file(dest).await,
+            UploadBody::Staged { path, .. } => move_file(dest, path).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(path = %dest.display(), error = %e, "Failed to clean up stored upload");
+        }
+    }
+}
+
+/// Rename `from` to `to`, copying when they are on different volumes
+async fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    tokio::fs::copy(from, to).await?;
+    tokio::fs::remove_file(from).await
+}
+
+/// Result of a successful upload
+enum UploadOutcome {
+    Created(file::Model),
+    /// The stored file with the bytes it grew by
+    Overwritten(file::Model, i64),
+    /// Identical content already exists at the target path
+    Skipped(file::Model),
+    /// The target changed on the server since `base_hash`, the upload was stored next to it
+    Conflicted {
+        copy: file::Model,
+        original_path: String,
+    },
+}
+
+impl UploadContext {
+    pub(super) fn new(
+        state: &AppState,
+        claims: &jwt::Claims,
+        owner_id: i32,
+        request_id: &str,
+        storage_root: PathBuf,
+    ) -> Self {
+        UploadContext {
+            request_id: request_id.to_string(),
+            user_id: owner_id,
+            username: claims.username.clone(),
+            own_tree: claims.sub.parse::<i32>().is_ok_and(|id| id == owner_id),
+            storage_root,
+            reserve_bytes: state.config.storage.reserve_bytes,
+            quota: state.config.quota.clone(),
+        }
+    }
+}
+
+/// Body cut off by the upload limit of the caller's role
+fn over_role_limit(request_id: &str) -> Response {
+    tracing::warn!(request_id = %request_id, "Upload over the role limit cut off");
+    error_resp(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        request_id.to_string(),
+        &i18n::UPLOAD_ROLE_LIMIT_EXCEEDED,
+    )
 }
 
 async fn parse_multipart_data(
@@ -43,15 +170,37 @@ async fn parse_multipart_data(
     request_id: &str,
 ) -> Result<Option<FileUploadData>, Response> {
     let mut upload_path = "/".to_string();
+    let mut on_conflict = None;
+    let mut base_hash = None;
+    let mut client_modified = None;
     let mut file_data: Option<FileUploadData> = None;
 
-    while let Ok(Some(field)) = multipart.next_field().await {
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                return Err(over_role_limit(request_id));
+            }
+            Ok(None) | Err(_) => break,
+        };
         let name = field.name().unwrap_or("").to_string();
 
         if name == "path" {
             if let Ok(val) = field.text().await {
                 upload_path = val;
             }
+        } else if name == "on_conflict" {
+            if let Ok(val) = field.text().await {
+                on_conflict = Some(val);
+            }
+        } else if name == "base_hash" {
+            if let Ok(val) = field.text().await {
+                base_hash = Some(val.trim().to_lowercase()).filter(|h| !h.is_empty());
+            }
+        } else if name == "client_modified" {
+            if let Ok(val) = field.text().await {
+                client_modified = Some(val).filter(|v| !v.trim().is_empty());
+            }
         } else if name == "file" {
             let file_name = match field.file_name() {
                 Some(name) => name.to_string(),
@@ -62,6 +211,9 @@ async fn parse_multipart_data(
 
             let data = match field.bytes().await {
                 Ok(d) => d,
+                Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                    return Err(over_role_limit(request_id));
+                }
                 Err(e) => {
                     tracing::error!(
                         request_id = %request_id,
@@ -72,7 +224,7 @@ async fn parse_multipart_data(
                     return Err(error_resp(
                         StatusCode::BAD_REQUEST,
                         request_id.to_string(),
-                        &format!("Failed to read file '{}'", file_name),
+                        format!("Failed to read file '{}'", file_name),
                     ));
                 }
             };
@@ -80,48 +232,120 @@ async fn parse_multipart_data(
             file_data = Some(FileUploadData {
                 file_name,
                 content_type,
-                data,
+                data: UploadBody::Memory(data),
                 upload_path: upload_path.clone(),
+                on_conflict: ConflictMode::default(),
+                base_hash: None,
+                client_modified: None,
             });
         }
     }
 
-    Ok(file_data)
+    // The mode may be sent before or after the file field
+    let on_conflict = match on_conflict.as_deref().map(str::trim) {
+        None | Some("") => ConflictMode::default(),
+        Some(value) => ConflictMode::parse(value).ok_or_else(|| {
+            error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id.to_string(),
+                &i18n::FILE_INVALID_ON_CONFLICT,
+            )
+        })?,
+    };
+
+    let client_modified = match client_modified {
+        None => None,
+        Some(value) => Some(export::parse_time_bound(&value, false).ok_or_else(|| {
+            error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id.to_string(),
+                &i18n::FILE_INVALID_CLIENT_MODIFIED,
+            )
+        })?),
+    };
+
+    Ok(file_data.map(|data| FileUploadData {
+        on_conflict,
+        base_hash,
+        client_modified,
+        ..data
+    }))
 }
 
 async fn process_file_upload(
     ctx: &UploadContext,
-    upload_data: FileUploadData,
+    mut upload_data: FileUploadData,
     db: &sea_orm::DatabaseConnection,
-) -> Result<file::Model, String> {
-    let file_hash = crate::services::deduplication::calculate_hash_from_bytes(&upload_data.data);
+) -> Result<UploadOutcome, (StatusCode, Text)> {
+    let file_hash = upload_data.data.hash().await?;
 
     let size_bytes = upload_data.data.len() as i64;
     if size_bytes > crate::constants::MAX_FILE_SIZE_BYTES {
-        return Err(format!(
+        return Err(internal(format!(
             "File size ({} bytes) exceeds maximum allowed size ({} bytes)",
             size_bytes,
             crate::constants::MAX_FILE_SIZE_BYTES
-        ));
+        )));
     }
 
     let clean_path = file_utils::sanitize_path(&upload_data.upload_path)
-        .map_err(|e| format!("Invalid path: {}", e))?;
+        .map_err(|e| internal(format!("Invalid path: {}", e)))?;
+
+    let mut conflicted_with = None;
+    if upload_data.on_conflict != ConflictMode::Rename || upload_data.base_hash.is_some() {
+        let target_path = format!(
+            "{}/{}",
+            clean_path.trim_end_matches('/'),
+            upload_data.file_name
+        );
+        let existing = file::Entity::find()
+            .filter(file::Column::UserId.eq(ctx.user_id))
+            .filter(file::Column::Path.eq(&target_path))
+            .one(db)
+            .await
+            .map_err(|e| {
+                tracing::error!(request_id = %ctx.request_id, error = ?e, "Database error");
+                internal(&i18n::COMMON_DATABASE_ERROR)
+            })?;
+
+        if let Some(existing) = existing {
+            let conflicted = match upload_data.base_hash.as_deref() {
+                Some(base_hash) if existing.file_type == "file" => {
+                    changed_on_server(&existing, base_hash, &file_hash).await
+                }
+                _ => false,
+            };
+
+            if conflicted {
+                // Keep both versions, the client sees the conflict in the changes feed
+                upload_data.file_name = file_utils::conflicted_copy_name(
+                    &upload_data.file_name,
+                    &chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                    &ctx.username,
+                );
+                conflicted_with = Some(existing.path);
+            } else if upload_data.on_conflict != ConflictMode::Rename {
+                return resolve_conflict(ctx, existing, upload_data, file_hash, db).await;
+            }
+        }
+    }
 
     let unique_filename =
         generate_unique_filename(&upload_data.file_name, ctx.user_id, &clean_path, db)
             .await
-            .map_err(|_| "Failed to generate unique filename".to_string())?;
+            .map_err(|_| internal(&i18n::FILE_UNIQUE_NAME_FAILED))?;
 
     // Database path uses forward slashes
     let file_path = format!("{}/{}", clean_path.trim_end_matches('/'), unique_filename);
+    if file_utils::is_protected_path(&file_path) {
+        return Err((
+            StatusCode::CONFLICT,
+            Text::Catalog(&i18n::FILE_RESERVED_PATH),
+        ));
+    }
 
-    // Physical path uses OS-specific separator
-    let path_for_fs = file_path
-        .trim_start_matches('/')
-        .replace('/', std::path::MAIN_SEPARATOR_STR);
-    let physical_path =
-        file_utils::get_user_storage_path(&ctx.storage_root, ctx.user_id).join(path_for_fs);
+    let mount = writable_mount(db, ctx.user_id, &file_path, &ctx.request_id).await?;
+    let physical_path = physical_path(mount.as_ref(), &ctx.storage_root, ctx.user_id, &file_path);
 
     tracing::info!(
         request_id = %ctx.request_id,
@@ -130,17 +354,23 @@ async fn process_file_upload(
         "Uploading file"
     );
 
-    let _ = file_utils::ensure_user_directory(&ctx.storage_root, ctx.user_id);
+    check_free_space(ctx, size_bytes as u64).await?;
+    check_quota(ctx, db, size_bytes).await?;
+
+    let _ = file_utils::ensure_user_directory(&ctx.storage_root, ctx.user_id).await;
     if let Some(parent) = physical_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| internal(format!("Failed to create directory: {}", e)))?;
     }
 
-    tokio::fs::write(&physical_path, &upload_data.data)
+    upload_data
+        .data
+        .write_to(&physical_path)
         .await
         .map_err(|e| {
             tracing::error!(request_id = %ctx.request_id, error = ?e, "Failed to write file");
-            "Failed to save file to disk".to_string()
+            internal("Failed to save file to disk".to_string())
         })?;
 
     // Normalize storage_path: always use forward slashes in database
@@ -159,6 +389,7 @@ async fn process_file_upload(
         storage_path: Set(storage_path_str),
         file_hash: Set(Some(file_hash)),
         ref_count: Set(1),
+        client_modified: Set(upload_data.client_modified),
         created_at: Set(now),
         updated_at: Set(now),
         ..Default::default()
@@ -166,6 +397,7 @@ async fn process_file_upload(
 
     match new_file.insert(db).await {
         Ok(file_model) => {
+            folder_sizes::adjust(db, ctx.user_id, &clean_path, size_bytes).await;
             tracing::info!(
                 request_id = %ctx.request_id,
                 file_id = file_model.id,
@@ -173,11 +405,20 @@ async fn process_file_upload(
                 size_bytes = size_bytes,
                 "File uploaded successfully"
             );
-            Ok(file_model)
+            if let Err(e) = folder_defaults::apply(db, &file_model).await {
+                tracing::warn!(request_id = %ctx.request_id, error = ?e, "Failed to apply folder default permissions");
+            }
+            Ok(match conflicted_with {
+                Some(original_path) => UploadOutcome::Conflicted {
+                    copy: file_model,
+                    original_path,
+                },
+                None => UploadOutcome::Created(file_model),
+            })
         }
         Err(e) => {
             // Clean up physical file on database error
-            let _ = std::fs::remove_file(&physical_path);
+            upload_data.data.discard(&physical_path).await;
             tracing::error!(
                 request_id = %ctx.request_id,
                 error = ?e,
@@ -187,18 +428,174 @@ async fn process_file_upload(
 
             let error_msg = format!("{:?}", e);
             if error_msg.contains("UNIQUE constraint") {
-                Err("File with this name already exists. Please try again.".to_string())
+                Err(internal(
+                    "File with this name already exists. Please try again.".to_string(),
+                ))
             } else {
-                Err("Database error occurred".to_string())
+                Err(internal(&i18n::COMMON_DATABASE_ERROR))
             }
         }
     }
 }
 
+/// Whether the stored content is neither the client's base version nor what it uploads now
+async fn changed_on_server(existing: &file::Model, base_hash: &str, upload_hash: &str) -> bool {
+    let current = match &existing.file_hash {
+        Some(hash) => hash.clone(),
+        // Files indexed from mounts are hashed on demand
+        None => {
+            let path = PathBuf::from(&existing.storage_path);
+            match tokio::task::spawn_blocking(move || {
+                crate::services::deduplication::calculate_hash_from_file(&path)
+            })
+            .await
+            {
+                Ok(Ok(hash)) => hash,
+                _ => return false,
+            }
+        }
+    };
+    current != base_hash && current != upload_hash
+}
+
+/// Apply the requested conflict mode to a file already stored at the target path
+async fn resolve_conflict(
+    ctx: &UploadContext,
+    existing: file::Model,
+    upload_data: FileUploadData,
+    file_hash: String,
+    db: &sea_orm::DatabaseConnection,
+) -> Result<UploadOutcome, (StatusCode, Text)> {
+    if upload_data.on_conflict == ConflictMode::Fail || existing.file_type == "folder" {
+        return Err((
+            StatusCode::CONFLICT,
+            Text::Plain(format!("'{}' already exists", existing.path)),
+        ));
+    }
+
+    if upload_data.on_conflict == ConflictMode::SkipIfSameHash
+        && existing.file_hash.as_deref() == Some(file_hash.as_str())
+    {
+        tracing::info!(
+            request_id = %ctx.request_id,
+            file_id = existing.id,
+            "Upload skipped, content unchanged"
+        );
+        return Ok(UploadOutcome::Skipped(existing));
+    }
+
+    check_free_space(ctx, upload_data.data.len()).await?;
+    let grown_bytes = upload_data.data.len() as i64 - existing.size_bytes.unwrap_or(0);
+    check_quota(ctx, db, grown_bytes).await?;
+
+    writable_mount(db, existing.user_id, &existing.path, &ctx.request_id).await?;
+    not_on_hold(db, &existing, &ctx.request_id).await?;
+
+    // The new content goes next to the stored file and replaces it only once the row is updated,
+    // so a failed update keeps the old content and readers never see a half-written file
+    let physical_path = PathBuf::from(&existing.storage_path);
+    if let Some(parent) = physical_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| internal(format!("Failed to create directory: {}", e)))?;
+    }
+    let temp_path = physical_path.with_file_name(format!(".{}.part", uuid::Uuid::new_v4()));
+    upload_data.data.write_to(&temp_path).await.map_err(|e| {
+        tracing::error!(request_id = %ctx.request_id, error = ?e, "Failed to write file");
+        internal("Failed to save file to disk".to_string())
+    })?;
+
+    let file_id = existing.id;
+    let version = existing.version;
+    let (owner_id, parent_path) = (existing.user_id, existing.parent_path.clone());
+    let previous = existing.clone();
+    let mut active: file::ActiveModel = existing.into();
+    active.version = Set(version + 1);
+    active.size_bytes = Set(Some(upload_data.data.len() as i64));
+    active.file_hash = Set(Some(file_hash));
+    if upload_data.content_type.is_some() {
+        active.mime_type = Set(upload_data.content_type);
+    }
+    active.client_modified = Set(upload_data.client_modified);
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    // Only update the version we checked, a concurrent change wins
+    let file_model = match file::Entity::update(active)
+        .filter(file::Column::Version.eq(version))
+        .exec(db)
+        .await
+    {
+        Ok(f) => f,
+        Err(DbErr::RecordNotUpdated) => {
+            upload_data.data.discard(&temp_path).await;
+            return Err((
+                StatusCode::CONFLICT,
+                Text::Plain(format!(
+                    "'{}' was changed while uploading, retry",
+                    previous.path
+                )),
+            ));
+        }
+        Err(e) => {
+            upload_data.data.discard(&temp_path).await;
+            tracing::error!(request_id = %ctx.request_id, error = ?e, "Database error during overwrite");
+            return Err(internal(&i18n::COMMON_DATABASE_ERROR));
+        }
+    };
+
+    if let Err(e) = tokio::fs::rename(&temp_path, &physical_path).await {
+        tracing::error!(request_id = %ctx.request_id, error = ?e, "Failed to replace file");
+        upload_data.data.discard(&temp_path).await;
+        // Put the row back so it describes the content still on disk
+        let restored = file::Entity::update(file::ActiveModel::from(previous).reset_all())
+            .filter(file::Column::Version.eq(version + 1))
+            .exec(db)
+            .await;
+        if let Err(e) = restored {
+            tracing::error!(request_id = %ctx.request_id, file_id = file_id, error = ?e, "Failed to restore file record");
+        }
+        return Err(internal("Failed to save file to disk".to_string()));
+    }
+
+    folder_sizes::adjust(db, owner_id, &parent_path, grown_bytes).await;
+    tracing::info!(request_id = %ctx.request_id, file_id = file_id, "File overwritten");
+    Ok(UploadOutcome::Overwritten(file_model, grown_bytes))
+}
+
+/// Refuse a write that would cut into the reserved free space of the storage volume
+async fn check_free_space(ctx: &UploadContext, bytes: u64) -> Result<(), (StatusCode, Text)> {
+    disk_space::ensure_free_space(&ctx.storage_root, bytes, ctx.reserve_bytes)
+        .await
+        .map_err(|msg| {
+            tracing::warn!(request_id = %ctx.request_id, bytes = bytes, "Upload rejected, insufficient storage");
+            (StatusCode::INSUFFICIENT_STORAGE, msg.into())
+        })
+}
+
+/// Refuse a write that would take the owner past their quota and its grace overage
+async fn check_quota(
+    ctx: &UploadContext,
+    db: &sea_orm::DatabaseConnection,
+    bytes: i64,
+) -> Result<(), (StatusCode, Text)> {
+    quota::ensure_room(db, &ctx.quota, ctx.user_id, bytes)
+        .await
+        .map_err(|msg| {
+            tracing::warn!(request_id = %ctx.request_id, bytes = bytes, "Upload rejected, storage quota exceeded");
+            (StatusCode::INSUFFICIENT_STORAGE, msg.into())
+        })
+}
+
+fn internal(msg: impl Into<Text>) -> (StatusCode, Text) {
+    (StatusCode::INTERNAL_SERVER_ERROR, msg.into())
+}
+
 pub async fn upload_file(
     State(state): State<AppState>,
     Extension(claims): Extension<jwt::Claims>,
-    mut multipart: Multipart,
+    Query(query): Query<UploadQuery>,
+    headers: HeaderMap,
+    multipart: Multipart,
 ) -> Response {
     let request_id = request_id::generate_request_id();
 
@@ -207,28 +604,303 @@ pub async fn upload_file(
         Err(resp) => return resp,
     };
 
-    let ctx = UploadContext {
-        request_id: request_id.clone(),
-        user_id,
-        storage_root: state.config.get_storage_dir(),
+    let Some(folder_id) = query.folder_id else {
+        return upload_into(
+            &state, &claims, user_id, None, request_id, &headers, multipart,
+        )
+        .await;
+    };
+
+    // A folder shared with the caller keeps its files in its owner's tree and storage
+    let folder = match file::Entity::find_by_id(folder_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::FILE_DESTINATION_NOT_FOUND,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
     };
+    // Drop-only folders take uploads without being readable
+    let (can_read, can_write, _) =
+        get_file_permissions(&state.db, user_id, &claims.role, &folder).await;
+    if !can_read && !can_write {
+        return error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::FILE_DESTINATION_NOT_FOUND,
+        );
+    }
+    if folder.file_type != "folder" {
+        return error_resp(
+            StatusCode::CONFLICT,
+            request_id,
+            &i18n::FILE_DESTINATION_NOT_FOLDER,
+        );
+    }
+
+    upload_into(
+        &state,
+        &claims,
+        folder.user_id,
+        Some(folder.path),
+        request_id,
+        &headers,
+        multipart,
+    )
+    .await
+}
+
+/// Upload into the Shared Library, the caller needs write access to the target folder
+pub async fn upload_library_file(
+    State(state): State<AppState>,
+    Extension(claims): Extension<jwt::Claims>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let owner_id = match library::owner_id() {
+        Some(id) => id,
+        None => {
+            return error_resp(
+                StatusCode::SERVICE_UNAVAILABLE,
+                request_id,
+                &i18n::LIBRARY_UNAVAILABLE,
+            );
+        }
+    };
+
+    upload_into(
+        &state, &claims, owner_id, None, request_id, &headers, multipart,
+    )
+    .await
+}
+
+/// Background work on newly stored content: tags, photo positions and processing hooks
+fn process_content(state: &AppState, f: &file::Model) {
+    music::spawn_index(&state.db, f);
+    photos::spawn_index(&state.db, f);
+    processing::spawn(&state.db, &state.config, f);
+}
+
+/// Store an uploaded file in the tree of `owner_id`
+/// `target_path` replaces the `path` field of the form when the target folder is already known
+async fn upload_into(
+    state: &AppState,
+    claims: &jwt::Claims,
+    owner_id: i32,
+    target_path: Option<String>,
+    request_id: String,
+    headers: &HeaderMap,
+    mut multipart: Multipart,
+) -> Response {
+    let user_id = match parse_user_id(claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let storage_root = match super::helpers::user_storage_root(state, owner_id, &request_id).await {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+
+    let ctx = UploadContext::new(state, claims, owner_id, &request_id, storage_root);
 
-    let upload_data = match parse_multipart_data(&mut multipart, &request_id).await {
+    // Fail fast on the declared size before the body is read
+    let declared_size = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if let Some(size) = declared_size {
+        if let Err((status, msg)) = check_free_space(&ctx, size).await {
+            return error_resp(status, request_id, msg);
+        }
+    }
+
+    let mut upload_data = match parse_multipart_data(&mut multipart, &request_id).await {
         Ok(Some(data)) => data,
-        Ok(None) => return error_resp(StatusCode::BAD_REQUEST, request_id, "No file uploaded"),
+        Ok(None) => return error_resp(StatusCode::BAD_REQUEST, request_id, &i18n::FILE_NO_UPLOAD),
         Err(resp) => return resp,
     };
 
-    match process_file_upload(&ctx, upload_data, &state.db).await {
-        Ok(file_model) => {
-            tracing::info!(request_id = %request_id, "File uploaded successfully");
-            crate::utils::response::do_json_detail_resp(
-                StatusCode::CREATED,
+    if let Some(path) = target_path {
+        upload_data.upload_path = path;
+    }
+    upload_data.file_name = file_utils::normalize_name(&upload_data.file_name);
+    if let Err(e) = validation::validate_filename(&upload_data.file_name, &state.config.validation)
+    {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+    }
+
+    let target = match file_utils::sanitize_path(&upload_data.upload_path) {
+        Ok(p) => p,
+        Err(e) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
                 request_id,
-                "File uploaded successfully",
-                Some(file_model),
-            )
+                format!("Invalid path: {}", e),
+            );
+        }
+    };
+    let readable = match writable_destination(
+        &state.db,
+        owner_id,
+        user_id,
+        &claims.role,
+        &target,
+        &i18n::FILE_NO_PERMISSION_UPLOAD,
+        &request_id,
+    )
+    .await
+    {
+        Ok(readable) => readable,
+        Err((status, msg)) => return error_resp(status, request_id, msg),
+    };
+
+    // Existing files of a drop-only folder can neither be replaced nor compared against
+    if !readable {
+        upload_data.on_conflict = ConflictMode::Rename;
+        upload_data.base_hash = None;
+    }
+
+    store_upload(state, &ctx, upload_data).await
+}
+
+/// Send an upload to the folder chosen by the first organize rule of the owner that matches
+/// When the destination cannot be created the upload stays in the folder it was sent to
+async fn organize_upload(state: &AppState, ctx: &UploadContext, upload_data: &mut FileUploadData) {
+    if !ctx.own_tree {
+        return;
+    }
+    let Ok(folder_path) = file_utils::sanitize_path(&upload_data.upload_path) else {
+        return;
+    };
+    let folder_path = match folder_path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    let rules = match organize::for_user(&state.db, ctx.user_id).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            tracing::warn!(request_id = %ctx.request_id, error = ?e, "Failed to load organize rules");
+            return;
         }
-        Err(error_msg) => error_resp(StatusCode::INTERNAL_SERVER_ERROR, request_id, &error_msg),
+    };
+    let time = upload_data
+        .client_modified
+        .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+    let Some((rule, destination)) = organize::destination(
+        &rules,
+        &folder_path,
+        &upload_data.file_name,
+        upload_data.content_type.as_deref(),
+        time,
+    ) else {
+        return;
+    };
+
+    let (parent_path, name) = match destination.rfind('/') {
+        Some(0) => ("/".to_string(), &destination[1..]),
+        Some(idx) => (destination[..idx].to_string(), &destination[idx + 1..]),
+        None => return,
+    };
+    let created = super::create_folder_in(
+        state,
+        ctx.user_id,
+        parent_path,
+        name,
+        true,
+        ctx.request_id.clone(),
+    )
+    .await;
+    if !created.status().is_success() {
+        tracing::warn!(
+            request_id = %ctx.request_id,
+            rule_id = rule.id,
+            destination = %destination,
+            status = %created.status(),
+            "Organize rule destination could not be created, upload kept in place"
+        );
+        return;
     }
+
+    tracing::info!(
+        request_id = %ctx.request_id,
+        rule_id = rule.id,
+        destination = %destination,
+        "Upload filed by organize rule"
+    );
+    upload_data.upload_path = destination;
+}
+
+/// Store parsed upload data and record the outcome in the changes feed
+pub(super) async fn store_upload(
+    state: &AppState,
+    ctx: &UploadContext,
+    mut upload_data: FileUploadData,
+) -> Response {
+    let request_id = ctx.request_id.clone();
+    organize_upload(state, ctx, &mut upload_data).await;
+    let (status, message, file_model) = match process_file_upload(ctx, upload_data, &state.db).await
+    {
+        Ok(UploadOutcome::Created(f)) => {
+            changes::record(&state.db, &f, changes::CHANGE_CREATED, None).await;
+            process_content(state, &f);
+            let grown_bytes = f.size_bytes.unwrap_or(0);
+            quota::warn_if_crossed(&state.db, &state.config, ctx.user_id, grown_bytes).await;
+            (StatusCode::CREATED, "File uploaded successfully", f)
+        }
+        Ok(UploadOutcome::Overwritten(f, grown_bytes)) => {
+            changes::record(&state.db, &f, changes::CHANGE_UPDATED, None).await;
+            process_content(state, &f);
+            quota::warn_if_crossed(&state.db, &state.config, ctx.user_id, grown_bytes).await;
+            (StatusCode::OK, "File overwritten successfully", f)
+        }
+        Ok(UploadOutcome::Skipped(f)) => (
+            StatusCode::OK,
+            "File skipped, identical content already exists",
+            f,
+        ),
+        Ok(UploadOutcome::Conflicted {
+            copy,
+            original_path,
+        }) => {
+            tracing::warn!(
+                request_id = %request_id,
+                file_id = copy.id,
+                original_path = %original_path,
+                "Upload conflicted with a newer server version"
+            );
+            changes::record(
+                &state.db,
+                &copy,
+                changes::CHANGE_CONFLICT,
+                Some(&original_path),
+            )
+            .await;
+            process_content(state, &copy);
+            let grown_bytes = copy.size_bytes.unwrap_or(0);
+            quota::warn_if_crossed(&state.db, &state.config, ctx.user_id, grown_bytes).await;
+            (
+                StatusCode::CREATED,
+                "File changed on the server, upload saved as a conflicted copy",
+                copy,
+            )
+        }
+        Err((status, error_msg)) => return error_resp(status, request_id, error_msg),
+    };
+
+    tracing::info!(request_id = %request_id, "{}", message);
+    crate::utils::response::do_json_detail_resp(status, request_id, message, Some(file_model))
 }
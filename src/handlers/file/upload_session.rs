@@ -0,0 +1,538 @@
+use crate::{
+    entities::{upload_chunk, upload_session},
+    error::ErrorCode,
+    models::file::{
+        ByteRange, CompleteUploadQuery, ConflictMode, StartUploadRequest, UploadChunkQuery,
+        UploadSessionItem,
+    },
+    services::{deduplication, staging, upload_sessions},
+    utils::{
+        export, file_utils, i18n,
+        jwt::Claims,
+        range, request_id,
+        response::{do_json_detail_resp, error_code_detail_resp, error_resp, EmptyData},
+        timestamp, validation,
+    },
+    AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use std::path::PathBuf;
+
+use super::helpers::{parse_user_id, writable_destination};
+use super::upload::{store_upload, FileUploadData, UploadBody, UploadContext};
+
+/// Keeps the chunk records of one upload within reason, the largest file still fits in 1 MB chunks
+const MAX_CHUNKS_PER_SESSION: usize =
+    (crate::constants::MAX_FILE_SIZE_BYTES / (1024 * 1024)) as usize;
+
+fn to_item(s: upload_session::Model, chunks: &[upload_chunk::Model]) -> UploadSessionItem {
+    let received: Vec<(i64, i64)> = chunks.iter().map(|c| (c.offset, c.size)).collect();
+    UploadSessionItem {
+        id: s.id,
+        path: s.path,
+        file_name: s.file_name,
+        missing_ranges: range::gaps(&received, s.total_bytes)
+            .into_iter()
+            .map(|(offset, length)| ByteRange { offset, length })
+            .collect(),
+        total_bytes: s.total_bytes,
+        received_bytes: s.received_bytes,
+        created_at: timestamp::format(s.created_at),
+        updated_at: timestamp::format(s.updated_at),
+        expires_at: timestamp::format(s.expires_at),
+    }
+}
+
+/// Unexpired session of the caller, 404 otherwise
+async fn find_session(
+    state: &AppState,
+    user_id: i32,
+    session_id: i32,
+    request_id: &str,
+) -> Result<upload_session::Model, Response> {
+    match upload_sessions::find(&state.db, user_id, session_id).await {
+        Ok(Some(s)) => Ok(s),
+        Ok(None) => Err(error_resp(
+            StatusCode::NOT_FOUND,
+            request_id.to_string(),
+            &i18n::UPLOAD_SESSION_NOT_FOUND,
+        )),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ))
+        }
+    }
+}
+
+/// Start a chunked upload into the caller's tree, the declared size is reserved in staging
+pub async fn start_upload_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(mut payload): Json<StartUploadRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    payload.file_name = file_utils::normalize_name(payload.file_name.trim());
+    if let Err(e) = validation::validate_filename(&payload.file_name, &state.config.validation) {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, e);
+    }
+    payload.path = match file_utils::sanitize_path(&payload.path) {
+        Ok(p) => p,
+        Err(e) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                format!("Invalid path: {}", e),
+            );
+        }
+    };
+    if let Err((status, msg)) = writable_destination(
+        &state.db,
+        user_id,
+        user_id,
+        &claims.role,
+        &payload.path,
+        &i18n::FILE_NO_PERMISSION_UPLOAD,
+        &request_id,
+    )
+    .await
+    {
+        return error_resp(status, request_id, msg);
+    }
+    if !(0..=crate::constants::MAX_FILE_SIZE_BYTES).contains(&payload.size) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!(
+                "File size must be between 0 and {} bytes",
+                crate::constants::MAX_FILE_SIZE_BYTES
+            ),
+        );
+    }
+    if state
+        .config
+        .server
+        .max_upload_size_for(&claims.role)
+        .is_some_and(|limit| payload.size as u64 > limit)
+    {
+        return error_resp(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            request_id,
+            &i18n::UPLOAD_ROLE_LIMIT_EXCEEDED,
+        );
+    }
+    payload.on_conflict = payload
+        .on_conflict
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    if payload
+        .on_conflict
+        .as_deref()
+        .is_some_and(|m| ConflictMode::parse(m).is_none())
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::FILE_INVALID_ON_CONFLICT,
+        );
+    }
+    payload.base_hash = payload
+        .base_hash
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| !h.is_empty());
+    let client_modified = match payload.client_modified.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(value) => match export::parse_time_bound(value, false) {
+            Some(t) => Some(t),
+            None => {
+                return error_resp(
+                    StatusCode::BAD_REQUEST,
+                    request_id,
+                    &i18n::FILE_INVALID_CLIENT_MODIFIED,
+                );
+            }
+        },
+    };
+
+    if let Err(msg) = staging::ensure_space(&state.config, payload.size as u64).await {
+        tracing::warn!(request_id = %request_id, size = payload.size, "Upload session rejected, staging is full");
+        return error_resp(StatusCode::INSUFFICIENT_STORAGE, request_id, msg);
+    }
+
+    match upload_sessions::create(&state.db, &state.config, user_id, payload, client_modified).await
+    {
+        Ok(session) => {
+            tracing::info!(request_id = %request_id, session_id = session.id, "Upload session started");
+            do_json_detail_resp(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::UPLOAD_SESSION_STARTED,
+                Some(to_item(session, &[])),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to start upload session");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::UPLOAD_SESSION_FAILED,
+            )
+        }
+    }
+}
+
+/// Write the request body at `offset`, chunks can come in any order and in parallel
+/// Sending a chunk again is harmless, the same bytes are acknowledged and new bytes replace the old
+pub async fn upload_chunk(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<i32>,
+    Query(query): Query<UploadChunkQuery>,
+    body: Bytes,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let session = match find_session(&state, user_id, session_id, &request_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+    let chunks = match upload_sessions::chunks(&state.db, session.id).await {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let offset = query.offset;
+    let Some(end) = range::end_within(offset, body.len() as i64, session.total_bytes) else {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::UPLOAD_CHUNK_TOO_LARGE,
+        );
+    };
+    if body.is_empty() {
+        return do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::UPLOAD_CHUNK_RECEIVED,
+            Some(to_item(session, &chunks)),
+        );
+    }
+
+    let checksum = deduplication::calculate_hash_from_bytes(&body);
+    if query
+        .checksum
+        .as_deref()
+        .map(|c| c.trim().to_lowercase())
+        .is_some_and(|expected| expected != checksum)
+    {
+        tracing::info!(request_id = %request_id, session_id = session_id, offset = offset, "Damaged chunk refused");
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::UPLOAD_CHUNK_CHECKSUM,
+        );
+    }
+
+    let overlapping: Vec<&upload_chunk::Model> = chunks
+        .iter()
+        .filter(|c| c.offset < end && offset < c.offset + c.size)
+        .collect();
+    let replaces = match overlapping.as_slice() {
+        [] if chunks.len() >= MAX_CHUNKS_PER_SESSION => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                format!(
+                    "An upload can have at most {} chunks, send larger ones",
+                    MAX_CHUNKS_PER_SESSION
+                ),
+            );
+        }
+        [] => None,
+        [c] if c.offset == offset && c.size == body.len() as i64 => {
+            if c.checksum == checksum {
+                return do_json_detail_resp(
+                    StatusCode::OK,
+                    request_id,
+                    &i18n::UPLOAD_CHUNK_DUPLICATE,
+                    Some(to_item(session, &chunks)),
+                );
+            }
+            Some((*c).clone())
+        }
+        _ => {
+            return error_code_detail_resp(
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+                request_id,
+                &i18n::UPLOAD_CHUNK_OVERLAP,
+                Some(to_item(session, &chunks)),
+            );
+        }
+    };
+
+    let result = upload_sessions::write_chunk(
+        &state.db,
+        &state.config,
+        session,
+        offset,
+        &body,
+        checksum,
+        replaces,
+    )
+    .await;
+    let session = match result {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, session_id = session_id, error = ?e, "Failed to store chunk");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::UPLOAD_CHUNK_FAILED,
+            );
+        }
+    };
+    match upload_sessions::chunks(&state.db, session.id).await {
+        Ok(chunks) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::UPLOAD_CHUNK_RECEIVED,
+            Some(to_item(session, &chunks)),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Store the assembled file like a regular upload and close the session
+/// The chunks are checked against their checksums first, a 409 lists the ranges to send again
+pub async fn complete_upload_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<i32>,
+    Query(query): Query<CompleteUploadQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let session = match find_session(&state, user_id, session_id, &request_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+    let chunks = match upload_sessions::chunks(&state.db, session.id).await {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let item = to_item(session.clone(), &chunks);
+    if !item.missing_ranges.is_empty() {
+        return error_code_detail_resp(
+            StatusCode::CONFLICT,
+            ErrorCode::Conflict,
+            request_id,
+            &i18n::UPLOAD_INCOMPLETE,
+            Some(item),
+        );
+    }
+
+    let (hash, damaged) = match upload_sessions::verify(&session, chunks).await {
+        Ok(verified) => verified,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, session_id = session_id, error = ?e, "Failed to read staged upload");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::UPLOAD_READ_STAGED_FAILED,
+            );
+        }
+    };
+
+    // Bytes damaged in staging since they were received are sent again
+    if !damaged.is_empty() {
+        tracing::warn!(request_id = %request_id, session_id = session_id, chunks = damaged.len(), "Staged upload failed verification");
+        return match upload_sessions::drop_chunks(&state.db, &state.config, session, &damaged).await
+        {
+            Ok(session) => {
+                let chunks = upload_sessions::chunks(&state.db, session.id)
+                    .await
+                    .unwrap_or_default();
+                error_code_detail_resp(
+                    StatusCode::CONFLICT,
+                    ErrorCode::Conflict,
+                    request_id,
+                    &i18n::UPLOAD_VERIFICATION_FAILED,
+                    Some(to_item(session, &chunks)),
+                )
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = ?e, "Database error");
+                error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                )
+            }
+        };
+    }
+    if query
+        .sha256
+        .as_deref()
+        .map(|h| h.trim().to_lowercase())
+        .is_some_and(|expected| expected != hash)
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::UPLOAD_FILE_CHECKSUM,
+        );
+    }
+
+    let storage_root = match super::helpers::user_storage_root(&state, user_id, &request_id).await {
+        Ok(root) => root,
+        Err(resp) => return resp,
+    };
+    let ctx = UploadContext::new(&state, &claims, user_id, &request_id, storage_root);
+    let upload_data = FileUploadData {
+        file_name: session.file_name.clone(),
+        content_type: session.mime_type.clone(),
+        data: UploadBody::Staged {
+            path: PathBuf::from(&session.staging_path),
+            size: session.total_bytes as u64,
+            hash,
+        },
+        upload_path: session.path.clone(),
+        on_conflict: ConflictMode::parse(&session.on_conflict).unwrap_or_default(),
+        base_hash: session.base_hash.clone(),
+        client_modified: session.client_modified,
+    };
+
+    let response = store_upload(&state, &ctx, upload_data).await;
+
+    // A failed store keeps the session so it can be retried, e.g. after freeing space
+    if response.status().is_success() {
+        if let Err(e) = upload_sessions::remove(&state.db, &session).await {
+            tracing::warn!(request_id = %request_id, session_id = session_id, error = ?e, "Failed to close upload session");
+        }
+    }
+    response
+}
+
+/// Chunked uploads of the caller still in progress
+pub async fn list_upload_sessions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let result = async {
+        let mut items = Vec::new();
+        for session in upload_sessions::list(&state.db, user_id).await? {
+            let chunks = upload_sessions::chunks(&state.db, session.id).await?;
+            items.push(to_item(session, &chunks));
+        }
+        Ok::<_, sea_orm::DbErr>(items)
+    }
+    .await;
+
+    match result {
+        Ok(items) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::UPLOAD_SESSIONS_RETRIEVED,
+            Some(items),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Cancel a chunked upload and free its staged bytes
+pub async fn cancel_upload_session(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let session = match find_session(&state, user_id, session_id, &request_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    match upload_sessions::remove(&state.db, &session).await {
+        Ok(()) => {
+            tracing::info!(request_id = %request_id, session_id = session_id, "Upload session cancelled");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::UPLOAD_CANCELLED,
+                None,
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    entities::job,
+    error::ErrorCode,
+    services::jobs,
+    utils::{
+        archive::ArchiveFormat,
+        i18n,
+        jwt::Claims,
+        range, request_id,
+        response::{do_json_detail_resp, error_code_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension,
+};
+use sea_orm::EntityTrait;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use tokio_util::io::ReaderStream;
+
+/// Server-sent event stream of the caller's job progress
+/// Each update is a `job` event whose data is the job status as JSON
+pub async fn event_stream(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    tracing::info!(request_id = %request_id, user_id = user_id, "Event stream opened");
+
+    // Lagging subscribers skip missed updates, the next one carries the full state
+    let stream = BroadcastStream::new(state.events.subscribe()).filter_map(move |event| {
+        let event = event.ok().filter(|e| e.user_id == user_id)?;
+        Some(Event::default().event("job").json_data(&event.job))
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Get status and progress of a background job
+pub async fn get_job(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(job_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let job = match find_job(&state, &claims, job_id, &request_id).await {
+        Ok(j) => j,
+        Err(resp) => return resp,
+    };
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::JOB_RETRIEVED,
+        Some(jobs::job_info(job)),
+    )
+}
+
+/// Download the archive of a completed job
+/// Supports single byte ranges so interrupted downloads can resume
+pub async fn download_job_result(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(job_id): Path<i32>,
+    headers: HeaderMap,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let job = match find_job(&state, &claims, job_id, &request_id).await {
+        Ok(j) => j,
+        Err(resp) => return resp,
+    };
+
+    let path = match (job.status.as_str(), job.result_path.as_deref()) {
+        (jobs::STATUS_COMPLETED, Some(path)) => path.to_string(),
+        (jobs::STATUS_EXPIRED, _) => {
+            return error_resp(
+                StatusCode::GONE,
+                request_id,
+                &i18n::DOWNLOAD_ARCHIVE_EXPIRED,
+            );
+        }
+        _ => {
+            return error_code_resp(
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+                request_id,
+                &i18n::DOWNLOAD_ARCHIVE_NOT_READY,
+            );
+        }
+    };
+
+    let mut file = match tokio::fs::File::open(&path).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, path = %path, "Failed to open archive");
+            return error_resp(StatusCode::GONE, request_id, &i18n::DOWNLOAD_ARCHIVE_GONE);
+        }
+    };
+    let size = match file.metadata().await {
+        Ok(m) => m.len(),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Failed to read archive metadata");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                "Failed to read archive",
+            );
+        }
+    };
+
+    let format = ArchiveFormat::from_path(&path);
+    let filename = format!(
+        "files_{}.{}",
+        job.created_at.format("%Y%m%d_%H%M%S"),
+        format.extension()
+    );
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        );
+
+    let requested = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| range::parse_byte_range(v, size));
+
+    let (start, end) = match requested {
+        None => {
+            return builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, size)
+                .body(Body::from_stream(ReaderStream::new(file)))
+                .unwrap();
+        }
+        Some(None) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                .body(Body::empty())
+                .unwrap();
+        }
+        Some(Some(range)) => range,
+    };
+
+    if let Err(e) = file.seek(std::io::SeekFrom::Start(start)).await {
+        tracing::error!(request_id = %request_id, error = ?e, "Failed to seek archive");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            "Failed to read archive",
+        );
+    }
+
+    let length = end - start + 1;
+    tracing::info!(request_id = %request_id, job_id = job_id, start = start, length = length, "Resuming archive download");
+
+    builder
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_LENGTH, length)
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, size),
+        )
+        .body(Body::from_stream(ReaderStream::new(file.take(length))))
+        .unwrap()
+}
+
+/// Load a job the caller may see (its owner or an admin)
+async fn find_job(
+    state: &AppState,
+    claims: &Claims,
+    job_id: i32,
+    request_id: &str,
+) -> Result<job::Model, Response> {
+    let user_id = claims.sub.parse::<i32>().map_err(|_| {
+        error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id.to_string(),
+            &i18n::COMMON_INVALID_USER_ID,
+        )
+    })?;
+
+    match job::Entity::find_by_id(job_id).one(&state.db).await {
+        Ok(Some(j)) if j.user_id == user_id || claims.role == "admin" => Ok(j),
+        Ok(_) => Err(error_resp(
+            StatusCode::NOT_FOUND,
+            request_id.to_string(),
+            &i18n::JOB_NOT_FOUND,
+        )),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = ?e, "Database error");
+            Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ))
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use crate::{
+    constants::{ROLE_ADMIN, ROLE_USER},
+    entities::{file, group, group_member, library_grant, user},
+    handlers::file::{build_file_items, create_folder_in},
+    models::{
+        file::{CreateFolderRequest, FileListResponse},
+        library::{
+            CreateGroupRequest, GroupItem, GroupMemberRequest, LibraryGrantItem,
+            LibraryGrantRequest, LibraryListQuery,
+        },
+    },
+    services::{
+        audit::{self, AuditEvent},
+        library,
+    },
+    utils::{
+        client::ClientInfo,
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+
+#[allow(clippy::result_large_err)]
+fn library_owner(request_id: &str) -> Result<i32, Response> {
+    library::owner_id().ok_or_else(|| {
+        error_resp(
+            StatusCode::SERVICE_UNAVAILABLE,
+            request_id.to_string(),
+            &i18n::LIBRARY_UNAVAILABLE,
+        )
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn require_admin(claims: &Claims, request_id: &str) -> Result<(), Response> {
+    if claims.role != ROLE_ADMIN {
+        return Err(error_resp(
+            StatusCode::FORBIDDEN,
+            request_id.to_string(),
+            &i18n::ADMIN_LIBRARY_ONLY,
+        ));
+    }
+    Ok(())
+}
+
+fn db_error(request_id: String, e: sea_orm::DbErr) -> Response {
+    tracing::error!(request_id = %request_id, error = %e, "Database error");
+    error_resp(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        request_id,
+        &i18n::COMMON_DATABASE_ERROR,
+    )
+}
+
+fn grant_item(g: library_grant::Model) -> LibraryGrantItem {
+    LibraryGrantItem {
+        id: g.id,
+        path: g.path,
+        role: g.role,
+        group_id: g.group_id,
+        can_read: g.can_read,
+        can_write: g.can_write,
+        can_delete: g.can_delete,
+        granted_by: g.granted_by,
+        created_at: timestamp::format(g.created_at),
+    }
+}
+
+/// List a Shared Library folder
+/// Entries the caller has no grant for are left out, so everyone can browse down to their folders
+pub async fn list_library(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<LibraryListQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let owner_id = match library_owner(&request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let clean_path = match file_utils::sanitize_path(query.path.as_deref().unwrap_or("/")) {
+        Ok(p) => p,
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+    };
+
+    // A drop-only folder takes new files but keeps what it holds to itself
+    let drop_only = match library::permissions(&state.db, user_id, &claims.role, &clean_path).await
+    {
+        Ok((read, write, _)) => !read && write,
+        Err(e) => return db_error(request_id, e),
+    };
+
+    let files = if drop_only {
+        Vec::new()
+    } else {
+        match file::Entity::find()
+            .filter(file::Column::UserId.eq(owner_id))
+            .filter(file::Column::ParentPath.eq(&clean_path))
+            .all(&state.db)
+            .await
+        {
+            Ok(files) => files,
+            Err(e) => return db_error(request_id, e),
+        }
+    };
+
+    let files = build_file_items(&state.db, files, user_id, &claims.role, &request_id).await;
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::FILE_LISTED,
+        Some(FileListResponse {
+            files,
+            current_path: clean_path,
+        }),
+    )
+}
+
+/// Create a folder in the Shared Library, the caller needs write access to the parent folder
+pub async fn create_library_folder(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CreateFolderRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let owner_id = match library_owner(&request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let parent_path = match file_utils::sanitize_path(&req.path) {
+        Ok(p) => p,
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+    };
+
+    match library::permissions(&state.db, user_id, &claims.role, &parent_path).await {
+        Ok((_, true, _)) => {}
+        Ok(_) => {
+            return error_resp(
+                StatusCode::FORBIDDEN,
+                request_id,
+                &i18n::FILE_NO_PERMISSION_CREATE_FOLDER,
+            );
+        }
+        Err(e) => return db_error(request_id, e),
+    }
+
+    create_folder_in(
+        &state,
+        owner_id,
+        parent_path,
+        &req.name,
+        req.exist_ok,
+        request_id,
+    )
+    .await
+}
+
+/// Create a group (admin only)
+pub async fn create_group(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<CreateGroupRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    let name = req.name.trim();
+    if name.is_empty() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::LIBRARY_GROUP_NAME_REQUIRED,
+        );
+    }
+
+    match group::Entity::find()
+        .filter(group::Column::Name.eq(name))
+        .one(&state.db)
+        .await
+    {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return error_resp(
+                StatusCode::CONFLICT,
+                request_id,
+                format!("Group '{}' already exists", name),
+            );
+        }
+        Err(e) => return db_error(request_id, e),
+    }
+
+    let now = Utc::now().naive_utc();
+    match (group::ActiveModel {
+        name: Set(name.to_string()),
+        created_at: Set(now),
+        ..Default::default()
+    })
+    .insert(&state.db)
+    .await
+    {
+        Ok(g) => {
+            tracing::info!(request_id = %request_id, group_id = g.id, "Group created");
+            do_json_detail_resp(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::LIBRARY_GROUP_CREATED,
+                Some(GroupItem {
+                    id: g.id,
+                    name: g.name,
+                    member_ids: Vec::new(),
+                    created_at: timestamp::format(g.created_at),
+                }),
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// List groups with their members (admin only)
+pub async fn list_groups(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    let groups = match group::Entity::find()
+        .order_by_asc(group::Column::Name)
+        .all(&state.db)
+        .await
+    {
+        Ok(g) => g,
+        Err(e) => return db_error(request_id, e),
+    };
+
+    let mut members: HashMap<i32, Vec<i32>> = HashMap::new();
+    match group_member::Entity::find()
+        .order_by_asc(group_member::Column::UserId)
+        .all(&state.db)
+        .await
+    {
+        Ok(rows) => {
+            for m in rows {
+                members.entry(m.group_id).or_default().push(m.user_id);
+            }
+        }
+        Err(e) => return db_error(request_id, e),
+    }
+
+    let items: Vec<GroupItem> = groups
+        .into_iter()
+        .map(|g| GroupItem {
+            member_ids: members.remove(&g.id).unwrap_or_default(),
+            id: g.id,
+            name: g.name,
+            created_at: timestamp::format(g.created_at),
+        })
+        .collect();
+
+    do_json_detail_resp(StatusCode::OK, request_id, "Groups retrieved", Some(items))
+}
+
+/// Delete a group together with its memberships and library grants (admin only)
+pub async fn delete_group(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(group_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    let deleted = async {
+        let txn = state.db.begin().await?;
+        group_member::Entity::delete_many()
+            .filter(group_member::Column::GroupId.eq(group_id))
+            .exec(&txn)
+            .await?;
+        library_grant::Entity::delete_many()
+            .filter(library_grant::Column::GroupId.eq(group_id))
+            .exec(&txn)
+            .await?;
+        let result = group::Entity::delete_by_id(group_id).exec(&txn).await?;
+        txn.commit().await?;
+        Ok::<_, sea_orm::DbErr>(result.rows_affected)
+    }
+    .await;
+
+    match deleted {
+        Ok(0) => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::LIBRARY_GROUP_NOT_FOUND,
+        ),
+        Ok(_) => {
+            tracing::info!(request_id = %request_id, group_id = group_id, "Group deleted");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::LIBRARY_GROUP_DELETED,
+                None,
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// Add a user to a group (admin only)
+pub async fn add_group_member(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(group_id): Path<i32>,
+    Json(req): Json<GroupMemberRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    match group::Entity::find_by_id(group_id).one(&state.db).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return error_resp(
+                StatusCode::NOT_FOUND,
+                request_id,
+                &i18n::LIBRARY_GROUP_NOT_FOUND,
+            )
+        }
+        Err(e) => return db_error(request_id, e),
+    }
+    match user::Entity::find_by_id(req.user_id).one(&state.db).await {
+        Ok(Some(u)) if u.role == ROLE_USER || u.role == ROLE_ADMIN => {}
+        Ok(_) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND),
+        Err(e) => return db_error(request_id, e),
+    }
+
+    let existing = group_member::Entity::find()
+        .filter(group_member::Column::GroupId.eq(group_id))
+        .filter(group_member::Column::UserId.eq(req.user_id))
+        .one(&state.db)
+        .await;
+    match existing {
+        Ok(Some(_)) => {
+            return do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::LIBRARY_ALREADY_MEMBER,
+                None,
+            );
+        }
+        Ok(None) => {}
+        Err(e) => return db_error(request_id, e),
+    }
+
+    let member = group_member::ActiveModel {
+        group_id: Set(group_id),
+        user_id: Set(req.user_id),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    match member.insert(&state.db).await {
+        Ok(_) => {
+            tracing::info!(request_id = %request_id, group_id = group_id, user_id = req.user_id, "Group member added");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::CREATED,
+                request_id,
+                &i18n::LIBRARY_MEMBER_ADDED,
+                None,
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// Remove a user from a group (admin only)
+pub async fn remove_group_member(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((group_id, user_id)): Path<(i32, i32)>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    match group_member::Entity::delete_many()
+        .filter(group_member::Column::GroupId.eq(group_id))
+        .filter(group_member::Column::UserId.eq(user_id))
+        .exec(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::LIBRARY_MEMBERSHIP_NOT_FOUND,
+        ),
+        Ok(_) => {
+            tracing::info!(request_id = %request_id, group_id = group_id, user_id = user_id, "Group member removed");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::LIBRARY_MEMBER_REMOVED,
+                None,
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// Grant a role or a group access to a library folder (admin only)
+pub async fn set_library_grant(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<LibraryGrantRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+    let admin_id = claims.sub.parse::<i32>().unwrap_or_default();
+
+    let path = match file_utils::sanitize_path(&req.path) {
+        Ok(p) => p,
+        Err(e) => return error_resp(StatusCode::BAD_REQUEST, request_id, e),
+    };
+
+    let principal = match (&req.role, req.group_id) {
+        (Some(role), None) if role == ROLE_USER || role == ROLE_ADMIN => {
+            library_grant::Column::Role.eq(role.as_str())
+        }
+        (None, Some(group_id)) => match group::Entity::find_by_id(group_id).one(&state.db).await {
+            Ok(Some(_)) => library_grant::Column::GroupId.eq(group_id),
+            Ok(None) => {
+                return error_resp(
+                    StatusCode::NOT_FOUND,
+                    request_id,
+                    &i18n::LIBRARY_GROUP_NOT_FOUND,
+                )
+            }
+            Err(e) => return db_error(request_id, e),
+        },
+        _ => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Set either role ('user' or 'admin') or group_id",
+            );
+        }
+    };
+
+    let existing = library_grant::Entity::find()
+        .filter(library_grant::Column::Path.eq(&path))
+        .filter(principal)
+        .one(&state.db)
+        .await;
+
+    let now = Utc::now().naive_utc();
+    let saved = match existing {
+        Ok(Some(g)) => {
+            let mut active: library_grant::ActiveModel = g.into();
+            active.can_read = Set(req.can_read);
+            active.can_write = Set(req.can_write);
+            active.can_delete = Set(req.can_delete);
+            active.granted_by = Set(admin_id);
+            active.update(&state.db).await
+        }
+        Ok(None) => {
+            library_grant::ActiveModel {
+                path: Set(path.clone()),
+                role: Set(req.role.clone()),
+                group_id: Set(req.group_id),
+                can_read: Set(req.can_read),
+                can_write: Set(req.can_write),
+                can_delete: Set(req.can_delete),
+                granted_by: Set(admin_id),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&state.db)
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match saved {
+        Ok(grant) => {
+            audit::record(
+                &state.db,
+                Some(admin_id),
+                AuditEvent::PermissionGranted,
+                &client,
+                Some(serde_json::json!({
+                    "library_path": grant.path,
+                    "role": grant.role,
+                    "group_id": grant.group_id,
+                    "can_read": grant.can_read,
+                    "can_write": grant.can_write,
+                    "can_delete": grant.can_delete,
+                })),
+            )
+            .await;
+            tracing::info!(request_id = %request_id, grant_id = grant.id, path = %grant.path, "Library grant saved");
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::LIBRARY_GRANT_SAVED,
+                Some(grant_item(grant)),
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// List library grants (admin only)
+pub async fn list_library_grants(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    match library_grant::Entity::find()
+        .order_by_asc(library_grant::Column::Path)
+        .all(&state.db)
+        .await
+    {
+        Ok(grants) => {
+            let items: Vec<LibraryGrantItem> = grants.into_iter().map(grant_item).collect();
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                "Library grants retrieved",
+                Some(items),
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
+
+/// Remove a library grant (admin only)
+pub async fn delete_library_grant(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(grant_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    if let Err(resp) = require_admin(&claims, &request_id) {
+        return resp;
+    }
+
+    match library_grant::Entity::delete_by_id(grant_id)
+        .exec(&state.db)
+        .await
+    {
+        Ok(r) if r.rows_affected == 0 => error_resp(
+            StatusCode::NOT_FOUND,
+            request_id,
+            &i18n::LIBRARY_GRANT_NOT_FOUND,
+        ),
+        Ok(_) => {
+            tracing::info!(request_id = %request_id, grant_id = grant_id, "Library grant removed");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::LIBRARY_GRANT_REMOVED,
+                None,
+            )
+        }
+        Err(e) => db_error(request_id, e),
+    }
+}
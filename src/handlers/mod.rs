@@ -1,4 +1,16 @@
+pub mod admin;
+pub mod announcement;
 pub mod auth;
+pub mod feature;
 pub mod file;
+pub mod jobs;
+pub mod library;
+pub mod mount;
+pub mod music;
+pub mod notification;
+pub mod photo;
+pub mod quarantine;
+pub mod share;
 pub mod storage;
+pub mod takedown;
 pub mod user;
@@ -0,0 +1,288 @@
+use crate::{
+    entities::{file, mount, user},
+    handlers::file::delete_file_record,
+    models::mount::{CreateMountRequest, MountItem},
+    services::mounts,
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+fn to_item(m: mount::Model) -> MountItem {
+    MountItem {
+        id: m.id,
+        user_id: m.user_id,
+        mount_point: m.mount_point(),
+        name: m.name,
+        host_path: m.host_path,
+        read_only: m.read_only,
+        last_indexed_at: m.last_indexed_at.map(timestamp::format),
+        created_at: timestamp::format(m.created_at),
+    }
+}
+
+/// Register a host directory as a folder at the root of a user's tree (admin only)
+/// Its files are indexed in the background, the bytes stay on the host
+pub async fn create_mount(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateMountRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_MOUNTS_ONLY);
+    }
+
+    let name = payload.name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return error_resp(StatusCode::BAD_REQUEST, request_id, "Invalid mount name");
+    }
+
+    let host_path = std::path::PathBuf::from(payload.host_path.trim());
+    if !host_path.is_absolute() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Host path must be an absolute path",
+        );
+    }
+    let host_path = match tokio::fs::canonicalize(&host_path).await {
+        Ok(p) if tokio::fs::metadata(&p).await.is_ok_and(|m| m.is_dir()) => p,
+        _ => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "Host path is not an existing directory",
+            );
+        }
+    };
+
+    // Mounting managed storage would expose other users' files
+    let mut overlaps_storage = false;
+    for volume in state.config.get_storage_volumes() {
+        if let Ok(v) = tokio::fs::canonicalize(&volume).await {
+            overlaps_storage |= host_path.starts_with(&v) || v.starts_with(&host_path);
+        }
+    }
+    if overlaps_storage {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Host path must not overlap the storage directory",
+        );
+    }
+
+    match user::Entity::find_by_id(payload.user_id)
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let mount_point = format!("/{}", name);
+    match file::Entity::find()
+        .filter(file::Column::UserId.eq(payload.user_id))
+        .filter(file::Column::Path.eq(&mount_point))
+        .one(&state.db)
+        .await
+    {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return error_resp(
+                StatusCode::CONFLICT,
+                request_id,
+                format!("'{}' already exists", mount_point),
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+    let host_path_str = host_path.to_string_lossy().replace('\\', "/");
+    let created = async {
+        let txn = state.db.begin().await?;
+        let m = mount::ActiveModel {
+            user_id: Set(payload.user_id),
+            name: Set(name.to_string()),
+            host_path: Set(host_path_str.clone()),
+            read_only: Set(payload.read_only.unwrap_or(true)),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        file::ActiveModel {
+            user_id: Set(payload.user_id),
+            name: Set(name.to_string()),
+            path: Set(mount_point.clone()),
+            parent_path: Set("/".to_string()),
+            file_type: Set("folder".into()),
+            storage_path: Set(host_path_str.clone()),
+            ref_count: Set(1),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok::<_, sea_orm::DbErr>(m)
+    }
+    .await;
+
+    let m = match created {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to create mount");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let db = state.db.clone();
+    let indexed = m.clone();
+    tokio::spawn(async move {
+        if let Err(e) = mounts::index(&db, &indexed).await {
+            tracing::warn!(mount_id = indexed.id, error = %e, "Mount indexing failed");
+        }
+    });
+
+    tracing::info!(
+        request_id = %request_id,
+        mount_id = m.id,
+        user_id = m.user_id,
+        host_path = %m.host_path,
+        read_only = m.read_only,
+        "Mount created"
+    );
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        "Mount created",
+        Some(to_item(m)),
+    )
+}
+
+/// List all mounts (admin only)
+pub async fn list_mounts(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_MOUNTS_ONLY);
+    }
+
+    match mount::Entity::find()
+        .order_by_asc(mount::Column::Id)
+        .all(&state.db)
+        .await
+    {
+        Ok(mounts) => {
+            let items: Vec<MountItem> = mounts.into_iter().map(to_item).collect();
+            do_json_detail_resp(StatusCode::OK, request_id, "Mounts retrieved", Some(items))
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
+
+/// Remove a mount and its index from the user's tree, host files are not touched (admin only)
+pub async fn delete_mount(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(mount_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::ADMIN_MOUNTS_ONLY);
+    }
+
+    let m = match mount::Entity::find_by_id(mount_id).one(&state.db).await {
+        Ok(Some(m)) => m,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, "Mount not found"),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let mount_point = m.mount_point();
+    let removed = async {
+        let rows = file::Entity::find()
+            .filter(file::Column::UserId.eq(m.user_id))
+            .filter(
+                Condition::any()
+                    .add(file::Column::Path.eq(&mount_point))
+                    .add(file::Column::Path.starts_with(format!("{}/", mount_point))),
+            )
+            .all(&state.db)
+            .await?;
+        for f in rows {
+            delete_file_record(&state.db, f.id).await?;
+        }
+        mount::Entity::delete_by_id(m.id).exec(&state.db).await?;
+        Ok::<_, sea_orm::DbErr>(())
+    }
+    .await;
+
+    if let Err(e) = removed {
+        tracing::error!(request_id = %request_id, error = %e, "Failed to remove mount");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_DATABASE_ERROR,
+        );
+    }
+
+    tracing::info!(request_id = %request_id, mount_id = mount_id, "Mount removed");
+    do_json_detail_resp::<EmptyData>(StatusCode::OK, request_id, "Mount removed", None)
+}
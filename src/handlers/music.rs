@@ -0,0 +1,206 @@
+use crate::{
+    entities::{audio_cover, audio_metadata, file},
+    handlers::file::parse_user_id,
+    models::music::{AlbumItem, TrackItem, TrackQuery},
+    utils::{
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+    Extension,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::collections::BTreeMap;
+
+const DEFAULT_TRACK_LIMIT: u64 = 200;
+const MAX_TRACK_LIMIT: u64 = 1000;
+
+fn to_track_item(meta: audio_metadata::Model, f: file::Model) -> TrackItem {
+    let title = meta.title.unwrap_or_else(|| {
+        let (stem, _) = file_utils::split_filename(&f.name);
+        stem.to_string()
+    });
+    TrackItem {
+        file_id: f.id,
+        path: f.path,
+        title,
+        artist: meta.artist,
+        album: meta.album,
+        track_number: meta.track_number,
+        year: meta.year,
+        has_cover: meta.has_cover,
+    }
+}
+
+/// List the caller's audio files with their tags, optionally of one artist or album
+pub async fn list_tracks(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<TrackQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRACK_LIMIT)
+        .clamp(1, MAX_TRACK_LIMIT);
+
+    let mut select = audio_metadata::Entity::find()
+        .find_also_related(file::Entity)
+        .filter(audio_metadata::Column::UserId.eq(user_id));
+    if let Some(artist) = &query.artist {
+        select = select.filter(audio_metadata::Column::Artist.eq(artist));
+    }
+    if let Some(album) = &query.album {
+        select = select.filter(audio_metadata::Column::Album.eq(album));
+    }
+
+    let rows = match select
+        .order_by_asc(audio_metadata::Column::Artist)
+        .order_by_asc(audio_metadata::Column::Album)
+        .order_by_asc(audio_metadata::Column::TrackNumber)
+        .order_by_asc(audio_metadata::Column::Title)
+        .limit(limit)
+        .offset(query.offset.unwrap_or(0))
+        .all(&state.db)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let tracks: Vec<TrackItem> = rows
+        .into_iter()
+        .filter_map(|(meta, f)| Some(to_track_item(meta, f?)))
+        .collect();
+
+    do_json_detail_resp(StatusCode::OK, request_id, "Tracks retrieved", Some(tracks))
+}
+
+/// List the albums in the caller's audio files, grouped by album and artist tags
+pub async fn list_albums(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let tracks = match audio_metadata::Entity::find()
+        .filter(audio_metadata::Column::UserId.eq(user_id))
+        .filter(audio_metadata::Column::Album.is_not_null())
+        .order_by_asc(audio_metadata::Column::TrackNumber)
+        .all(&state.db)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let mut albums: BTreeMap<(String, Option<String>), AlbumItem> = BTreeMap::new();
+    for t in tracks {
+        let Some(album) = t.album else { continue };
+        let item = albums
+            .entry((album.clone(), t.artist.clone()))
+            .or_insert_with(|| AlbumItem {
+                album,
+                artist: t.artist,
+                year: None,
+                track_count: 0,
+                cover_file_id: None,
+            });
+        item.track_count += 1;
+        item.year = item.year.or(t.year);
+        if t.has_cover && item.cover_file_id.is_none() {
+            item.cover_file_id = Some(t.file_id);
+        }
+    }
+
+    let albums: Vec<AlbumItem> = albums.into_values().collect();
+    do_json_detail_resp(StatusCode::OK, request_id, "Albums retrieved", Some(albums))
+}
+
+/// Cover art embedded in one of the caller's audio files
+pub async fn get_track_cover(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let user_id = match parse_user_id(&claims, &request_id) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let found = async {
+        let meta = audio_metadata::Entity::find_by_id(file_id)
+            .one(&state.db)
+            .await?;
+        match meta {
+            Some(m) if m.user_id == user_id || claims.role == "admin" => {
+                audio_cover::Entity::find_by_id(file_id)
+                    .one(&state.db)
+                    .await
+            }
+            _ => Ok(None),
+        }
+    }
+    .await;
+
+    match found {
+        Ok(Some(cover)) => Response::builder()
+            .status(StatusCode::OK)
+            // The type comes from the file's tags, a cover able to run scripts is sent as bytes
+            .header(
+                header::CONTENT_TYPE,
+                if cover.mime_type.starts_with("image/")
+                    && !state.config.download.is_active_content(&cover.mime_type)
+                {
+                    cover.mime_type.as_str()
+                } else {
+                    "application/octet-stream"
+                },
+            )
+            .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+            .header(header::CACHE_CONTROL, "private, max-age=86400")
+            .body(Body::from(cover.data))
+            .unwrap(),
+        Ok(None) => error_resp(StatusCode::NOT_FOUND, request_id, "Cover not found"),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            )
+        }
+    }
+}
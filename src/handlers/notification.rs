@@ -0,0 +1,349 @@
+use crate::{
+    entities::{notification, notification_preference},
+    models::notification::{
+        NotificationItem, NotificationListQuery, NotificationListResponse,
+        NotificationPreferenceItem, UpdateNotificationPreferencesRequest,
+    },
+    services::notifications::{self, NotificationCategory},
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+
+const DEFAULT_NOTIFICATION_LIMIT: u64 = 50;
+const MAX_NOTIFICATION_LIMIT: u64 = 200;
+
+/// List the current user's in-app notifications, newest first
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<NotificationListQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_NOTIFICATION_LIMIT)
+        .clamp(1, MAX_NOTIFICATION_LIMIT);
+
+    let mut select = notification::Entity::find().filter(notification::Column::UserId.eq(user_id));
+    if query.unread_only {
+        select = select.filter(notification::Column::ReadAt.is_null());
+    }
+
+    let items = select
+        .order_by_desc(notification::Column::CreatedAt)
+        .order_by_desc(notification::Column::Id)
+        .offset(query.offset.unwrap_or(0))
+        .limit(limit)
+        .all(&state.db)
+        .await;
+
+    let unread_count = notification::Entity::find()
+        .filter(notification::Column::UserId.eq(user_id))
+        .filter(notification::Column::ReadAt.is_null())
+        .count(&state.db)
+        .await;
+
+    let (items, unread_count) = match (items, unread_count) {
+        (Ok(i), Ok(c)) => (i, c),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let notifications = items
+        .into_iter()
+        .map(|n| NotificationItem {
+            id: n.id,
+            category: n.category,
+            title: n.title,
+            body: n.body,
+            link: n.link,
+            read: n.read_at.is_some(),
+            created_at: timestamp::format(n.created_at),
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::NOTIFICATION_LISTED,
+        Some(NotificationListResponse {
+            notifications,
+            unread_count,
+        }),
+    )
+}
+
+/// Mark one notification as read
+pub async fn mark_read(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(notification_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let result = notification::Entity::update_many()
+        .col_expr(
+            notification::Column::ReadAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(notification::Column::Id.eq(notification_id))
+        .filter(notification::Column::UserId.eq(user_id))
+        .filter(notification::Column::ReadAt.is_null())
+        .exec(&state.db)
+        .await;
+
+    match result {
+        Ok(_) => do_json_detail_resp::<EmptyData>(
+            StatusCode::OK,
+            request_id,
+            &i18n::NOTIFICATION_READ,
+            None,
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Mark all of the current user's notifications as read
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let result = notification::Entity::update_many()
+        .col_expr(
+            notification::Column::ReadAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(notification::Column::UserId.eq(user_id))
+        .filter(notification::Column::ReadAt.is_null())
+        .exec(&state.db)
+        .await;
+
+    match result {
+        Ok(r) => {
+            tracing::info!(request_id = %request_id, user_id = user_id, count = r.rows_affected, "Notifications marked as read");
+            do_json_detail_resp::<EmptyData>(
+                StatusCode::OK,
+                request_id,
+                &i18n::NOTIFICATION_ALL_READ,
+                None,
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Get email / in-app settings for every notification category
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    match load_preferences(&state.db, user_id).await {
+        Ok(preferences) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::NOTIFICATION_PREFERENCES_RETRIEVED,
+            Some(preferences),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Update email / in-app settings for one or more notification categories
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UpdateNotificationPreferencesRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    // Validate everything before writing anything
+    let mut rows = Vec::new();
+    for item in &payload.preferences {
+        let category = match NotificationCategory::parse(&item.category) {
+            Some(c) => c,
+            None => {
+                return error_resp(
+                    StatusCode::BAD_REQUEST,
+                    request_id,
+                    format!("Unknown notification category: {}", item.category),
+                );
+            }
+        };
+
+        rows.push(notification_preference::ActiveModel {
+            user_id: Set(user_id),
+            category: Set(category.as_str().to_string()),
+            email_enabled: Set(item.email),
+            in_app_enabled: Set(item.in_app),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        });
+    }
+
+    for row in rows {
+        let result = notification_preference::Entity::insert(row)
+            .on_conflict(
+                OnConflict::columns([
+                    notification_preference::Column::UserId,
+                    notification_preference::Column::Category,
+                ])
+                .update_columns([
+                    notification_preference::Column::EmailEnabled,
+                    notification_preference::Column::InAppEnabled,
+                    notification_preference::Column::UpdatedAt,
+                ])
+                .to_owned(),
+            )
+            .exec(&state.db)
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    }
+
+    tracing::info!(request_id = %request_id, user_id = user_id, "Notification preferences updated");
+
+    match load_preferences(&state.db, user_id).await {
+        Ok(preferences) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::NOTIFICATION_PREFERENCES_UPDATED,
+            Some(preferences),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Effective channels for every category, including defaults
+async fn load_preferences(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<NotificationPreferenceItem>, DbErr> {
+    let mut preferences = Vec::new();
+    for category in NotificationCategory::ALL {
+        let channels = notifications::channels_for(db, user_id, category).await?;
+        preferences.push(NotificationPreferenceItem {
+            category: category.as_str().to_string(),
+            email: channels.email,
+            in_app: channels.in_app,
+        });
+    }
+    Ok(preferences)
+}
@@ -0,0 +1,136 @@
+use crate::{
+    entities::{file, photo_location},
+    models::photo::{GeoQuery, PhotoLocationItem},
+    utils::{
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+    },
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+const DEFAULT_GEO_LIMIT: u64 = 500;
+const MAX_GEO_LIMIT: u64 = 2000;
+
+/// Positions of the caller's photos within a bounding box, for the map view
+pub async fn list_photo_locations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<GeoQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let latitudes = [query.min_lat, query.max_lat];
+    let longitudes = [query.min_lon, query.max_lon];
+    if latitudes
+        .iter()
+        .flatten()
+        .any(|l| !(-90.0..=90.0).contains(l))
+        || longitudes
+            .iter()
+            .flatten()
+            .any(|l| !(-180.0..=180.0).contains(l))
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            "Coordinates are out of range",
+        );
+    }
+    if let (Some(min), Some(max)) = (query.min_lat, query.max_lat) {
+        if min > max {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                "min_lat must not be greater than max_lat",
+            );
+        }
+    }
+
+    let mut select = photo_location::Entity::find()
+        .find_also_related(file::Entity)
+        .filter(photo_location::Column::UserId.eq(user_id));
+    if let Some(min) = query.min_lat {
+        select = select.filter(photo_location::Column::Latitude.gte(min));
+    }
+    if let Some(max) = query.max_lat {
+        select = select.filter(photo_location::Column::Latitude.lte(max));
+    }
+    select = match (query.min_lon, query.max_lon) {
+        (Some(min), Some(max)) if min > max => select.filter(
+            Condition::any()
+                .add(photo_location::Column::Longitude.gte(min))
+                .add(photo_location::Column::Longitude.lte(max)),
+        ),
+        (min, max) => {
+            if let Some(min) = min {
+                select = select.filter(photo_location::Column::Longitude.gte(min));
+            }
+            if let Some(max) = max {
+                select = select.filter(photo_location::Column::Longitude.lte(max));
+            }
+            select
+        }
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_GEO_LIMIT)
+        .clamp(1, MAX_GEO_LIMIT);
+    let rows = match select
+        .order_by_asc(photo_location::Column::FileId)
+        .limit(limit)
+        .all(&state.db)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let photos: Vec<PhotoLocationItem> = rows
+        .into_iter()
+        .filter_map(|(loc, f)| {
+            let f = f?;
+            Some(PhotoLocationItem {
+                file_id: f.id,
+                name: f.name,
+                path: f.path,
+                latitude: loc.latitude,
+                longitude: loc.longitude,
+            })
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        "Photo locations retrieved",
+        Some(photos),
+    )
+}
@@ -0,0 +1,259 @@
+use crate::{
+    entities::file,
+    handlers::file::stream_file,
+    models::admin::QuarantinedFile,
+    services::{
+        audit::{self, AuditEvent},
+        legal_hold, quarantine,
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension,
+};
+
+fn to_item(f: file::Model) -> QuarantinedFile {
+    QuarantinedFile {
+        file_id: f.id,
+        owner_id: f.user_id,
+        path: f.path,
+        size_bytes: f.size_bytes,
+        mime_type: f.mime_type,
+        reason: f.quarantine_reason,
+        quarantined_at: f.quarantined_at.map(timestamp::format).unwrap_or_default(),
+    }
+}
+
+/// Quarantined file `file_id`, or the response to send when there is none
+async fn load(state: &AppState, file_id: i32, request_id: &str) -> Result<file::Model, Response> {
+    match quarantine::find(&state.db, file_id).await {
+        Ok(Some(f)) => Ok(f),
+        Ok(None) => Err(error_resp(
+            StatusCode::NOT_FOUND,
+            request_id.to_string(),
+            &i18n::ADMIN_QUARANTINE_NOT_FOUND,
+        )),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to load file");
+            Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_INTERNAL_ERROR,
+            ))
+        }
+    }
+}
+
+/// Files scanners flagged, waiting for review (admin only)
+pub async fn list_quarantined(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_QUARANTINE_ONLY,
+        );
+    }
+
+    match quarantine::list(&state.db).await {
+        Ok(files) => {
+            let items: Vec<QuarantinedFile> = files.into_iter().map(to_item).collect();
+            do_json_detail_resp(
+                StatusCode::OK,
+                request_id,
+                &i18n::ADMIN_QUARANTINE_RETRIEVED,
+                Some(items),
+            )
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to list quarantined files");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Clear a file the scanner flagged by mistake, it can be downloaded again (admin only)
+pub async fn release_quarantined(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_QUARANTINE_ONLY,
+        );
+    }
+
+    let f = match load(&state, file_id, &request_id).await {
+        Ok(f) => f,
+        Err(resp) => return resp,
+    };
+    let reason = f.quarantine_reason.clone();
+
+    let released = match quarantine::release(&state.db, f).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to release file");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    audit::record_file(
+        &state.db,
+        claims.sub.parse::<i32>().ok(),
+        AuditEvent::QuarantineReleased,
+        &client,
+        Some(released.id),
+        Some(serde_json::json!({ "owner_id": released.user_id, "path": released.path, "reason": reason })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        file_id = released.id,
+        "Quarantined file released"
+    );
+
+    do_json_detail_resp::<EmptyData>(
+        StatusCode::OK,
+        request_id,
+        &i18n::ADMIN_QUARANTINE_RELEASED,
+        None,
+    )
+}
+
+/// Delete a quarantined file for good (admin only)
+pub async fn delete_quarantined(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_QUARANTINE_ONLY,
+        );
+    }
+
+    let f = match load(&state, file_id, &request_id).await {
+        Ok(f) => f,
+        Err(resp) => return resp,
+    };
+
+    match legal_hold::holding(&state.db, &f).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return error_resp(StatusCode::CONFLICT, request_id, &i18n::FILE_LEGAL_HOLD);
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to check legal holds");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    }
+
+    if let Err(e) = quarantine::delete(&state.db, &f).await {
+        tracing::error!(request_id = %request_id, error = %e, "Failed to delete quarantined file");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_INTERNAL_ERROR,
+        );
+    }
+
+    audit::record_file(
+        &state.db,
+        claims.sub.parse::<i32>().ok(),
+        AuditEvent::QuarantineDeleted,
+        &client,
+        Some(f.id),
+        Some(serde_json::json!({ "owner_id": f.user_id, "path": f.path, "reason": f.quarantine_reason })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        file_id = f.id,
+        "Quarantined file deleted"
+    );
+
+    do_json_detail_resp::<EmptyData>(
+        StatusCode::OK,
+        request_id,
+        &i18n::ADMIN_QUARANTINE_DELETED,
+        None,
+    )
+}
+
+/// Download a quarantined file for analysis, always as an attachment (admin only)
+pub async fn download_quarantined(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_QUARANTINE_ONLY,
+        );
+    }
+
+    let f = match load(&state, file_id, &request_id).await {
+        Ok(f) => f,
+        Err(resp) => return resp,
+    };
+
+    audit::record_file(
+        &state.db,
+        claims.sub.parse::<i32>().ok(),
+        AuditEvent::QuarantineDownloaded,
+        &client,
+        Some(f.id),
+        Some(serde_json::json!({ "owner_id": f.user_id, "path": f.path })),
+    )
+    .await;
+
+    // Served as opaque bytes so a browser never renders the content
+    let mut opaque = f;
+    opaque.mime_type = Some("application/octet-stream".to_string());
+    stream_file(&opaque, request_id, "attachment", false).await
+}
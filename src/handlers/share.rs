@@ -0,0 +1,817 @@
+use crate::{
+    entities::{file, share_link},
+    handlers::file::{stream_file, unavailable_reason, with_cache_headers},
+    models::share::{
+        CreateShareLinkRequest, ShareLinkItem, ShareUnfurl, SharedDownloadQuery, SharedItem,
+        SharedListQuery, SharedListResponse,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        features::{self, Feature},
+        file_stats, photos,
+    },
+    utils::{
+        client::ClientInfo,
+        file_utils, i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp, EmptyData},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
+    Extension, Json,
+};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use sea_orm::{
+    sea_query::{Expr, LikeExpr},
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, Order, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use serde_json::json;
+
+const DEFAULT_SHARE_LIST_LIMIT: u64 = 100;
+const MAX_SHARE_LIST_LIMIT: u64 = 500;
+
+/// Link previews may be cached this long, a revoked link stops showing them after it
+const SHARE_THUMBNAIL_MAX_AGE_SECS: u64 = 300;
+
+/// Whether a link can still be used to download
+pub(crate) fn is_active(link: &share_link::Model, now: NaiveDateTime) -> bool {
+    link.revoked_at.is_none()
+        && link.expires_at.is_none_or(|expires_at| expires_at > now)
+        && link
+            .max_downloads
+            .is_none_or(|max| link.download_count < max)
+}
+
+/// Expiry of a link created at `now`, rejecting lifetimes outside 1..=`max_hours`
+fn expiry(
+    now: NaiveDateTime,
+    hours: Option<i64>,
+    max_hours: i64,
+) -> Result<Option<NaiveDateTime>, String> {
+    let Some(hours) = hours else {
+        return Ok(None);
+    };
+    let out_of_range = || format!("expires_in_hours must be between 1 and {}", max_hours);
+    if !(1..=max_hours).contains(&hours) {
+        return Err(out_of_range());
+    }
+    TimeDelta::try_hours(hours)
+        .and_then(|lifetime| now.checked_add_signed(lifetime))
+        .map(Some)
+        .ok_or_else(out_of_range)
+}
+
+fn to_item(link: share_link::Model, file_name: String) -> ShareLinkItem {
+    let active = is_active(&link, Utc::now().naive_utc());
+    ShareLinkItem {
+        id: link.id,
+        token: link.token,
+        file_id: link.file_id,
+        file_name,
+        max_downloads: link.max_downloads,
+        download_count: link.download_count,
+        expires_at: link.expires_at.map(timestamp::format),
+        revoked: link.revoked_at.is_some(),
+        active,
+        created_at: timestamp::format(link.created_at),
+    }
+}
+
+/// Create a public share link for a file or folder
+pub async fn create_share_link(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateShareLinkRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    if !features::enabled(
+        &state.db,
+        &state.config.features,
+        Feature::PublicSharing,
+        Some((user_id, &claims.role)),
+    )
+    .await
+    {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::FEATURES_DISABLED);
+    }
+
+    if payload.max_downloads.is_some_and(|max| max < 1) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::SHARE_INVALID_MAX_DOWNLOADS,
+        );
+    }
+
+    let now = Utc::now().naive_utc();
+    let expires_at = match expiry(
+        now,
+        payload.expires_in_hours,
+        state.config.download.share_link_max_hours,
+    ) {
+        Ok(e) => e,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let file_entity = match file::Entity::find_by_id(payload.file_id)
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    // Public links bypass per-user permissions, so only the owner may create them
+    if file_entity.user_id != user_id && claims.role != "admin" {
+        return error_resp(StatusCode::FORBIDDEN, request_id, &i18n::SHARE_OWNER_ONLY);
+    }
+
+    if let Some(reason) = unavailable_reason(&file_entity) {
+        return error_resp(StatusCode::FORBIDDEN, request_id, reason);
+    }
+
+    let link = share_link::ActiveModel {
+        token: Set(uuid::Uuid::new_v4().simple().to_string()),
+        file_id: Set(file_entity.id),
+        created_by: Set(user_id),
+        max_downloads: Set(payload.max_downloads),
+        download_count: Set(0),
+        expires_at: Set(expires_at),
+        revoked_at: Set(None),
+        created_at: Set(now),
+        ..Default::default()
+    };
+
+    let link = match link.insert(&state.db).await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database insert error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::SHARE_CREATE_FAILED,
+            );
+        }
+    };
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = user_id,
+        file_id = file_entity.id,
+        share_id = link.id,
+        "Share link created"
+    );
+
+    audit::record_file(
+        &state.db,
+        Some(user_id),
+        AuditEvent::ShareLinkCreated,
+        &client,
+        Some(file_entity.id),
+        Some(json!({ "share_id": link.id })),
+    )
+    .await;
+
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        &i18n::SHARE_CREATED,
+        Some(to_item(link, file_entity.name)),
+    )
+}
+
+/// List share links created by the current user
+pub async fn list_share_links(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let links = match share_link::Entity::find()
+        .filter(share_link::Column::CreatedBy.eq(user_id))
+        .order_by_desc(share_link::Column::CreatedAt)
+        .find_also_related(file::Entity)
+        .all(&state.db)
+        .await
+    {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let items: Vec<ShareLinkItem> = links
+        .into_iter()
+        .map(|(link, file)| to_item(link, file.map(|f| f.name).unwrap_or_default()))
+        .collect();
+
+    do_json_detail_resp(StatusCode::OK, request_id, &i18n::SHARE_LISTED, Some(items))
+}
+
+/// Revoke a share link
+pub async fn revoke_share_link(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(share_id): Path<i32>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let link = match share_link::Entity::find_by_id(share_id)
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(l)) => l,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::SHARE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if link.created_by != user_id && claims.role != "admin" {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::SHARE_REVOKE_OWN_ONLY,
+        );
+    }
+
+    let file_id = link.file_id;
+    let mut active: share_link::ActiveModel = link.into();
+    active.revoked_at = Set(Some(Utc::now().naive_utc()));
+
+    if let Err(e) = active.update(&state.db).await {
+        tracing::error!(request_id = %request_id, error = %e, "Database update error");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::SHARE_REVOKE_FAILED,
+        );
+    }
+
+    tracing::info!(request_id = %request_id, share_id = share_id, "Share link revoked");
+
+    audit::record_file(
+        &state.db,
+        Some(user_id),
+        AuditEvent::ShareLinkRevoked,
+        &client,
+        Some(file_id),
+        Some(json!({ "share_id": share_id })),
+    )
+    .await;
+
+    do_json_detail_resp::<EmptyData>(StatusCode::OK, request_id, &i18n::SHARE_REVOKED, None)
+}
+
+/// Download a file through a public share link, or with `file_id` a file below a shared folder
+/// Returns 410 Gone once the link is revoked, expired or out of downloads
+pub async fn download_shared_file(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Query(query): Query<SharedDownloadQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+    let now = Utc::now().naive_utc();
+
+    // Claim a download slot atomically so concurrent requests can't exceed the limit
+    let claimed = share_link::Entity::update_many()
+        .col_expr(
+            share_link::Column::DownloadCount,
+            Expr::col(share_link::Column::DownloadCount).add(1),
+        )
+        .filter(share_link::Column::Token.eq(&token))
+        .filter(share_link::Column::RevokedAt.is_null())
+        .filter(
+            Condition::any()
+                .add(share_link::Column::ExpiresAt.is_null())
+                .add(share_link::Column::ExpiresAt.gt(now)),
+        )
+        .filter(
+            Condition::any()
+                .add(share_link::Column::MaxDownloads.is_null())
+                .add(
+                    Expr::col(share_link::Column::DownloadCount)
+                        .lt(Expr::col(share_link::Column::MaxDownloads)),
+                ),
+        )
+        .exec(&state.db)
+        .await;
+
+    let claimed = match claimed {
+        Ok(r) => r.rows_affected > 0,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let link = match share_link::Entity::find()
+        .filter(share_link::Column::Token.eq(&token))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(l)) => l,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::SHARE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    if !claimed {
+        tracing::info!(request_id = %request_id, share_id = link.id, "Share link no longer available");
+        return error_resp(StatusCode::GONE, request_id, &i18n::SHARE_UNAVAILABLE);
+    }
+
+    let file_entity = match file::Entity::find_by_id(link.file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => {
+            return error_resp(StatusCode::GONE, request_id, &i18n::SHARE_FILE_GONE);
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    tracing::info!(
+        request_id = %request_id,
+        share_id = link.id,
+        download_count = link.download_count,
+        "Shared file download"
+    );
+
+    let response = match shared_file(&state, file_entity, query.file_id, &request_id).await {
+        Ok(target) => match unavailable_reason(&target) {
+            Some(reason) => error_resp(StatusCode::FORBIDDEN, request_id.clone(), reason),
+            None => {
+                let mut response =
+                    stream_file(&target, request_id.clone(), "attachment", false).await;
+                if response.status().is_success() {
+                    file_stats::record_downloads(&state.db, &[target.id]).await;
+                    response = with_cache_headers(response, &state.config.download, &target, false);
+                }
+                response
+            }
+        },
+        Err(resp) => resp,
+    };
+
+    // Give the slot back if nothing was served
+    if !response.status().is_success() {
+        let _ = share_link::Entity::update_many()
+            .col_expr(
+                share_link::Column::DownloadCount,
+                Expr::col(share_link::Column::DownloadCount).sub(1),
+            )
+            .filter(share_link::Column::Id.eq(link.id))
+            .exec(&state.db)
+            .await;
+    }
+
+    response
+}
+
+/// File a download through a link serves, the shared file or one of the files below a shared folder
+async fn shared_file(
+    state: &AppState,
+    shared: file::Model,
+    file_id: Option<i32>,
+    request_id: &str,
+) -> Result<file::Model, Response> {
+    if shared.file_type != "folder" {
+        return Ok(shared);
+    }
+    let Some(file_id) = file_id else {
+        return Err(error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id.to_string(),
+            &i18n::SHARE_FILE_ID_REQUIRED,
+        ));
+    };
+
+    match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f))
+            if f.user_id == shared.user_id
+                && f.file_type == "file"
+                && file_utils::in_subtree(&f.path, &shared.path) =>
+        {
+            Ok(f)
+        }
+        Ok(_) => Err(error_resp(
+            StatusCode::NOT_FOUND,
+            request_id.to_string(),
+            &i18n::FILE_NOT_FOUND,
+        )),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ))
+        }
+    }
+}
+
+/// Active link for `token` with its file, without using up a download
+async fn find_active_share(
+    state: &AppState,
+    token: &str,
+    request_id: &str,
+) -> Result<(share_link::Model, file::Model), Response> {
+    let link = match share_link::Entity::find()
+        .filter(share_link::Column::Token.eq(token))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(l)) => l,
+        Ok(None) => {
+            return Err(error_resp(
+                StatusCode::NOT_FOUND,
+                request_id.to_string(),
+                &i18n::SHARE_NOT_FOUND,
+            ));
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ));
+        }
+    };
+
+    if !is_active(&link, Utc::now().naive_utc()) {
+        return Err(error_resp(
+            StatusCode::GONE,
+            request_id.to_string(),
+            &i18n::SHARE_UNAVAILABLE,
+        ));
+    }
+
+    match file::Entity::find_by_id(link.file_id).one(&state.db).await {
+        // Previews and metadata of held back files are not served either
+        Ok(Some(f)) => match unavailable_reason(&f) {
+            Some(reason) => Err(error_resp(
+                StatusCode::FORBIDDEN,
+                request_id.to_string(),
+                reason,
+            )),
+            None => Ok((link, f)),
+        },
+        Ok(None) => Err(error_resp(
+            StatusCode::GONE,
+            request_id.to_string(),
+            &i18n::SHARE_FILE_GONE,
+        )),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            Err(error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id.to_string(),
+                &i18n::COMMON_DATABASE_ERROR,
+            ))
+        }
+    }
+}
+
+/// List or search a shared folder, one page at a time
+/// Does not count as a download
+pub async fn list_shared_folder(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Query(query): Query<SharedListQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let (_, shared) = match find_active_share(&state, &token, &request_id).await {
+        Ok(found) => found,
+        Err(resp) => return resp,
+    };
+    if shared.file_type != "folder" {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::DOWNLOAD_NOT_FOLDER,
+        );
+    }
+
+    let relative = match file_utils::sanitize_path(query.path.as_deref().unwrap_or("/")) {
+        Ok(p) => match p.trim_end_matches('/') {
+            "" => "/".to_string(),
+            p => p.to_string(),
+        },
+        Err(e) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                format!("Invalid path: {}", e),
+            );
+        }
+    };
+    let shared_root = shared.path.trim_end_matches('/');
+    let base = match relative.as_str() {
+        "/" => shared.path.clone(),
+        r => format!("{}{}", shared_root, r),
+    };
+
+    let sort = match query.sort.as_deref() {
+        None | Some("name") => file::Column::Name,
+        Some("size") => file::Column::SizeBytes,
+        Some("modified") => file::Column::UpdatedAt,
+        Some(_) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::SHARE_INVALID_SORT,
+            );
+        }
+    };
+    let order = match query.order.as_deref() {
+        None | Some("asc") => Order::Asc,
+        Some("desc") => Order::Desc,
+        Some(_) => {
+            return error_resp(
+                StatusCode::BAD_REQUEST,
+                request_id,
+                &i18n::SHARE_INVALID_ORDER,
+            );
+        }
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SHARE_LIST_LIMIT)
+        .clamp(1, MAX_SHARE_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    if base != shared.path {
+        match file::Entity::find()
+            .filter(file::Column::UserId.eq(shared.user_id))
+            .filter(file::Column::Path.eq(&base))
+            .filter(file::Column::FileType.eq("folder"))
+            .one(&state.db)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return error_resp(
+                    StatusCode::NOT_FOUND,
+                    request_id,
+                    &i18n::FILE_FOLDER_NOT_FOUND,
+                );
+            }
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_DATABASE_ERROR,
+                );
+            }
+        }
+    }
+
+    // A name search goes through the whole subtree, otherwise only the folder's children are listed
+    let select = file::Entity::find().filter(file::Column::UserId.eq(shared.user_id));
+    let select = match query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(q) => select
+            .filter(
+                Expr::col(file::Column::Path)
+                    .like(LikeExpr::new(file_utils::descendant_pattern(&base)).escape('\\')),
+            )
+            .filter(file::Column::Name.contains(file_utils::normalize_name(q))),
+        None => select.filter(file::Column::ParentPath.eq(&base)),
+    };
+
+    let total = match select.clone().count(&state.db).await {
+        Ok(n) => n,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+    // "folder" sorts after "file", descending puts the folders first
+    let files = match select
+        .order_by_desc(file::Column::FileType)
+        .order_by(sort, order)
+        .order_by_asc(file::Column::Id)
+        .offset(offset)
+        .limit(limit)
+        .all(&state.db)
+        .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_DATABASE_ERROR,
+            );
+        }
+    };
+
+    let items = files
+        .into_iter()
+        .map(|f| SharedItem {
+            id: f.id,
+            path: f.path[shared_root.len()..].to_string(),
+            name: f.name,
+            file_type: f.file_type,
+            size_bytes: f.size_bytes,
+            mime_type: f.mime_type,
+            updated_at: timestamp::format(f.updated_at),
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::SHARE_FOLDER_LISTED,
+        Some(SharedListResponse {
+            path: relative,
+            total,
+            limit,
+            offset,
+            items,
+        }),
+    )
+}
+
+/// Metadata for rendering a rich preview of a share link, e.g. in chat apps
+/// Does not count as a download
+pub async fn unfurl_share_link(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let (link, file_entity) = match find_active_share(&state, &token, &request_id).await {
+        Ok(found) => found,
+        Err(resp) => return resp,
+    };
+
+    let url = format!(
+        "{}/api/public/shares/{}",
+        state.config.server.public_base_url(),
+        link.token
+    );
+    let thumbnail_url = photos::thumbnail(&file_entity)
+        .await
+        .map(|_| format!("{}/thumbnail", url));
+    let mime_type = file_entity
+        .mime_type
+        .clone()
+        .filter(|m| m != "application/octet-stream")
+        .unwrap_or_else(|| file_utils::get_mime_type(&file_entity.name));
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::SHARE_PREVIEW_RETRIEVED,
+        Some(ShareUnfurl {
+            name: file_entity.name,
+            size_bytes: file_entity.size_bytes.unwrap_or(0),
+            mime_type,
+            url,
+            thumbnail_url,
+            expires_at: link.expires_at.map(timestamp::format),
+        }),
+    )
+}
+
+/// Embedded thumbnail of a shared image, for link previews
+/// Does not count as a download
+pub async fn shared_thumbnail(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let (_, file_entity) = match find_active_share(&state, &token, &request_id).await {
+        Ok(found) => found,
+        Err(resp) => return resp,
+    };
+
+    let Some(thumbnail) = photos::thumbnail(&file_entity).await else {
+        return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::SHARE_NO_THUMBNAIL);
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/jpeg")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", SHARE_THUMBNAIL_MAX_AGE_SECS),
+        )
+        .body(Body::from(thumbnail))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_bounds() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(expiry(now, None, 24), Ok(None));
+        assert_eq!(
+            expiry(now, Some(24), 24),
+            Ok(Some(now + TimeDelta::hours(24)))
+        );
+        assert!(expiry(now, Some(0), 24).is_err());
+        assert!(expiry(now, Some(25), 24).is_err());
+    }
+
+    #[test]
+    fn test_oversized_expiry_is_rejected() {
+        let now = Utc::now().naive_utc();
+        for hours in [3_000_000_000, i64::MAX] {
+            assert!(expiry(now, Some(hours), 24 * 365).is_err());
+            // Even a misconfigured maximum must not overflow the date arithmetic
+            assert!(expiry(now, Some(hours), i64::MAX).is_err());
+        }
+    }
+}
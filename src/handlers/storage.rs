@@ -1,6 +1,7 @@
 use crate::{
+    services::storage_health::{self, VolumeHealth},
     utils::{
-        request_id,
+        i18n, request_id,
         response::{do_json_detail_resp, error_resp},
     },
     AppState,
@@ -11,7 +12,8 @@ use axum::{
     response::Response,
 };
 use serde::Serialize;
-use sysinfo::Disks;
+use std::path::Path;
+use sysinfo::{Disk, Disks};
 
 #[derive(Serialize)]
 pub struct StorageInfo {
@@ -20,87 +22,113 @@ pub struct StorageInfo {
     usage_percentage: f64,
 }
 
-pub async fn get_storage_info(State(state): State<AppState>, _request: Request) -> Response {
-    let request_id = request_id::generate_request_id();
+#[derive(Serialize)]
+pub struct Readiness {
+    database: bool,
+    failover: bool,
+    volumes: Vec<VolumeHealth>,
+}
 
-    tracing::info!(request_id = %request_id, "Get storage info request received");
+/// Readiness probe for load balancers and orchestrators
+/// Ready when the database answers and the volumes can take writes: all of them,
+/// or with failover at least one
+pub async fn readiness(State(state): State<AppState>) -> Response {
+    let request_id = request_id::generate_request_id();
 
-    let storage_dir = state.config.get_storage_dir();
-    let storage_path = match std::fs::canonicalize(&storage_dir) {
-        Ok(path) => path,
+    let database = match state.db.ping().await {
+        Ok(()) => true,
         Err(e) => {
-            tracing::error!(request_id = %request_id, error = %e, "Failed to canonicalize storage directory");
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Failed to access storage directory",
-            );
+            tracing::error!(request_id = %request_id, error = %e, "Readiness check: database unreachable");
+            false
         }
     };
 
-    tracing::debug!(request_id = %request_id, storage_path = ?storage_path, "Canonicalized storage path");
-
-    let disks = Disks::new_with_refreshed_list();
+    let failover = state.config.storage.failover;
+    let volumes = storage_health::snapshot();
+    let volumes_ready = if failover {
+        volumes.is_empty() || volumes.iter().any(|v| v.healthy)
+    } else {
+        volumes.iter().all(|v| v.healthy)
+    };
 
-    let storage_path_str = storage_path.to_string_lossy();
+    let (status, message) = if database && volumes_ready {
+        (StatusCode::OK, "Ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "Not ready")
+    };
 
-    let disk = match disks.iter().find(|d| {
-        let mount_point = d.mount_point();
-        let mount_str = mount_point.to_string_lossy();
+    do_json_detail_resp(
+        status,
+        request_id,
+        message,
+        Some(Readiness {
+            database,
+            failover,
+            volumes,
+        }),
+    )
+}
 
-        tracing::debug!(
-            request_id = %request_id,
-            mount_point = ?mount_point,
-            storage_path = ?storage_path,
-            "Checking disk mount point"
-        );
+/// Disk usage summed over the disks holding the storage volumes
+pub async fn get_storage_info(State(state): State<AppState>, _request: Request) -> Response {
+    let request_id = request_id::generate_request_id();
 
-        if storage_path.starts_with(mount_point) {
-            return true;
-        }
+    tracing::info!(request_id = %request_id, "Get storage info request received");
 
-        #[cfg(target_os = "windows")]
-        {
-            if let Some(storage_drive) = storage_path_str.chars().nth(4) {
-                if let Some(mount_drive) = mount_str.chars().next() {
-                    if storage_drive.to_ascii_uppercase() == mount_drive.to_ascii_uppercase() {
-                        tracing::debug!(
-                            request_id = %request_id,
-                            storage_drive = %storage_drive,
-                            mount_drive = %mount_drive,
-                            "Matched by drive letter"
-                        );
-                        return true;
-                    }
-                }
+    let disks = Disks::new_with_refreshed_list();
+    let mut used_disks: Vec<&Disk> = Vec::new();
+
+    for storage_dir in state.config.get_storage_volumes() {
+        let storage_path = match tokio::fs::canonicalize(&storage_dir).await {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, storage_dir = ?storage_dir, "Failed to canonicalize storage directory");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::STORAGE_ACCESS_FAILED,
+                );
             }
-        }
+        };
+
+        tracing::debug!(request_id = %request_id, storage_path = ?storage_path, "Canonicalized storage path");
 
-        false
-    }) {
-        Some(d) => d,
-        None => {
-            tracing::error!(
-                request_id = %request_id,
-                storage_path = ?storage_path,
-                "Disk not found for storage path. Available mount points:"
-            );
-            for disk in disks.iter() {
+        let disk = match disks
+            .iter()
+            .find(|d| disk_contains(d, &storage_path, &request_id))
+        {
+            Some(d) => d,
+            None => {
                 tracing::error!(
-                    mount_point = ?disk.mount_point(),
-                    "Available disk"
+                    request_id = %request_id,
+                    storage_path = ?storage_path,
+                    "Disk not found for storage path. Available mount points:"
+                );
+                for disk in disks.iter() {
+                    tracing::error!(
+                        mount_point = ?disk.mount_point(),
+                        "Available disk"
+                    );
+                }
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::STORAGE_DISK_INFO_UNAVAILABLE,
                 );
             }
-            return error_resp(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id,
-                "Disk information not available",
-            );
+        };
+
+        // Volumes sharing a disk count once
+        if !used_disks
+            .iter()
+            .any(|d| d.mount_point() == disk.mount_point())
+        {
+            used_disks.push(disk);
         }
-    };
+    }
 
-    let total_bytes = disk.total_space();
-    let available_bytes = disk.available_space();
+    let total_bytes: u64 = used_disks.iter().map(|d| d.total_space()).sum();
+    let available_bytes: u64 = used_disks.iter().map(|d| d.available_space()).sum();
     let used_bytes = total_bytes.saturating_sub(available_bytes);
 
     let usage_percentage = if total_bytes > 0 {
@@ -125,7 +153,44 @@ pub async fn get_storage_info(State(state): State<AppState>, _request: Request)
     do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "Storage info retrieved",
+        &i18n::STORAGE_INFO_RETRIEVED,
         Some(response),
     )
 }
+
+/// Whether `path` lives on `disk`
+fn disk_contains(disk: &Disk, path: &Path, request_id: &str) -> bool {
+    let mount_point = disk.mount_point();
+
+    tracing::debug!(
+        request_id = %request_id,
+        mount_point = ?mount_point,
+        storage_path = ?path,
+        "Checking disk mount point"
+    );
+
+    if path.starts_with(mount_point) {
+        return true;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let storage_path_str = path.to_string_lossy();
+        let mount_str = mount_point.to_string_lossy();
+        if let Some(storage_drive) = storage_path_str.chars().nth(4) {
+            if let Some(mount_drive) = mount_str.chars().next() {
+                if storage_drive.to_ascii_uppercase() == mount_drive.to_ascii_uppercase() {
+                    tracing::debug!(
+                        request_id = %request_id,
+                        storage_drive = %storage_drive,
+                        mount_drive = %mount_drive,
+                        "Matched by drive letter"
+                    );
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
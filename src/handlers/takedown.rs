@@ -0,0 +1,490 @@
+use crate::{
+    constants::ROLE_ADMIN,
+    entities::{file, takedown},
+    models::takedown::{
+        CounterNoticeRequest, IssueTakedownRequest, ResolveTakedownRequest, TakedownItem,
+        TakedownListQuery,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        takedown as takedowns,
+    },
+    utils::{
+        client::ClientInfo,
+        i18n,
+        jwt::Claims,
+        request_id,
+        response::{do_json_detail_resp, error_resp},
+        timestamp,
+    },
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+
+const MAX_REASON_LEN: usize = 2000;
+const MAX_STATEMENT_LEN: usize = 10000;
+
+fn to_item(t: takedown::Model, path: Option<String>) -> TakedownItem {
+    TakedownItem {
+        id: t.id,
+        file_id: t.file_id,
+        owner_id: t.owner_id,
+        path,
+        reason: t.reason,
+        issued_by: t.issued_by,
+        status: t.status,
+        counter_notice: t.counter_notice,
+        countered_at: t.countered_at.map(timestamp::format),
+        resolved_by: t.resolved_by,
+        resolution_note: t.resolution_note,
+        resolved_at: t.resolved_at.map(timestamp::format),
+        created_at: timestamp::format(t.created_at),
+    }
+}
+
+/// Takedowns as response items, with the paths of the files still present
+async fn to_items(
+    db: &DatabaseConnection,
+    records: Vec<takedown::Model>,
+) -> Result<Vec<TakedownItem>, sea_orm::DbErr> {
+    let file_ids: Vec<i32> = records.iter().map(|t| t.file_id).collect();
+    let mut paths: HashMap<i32, String> = file::Entity::find()
+        .filter(file::Column::Id.is_in(file_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|f| (f.id, f.path))
+        .collect();
+    Ok(records
+        .into_iter()
+        .map(|t| {
+            let path = paths.remove(&t.file_id);
+            to_item(t, path)
+        })
+        .collect())
+}
+
+/// Trimmed text of a request field, or the error to answer with
+fn required_text(value: &str, field: &str, max_len: usize) -> Result<String, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(format!("{} is required", field));
+    }
+    if value.chars().count() > max_len {
+        return Err(format!(
+            "{} is too long (maximum {} characters)",
+            field, max_len
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Disable downloads and shares of a file without deleting it (admin only)
+pub async fn issue_takedown(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(file_id): Path<i32>,
+    Json(req): Json<IssueTakedownRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let reason = match required_text(&req.reason, "reason", MAX_REASON_LEN) {
+        Ok(r) => r,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let f = match file::Entity::find_by_id(file_id).one(&state.db).await {
+        Ok(Some(f)) => f,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::FILE_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to load file");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+    if f.file_type == "folder" {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_FOLDER,
+        );
+    }
+    if f.taken_down {
+        return error_resp(
+            StatusCode::CONFLICT,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_EXISTS,
+        );
+    }
+
+    let record = match takedowns::issue(&state.db, &state.config.smtp, f, admin_id, reason).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to take file down");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    audit::record_file(
+        &state.db,
+        Some(admin_id),
+        AuditEvent::TakedownIssued,
+        &client,
+        Some(record.file_id),
+        Some(serde_json::json!({
+            "takedown_id": record.id,
+            "owner_id": record.owner_id,
+            "reason": record.reason,
+        })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        file_id = record.file_id,
+        takedown_id = record.id,
+        "File taken down"
+    );
+
+    do_json_detail_resp(
+        StatusCode::CREATED,
+        request_id,
+        &i18n::ADMIN_TAKEDOWN_ISSUED,
+        Some(to_item(record, None)),
+    )
+}
+
+/// Every takedown, optionally filtered by status (admin only)
+pub async fn list_takedowns(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<TakedownListQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_ONLY,
+        );
+    }
+
+    let status = query.status.as_deref().filter(|s| !s.is_empty());
+    if status.is_some_and(|s| !takedowns::STATUSES.contains(&s)) {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_INVALID_STATUS,
+        );
+    }
+
+    let items = match takedowns::list(&state.db, status, None).await {
+        Ok(records) => to_items(&state.db, records).await,
+        Err(e) => Err(e),
+    };
+    match items {
+        Ok(items) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::TAKEDOWN_RETRIEVED,
+            Some(items),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to list takedowns");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Restore a file or uphold its takedown, typically after a counter-notice (admin only)
+pub async fn resolve_takedown(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(req): Json<ResolveTakedownRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_ONLY,
+        );
+    }
+
+    let admin_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let note = req
+        .note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_REASON_LEN)
+    {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            format!("note is too long (maximum {} characters)", MAX_REASON_LEN),
+        );
+    }
+
+    let record = match takedown::Entity::find_by_id(id).one(&state.db).await {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::TAKEDOWN_NOT_FOUND)
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to load takedown");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+    if !takedowns::is_open(&record) {
+        return error_resp(
+            StatusCode::CONFLICT,
+            request_id,
+            &i18n::ADMIN_TAKEDOWN_CLOSED,
+        );
+    }
+
+    let resolved = match takedowns::resolve(
+        &state.db,
+        &state.config.smtp,
+        record,
+        admin_id,
+        req.restore,
+        note,
+    )
+    .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to resolve takedown");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    audit::record_file(
+        &state.db,
+        Some(admin_id),
+        if req.restore {
+            AuditEvent::TakedownRestored
+        } else {
+            AuditEvent::TakedownUpheld
+        },
+        &client,
+        Some(resolved.file_id),
+        Some(serde_json::json!({
+            "takedown_id": resolved.id,
+            "owner_id": resolved.owner_id,
+            "note": resolved.resolution_note,
+        })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        admin = %claims.sub,
+        takedown_id = resolved.id,
+        restored = req.restore,
+        "Takedown resolved"
+    );
+
+    let message = if req.restore {
+        &i18n::ADMIN_TAKEDOWN_RESTORED
+    } else {
+        &i18n::ADMIN_TAKEDOWN_UPHELD
+    };
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        message,
+        Some(to_item(resolved, None)),
+    )
+}
+
+/// Takedowns of the caller's files
+pub async fn my_takedowns(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let items = match takedowns::list(&state.db, None, Some(user_id)).await {
+        Ok(records) => to_items(&state.db, records).await,
+        Err(e) => Err(e),
+    };
+    match items {
+        Ok(items) => do_json_detail_resp(
+            StatusCode::OK,
+            request_id,
+            &i18n::TAKEDOWN_RETRIEVED,
+            Some(items),
+        ),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to list takedowns");
+            error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            )
+        }
+    }
+}
+
+/// Dispute the takedown of one of the caller's files, administrators review it
+pub async fn submit_counter_notice(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Json(req): Json<CounterNoticeRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let statement = match required_text(&req.statement, "statement", MAX_STATEMENT_LEN) {
+        Ok(s) => s,
+        Err(msg) => return error_resp(StatusCode::BAD_REQUEST, request_id, msg),
+    };
+
+    let record = match takedown::Entity::find_by_id(id)
+        .filter(takedown::Column::OwnerId.eq(user_id))
+        .one(&state.db)
+        .await
+    {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::TAKEDOWN_NOT_FOUND)
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to load takedown");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+    if record.status != takedowns::STATUS_ACTIVE {
+        return error_resp(
+            StatusCode::CONFLICT,
+            request_id,
+            &i18n::TAKEDOWN_COUNTER_NOT_ACTIVE,
+        );
+    }
+
+    let countered = match takedowns::counter(&state.db, &state.config.smtp, record, statement).await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Failed to record counter-notice");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    audit::record_file(
+        &state.db,
+        Some(user_id),
+        AuditEvent::TakedownCountered,
+        &client,
+        Some(countered.file_id),
+        Some(serde_json::json!({ "takedown_id": countered.id })),
+    )
+    .await;
+    tracing::info!(
+        request_id = %request_id,
+        user_id = user_id,
+        takedown_id = countered.id,
+        "Counter-notice submitted"
+    );
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::TAKEDOWN_COUNTERED,
+        Some(to_item(countered, None)),
+    )
+}
@@ -1,84 +1,805 @@
 use crate::{
-    entities::user,
-    models::auth::UserResponse,
+    constants::ROLE_ADMIN,
+    entities::{audit_log, user},
+    error::ErrorCode,
+    handlers,
+    models::{
+        auth::{
+            AccountDeletionResponse, ChangePasswordRequest, DeleteAccountRequest, SecurityLogEntry,
+            SecurityLogQuery, UpdateProfileRequest, UpdateUserSettingsRequest, UsageHistory,
+            UsageHistoryDay, UsageHistoryQuery, UserResponse, UserSettings,
+        },
+        job::JobInfo,
+    },
+    services::{
+        audit::{self, AuditEvent},
+        download, jobs,
+        mailer::{self, EmailTemplate},
+        staging, usage_history, user_cache,
+    },
     utils::{
+        archive::ArchiveFormat,
+        client::ClientInfo,
+        i18n,
         jwt::Claims,
-        request_id,
-        response::{do_json_detail_resp, error_resp},
+        password, request_id,
+        response::{do_json_detail_resp, error_code_resp, error_resp},
+        timestamp, validation,
     },
     AppState,
 };
 use axum::{
-    extract::{Request, State},
+    extract::{Query, State},
     http::StatusCode,
     response::Response,
+    Extension, Json,
 };
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde_json::json;
+
+const DEFAULT_SECURITY_LOG_LIMIT: u64 = 50;
+const MAX_SECURITY_LOG_LIMIT: u64 = 200;
+const DEFAULT_USAGE_HISTORY_DAYS: i64 = 30;
+const MAX_USAGE_HISTORY_DAYS: i64 = 366;
+
+/// How long the link confirming an email change stays valid
+const EMAIL_TOKEN_TTL_HOURS: i64 = 24;
+
+fn profile_response(user: user::Model) -> UserResponse {
+    UserResponse {
+        id: user.id,
+        username: user.username,
+        email: user.email,
+        display_name: user.display_name,
+        locale: user.locale,
+        timezone: user.timezone,
+        pending_email: user.pending_email,
+        deletion_scheduled_at: user.deletion_scheduled_at.map(timestamp::format),
+        created_at: timestamp::format(user.created_at),
+    }
+}
+
+/// Trimmed value of an optional profile field, `None` when it was cleared
+fn profile_field(
+    value: &str,
+    validate: fn(&str) -> anyhow::Result<()>,
+) -> anyhow::Result<Option<String>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    validate(value)?;
+    Ok(Some(value.to_string()))
+}
+
+pub async fn get_profile(Extension(user): Extension<user::Model>) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = user.id,
+        username = %user.username,
+        "User profile retrieved"
+    );
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::USER_PROFILE_RETRIEVED,
+        Some(profile_response(user)),
+    )
+}
+
+/// Update the current user's profile
+/// A new email is only stored as pending until the link mailed to it is opened
+pub async fn update_profile(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(user): Extension<user::Model>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Update profile request received");
+
+    let fields = (|| -> anyhow::Result<_> {
+        let field = |value: &Option<String>, validate| {
+            value
+                .as_deref()
+                .map(|v| profile_field(v, validate))
+                .transpose()
+        };
+        Ok((
+            field(&payload.display_name, validation::validate_display_name)?,
+            field(&payload.locale, validation::validate_locale)?,
+            field(&payload.timezone, validation::validate_timezone)?,
+        ))
+    })();
+    let (display_name, locale, timezone) = match fields {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = %e, "Validation failed: profile");
+            return error_code_resp(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::ValidationFailed,
+                request_id,
+                e.to_string(),
+            );
+        }
+    };
+
+    let mut active: user::ActiveModel = user.clone().into();
+    let mut changed = Vec::new();
+    if let Some(value) = display_name {
+        active.display_name = Set(value);
+        changed.push("display_name");
+    }
+    if let Some(value) = locale {
+        active.locale = Set(value);
+        changed.push("locale");
+    }
+    if let Some(value) = timezone {
+        active.timezone = Set(value);
+        changed.push("timezone");
+    }
+
+    let mut email_change = None;
+    if let Some(email) = payload.email.as_deref().map(str::trim) {
+        if let Err(e) = validation::validate_email(email, &state.config.validation) {
+            tracing::warn!(request_id = %request_id, error = %e, "Validation failed: email");
+            return error_code_resp(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::ValidationFailed,
+                request_id,
+                e.to_string(),
+            );
+        }
+
+        if email == user.email {
+            // Asking for the current address again cancels a pending change
+            active.pending_email = Set(None);
+            active.email_token = Set(None);
+            active.email_token_expires_at = Set(None);
+        } else {
+            let existing = match user::Entity::find()
+                .filter(user::Column::Email.eq(email))
+                .filter(user::Column::Id.ne(user.id))
+                .one(&state.db)
+                .await
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    tracing::error!(request_id = %request_id, error = %e, "Database error");
+                    return error_resp(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        request_id,
+                        &i18n::COMMON_INTERNAL_ERROR,
+                    );
+                }
+            };
+
+            if existing.is_some() {
+                tracing::warn!(request_id = %request_id, email = %email, "Email already exists");
+                return error_code_resp(
+                    StatusCode::BAD_REQUEST,
+                    ErrorCode::NameConflict,
+                    request_id,
+                    &i18n::AUTH_EMAIL_TAKEN,
+                );
+            }
+
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            let expires_at =
+                chrono::Utc::now().naive_utc() + chrono::Duration::hours(EMAIL_TOKEN_TTL_HOURS);
+            active.pending_email = Set(Some(email.to_string()));
+            active.email_token = Set(Some(token.clone()));
+            active.email_token_expires_at = Set(Some(expires_at));
+            email_change = Some((email.to_string(), token));
+        }
+    }
+
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    let user = match active.update(&state.db).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database update error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    user_cache::invalidate(user.id);
+
+    if !changed.is_empty() {
+        audit::record(
+            &state.db,
+            Some(user.id),
+            AuditEvent::ProfileUpdated,
+            &client,
+            Some(json!({ "fields": changed })),
+        )
+        .await;
+    }
+
+    let message = match email_change {
+        Some((email, token)) => {
+            let template = EmailTemplate::Verification {
+                username: user.username.clone(),
+                link: email_verification_link(&state, &token),
+            };
+            if let Err(e) = mailer::enqueue(&state.db, &state.config.smtp, &email, &template).await
+            {
+                tracing::error!(request_id = %request_id, error = %e, "Failed to queue verification email");
+            }
+
+            audit::record(
+                &state.db,
+                Some(user.id),
+                AuditEvent::EmailChangeRequested,
+                &client,
+                Some(json!({ "email": email })),
+            )
+            .await;
+
+            &i18n::USER_EMAIL_CHANGE_PENDING
+        }
+        None => &i18n::USER_PROFILE_UPDATED,
+    };
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Profile updated");
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        message,
+        Some(profile_response(user)),
+    )
+}
 
-pub async fn get_profile(State(state): State<AppState>, request: Request) -> Response {
+/// Link to the page confirming an email change
+fn email_verification_link(state: &AppState, token: &str) -> String {
+    format!(
+        "{}/verify-email?token={}",
+        state.config.server.public_base_url(),
+        token
+    )
+}
+
+/// Change the current user's password
+/// Bumps the token version so every previously issued token stops working
+pub async fn change_password(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Response {
     let request_id = request_id::generate_request_id();
 
-    tracing::info!(request_id = %request_id, "Get profile request received");
+    tracing::info!(request_id = %request_id, "Change password request received");
 
-    let claims = match request.extensions().get::<Claims>() {
-        Some(c) => c,
-        None => {
-            tracing::warn!(request_id = %request_id, "Unauthorized: no claims found");
-            return error_resp(StatusCode::UNAUTHORIZED, request_id, "Unauthorized");
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let user = match user::Entity::find_by_id(user_id).one(&state.db).await {
+        Ok(Some(u)) => u,
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
         }
     };
 
+    match password::verify_password(&payload.current_password, &user.password_hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!(request_id = %request_id, user_id = user_id, "Current password mismatch");
+            return error_resp(
+                StatusCode::UNAUTHORIZED,
+                request_id,
+                &i18n::USER_CURRENT_PASSWORD_INCORRECT,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Password verification error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    }
+
+    if let Err(e) = validation::validate_password(
+        &payload.new_password,
+        &[&user.username, &user.email],
+        &state.config.validation,
+    ) {
+        tracing::warn!(request_id = %request_id, error = %e, "Validation failed: password");
+        return error_code_resp(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
+            request_id,
+            e.to_string(),
+        );
+    }
+
+    let password_hash = match password::hash_password(
+        &payload.new_password,
+        &state.config.password_hashing,
+    )
+    .await
+    {
+        Ok(h) => h,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Password hashing error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let next_version = user.token_version + 1;
+    let mut active: user::ActiveModel = user.into();
+    active.password_hash = Set(password_hash);
+    active.token_version = Set(next_version);
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    let user = match active.update(&state.db).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database update error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    user_cache::invalidate(user.id);
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::PasswordChanged,
+        &client,
+        None,
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Password changed successfully");
+
+    // Existing sessions are now revoked, hand the caller a fresh token of the same kind
+    handlers::auth::token_response(
+        &state,
+        request_id,
+        StatusCode::OK,
+        &i18n::USER_PASSWORD_CHANGED,
+        user,
+        claims.csrf.is_some(),
+    )
+}
+
+/// List the current user's recent authentication events, newest first
+pub async fn get_security_log(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<SecurityLogQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
     let user_id = match claims.sub.parse::<i32>() {
         Ok(id) => id,
         Err(_) => {
-            tracing::error!(request_id = %request_id, "Invalid user ID in token");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Invalid user ID",
+                &i18n::COMMON_INVALID_USER_ID,
             );
         }
     };
 
-    // Query full user info from database
-    let user = match user::Entity::find()
-        .filter(user::Column::Id.eq(user_id))
-        .one(&state.db)
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SECURITY_LOG_LIMIT)
+        .clamp(1, MAX_SECURITY_LOG_LIMIT);
+
+    let entries = match audit_log::Entity::find()
+        .filter(audit_log::Column::UserId.eq(user_id))
+        .order_by_desc(audit_log::Column::CreatedAt)
+        .order_by_desc(audit_log::Column::Id)
+        .offset(query.offset.unwrap_or(0))
+        .limit(limit)
+        .all(&state.db)
         .await
     {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    let events: Vec<SecurityLogEntry> = entries
+        .into_iter()
+        .map(|e| SecurityLogEntry {
+            id: e.id,
+            event: e.event,
+            ip_address: e.ip_address,
+            user_agent: e.user_agent,
+            details: e.details.and_then(|d| serde_json::from_str(&d).ok()),
+            created_at: timestamp::format(e.created_at),
+        })
+        .collect();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::USER_SECURITY_LOG_RETRIEVED,
+        Some(events),
+    )
+}
+
+/// Daily storage usage of the caller, or of any account for administrators
+pub async fn get_usage_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<UsageHistoryQuery>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let caller_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INVALID_USER_ID,
+            );
+        }
+    };
+
+    let user_id = query.user_id.unwrap_or(caller_id);
+    if user_id != caller_id && claims.role != ROLE_ADMIN {
+        return error_resp(
+            StatusCode::FORBIDDEN,
+            request_id,
+            &i18n::USER_USAGE_HISTORY_OTHERS,
+        );
+    }
+
+    let owner = match user::Entity::find_by_id(user_id).one(&state.db).await {
         Ok(Some(u)) => u,
-        Ok(None) => {
-            tracing::warn!(request_id = %request_id, user_id = user_id, "User not found in database");
-            return error_resp(StatusCode::NOT_FOUND, request_id, "User not found");
+        Ok(None) => return error_resp(StatusCode::NOT_FOUND, request_id, &i18n::USER_NOT_FOUND),
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
         }
+    };
+
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_USAGE_HISTORY_DAYS)
+        .clamp(1, MAX_USAGE_HISTORY_DAYS);
+    let since = chrono::Utc::now().date_naive() - chrono::Duration::days(days - 1);
+
+    let snapshots = match usage_history::history(&state.db, user_id, since).await {
+        Ok(s) => s,
         Err(e) => {
             tracing::error!(request_id = %request_id, error = %e, "Database error");
             return error_resp(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 request_id,
-                "Internal server error",
+                &i18n::COMMON_INTERNAL_ERROR,
             );
         }
     };
 
-    tracing::info!(
-        request_id = %request_id,
-        user_id = user.id,
-        username = %user.username,
-        "User profile retrieved from database"
-    );
+    let growth_bytes = match (snapshots.first(), snapshots.last()) {
+        (Some(first), Some(last)) => last.used_bytes - first.used_bytes,
+        _ => 0,
+    };
+    let history = UsageHistory {
+        user_id,
+        quota_bytes: state.config.quota.limit_for(&owner.role),
+        growth_bytes,
+        days: snapshots
+            .into_iter()
+            .map(|s| UsageHistoryDay {
+                day: s.day.to_string(),
+                used_bytes: s.used_bytes,
+                file_count: s.file_count,
+            })
+            .collect(),
+    };
 
-    let response = UserResponse {
-        id: user.id,
-        username: user.username,
-        email: user.email,
-        created_at: user.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::USER_USAGE_HISTORY_RETRIEVED,
+        Some(history),
+    )
+}
+
+/// Get the current user's account settings
+pub async fn get_settings(Extension(user): Extension<user::Model>) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::USER_SETTINGS_RETRIEVED,
+        Some(UserSettings {
+            login_alerts: user.login_alerts_enabled,
+        }),
+    )
+}
+
+/// Update the current user's account settings
+pub async fn update_settings(
+    State(state): State<AppState>,
+    Extension(user): Extension<user::Model>,
+    Json(payload): Json<UpdateUserSettingsRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    let mut active: user::ActiveModel = user.into();
+    if let Some(login_alerts) = payload.login_alerts {
+        active.login_alerts_enabled = Set(login_alerts);
+    }
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    let user = match active.update(&state.db).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database update error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
     };
 
+    user_cache::invalidate(user.id);
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "User settings updated");
+
+    do_json_detail_resp(
+        StatusCode::OK,
+        request_id,
+        &i18n::USER_SETTINGS_UPDATED,
+        Some(UserSettings {
+            login_alerts: user.login_alerts_enabled,
+        }),
+    )
+}
+
+/// Schedule deletion of the current account after the configured grace period
+/// An archive of the account's files is prepared so it can be downloaded one last time
+pub async fn delete_account(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(user): Extension<user::Model>,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Account deletion request received");
+
+    if user.deletion_scheduled_at.is_some() {
+        return error_code_resp(
+            StatusCode::CONFLICT,
+            ErrorCode::Conflict,
+            request_id,
+            &i18n::USER_DELETION_ALREADY_SCHEDULED,
+        );
+    }
+
+    match password::verify_password(&payload.password, &user.password_hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!(request_id = %request_id, user_id = user.id, "Password mismatch");
+            return error_resp(
+                StatusCode::UNAUTHORIZED,
+                request_id,
+                &i18n::USER_PASSWORD_INCORRECT,
+            );
+        }
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Password verification error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    }
+
+    if user.role == ROLE_ADMIN {
+        let other_admins = match user::Entity::find()
+            .filter(user::Column::Role.eq(ROLE_ADMIN))
+            .filter(user::Column::Id.ne(user.id))
+            .filter(user::Column::DeletionScheduledAt.is_null())
+            .count(&state.db)
+            .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::error!(request_id = %request_id, error = %e, "Database error");
+                return error_resp(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    request_id,
+                    &i18n::COMMON_INTERNAL_ERROR,
+                );
+            }
+        };
+
+        if other_admins == 0 {
+            return error_resp(StatusCode::BAD_REQUEST, request_id, &i18n::USER_LAST_ADMIN);
+        }
+    }
+
+    let scheduled_at = chrono::Utc::now().naive_utc()
+        + chrono::Duration::days(state.config.account_deletion.grace_period_days);
+
+    let mut active: user::ActiveModel = user.clone().into();
+    active.deletion_scheduled_at = Set(Some(scheduled_at));
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    if let Err(e) = active.update(&state.db).await {
+        tracing::error!(request_id = %request_id, error = %e, "Database update error");
+        return error_resp(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+            &i18n::COMMON_INTERNAL_ERROR,
+        );
+    }
+
+    user_cache::invalidate(user.id);
+
+    let export = start_export(&state, &user, &request_id).await;
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::AccountDeletionRequested,
+        &client,
+        Some(json!({
+            "scheduled_at": timestamp::format(scheduled_at),
+            "export_job_id": export.as_ref().map(|j| j.id),
+        })),
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, scheduled_at = %scheduled_at, "Account deletion scheduled");
+
+    do_json_detail_resp(
+        StatusCode::ACCEPTED,
+        request_id,
+        &i18n::USER_DELETION_SCHEDULED,
+        Some(AccountDeletionResponse {
+            deletion_scheduled_at: timestamp::format(scheduled_at),
+            export,
+        }),
+    )
+}
+
+/// Queue an archive of all the user's files, skipped when there is nothing or no room for it
+async fn start_export(state: &AppState, user: &user::Model, request_id: &str) -> Option<JobInfo> {
+    let collected = match download::collect_user_files(&state.db, user.id, &user.username).await {
+        Ok(c) if !c.is_empty() => c,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = %e, "Failed to collect files for export");
+            return None;
+        }
+    };
+
+    let total_size = download::calculate_total_size(&collected.files);
+    if let Err(msg) = staging::ensure_space(&state.config, total_size as u64).await {
+        tracing::warn!(request_id = %request_id, total_size = total_size, reason = %msg, "No staging space for account export");
+        return None;
+    }
+
+    let should_compress = total_size as usize > state.config.batch_download.compression_threshold;
+    match jobs::start_batch_download(
+        &state.db,
+        &state.config,
+        &state.events,
+        user.id,
+        collected,
+        ArchiveFormat::Zip,
+        should_compress,
+    )
+    .await
+    {
+        Ok(job) => Some(jobs::job_info(job)),
+        Err(e) => {
+            tracing::warn!(request_id = %request_id, error = %e, "Failed to queue account export");
+            None
+        }
+    }
+}
+
+/// Cancel a scheduled deletion of the current account during its grace period
+pub async fn cancel_account_deletion(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Extension(user): Extension<user::Model>,
+) -> Response {
+    let request_id = request_id::generate_request_id();
+
+    if user.deletion_scheduled_at.is_none() {
+        return error_resp(
+            StatusCode::BAD_REQUEST,
+            request_id,
+            &i18n::USER_DELETION_NOT_SCHEDULED,
+        );
+    }
+
+    let mut active: user::ActiveModel = user.into();
+    active.deletion_scheduled_at = Set(None);
+    active.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    let user = match active.update(&state.db).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::error!(request_id = %request_id, error = %e, "Database update error");
+            return error_resp(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                request_id,
+                &i18n::COMMON_INTERNAL_ERROR,
+            );
+        }
+    };
+
+    user_cache::invalidate(user.id);
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::AccountDeletionCancelled,
+        &client,
+        None,
+    )
+    .await;
+
+    tracing::info!(request_id = %request_id, user_id = user.id, "Account deletion cancelled");
+
     do_json_detail_resp(
         StatusCode::OK,
         request_id,
-        "User profile retrieved",
-        Some(response),
+        &i18n::USER_DELETION_CANCELLED,
+        Some(profile_response(user)),
     )
 }
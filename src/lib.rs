@@ -12,10 +12,18 @@ pub mod services;
 pub mod utils;
 
 use sea_orm::DatabaseConnection;
+use std::sync::Arc;
 
 /// Application state shared across all handlers
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
     pub config: config::Config,
+    pub jwt_keys: Arc<utils::jwt::JwtKeyring>,
+    /// Job progress updates for `/api/events`
+    pub events: services::events::EventBus,
+    /// External search engine, `None` when file search runs in the database
+    pub search: Option<Arc<dyn services::search::SearchBackend>>,
+    /// Requests in progress, capped when load shedding is on
+    pub load: Arc<services::load::LoadTracker>,
 }
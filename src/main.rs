@@ -1,6 +1,17 @@
-use cloud_drive::{config::Config, db, routes, AppState};
+use cloud_drive::{
+    config::Config,
+    db, routes,
+    services::{
+        account_deletion, backup, cleanup, events::EventBus, folder_sizes, job_scheduler, jobs,
+        library, load::LoadTracker, log_shipping::LogShipper, mailer, mounts, replication, search,
+        staging, storage_health, usage_history,
+    },
+    utils::{cookie, json_log::JsonLayer, jwt::JwtKeyring},
+    AppState,
+};
 use sea_orm::DatabaseConnection;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use std::{net::SocketAddr, sync::Arc};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -13,16 +24,82 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize logging system
     init_logging(&config);
+    job_scheduler::configure(&config.jobs);
+
+    // Maintenance commands run instead of the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some((command, rest)) = args.split_first() {
+        return run_command(&config, command, rest).await;
+    }
 
     tracing::info!("Starting file management server...");
 
     // Setup database connection and schema
     let db = init_database(&config).await?;
 
+    // Load JWT signing/verification keys
+    let jwt_keys = JwtKeyring::from_config(&config.security)
+        .map_err(|e| anyhow::anyhow!("Invalid JWT key configuration: {}", e))?;
+    cookie::validate_config(&config.security.session_cookie)
+        .map_err(|e| anyhow::anyhow!("Invalid session cookie configuration: {}", e))?;
+
+    let events = EventBus::new();
+
+    // Remove expired batch-download archives in the background
+    jobs::spawn_sweeper(db.clone(), config.clone(), events.clone());
+
+    // Remove stale temporary artifacts in the background
+    staging::spawn_sweeper(db.clone(), config.clone());
+
+    // Probe the storage volumes, the first round before any request is served
+    storage_health::check_all(&config).await;
+    storage_health::spawn_prober(config.clone());
+
+    // Back up user files on a schedule
+    backup::spawn_scheduler(db.clone(), config.clone());
+
+    // Mirror stored files to the secondary target
+    replication::spawn_worker(db.clone(), config.clone());
+
+    // Keep the file index of external folder mounts current
+    mounts::spawn_indexer(db.clone(), config.clone());
+
+    // Recompute cached folder sizes in the background
+    folder_sizes::spawn_reconciler(db.clone(), config.clone());
+
+    // Purge accounts whose deletion grace period has ended
+    account_deletion::spawn_sweeper(db.clone(), config.clone());
+
+    // Take the daily storage usage snapshots behind the usage history
+    usage_history::spawn_recorder(db.clone(), config.clone());
+
+    // Run the users' cleanup rules, announcing the files due before deleting them
+    cleanup::spawn_scheduler(db.clone(), config.clone(), events.clone());
+
+    // Deliver queued emails in the background
+    if config.smtp.enabled {
+        mailer::spawn_worker(db.clone(), config.smtp.clone());
+    }
+
+    // Feed file names to the external search engine, if one is configured
+    let search = search::from_config(&config.search)
+        .map_err(|e| anyhow::anyhow!("Invalid search configuration: {}", e))?;
+    if let Some(backend) = &search {
+        search::spawn_indexer(
+            db.clone(),
+            backend.clone(),
+            config.search.sync_interval_secs,
+        );
+    }
+
     // Create application state
     let state = AppState {
         db,
         config: config.clone(),
+        jwt_keys: Arc::new(jwt_keys),
+        events,
+        search,
+        load: Arc::new(LoadTracker::new(&config.load_shedding)),
     };
 
     // Setup routes
@@ -32,12 +109,70 @@ async fn main() -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(config.server_address()).await?;
     tracing::info!("Server listening on {}", config.server_address());
 
-    axum::serve(listener, app).await?;
+    // Connection info gives handlers the client address for audit logging
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
-/// Initialize logging system with file and console output
+const USAGE: &str = "Usage:
+  cloud_drive                      start the server
+  cloud_drive backup               run a file backup now
+  cloud_drive restore <dir> [--run <id>] [--user <id>]
+                                   rebuild the backed-up files into <dir>/<user_id>/...";
+
+/// Run a maintenance command from the command line
+async fn run_command(config: &Config, command: &str, args: &[String]) -> anyhow::Result<()> {
+    match command {
+        "backup" => {
+            let db = init_database(config).await?;
+            let run = backup::run(&db, config).await?;
+            println!(
+                "Backup run {} completed ({}, {} entries, {} bytes copied)",
+                run.id,
+                if run.full { "full" } else { "incremental" },
+                run.entries,
+                run.bytes
+            );
+            Ok(())
+        }
+        "restore" => {
+            let mut output = None;
+            let mut run_id = None;
+            let mut user_id = None;
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--run" => run_id = Some(flag_value(args.next(), "--run")?),
+                    "--user" => user_id = Some(flag_value(args.next(), "--user")?),
+                    dir if output.is_none() && !dir.starts_with("--") => {
+                        output = Some(std::path::PathBuf::from(dir))
+                    }
+                    other => anyhow::bail!("Unexpected argument '{}'\n{}", other, USAGE),
+                }
+            }
+            let output =
+                output.ok_or_else(|| anyhow::anyhow!("Missing output directory\n{}", USAGE))?;
+
+            let restored = backup::restore(config, &output, run_id, user_id)?;
+            println!("Restored {} files into {}", restored, output.display());
+            Ok(())
+        }
+        _ => anyhow::bail!("Unknown command '{}'\n{}", command, USAGE),
+    }
+}
+
+fn flag_value(value: Option<&String>, flag: &str) -> anyhow::Result<i32> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("{} needs a numeric value", flag))
+}
+
+/// Initialize logging system with console, file and log shipping output
 fn init_logging(config: &Config) {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         EnvFilter::new(format!(
@@ -46,45 +181,58 @@ fn init_logging(config: &Config) {
         ))
     });
 
-    let console_layer = fmt::layer();
+    let json = config.logging.is_json();
+    let mut layers = Vec::new();
+    layers.push(if json {
+        JsonLayer::new(std::io::stdout).boxed()
+    } else {
+        fmt::layer().boxed()
+    });
 
     if config.logging.log_to_file {
         if let Some(log_dir) = &config.logging.log_dir {
             // File appender with daily rotation
             let file_appender = tracing_appender::rolling::daily(log_dir, "cloud_drive");
-            let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-            let file_layer = fmt::layer().with_writer(non_blocking).with_ansi(false);
-
-            tracing_subscriber::registry()
-                .with(env_filter)
-                .with(console_layer)
-                .with(file_layer)
-                .init();
+            let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+            layers.push(if json {
+                JsonLayer::new(non_blocking).boxed()
+            } else {
+                fmt::layer()
+                    .with_writer(non_blocking)
+                    .with_ansi(false)
+                    .boxed()
+            });
 
             // Keep guard alive for the lifetime of the program
-            std::mem::forget(_guard);
-            return;
+            std::mem::forget(guard);
         }
     }
 
-    // Console-only logging
+    // Shipped lines are always JSON, collectors need not parse free text
+    if let Some(shipper) = LogShipper::spawn(&config.logging) {
+        layers.push(JsonLayer::new(shipper).boxed());
+    }
+
     tracing_subscriber::registry()
+        .with(layers)
         .with(env_filter)
-        .with(console_layer)
         .init();
 }
 
 /// Initialize database connection and schema
 async fn init_database(config: &Config) -> anyhow::Result<DatabaseConnection> {
     // Connect to database
-    let db = db::create_connection(config.database_url()).await?;
+    let db = db::create_connection(&config.database).await?;
 
     // Initialize tables
-    db::init_database(&db).await?;
+    db::init_database(&db, &config.password_hashing).await?;
 
     // Run database migrations
     db::migrate_database(&db).await?;
 
+    // Account owning the Shared Library tree
+    library::init(&db).await?;
+
     // Create indexes for optimal performance
     if let Err(e) = cloud_drive::db_indexes::create_composite_indexes(&db).await {
         tracing::warn!("Failed to create some indexes: {:?}", e);
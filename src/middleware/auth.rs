@@ -1,7 +1,14 @@
-use crate::{error::AppError, utils::jwt, AppState};
+use crate::{
+    config::SessionCookieConfig,
+    error::AppError,
+    middleware::timeout::ResponseUser,
+    services::user_cache,
+    utils::{cookie, i18n, jwt},
+    AppState,
+};
 use axum::{
     extract::{Request, State},
-    http::header,
+    http::{header, HeaderMap},
     middleware::Next,
     response::Response,
 };
@@ -12,37 +19,79 @@ pub async fn auth_middleware(
     mut request: Request,
     next: Next,
 ) -> Response {
-    // Get Authorization header
-    let auth_header = match request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-    {
-        Some(h) => h,
-        None => {
-            return AppError::Auth("Missing authorization header".to_string()).into_response();
-        }
-    };
+    let session_config = &state.config.security.session_cookie;
 
-    // Verify Bearer token format
-    let token = match auth_header.strip_prefix("Bearer ") {
-        Some(t) => t,
-        None => {
-            return AppError::Auth("Invalid authorization header format".to_string())
-                .into_response();
+    let (token, from_cookie) = match extract_token(request.headers(), session_config) {
+        Ok(Some(t)) => t,
+        Ok(None) => {
+            return AppError::Auth(&i18n::AUTH_MISSING_HEADER).into_response();
         }
+        Err(e) => return e.into_response(),
     };
 
     // Verify JWT token
-    let claims = match jwt::validate_token(token, state.config.jwt_secret()) {
+    let claims = match jwt::validate_token(&token, &state.jwt_keys) {
         Ok(c) => c,
         Err(_) => {
-            return AppError::Auth("Invalid or expired token".to_string()).into_response();
+            return AppError::Auth(&i18n::AUTH_INVALID_TOKEN).into_response();
+        }
+    };
+
+    // Cookies are sent automatically by the browser, so mutating requests
+    // must echo the CSRF token bound to the session
+    if from_cookie && !request.method().is_safe() {
+        let provided = request
+            .headers()
+            .get(cookie::CSRF_HEADER)
+            .and_then(|h| h.to_str().ok());
+
+        if claims.csrf.is_none() || provided != claims.csrf.as_deref() {
+            return AppError::Forbidden(&i18n::AUTH_INVALID_CSRF).into_response();
+        }
+    }
+
+    // Reject tokens issued before the user's token version was bumped
+    let user_id = match claims.sub.parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return AppError::Auth(&i18n::AUTH_INVALID_TOKEN).into_response();
         }
     };
 
-    // Store user info in request extensions
+    let user = match user_cache::load(&state.db, user_id).await {
+        Ok(Some(u)) if u.token_version == claims.ver => u,
+        Ok(_) => return AppError::Auth(&i18n::AUTH_TOKEN_REVOKED).into_response(),
+        Err(e) => return AppError::Database(e).into_response(),
+    };
+
+    // Store user info in request extensions so handlers need not fetch the user again
     request.extensions_mut().insert(claims);
+    request.extensions_mut().insert(user);
+
+    let mut response = next.run(request).await;
+    response.extensions_mut().insert(ResponseUser(user_id));
+    response
+}
+
+/// Extract the request token, preferring the Authorization header over the session cookie
+/// Returns the token and whether it came from the cookie
+pub fn extract_token(
+    headers: &HeaderMap,
+    session_config: &SessionCookieConfig,
+) -> Result<Option<(String, bool)>, AppError> {
+    if let Some(auth_header) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+    {
+        return match auth_header.strip_prefix("Bearer ") {
+            Some(t) => Ok(Some((t.to_string(), false))),
+            None => Err(AppError::Auth(&i18n::AUTH_INVALID_HEADER)),
+        };
+    }
+
+    if !session_config.enabled {
+        return Ok(None);
+    }
 
-    next.run(request).await
+    Ok(cookie::get_cookie(headers, &session_config.cookie_name).map(|t| (t, true)))
 }
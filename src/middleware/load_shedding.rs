@@ -0,0 +1,51 @@
+use crate::{
+    error::ErrorCode,
+    utils::{i18n, request_id, response::error_code_resp},
+    AppState,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+/// Probes have to answer while the server is busy
+const EXEMPT_ROUTES: [&str; 2] = ["/health", "/health/ready"];
+
+/// Routes no handler matched share one entry, so random URLs cannot grow the counters
+const UNMATCHED_ROUTE: &str = "(unmatched)";
+
+/// Count requests in progress per route and refuse them with 503 over the configured limits
+pub async fn load_shedding_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, |p| p.as_str())
+        .to_string();
+    if EXEMPT_ROUTES.contains(&route.as_str()) {
+        return next.run(request).await;
+    }
+
+    let Some(_in_flight) = state.load.admit(&route).await else {
+        let request_id = request_id::generate_request_id();
+        tracing::warn!(request_id = %request_id, route = %route, "Request shed, server is overloaded");
+        let mut response = error_code_resp(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Overloaded,
+            request_id,
+            &i18n::COMMON_SERVER_BUSY,
+        );
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(state.config.load_shedding.retry_after_secs),
+        );
+        return response;
+    };
+
+    next.run(request).await
+}
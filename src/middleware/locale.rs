@@ -0,0 +1,33 @@
+use crate::utils::{i18n, response::PROBLEM_JSON};
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// Translate response messages into the language requested by `Accept-Language`
+pub async fn locale_middleware(request: Request, next: Next) -> Response {
+    let language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|h| h.to_str().ok())
+        .map(i18n::negotiate)
+        .unwrap_or_default();
+
+    let mut response = i18n::scope(language, next.run(request)).await;
+
+    // Only API responses carry messages, file contents keep their own language
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json") || v.starts_with(PROBLEM_JSON));
+    if is_json {
+        response.headers_mut().insert(
+            header::CONTENT_LANGUAGE,
+            HeaderValue::from_static(language.tag()),
+        );
+    }
+    response
+}
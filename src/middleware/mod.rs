@@ -1 +1,7 @@
 pub mod auth;
+pub mod load_shedding;
+pub mod locale;
+pub mod problem;
+pub mod timeout;
+pub mod timestamp_format;
+pub mod upload_limit;
@@ -0,0 +1,13 @@
+use crate::utils::response;
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
+
+/// Render error responses as RFC 7807 problem details when the client asks for them
+pub async fn problem_details_middleware(request: Request, next: Next) -> Response {
+    let enabled = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(response::accepts_problem_json);
+
+    response::with_problem_details(enabled, next.run(request)).await
+}
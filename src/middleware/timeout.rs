@@ -0,0 +1,88 @@
+use crate::{
+    error::ErrorCode,
+    utils::{i18n, request_id, response::error_code_resp},
+    AppState,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use std::time::{Duration, Instant};
+
+/// Authenticated user of a request, attached to the response by the auth middleware
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseUser(pub i32);
+
+/// Name of the matched route, or the raw path when no route matched
+fn route_of(request: &Request) -> String {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string())
+}
+
+/// Abort requests running longer than the configured timeout
+/// Only layered on routers whose requests are expected to finish quickly, transfers are never cut off
+pub async fn request_timeout_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let timeout_secs = state.config.server.request_timeout_secs;
+    if timeout_secs == 0 {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let route = route_of(&request);
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            let request_id = request_id::generate_request_id();
+            tracing::error!(
+                request_id = %request_id,
+                method = %method,
+                route = %route,
+                timeout_secs = timeout_secs,
+                "Request timed out"
+            );
+            error_code_resp(
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorCode::RequestTimeout,
+                request_id,
+                &i18n::COMMON_REQUEST_TIMEOUT,
+            )
+        }
+    }
+}
+
+/// Log requests slower than the configured threshold, transfers included
+pub async fn slow_request_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let slow_request_ms = state.config.server.slow_request_ms;
+    let method = request.method().clone();
+    let route = route_of(&request);
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    let elapsed = started.elapsed();
+    if slow_request_ms > 0 && elapsed >= Duration::from_millis(slow_request_ms) {
+        tracing::warn!(
+            method = %method,
+            route = %route,
+            user_id = ?response.extensions().get::<ResponseUser>().map(|u| u.0),
+            status = response.status().as_u16(),
+            duration_ms = elapsed.as_millis() as u64,
+            "Slow request"
+        );
+    }
+
+    response
+}
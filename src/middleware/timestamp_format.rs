@@ -0,0 +1,14 @@
+use crate::utils::timestamp;
+use axum::{extract::Request, middleware::Next, response::Response};
+
+/// Render the timestamps of a response in the format asked for by `X-Timestamp-Format`
+/// Clients still parsing the legacy format opt in per request while they migrate to RFC 3339
+pub async fn timestamp_format_middleware(request: Request, next: Next) -> Response {
+    let legacy = request
+        .headers()
+        .get(timestamp::FORMAT_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case(timestamp::FORMAT_LEGACY));
+
+    timestamp::scope(legacy, next.run(request)).await
+}
@@ -0,0 +1,49 @@
+use crate::{
+    utils::{i18n, jwt::Claims, request_id, response::error_resp},
+    AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use http_body_util::Limited;
+
+/// Hold upload bodies to the size allowed for the caller's role
+/// The routes it guards skip the global body limit, so it has to run after authentication
+pub async fn upload_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let role = request
+        .extensions()
+        .get::<Claims>()
+        .map(|c| c.role.clone())
+        .unwrap_or_default();
+    let Some(limit) = state.config.server.max_upload_size_for(&role) else {
+        return next.run(request).await;
+    };
+
+    // Fail fast on the declared size, bodies without one are cut off once they exceed it
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|size| size > limit) {
+        let request_id = request_id::generate_request_id();
+        tracing::warn!(request_id = %request_id, role = %role, limit = limit, "Upload over the role limit refused");
+        return error_resp(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            request_id,
+            &i18n::UPLOAD_ROLE_LIMIT_EXCEEDED,
+        );
+    }
+
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    next.run(request.map(|body| Body::new(Limited::new(body, limit))))
+        .await
+}
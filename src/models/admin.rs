@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+
+/// Admin audit log query filters
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub user_id: Option<i32>,
+    /// Event type, e.g. "login_failed"
+    pub action: Option<String>,
+    pub file_id: Option<i32>,
+    /// Inclusive lower bound, "YYYY-MM-DD" or "YYYY-MM-DD HH:MM:SS" (UTC)
+    pub from: Option<String>,
+    /// Inclusive upper bound, a bare date covers the whole day
+    pub to: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    /// Export format: "csv" (default) or "jsonl"
+    pub format: Option<String>,
+}
+
+/// Audit log entry as seen by administrators
+#[derive(Debug, Serialize)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub event: String,
+    pub file_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// One page of audit log results
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+    pub entries: Vec<AuditLogEntry>,
+}
+
+/// Report size query
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    pub limit: Option<u64>,
+}
+
+/// Entry of the most downloaded files report
+#[derive(Debug, Serialize)]
+pub struct MostDownloadedFile {
+    pub file_id: i32,
+    pub name: String,
+    pub path: String,
+    pub owner_id: i32,
+    pub download_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_downloaded_at: Option<String>,
+}
+
+/// Admin dashboard query
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// Number of days covered by the time series (default 30)
+    pub days: Option<i64>,
+}
+
+/// Aggregated numbers for the admin dashboard
+#[derive(Debug, Serialize)]
+pub struct AdminStats {
+    pub window_days: i64,
+    pub users: UserStats,
+    pub uploads_per_day: Vec<DailyCount>,
+    pub downloads_per_day: Vec<DailyCount>,
+    pub storage_growth: Vec<StorageGrowthPoint>,
+    pub deduplication: DedupStats,
+    pub top_consumers: Vec<StorageConsumer>,
+    pub database_pool: DatabasePoolStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserStats {
+    pub total: u64,
+    /// Users who signed in during the window
+    pub active: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyCount {
+    pub day: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageGrowthPoint {
+    pub day: String,
+    /// Bytes of files added that day
+    pub added_bytes: i64,
+    /// Logical bytes stored at the end of the day
+    pub total_bytes: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DedupStats {
+    /// Sum of all file sizes as users see them
+    pub logical_bytes: i64,
+    /// Bytes actually stored on disk
+    pub physical_bytes: i64,
+    pub saved_bytes: i64,
+    /// Bytes still stored more than once with identical content
+    pub reclaimable_bytes: i64,
+}
+
+/// Connection pool usage at the time of the request
+#[derive(Debug, Serialize)]
+pub struct DatabasePoolStats {
+    /// Open connections, busy or idle
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageConsumer {
+    pub user_id: i32,
+    pub username: String,
+    pub file_count: i64,
+    pub total_bytes: i64,
+}
+
+/// Disk usage of the temp/staging directory
+#[derive(Debug, Serialize)]
+pub struct StagingUsage {
+    pub dir: String,
+    pub used_bytes: u64,
+    pub file_count: u64,
+    /// Configured limit for temporary data
+    pub max_bytes: u64,
+    pub usage_percentage: f64,
+    /// Free space on the volume holding the staging directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_available_bytes: Option<u64>,
+    pub areas: Vec<StagingAreaUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StagingAreaUsage {
+    pub name: String,
+    pub used_bytes: u64,
+    pub file_count: u64,
+}
+
+/// Relocate stored files to another directory or volume
+#[derive(Debug, Deserialize)]
+pub struct StorageMigrationRequest {
+    /// New storage root, absolute path
+    pub destination: String,
+    /// Only move this user's files, all users when omitted
+    pub user_id: Option<i32>,
+}
+
+/// Replication state of the stored files
+#[derive(Debug, Serialize)]
+pub struct ReplicationReport {
+    pub enabled: bool,
+    pub target: Option<String>,
+    pub replicated: u64,
+    /// Files whose current content has no replica yet, including failed ones
+    pub lagging_count: u64,
+    pub failed: u64,
+    pub missing_count: u64,
+    /// Oldest lagging files first
+    pub lagging: Vec<ReplicaItem>,
+    /// Marked replicated but the replica is gone from the target
+    pub missing: Vec<ReplicaItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplicaItem {
+    pub file_id: i32,
+    pub owner_id: i32,
+    pub path: String,
+    pub status: Option<String>,
+    pub updated_at: String,
+    pub replicated_at: Option<String>,
+    pub replica_path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupRunItem {
+    pub id: i32,
+    pub status: String,
+    pub full: bool,
+    pub entries: i64,
+    pub bytes: i64,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Consistency report query
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyQuery {
+    /// Maximum number of issues listed, all of them are counted
+    pub limit: Option<u64>,
+}
+
+/// Repairs to apply to the issues found by a consistency check
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyRepairRequest {
+    /// remove_missing, fix_sizes, create_folders and/or restore_parents
+    pub actions: Vec<String>,
+}
+
+/// Database records that do not match the physical storage
+#[derive(Debug, Serialize)]
+pub struct ConsistencyReport {
+    /// Records checked against storage
+    pub checked: u64,
+    /// Records on unavailable volumes, left out of the check
+    pub skipped: u64,
+    pub missing_files: u64,
+    pub size_mismatches: u64,
+    pub missing_folders: u64,
+    pub orphaned_children: u64,
+    pub issues: Vec<ConsistencyIssue>,
+    /// Set when repairs were requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repaired: Option<ConsistencyRepairs>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsistencyIssue {
+    /// missing_file, size_mismatch, missing_folder or orphaned_child
+    pub kind: &'static str,
+    pub file_id: i32,
+    pub owner_id: i32,
+    pub path: String,
+    pub storage_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_size: Option<i64>,
+    /// Repair action fixing the issue
+    pub repair: &'static str,
+}
+
+/// Number of issues each repair action fixed
+#[derive(Debug, Default, Serialize)]
+pub struct ConsistencyRepairs {
+    pub removed_records: u64,
+    pub fixed_sizes: u64,
+    pub created_folders: u64,
+    pub restored_parents: u64,
+    pub failed: u64,
+}
+
+/// Requests in progress and refused by load shedding since startup
+#[derive(Debug, Serialize)]
+pub struct LoadStats {
+    pub shedding_enabled: bool,
+    pub in_flight: usize,
+    /// Requests waiting for a free slot
+    pub queued: usize,
+    pub max_in_flight: usize,
+    pub max_queued: usize,
+    pub shed: u64,
+    /// Busiest routes first
+    pub routes: Vec<RouteLoad>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteLoad {
+    pub route: String,
+    /// Requests on the route, including those waiting for a slot
+    pub in_flight: usize,
+    /// Most requests handled at the same time
+    pub peak: usize,
+    pub completed: u64,
+    pub shed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+}
+
+/// Background task limits and what runs under them
+#[derive(Debug, Serialize)]
+pub struct JobLimits {
+    pub max_concurrent: usize,
+    pub running: usize,
+    pub waiting: usize,
+    pub classes: Vec<JobClassLimits>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobClassLimits {
+    /// hashing, thumbnailing, replication, processing or archive
+    pub class: String,
+    /// low, normal or high
+    pub priority: String,
+    pub max_concurrent: usize,
+    pub running: usize,
+    pub waiting: usize,
+    /// Tasks finished since startup
+    pub completed: u64,
+}
+
+/// Change background task limits until the next restart, fields left out stay as they are
+#[derive(Debug, Deserialize)]
+pub struct UpdateJobLimitsRequest {
+    pub max_concurrent: Option<usize>,
+    #[serde(default)]
+    pub classes: Vec<UpdateJobClassRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateJobClassRequest {
+    pub class: String,
+    pub max_concurrent: Option<usize>,
+    pub priority: Option<String>,
+}
+
+/// Place or lift a legal hold on a file or folder
+#[derive(Debug, Deserialize)]
+pub struct SetLegalHoldRequest {
+    pub held: bool,
+    /// Case or matter the hold is kept for
+    pub reason: Option<String>,
+}
+
+/// A file held for review after a scanner flagged it
+#[derive(Debug, Serialize)]
+pub struct QuarantinedFile {
+    pub file_id: i32,
+    pub owner_id: i32,
+    pub path: String,
+    pub size_bytes: Option<i64>,
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub quarantined_at: String,
+}
+
+/// A record kept in place by a legal hold
+#[derive(Debug, Serialize)]
+pub struct LegalHoldItem {
+    pub file_id: i32,
+    pub owner_id: i32,
+    pub path: String,
+    pub file_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Publish an announcement to every user
+#[derive(Debug, Deserialize)]
+pub struct CreateAnnouncementRequest {
+    pub title: String,
+    pub body: String,
+    /// "info" (default), "warning" or "critical"
+    pub severity: Option<String>,
+    /// Display window as "YYYY-MM-DD HH:MM:SS" UTC, open-ended when omitted
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+}
+
+/// Announcement information
+#[derive(Debug, Serialize)]
+pub struct AnnouncementItem {
+    pub id: i32,
+    pub title: String,
+    pub body: String,
+    pub severity: String,
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    pub active: bool,
+    pub created_at: String,
+}
@@ -1,3 +1,4 @@
+use crate::models::job::JobInfo;
 use serde::{Deserialize, Serialize};
 
 /// User registration request
@@ -12,11 +13,19 @@ pub struct RegisterRequest {
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Start a cookie session instead of returning a Bearer token
+    #[serde(default)]
+    pub use_cookie: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
-    pub token: String,
+    /// Bearer token, omitted for cookie sessions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// CSRF token to send in `X-CSRF-Token`, only for cookie sessions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csrf_token: Option<String>,
     pub user_id: i32,
     pub username: String,
     pub role: String,
@@ -28,5 +37,116 @@ pub struct UserResponse {
     pub id: i32,
     pub username: String,
     pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// New email waiting for confirmation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+    /// When the account will be purged, if its deletion was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deletion_scheduled_at: Option<String>,
     pub created_at: String,
 }
+
+/// Update the current user's profile; omitted fields are left unchanged
+/// and an empty string clears `display_name`, `locale` or `timezone`
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfileRequest {
+    /// Takes effect once confirmed through the link sent to the new address
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+}
+
+/// Confirm an email change
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+/// Change password request
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Security log query
+#[derive(Debug, Deserialize)]
+pub struct SecurityLogQuery {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Security log entry
+#[derive(Debug, Serialize)]
+pub struct SecurityLogEntry {
+    pub id: i32,
+    pub event: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// Storage usage history query
+#[derive(Debug, Deserialize)]
+pub struct UsageHistoryQuery {
+    /// Days back from today, 30 by default
+    pub days: Option<i64>,
+    /// Account to report on, administrators only (defaults to the caller)
+    pub user_id: Option<i32>,
+}
+
+/// Daily storage usage of an account, oldest day first
+#[derive(Debug, Serialize)]
+pub struct UsageHistory {
+    pub user_id: i32,
+    /// Storage quota of the account, absent when unlimited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_bytes: Option<u64>,
+    /// Bytes added between the first and the last day, negative when usage shrank
+    pub growth_bytes: i64,
+    pub days: Vec<UsageHistoryDay>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageHistoryDay {
+    /// UTC day, `YYYY-MM-DD`
+    pub day: String,
+    pub used_bytes: i64,
+    pub file_count: i64,
+}
+
+/// Per-user account settings
+#[derive(Debug, Serialize)]
+pub struct UserSettings {
+    pub login_alerts: bool,
+}
+
+/// Update account settings; omitted fields are left unchanged
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserSettingsRequest {
+    pub login_alerts: Option<bool>,
+}
+
+/// Delete the current account, confirmed with its password
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+/// Scheduled account deletion
+#[derive(Debug, Serialize)]
+pub struct AccountDeletionResponse {
+    pub deletion_scheduled_at: String,
+    /// Archive of the account's files being prepared for a last download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<JobInfo>,
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether a feature is on for the caller
+#[derive(Debug, Serialize)]
+pub struct FeatureState {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// A feature with its configured defaults and the overrides set by administrators
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagItem {
+    pub name: String,
+    /// Configured state for roles not listed in `roles`
+    pub enabled: bool,
+    /// Roles the feature is configured on for
+    pub roles: Vec<String>,
+    pub overrides: Vec<FeatureOverrideItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureOverrideItem {
+    pub id: i32,
+    /// Both `role` and `user_id` are absent for an override applying to everyone
+    pub role: Option<String>,
+    pub user_id: Option<i32>,
+    pub enabled: bool,
+    pub updated_by: i32,
+    pub updated_at: String,
+}
+
+/// Turn a feature on or off for one user, one role, or everyone when both are left out
+#[derive(Debug, Deserialize)]
+pub struct SetFeatureOverrideRequest {
+    pub role: Option<String>,
+    pub user_id: Option<i32>,
+    pub enabled: bool,
+}
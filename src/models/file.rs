@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// File type enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -17,6 +18,32 @@ impl FileType {
     }
 }
 
+/// What an upload does when the target path already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ConflictMode {
+    /// Store under a new " (n)" name
+    #[default]
+    Rename,
+    /// Replace the existing file content in place
+    Overwrite,
+    /// Reject with 409 Conflict
+    Fail,
+    /// Keep the existing file when its hash matches, otherwise overwrite
+    SkipIfSameHash,
+}
+
+impl ConflictMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "rename" => Some(ConflictMode::Rename),
+            "overwrite" => Some(ConflictMode::Overwrite),
+            "fail" => Some(ConflictMode::Fail),
+            "skip-if-same-hash" => Some(ConflictMode::SkipIfSameHash),
+            _ => None,
+        }
+    }
+}
+
 /// File list query
 #[derive(Debug, Deserialize)]
 pub struct FileListQuery {
@@ -24,8 +51,136 @@ pub struct FileListQuery {
     pub owner_id: Option<i32>,
 }
 
-/// File item (with permission info)
+/// File stat query, by ID or by path
+#[derive(Debug, Deserialize)]
+pub struct FileStatQuery {
+    pub file_id: Option<i32>,
+    pub path: Option<String>,
+    /// Owner of `path` (defaults to the caller)
+    pub owner_id: Option<i32>,
+}
+
+/// Full metadata of a single file or folder
+#[derive(Debug, Serialize)]
+pub struct FileStat {
+    pub id: i32,
+    pub owner_id: i32,
+    pub name: String,
+    pub path: String,
+    pub parent_path: String,
+    pub file_type: FileType,
+    pub size_bytes: Option<i64>,
+    pub mime_type: Option<String>,
+    /// SHA-256 of the content (None for folders)
+    pub file_hash: Option<String>,
+    pub ref_count: i32,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Modification time reported by the client that wrote the file
+    pub client_modified: Option<String>,
+    /// Current version, also sent as the ETag header
+    pub version: i32,
+    pub download_count: i64,
+    pub last_downloaded_at: Option<String>,
+
+    /// User-defined key-value metadata
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Labels attached by processing hooks and tags set by the user
+    pub labels: Vec<String>,
+    /// Metadata produced by processing hooks, by hook name
+    pub derived_metadata: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+
+    // Effective permissions of the caller
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+    pub is_owner: bool,
+}
+
+/// File search query, all given filters must match
+#[derive(Debug, Deserialize)]
+pub struct FileSearchQuery {
+    /// Query language, e.g. `type:image size:>10MB modified:>2024-01-01 tag:tax`
+    pub q: Option<String>,
+    /// Part of the file name, case-insensitive
+    pub name: Option<String>,
+    /// Label attached by a processing hook or tagged by the user
+    pub label: Option<String>,
+    /// Key of user-defined metadata the file must have
+    pub meta_key: Option<String>,
+    /// Value `meta_key` must have, compared in its text form
+    pub meta_value: Option<String>,
+    pub limit: Option<u64>,
+}
+
+/// User-defined metadata of a file after an update
+#[derive(Debug, Serialize)]
+pub struct FileMetadataResponse {
+    pub file_id: i32,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Display style of a folder for the current user; both unset removes it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FolderStyle {
+    /// Hex color, e.g. `#3b82f6`
+    pub color: Option<String>,
+    /// Emoji or icon name
+    pub icon: Option<String>,
+}
+
+/// Descendant listing query
+#[derive(Debug, Deserialize)]
+pub struct FileTreeQuery {
+    pub path: Option<String>,
+    pub owner_id: Option<i32>,
+    /// Levels below `path` to include (1 = direct children, default unlimited)
+    pub depth: Option<u32>,
+    /// "nested" (default) or "flat"
+    pub format: Option<String>,
+    /// Page size for the flat format
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Folder tree node
+#[derive(Debug, Serialize)]
+pub struct FileTreeNode {
+    #[serde(flatten)]
+    pub item: FileItem,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<FileTreeNode>,
+}
+
+/// Flat descendant entry
+#[derive(Debug, Serialize)]
+pub struct FlatFileItem {
+    #[serde(flatten)]
+    pub item: FileItem,
+    /// Levels below the requested path (1 = direct child)
+    pub depth: u32,
+}
+
+/// Nested descendant listing
+#[derive(Debug, Serialize)]
+pub struct FileTreeResponse {
+    pub root_path: String,
+    pub total: usize,
+    pub tree: Vec<FileTreeNode>,
+}
+
+/// Flat, paginated descendant listing
 #[derive(Debug, Serialize)]
+pub struct FlatFileListResponse {
+    pub root_path: String,
+    pub total: usize,
+    pub limit: u64,
+    pub offset: u64,
+    pub files: Vec<FlatFileItem>,
+}
+
+/// File item (with permission info)
+#[derive(Debug, Clone, Serialize)]
 pub struct FileItem {
     pub id: i32,
     pub name: String,
@@ -33,10 +188,39 @@ pub struct FileItem {
     pub file_type: FileType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size_bytes: Option<i64>,
+    /// Total size of a folder's files, absent until it is first computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size_bytes: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Modification time reported by the client that wrote the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_modified: Option<String>,
+    pub version: i32,
+    pub pinned: bool,
+    /// Under legal hold, it cannot be deleted, moved or overwritten
+    pub legal_hold: bool,
+    /// Flagged by a scanner, it cannot be downloaded until an administrator reviews it
+    pub quarantined: bool,
+    /// Taken down by an administrator, it cannot be downloaded or shared
+    pub taken_down: bool,
+    /// Starred by the current user
+    pub starred: bool,
+
+    // Download statistics
+    pub download_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_downloaded_at: Option<String>,
+
+    /// User-defined key-value metadata
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+
+    /// Folder color and icon chosen by the current user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<FolderStyle>,
 
     // Permission information
     pub can_read: bool,
@@ -52,11 +236,201 @@ pub struct FileListResponse {
     pub current_path: String,
 }
 
+/// Home feed query
+#[derive(Debug, Deserialize)]
+pub struct HomeFeedQuery {
+    /// Items per section
+    pub limit: Option<u64>,
+}
+
+/// Landing page sections, each newest first
+#[derive(Debug, Serialize)]
+pub struct HomeFeedResponse {
+    /// Files of the caller changed most recently
+    pub recent: Vec<FileItem>,
+    /// Items the caller starred
+    pub starred: Vec<FileItem>,
+    /// Items other users shared with the caller
+    pub shared: Vec<FileItem>,
+}
+
+/// Changes feed query
+#[derive(Debug, Deserialize)]
+pub struct FileChangesQuery {
+    /// Cursor returned by the previous call, omit to read from the start
+    pub since: Option<i32>,
+    pub limit: Option<u64>,
+    /// Owner of the tree (admin only, defaults to the caller)
+    pub owner_id: Option<i32>,
+    /// `modified` orders a page by modification time instead of cursor order
+    pub order: Option<String>,
+}
+
+/// One change in the feed
+#[derive(Debug, Serialize)]
+pub struct FileChangeItem {
+    pub id: i32,
+    pub file_id: i32,
+    /// created, updated, moved, deleted, conflict, pinned or unpinned
+    pub change: String,
+    pub path: String,
+    pub previous_path: Option<String>,
+    /// Current pin state of the file, false once it is deleted
+    pub pinned: bool,
+    /// Client modification time of the file when the change was made
+    pub client_modified: Option<String>,
+    pub created_at: String,
+}
+
+/// Page of the changes feed
+#[derive(Debug, Serialize)]
+pub struct FileChangesResponse {
+    pub changes: Vec<FileChangeItem>,
+    /// Pass as `since` to continue after this page
+    pub cursor: i32,
+    pub has_more: bool,
+}
+
+/// Pin or unpin a file for offline use
+#[derive(Debug, Deserialize)]
+pub struct SetPinnedRequest {
+    pub pinned: bool,
+}
+
+/// Pin state after an update
+#[derive(Debug, Serialize)]
+pub struct FilePinResponse {
+    pub file_id: i32,
+    pub pinned: bool,
+}
+
+/// Star or unstar a file for the current user
+#[derive(Debug, Deserialize)]
+pub struct SetStarredRequest {
+    pub starred: bool,
+}
+
+/// Star state after an update
+#[derive(Debug, Serialize)]
+pub struct FileStarResponse {
+    pub file_id: i32,
+    pub starred: bool,
+}
+
+/// Attach and detach tags on many files, chosen by ID or by a search query
+#[derive(Debug, Deserialize)]
+pub struct BulkTagRequest {
+    #[serde(default)]
+    pub file_ids: Vec<i32>,
+    /// Query language of the search, selects the caller's own files
+    pub query: Option<String>,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// Star or unstar many files, chosen by ID or by a search query
+#[derive(Debug, Deserialize)]
+pub struct BulkStarRequest {
+    #[serde(default)]
+    pub file_ids: Vec<i32>,
+    /// Query language of the search, selects the caller's own files
+    pub query: Option<String>,
+    pub starred: bool,
+}
+
+/// Outcome for a single file within a bulk operation
+#[derive(Debug, Serialize)]
+pub struct BulkFileResult {
+    pub file_id: i32,
+    /// "updated", "unchanged" or "skipped"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Bulk tag or star result report
+#[derive(Debug, Serialize)]
+pub struct BulkFileReport {
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub results: Vec<BulkFileResult>,
+}
+
+/// Start a chunked upload
+#[derive(Debug, Deserialize)]
+pub struct StartUploadRequest {
+    /// Folder the file goes to
+    #[serde(default = "default_upload_path")]
+    pub path: String,
+    pub file_name: String,
+    /// Total size of the file in bytes
+    pub size: i64,
+    pub mime_type: Option<String>,
+    /// rename (default), overwrite, fail or skip-if-same-hash
+    pub on_conflict: Option<String>,
+    /// Server hash the sync client last saw for the target file
+    pub base_hash: Option<String>,
+    /// Modification time of the file on the client
+    pub client_modified: Option<String>,
+}
+
+fn default_upload_path() -> String {
+    "/".to_string()
+}
+
+/// Position of an uploaded chunk within the file
+#[derive(Debug, Deserialize)]
+pub struct UploadChunkQuery {
+    pub offset: i64,
+    /// SHA-256 of the chunk computed by the client, a chunk damaged on the way is refused
+    pub checksum: Option<String>,
+}
+
+/// Completion of a chunked upload
+#[derive(Debug, Deserialize)]
+pub struct CompleteUploadQuery {
+    /// SHA-256 of the whole file computed by the client
+    pub sha256: Option<String>,
+}
+
+/// Bytes of a file, as a start position and a length
+#[derive(Debug, Serialize)]
+pub struct ByteRange {
+    pub offset: i64,
+    pub length: i64,
+}
+
+/// Chunked upload in progress
+#[derive(Debug, Serialize)]
+pub struct UploadSessionItem {
+    pub id: i32,
+    pub path: String,
+    pub file_name: String,
+    pub total_bytes: i64,
+    pub received_bytes: i64,
+    /// Parts of the file no chunk has been received for yet
+    pub missing_ranges: Vec<ByteRange>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub expires_at: String,
+}
+
 /// Create folder request
 #[derive(Debug, Deserialize)]
 pub struct CreateFolderRequest {
+    /// Parent folder in the caller's drive, ignored when `parent_id` is given
+    #[serde(default)]
     pub path: String,
     pub name: String,
+    /// Parent folder by id, e.g. one shared with the caller
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    /// Create missing parent folders and accept an existing folder, like `mkdir -p`
+    #[serde(default)]
+    pub exist_ok: bool,
 }
 
 /// Rename request
@@ -72,6 +446,48 @@ pub struct DeleteQuery {
     pub file_id: i32,
 }
 
+/// Request for a presigned download URL
+#[derive(Debug, Deserialize)]
+pub struct CreateDownloadUrlRequest {
+    pub file_id: i32,
+    /// Lifetime of the URL (default from the download configuration)
+    pub expires_in_secs: Option<i64>,
+    /// Only accept downloads from the IP requesting the URL
+    #[serde(default)]
+    pub bind_ip: bool,
+    /// Stop accepting downloads after the first one
+    #[serde(default)]
+    pub single_use: bool,
+}
+
+/// Presigned download URL, usable without authentication until it expires
+#[derive(Debug, Serialize)]
+pub struct DownloadUrl {
+    pub token: String,
+    pub url: String,
+    pub file_id: i32,
+    pub bound_ip: Option<String>,
+    pub single_use: bool,
+    pub expires_at: String,
+}
+
+/// Single file download query parameters
+#[derive(Debug, Deserialize)]
+pub struct GetFileQuery {
+    pub file_id: i32,
+    /// Pins the download to a version, such URLs are cached as immutable
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+/// Folder download query parameters
+#[derive(Debug, Deserialize)]
+pub struct DownloadFolderQuery {
+    pub folder_id: i32,
+    /// Archive format, "zip" (default) or "tar"
+    pub format: Option<String>,
+}
+
 /// Download query parameters
 #[derive(Debug, Deserialize)]
 pub struct DownloadQuery {
@@ -106,6 +522,109 @@ pub struct RevokePermissionQuery {
     pub user_id: i32,
 }
 
+/// Default permission policy for a folder (admin only)
+#[derive(Debug, Deserialize)]
+pub struct FolderDefaultPermissionRequest {
+    pub folder_id: i32,
+    pub user_id: i32,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+    /// Also grant the policy on everything already inside the folder
+    #[serde(default)]
+    pub apply_to_existing: bool,
+}
+
+/// Remove folder default permission query (admin only)
+#[derive(Debug, Deserialize)]
+pub struct FolderDefaultPermissionQuery {
+    pub folder_id: i32,
+    pub user_id: i32,
+}
+
+/// Folder default permission policy information
+#[derive(Debug, Serialize)]
+pub struct FolderDefaultPermission {
+    pub folder_id: i32,
+    pub user_id: i32,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+    pub created_by: i32,
+    pub created_at: String,
+}
+
+/// Create permission template request (admin only)
+#[derive(Debug, Deserialize)]
+pub struct CreatePermissionTemplateRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+}
+
+/// Permission template information
+#[derive(Debug, Serialize)]
+pub struct PermissionTemplate {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+    pub created_by: i32,
+    pub created_at: String,
+}
+
+/// Apply a permission template to every file/user combination (admin only)
+#[derive(Debug, Deserialize)]
+pub struct BulkGrantRequest {
+    pub template_id: i32,
+    pub file_ids: Vec<i32>,
+    pub user_ids: Vec<i32>,
+}
+
+/// Outcome of a single file/user grant within a bulk grant
+#[derive(Debug, Serialize)]
+pub struct BulkGrantResult {
+    pub file_id: i32,
+    pub user_id: i32,
+    /// "granted", "updated" or "skipped"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Bulk grant result report
+#[derive(Debug, Serialize)]
+pub struct BulkGrantReport {
+    pub template_id: i32,
+    pub granted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub results: Vec<BulkGrantResult>,
+}
+
+/// Copy permissions between files request (admin only)
+#[derive(Debug, Deserialize)]
+pub struct CopyPermissionsRequest {
+    pub source_file_id: i32,
+    pub target_file_id: i32,
+    /// Also clone the source's active share links onto the target
+    #[serde(default)]
+    pub include_share_links: bool,
+}
+
+/// Copy permissions result
+#[derive(Debug, Serialize)]
+pub struct CopyPermissionsReport {
+    pub source_file_id: i32,
+    pub target_file_id: i32,
+    pub permissions_copied: usize,
+    pub share_links_copied: usize,
+}
+
 /// File permission information
 #[derive(Debug, Serialize)]
 pub struct FilePermission {
@@ -123,6 +642,8 @@ pub struct FilePermission {
 pub struct BatchDownloadRequest {
     /// List of file IDs to download (can be files or folders)
     pub file_ids: Vec<i32>,
+    /// Archive format, "zip" (default) or "tar"
+    pub format: Option<String>,
 }
 
 /// Move file/folder request
@@ -130,13 +651,74 @@ pub struct BatchDownloadRequest {
 pub struct MoveRequest {
     pub file_id: i32,
     pub destination_path: String,
+    /// Modification time of the item on the client, the stored one is kept when omitted
+    pub client_modified: Option<String>,
 }
 
 /// Copy file/folder request
+/// The copy stays in the tree the item belongs to unless `to_my_drive` is set
 #[derive(Debug, Deserialize)]
 pub struct CopyRequest {
     pub file_id: i32,
     pub destination_path: String,
+    /// Copy shared content into the caller's own drive
+    #[serde(default)]
+    pub to_my_drive: bool,
+}
+
+/// Upload target, by default a path in the caller's own drive
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// Upload into this folder, e.g. one shared with the caller, its owner stores the file
+    pub folder_id: Option<i32>,
+}
+
+/// A file a client intends to upload
+#[derive(Debug, Deserialize)]
+pub struct PrecheckFile {
+    /// Target path including the file name
+    pub path: String,
+    pub size: i64,
+    /// SHA-256 of the content
+    pub hash: Option<String>,
+}
+
+/// Batch upload pre-check request
+#[derive(Debug, Deserialize)]
+pub struct UploadPrecheckRequest {
+    pub files: Vec<PrecheckFile>,
+}
+
+/// What uploading one file would do
+#[derive(Debug, Serialize)]
+pub struct PrecheckResult {
+    pub path: String,
+    /// Another item exists at the target path
+    pub conflict: bool,
+    /// The file at the target path already has this content, nothing needs uploading
+    pub identical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_file_id: Option<i32>,
+    /// The file is too large or does not fit the free space left by the files before it
+    pub exceeds_quota: bool,
+    /// The content is already stored, copying `source_file_id` replaces the upload
+    pub instant_upload: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_file_id: Option<i32>,
+    /// Reason the entry could not be checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Batch upload pre-check response
+#[derive(Debug, Serialize)]
+pub struct UploadPrecheckResponse {
+    pub results: Vec<PrecheckResult>,
+    /// Bytes that would actually be transferred
+    pub upload_bytes: i64,
+    /// Free space for uploads within the quota, absent when it cannot be determined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_bytes: Option<u64>,
 }
 
 /// Calculate size request
@@ -152,3 +734,80 @@ pub struct CalculateSizeResponse {
     pub file_count: usize,
     pub folder_count: usize,
 }
+
+/// Create or replace an upload auto-organize rule
+#[derive(Debug, Deserialize)]
+pub struct OrganizeRuleRequest {
+    /// Folder whose new uploads are filed
+    pub folder_path: String,
+    /// Extensions the rule applies to, e.g. `["jpg", "png"]`; any when empty
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Start of the MIME type, e.g. `image/`; any when omitted
+    pub mime_prefix: Option<String>,
+    /// Destination folder, e.g. `/Photos/{YYYY}/{MM}`
+    pub destination: String,
+}
+
+/// Upload auto-organize rule
+#[derive(Debug, Serialize)]
+pub struct OrganizeRule {
+    pub id: i32,
+    pub folder_path: String,
+    pub extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_prefix: Option<String>,
+    pub destination: String,
+    pub position: i32,
+    pub created_at: String,
+}
+
+/// Dry run of the organize rules against the files of a folder
+#[derive(Debug, Deserialize)]
+pub struct OrganizePreviewRequest {
+    pub folder_path: String,
+    /// Unsaved rule tried alone, the saved rules are used when omitted
+    pub rule: Option<OrganizeRuleRequest>,
+}
+
+/// Where a file of the previewed folder would be filed if it was uploaded now
+#[derive(Debug, Serialize)]
+pub struct OrganizePreviewItem {
+    pub file_id: i32,
+    pub name: String,
+    pub destination: String,
+    /// Saved rule that matched, absent for an unsaved rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<i32>,
+}
+
+/// Create or replace a scheduled cleanup rule
+#[derive(Debug, Deserialize)]
+pub struct CleanupRuleRequest {
+    /// Folder cleaned up, its subfolders included
+    pub folder_path: String,
+    /// Extensions the rule applies to, e.g. `["png"]`; any when empty
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Text the file name has to contain, case insensitive, e.g. `Screenshot`
+    pub name_contains: Option<String>,
+    /// Files unchanged for longer are deleted
+    pub max_age_days: i32,
+}
+
+/// Scheduled cleanup rule
+#[derive(Debug, Serialize)]
+pub struct CleanupRule {
+    pub id: i32,
+    pub folder_path: String,
+    pub extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_contains: Option<String>,
+    pub max_age_days: i32,
+    /// Set once the owner was told which files are due, they are deleted at this time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deletion_due_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<String>,
+    pub created_at: String,
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+/// Background job status and progress
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: i32,
+    pub kind: String,
+    pub status: String,
+    pub total_items: i64,
+    pub processed_items: i64,
+    pub total_bytes: i64,
+    pub processed_bytes: i64,
+    /// Completion in percent (0-100)
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where to fetch the result once the job is completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+/// Job progress update sent to the owner's event stream
+#[derive(Debug, Clone)]
+pub struct JobEvent {
+    pub user_id: i32,
+    pub job: JobInfo,
+}
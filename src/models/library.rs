@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Shared Library listing query
+#[derive(Debug, Deserialize)]
+pub struct LibraryListQuery {
+    pub path: Option<String>,
+}
+
+/// Create a group
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+}
+
+/// Add a user to a group
+#[derive(Debug, Deserialize)]
+pub struct GroupMemberRequest {
+    pub user_id: i32,
+}
+
+/// Group with its members
+#[derive(Debug, Serialize)]
+pub struct GroupItem {
+    pub id: i32,
+    pub name: String,
+    pub member_ids: Vec<i32>,
+    pub created_at: String,
+}
+
+/// Grant a role or a group access to a library folder, replacing an earlier grant for the same pair
+#[derive(Debug, Deserialize)]
+pub struct LibraryGrantRequest {
+    /// Library folder, "/" for the whole library
+    pub path: String,
+    pub role: Option<String>,
+    pub group_id: Option<i32>,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+}
+
+/// Library grant information
+#[derive(Debug, Serialize)]
+pub struct LibraryGrantItem {
+    pub id: i32,
+    pub path: String,
+    pub role: Option<String>,
+    pub group_id: Option<i32>,
+    pub can_read: bool,
+    pub can_write: bool,
+    pub can_delete: bool,
+    pub granted_by: i32,
+    pub created_at: String,
+}
@@ -1,2 +1,13 @@
+pub mod admin;
+pub mod announcement;
 pub mod auth;
+pub mod feature;
 pub mod file;
+pub mod job;
+pub mod library;
+pub mod mount;
+pub mod music;
+pub mod notification;
+pub mod photo;
+pub mod share;
+pub mod takedown;
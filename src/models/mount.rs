@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Register a host directory in a user's tree
+#[derive(Debug, Deserialize)]
+pub struct CreateMountRequest {
+    pub user_id: i32,
+    /// Folder name at the root of the user's tree, e.g. "Media"
+    pub name: String,
+    /// Absolute directory on the host
+    pub host_path: String,
+    /// Defaults to read-only
+    pub read_only: Option<bool>,
+}
+
+/// Mount information
+#[derive(Debug, Serialize)]
+pub struct MountItem {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub mount_point: String,
+    pub host_path: String,
+    pub read_only: bool,
+    pub last_indexed_at: Option<String>,
+    pub created_at: String,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Track listing filters
+#[derive(Debug, Deserialize)]
+pub struct TrackQuery {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Audio file with its tags, untagged files are titled by their filename
+#[derive(Debug, Serialize)]
+pub struct TrackItem {
+    pub file_id: i32,
+    pub path: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<i32>,
+    pub year: Option<i32>,
+    pub has_cover: bool,
+}
+
+/// Album grouped from the tags of its tracks
+#[derive(Debug, Serialize)]
+pub struct AlbumItem {
+    pub album: String,
+    pub artist: Option<String>,
+    pub year: Option<i32>,
+    pub track_count: u64,
+    /// Track whose cover art stands for the album
+    pub cover_file_id: Option<i32>,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// Notification list query
+#[derive(Debug, Deserialize)]
+pub struct NotificationListQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// In-app notification
+#[derive(Debug, Serialize)]
+pub struct NotificationItem {
+    pub id: i32,
+    pub category: String,
+    pub title: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub read: bool,
+    pub created_at: String,
+}
+
+/// Notification list response
+#[derive(Debug, Serialize)]
+pub struct NotificationListResponse {
+    pub notifications: Vec<NotificationItem>,
+    pub unread_count: u64,
+}
+
+/// Delivery channels for one notification category
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotificationPreferenceItem {
+    pub category: String,
+    pub email: bool,
+    pub in_app: bool,
+}
+
+/// Update notification preferences; categories not listed are left unchanged
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub preferences: Vec<NotificationPreferenceItem>,
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Bounding box for the photo map, a side left out is unbounded
+/// `min_lon` greater than `max_lon` selects a box crossing the antimeridian
+#[derive(Debug, Deserialize)]
+pub struct GeoQuery {
+    pub min_lat: Option<f64>,
+    pub max_lat: Option<f64>,
+    pub min_lon: Option<f64>,
+    pub max_lon: Option<f64>,
+    pub limit: Option<u64>,
+}
+
+/// Image with the position it was taken at
+#[derive(Debug, Serialize)]
+pub struct PhotoLocationItem {
+    pub file_id: i32,
+    pub name: String,
+    pub path: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// Create share link request
+#[derive(Debug, Deserialize)]
+pub struct CreateShareLinkRequest {
+    pub file_id: i32,
+    /// Disable the link after this many downloads
+    pub max_downloads: Option<i32>,
+    /// Disable the link after this many hours
+    pub expires_in_hours: Option<i64>,
+}
+
+/// Share link information
+#[derive(Debug, Serialize)]
+pub struct ShareLinkItem {
+    pub id: i32,
+    pub token: String,
+    pub file_id: i32,
+    pub file_name: String,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub expires_at: Option<String>,
+    pub revoked: bool,
+    /// False once revoked, expired or out of downloads
+    pub active: bool,
+    pub created_at: String,
+}
+
+/// Open Graph-style metadata of a share link, for rich link previews
+#[derive(Debug, Serialize)]
+pub struct ShareUnfurl {
+    pub name: String,
+    pub size_bytes: i64,
+    pub mime_type: String,
+    /// Public download URL of the link
+    pub url: String,
+    /// Set for images with an embedded thumbnail
+    pub thumbnail_url: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+/// Download query of a share link
+#[derive(Debug, Deserialize)]
+pub struct SharedDownloadQuery {
+    /// File below a shared folder, required for folder links
+    pub file_id: Option<i32>,
+}
+
+/// Listing of a shared folder, one level or a name search through the subtree
+#[derive(Debug, Deserialize)]
+pub struct SharedListQuery {
+    /// Folder below the shared one, relative to it (default: the shared folder)
+    pub path: Option<String>,
+    /// Part of the name, searches the whole subtree of `path` instead of one level
+    pub q: Option<String>,
+    /// `name` (default), `size` or `modified`, folders always come first
+    pub sort: Option<String>,
+    /// `asc` (default) or `desc`
+    pub order: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Entry of a shared folder, paths are relative to the shared folder
+#[derive(Debug, Serialize)]
+pub struct SharedItem {
+    pub id: i32,
+    pub name: String,
+    pub path: String,
+    pub file_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub updated_at: String,
+}
+
+/// One page of a shared folder listing
+#[derive(Debug, Serialize)]
+pub struct SharedListResponse {
+    pub path: String,
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+    pub items: Vec<SharedItem>,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Take a file down
+#[derive(Debug, Deserialize)]
+pub struct IssueTakedownRequest {
+    /// Shown to the owner, e.g. the notice the takedown answers
+    pub reason: String,
+}
+
+/// The owner's dispute of a takedown
+#[derive(Debug, Deserialize)]
+pub struct CounterNoticeRequest {
+    pub statement: String,
+}
+
+/// Close a takedown
+#[derive(Debug, Deserialize)]
+pub struct ResolveTakedownRequest {
+    /// Make the file available again, otherwise the takedown is upheld
+    pub restore: bool,
+    /// Sent to the owner with the outcome
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TakedownListQuery {
+    /// active, countered, restored or upheld (default: all)
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TakedownItem {
+    pub id: i32,
+    pub file_id: i32,
+    pub owner_id: i32,
+    /// Path of the file, absent once it was deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub reason: String,
+    pub issued_by: i32,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counter_notice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub countered_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution_note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+}
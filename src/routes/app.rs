@@ -1,12 +1,23 @@
-use crate::{handlers, middleware::auth, AppState};
+use crate::{
+    handlers,
+    middleware::{
+        auth, load_shedding, locale, problem,
+        timeout::{self, ResponseUser},
+        timestamp_format, upload_limit,
+    },
+    AppState,
+};
 use axum::{
+    extract::{MatchedPath, Request},
     middleware,
-    routing::{delete, get, post, put},
+    response::Response,
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
-use tracing::Level;
+use tower_http::trace::TraceLayer;
+use tracing::Span;
 
 use axum::extract::DefaultBodyLimit;
 
@@ -17,18 +28,112 @@ pub fn create_routes(state: AppState) -> Router {
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any)
-        .expose_headers([header::CONTENT_DISPOSITION, header::CONTENT_TYPE]);
+        .expose_headers([
+            header::CONTENT_DISPOSITION,
+            header::CONTENT_TYPE,
+            header::ETAG,
+            header::CONTENT_RANGE,
+            header::ACCEPT_RANGES,
+        ]);
 
+    // The route and user are span fields, so every event of a request carries them
     let trace_layer = TraceLayer::new_for_http()
-        .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-        .on_response(DefaultOnResponse::new().level(Level::INFO));
+        .make_span_with(|request: &Request| {
+            let route = request
+                .extensions()
+                .get::<MatchedPath>()
+                .map_or_else(|| request.uri().path(), |p| p.as_str());
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                route = %route,
+                user_id = tracing::field::Empty,
+            )
+        })
+        .on_response(|response: &Response, latency: Duration, span: &Span| {
+            if let Some(user) = response.extensions().get::<ResponseUser>() {
+                span.record("user_id", user.0);
+            }
+            tracing::info!(
+                status = response.status().as_u16(),
+                latency_ms = latency.as_millis() as u64,
+                "finished processing request"
+            );
+        });
+
+    // Layered on every router except the transfer ones, which may legitimately run for long
+    let request_timeout =
+        middleware::from_fn_with_state(state.clone(), timeout::request_timeout_middleware);
+    let auth = middleware::from_fn_with_state(state.clone(), auth::auth_middleware);
 
     let public_routes = Router::new()
         .route("/api/auth/register", post(handlers::auth::register))
-        .route("/api/auth/login", post(handlers::auth::login));
+        .route("/api/auth/login", post(handlers::auth::login))
+        .route("/api/auth/logout", post(handlers::auth::logout))
+        .route("/api/auth/verify-email", post(handlers::auth::verify_email))
+        .route("/.well-known/jwks.json", get(handlers::auth::jwks))
+        .route(
+            "/api/public/shares/:token/list",
+            get(handlers::share::list_shared_folder),
+        )
+        .route(
+            "/api/public/shares/:token/preview",
+            get(handlers::share::unfurl_share_link),
+        )
+        .route(
+            "/api/public/shares/:token/thumbnail",
+            get(handlers::share::shared_thumbnail),
+        )
+        .layer(request_timeout.clone());
+
+    // Uploads are held to the limit of the caller's role instead of the global one
+    let upload_limit =
+        middleware::from_fn_with_state(state.clone(), upload_limit::upload_limit_middleware);
 
     let protected_routes = Router::new()
         .route("/api/users/profile", get(handlers::user::get_profile))
+        .route("/api/users/profile", patch(handlers::user::update_profile))
+        .route("/api/users/me", delete(handlers::user::delete_account))
+        .route(
+            "/api/users/me/cancel-deletion",
+            post(handlers::user::cancel_account_deletion),
+        )
+        .route("/api/users/password", put(handlers::user::change_password))
+        .route("/api/auth/logout-all", post(handlers::auth::logout_all))
+        .route("/api/users/settings", get(handlers::user::get_settings))
+        .route("/api/users/settings", put(handlers::user::update_settings))
+        .route(
+            "/api/users/security-log",
+            get(handlers::user::get_security_log),
+        )
+        .route("/api/users/features", get(handlers::feature::my_features))
+        .route(
+            "/api/users/usage/history",
+            get(handlers::user::get_usage_history),
+        )
+        .route("/api/auth/refresh", post(handlers::auth::refresh))
+        // Notification routes
+        .route(
+            "/api/notifications",
+            get(handlers::notification::list_notifications),
+        )
+        .route(
+            "/api/notifications/read-all",
+            put(handlers::notification::mark_all_read),
+        )
+        .route(
+            "/api/notifications/:id/read",
+            put(handlers::notification::mark_read),
+        )
+        .route(
+            "/api/notifications/preferences",
+            get(handlers::notification::get_preferences),
+        )
+        .route(
+            "/api/notifications/preferences",
+            put(handlers::notification::update_preferences),
+        )
         .route(
             "/api/storage/info",
             get(handlers::storage::get_storage_info),
@@ -36,17 +141,215 @@ pub fn create_routes(state: AppState) -> Router {
         // File operation routes
         .route("/api/files", get(handlers::file::list_files))
         .route("/api/files", delete(handlers::file::delete_file))
-        .route("/api/files/download", get(handlers::file::get_file))
+        .route("/api/files/tree", get(handlers::file::list_tree))
+        .route("/api/files/stat", get(handlers::file::stat_file))
+        .route("/api/files/changes", get(handlers::file::list_changes))
+        .route("/api/files/search", get(handlers::file::search_files))
         .route(
-            "/api/files/batch-download",
-            post(handlers::file::batch_download_files),
+            "/api/files/:id/metadata",
+            patch(handlers::file::update_file_metadata),
+        )
+        .route("/api/files/:id/pin", put(handlers::file::set_file_pinned))
+        .route("/api/files/:id/star", put(handlers::file::set_file_starred))
+        .route("/api/files/bulk/tags", post(handlers::file::bulk_tag_files))
+        .route(
+            "/api/files/bulk/star",
+            post(handlers::file::bulk_star_files),
+        )
+        .route("/api/files/home", get(handlers::file::get_home_feed))
+        .route(
+            "/api/files/:id/style",
+            put(handlers::file::set_folder_style),
+        )
+        .route(
+            "/api/files/takedowns",
+            get(handlers::takedown::my_takedowns),
+        )
+        .route(
+            "/api/files/takedowns/:id/counter-notice",
+            post(handlers::takedown::submit_counter_notice),
+        )
+        .route(
+            "/api/files/download-url",
+            post(handlers::file::create_download_url),
+        )
+        .route(
+            "/api/files/batch-download/prepare",
+            post(handlers::file::prepare_batch_download),
+        )
+        .route("/api/jobs/:id", get(handlers::jobs::get_job))
+        .route(
+            "/api/files/upload/precheck",
+            post(handlers::file::precheck_upload),
+        )
+        .route(
+            "/api/files/uploads",
+            get(handlers::file::list_upload_sessions).post(handlers::file::start_upload_session),
         )
-        .route("/api/files/upload", post(handlers::file::upload_file))
         .route("/api/files/folder", post(handlers::file::create_folder))
+        .route(
+            "/api/cleanup-rules",
+            get(handlers::file::list_cleanup_rules).post(handlers::file::create_cleanup_rule),
+        )
+        .route(
+            "/api/cleanup-rules/:id",
+            put(handlers::file::update_cleanup_rule).delete(handlers::file::delete_cleanup_rule),
+        )
+        .route(
+            "/api/organize-rules",
+            get(handlers::file::list_organize_rules).post(handlers::file::create_organize_rule),
+        )
+        .route(
+            "/api/organize-rules/preview",
+            post(handlers::file::preview_organize_rules),
+        )
+        .route(
+            "/api/organize-rules/:id",
+            put(handlers::file::update_organize_rule).delete(handlers::file::delete_organize_rule),
+        )
         .route("/api/files/rename", put(handlers::file::rename_file))
         .route("/api/files/move", put(handlers::file::move_file))
-        .route("/api/files/copy", post(handlers::file::copy_file))
         .route("/api/files/size", post(handlers::file::calculate_size))
+        // Music library routes
+        .route("/api/music/tracks", get(handlers::music::list_tracks))
+        .route("/api/music/albums", get(handlers::music::list_albums))
+        .route(
+            "/api/music/tracks/:file_id/cover",
+            get(handlers::music::get_track_cover),
+        )
+        // Photo map routes
+        .route(
+            "/api/photos/geo",
+            get(handlers::photo::list_photo_locations),
+        )
+        // Share link routes
+        .route("/api/shares", post(handlers::share::create_share_link))
+        .route("/api/shares", get(handlers::share::list_share_links))
+        .route(
+            "/api/shares/:id",
+            delete(handlers::share::revoke_share_link),
+        )
+        // Admin routes
+        .route("/api/admin/stats", get(handlers::admin::get_stats))
+        .route("/api/admin/audit", get(handlers::admin::query_audit_log))
+        .route(
+            "/api/admin/reports/most-downloaded",
+            get(handlers::admin::most_downloaded_files),
+        )
+        .route("/api/admin/backups", get(handlers::admin::list_backup_runs))
+        .route(
+            "/api/admin/reports/replication",
+            get(handlers::admin::replication_report),
+        )
+        .route("/api/admin/load", get(handlers::admin::get_load))
+        .route(
+            "/api/admin/search/reindex",
+            post(handlers::admin::rebuild_search_index),
+        )
+        .route("/api/admin/features", get(handlers::feature::list_features))
+        .route(
+            "/api/admin/features/:name/overrides",
+            put(handlers::feature::set_feature_override),
+        )
+        .route(
+            "/api/admin/features/:name/overrides/:id",
+            delete(handlers::feature::delete_feature_override),
+        )
+        .route(
+            "/api/admin/jobs/limits",
+            get(handlers::admin::get_job_limits).put(handlers::admin::update_job_limits),
+        )
+        .route(
+            "/api/admin/legal-holds",
+            get(handlers::admin::list_legal_holds),
+        )
+        .route(
+            "/api/admin/files/:id/legal-hold",
+            put(handlers::admin::set_legal_hold),
+        )
+        .route(
+            "/api/admin/files/:id/takedown",
+            post(handlers::takedown::issue_takedown),
+        )
+        .route(
+            "/api/admin/takedowns",
+            get(handlers::takedown::list_takedowns),
+        )
+        .route(
+            "/api/admin/takedowns/:id/resolve",
+            post(handlers::takedown::resolve_takedown),
+        )
+        .route(
+            "/api/admin/quarantine",
+            get(handlers::quarantine::list_quarantined),
+        )
+        .route(
+            "/api/admin/quarantine/:id",
+            delete(handlers::quarantine::delete_quarantined),
+        )
+        .route(
+            "/api/admin/quarantine/:id/release",
+            post(handlers::quarantine::release_quarantined),
+        )
+        .route(
+            "/api/admin/staging",
+            get(handlers::admin::get_staging_usage),
+        )
+        .route(
+            "/api/admin/storage/migrate",
+            post(handlers::admin::migrate_storage),
+        )
+        .route(
+            "/api/admin/announcements",
+            get(handlers::announcement::list_all_announcements)
+                .post(handlers::announcement::create_announcement),
+        )
+        .route(
+            "/api/admin/announcements/:id",
+            delete(handlers::announcement::delete_announcement),
+        )
+        .route(
+            "/api/announcements",
+            get(handlers::announcement::list_announcements),
+        )
+        .route(
+            "/api/admin/mounts",
+            get(handlers::mount::list_mounts).post(handlers::mount::create_mount),
+        )
+        .route(
+            "/api/admin/mounts/:id",
+            delete(handlers::mount::delete_mount),
+        )
+        // Shared Library routes
+        .route("/api/library", get(handlers::library::list_library))
+        .route(
+            "/api/library/folder",
+            post(handlers::library::create_library_folder),
+        )
+        .route(
+            "/api/admin/groups",
+            get(handlers::library::list_groups).post(handlers::library::create_group),
+        )
+        .route(
+            "/api/admin/groups/:id",
+            delete(handlers::library::delete_group),
+        )
+        .route(
+            "/api/admin/groups/:id/members",
+            post(handlers::library::add_group_member),
+        )
+        .route(
+            "/api/admin/groups/:id/members/:user_id",
+            delete(handlers::library::remove_group_member),
+        )
+        .route(
+            "/api/admin/library/grants",
+            get(handlers::library::list_library_grants).put(handlers::library::set_library_grant),
+        )
+        .route(
+            "/api/admin/library/grants/:id",
+            delete(handlers::library::delete_library_grant),
+        )
         // Permission management routes (admin only)
         .route(
             "/api/files/permissions/grant",
@@ -60,12 +363,104 @@ pub fn create_routes(state: AppState) -> Router {
             "/api/files/permissions/user/:user_id",
             get(handlers::file::list_user_permissions),
         )
-        .route_layer(middleware::from_fn_with_state(
-            state.clone(),
-            auth::auth_middleware,
-        ));
+        .route(
+            "/api/files/permissions/defaults",
+            put(handlers::file::set_folder_default_permission)
+                .delete(handlers::file::remove_folder_default_permission),
+        )
+        .route(
+            "/api/files/permissions/defaults/:folder_id",
+            get(handlers::file::list_folder_default_permissions),
+        )
+        .route(
+            "/api/files/permissions/templates",
+            post(handlers::file::create_permission_template)
+                .get(handlers::file::list_permission_templates),
+        )
+        .route(
+            "/api/files/permissions/templates/:id",
+            delete(handlers::file::delete_permission_template),
+        )
+        .route(
+            "/api/files/permissions/copy",
+            post(handlers::file::copy_permissions),
+        )
+        .route(
+            "/api/files/permissions/bulk-grant",
+            post(handlers::file::bulk_grant_permissions),
+        )
+        .route_layer(auth.clone())
+        .layer(request_timeout.clone());
+
+    // Routes that move file contents or scan the whole storage, never cut off by the request timeout
+    let public_transfer_routes = Router::new()
+        .route(
+            "/api/public/shares/:token",
+            get(handlers::share::download_shared_file),
+        )
+        .route(
+            "/api/public/downloads/:token",
+            get(handlers::file::download_with_token),
+        );
+
+    let transfer_routes = Router::new()
+        .route("/api/files/download", get(handlers::file::get_file))
+        .route(
+            "/api/files/download-folder",
+            get(handlers::file::download_folder),
+        )
+        .route(
+            "/api/files/batch-download",
+            post(handlers::file::batch_download_files),
+        )
+        .route("/api/events", get(handlers::jobs::event_stream))
+        .route(
+            "/api/jobs/:id/download",
+            get(handlers::jobs::download_job_result),
+        )
+        .route(
+            "/api/files/upload",
+            post(handlers::file::upload_file)
+                .route_layer(upload_limit.clone())
+                .route_layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/api/files/uploads/:id",
+            put(handlers::file::upload_chunk).delete(handlers::file::cancel_upload_session),
+        )
+        .route(
+            "/api/files/uploads/:id/complete",
+            post(handlers::file::complete_upload_session),
+        )
+        .route("/api/files/copy", post(handlers::file::copy_file))
+        .route(
+            "/api/admin/audit/export",
+            get(handlers::admin::export_audit_log),
+        )
+        .route(
+            "/api/admin/consistency",
+            get(handlers::admin::consistency_report),
+        )
+        .route(
+            "/api/admin/consistency/repair",
+            post(handlers::admin::repair_consistency),
+        )
+        .route(
+            "/api/admin/quarantine/:id/download",
+            get(handlers::quarantine::download_quarantined),
+        )
+        .route(
+            "/api/library/upload",
+            post(handlers::file::upload_library_file)
+                .route_layer(upload_limit)
+                .route_layer(DefaultBodyLimit::disable()),
+        )
+        .route_layer(auth);
 
-    let health_route = Router::new().route("/health", get(|| async { "OK" }));
+    let health_route = Router::new()
+        .route("/health", get(|| async { "OK" }))
+        .route("/health/ready", get(handlers::storage::readiness))
+        .layer(request_timeout);
 
     let max_upload_size = state.config.server.max_upload_size;
 
@@ -73,6 +468,21 @@ pub fn create_routes(state: AppState) -> Router {
         .merge(health_route)
         .merge(public_routes)
         .merge(protected_routes)
+        .merge(public_transfer_routes)
+        .merge(transfer_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            timeout::slow_request_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            load_shedding::load_shedding_middleware,
+        ))
+        .layer(middleware::from_fn(problem::problem_details_middleware))
+        .layer(middleware::from_fn(locale::locale_middleware))
+        .layer(middleware::from_fn(
+            timestamp_format::timestamp_format_middleware,
+        ))
         .layer(trace_layer)
         .layer(cors)
         .layer(DefaultBodyLimit::max(max_upload_size))
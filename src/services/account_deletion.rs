@@ -0,0 +1,253 @@
+use crate::{
+    config::Config,
+    constants::ROLE_ADMIN,
+    entities::{
+        audit_log, cleanup_rule, download_token, feature_override, file, file_change,
+        file_permission, file_star, folder_default_permission, folder_style, group_member, job,
+        mount, notification, notification_preference, organize_rule, permission_template,
+        share_link, usage_snapshot, user,
+    },
+    handlers::file::delete_file_record,
+    services::{
+        audit::{self, AuditEvent},
+        legal_hold, permission_cache, upload_sessions, user_cache,
+    },
+    utils::client::ClientInfo,
+};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Start the background task purging accounts whose grace period has ended
+pub fn spawn_sweeper(db: DatabaseConnection, config: Config) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            config.account_deletion.sweep_interval_secs,
+        ));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep(&db).await {
+                tracing::warn!(error = %e, "Account deletion sweep failed");
+            }
+        }
+    });
+}
+
+async fn sweep(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let due = user::Entity::find()
+        .filter(user::Column::DeletionScheduledAt.lte(chrono::Utc::now().naive_utc()))
+        .all(db)
+        .await?;
+
+    for u in due {
+        // Held files have to outlive the account, it is purged once the holds are lifted
+        match legal_hold::count_for_user(db, u.id).await {
+            Ok(0) => {}
+            Ok(held) => {
+                tracing::warn!(
+                    user_id = u.id,
+                    held = held,
+                    "Account purge postponed, files under legal hold"
+                );
+                continue;
+            }
+            Err(e) => {
+                tracing::error!(user_id = u.id, error = %e, "Failed to check legal holds");
+                continue;
+            }
+        }
+
+        match purge(db, &u).await {
+            Ok(files) => {
+                audit::record(
+                    db,
+                    None,
+                    AuditEvent::AccountDeleted,
+                    &ClientInfo::default(),
+                    Some(serde_json::json!({ "user_id": u.id, "files": files })),
+                )
+                .await;
+                tracing::info!(user_id = u.id, files = files, "Account purged");
+            }
+            Err(e) => {
+                tracing::error!(user_id = u.id, error = %e, "Failed to purge account");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove an account with its files, grants, shares and history
+/// Audit events and takedown records are kept as the legal record, detached from the account
+/// Its sessions end with it, as tokens of a missing user are rejected
+/// Returns the number of file records removed
+pub async fn purge(db: &DatabaseConnection, u: &user::Model) -> Result<usize, DbErr> {
+    // Bytes of mounted host folders are not ours to delete
+    let mounts = mount::Entity::find()
+        .filter(mount::Column::UserId.eq(u.id))
+        .all(db)
+        .await?;
+    let files = file::Entity::find()
+        .filter(file::Column::UserId.eq(u.id))
+        .all(db)
+        .await?;
+
+    let mut folders = Vec::new();
+    for f in &files {
+        delete_file_record(db, f.id).await?;
+
+        if mounts.iter().any(|m| m.contains(&f.path)) {
+            continue;
+        }
+        if f.file_type == "folder" {
+            folders.push(PathBuf::from(&f.storage_path));
+            continue;
+        }
+
+        // Copies may share the physical file
+        let remaining = file::Entity::find()
+            .filter(file::Column::StoragePath.eq(&f.storage_path))
+            .one(db)
+            .await?;
+        if remaining.is_none() {
+            if let Err(e) = tokio::fs::remove_file(&f.storage_path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!(file_id = f.id, error = %e, "Failed to delete physical file");
+                }
+            }
+        }
+    }
+
+    // Deepest first, folders still holding shared files stay
+    folders.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+    for folder in folders {
+        let _ = tokio::fs::remove_dir(&folder).await;
+    }
+    if let Some(root) = &u.storage_root {
+        let _ = tokio::fs::remove_dir(PathBuf::from(root).join(u.id.to_string())).await;
+    }
+
+    upload_sessions::remove_for_user(db, u.id).await?;
+
+    let jobs = job::Entity::find()
+        .filter(job::Column::UserId.eq(u.id))
+        .all(db)
+        .await?;
+    for path in jobs.iter().filter_map(|j| j.result_path.as_ref()) {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+
+    // Templates are shared configuration, another administrator takes them over
+    let successor: Option<i32> = user::Entity::find()
+        .select_only()
+        .column(user::Column::Id)
+        .filter(user::Column::Role.eq(ROLE_ADMIN))
+        .filter(user::Column::Id.ne(u.id))
+        .order_by_asc(user::Column::Id)
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    let txn = db.begin().await?;
+    match successor {
+        Some(admin_id) => {
+            permission_template::Entity::update_many()
+                .col_expr(
+                    permission_template::Column::CreatedBy,
+                    Expr::value(admin_id),
+                )
+                .filter(permission_template::Column::CreatedBy.eq(u.id))
+                .exec(&txn)
+                .await?;
+        }
+        None => {
+            permission_template::Entity::delete_many()
+                .filter(permission_template::Column::CreatedBy.eq(u.id))
+                .exec(&txn)
+                .await?;
+        }
+    }
+    // The search index drops files that are gone on its next rebuild
+    file_change::Entity::delete_many()
+        .filter(file_change::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    file_permission::Entity::delete_many()
+        .filter(file_permission::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    folder_default_permission::Entity::delete_many()
+        .filter(folder_default_permission::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    share_link::Entity::delete_many()
+        .filter(share_link::Column::CreatedBy.eq(u.id))
+        .exec(&txn)
+        .await?;
+    folder_style::Entity::delete_many()
+        .filter(folder_style::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    organize_rule::Entity::delete_many()
+        .filter(organize_rule::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    cleanup_rule::Entity::delete_many()
+        .filter(cleanup_rule::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    feature_override::Entity::delete_many()
+        .filter(feature_override::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    usage_snapshot::Entity::delete_many()
+        .filter(usage_snapshot::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    download_token::Entity::delete_many()
+        .filter(download_token::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    file_star::Entity::delete_many()
+        .filter(file_star::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    group_member::Entity::delete_many()
+        .filter(group_member::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    mount::Entity::delete_many()
+        .filter(mount::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    notification::Entity::delete_many()
+        .filter(notification::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    notification_preference::Entity::delete_many()
+        .filter(notification_preference::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    job::Entity::delete_many()
+        .filter(job::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    // The audit trail outlives the account, its events are kept without the user
+    audit_log::Entity::update_many()
+        .col_expr(audit_log::Column::UserId, Expr::value(Option::<i32>::None))
+        .filter(audit_log::Column::UserId.eq(u.id))
+        .exec(&txn)
+        .await?;
+    user::Entity::delete_by_id(u.id).exec(&txn).await?;
+    txn.commit().await?;
+
+    user_cache::invalidate(u.id);
+    permission_cache::invalidate_user(u.id);
+
+    Ok(files.len())
+}
@@ -0,0 +1,186 @@
+use crate::{
+    entities::{audit_log, daily_download, file, user},
+    models::admin::{
+        AdminStats, DailyCount, DatabasePoolStats, DedupStats, StorageConsumer, StorageGrowthPoint,
+        UserStats,
+    },
+    services::audit::AuditEvent,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
+};
+use std::collections::HashMap;
+
+const TOP_CONSUMERS: u64 = 10;
+
+/// Aggregate dashboard numbers over the last `days` days (UTC)
+pub async fn collect(db: &DatabaseConnection, days: i64) -> Result<AdminStats, DbErr> {
+    let today = Utc::now().date_naive();
+    let first_day = today - Duration::days(days - 1);
+    let since = first_day.and_hms_opt(0, 0, 0).unwrap_or_default();
+
+    let users = UserStats {
+        total: user::Entity::find().count(db).await?,
+        active: audit_log::Entity::find()
+            .select_only()
+            .column(audit_log::Column::UserId)
+            .distinct()
+            .filter(audit_log::Column::Event.eq(AuditEvent::Login.as_str()))
+            .filter(audit_log::Column::CreatedAt.gte(since))
+            .count(db)
+            .await?,
+    };
+
+    // Files added per day: (day, count, bytes)
+    let added: Vec<(String, i64, Option<i64>)> = file::Entity::find()
+        .select_only()
+        .column_as(Expr::cust("date(created_at)"), "day")
+        .column_as(file::Column::Id.count(), "count")
+        .column_as(file::Column::SizeBytes.sum(), "bytes")
+        .filter(file::Column::FileType.eq("file"))
+        .filter(file::Column::CreatedAt.gte(since))
+        .group_by(Expr::cust("date(created_at)"))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let added: HashMap<String, (i64, i64)> = added
+        .into_iter()
+        .map(|(day, count, bytes)| (day, (count, bytes.unwrap_or(0))))
+        .collect();
+
+    let downloads: HashMap<String, i64> = daily_download::Entity::find()
+        .filter(daily_download::Column::Day.gte(first_day))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|d| (d.day.format("%Y-%m-%d").to_string(), d.download_count))
+        .collect();
+
+    let bytes_before: Option<i64> = file::Entity::find()
+        .select_only()
+        .column_as(file::Column::SizeBytes.sum(), "bytes")
+        .filter(file::Column::FileType.eq("file"))
+        .filter(file::Column::CreatedAt.lt(since))
+        .into_tuple()
+        .one(db)
+        .await?
+        .flatten();
+
+    let mut uploads_per_day = Vec::new();
+    let mut downloads_per_day = Vec::new();
+    let mut storage_growth = Vec::new();
+    let mut total_bytes = bytes_before.unwrap_or(0);
+    for day in days_between(first_day, today) {
+        let key = day.format("%Y-%m-%d").to_string();
+        let (count, bytes) = added.get(&key).copied().unwrap_or((0, 0));
+        total_bytes += bytes;
+        uploads_per_day.push(DailyCount {
+            day: key.clone(),
+            count,
+        });
+        downloads_per_day.push(DailyCount {
+            day: key.clone(),
+            count: downloads.get(&key).copied().unwrap_or(0),
+        });
+        storage_growth.push(StorageGrowthPoint {
+            day: key,
+            added_bytes: bytes,
+            total_bytes,
+        });
+    }
+
+    Ok(AdminStats {
+        window_days: days,
+        users,
+        uploads_per_day,
+        downloads_per_day,
+        storage_growth,
+        deduplication: dedup_stats(db).await?,
+        top_consumers: top_consumers(db).await?,
+        database_pool: pool_stats(db),
+    })
+}
+
+/// Current usage of the database connection pool
+pub fn pool_stats(db: &DatabaseConnection) -> DatabasePoolStats {
+    let pool = db.get_sqlite_connection_pool();
+    DatabasePoolStats {
+        size: pool.size(),
+        idle: pool.num_idle(),
+        max_connections: pool.options().get_max_connections(),
+    }
+}
+
+/// Compare the bytes users see with the bytes stored once per physical file
+async fn dedup_stats(db: &DatabaseConnection) -> Result<DedupStats, DbErr> {
+    let row = db
+        .query_one(Statement::from_string(
+            db.get_database_backend(),
+            "SELECT \
+                (SELECT COALESCE(SUM(size_bytes), 0) FROM files WHERE file_type = 'file') AS logical_bytes, \
+                (SELECT COALESCE(SUM(size_bytes), 0) FROM \
+                    (SELECT MAX(size_bytes) AS size_bytes FROM files \
+                     WHERE file_type = 'file' GROUP BY storage_path)) AS physical_bytes, \
+                (SELECT COALESCE(SUM(size_bytes), 0) FROM \
+                    (SELECT MAX(size_bytes) AS size_bytes FROM files \
+                     WHERE file_type = 'file' GROUP BY COALESCE(file_hash, storage_path))) AS unique_bytes"
+                .to_string(),
+        ))
+        .await?;
+
+    let (logical_bytes, physical_bytes, unique_bytes) = match row {
+        Some(row) => (
+            row.try_get::<i64>("", "logical_bytes")?,
+            row.try_get::<i64>("", "physical_bytes")?,
+            row.try_get::<i64>("", "unique_bytes")?,
+        ),
+        None => (0, 0, 0),
+    };
+
+    Ok(DedupStats {
+        logical_bytes,
+        physical_bytes,
+        saved_bytes: logical_bytes - physical_bytes,
+        reclaimable_bytes: (physical_bytes - unique_bytes).max(0),
+    })
+}
+
+/// Users storing the most bytes
+async fn top_consumers(db: &DatabaseConnection) -> Result<Vec<StorageConsumer>, DbErr> {
+    let rows: Vec<(i32, i64, Option<i64>)> = file::Entity::find()
+        .select_only()
+        .column(file::Column::UserId)
+        .column_as(file::Column::Id.count(), "file_count")
+        .column_as(file::Column::SizeBytes.sum(), "total_bytes")
+        .filter(file::Column::FileType.eq("file"))
+        .group_by(file::Column::UserId)
+        .order_by_desc(Expr::cust("total_bytes"))
+        .limit(TOP_CONSUMERS)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let usernames: HashMap<i32, String> = user::Entity::find()
+        .filter(user::Column::Id.is_in(rows.iter().map(|(id, _, _)| *id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u.username))
+        .collect();
+
+    Ok(rows
+        .into_iter()
+        .map(|(user_id, file_count, total_bytes)| StorageConsumer {
+            user_id,
+            username: usernames.get(&user_id).cloned().unwrap_or_default(),
+            file_count,
+            total_bytes: total_bytes.unwrap_or(0),
+        })
+        .collect())
+}
+
+fn days_between(first: NaiveDate, last: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    first.iter_days().take_while(move |d| *d <= last)
+}
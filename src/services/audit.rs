@@ -0,0 +1,113 @@
+use crate::{entities::audit_log, utils::client::ClientInfo};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+/// Kinds of events recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    Login,
+    LoginFailed,
+    NewDeviceLogin,
+    Logout,
+    LogoutEverywhere,
+    PasswordChanged,
+    ProfileUpdated,
+    EmailChangeRequested,
+    EmailChanged,
+    AccountDeletionRequested,
+    AccountDeletionCancelled,
+    AccountDeleted,
+    TokenRefreshed,
+    FileDeleted,
+    PermissionGranted,
+    PermissionsCopied,
+    ShareLinkCreated,
+    ShareLinkRevoked,
+    AnnouncementPublished,
+    LegalHoldPlaced,
+    LegalHoldLifted,
+    FeatureOverrideSet,
+    FeatureOverrideRemoved,
+    DownloadUrlCreated,
+    QuarantineReleased,
+    QuarantineDeleted,
+    QuarantineDownloaded,
+    TakedownIssued,
+    TakedownCountered,
+    TakedownRestored,
+    TakedownUpheld,
+}
+
+impl AuditEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEvent::Login => "login",
+            AuditEvent::LoginFailed => "login_failed",
+            AuditEvent::NewDeviceLogin => "new_device_login",
+            AuditEvent::Logout => "logout",
+            AuditEvent::LogoutEverywhere => "logout_everywhere",
+            AuditEvent::PasswordChanged => "password_changed",
+            AuditEvent::ProfileUpdated => "profile_updated",
+            AuditEvent::EmailChangeRequested => "email_change_requested",
+            AuditEvent::EmailChanged => "email_changed",
+            AuditEvent::AccountDeletionRequested => "account_deletion_requested",
+            AuditEvent::AccountDeletionCancelled => "account_deletion_cancelled",
+            AuditEvent::AccountDeleted => "account_deleted",
+            AuditEvent::TokenRefreshed => "token_refreshed",
+            AuditEvent::FileDeleted => "file_deleted",
+            AuditEvent::PermissionGranted => "permission_granted",
+            AuditEvent::PermissionsCopied => "permissions_copied",
+            AuditEvent::ShareLinkCreated => "share_link_created",
+            AuditEvent::ShareLinkRevoked => "share_link_revoked",
+            AuditEvent::AnnouncementPublished => "announcement_published",
+            AuditEvent::LegalHoldPlaced => "legal_hold_placed",
+            AuditEvent::LegalHoldLifted => "legal_hold_lifted",
+            AuditEvent::FeatureOverrideSet => "feature_override_set",
+            AuditEvent::FeatureOverrideRemoved => "feature_override_removed",
+            AuditEvent::DownloadUrlCreated => "download_url_created",
+            AuditEvent::QuarantineReleased => "quarantine_released",
+            AuditEvent::QuarantineDeleted => "quarantine_deleted",
+            AuditEvent::QuarantineDownloaded => "quarantine_downloaded",
+            AuditEvent::TakedownIssued => "takedown_issued",
+            AuditEvent::TakedownCountered => "takedown_countered",
+            AuditEvent::TakedownRestored => "takedown_restored",
+            AuditEvent::TakedownUpheld => "takedown_upheld",
+        }
+    }
+}
+
+/// Record an audit event
+/// Failures are logged but never fail the request that triggered them
+pub async fn record(
+    db: &DatabaseConnection,
+    user_id: Option<i32>,
+    event: AuditEvent,
+    client: &ClientInfo,
+    details: Option<serde_json::Value>,
+) {
+    record_file(db, user_id, event, client, None, details).await;
+}
+
+/// Record an audit event concerning a specific file
+pub async fn record_file(
+    db: &DatabaseConnection,
+    user_id: Option<i32>,
+    event: AuditEvent,
+    client: &ClientInfo,
+    file_id: Option<i32>,
+    details: Option<serde_json::Value>,
+) {
+    let entry = audit_log::ActiveModel {
+        user_id: Set(user_id),
+        event: Set(event.as_str().to_string()),
+        file_id: Set(file_id),
+        ip_address: Set(client.ip.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        details: Set(details.map(|d| d.to_string())),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    if let Err(e) = entry.insert(db).await {
+        tracing::warn!(error = %e, event = event.as_str(), "Failed to record audit event");
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{
+    config::Config,
+    entities::{backup_run, file, file_change},
+    services::{
+        changes, deduplication,
+        job_scheduler::{self, JobClass},
+    },
+    utils::file_utils::in_subtree,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_FAILED: &str = "failed";
+
+const RUNS_DIR: &str = "runs";
+const BLOBS_DIR: &str = "blobs";
+
+/// What one run recorded, replayed in order on restore
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub run_id: i32,
+    pub full: bool,
+    pub created_at: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub user_id: i32,
+    /// Change type, see `services::changes`
+    pub change: String,
+    pub path: String,
+    #[serde(default)]
+    pub previous_path: Option<String>,
+    pub folder: bool,
+    /// Content hash naming the blob, files only
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+/// Run a backup periodically when a target is configured
+pub fn spawn_scheduler(db: DatabaseConnection, config: Config) {
+    if !config.backup.enabled {
+        return;
+    }
+    if config.backup.target.is_none() {
+        tracing::warn!("Backup is enabled but no target is configured");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.backup.interval_secs));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = run(&db, &config).await {
+                tracing::error!(error = %e, "Backup failed");
+            }
+        }
+    });
+}
+
+fn target(config: &Config) -> Result<PathBuf> {
+    config
+        .backup
+        .target
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("No backup target configured"))
+}
+
+/// Back up everything that changed since the last completed run, or all files when a full run is due
+pub async fn run(db: &DatabaseConnection, config: &Config) -> Result<backup_run::Model> {
+    let target = target(config)?;
+
+    let last_full = backup_run::Entity::find()
+        .filter(backup_run::Column::Status.eq(STATUS_COMPLETED))
+        .filter(backup_run::Column::Full.eq(true))
+        .order_by_desc(backup_run::Column::Id)
+        .one(db)
+        .await?;
+    let last = backup_run::Entity::find()
+        .filter(backup_run::Column::Status.eq(STATUS_COMPLETED))
+        .order_by_desc(backup_run::Column::Id)
+        .one(db)
+        .await?;
+    let runs_since_full = match &last_full {
+        Some(f) => {
+            backup_run::Entity::find()
+                .filter(backup_run::Column::Status.eq(STATUS_COMPLETED))
+                .filter(backup_run::Column::Id.gt(f.id))
+                .count(db)
+                .await?
+        }
+        None => 0,
+    };
+    let full = last_full.is_none() || runs_since_full + 1 >= config.backup.full_every_runs;
+
+    // Changes after this point go to the next run
+    let cursor = file_change::Entity::find()
+        .order_by_desc(file_change::Column::Id)
+        .one(db)
+        .await?
+        .map_or(0, |c| c.id);
+
+    let started = backup_run::ActiveModel {
+        status: Set(STATUS_RUNNING.to_string()),
+        full: Set(full),
+        cursor: Set(cursor),
+        entries: Set(0),
+        bytes: Set(0),
+        started_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    tracing::info!(run_id = started.id, full = full, "Backup started");
+
+    let since = last.map_or(0, |r| r.cursor);
+    let outcome = async {
+        let entries = if full {
+            full_entries(db).await?
+        } else {
+            incremental_entries(db, since, cursor).await?
+        };
+        write_run(&target, started.id, full, entries).await
+    }
+    .await;
+
+    let mut finished: backup_run::ActiveModel = started.into();
+    finished.finished_at = Set(Some(Utc::now().naive_utc()));
+    match &outcome {
+        Ok((entries, bytes)) => {
+            finished.status = Set(STATUS_COMPLETED.to_string());
+            finished.entries = Set(*entries as i64);
+            finished.bytes = Set(*bytes as i64);
+        }
+        Err(e) => {
+            finished.status = Set(STATUS_FAILED.to_string());
+            finished.error = Set(Some(e.to_string()));
+        }
+    }
+    let finished = finished.update(db).await?;
+
+    let (entries, bytes) = outcome?;
+    tracing::info!(
+        run_id = finished.id,
+        entries = entries,
+        bytes = bytes,
+        "Backup completed"
+    );
+    Ok(finished)
+}
+
+fn entry(f: &file::Model, change: &str, path: &str) -> ManifestEntry {
+    ManifestEntry {
+        user_id: f.user_id,
+        change: change.to_string(),
+        path: path.to_string(),
+        previous_path: None,
+        folder: f.file_type == "folder",
+        hash: None,
+    }
+}
+
+/// Every file and folder, parents before their children
+async fn full_entries(
+    db: &DatabaseConnection,
+) -> Result<Vec<(ManifestEntry, Option<file::Model>)>> {
+    let files = file::Entity::find()
+        .order_by_asc(file::Column::UserId)
+        .order_by_asc(file::Column::Path)
+        .all(db)
+        .await?;
+
+    Ok(files
+        .into_iter()
+        .map(|f| (entry(&f, changes::CHANGE_CREATED, &f.path), Some(f)))
+        .collect())
+}
+
+/// Entries for the changes in `(since, cursor]`, with the current content of created and updated files
+/// A created folder brings its current subtree along, e.g. for copies recorded only at the top
+async fn incremental_entries(
+    db: &DatabaseConnection,
+    since: i32,
+    cursor: i32,
+) -> Result<Vec<(ManifestEntry, Option<file::Model>)>> {
+    let feed = file_change::Entity::find()
+        .filter(file_change::Column::Id.gt(since))
+        .filter(file_change::Column::Id.lte(cursor))
+        .order_by_asc(file_change::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut entries = Vec::new();
+    for c in feed {
+        match c.change.as_str() {
+            changes::CHANGE_MOVED | changes::CHANGE_DELETED => {
+                entries.push((
+                    ManifestEntry {
+                        user_id: c.user_id,
+                        change: c.change.clone(),
+                        path: c.path.clone(),
+                        previous_path: c.previous_path.clone(),
+                        folder: false,
+                        hash: None,
+                    },
+                    None,
+                ));
+            }
+            _ => {
+                // Gone since, a later deletion entry covers it
+                let Some(f) = file::Entity::find_by_id(c.file_id).one(db).await? else {
+                    continue;
+                };
+                if f.file_type == "folder" {
+                    let descendants = file::Entity::find()
+                        .filter(file::Column::UserId.eq(f.user_id))
+                        .filter(file::Column::Path.starts_with(format!("{}/", f.path)))
+                        .order_by_asc(file::Column::Path)
+                        .all(db)
+                        .await?;
+                    entries.push((entry(&f, &c.change, &c.path), None));
+                    for d in descendants {
+                        let path = format!("{}{}", c.path, &d.path[f.path.len()..]);
+                        entries.push((entry(&d, &c.change, &path), Some(d)));
+                    }
+                } else {
+                    entries.push((entry(&f, &c.change, &c.path), Some(f)));
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Copy the blobs of a run and write its manifest last, so only complete runs are restored
+async fn write_run(
+    target: &Path,
+    run_id: i32,
+    full: bool,
+    entries: Vec<(ManifestEntry, Option<file::Model>)>,
+) -> Result<(usize, u64)> {
+    let target = target.to_path_buf();
+    let _permit = job_scheduler::acquire(JobClass::Hashing).await;
+    tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(target.join(RUNS_DIR))?;
+
+        let mut bytes = 0u64;
+        let mut written = Vec::with_capacity(entries.len());
+        for (mut entry, source) in entries {
+            if let Some(f) = source.filter(|f| f.file_type == "file") {
+                match copy_blob(&target, &f) {
+                    Ok((hash, copied)) => {
+                        entry.hash = Some(hash);
+                        bytes += copied;
+                    }
+                    Err(e) => {
+                        tracing::warn!(file_id = f.id, path = %f.storage_path, error = %e, "File left out of backup");
+                        continue;
+                    }
+                }
+            }
+            written.push(entry);
+        }
+
+        let manifest = Manifest {
+            run_id,
+            full,
+            created_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            entries: written,
+        };
+        let count = manifest.entries.len();
+        let path = manifest_path(&target, run_id);
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(&manifest)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok((count, bytes))
+    })
+    .await?
+}
+
+fn manifest_path(target: &Path, run_id: i32) -> PathBuf {
+    target.join(RUNS_DIR).join(format!("{:08}.json", run_id))
+}
+
+fn blob_path(target: &Path, hash: &str) -> PathBuf {
+    target
+        .join(BLOBS_DIR)
+        .join(&hash[..2.min(hash.len())])
+        .join(hash)
+}
+
+/// Store a file's content by hash, returns the hash and the bytes copied (0 when already stored)
+fn copy_blob(target: &Path, f: &file::Model) -> Result<(String, u64)> {
+    let source = PathBuf::from(&f.storage_path);
+    let hash = match &f.file_hash {
+        Some(h) => h.clone(),
+        None => deduplication::calculate_hash_from_file(&source)?,
+    };
+
+    let blob = blob_path(target, &hash);
+    if blob.is_file() {
+        return Ok((hash, 0));
+    }
+    let dir = blob.parent().context("Invalid blob path")?;
+    std::fs::create_dir_all(dir)?;
+    let partial = blob.with_extension("partial");
+    let copied = std::fs::copy(&source, &partial)?;
+    std::fs::rename(&partial, &blob)?;
+    Ok((hash, copied))
+}
+
+/// Rebuild the trees as of `run_id` (latest run when None) into `output/<user_id>/...`
+/// Replays the manifests from the last full run up to that run, reads the target only
+pub fn restore(
+    config: &Config,
+    output: &Path,
+    run_id: Option<i32>,
+    user_id: Option<i32>,
+) -> Result<usize> {
+    let target = target(config)?;
+
+    let mut manifests: Vec<Manifest> = Vec::new();
+    let mut run_ids: Vec<i32> = std::fs::read_dir(target.join(RUNS_DIR))
+        .with_context(|| format!("No backup runs in {:?}", target))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.strip_suffix(".json")?.parse().ok()
+        })
+        .filter(|id| run_id.is_none_or(|r| *id <= r))
+        .collect();
+    run_ids.sort_unstable();
+
+    // Walk back to the last full run
+    for id in run_ids.into_iter().rev() {
+        let raw = std::fs::read(manifest_path(&target, id))?;
+        let manifest: Manifest = serde_json::from_slice(&raw)
+            .with_context(|| format!("Invalid manifest for run {}", id))?;
+        let full = manifest.full;
+        manifests.push(manifest);
+        if full {
+            break;
+        }
+    }
+    if !manifests.last().is_some_and(|m| m.full) {
+        return Err(anyhow!("No full backup run to restore from"));
+    }
+    manifests.reverse();
+
+    // (user_id, path) -> blob hash, None for folders
+    let mut tree: BTreeMap<(i32, String), Option<String>> = BTreeMap::new();
+    for manifest in &manifests {
+        for e in &manifest.entries {
+            if user_id.is_some_and(|u| u != e.user_id) {
+                continue;
+            }
+            apply(&mut tree, e);
+        }
+    }
+
+    let mut restored = 0;
+    for ((owner, path), hash) in &tree {
+        let dest = output
+            .join(owner.to_string())
+            .join(path.trim_start_matches('/'));
+        match hash {
+            None => std::fs::create_dir_all(&dest)?,
+            Some(hash) => {
+                if let Some(parent) = dest.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::copy(blob_path(&target, hash), &dest)
+                    .with_context(|| format!("Failed to restore {}", path))?;
+                restored += 1;
+            }
+        }
+    }
+
+    Ok(restored)
+}
+
+fn apply(tree: &mut BTreeMap<(i32, String), Option<String>>, e: &ManifestEntry) {
+    match e.change.as_str() {
+        changes::CHANGE_DELETED => {
+            tree.retain(|(u, p), _| *u != e.user_id || !in_subtree(p, &e.path));
+        }
+        changes::CHANGE_MOVED => {
+            let Some(from) = &e.previous_path else { return };
+            let moved: Vec<(i32, String)> = tree
+                .keys()
+                .filter(|(u, p)| *u == e.user_id && in_subtree(p, from))
+                .cloned()
+                .collect();
+            for key in moved {
+                if let Some(hash) = tree.remove(&key) {
+                    let path = format!("{}{}", e.path, &key.1[from.len()..]);
+                    tree.insert((e.user_id, path), hash);
+                }
+            }
+        }
+        _ => {
+            let hash = if e.folder { None } else { e.hash.clone() };
+            if e.folder || hash.is_some() {
+                tree.insert((e.user_id, e.path.clone()), hash);
+            }
+        }
+    }
+}
+
+/// Backup runs, newest first
+pub async fn list_runs(db: &DatabaseConnection, limit: u64) -> Result<Vec<backup_run::Model>> {
+    Ok(backup_run::Entity::find()
+        .order_by_desc(backup_run::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?)
+}
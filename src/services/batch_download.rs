@@ -1,45 +1,7 @@
-use crate::entities::{file, user};
-use crate::utils::jwt;
+use crate::entities::file;
 use anyhow::Result;
-use axum::http::StatusCode;
 use sea_orm::{DatabaseConnection, EntityTrait};
 
-/// Extract and validate user from request
-pub async fn extract_user_from_request(
-    db: &DatabaseConnection,
-    claims: &jwt::Claims,
-    request_id: &str,
-) -> Result<user::Model, (StatusCode, String, String)> {
-    let user_id = claims.sub.parse::<i32>().map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            request_id.to_string(),
-            "Invalid user ID".to_string(),
-        )
-    })?;
-
-    let user_entity = user::Entity::find_by_id(user_id)
-        .one(db)
-        .await
-        .map_err(|e| {
-            tracing::error!(request_id = %request_id, error = ?e, "Failed to query user");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                request_id.to_string(),
-                "Database error".to_string(),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                request_id.to_string(),
-                "User not found".to_string(),
-            )
-        })?;
-
-    Ok(user_entity)
-}
-
 /// Handle single file download optimization
 pub async fn try_single_file_download(
     db: &DatabaseConnection,
@@ -0,0 +1,75 @@
+use crate::entities::{file, file_change};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use std::collections::HashSet;
+
+pub const CHANGE_CREATED: &str = "created";
+pub const CHANGE_UPDATED: &str = "updated";
+pub const CHANGE_MOVED: &str = "moved";
+pub const CHANGE_DELETED: &str = "deleted";
+pub const CHANGE_PINNED: &str = "pinned";
+pub const CHANGE_UNPINNED: &str = "unpinned";
+/// An upload hit a file changed on the server and was stored as a conflicted copy
+pub const CHANGE_CONFLICT: &str = "conflict";
+
+/// Append a change of `f` to its owner's feed
+/// Failures are logged but never fail the operation that made the change
+pub async fn record(
+    db: &DatabaseConnection,
+    f: &file::Model,
+    change: &str,
+    previous_path: Option<&str>,
+) {
+    let entry = file_change::ActiveModel {
+        user_id: Set(f.user_id),
+        file_id: Set(f.id),
+        change: Set(change.to_string()),
+        path: Set(f.path.clone()),
+        previous_path: Set(previous_path.map(str::to_string)),
+        client_modified: Set(f.client_modified),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    if let Err(e) = entry.insert(db).await {
+        tracing::warn!(file_id = f.id, change = %change, error = %e, "Failed to record file change");
+    }
+}
+
+/// Changes in a user's tree after the `since` cursor, oldest first
+pub async fn list(
+    db: &DatabaseConnection,
+    user_id: i32,
+    since: i32,
+    limit: u64,
+) -> Result<Vec<file_change::Model>, DbErr> {
+    file_change::Entity::find()
+        .filter(file_change::Column::UserId.eq(user_id))
+        .filter(file_change::Column::Id.gt(since))
+        .order_by_asc(file_change::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await
+}
+
+/// Which of `file_ids` are currently pinned
+pub async fn pinned_among(
+    db: &DatabaseConnection,
+    file_ids: &[i32],
+) -> Result<HashSet<i32>, DbErr> {
+    if file_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let ids: Vec<i32> = file::Entity::find()
+        .select_only()
+        .column(file::Column::Id)
+        .filter(file::Column::Id.is_in(file_ids.iter().copied()))
+        .filter(file::Column::Pinned.eq(true))
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(ids.into_iter().collect())
+}
@@ -0,0 +1,258 @@
+use crate::{
+    config::Config,
+    entities::{cleanup_rule, file, mount, user},
+    handlers::file::delete_file_record,
+    services::{
+        audit::{self, AuditEvent},
+        changes,
+        events::EventBus,
+        folder_sizes, jobs, legal_hold,
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+    utils::{client::ClientInfo, file_utils},
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{
+    sea_query::{Expr, LikeExpr},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Set,
+};
+
+/// Rules one user can keep
+pub const MAX_RULES: u64 = 100;
+
+/// Start the background task running the users' cleanup rules
+/// Files due are announced first and deleted once the notice period has passed
+pub fn spawn_scheduler(db: DatabaseConnection, config: Config, events: EventBus) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(config.cleanup.interval_secs));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = run(&db, &config, &events).await {
+                tracing::warn!(error = %e, "Cleanup run failed");
+            }
+        }
+    });
+}
+
+/// Time the files announced by a notice sent at `warned_at` are deleted
+pub fn deletion_time(config: &Config, warned_at: NaiveDateTime) -> NaiveDateTime {
+    warned_at + Duration::hours(config.cleanup.notice_hours)
+}
+
+async fn run(db: &DatabaseConnection, config: &Config, events: &EventBus) -> Result<(), DbErr> {
+    let now = Utc::now().naive_utc();
+    for rule in cleanup_rule::Entity::find().all(db).await? {
+        let rule_id = rule.id;
+        let result = match rule.warned_at {
+            None => warn(db, config, rule, now).await,
+            Some(warned_at) if deletion_time(config, warned_at) <= now => {
+                execute(db, events, rule, deletion_time(config, warned_at)).await
+            }
+            Some(_) => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::warn!(rule_id = rule_id, error = %e, "Cleanup rule failed");
+        }
+    }
+    Ok(())
+}
+
+/// Files of a rule's folder older than its maximum age at `at`
+/// Held files and files of mounted host folders are never cleaned up
+pub async fn due_files(
+    db: &DatabaseConnection,
+    rule: &cleanup_rule::Model,
+    at: NaiveDateTime,
+) -> Result<Vec<file::Model>, DbErr> {
+    let cutoff = at - Duration::days(rule.max_age_days as i64);
+    let in_folder = if rule.folder_path == "/" {
+        Condition::all()
+    } else {
+        Condition::any()
+            .add(file::Column::ParentPath.eq(&rule.folder_path))
+            .add(Expr::col(file::Column::Path).like(
+                LikeExpr::new(file_utils::descendant_pattern(&rule.folder_path)).escape('\\'),
+            ))
+    };
+    let candidates = file::Entity::find()
+        .filter(file::Column::UserId.eq(rule.user_id))
+        .filter(file::Column::FileType.eq("file"))
+        .filter(file::Column::UpdatedAt.lt(cutoff))
+        .filter(in_folder)
+        .all(db)
+        .await?;
+
+    let mounts = mount::Entity::find()
+        .filter(mount::Column::UserId.eq(rule.user_id))
+        .all(db)
+        .await?;
+    let mut due = Vec::new();
+    for f in candidates {
+        if !matches(rule, &f.name) || mounts.iter().any(|m| m.contains(&f.path)) {
+            continue;
+        }
+        if legal_hold::holding(db, &f).await?.is_none() {
+            due.push(f);
+        }
+    }
+    Ok(due)
+}
+
+fn matches(rule: &cleanup_rule::Model, name: &str) -> bool {
+    let name = name.to_lowercase();
+    let (_, extension) = file_utils::split_filename(&name);
+    rule.extensions
+        .as_deref()
+        .is_none_or(|list| list.split(',').any(|e| e == extension))
+        && rule
+            .name_contains
+            .as_deref()
+            .is_none_or(|text| name.contains(text))
+}
+
+/// Tell the owner which files the rule will delete, nothing is sent while none are due
+async fn warn(
+    db: &DatabaseConnection,
+    config: &Config,
+    rule: cleanup_rule::Model,
+    now: NaiveDateTime,
+) -> Result<(), DbErr> {
+    let delete_at = deletion_time(config, now);
+    let due = due_files(db, &rule, delete_at).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    let Some(owner) = user::Entity::find_by_id(rule.user_id).one(db).await? else {
+        return Ok(());
+    };
+
+    let time = delete_at.format("%Y-%m-%d %H:%M").to_string();
+    let notification = Notification {
+        category: NotificationCategory::Job,
+        title: format!(
+            "{} files in {} will be deleted",
+            due.len(),
+            rule.folder_path
+        ),
+        body: format!(
+            "Files unchanged for more than {} days will be deleted by your cleanup rule at {} UTC",
+            rule.max_age_days, time
+        ),
+        link: None,
+        email: Some(EmailTemplate::CleanupNotice {
+            username: owner.username.clone(),
+            folder_path: rule.folder_path.clone(),
+            count: due.len(),
+            max_age_days: rule.max_age_days,
+            delete_at: time,
+        }),
+    };
+    notifications::dispatch(db, &config.smtp, &owner, notification).await?;
+
+    tracing::info!(
+        rule_id = rule.id,
+        user_id = rule.user_id,
+        files = due.len(),
+        "Cleanup announced"
+    );
+    let mut active: cleanup_rule::ActiveModel = rule.into();
+    active.warned_at = Set(Some(now));
+    active.update(db).await?;
+    Ok(())
+}
+
+/// Delete the files announced by the last notice, as a job of the owner
+/// Files changed since then are no longer old enough and stay
+async fn execute(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    rule: cleanup_rule::Model,
+    delete_at: NaiveDateTime,
+) -> Result<(), DbErr> {
+    let due = due_files(db, &rule, delete_at).await?;
+    if !due.is_empty() {
+        let total_bytes = due.iter().map(|f| f.size_bytes.unwrap_or(0)).sum();
+        let job = jobs::create(
+            db,
+            events,
+            rule.user_id,
+            jobs::KIND_CLEANUP,
+            jobs::STATUS_RUNNING,
+            due.len() as i64,
+            total_bytes,
+        )
+        .await?;
+        let mut progress = jobs::ProgressReporter::new(events, job);
+
+        let mut failed = 0;
+        for f in &due {
+            match delete(db, &rule, f).await {
+                Ok(()) => progress.advance(1, f.size_bytes.unwrap_or(0)),
+                Err(e) => {
+                    tracing::warn!(rule_id = rule.id, file_id = f.id, error = %e, "Failed to clean up file");
+                    failed += 1;
+                }
+            }
+        }
+        let result = match failed {
+            0 => Ok(()),
+            n => Err(format!("{} files could not be deleted", n)),
+        };
+        progress.finish(db, result).await;
+        tracing::info!(
+            rule_id = rule.id,
+            user_id = rule.user_id,
+            files = due.len() - failed,
+            "Cleanup rule applied"
+        );
+    }
+
+    let mut active: cleanup_rule::ActiveModel = rule.into();
+    active.warned_at = Set(None);
+    active.last_run_at = Set(Some(Utc::now().naive_utc()));
+    active.update(db).await?;
+    Ok(())
+}
+
+async fn delete(
+    db: &DatabaseConnection,
+    rule: &cleanup_rule::Model,
+    f: &file::Model,
+) -> Result<(), DbErr> {
+    delete_file_record(db, f.id).await?;
+    changes::record(db, f, changes::CHANGE_DELETED, None).await;
+    audit::record_file(
+        db,
+        Some(rule.user_id),
+        AuditEvent::FileDeleted,
+        &ClientInfo::default(),
+        Some(f.id),
+        Some(serde_json::json!({
+            "name": f.name,
+            "path": f.path,
+            "file_type": f.file_type,
+            "cleanup_rule": rule.id,
+        })),
+    )
+    .await;
+
+    // Copies may share the physical file
+    let remaining = file::Entity::find()
+        .filter(file::Column::StoragePath.eq(&f.storage_path))
+        .one(db)
+        .await?;
+    if remaining.is_none() {
+        if let Err(e) = tokio::fs::remove_file(&f.storage_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(file_id = f.id, error = %e, "Failed to delete physical file");
+            }
+        }
+    }
+
+    folder_sizes::adjust(db, f.user_id, &f.parent_path, -f.size_bytes.unwrap_or(0)).await;
+    Ok(())
+}
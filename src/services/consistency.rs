@@ -0,0 +1,266 @@
+use crate::{
+    config::Config,
+    entities::file,
+    handlers::file::delete_file_record,
+    models::admin::{ConsistencyIssue, ConsistencyRepairs, ConsistencyReport},
+    services::{changes, folder_sizes, storage_health},
+    utils::file_utils,
+};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const ISSUE_MISSING_FILE: &str = "missing_file";
+pub const ISSUE_SIZE_MISMATCH: &str = "size_mismatch";
+pub const ISSUE_MISSING_FOLDER: &str = "missing_folder";
+pub const ISSUE_ORPHANED_CHILD: &str = "orphaned_child";
+
+/// Drop the records of files whose bytes are gone
+pub const REPAIR_REMOVE_MISSING: &str = "remove_missing";
+/// Take the size of the bytes on disk
+pub const REPAIR_FIX_SIZES: &str = "fix_sizes";
+/// Recreate the directories of folder records
+pub const REPAIR_CREATE_FOLDERS: &str = "create_folders";
+/// Recreate the missing folder records above orphaned children
+pub const REPAIR_RESTORE_PARENTS: &str = "restore_parents";
+
+pub const REPAIR_ACTIONS: [&str; 4] = [
+    REPAIR_REMOVE_MISSING,
+    REPAIR_FIX_SIZES,
+    REPAIR_CREATE_FOLDERS,
+    REPAIR_RESTORE_PARENTS,
+];
+
+/// A record that does not match storage
+struct Finding {
+    kind: &'static str,
+    file: file::Model,
+    /// Size of the bytes on disk for size mismatches
+    actual_size: Option<i64>,
+}
+
+fn repair_for(kind: &str) -> &'static str {
+    match kind {
+        ISSUE_MISSING_FILE => REPAIR_REMOVE_MISSING,
+        ISSUE_SIZE_MISMATCH => REPAIR_FIX_SIZES,
+        ISSUE_MISSING_FOLDER => REPAIR_CREATE_FOLDERS,
+        _ => REPAIR_RESTORE_PARENTS,
+    }
+}
+
+/// Cross-check every file record against storage, listing at most `limit` issues
+/// With `actions`, the issues they cover are repaired before the report is returned
+pub async fn check(
+    db: &DatabaseConnection,
+    config: &Config,
+    limit: usize,
+    actions: Option<&[String]>,
+) -> Result<ConsistencyReport, DbErr> {
+    let records = file::Entity::find().all(db).await?;
+    let checked = records.len() as u64;
+
+    // An unavailable volume would make all of its files look missing
+    let down: Vec<PathBuf> = config
+        .get_storage_volumes()
+        .into_iter()
+        .filter(|v| !storage_health::is_healthy(v))
+        .collect();
+    let (records, unavailable): (Vec<_>, Vec<_>) = records.into_iter().partition(|f| {
+        !down
+            .iter()
+            .any(|v| Path::new(&f.storage_path).starts_with(v))
+    });
+
+    let folders: HashSet<(i32, String)> = records
+        .iter()
+        .chain(unavailable.iter())
+        .filter(|f| f.file_type == "folder")
+        .map(|f| (f.user_id, f.path.clone()))
+        .collect();
+    let mut findings: Vec<Finding> = records
+        .iter()
+        .filter(|f| f.parent_path != "/" && !folders.contains(&(f.user_id, f.parent_path.clone())))
+        .map(|f| Finding {
+            kind: ISSUE_ORPHANED_CHILD,
+            file: f.clone(),
+            actual_size: None,
+        })
+        .collect();
+
+    let on_disk = tokio::task::spawn_blocking(move || {
+        records
+            .into_iter()
+            .filter_map(|f| {
+                let metadata = std::fs::metadata(&f.storage_path).ok();
+                let (kind, actual_size) = if f.file_type == "folder" {
+                    if metadata.is_some_and(|m| m.is_dir()) {
+                        return None;
+                    }
+                    (ISSUE_MISSING_FOLDER, None)
+                } else {
+                    match metadata.filter(|m| m.is_file()) {
+                        None => (ISSUE_MISSING_FILE, None),
+                        Some(m) if m.len() as i64 != f.size_bytes.unwrap_or(0) => {
+                            (ISSUE_SIZE_MISMATCH, Some(m.len() as i64))
+                        }
+                        Some(_) => return None,
+                    }
+                };
+                Some(Finding {
+                    kind,
+                    file: f,
+                    actual_size,
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| DbErr::Custom(e.to_string()))?;
+    findings.extend(on_disk);
+
+    let count = |kind: &str| findings.iter().filter(|f| f.kind == kind).count() as u64;
+    let mut report = ConsistencyReport {
+        checked,
+        skipped: unavailable.len() as u64,
+        missing_files: count(ISSUE_MISSING_FILE),
+        size_mismatches: count(ISSUE_SIZE_MISMATCH),
+        missing_folders: count(ISSUE_MISSING_FOLDER),
+        orphaned_children: count(ISSUE_ORPHANED_CHILD),
+        issues: findings
+            .iter()
+            .take(limit)
+            .map(|f| ConsistencyIssue {
+                kind: f.kind,
+                file_id: f.file.id,
+                owner_id: f.file.user_id,
+                path: f.file.path.clone(),
+                storage_path: f.file.storage_path.clone(),
+                expected_size: (f.kind == ISSUE_SIZE_MISMATCH)
+                    .then_some(f.file.size_bytes.unwrap_or(0)),
+                actual_size: f.actual_size,
+                repair: repair_for(f.kind),
+            })
+            .collect(),
+        repaired: None,
+    };
+
+    if let Some(actions) = actions {
+        report.repaired = Some(repair(db, findings, actions, &folders).await?);
+    }
+    Ok(report)
+}
+
+async fn repair(
+    db: &DatabaseConnection,
+    findings: Vec<Finding>,
+    actions: &[String],
+    folders: &HashSet<(i32, String)>,
+) -> Result<ConsistencyRepairs, DbErr> {
+    let wanted = |action: &str| actions.iter().any(|a| a == action);
+    let mut repairs = ConsistencyRepairs::default();
+    // Missing parents by owner and path, with the storage path derived from a child
+    let mut parents: BTreeMap<(i32, String), PathBuf> = BTreeMap::new();
+
+    for finding in findings {
+        let f = finding.file;
+        match finding.kind {
+            ISSUE_MISSING_FILE if wanted(REPAIR_REMOVE_MISSING) => {
+                match delete_file_record(db, f.id).await {
+                    Ok(()) => {
+                        changes::record(db, &f, changes::CHANGE_DELETED, None).await;
+                        repairs.removed_records += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!(file_id = f.id, error = %e, "Failed to remove record of missing file");
+                        repairs.failed += 1;
+                    }
+                }
+            }
+            ISSUE_SIZE_MISMATCH if wanted(REPAIR_FIX_SIZES) => {
+                let version = f.version;
+                let mut active: file::ActiveModel = f.clone().into();
+                active.size_bytes = Set(finding.actual_size);
+                active.version = Set(version + 1);
+                match active.update(db).await {
+                    Ok(updated) => {
+                        changes::record(db, &updated, changes::CHANGE_UPDATED, None).await;
+                        repairs.fixed_sizes += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!(file_id = f.id, error = %e, "Failed to fix file size");
+                        repairs.failed += 1;
+                    }
+                }
+            }
+            ISSUE_MISSING_FOLDER if wanted(REPAIR_CREATE_FOLDERS) => {
+                match tokio::fs::create_dir_all(&f.storage_path).await {
+                    Ok(()) => repairs.created_folders += 1,
+                    Err(e) => {
+                        tracing::warn!(file_id = f.id, error = %e, "Failed to create folder directory");
+                        repairs.failed += 1;
+                    }
+                }
+            }
+            ISSUE_ORPHANED_CHILD if wanted(REPAIR_RESTORE_PARENTS) => {
+                // storage_path is <root>/<user_id>/<path>, the parents sit above it
+                let depth = file_utils::path_depth(&f.path);
+                let storage_path = PathBuf::from(&f.storage_path);
+                for ancestor in file_utils::ancestor_paths(&f.parent_path) {
+                    if folders.contains(&(f.user_id, ancestor.clone())) {
+                        continue;
+                    }
+                    let up = depth - file_utils::path_depth(&ancestor);
+                    if let Some(dir) = storage_path.ancestors().nth(up) {
+                        parents
+                            .entry((f.user_id, ancestor))
+                            .or_insert_with(|| dir.to_path_buf());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Sorted by path, so every folder is recreated after its own parent
+    let now = chrono::Utc::now().naive_utc();
+    for ((user_id, path), storage_path) in parents {
+        let (parent_path, name) = match path.rfind('/') {
+            Some(0) => ("/".to_string(), path[1..].to_string()),
+            Some(idx) => (path[..idx].to_string(), path[idx + 1..].to_string()),
+            None => continue,
+        };
+        if let Err(e) = tokio::fs::create_dir_all(&storage_path).await {
+            tracing::warn!(user_id = user_id, path = %path, error = %e, "Failed to create parent directory");
+            repairs.failed += 1;
+            continue;
+        }
+        let created = file::ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name),
+            path: Set(path.clone()),
+            parent_path: Set(parent_path),
+            file_type: Set("folder".into()),
+            storage_path: Set(storage_path.to_string_lossy().replace('\\', "/")),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await;
+        match created {
+            Ok(folder) => {
+                changes::record(db, &folder, changes::CHANGE_CREATED, None).await;
+                repairs.restored_parents += 1;
+            }
+            Err(e) => {
+                tracing::warn!(user_id = user_id, path = %path, error = %e, "Failed to restore parent folder");
+                repairs.failed += 1;
+            }
+        }
+    }
+
+    if repairs.removed_records + repairs.fixed_sizes + repairs.restored_parents > 0 {
+        folder_sizes::reconcile(db).await?;
+    }
+    Ok(repairs)
+}
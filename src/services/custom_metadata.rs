@@ -0,0 +1,150 @@
+use crate::entities::{custom_metadata, file};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub const MAX_ENTRIES: usize = 32;
+pub const MAX_KEY_LEN: usize = 64;
+pub const MAX_STRING_LEN: usize = 1024;
+
+const TYPE_STRING: &str = "string";
+const TYPE_NUMBER: &str = "number";
+const TYPE_BOOLEAN: &str = "boolean";
+
+/// Metadata of one file, by key
+pub type Metadata = serde_json::Map<String, Value>;
+
+/// Check a key, letters, digits and `_ - .` only
+pub fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(format!(
+            "Metadata keys must be 1 to {} characters",
+            MAX_KEY_LEN
+        ));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "Metadata key '{}' may only contain letters, digits, '_', '-' and '.'",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// Type and text stored for a value, only strings, numbers and booleans are accepted
+pub fn encode(key: &str, value: &Value) -> Result<(&'static str, String), String> {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_STRING_LEN => Err(format!(
+            "Value of '{}' is longer than {} characters",
+            key, MAX_STRING_LEN
+        )),
+        Value::String(s) => Ok((TYPE_STRING, s.clone())),
+        Value::Number(n) => Ok((TYPE_NUMBER, n.to_string())),
+        Value::Bool(b) => Ok((TYPE_BOOLEAN, b.to_string())),
+        _ => Err(format!(
+            "Value of '{}' must be a string, number or boolean",
+            key
+        )),
+    }
+}
+
+fn decode(entry: custom_metadata::Model) -> Value {
+    match entry.value_type.as_str() {
+        TYPE_NUMBER | TYPE_BOOLEAN => serde_json::from_str(&entry.value).unwrap_or(Value::Null),
+        _ => Value::String(entry.value),
+    }
+}
+
+/// Metadata of the given files, files without any are absent
+pub async fn for_files<C: ConnectionTrait>(
+    db: &C,
+    file_ids: &[i32],
+) -> Result<HashMap<i32, Metadata>, DbErr> {
+    if file_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut metadata: HashMap<i32, Metadata> = HashMap::new();
+    for entry in custom_metadata::Entity::find()
+        .filter(custom_metadata::Column::FileId.is_in(file_ids.iter().copied()))
+        .order_by_asc(custom_metadata::Column::Key)
+        .all(db)
+        .await?
+    {
+        metadata
+            .entry(entry.file_id)
+            .or_default()
+            .insert(entry.key.clone(), decode(entry));
+    }
+    Ok(metadata)
+}
+
+/// Apply a merge patch, `null` removes a key; returns the resulting metadata
+/// The patch must have been checked with `validate_key` and `encode`
+pub async fn apply(
+    db: &DatabaseConnection,
+    f: &file::Model,
+    patch: Metadata,
+) -> Result<Metadata, DbErr> {
+    let txn = db.begin().await?;
+    let now = Utc::now().naive_utc();
+
+    for (key, value) in patch {
+        if value.is_null() {
+            custom_metadata::Entity::delete_many()
+                .filter(custom_metadata::Column::FileId.eq(f.id))
+                .filter(custom_metadata::Column::Key.eq(&key))
+                .exec(&txn)
+                .await?;
+            continue;
+        }
+
+        let (value_type, value) = encode(&key, &value).map_err(DbErr::Custom)?;
+        custom_metadata::Entity::insert(custom_metadata::ActiveModel {
+            file_id: Set(f.id),
+            user_id: Set(f.user_id),
+            key: Set(key),
+            value_type: Set(value_type.to_string()),
+            value: Set(value),
+            updated_at: Set(now),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                custom_metadata::Column::FileId,
+                custom_metadata::Column::Key,
+            ])
+            .update_columns([
+                custom_metadata::Column::ValueType,
+                custom_metadata::Column::Value,
+                custom_metadata::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+    }
+
+    let metadata = for_files(&txn, &[f.id])
+        .await?
+        .remove(&f.id)
+        .unwrap_or_default();
+    txn.commit().await?;
+    Ok(metadata)
+}
+
+/// Drop the metadata of a file
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    custom_metadata::Entity::delete_many()
+        .filter(custom_metadata::Column::FileId.eq(file_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -6,3 +6,11 @@ pub fn calculate_hash_from_bytes(data: &[u8]) -> String {
     hasher.update(data);
     format!("{:x}", hasher.finalize())
 }
+
+/// Calculate SHA-256 hash of a file on disk without loading it into memory
+pub fn calculate_hash_from_file(path: &std::path::Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
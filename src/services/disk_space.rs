@@ -0,0 +1,41 @@
+use std::path::Path;
+use sysinfo::Disks;
+
+/// Free space on the disk with the longest mount point containing `path`
+/// Blocks on the filesystem, async code uses `available_space`
+pub fn available_space_blocking(path: &Path) -> Option<u64> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .map(|d| d.available_space())
+}
+
+/// Free space on the disk holding `path`, measured on a blocking thread
+pub async fn available_space(path: &Path) -> Option<u64> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || available_space_blocking(&path))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Check that `bytes` can be written below `path` while keeping `reserve` bytes free
+/// Passes when the disk cannot be determined, the write itself will still fail if it is full
+pub async fn ensure_free_space(path: &Path, bytes: u64, reserve: u64) -> Result<(), String> {
+    let Some(available) = available_space(path).await else {
+        tracing::warn!(path = ?path, "Could not determine free disk space");
+        return Ok(());
+    };
+
+    if available < bytes.saturating_add(reserve) {
+        return Err(format!(
+            "Not enough free disk space ({} bytes needed, {} bytes available)",
+            bytes,
+            available.saturating_sub(reserve)
+        ));
+    }
+    Ok(())
+}
@@ -1,25 +1,41 @@
-use crate::entities::{file, file_permission};
+use crate::entities::file;
+use crate::handlers::file::{explicit_grants, get_file_permissions, get_files_permissions};
+use crate::services::library;
+use crate::utils::archive::{ArchiveFormat, ArchiveWriter};
 use anyhow::{anyhow, Result};
+use chrono::NaiveDateTime;
 use sea_orm::DatabaseConnection;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tokio::io::AsyncWrite;
 
-/// Result of file collection with metadata for ZIP structure
+/// Result of file collection with metadata for the archive structure
 pub struct CollectedFiles {
     pub files: Vec<file::Model>,
     /// Map of file_id to the root folder info it belongs to (folder_name, folder_path)
-    /// This is used to preserve folder structure in ZIP archives
+    /// This is used to preserve folder structure in archives
     pub folder_roots: HashMap<i32, (String, String)>,
+    /// Folders without anything readable inside, written as directory entries
+    pub empty_folders: Vec<file::Model>,
+}
+
+impl CollectedFiles {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.empty_folders.is_empty()
+    }
 }
 
 /// Collect all files to download based on file IDs
-/// If a file ID points to a folder, recursively collect all files inside
+/// If a file ID points to a folder, recursively collect the files inside it the user can read
 pub async fn collect_files_to_download(
     db: &DatabaseConnection,
     file_ids: Vec<i32>,
     user_id: i32,
+    user_role: &str,
 ) -> Result<CollectedFiles> {
     let mut all_files = Vec::new();
+    let mut all_empty_folders = Vec::new();
     let mut folder_roots = HashMap::new();
 
     for file_id in file_ids {
@@ -29,35 +45,34 @@ pub async fn collect_files_to_download(
             .await?
             .ok_or_else(|| anyhow!("File not found: {}", file_id))?;
 
-        // Check if it's the user's file or shared with them
-        if file_entity.user_id != user_id {
-            // Check if user has permission to this file
-            let permission = file_permission::Entity::find()
-                .filter(file_permission::Column::FileId.eq(file_id))
-                .filter(file_permission::Column::UserId.eq(user_id))
-                .one(db)
-                .await?;
-
-            if permission.is_none() {
-                return Err(anyhow!(
-                    "No permission to access file: {}",
-                    file_entity.name
-                ));
-            }
+        let (can_read, _, _) = get_file_permissions(db, user_id, user_role, &file_entity).await;
+        if !can_read {
+            return Err(anyhow!(
+                "No permission to access file: {}",
+                file_entity.name
+            ));
+        }
+        if file_entity.quarantined_at.is_some() {
+            return Err(anyhow!("File is quarantined: {}", file_entity.name));
+        }
+        if file_entity.taken_down {
+            return Err(anyhow!("File was taken down: {}", file_entity.name));
         }
 
         if file_entity.file_type == "folder" {
             // Recursively collect all files in this folder
             let folder_name = file_entity.name.clone();
             let folder_path = file_entity.path.clone();
-            let folder_files = collect_files_in_folder(db, &folder_path, user_id).await?;
+            let (folder_files, empty_folders) =
+                collect_files_in_folder(db, file_entity, user_id, user_role).await?;
 
             // Mark all files as belonging to this root folder
-            for file in &folder_files {
+            for file in folder_files.iter().chain(&empty_folders) {
                 folder_roots.insert(file.id, (folder_name.clone(), folder_path.clone()));
             }
 
             all_files.extend(folder_files);
+            all_empty_folders.extend(empty_folders);
         } else {
             // It's a file, add it directly (no folder root)
             all_files.push(file_entity);
@@ -67,30 +82,100 @@ pub async fn collect_files_to_download(
     Ok(CollectedFiles {
         files: all_files,
         folder_roots,
+        empty_folders: all_empty_folders,
+    })
+}
+
+/// Collect every file a user owns, laid out under a folder named after them
+pub async fn collect_user_files(
+    db: &DatabaseConnection,
+    user_id: i32,
+    username: &str,
+) -> Result<CollectedFiles> {
+    let records = file::Entity::find()
+        .filter(file::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    let parents: HashSet<&str> = records.iter().map(|f| f.parent_path.as_str()).collect();
+    let empty_folders: Vec<file::Model> = records
+        .iter()
+        .filter(|f| f.file_type == "folder" && !parents.contains(f.path.as_str()))
+        .cloned()
+        .collect();
+    let files: Vec<file::Model> = records
+        .into_iter()
+        .filter(|f| f.file_type != "folder")
+        .collect();
+
+    let folder_roots = files
+        .iter()
+        .chain(&empty_folders)
+        .map(|f| (f.id, (username.to_string(), "/".to_string())))
+        .collect();
+
+    Ok(CollectedFiles {
+        files,
+        folder_roots,
+        empty_folders,
     })
 }
 
-/// Recursively collect all files in a folder path
+/// Recursively collect the files in a folder that `user_id` can read
+/// Also returns the folders with nothing readable inside, the folder itself included
+/// The caller checks that the folder is readable, its contents inherit that access unless
+/// a grant of their own says otherwise, subfolders that end up unreadable are skipped whole
 async fn collect_files_in_folder(
     db: &DatabaseConnection,
-    folder_path: &str,
-    owner_id: i32,
-) -> Result<Vec<file::Model>> {
+    folder: file::Model,
+    user_id: i32,
+    user_role: &str,
+) -> Result<(Vec<file::Model>, Vec<file::Model>)> {
     let mut all_files = Vec::new();
-    let mut folders_to_process = vec![folder_path.to_string()];
+    let mut empty_folders = Vec::new();
+    let owner_id = folder.user_id;
+    // Owners, administrators and library grants already resolve access by path
+    let inherits = user_role != "admin" && owner_id != user_id && !library::is_library(owner_id);
+    let mut folders_to_process = vec![folder];
 
     while let Some(current_folder) = folders_to_process.pop() {
         // Find all direct children of this folder
         let children = file::Entity::find()
             .filter(file::Column::UserId.eq(owner_id))
-            .filter(file::Column::ParentPath.eq(&current_folder))
+            .filter(file::Column::ParentPath.eq(&current_folder.path))
             .all(db)
             .await?;
+        let readable: HashSet<i32> = if inherits {
+            let grants = explicit_grants(db, user_id, children.iter().map(|f| f.id)).await?;
+            children
+                .iter()
+                .filter(|f| grants.get(&f.id).is_none_or(|(read, _, _)| *read))
+                .map(|f| f.id)
+                .collect()
+        } else {
+            let permissions = get_files_permissions(db, user_id, user_role, &children).await;
+            children
+                .iter()
+                .filter(|f| permissions.get(&f.id).is_some_and(|(read, _, _)| *read))
+                .map(|f| f.id)
+                .collect()
+        };
+        if readable.is_empty() {
+            empty_folders.push(current_folder);
+            continue;
+        }
 
         for file_entity in children {
+            if !readable.contains(&file_entity.id) {
+                continue;
+            }
+
             if file_entity.file_type == "folder" {
                 // Add subfolder to processing queue
-                folders_to_process.push(file_entity.path.clone());
+                folders_to_process.push(file_entity);
+            } else if file_entity.quarantined_at.is_some() || file_entity.taken_down {
+                // Held back by an administrator, left out of the archive
+                continue;
             } else {
                 // Add file to results
                 all_files.push(file_entity);
@@ -98,7 +183,7 @@ async fn collect_files_in_folder(
         }
     }
 
-    Ok(all_files)
+    Ok((all_files, empty_folders))
 }
 
 /// Calculate total size of all files
@@ -118,72 +203,168 @@ pub fn verify_size_limit(total_size: i64, max_size: usize) -> Result<()> {
     Ok(())
 }
 
-/// Verify user has read permission for all files
-pub async fn verify_download_permissions(
-    db: &DatabaseConnection,
-    files: &[file::Model],
-    user_id: i32,
-    user_role: &str,
-) -> Result<bool> {
-    // Admin can download anything
-    if user_role == "admin" {
-        return Ok(true);
+/// Write the archive of the collected files to `dest`, folder structure preserved
+/// Returns the writer and the size of the archive
+pub async fn write_archive<W: AsyncWrite + Unpin>(
+    collected: &CollectedFiles,
+    format: ArchiveFormat,
+    should_compress: bool,
+    dest: W,
+) -> Result<(W, u64)> {
+    let mut archive = ArchiveWriter::new(dest, format, should_compress);
+    for folder in &collected.empty_folders {
+        archive
+            .add_dir(
+                &archive_path(folder, &collected.folder_roots),
+                entry_time(folder),
+            )
+            .await?;
     }
-
-    // Check each file
-    for file_entity in files {
-        // Owner can download their own files
-        if file_entity.user_id == user_id {
-            continue;
-        }
-
-        // Check if user has read permission
-        let permission = file_permission::Entity::find()
-            .filter(file_permission::Column::FileId.eq(file_entity.id))
-            .filter(file_permission::Column::UserId.eq(user_id))
-            .one(db)
+    for f in &collected.files {
+        archive
+            .add_file(
+                &archive_path(f, &collected.folder_roots),
+                Path::new(&f.storage_path),
+                entry_time(f),
+            )
             .await?;
-
-        match permission {
-            Some(perm) if perm.can_read => continue,
-            _ => return Err(anyhow!("No read permission for file: {}", file_entity.name)),
-        }
     }
+    archive.finish().await
+}
 
-    Ok(true)
+/// Modification time of an archive entry, as the client last reported it when known
+pub fn entry_time(file_entity: &file::Model) -> NaiveDateTime {
+    file_entity
+        .client_modified
+        .unwrap_or(file_entity.updated_at)
 }
 
-/// Create ZIP archive from file entities with folder structure preserved
-/// If should_compress is false, files will be stored without compression
-pub fn create_batch_download_zip(
-    files: &[file::Model],
+/// Path of a file inside the batch-download archive
+pub fn archive_path(
+    file_entity: &file::Model,
     folder_roots: &HashMap<i32, (String, String)>,
-    should_compress: bool,
-) -> Result<Vec<u8>> {
-    let mut file_paths = Vec::new();
-
-    for file_entity in files {
-        let physical_path = file_entity.storage_path.clone();
-
-        // Determine the archive path based on whether this file belongs to a selected folder
-        let archive_path =
-            if let Some((folder_name, folder_path)) = folder_roots.get(&file_entity.id) {
-                // This file belongs to a selected folder - preserve the folder structure
-                // Remove the folder_path prefix and add folder_name prefix
-                let relative_path = file_entity
-                    .path
-                    .strip_prefix(folder_path)
-                    .unwrap_or(&file_entity.path)
-                    .trim_start_matches('/');
-
-                format!("{}/{}", folder_name, relative_path)
+) -> String {
+    if let Some((folder_name, folder_path)) = folder_roots.get(&file_entity.id) {
+        // This file belongs to a selected folder - preserve the folder structure
+        // Remove the folder_path prefix and add folder_name prefix
+        let relative_path = file_entity
+            .path
+            .strip_prefix(folder_path)
+            .unwrap_or(&file_entity.path)
+            .trim_start_matches('/');
+
+        format!("{}/{}", folder_name, relative_path)
+    } else {
+        // This is a directly selected file - use just the filename
+        file_entity.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::file_permission;
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, Schema, Set, SqlxSqliteConnector};
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use std::str::FromStr;
+
+    const OWNER: i32 = 1;
+    const GUEST: i32 = 2;
+
+    async fn database() -> DatabaseConnection {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(false);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        let db = SqlxSqliteConnector::from_sqlx_sqlite_pool(pool);
+        let schema = Schema::new(db.get_database_backend());
+        for statement in [
+            schema.create_table_from_entity(file::Entity),
+            schema.create_table_from_entity(file_permission::Entity),
+        ] {
+            db.execute(db.get_database_backend().build(&statement))
+                .await
+                .unwrap();
+        }
+        db
+    }
+
+    async fn add(db: &DatabaseConnection, path: &str, file_type: &str) -> file::Model {
+        let (parent_path, name) = path.rsplit_once('/').unwrap();
+        let now = chrono::Utc::now().naive_utc();
+        file::ActiveModel {
+            user_id: Set(OWNER),
+            name: Set(name.to_string()),
+            path: Set(path.to_string()),
+            parent_path: Set(if parent_path.is_empty() {
+                "/"
             } else {
-                // This is a directly selected file - use just the filename
-                file_entity.name.clone()
-            };
+                parent_path
+            }
+            .to_string()),
+            file_type: Set(file_type.to_string()),
+            size_bytes: Set(Some(1)),
+            storage_path: Set(format!("/storage{}", path)),
+            ref_count: Set(1),
+            version: Set(1),
+            pinned: Set(false),
+            legal_hold: Set(false),
+            taken_down: Set(false),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
 
-        file_paths.push((physical_path, archive_path));
+    async fn grant(db: &DatabaseConnection, f: &file::Model, can_read: bool) {
+        file_permission::ActiveModel {
+            file_id: Set(f.id),
+            user_id: Set(GUEST),
+            can_read: Set(can_read),
+            can_write: Set(!can_read),
+            can_delete: Set(false),
+            granted_by: Set(OWNER),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap();
     }
 
-    crate::utils::archive::create_streaming_zip_from_paths(file_paths, should_compress)
+    #[tokio::test]
+    async fn test_folder_grant_covers_its_contents() {
+        let db = database().await;
+        let shared = add(&db, "/shared", "folder").await;
+        add(&db, "/shared/a.txt", "file").await;
+        add(&db, "/shared/sub", "folder").await;
+        add(&db, "/shared/sub/b.txt", "file").await;
+        let dropbox = add(&db, "/shared/dropbox", "folder").await;
+        add(&db, "/shared/dropbox/c.txt", "file").await;
+        add(&db, "/private.txt", "file").await;
+
+        // Read on the folder only, the drop box inside it is write-only
+        grant(&db, &shared, true).await;
+        grant(&db, &dropbox, false).await;
+
+        let collected = collect_files_to_download(&db, vec![shared.id], GUEST, "user")
+            .await
+            .unwrap();
+        let mut paths: Vec<&str> = collected.files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["/shared/a.txt", "/shared/sub/b.txt"]);
+
+        assert!(
+            collect_files_to_download(&db, vec![dropbox.id], GUEST, "user")
+                .await
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,33 @@
+use crate::models::job::JobEvent;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before a slow stream starts skipping
+const CHANNEL_CAPACITY: usize = 256;
+
+/// In-process fan-out of job progress to the connected event streams
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<JobEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Send an event to every open stream (dropped when nobody listens)
+    pub fn publish(&self, event: JobEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
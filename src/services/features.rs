@@ -0,0 +1,107 @@
+use crate::{config::FeaturesConfig, entities::feature_override};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+
+/// Subsystems that can be switched on or off per role or user
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// Creating share links anyone with the link can open
+    PublicSharing,
+    /// Signing up for a new account, checked without a user
+    Registration,
+    /// Downloading several files as one archive
+    BatchDownload,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::PublicSharing,
+        Feature::Registration,
+        Feature::BatchDownload,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::PublicSharing => "public_sharing",
+            Feature::Registration => "registration",
+            Feature::BatchDownload => "batch_download",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.as_str() == value)
+    }
+
+    /// Configured state for `role`, on when the config does not mention the feature
+    pub fn configured(&self, config: &FeaturesConfig, role: Option<&str>) -> bool {
+        match config.flags.iter().find(|f| f.name == self.as_str()) {
+            Some(flag) => flag.enabled || role.is_some_and(|r| flag.roles.iter().any(|x| x == r)),
+            None => true,
+        }
+    }
+}
+
+/// Whether `feature` is on for the user, `user` is `None` for anonymous requests
+/// The most specific override wins: the user's, then the role's, then the one for everyone
+pub async fn enabled(
+    db: &DatabaseConnection,
+    config: &FeaturesConfig,
+    feature: Feature,
+    user: Option<(i32, &str)>,
+) -> bool {
+    match overridden(db, feature, user).await {
+        Ok(Some(enabled)) => enabled,
+        Ok(None) => feature.configured(config, user.map(|(_, role)| role)),
+        Err(e) => {
+            tracing::warn!(feature = feature.as_str(), error = %e, "Failed to load feature overrides");
+            feature.configured(config, user.map(|(_, role)| role))
+        }
+    }
+}
+
+/// State of every feature for the user
+pub async fn for_user(
+    db: &DatabaseConnection,
+    config: &FeaturesConfig,
+    user_id: i32,
+    role: &str,
+) -> Vec<(Feature, bool)> {
+    let mut states = Vec::with_capacity(Feature::ALL.len());
+    for feature in Feature::ALL {
+        states.push((
+            feature,
+            enabled(db, config, feature, Some((user_id, role))).await,
+        ));
+    }
+    states
+}
+
+async fn overridden(
+    db: &DatabaseConnection,
+    feature: Feature,
+    user: Option<(i32, &str)>,
+) -> Result<Option<bool>, DbErr> {
+    let everyone = Condition::all()
+        .add(feature_override::Column::UserId.is_null())
+        .add(feature_override::Column::Role.is_null());
+    let mut applies = Condition::any().add(everyone);
+    if let Some((user_id, role)) = user {
+        applies = applies
+            .add(feature_override::Column::UserId.eq(user_id))
+            .add(
+                Condition::all()
+                    .add(feature_override::Column::UserId.is_null())
+                    .add(feature_override::Column::Role.eq(role)),
+            );
+    }
+
+    let overrides = feature_override::Entity::find()
+        .filter(feature_override::Column::Feature.eq(feature.as_str()))
+        .filter(applies)
+        .all(db)
+        .await?;
+
+    Ok(overrides
+        .iter()
+        .max_by_key(|o| (o.user_id.is_some(), o.role.is_some()))
+        .map(|o| o.enabled))
+}
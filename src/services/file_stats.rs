@@ -0,0 +1,80 @@
+use crate::entities::{daily_download, file_stat};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::collections::HashMap;
+
+/// Count one download for each of the given files
+/// Failures are logged but never fail the download itself
+pub async fn record_downloads(db: &DatabaseConnection, file_ids: &[i32]) {
+    if let Err(e) = increment(db, file_ids).await {
+        tracing::warn!(error = %e, "Failed to record download statistics");
+    }
+}
+
+async fn increment(db: &DatabaseConnection, file_ids: &[i32]) -> Result<(), DbErr> {
+    if file_ids.is_empty() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let rows = file_ids.iter().map(|&file_id| file_stat::ActiveModel {
+        file_id: Set(file_id),
+        download_count: Set(1),
+        last_downloaded_at: Set(Some(now)),
+    });
+
+    file_stat::Entity::insert_many(rows)
+        .on_conflict(
+            OnConflict::column(file_stat::Column::FileId)
+                .value(
+                    file_stat::Column::DownloadCount,
+                    Expr::col((file_stat::Entity, file_stat::Column::DownloadCount)).add(1),
+                )
+                .update_column(file_stat::Column::LastDownloadedAt)
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    // Daily totals feed the admin dashboard
+    daily_download::Entity::insert(daily_download::ActiveModel {
+        day: Set(now.date()),
+        download_count: Set(file_ids.len() as i64),
+    })
+    .on_conflict(
+        OnConflict::column(daily_download::Column::Day)
+            .value(
+                daily_download::Column::DownloadCount,
+                Expr::col((
+                    daily_download::Entity,
+                    daily_download::Column::DownloadCount,
+                ))
+                .add(file_ids.len() as i64),
+            )
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Load the counters of the given files, files never downloaded are absent
+pub async fn for_files(
+    db: &DatabaseConnection,
+    file_ids: &[i32],
+) -> Result<HashMap<i32, file_stat::Model>, DbErr> {
+    if file_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(file_stat::Entity::find()
+        .filter(file_stat::Column::FileId.is_in(file_ids.iter().copied()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.file_id, s))
+        .collect())
+}
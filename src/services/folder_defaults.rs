@@ -0,0 +1,161 @@
+use crate::{
+    entities::{file, file_permission, folder_default_permission},
+    services::permission_cache,
+    utils::file_utils,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Permissions a single user inherits from the folders above an item
+#[derive(Debug, Clone, Copy)]
+struct InheritedGrant {
+    can_read: bool,
+    can_write: bool,
+    can_delete: bool,
+    granted_by: i32,
+}
+
+/// Apply the default permission policies of every enclosing folder to `entry`
+/// For folders the policies are applied to everything inside them as well
+/// Existing grants are only ever widened, never reduced
+/// Returns the number of permission records created or updated
+pub async fn apply(db: &DatabaseConnection, entry: &file::Model) -> Result<usize, DbErr> {
+    let mut items = vec![entry.clone()];
+    if entry.file_type == "folder" {
+        items.extend(
+            file::Entity::find()
+                .filter(file::Column::UserId.eq(entry.user_id))
+                .filter(file::Column::Path.starts_with(format!("{}/", entry.path)))
+                .all(db)
+                .await?,
+        );
+    }
+
+    // Policies can come from folders above the entry or from folders inside it
+    let mut folder_paths = file_utils::ancestor_paths(&entry.parent_path);
+    folder_paths.extend(
+        items
+            .iter()
+            .filter(|f| f.file_type == "folder")
+            .map(|f| f.path.clone()),
+    );
+
+    let folders = file::Entity::find()
+        .filter(file::Column::UserId.eq(entry.user_id))
+        .filter(file::Column::FileType.eq("folder"))
+        .filter(file::Column::Path.is_in(folder_paths))
+        .all(db)
+        .await?;
+    if folders.is_empty() {
+        return Ok(0);
+    }
+
+    let folder_paths: HashMap<i32, String> = folders.into_iter().map(|f| (f.id, f.path)).collect();
+    let policies = folder_default_permission::Entity::find()
+        .filter(folder_default_permission::Column::FolderId.is_in(folder_paths.keys().copied()))
+        .all(db)
+        .await?;
+    if policies.is_empty() {
+        return Ok(0);
+    }
+
+    let mut policies_by_path: HashMap<&str, Vec<&folder_default_permission::Model>> =
+        HashMap::new();
+    for policy in &policies {
+        if let Some(path) = folder_paths.get(&policy.folder_id) {
+            policies_by_path.entry(path).or_default().push(policy);
+        }
+    }
+
+    let mut applied = 0;
+    for item in &items {
+        let mut grants: BTreeMap<i32, InheritedGrant> = BTreeMap::new();
+        for path in file_utils::ancestor_paths(&item.parent_path) {
+            let Some(folder_policies) = policies_by_path.get(path.as_str()) else {
+                continue;
+            };
+            for policy in folder_policies {
+                // Owners already have full access to their own files
+                if policy.user_id == item.user_id {
+                    continue;
+                }
+                let grant = grants.entry(policy.user_id).or_insert(InheritedGrant {
+                    can_read: false,
+                    can_write: false,
+                    can_delete: false,
+                    granted_by: policy.created_by,
+                });
+                grant.can_read |= policy.can_read;
+                grant.can_write |= policy.can_write;
+                grant.can_delete |= policy.can_delete;
+            }
+        }
+
+        for (user_id, grant) in grants {
+            if merge_grant(db, item.id, user_id, grant).await? {
+                applied += 1;
+            }
+        }
+    }
+
+    if applied > 0 {
+        tracing::info!(
+            file_id = entry.id,
+            applied = applied,
+            "Applied folder default permissions"
+        );
+    }
+
+    Ok(applied)
+}
+
+/// Create or widen a single permission record, returns whether anything changed
+async fn merge_grant(
+    db: &DatabaseConnection,
+    file_id: i32,
+    user_id: i32,
+    grant: InheritedGrant,
+) -> Result<bool, DbErr> {
+    let existing = file_permission::Entity::find()
+        .filter(file_permission::Column::FileId.eq(file_id))
+        .filter(file_permission::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+
+    match existing {
+        Some(perm) => {
+            let can_read = perm.can_read || grant.can_read;
+            let can_write = perm.can_write || grant.can_write;
+            let can_delete = perm.can_delete || grant.can_delete;
+            if (can_read, can_write, can_delete) == (perm.can_read, perm.can_write, perm.can_delete)
+            {
+                return Ok(false);
+            }
+
+            let mut active: file_permission::ActiveModel = perm.into();
+            active.can_read = Set(can_read);
+            active.can_write = Set(can_write);
+            active.can_delete = Set(can_delete);
+            active.update(db).await?;
+        }
+        None => {
+            file_permission::ActiveModel {
+                file_id: Set(file_id),
+                user_id: Set(user_id),
+                can_read: Set(grant.can_read),
+                can_write: Set(grant.can_write),
+                can_delete: Set(grant.can_delete),
+                granted_by: Set(grant.granted_by),
+                created_at: Set(chrono::Utc::now().naive_utc()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+
+    permission_cache::invalidate(user_id, file_id);
+    Ok(true)
+}
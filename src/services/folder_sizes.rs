@@ -0,0 +1,103 @@
+use crate::{config::Config, entities::file, utils::file_utils};
+use sea_orm::{
+    sea_query::{Expr, Func, LikeExpr},
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect,
+    Statement,
+};
+use std::time::Duration;
+
+/// Recompute every folder total from the files below it, in the owner's tree
+/// The path prefix is escaped the same way as `file_utils::descendant_pattern`
+const RECONCILE_SQL: &str = r"
+UPDATE files SET total_size_bytes = (
+    SELECT COALESCE(SUM(c.size_bytes), 0) FROM files c
+    WHERE c.user_id = files.user_id
+      AND c.file_type = 'file'
+      AND c.path LIKE replace(replace(replace(rtrim(files.path, '/'), '\', '\\'), '%', '\%'), '_', '\_') || '/%' ESCAPE '\'
+)
+WHERE file_type = 'folder'";
+
+/// Total size and number of the files below a folder, summed up by the database
+pub async fn compute(
+    db: &DatabaseConnection,
+    owner_id: i32,
+    folder_path: &str,
+) -> Result<(i64, i64), DbErr> {
+    let (size, count): (Option<i64>, i64) = file::Entity::find()
+        .select_only()
+        .column_as(Expr::col(file::Column::SizeBytes).sum(), "size")
+        .column_as(Expr::col(file::Column::Id).count(), "count")
+        .filter(file::Column::UserId.eq(owner_id))
+        .filter(file::Column::FileType.eq("file"))
+        .filter(
+            Expr::col(file::Column::Path)
+                .like(LikeExpr::new(file_utils::descendant_pattern(folder_path)).escape('\\')),
+        )
+        .into_tuple()
+        .one(db)
+        .await?
+        .unwrap_or((None, 0));
+    Ok((size.unwrap_or(0), count))
+}
+
+/// Bytes a file or folder adds to the folders containing it
+pub async fn item_size(db: &DatabaseConnection, f: &file::Model) -> Result<i64, DbErr> {
+    if f.file_type == "folder" {
+        Ok(compute(db, f.user_id, &f.path).await?.0)
+    } else {
+        Ok(f.size_bytes.unwrap_or(0))
+    }
+}
+
+/// Add `delta` bytes to the totals of `parent_path` and every folder above it
+/// Failures are logged, the next reconciliation corrects the totals
+pub async fn adjust(db: &DatabaseConnection, owner_id: i32, parent_path: &str, delta: i64) {
+    let ancestors = file_utils::ancestor_paths(parent_path);
+    if delta == 0 || ancestors.is_empty() {
+        return;
+    }
+
+    let total = Func::coalesce([
+        Expr::col(file::Column::TotalSizeBytes).into(),
+        Expr::val(0i64).into(),
+    ]);
+    let result = file::Entity::update_many()
+        .col_expr(file::Column::TotalSizeBytes, Expr::expr(total).add(delta))
+        .filter(file::Column::UserId.eq(owner_id))
+        .filter(file::Column::FileType.eq("folder"))
+        .filter(file::Column::Path.is_in(ancestors))
+        .exec(db)
+        .await;
+
+    if let Err(e) = result {
+        tracing::warn!(owner_id = owner_id, path = %parent_path, error = %e, "Failed to update folder sizes");
+    }
+}
+
+/// Start the background task recomputing folder totals, the first run fills in missing ones
+pub fn spawn_reconciler(db: DatabaseConnection, config: Config) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            config.storage.folder_size_reconcile_interval_secs,
+        ));
+
+        loop {
+            interval.tick().await;
+            match reconcile(&db).await {
+                Ok(folders) => tracing::debug!(folders = folders, "Folder sizes reconciled"),
+                Err(e) => tracing::warn!(error = %e, "Folder size reconciliation failed"),
+            }
+        }
+    });
+}
+
+/// Recompute the totals of all folders, returns how many folders were updated
+pub async fn reconcile(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let result = db
+        .execute(Statement::from_string(
+            db.get_database_backend(),
+            RECONCILE_SQL,
+        ))
+        .await?;
+    Ok(result.rows_affected())
+}
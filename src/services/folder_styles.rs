@@ -0,0 +1,81 @@
+use crate::{entities::folder_style, models::file::FolderStyle};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::collections::HashMap;
+
+/// Styles a user set on the given folders, unstyled folders are absent
+pub async fn for_files<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    file_ids: &[i32],
+) -> Result<HashMap<i32, FolderStyle>, DbErr> {
+    if file_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(folder_style::Entity::find()
+        .filter(folder_style::Column::UserId.eq(user_id))
+        .filter(folder_style::Column::FileId.is_in(file_ids.iter().copied()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| {
+            (
+                s.file_id,
+                FolderStyle {
+                    color: s.color,
+                    icon: s.icon,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Replace a user's style of a folder, an empty style removes it
+pub async fn set<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    file_id: i32,
+    style: &FolderStyle,
+) -> Result<(), DbErr> {
+    if style.color.is_none() && style.icon.is_none() {
+        folder_style::Entity::delete_many()
+            .filter(folder_style::Column::UserId.eq(user_id))
+            .filter(folder_style::Column::FileId.eq(file_id))
+            .exec(db)
+            .await?;
+        return Ok(());
+    }
+
+    folder_style::Entity::insert(folder_style::ActiveModel {
+        file_id: Set(file_id),
+        user_id: Set(user_id),
+        color: Set(style.color.clone()),
+        icon: Set(style.icon.clone()),
+        updated_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([folder_style::Column::FileId, folder_style::Column::UserId])
+            .update_columns([
+                folder_style::Column::Color,
+                folder_style::Column::Icon,
+                folder_style::Column::UpdatedAt,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Drop every user's style of a folder
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    folder_style::Entity::delete_many()
+        .filter(folder_style::Column::FileId.eq(file_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
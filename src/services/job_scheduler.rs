@@ -0,0 +1,300 @@
+use crate::{
+    config::JobsConfig,
+    models::admin::{JobClassLimits, JobLimits},
+};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tokio::sync::oneshot;
+
+/// Kind of background work, each class has its own limit of tasks running at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobClass {
+    /// Hashing and verified copies of stored files, e.g. for backups and storage migrations
+    Hashing,
+    /// Reading embedded thumbnails, EXIF and audio tags of uploads
+    Thumbnailing,
+    /// Mirroring files to the replication target
+    Replication,
+    /// External processing hooks
+    Processing,
+    /// Archives built for batch downloads
+    Archive,
+}
+
+impl JobClass {
+    pub const ALL: [JobClass; 5] = [
+        JobClass::Hashing,
+        JobClass::Thumbnailing,
+        JobClass::Replication,
+        JobClass::Processing,
+        JobClass::Archive,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobClass::Hashing => "hashing",
+            JobClass::Thumbnailing => "thumbnailing",
+            JobClass::Replication => "replication",
+            JobClass::Processing => "processing",
+            JobClass::Archive => "archive",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+
+    fn default_limit(&self) -> usize {
+        match self {
+            JobClass::Thumbnailing | JobClass::Processing | JobClass::Archive => 2,
+            JobClass::Hashing | JobClass::Replication => 1,
+        }
+    }
+
+    /// Archives are waited for by a user, mirroring and hooks can lag behind
+    fn default_priority(&self) -> Priority {
+        match self {
+            JobClass::Archive => Priority::High,
+            JobClass::Hashing | JobClass::Thumbnailing => Priority::Normal,
+            JobClass::Replication | JobClass::Processing => Priority::Low,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+struct ClassState {
+    max_concurrent: usize,
+    priority: Priority,
+    running: usize,
+    completed: u64,
+}
+
+struct Waiter {
+    class: JobClass,
+    /// Arrival order, tasks of the same priority start first come first served
+    seq: u64,
+    wake: oneshot::Sender<JobPermit>,
+}
+
+struct Scheduler {
+    max_concurrent: usize,
+    running: usize,
+    classes: HashMap<JobClass, ClassState>,
+    waiting: Vec<Waiter>,
+    next_seq: u64,
+}
+
+impl Scheduler {
+    fn can_start(&self, class: JobClass) -> bool {
+        self.running < self.max_concurrent
+            && self.classes[&class].running < self.classes[&class].max_concurrent
+    }
+
+    fn start(&mut self, class: JobClass) -> JobPermit {
+        self.running += 1;
+        if let Some(state) = self.classes.get_mut(&class) {
+            state.running += 1;
+        }
+        JobPermit { class }
+    }
+
+    fn finish(&mut self, class: JobClass, completed: bool) {
+        self.running -= 1;
+        if let Some(state) = self.classes.get_mut(&class) {
+            state.running -= 1;
+            if completed {
+                state.completed += 1;
+            }
+        }
+    }
+
+    /// Start waiting tasks while there is room, highest priority first
+    fn dispatch(&mut self) {
+        self.waiting.retain(|w| !w.wake.is_closed());
+        loop {
+            let next = self
+                .waiting
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| self.can_start(w.class))
+                .max_by_key(|(_, w)| (self.classes[&w.class].priority, std::cmp::Reverse(w.seq)))
+                .map(|(i, _)| i);
+            let Some(i) = next else {
+                return;
+            };
+
+            let waiter = self.waiting.swap_remove(i);
+            let permit = self.start(waiter.class);
+            if let Err(permit) = waiter.wake.send(permit) {
+                // The task stopped waiting, its permit must not lock the scheduler again on drop
+                std::mem::forget(permit);
+                self.finish(waiter.class, false);
+            }
+        }
+    }
+}
+
+static SCHEDULER: OnceLock<Mutex<Scheduler>> = OnceLock::new();
+
+fn scheduler() -> MutexGuard<'static, Scheduler> {
+    SCHEDULER
+        .get_or_init(|| Mutex::new(build(&JobsConfig::default())))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn build(config: &JobsConfig) -> Scheduler {
+    let mut classes: HashMap<JobClass, ClassState> = JobClass::ALL
+        .into_iter()
+        .map(|c| {
+            let state = ClassState {
+                max_concurrent: c.default_limit(),
+                priority: c.default_priority(),
+                running: 0,
+                completed: 0,
+            };
+            (c, state)
+        })
+        .collect();
+
+    for entry in &config.classes {
+        let Some(state) = JobClass::parse(&entry.class).and_then(|c| classes.get_mut(&c)) else {
+            tracing::warn!(class = %entry.class, "Unknown job class in configuration");
+            continue;
+        };
+        if let Some(max) = entry.max_concurrent {
+            state.max_concurrent = max.max(1);
+        }
+        match entry.priority.as_deref().map(Priority::parse) {
+            Some(Some(priority)) => state.priority = priority,
+            Some(None) => {
+                tracing::warn!(class = %entry.class, "Unknown job priority in configuration")
+            }
+            None => {}
+        }
+    }
+
+    Scheduler {
+        max_concurrent: config.max_concurrent.max(1),
+        running: 0,
+        classes,
+        waiting: Vec::new(),
+        next_seq: 0,
+    }
+}
+
+/// Apply the configured limits, call once at startup before background tasks run
+pub fn configure(config: &JobsConfig) {
+    if SCHEDULER.set(Mutex::new(build(config))).is_err() {
+        tracing::warn!("Job scheduler was configured twice, keeping the first settings");
+    }
+}
+
+/// Slot of a running background task, the next waiting task starts when it is dropped
+pub struct JobPermit {
+    class: JobClass,
+}
+
+impl Drop for JobPermit {
+    fn drop(&mut self) {
+        let mut s = scheduler();
+        s.finish(self.class, true);
+        s.dispatch();
+    }
+}
+
+/// Wait until a task of `class` may run
+pub async fn acquire(class: JobClass) -> JobPermit {
+    let waiting = {
+        let mut s = scheduler();
+        if s.can_start(class) {
+            return s.start(class);
+        }
+        let (wake, waiting) = oneshot::channel();
+        let seq = s.next_seq;
+        s.next_seq += 1;
+        s.waiting.push(Waiter { class, seq, wake });
+        waiting
+    };
+
+    match waiting.await {
+        Ok(permit) => permit,
+        // Waiters are only dropped after being sent a permit, this is not reached
+        Err(_) => scheduler().start(class),
+    }
+}
+
+/// Change limits and priorities at runtime, they go back to the configured ones on restart
+pub fn update(
+    max_concurrent: Option<usize>,
+    classes: &[(JobClass, Option<usize>, Option<Priority>)],
+) {
+    let mut s = scheduler();
+    if let Some(max) = max_concurrent {
+        s.max_concurrent = max.max(1);
+    }
+    for (class, max, priority) in classes {
+        if let Some(state) = s.classes.get_mut(class) {
+            if let Some(max) = max {
+                state.max_concurrent = (*max).max(1);
+            }
+            if let Some(priority) = priority {
+                state.priority = *priority;
+            }
+        }
+    }
+    // Raised limits let waiting tasks start right away
+    s.dispatch();
+}
+
+pub fn snapshot() -> JobLimits {
+    let mut s = scheduler();
+    s.waiting.retain(|w| !w.wake.is_closed());
+
+    let classes = JobClass::ALL
+        .into_iter()
+        .map(|c| {
+            let state = &s.classes[&c];
+            JobClassLimits {
+                class: c.as_str().to_string(),
+                priority: state.priority.as_str().to_string(),
+                max_concurrent: state.max_concurrent,
+                running: state.running,
+                waiting: s.waiting.iter().filter(|w| w.class == c).count(),
+                completed: state.completed,
+            }
+        })
+        .collect();
+
+    JobLimits {
+        max_concurrent: s.max_concurrent,
+        running: s.running,
+        waiting: s.waiting.len(),
+        classes,
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{
+    config::Config,
+    entities::job,
+    models::job::{JobEvent, JobInfo},
+    services::{
+        download,
+        events::EventBus,
+        file_stats,
+        job_scheduler::{self, JobClass},
+        staging,
+    },
+    utils::{
+        archive::{ArchiveFormat, ArchiveWriter},
+        timestamp,
+    },
+};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub const KIND_BATCH_DOWNLOAD: &str = "batch_download";
+pub const KIND_CLEANUP: &str = "cleanup";
+pub const KIND_COPY: &str = "copy";
+pub const KIND_SEARCH_REINDEX: &str = "search_reindex";
+pub const KIND_STORAGE_MIGRATION: &str = "storage_migration";
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_EXPIRED: &str = "expired";
+
+/// Minimum time between two progress updates
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Record a new job with its totals and announce it
+pub async fn create(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    user_id: i32,
+    kind: &str,
+    status: &str,
+    total_items: i64,
+    total_bytes: i64,
+) -> Result<job::Model, DbErr> {
+    let now = Utc::now().naive_utc();
+    let job = job::ActiveModel {
+        user_id: Set(user_id),
+        kind: Set(kind.to_string()),
+        status: Set(status.to_string()),
+        total_items: Set(total_items),
+        processed_items: Set(0),
+        total_bytes: Set(total_bytes),
+        processed_bytes: Set(0),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    publish(events, &job);
+    Ok(job)
+}
+
+/// Record a batch-download job and build its archive in the background
+pub async fn start_batch_download(
+    db: &DatabaseConnection,
+    config: &Config,
+    events: &EventBus,
+    user_id: i32,
+    collected: download::CollectedFiles,
+    format: ArchiveFormat,
+    should_compress: bool,
+) -> Result<job::Model, DbErr> {
+    let job = create(
+        db,
+        events,
+        user_id,
+        KIND_BATCH_DOWNLOAD,
+        STATUS_PENDING,
+        collected.files.len() as i64,
+        download::calculate_total_size(&collected.files),
+    )
+    .await?;
+
+    let db = db.clone();
+    let events = events.clone();
+    let archive_dir = staging::area_dir(config, staging::AREA_ARCHIVES);
+    let ttl = Duration::seconds(config.batch_download.archive_ttl_secs);
+    let job_id = job.id;
+    tokio::spawn(async move {
+        let _permit = job_scheduler::acquire(JobClass::Archive).await;
+        let result = build_archive(
+            &db,
+            &events,
+            &archive_dir,
+            job_id,
+            &collected,
+            format,
+            should_compress,
+        )
+        .await;
+        let now = Utc::now().naive_utc();
+        let mut active = job::ActiveModel {
+            id: Set(job_id),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+
+        match result {
+            Ok((path, size)) => {
+                active.status = Set(STATUS_COMPLETED.to_string());
+                active.processed_items = Set(collected.files.len() as i64);
+                active.processed_bytes = Set(download::calculate_total_size(&collected.files));
+                active.result_path = Set(Some(path.to_string_lossy().replace('\\', "/")));
+                active.result_size = Set(Some(size));
+                active.expires_at = Set(Some(now + ttl));
+                tracing::info!(job_id = job_id, size = size, "Batch download archive ready");
+
+                let ids: Vec<i32> = collected.files.iter().map(|f| f.id).collect();
+                file_stats::record_downloads(&db, &ids).await;
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(archive_file(&archive_dir, job_id, format)).await;
+                active.status = Set(STATUS_FAILED.to_string());
+                active.error = Set(Some(e.to_string()));
+                tracing::error!(job_id = job_id, error = %e, "Batch download job failed");
+            }
+        }
+
+        match active.update(&db).await {
+            Ok(job) => publish(&events, &job),
+            Err(e) => tracing::error!(job_id = job_id, error = %e, "Failed to save job result"),
+        }
+    });
+
+    Ok(job)
+}
+
+/// Write the archive file one entry at a time, reporting progress along the way
+async fn build_archive(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    archive_dir: &Path,
+    job_id: i32,
+    collected: &download::CollectedFiles,
+    format: ArchiveFormat,
+    should_compress: bool,
+) -> Result<(PathBuf, i64)> {
+    save_progress(db, events, job_id, 0, 0).await?;
+
+    tokio::fs::create_dir_all(archive_dir).await?;
+    let path = archive_file(archive_dir, job_id, format);
+    let file = tokio::fs::File::create(&path).await?;
+    let mut archive = ArchiveWriter::new(tokio::io::BufWriter::new(file), format, should_compress);
+
+    let mut processed_items = 0;
+    let mut processed_bytes = 0;
+    let mut last_report = Instant::now();
+
+    for folder in &collected.empty_folders {
+        let archive_path = download::archive_path(folder, &collected.folder_roots);
+        archive
+            .add_dir(&archive_path, download::entry_time(folder))
+            .await?;
+    }
+    for file_entity in &collected.files {
+        let archive_path = download::archive_path(file_entity, &collected.folder_roots);
+        archive
+            .add_file(
+                &archive_path,
+                Path::new(&file_entity.storage_path),
+                download::entry_time(file_entity),
+            )
+            .await?;
+
+        processed_items += 1;
+        processed_bytes += file_entity.size_bytes.unwrap_or(0);
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            save_progress(db, events, job_id, processed_items, processed_bytes).await?;
+            last_report = Instant::now();
+        }
+    }
+
+    let (_, size) = archive.finish().await?;
+    Ok((path, size as i64))
+}
+
+async fn save_progress(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    job_id: i32,
+    processed_items: i64,
+    processed_bytes: i64,
+) -> Result<(), DbErr> {
+    let job = job::ActiveModel {
+        id: Set(job_id),
+        status: Set(STATUS_RUNNING.to_string()),
+        processed_items: Set(processed_items),
+        processed_bytes: Set(processed_bytes),
+        updated_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    publish(events, &job);
+    Ok(())
+}
+
+/// Progress of a job that runs inside a request
+/// Updates only go to the event stream, the record is written once by `finish`
+pub struct ProgressReporter {
+    events: EventBus,
+    job: job::Model,
+    last_report: Instant,
+}
+
+impl ProgressReporter {
+    pub fn new(events: &EventBus, job: job::Model) -> Self {
+        Self {
+            events: events.clone(),
+            job,
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn advance(&mut self, items: i64, bytes: i64) {
+        self.job.processed_items += items;
+        self.job.processed_bytes += bytes;
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.job.updated_at = Utc::now().naive_utc();
+            publish(&self.events, &self.job);
+            self.last_report = Instant::now();
+        }
+    }
+
+    /// Store the final state of the job and announce it
+    pub async fn finish(self, db: &DatabaseConnection, result: Result<(), String>) {
+        let job_id = self.job.id;
+        let mut active = job::ActiveModel {
+            id: Set(job_id),
+            updated_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+        match result {
+            Ok(()) => {
+                active.status = Set(STATUS_COMPLETED.to_string());
+                active.processed_items = Set(self.job.total_items);
+                active.processed_bytes = Set(self.job.total_bytes);
+            }
+            Err(e) => {
+                active.status = Set(STATUS_FAILED.to_string());
+                active.processed_items = Set(self.job.processed_items);
+                active.processed_bytes = Set(self.job.processed_bytes);
+                active.error = Set(Some(e));
+            }
+        }
+
+        match active.update(db).await {
+            Ok(job) => publish(&self.events, &job),
+            Err(e) => tracing::error!(job_id = job_id, error = %e, "Failed to save job result"),
+        }
+    }
+}
+
+fn publish(events: &EventBus, job: &job::Model) {
+    events.publish(JobEvent {
+        user_id: job.user_id,
+        job: job_info(job.clone()),
+    });
+}
+
+fn archive_file(archive_dir: &Path, job_id: i32, format: ArchiveFormat) -> PathBuf {
+    archive_dir.join(format!("job_{}.{}", job_id, format.extension()))
+}
+
+/// Public view of a job with its completion percentage
+pub fn job_info(job: job::Model) -> JobInfo {
+    let progress = if job.status == STATUS_COMPLETED {
+        100.0
+    } else if job.total_bytes > 0 {
+        job.processed_bytes as f64 * 100.0 / job.total_bytes as f64
+    } else if job.total_items > 0 {
+        job.processed_items as f64 * 100.0 / job.total_items as f64
+    } else {
+        0.0
+    };
+
+    // Only batch downloads leave an artifact behind
+    let download_url = (job.kind == KIND_BATCH_DOWNLOAD && job.status == STATUS_COMPLETED)
+        .then(|| format!("/api/jobs/{}/download", job.id));
+
+    JobInfo {
+        id: job.id,
+        kind: job.kind,
+        status: job.status,
+        total_items: job.total_items,
+        processed_items: job.processed_items,
+        total_bytes: job.total_bytes,
+        processed_bytes: job.processed_bytes,
+        progress: (progress * 10.0).round() / 10.0,
+        result_size: job.result_size,
+        error: job.error,
+        download_url,
+        created_at: timestamp::format(job.created_at),
+        updated_at: timestamp::format(job.updated_at),
+        expires_at: job.expires_at.map(timestamp::format),
+    }
+}
+
+/// Start the background task that deletes expired archives
+/// Jobs interrupted by a restart are marked failed first
+pub fn spawn_sweeper(db: DatabaseConnection, config: Config, events: EventBus) {
+    tokio::spawn(async move {
+        if let Err(e) =
+            fail_interrupted(&db, &staging::area_dir(&config, staging::AREA_ARCHIVES)).await
+        {
+            tracing::warn!(error = %e, "Failed to clean up interrupted jobs");
+        }
+
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            config.batch_download.archive_sweep_interval_secs,
+        ));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_expired(&db, &events).await {
+                tracing::warn!(error = %e, "Archive sweep failed");
+            }
+        }
+    });
+}
+
+async fn fail_interrupted(db: &DatabaseConnection, archive_dir: &Path) -> Result<(), DbErr> {
+    let interrupted = job::Entity::find()
+        .filter(job::Column::Status.is_in([STATUS_PENDING, STATUS_RUNNING]))
+        .all(db)
+        .await?;
+
+    for job in interrupted {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::Tar] {
+            let _ = tokio::fs::remove_file(archive_file(archive_dir, job.id, format)).await;
+        }
+        let mut active: job::ActiveModel = job.into();
+        active.status = Set(STATUS_FAILED.to_string());
+        active.error = Set(Some("Interrupted by server restart".to_string()));
+        active.updated_at = Set(Utc::now().naive_utc());
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+async fn sweep_expired(db: &DatabaseConnection, events: &EventBus) -> Result<(), DbErr> {
+    let now = Utc::now().naive_utc();
+    let expired = job::Entity::find()
+        .filter(job::Column::Status.eq(STATUS_COMPLETED))
+        .filter(job::Column::ExpiresAt.lte(now))
+        .all(db)
+        .await?;
+
+    for job in expired {
+        if let Some(path) = &job.result_path {
+            if let Err(e) = tokio::fs::remove_file(path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!(job_id = job.id, error = %e, "Failed to delete expired archive");
+                    continue;
+                }
+            }
+        }
+
+        let job_id = job.id;
+        let mut active: job::ActiveModel = job.into();
+        active.status = Set(STATUS_EXPIRED.to_string());
+        active.result_path = Set(None);
+        active.updated_at = Set(now);
+        let job = active.update(db).await?;
+        publish(events, &job);
+        tracing::info!(job_id = job_id, "Expired archive removed");
+    }
+
+    Ok(())
+}
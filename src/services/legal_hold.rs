@@ -0,0 +1,53 @@
+use crate::{entities::file, utils::file_utils};
+use sea_orm::{
+    sea_query::{Expr, LikeExpr},
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder,
+};
+
+/// Held record keeping `f` in place, `None` when it may be changed
+/// A hold on a folder covers everything inside it, and a folder holding a held item
+/// cannot be deleted or moved either
+pub async fn holding<C: ConnectionTrait>(
+    db: &C,
+    f: &file::Model,
+) -> Result<Option<file::Model>, DbErr> {
+    if f.legal_hold {
+        return Ok(Some(f.clone()));
+    }
+
+    let mut related =
+        Condition::any().add(file::Column::Path.is_in(file_utils::ancestor_paths(&f.parent_path)));
+    if f.file_type == "folder" {
+        related = related.add(
+            Expr::col(file::Column::Path)
+                .like(LikeExpr::new(file_utils::descendant_pattern(&f.path)).escape('\\')),
+        );
+    }
+
+    file::Entity::find()
+        .filter(file::Column::UserId.eq(f.user_id))
+        .filter(file::Column::LegalHold.eq(true))
+        .filter(related)
+        .one(db)
+        .await
+}
+
+/// Number of held records a user owns
+pub async fn count_for_user<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<u64, DbErr> {
+    file::Entity::find()
+        .filter(file::Column::UserId.eq(user_id))
+        .filter(file::Column::LegalHold.eq(true))
+        .count(db)
+        .await
+}
+
+/// Every held record, by owner and path
+pub async fn list<C: ConnectionTrait>(db: &C) -> Result<Vec<file::Model>, DbErr> {
+    file::Entity::find()
+        .filter(file::Column::LegalHold.eq(true))
+        .order_by_asc(file::Column::UserId)
+        .order_by_asc(file::Column::Path)
+        .all(db)
+        .await
+}
@@ -0,0 +1,93 @@
+use crate::{
+    constants::ROLE_LIBRARY,
+    entities::{group_member, library_grant, user},
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::sync::OnceLock;
+
+/// Username of the account owning the Shared Library, not a valid registration name
+const LIBRARY_USERNAME: &str = "@library";
+const LIBRARY_EMAIL: &str = "library@localhost";
+
+static OWNER_ID: OnceLock<i32> = OnceLock::new();
+
+/// Make sure the Shared Library account exists and remember its ID
+/// Library files are owned by it, so the rest of the file code treats the library as one more tree
+pub async fn init(db: &DatabaseConnection) -> Result<i32, DbErr> {
+    let existing = user::Entity::find()
+        .filter(user::Column::Role.eq(ROLE_LIBRARY))
+        .one(db)
+        .await?;
+
+    let owner = match existing {
+        Some(u) => u,
+        None => {
+            let now = Utc::now().naive_utc();
+            let owner = user::ActiveModel {
+                username: Set(LIBRARY_USERNAME.to_string()),
+                email: Set(LIBRARY_EMAIL.to_string()),
+                // Not a valid hash, nobody can log in as the library
+                password_hash: Set("!".to_string()),
+                role: Set(ROLE_LIBRARY.to_string()),
+                token_version: Set(0),
+                login_alerts_enabled: Set(false),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            tracing::info!(owner_id = owner.id, "Shared Library account created");
+            owner
+        }
+    };
+
+    let _ = OWNER_ID.set(owner.id);
+    Ok(owner.id)
+}
+
+/// ID of the account owning the Shared Library
+pub fn owner_id() -> Option<i32> {
+    OWNER_ID.get().copied()
+}
+
+/// Whether files of `user_id` belong to the Shared Library
+pub fn is_library(user_id: i32) -> bool {
+    owner_id() == Some(user_id)
+}
+
+/// Read, write and delete access of a user to a library path
+/// Grants on a folder cover everything below it, all matching grants add up
+pub async fn permissions(
+    db: &DatabaseConnection,
+    user_id: i32,
+    user_role: &str,
+    path: &str,
+) -> Result<(bool, bool, bool), DbErr> {
+    if user_role == crate::constants::ROLE_ADMIN {
+        return Ok((true, true, true));
+    }
+
+    let group_ids: Vec<i32> = group_member::Entity::find()
+        .filter(group_member::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|m| m.group_id)
+        .collect();
+
+    let grants = library_grant::Entity::find().all(db).await?;
+    Ok(grants
+        .iter()
+        .filter(|g| g.covers(path))
+        .filter(|g| {
+            g.role.as_deref() == Some(user_role)
+                || g.group_id.is_some_and(|id| group_ids.contains(&id))
+        })
+        .fold((false, false, false), |(r, w, d), g| {
+            (r || g.can_read, w || g.can_write, d || g.can_delete)
+        }))
+}
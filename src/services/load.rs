@@ -0,0 +1,163 @@
+use crate::{
+    config::LoadSheddingConfig,
+    models::admin::{LoadStats, RouteLoad},
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Default)]
+struct RouteCounters {
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+    completed: AtomicU64,
+    shed: AtomicU64,
+}
+
+/// Counts the requests in progress per route and, with load shedding on, caps them
+pub struct LoadTracker {
+    config: LoadSheddingConfig,
+    slots: Arc<Semaphore>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    shed: AtomicU64,
+    routes: Mutex<HashMap<String, Arc<RouteCounters>>>,
+}
+
+/// A request being handled, its slot is released when dropped
+pub struct InFlight<'a> {
+    tracker: &'a LoadTracker,
+    slot: RouteSlot,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.slot.0.completed.fetch_add(1, Ordering::Relaxed);
+        self.tracker.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Request counted on its route, also released when it is cancelled while waiting
+struct RouteSlot(Arc<RouteCounters>);
+
+impl Drop for RouteSlot {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Request waiting for a free slot
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl LoadTracker {
+    pub fn new(config: &LoadSheddingConfig) -> Self {
+        LoadTracker {
+            config: config.clone(),
+            slots: Arc::new(Semaphore::new(config.max_in_flight.max(1))),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            shed: AtomicU64::new(0),
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn counters(&self, route: &str) -> Arc<RouteCounters> {
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        routes.entry(route.to_string()).or_default().clone()
+    }
+
+    /// Take a slot for a request on `route`, `None` when it has to be refused
+    /// Over the global limit the request waits in the queue, over a route limit it is refused
+    pub async fn admit(&self, route: &str) -> Option<InFlight<'_>> {
+        let counters = self.counters(route);
+        let in_flight = counters.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        let slot = RouteSlot(counters);
+
+        let permit = if self.config.enabled {
+            match self.acquire(route, in_flight).await {
+                Some(permit) => Some(permit),
+                None => {
+                    slot.0.shed.fetch_add(1, Ordering::Relaxed);
+                    self.shed.fetch_add(1, Ordering::Relaxed);
+                    return None;
+                }
+            }
+        } else {
+            None
+        };
+
+        slot.0.peak.fetch_max(in_flight, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        Some(InFlight {
+            tracker: self,
+            slot,
+            _permit: permit,
+        })
+    }
+
+    async fn acquire(&self, route: &str, in_flight: usize) -> Option<OwnedSemaphorePermit> {
+        if self
+            .config
+            .route_limit(route)
+            .is_some_and(|limit| in_flight > limit)
+        {
+            return None;
+        }
+        if let Ok(permit) = self.slots.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+
+        if self.queued.fetch_add(1, Ordering::Relaxed) >= self.config.max_queued {
+            self.queued.fetch_sub(1, Ordering::Relaxed);
+            return None;
+        }
+        let _queued = Queued(&self.queued);
+        tokio::time::timeout(
+            Duration::from_millis(self.config.queue_timeout_ms),
+            self.slots.clone().acquire_owned(),
+        )
+        .await
+        .ok()?
+        .ok()
+    }
+
+    pub fn snapshot(&self) -> LoadStats {
+        let routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut routes: Vec<RouteLoad> = routes
+            .iter()
+            .map(|(route, c)| RouteLoad {
+                route: route.clone(),
+                in_flight: c.in_flight.load(Ordering::Relaxed),
+                peak: c.peak.load(Ordering::Relaxed),
+                completed: c.completed.load(Ordering::Relaxed),
+                shed: c.shed.load(Ordering::Relaxed),
+                max_in_flight: self.config.route_limit(route),
+            })
+            .collect();
+        routes.sort_by(|a, b| {
+            b.in_flight
+                .cmp(&a.in_flight)
+                .then(b.peak.cmp(&a.peak))
+                .then(a.route.cmp(&b.route))
+        });
+
+        LoadStats {
+            shedding_enabled: self.config.enabled,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            max_in_flight: self.config.max_in_flight,
+            max_queued: self.config.max_queued,
+            shed: self.shed.load(Ordering::Relaxed),
+            routes,
+        }
+    }
+}
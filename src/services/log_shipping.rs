@@ -0,0 +1,183 @@
+use crate::{config::LoggingConfig, utils::http_client};
+use axum::http::{header, Method};
+use std::io::{self, Write};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Lines waiting to be shipped, later ones are dropped while the queue is full
+const QUEUE_CAPACITY: usize = 10_000;
+/// Lines sent in one HTTP request
+const HTTP_BATCH_SIZE: usize = 500;
+const HTTP_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+/// syslog facility "user"
+const SYSLOG_FACILITY: u8 = 1;
+
+/// Hands JSON log lines to the background task shipping them to syslog and/or HTTP
+/// Logging never waits on the shipping targets
+#[derive(Clone)]
+pub struct LogShipper {
+    tx: mpsc::Sender<(Level, Vec<u8>)>,
+}
+
+/// Buffers one log line and queues it when dropped
+pub struct ShipWriter<'a> {
+    shipper: &'a LogShipper,
+    level: Level,
+    line: Vec<u8>,
+}
+
+impl LogShipper {
+    /// Start shipping when a target is configured, must run inside the Tokio runtime
+    pub fn spawn(config: &LoggingConfig) -> Option<Self> {
+        let syslog = config.syslog.clone().filter(|s| !s.trim().is_empty());
+        let http_endpoint = config
+            .http_endpoint
+            .clone()
+            .filter(|s| !s.trim().is_empty());
+        if syslog.is_none() && http_endpoint.is_none() {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(ship(rx, syslog, http_endpoint));
+        Some(LogShipper { tx })
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogShipper {
+    type Writer = ShipWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ShipWriter {
+            shipper: self,
+            level: Level::INFO,
+            line: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        ShipWriter {
+            shipper: self,
+            level: *meta.level(),
+            line: Vec::new(),
+        }
+    }
+}
+
+impl Write for ShipWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ShipWriter<'_> {
+    fn drop(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            let _ = self.shipper.tx.try_send((self.level, line));
+        }
+    }
+}
+
+/// Shipping failures go to stderr, logging them would feed them back into the queue
+async fn ship(
+    mut rx: mpsc::Receiver<(Level, Vec<u8>)>,
+    syslog: Option<String>,
+    http_endpoint: Option<String>,
+) {
+    let socket = match &syslog {
+        Some(_) => match tokio::net::UdpSocket::bind("0.0.0.0:0").await {
+            Ok(socket) => Some(socket),
+            Err(e) => {
+                eprintln!("Log shipping to syslog disabled: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+
+    let mut batch: Vec<u8> = Vec::new();
+    let mut batched = 0;
+    let mut flush = tokio::time::interval(HTTP_FLUSH_INTERVAL);
+
+    loop {
+        let received = tokio::select! {
+            line = rx.recv() => line,
+            _ = flush.tick() => {
+                if let Some(url) = &http_endpoint {
+                    post_batch(url, &mut batch, &mut batched).await;
+                }
+                continue;
+            }
+        };
+        let Some((level, line)) = received else {
+            break;
+        };
+
+        if let (Some(socket), Some(target)) = (&socket, &syslog) {
+            let message = syslog_message(level, &hostname, &line);
+            if let Err(e) = socket.send_to(&message, target.as_str()).await {
+                eprintln!("Failed to send log line to syslog: {}", e);
+            }
+        }
+        if let Some(url) = &http_endpoint {
+            batch.extend_from_slice(&line);
+            batched += 1;
+            if batched >= HTTP_BATCH_SIZE {
+                post_batch(url, &mut batch, &mut batched).await;
+            }
+        }
+    }
+}
+
+/// RFC 5424 message carrying the JSON line
+fn syslog_message(level: Level, hostname: &str, line: &[u8]) -> Vec<u8> {
+    let severity = match level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        Level::DEBUG | Level::TRACE => 7,
+    };
+    let mut message = format!(
+        "<{}>1 {} {} {} {} - - ",
+        SYSLOG_FACILITY * 8 + severity,
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        hostname,
+        env!("CARGO_PKG_NAME"),
+        std::process::id(),
+    )
+    .into_bytes();
+    message.extend_from_slice(line.strip_suffix(b"\n").unwrap_or(line));
+    message
+}
+
+async fn post_batch(url: &str, batch: &mut Vec<u8>, batched: &mut usize) {
+    if batch.is_empty() {
+        return;
+    }
+    let body = std::mem::take(batch);
+    let lines = std::mem::take(batched);
+
+    let headers = [(header::CONTENT_TYPE, "application/x-ndjson".to_string())];
+    let sent = tokio::time::timeout(
+        HTTP_TIMEOUT,
+        http_client::send(Method::POST, url, &headers, body, MAX_RESPONSE_BYTES),
+    )
+    .await
+    .unwrap_or_else(|_| Err("timed out".to_string()));
+    match sent {
+        Ok((status, _)) if status.is_success() => {}
+        Ok((status, _)) => eprintln!("Log shipping endpoint dropped {} lines: {}", lines, status),
+        Err(e) => eprintln!("Failed to ship {} log lines: {}", lines, e),
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    entities::{audit_log, user},
+    services::{
+        audit::{self, AuditEvent},
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+    utils::client::ClientInfo,
+    AppState,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter};
+use serde_json::json;
+
+/// Check a successful login against the user's login history
+/// Must run before the login itself is recorded
+pub async fn check_login(state: &AppState, user: &user::Model, client: &ClientInfo) {
+    let is_new = match is_new_device(&state.db, user.id, client).await {
+        Ok(n) => n,
+        Err(e) => {
+            tracing::warn!(user_id = user.id, error = %e, "Failed to check login history");
+            return;
+        }
+    };
+
+    if !is_new {
+        return;
+    }
+
+    tracing::info!(user_id = user.id, ip = ?client.ip, "Login from a new device or location");
+
+    audit::record(
+        &state.db,
+        Some(user.id),
+        AuditEvent::NewDeviceLogin,
+        client,
+        Some(json!({ "alert_sent": user.login_alerts_enabled })),
+    )
+    .await;
+
+    if !user.login_alerts_enabled {
+        return;
+    }
+
+    let time = chrono::Utc::now()
+        .format("%Y-%m-%d %H:%M:%S UTC")
+        .to_string();
+    let ip_address = client.ip.clone().unwrap_or_else(|| "unknown".to_string());
+    let device = client
+        .user_agent
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let notification = Notification {
+        category: NotificationCategory::Security,
+        title: "New sign-in to your account".to_string(),
+        body: format!("Signed in from {} ({}) at {}", ip_address, device, time),
+        link: None,
+        email: Some(EmailTemplate::LoginAlert {
+            username: user.username.clone(),
+            time,
+            ip_address,
+            device,
+        }),
+    };
+
+    if let Err(e) = notifications::dispatch(&state.db, &state.config.smtp, user, notification).await
+    {
+        tracing::warn!(user_id = user.id, error = %e, "Failed to send login alert");
+    }
+}
+
+/// A login is new when the IP or User-Agent hasn't been seen in a previous login
+/// The very first login of an account never counts as new
+async fn is_new_device(
+    db: &DatabaseConnection,
+    user_id: i32,
+    client: &ClientInfo,
+) -> Result<bool, DbErr> {
+    let previous_logins = || {
+        audit_log::Entity::find()
+            .filter(audit_log::Column::UserId.eq(user_id))
+            .filter(audit_log::Column::Event.eq(AuditEvent::Login.as_str()))
+    };
+
+    if previous_logins().count(db).await? == 0 {
+        return Ok(false);
+    }
+
+    let known_ip = match &client.ip {
+        Some(ip) => {
+            previous_logins()
+                .filter(audit_log::Column::IpAddress.eq(ip.as_str()))
+                .count(db)
+                .await?
+                > 0
+        }
+        None => true,
+    };
+
+    let known_agent = match &client.user_agent {
+        Some(ua) => {
+            previous_logins()
+                .filter(audit_log::Column::UserAgent.eq(ua.as_str()))
+                .count(db)
+                .await?
+                > 0
+        }
+        None => true,
+    };
+
+    Ok(!known_ip || !known_agent)
+}
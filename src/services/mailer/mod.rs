@@ -0,0 +1,48 @@
+//! Outgoing email: templates, a persistent outbox and SMTP delivery
+//!
+//! Emails are written to the `email_outbox` table and delivered by a
+//! background worker, so transient SMTP failures are retried instead of lost.
+
+mod outbox;
+mod templates;
+mod transport;
+
+pub use outbox::{spawn_worker, STATUS_FAILED, STATUS_PENDING, STATUS_SENT};
+pub use templates::{EmailTemplate, RenderedEmail};
+pub use transport::send_email;
+
+use crate::{config::SmtpConfig, entities::email_outbox};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, Set};
+
+/// Queue an email for delivery
+/// When SMTP is disabled the email is logged and dropped
+pub async fn enqueue(
+    db: &DatabaseConnection,
+    config: &SmtpConfig,
+    to: &str,
+    template: &EmailTemplate,
+) -> Result<(), DbErr> {
+    let rendered = template.render();
+
+    if !config.enabled {
+        tracing::info!(to = %to, subject = %rendered.subject, "SMTP disabled, email not queued");
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let email = email_outbox::ActiveModel {
+        to_address: Set(to.to_string()),
+        subject: Set(rendered.subject),
+        body: Set(rendered.body),
+        status: Set(STATUS_PENDING.to_string()),
+        attempts: Set(0),
+        last_error: Set(None),
+        next_attempt_at: Set(now),
+        created_at: Set(now),
+        sent_at: Set(None),
+        ..Default::default()
+    };
+
+    email.insert(db).await?;
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use super::transport;
+use crate::{config::SmtpConfig, entities::email_outbox};
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_SENT: &str = "sent";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Emails sent per worker pass
+const BATCH_SIZE: u64 = 20;
+/// First retry delay, doubled on every further failure
+const BASE_RETRY_DELAY_SECS: i64 = 60;
+const MAX_RETRY_DELAY_SECS: i64 = 60 * 60;
+
+/// Start the background task that delivers queued emails
+pub fn spawn_worker(db: DatabaseConnection, config: SmtpConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            config.outbox_poll_interval_secs,
+        ));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = deliver_due(&db, &config).await {
+                tracing::warn!(error = %e, "Email outbox pass failed");
+            }
+        }
+    });
+}
+
+/// Try to send every pending email whose retry time has come
+async fn deliver_due(db: &DatabaseConnection, config: &SmtpConfig) -> Result<(), DbErr> {
+    let now = Utc::now().naive_utc();
+    let due = email_outbox::Entity::find()
+        .filter(email_outbox::Column::Status.eq(STATUS_PENDING))
+        .filter(email_outbox::Column::NextAttemptAt.lte(now))
+        .order_by_asc(email_outbox::Column::NextAttemptAt)
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await?;
+
+    for email in due {
+        let result = transport::send_email(
+            config,
+            &email.to_address,
+            &email.subject,
+            email.body.clone(),
+        )
+        .await;
+
+        let attempts = email.attempts + 1;
+        let id = email.id;
+        let mut active: email_outbox::ActiveModel = email.into();
+        active.attempts = Set(attempts);
+
+        match result {
+            Ok(()) => {
+                active.status = Set(STATUS_SENT.to_string());
+                active.sent_at = Set(Some(Utc::now().naive_utc()));
+                active.last_error = Set(None);
+                tracing::info!(email_id = id, "Email sent");
+            }
+            Err(e) => {
+                active.last_error = Set(Some(e.to_string()));
+                if attempts >= config.outbox_max_attempts {
+                    active.status = Set(STATUS_FAILED.to_string());
+                    tracing::error!(email_id = id, attempts = attempts, error = %e, "Giving up on email");
+                } else {
+                    active.next_attempt_at = Set(Utc::now().naive_utc() + retry_delay(attempts));
+                    tracing::warn!(email_id = id, attempts = attempts, error = %e, "Email delivery failed, will retry");
+                }
+            }
+        }
+
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+/// Exponential backoff: 1m, 2m, 4m, ... capped at one hour
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    let secs = BASE_RETRY_DELAY_SECS.saturating_mul(1 << exponent);
+    Duration::seconds(secs.min(MAX_RETRY_DELAY_SECS))
+}
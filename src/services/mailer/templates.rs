@@ -0,0 +1,251 @@
+/// Emails the application sends
+#[derive(Debug, Clone)]
+pub enum EmailTemplate {
+    /// Confirm ownership of an email address
+    Verification { username: String, link: String },
+    /// Reset a forgotten password
+    PasswordReset {
+        username: String,
+        link: String,
+        expires_in_minutes: i64,
+    },
+    /// Someone shared a file or folder with the recipient
+    ShareNotification {
+        username: String,
+        shared_by: String,
+        item_name: String,
+        /// What the recipient may do, e.g. "read and write"
+        access: String,
+        link: Option<String>,
+    },
+    /// Message an administrator sent to every user
+    Announcement {
+        username: String,
+        title: String,
+        body: String,
+    },
+    /// Login from a device or location not seen before
+    LoginAlert {
+        username: String,
+        time: String,
+        ip_address: String,
+        device: String,
+    },
+    /// Files a cleanup rule is about to delete
+    CleanupNotice {
+        username: String,
+        folder_path: String,
+        count: usize,
+        max_age_days: i32,
+        delete_at: String,
+    },
+    /// Stored data crossed a warning threshold of the quota
+    QuotaWarning {
+        username: String,
+        percent: u64,
+        used_bytes: i64,
+        quota_bytes: u64,
+        /// Largest usage uploads are accepted up to
+        hard_limit_bytes: u64,
+    },
+    /// An administrator took a file down
+    Takedown {
+        username: String,
+        path: String,
+        reason: String,
+    },
+    /// Outcome of a disputed or reviewed takedown
+    TakedownResolved {
+        username: String,
+        path: String,
+        restored: bool,
+        note: Option<String>,
+    },
+    /// A scanner flagged an uploaded file, it is held for review
+    FileQuarantined {
+        username: String,
+        path: String,
+        threat: String,
+    },
+}
+
+/// Subject and plain-text body ready to send
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailTemplate {
+    pub fn render(&self) -> RenderedEmail {
+        match self {
+            EmailTemplate::Verification { username, link } => RenderedEmail {
+                subject: "Verify your Cloud Drive email address".to_string(),
+                body: format!(
+                    "Hi {},\n\n\
+                     Please confirm your email address by opening the link below:\n\n\
+                     {}\n\n\
+                     If you didn't create a Cloud Drive account, you can ignore this email.\n",
+                    username, link
+                ),
+            },
+            EmailTemplate::PasswordReset {
+                username,
+                link,
+                expires_in_minutes,
+            } => RenderedEmail {
+                subject: "Reset your Cloud Drive password".to_string(),
+                body: format!(
+                    "Hi {},\n\n\
+                     We received a request to reset your password. Open the link below to choose a new one:\n\n\
+                     {}\n\n\
+                     The link expires in {} minutes. If you didn't ask for a reset, you can ignore this email.\n",
+                    username, link, expires_in_minutes
+                ),
+            },
+            EmailTemplate::ShareNotification {
+                username,
+                shared_by,
+                item_name,
+                access,
+                link,
+            } => RenderedEmail {
+                subject: format!("{} shared \"{}\" with you", shared_by, item_name),
+                body: format!(
+                    "Hi {},\n\n\
+                     {} shared \"{}\" with you on Cloud Drive.\n\
+                     You have {} access.\n\n\
+                     {}",
+                    username,
+                    shared_by,
+                    item_name,
+                    access,
+                    link.as_ref()
+                        .map(|l| format!("Open it here: {}\n", l))
+                        .unwrap_or_default()
+                ),
+            },
+            EmailTemplate::Announcement {
+                username,
+                title,
+                body,
+            } => RenderedEmail {
+                subject: format!("Cloud Drive: {}", title),
+                body: format!("Hi {},\n\n{}\n", username, body),
+            },
+            EmailTemplate::LoginAlert {
+                username,
+                time,
+                ip_address,
+                device,
+            } => RenderedEmail {
+                subject: "New sign-in to your Cloud Drive".to_string(),
+                body: format!(
+                    "Hi {},\n\n\
+                     Your Cloud Drive account was just signed in to from a device or location we haven't seen before.\n\n\
+                     Time: {} UTC\n\
+                     IP address: {}\n\
+                     Device: {}\n\n\
+                     If this was you, no action is needed.\n\
+                     If not, change your password right away; this signs out every other session.\n\n\
+                     You can turn these alerts off in your account settings.\n",
+                    username, time, ip_address, device
+                ),
+            },
+            EmailTemplate::CleanupNotice {
+                username,
+                folder_path,
+                count,
+                max_age_days,
+                delete_at,
+            } => RenderedEmail {
+                subject: format!("Cloud Drive: {} files in {} will be deleted", count, folder_path),
+                body: format!(
+                    "Hi {},\n\n\
+                     Your cleanup rule for {} will delete {} files unchanged for more than {} days at {} UTC.\n\n\
+                     To keep a file, change or move it before then, or edit the rule in your settings.\n",
+                    username, folder_path, count, max_age_days, delete_at
+                ),
+            },
+            EmailTemplate::QuotaWarning {
+                username,
+                percent,
+                used_bytes,
+                quota_bytes,
+                hard_limit_bytes,
+            } => RenderedEmail {
+                subject: format!("Cloud Drive: you have used {}% of your storage", percent),
+                body: format!(
+                    "Hi {},\n\n\
+                     Your files now take {} of your {} of storage.\n\
+                     Uploads are rejected once they would take more than {}.\n\n\
+                     Delete files you no longer need, or ask an administrator for more space.\n",
+                    username,
+                    megabytes(*used_bytes as u64),
+                    megabytes(*quota_bytes),
+                    megabytes(*hard_limit_bytes)
+                ),
+            },
+            EmailTemplate::Takedown {
+                username,
+                path,
+                reason,
+            } => RenderedEmail {
+                subject: "Cloud Drive: one of your files was taken down".to_string(),
+                body: format!(
+                    "Hi {},\n\n\
+                     An administrator took down your file {}.\n\
+                     Reason: {}\n\n\
+                     The file is kept, but it can't be downloaded or shared anymore.\n\
+                     If you believe this is a mistake, you can submit a counter-notice from the file's details.\n",
+                    username, path, reason
+                ),
+            },
+            EmailTemplate::TakedownResolved {
+                username,
+                path,
+                restored,
+                note,
+            } => RenderedEmail {
+                subject: if *restored {
+                    "Cloud Drive: your file was restored".to_string()
+                } else {
+                    "Cloud Drive: the takedown of your file was upheld".to_string()
+                },
+                body: format!(
+                    "Hi {},\n\n\
+                     {}\n\
+                     {}",
+                    username,
+                    if *restored {
+                        format!("Your file {} is available again.", path)
+                    } else {
+                        format!("Your file {} stays unavailable after review.", path)
+                    },
+                    note.as_ref()
+                        .map(|n| format!("Note from the administrator: {}\n", n))
+                        .unwrap_or_default()
+                ),
+            },
+            EmailTemplate::FileQuarantined {
+                username,
+                path,
+                threat,
+            } => RenderedEmail {
+                subject: "Cloud Drive: a file you uploaded was quarantined".to_string(),
+                body: format!(
+                    "Hi {},\n\n\
+                     Your file {} was flagged as {} and quarantined.\n\
+                     It can't be downloaded or shared until an administrator reviews it,\n\
+                     who will either release it or delete it.\n\n\
+                     If you believe this is a mistake, contact your administrator.\n",
+                    username, path, threat
+                ),
+            },
+        }
+    }
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
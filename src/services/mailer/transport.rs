@@ -0,0 +1,54 @@
+use crate::config::SmtpConfig;
+use anyhow::{anyhow, Result};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+/// Send a plain-text email over SMTP
+/// When SMTP is disabled the email is logged and dropped
+pub async fn send_email(config: &SmtpConfig, to: &str, subject: &str, body: String) -> Result<()> {
+    if !config.enabled {
+        tracing::info!(to = %to, subject = %subject, "SMTP disabled, email not sent");
+        return Ok(());
+    }
+
+    let from: Mailbox = config
+        .from
+        .parse()
+        .map_err(|e| anyhow!("Invalid sender address: {}", e))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| anyhow!("Invalid recipient address: {}", e))?;
+
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)?;
+
+    build_transport(config)?.send(message).await?;
+
+    Ok(())
+}
+
+fn build_transport(config: &SmtpConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let builder = match config.tls.to_lowercase().as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        other => return Err(anyhow!("Unsupported SMTP tls mode: {}", other)),
+    };
+
+    let builder = builder.port(config.port);
+    let builder = match (&config.username, &config.password) {
+        (Some(username), Some(password)) => {
+            builder.credentials(Credentials::new(username.clone(), password.clone()))
+        }
+        _ => builder,
+    };
+
+    Ok(builder.build())
+}
@@ -1,3 +1,48 @@
+pub mod account_deletion;
+pub mod admin_stats;
+pub mod audit;
+pub mod backup;
 pub mod batch_download;
+pub mod changes;
+pub mod cleanup;
+pub mod consistency;
+pub mod custom_metadata;
 pub mod deduplication;
+pub mod disk_space;
 pub mod download;
+pub mod events;
+pub mod features;
+pub mod file_stats;
+pub mod folder_defaults;
+pub mod folder_sizes;
+pub mod folder_styles;
+pub mod job_scheduler;
+pub mod jobs;
+pub mod legal_hold;
+pub mod library;
+pub mod load;
+pub mod log_shipping;
+pub mod login_alert;
+pub mod mailer;
+pub mod mounts;
+pub mod music;
+pub mod notifications;
+pub mod organize;
+pub mod permission_cache;
+pub mod photos;
+pub mod processing;
+pub mod quarantine;
+pub mod quota;
+pub mod replication;
+pub mod search;
+pub mod share_alert;
+pub mod staging;
+pub mod stars;
+pub mod storage_health;
+pub mod storage_migration;
+pub mod tags;
+pub mod takedown;
+pub mod upload_sessions;
+pub mod usage_history;
+pub mod user_cache;
+pub mod volumes;
@@ -0,0 +1,211 @@
+use crate::{
+    config::Config,
+    entities::{file, mount},
+    handlers::file::delete_file_record,
+    utils::file_utils,
+};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Set,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Entry found on the host while indexing, `path` is relative to the mount
+struct HostEntry {
+    path: String,
+    is_dir: bool,
+    size: i64,
+    modified: NaiveDateTime,
+}
+
+/// Changes applied by one indexing run
+#[derive(Debug, Default)]
+pub struct IndexSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// Mount of `user_id` that contains `path`, if any
+pub async fn find_for_path(
+    db: &DatabaseConnection,
+    user_id: i32,
+    path: &str,
+) -> Result<Option<mount::Model>, DbErr> {
+    let mounts = mount::Entity::find()
+        .filter(mount::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+    Ok(mounts.into_iter().find(|m| m.contains(path)))
+}
+
+/// Host location of a path inside a mount
+pub fn host_path(mount: &mount::Model, path: &str) -> PathBuf {
+    let relative = path
+        .strip_prefix(&mount.mount_point())
+        .unwrap_or("")
+        .trim_start_matches('/');
+    if relative.is_empty() {
+        PathBuf::from(&mount.host_path)
+    } else {
+        Path::new(&mount.host_path).join(relative.replace('/', std::path::MAIN_SEPARATOR_STR))
+    }
+}
+
+/// Start the background task that keeps the file index of every mount current
+pub fn spawn_indexer(db: DatabaseConnection, config: Config) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.mounts.index_interval_secs));
+
+        loop {
+            interval.tick().await;
+            let mounts = match mount::Entity::find().all(&db).await {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to load mounts");
+                    continue;
+                }
+            };
+
+            for m in mounts {
+                if let Err(e) = index(&db, &m).await {
+                    tracing::warn!(mount_id = m.id, error = %e, "Mount indexing failed");
+                }
+            }
+        }
+    });
+}
+
+/// Sync the file rows below a mount with the host directory
+/// Nothing is removed when the host directory cannot be read, e.g. while a NAS is offline
+pub async fn index(db: &DatabaseConnection, m: &mount::Model) -> anyhow::Result<IndexSummary> {
+    let root = PathBuf::from(&m.host_path);
+    let entries = tokio::task::spawn_blocking(move || {
+        let mut entries = Vec::new();
+        scan(&root, "", &mut entries)?;
+        Ok::<_, std::io::Error>(entries)
+    })
+    .await??;
+
+    let mount_point = m.mount_point();
+    let existing: HashMap<String, file::Model> = file::Entity::find()
+        .filter(file::Column::UserId.eq(m.user_id))
+        .filter(
+            Condition::any()
+                .add(file::Column::Path.eq(&mount_point))
+                .add(file::Column::Path.starts_with(format!("{}/", mount_point))),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|f| (f.path.clone(), f))
+        .collect();
+
+    let mut summary = IndexSummary::default();
+    let mut seen = HashSet::with_capacity(entries.len());
+    let now = Utc::now().naive_utc();
+
+    for entry in entries {
+        let path = format!("{}/{}", mount_point, entry.path);
+        seen.insert(path.clone());
+
+        match existing.get(&path) {
+            Some(f) if f.file_type == "file" && !entry.is_dir => {
+                if f.size_bytes != Some(entry.size) || entry.modified > f.updated_at {
+                    let mut active: file::ActiveModel = f.clone().into();
+                    active.size_bytes = Set(Some(entry.size));
+                    active.file_hash = Set(None);
+                    active.version = Set(f.version + 1);
+                    active.updated_at = Set(entry.modified);
+                    active.update(db).await?;
+                    summary.updated += 1;
+                }
+            }
+            Some(f) if (f.file_type == "folder") == entry.is_dir => {}
+            other => {
+                // Replaced by an entry of the other type
+                if let Some(f) = other {
+                    delete_file_record(db, f.id).await?;
+                }
+
+                let (parent_path, name) = path.rsplit_once('/').unwrap_or(("", &path));
+                file::ActiveModel {
+                    user_id: Set(m.user_id),
+                    name: Set(name.to_string()),
+                    path: Set(path.clone()),
+                    parent_path: Set(parent_path.to_string()),
+                    file_type: Set(if entry.is_dir { "folder" } else { "file" }.into()),
+                    mime_type: Set((!entry.is_dir).then(|| file_utils::get_mime_type(name))),
+                    size_bytes: Set((!entry.is_dir).then_some(entry.size)),
+                    storage_path: Set(host_path(m, &path).to_string_lossy().replace('\\', "/")),
+                    ref_count: Set(1),
+                    created_at: Set(now),
+                    updated_at: Set(entry.modified),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+                summary.added += 1;
+            }
+        }
+    }
+
+    for (path, f) in &existing {
+        if *path != mount_point && !seen.contains(path) {
+            delete_file_record(db, f.id).await?;
+            summary.removed += 1;
+        }
+    }
+
+    mount::ActiveModel {
+        id: Set(m.id),
+        last_indexed_at: Set(Some(now)),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    tracing::info!(
+        mount_id = m.id,
+        added = summary.added,
+        updated = summary.updated,
+        removed = summary.removed,
+        "Mount indexed"
+    );
+    Ok(summary)
+}
+
+/// Collect everything below `dir`, symlinks are skipped so a mount cannot reach outside its directory
+fn scan(dir: &Path, prefix: &str, entries: &mut Vec<HostEntry>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_symlink() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+        entries.push(HostEntry {
+            path: path.clone(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len() as i64,
+            modified: chrono::DateTime::<Utc>::from(modified).naive_utc(),
+        });
+
+        if metadata.is_dir() {
+            scan(&entry.path(), &path, entries)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use crate::{
+    entities::{audio_cover, audio_metadata, file},
+    services::job_scheduler::{self, JobClass},
+    utils::{file_utils, id3},
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Set,
+};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Tags larger than this are not read, they are almost all cover art
+const MAX_TAG_BYTES: usize = 16 * 1024 * 1024;
+
+/// Whether a file is audio, by its stored MIME type or else its extension
+pub fn is_audio(f: &file::Model) -> bool {
+    f.file_type == "file"
+        && f.mime_type
+            .as_deref()
+            .filter(|m| *m != "application/octet-stream")
+            .map_or_else(|| file_utils::get_mime_type(&f.name), str::to_string)
+            .starts_with("audio/")
+}
+
+/// Read the tags of an uploaded audio file in the background
+/// Failures are logged, the upload itself has already succeeded
+pub fn spawn_index(db: &DatabaseConnection, f: &file::Model) {
+    if !is_audio(f) {
+        return;
+    }
+    let db = db.clone();
+    let f = f.clone();
+    tokio::spawn(async move {
+        let _permit = job_scheduler::acquire(JobClass::Thumbnailing).await;
+        if let Err(e) = index(&db, &f).await {
+            tracing::warn!(file_id = f.id, error = %e, "Failed to read audio metadata");
+        }
+    });
+}
+
+/// Store the tags of an audio file, replacing what an earlier version had
+pub async fn index(db: &DatabaseConnection, f: &file::Model) -> Result<(), DbErr> {
+    let path = f.storage_path.clone();
+    let tags = tokio::task::spawn_blocking(move || read_tags(Path::new(&path)))
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    // The new version may come without cover art
+    audio_cover::Entity::delete_by_id(f.id).exec(db).await?;
+
+    let has_cover = tags.cover.is_some();
+    audio_metadata::Entity::insert(audio_metadata::ActiveModel {
+        file_id: Set(f.id),
+        user_id: Set(f.user_id),
+        title: Set(tags.title),
+        artist: Set(tags.artist),
+        album: Set(tags.album),
+        track_number: Set(tags.track_number),
+        year: Set(tags.year),
+        has_cover: Set(has_cover),
+        updated_at: Set(Utc::now().naive_utc()),
+    })
+    .on_conflict(
+        OnConflict::column(audio_metadata::Column::FileId)
+            .update_columns([
+                audio_metadata::Column::Title,
+                audio_metadata::Column::Artist,
+                audio_metadata::Column::Album,
+                audio_metadata::Column::TrackNumber,
+                audio_metadata::Column::Year,
+                audio_metadata::Column::HasCover,
+                audio_metadata::Column::UpdatedAt,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+
+    if let Some((mime_type, data)) = tags.cover {
+        audio_cover::Entity::insert(audio_cover::ActiveModel {
+            file_id: Set(f.id),
+            mime_type: Set(mime_type),
+            data: Set(data),
+        })
+        .exec(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Drop the stored tags and cover of a file
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    audio_cover::Entity::delete_by_id(file_id).exec(db).await?;
+    audio_metadata::Entity::delete_by_id(file_id)
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// ID3v2 tags from the start of the file, gaps filled from an ID3v1 tag at the end
+fn read_tags(path: &Path) -> std::io::Result<id3::AudioTags> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut tags = id3::AudioTags::default();
+    let mut header = [0u8; 10];
+    if file.read_exact(&mut header).is_ok() {
+        if let Some(tag_len) = id3::id3v2_len(&header).filter(|l| *l <= MAX_TAG_BYTES) {
+            let mut data = header.to_vec();
+            data.resize(tag_len, 0);
+            file.read_exact(&mut data[10..])?;
+            tags = id3::parse_id3v2(&data).unwrap_or_default();
+        }
+    }
+
+    if len >= 128 {
+        let mut trailer = vec![0u8; 128];
+        file.seek(SeekFrom::End(-128))?;
+        file.read_exact(&mut trailer)?;
+        if let Some(v1) = id3::parse_id3v1(&trailer) {
+            tags.title = tags.title.or(v1.title);
+            tags.artist = tags.artist.or(v1.artist);
+            tags.album = tags.album.or(v1.album);
+            tags.track_number = tags.track_number.or(v1.track_number);
+            tags.year = tags.year.or(v1.year);
+        }
+    }
+
+    Ok(tags)
+}
@@ -0,0 +1,153 @@
+use crate::{
+    config::SmtpConfig,
+    constants::ROLE_LIBRARY,
+    entities::{notification, notification_preference, user},
+    services::mailer::{self, EmailTemplate},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+/// Kinds of events users can be notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    Share,
+    Comment,
+    Security,
+    Job,
+    Announcement,
+    Quota,
+}
+
+impl NotificationCategory {
+    pub const ALL: [NotificationCategory; 6] = [
+        NotificationCategory::Share,
+        NotificationCategory::Comment,
+        NotificationCategory::Security,
+        NotificationCategory::Job,
+        NotificationCategory::Announcement,
+        NotificationCategory::Quota,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationCategory::Share => "share",
+            NotificationCategory::Comment => "comment",
+            NotificationCategory::Security => "security",
+            NotificationCategory::Job => "job",
+            NotificationCategory::Announcement => "announcement",
+            NotificationCategory::Quota => "quota",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+
+    /// Channels used until the user changes them: (email, in-app)
+    pub fn default_channels(&self) -> (bool, bool) {
+        match self {
+            NotificationCategory::Share => (true, true),
+            NotificationCategory::Comment => (false, true),
+            NotificationCategory::Security => (true, true),
+            NotificationCategory::Job => (false, true),
+            NotificationCategory::Announcement => (false, true),
+            NotificationCategory::Quota => (true, true),
+        }
+    }
+}
+
+/// Delivery channels a user chose for a category
+#[derive(Debug, Clone, Copy)]
+pub struct Channels {
+    pub email: bool,
+    pub in_app: bool,
+}
+
+/// A notification to deliver to one user
+pub struct Notification {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    /// Email to send when the user has email enabled for the category
+    pub email: Option<EmailTemplate>,
+}
+
+/// Resolve the user's channels for a category, falling back to the defaults
+pub async fn channels_for(
+    db: &DatabaseConnection,
+    user_id: i32,
+    category: NotificationCategory,
+) -> Result<Channels, DbErr> {
+    let pref = notification_preference::Entity::find()
+        .filter(notification_preference::Column::UserId.eq(user_id))
+        .filter(notification_preference::Column::Category.eq(category.as_str()))
+        .one(db)
+        .await?;
+
+    Ok(match pref {
+        Some(p) => Channels {
+            email: p.email_enabled,
+            in_app: p.in_app_enabled,
+        },
+        None => {
+            let (email, in_app) = category.default_channels();
+            Channels { email, in_app }
+        }
+    })
+}
+
+/// Deliver a notification through the channels the user enabled
+pub async fn dispatch(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    recipient: &user::Model,
+    notification: Notification,
+) -> Result<(), DbErr> {
+    let channels = channels_for(db, recipient.id, notification.category).await?;
+
+    if channels.in_app {
+        notification::ActiveModel {
+            user_id: Set(recipient.id),
+            category: Set(notification.category.as_str().to_string()),
+            title: Set(notification.title),
+            body: Set(notification.body),
+            link: Set(notification.link),
+            read_at: Set(None),
+            created_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    if channels.email {
+        if let Some(template) = &notification.email {
+            mailer::enqueue(db, smtp, &recipient.email, template).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Deliver a notification built by `make` to every user account
+/// Returns the number of recipients; a failure for one user does not stop the rest
+pub async fn broadcast(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    make: impl Fn(&user::Model) -> Notification,
+) -> Result<usize, DbErr> {
+    let recipients = user::Entity::find()
+        .filter(user::Column::Role.ne(ROLE_LIBRARY))
+        .all(db)
+        .await?;
+
+    for recipient in &recipients {
+        if let Err(e) = dispatch(db, smtp, recipient, make(recipient)).await {
+            tracing::warn!(user_id = recipient.id, error = %e, "Failed to deliver broadcast notification");
+        }
+    }
+
+    Ok(recipients.len())
+}
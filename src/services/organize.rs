@@ -0,0 +1,65 @@
+use crate::{entities::organize_rule, utils::file_utils};
+use chrono::{Datelike, NaiveDateTime};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+/// Rules one user can keep
+pub const MAX_RULES: u64 = 100;
+
+/// Rules of a user in the order they are tried
+pub async fn for_user<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+) -> Result<Vec<organize_rule::Model>, DbErr> {
+    organize_rule::Entity::find()
+        .filter(organize_rule::Column::UserId.eq(user_id))
+        .order_by_asc(organize_rule::Column::Position)
+        .order_by_asc(organize_rule::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Whether `rule` takes a file named `name`, of type `mime_type` when the client sent one
+pub fn matches(rule: &organize_rule::Model, name: &str, mime_type: Option<&str>) -> bool {
+    let (_, extension) = file_utils::split_filename(name);
+    let extension = extension.to_lowercase();
+    let by_extension = rule
+        .extensions
+        .as_deref()
+        .is_none_or(|list| list.split(',').any(|e| e == extension));
+
+    // Clients often send octet-stream for everything, the name tells more
+    let mime_type = match mime_type {
+        Some(m) if m != "application/octet-stream" => m.to_lowercase(),
+        _ => file_utils::get_mime_type(name),
+    };
+    let by_mime = rule
+        .mime_prefix
+        .as_deref()
+        .is_none_or(|prefix| mime_type.starts_with(prefix));
+
+    by_extension && by_mime
+}
+
+/// Destination of a rule for a file dated `time`
+pub fn expand(destination: &str, time: NaiveDateTime) -> String {
+    destination
+        .replace("{YYYY}", &format!("{:04}", time.year()))
+        .replace("{MM}", &format!("{:02}", time.month()))
+        .replace("{DD}", &format!("{:02}", time.day()))
+}
+
+/// First rule filing a new file of `folder_path`, with the folder it goes to
+pub fn destination<'a>(
+    rules: &'a [organize_rule::Model],
+    folder_path: &str,
+    name: &str,
+    mime_type: Option<&str>,
+    time: NaiveDateTime,
+) -> Option<(&'a organize_rule::Model, String)> {
+    rules
+        .iter()
+        .filter(|r| r.folder_path == folder_path)
+        .find(|r| matches(r, name, mime_type))
+        .map(|r| (r, expand(&r.destination, time)))
+        .filter(|(_, destination)| destination != folder_path)
+}
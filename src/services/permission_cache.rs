@@ -0,0 +1,52 @@
+use moka::sync::Cache;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Entries expire after this, bounding staleness from writes that skip invalidation
+const TTL: Duration = Duration::from_secs(60);
+const MAX_ENTRIES: u64 = 100_000;
+
+/// Read, write and delete flags of a grant
+pub type Grant = (bool, bool, bool);
+
+/// Direct grants by (user, file), files without a grant are cached as no access
+static CACHE: OnceLock<Cache<(i32, i32), Grant>> = OnceLock::new();
+
+fn cache() -> &'static Cache<(i32, i32), Grant> {
+    CACHE.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(MAX_ENTRIES)
+            .time_to_live(TTL)
+            .support_invalidation_closures()
+            .build()
+    })
+}
+
+pub fn get(user_id: i32, file_id: i32) -> Option<Grant> {
+    cache().get(&(user_id, file_id))
+}
+
+pub fn insert(user_id: i32, file_id: i32, grant: Grant) {
+    cache().insert((user_id, file_id), grant);
+}
+
+/// Forget a user's grant on a file, call after it is created or changed
+pub fn invalidate(user_id: i32, file_id: i32) {
+    cache().invalidate(&(user_id, file_id));
+}
+
+/// Forget every grant on a file, call after its grants are removed
+pub fn invalidate_file(file_id: i32) {
+    if let Err(e) = cache().invalidate_entries_if(move |(_, id), _| *id == file_id) {
+        tracing::warn!(file_id = file_id, error = %e, "Failed to invalidate cached permissions");
+        cache().invalidate_all();
+    }
+}
+
+/// Forget every grant of a user, call after the account is removed
+pub fn invalidate_user(user_id: i32) {
+    if let Err(e) = cache().invalidate_entries_if(move |(id, _), _| *id == user_id) {
+        tracing::warn!(user_id = user_id, error = %e, "Failed to invalidate cached permissions");
+        cache().invalidate_all();
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    entities::{file, photo_location},
+    services::job_scheduler::{self, JobClass},
+    utils::{exif, file_utils},
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Set,
+};
+use std::io::Read;
+use std::path::Path;
+
+/// EXIF data sits near the start of the file, the rest is not read
+const MAX_EXIF_BYTES: u64 = 256 * 1024;
+
+/// Whether a file is an image, by its stored MIME type or else its extension
+pub fn is_image(f: &file::Model) -> bool {
+    f.file_type == "file"
+        && f.mime_type
+            .as_deref()
+            .filter(|m| *m != "application/octet-stream")
+            .map_or_else(|| file_utils::get_mime_type(&f.name), str::to_string)
+            .starts_with("image/")
+}
+
+/// Read the GPS position of an uploaded image in the background
+/// Failures are logged, the upload itself has already succeeded
+pub fn spawn_index(db: &DatabaseConnection, f: &file::Model) {
+    if !is_image(f) {
+        return;
+    }
+    let db = db.clone();
+    let f = f.clone();
+    tokio::spawn(async move {
+        let _permit = job_scheduler::acquire(JobClass::Thumbnailing).await;
+        if let Err(e) = index(&db, &f).await {
+            tracing::warn!(file_id = f.id, error = %e, "Failed to read photo location");
+        }
+    });
+}
+
+/// Store the position of an image, or drop an earlier one when the new version has none
+pub async fn index(db: &DatabaseConnection, f: &file::Model) -> Result<(), DbErr> {
+    let path = f.storage_path.clone();
+    let position = tokio::task::spawn_blocking(move || read_position(Path::new(&path)))
+        .await
+        .map_err(|e| DbErr::Custom(e.to_string()))?
+        .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let Some((latitude, longitude)) = position else {
+        return remove(db, f.id).await;
+    };
+
+    photo_location::Entity::insert(photo_location::ActiveModel {
+        file_id: Set(f.id),
+        user_id: Set(f.user_id),
+        latitude: Set(latitude),
+        longitude: Set(longitude),
+        updated_at: Set(Utc::now().naive_utc()),
+    })
+    .on_conflict(
+        OnConflict::column(photo_location::Column::FileId)
+            .update_columns([
+                photo_location::Column::Latitude,
+                photo_location::Column::Longitude,
+                photo_location::Column::UpdatedAt,
+            ])
+            .to_owned(),
+    )
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Drop the stored position of a file
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    photo_location::Entity::delete_by_id(file_id)
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Thumbnail embedded in the EXIF data of an image, `None` when there is none
+pub async fn thumbnail(f: &file::Model) -> Option<Vec<u8>> {
+    if !is_image(f) {
+        return None;
+    }
+    let path = f.storage_path.clone();
+    tokio::task::spawn_blocking(move || {
+        read_exif_block(Path::new(&path))
+            .ok()
+            .and_then(|data| exif::thumbnail(&data).map(<[u8]>::to_vec))
+    })
+    .await
+    .ok()?
+}
+
+fn read_position(path: &Path) -> std::io::Result<Option<(f64, f64)>> {
+    Ok(exif::gps_position(&read_exif_block(path)?))
+}
+
+fn read_exif_block(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_EXIF_BYTES)
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
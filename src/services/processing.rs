@@ -0,0 +1,287 @@
+use crate::{
+    config::{Config, ProcessingHook},
+    entities::{file, file_label, file_metadata},
+    services::{
+        job_scheduler::{self, JobClass},
+        quarantine,
+    },
+    utils::{http_cache, http_client},
+};
+use axum::http::{header, HeaderName, Method};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set, TransactionTrait,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+const MAX_LABELS: usize = 50;
+const MAX_LABEL_LEN: usize = 64;
+const MAX_METADATA_ENTRIES: usize = 50;
+const MAX_KEY_LEN: usize = 64;
+const MAX_VALUE_LEN: usize = 4096;
+/// Hook answers are small JSON documents
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Hook metadata of a file, by hook name
+pub type DerivedMetadata = BTreeMap<String, serde_json::Map<String, serde_json::Value>>;
+
+/// What a hook reports for a file
+#[derive(Debug, Default, Deserialize)]
+pub struct HookOutput {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// Set by scanning hooks to the name of the malware found, quarantines the file
+    #[serde(default)]
+    pub threat: Option<String>,
+}
+
+/// Run the hooks matching an uploaded file in the background
+/// Failures are logged per hook, the upload itself has already succeeded
+pub fn spawn(db: &DatabaseConnection, config: &Config, f: &file::Model) {
+    let smtp = config.smtp.clone();
+    let config = &config.processing;
+    let Some(mime_type) = f.mime_type.clone() else {
+        return;
+    };
+    let hooks: Vec<ProcessingHook> = config
+        .hooks
+        .iter()
+        .filter(|h| http_cache::mime_matches(&h.mime, &mime_type))
+        .cloned()
+        .collect();
+    if hooks.is_empty() || f.file_type != "file" {
+        return;
+    }
+    if f.size_bytes.unwrap_or(0) as u64 > config.max_file_size {
+        tracing::debug!(file_id = f.id, "File too large for processing hooks");
+        return;
+    }
+
+    let db = db.clone();
+    let f = f.clone();
+    tokio::spawn(async move {
+        for hook in &hooks {
+            let _permit = job_scheduler::acquire(JobClass::Processing).await;
+            let timeout = Duration::from_secs(hook.timeout_secs);
+            let mut output = match tokio::time::timeout(timeout, run_hook(hook, &f, &mime_type))
+                .await
+            {
+                Ok(Ok(output)) => output,
+                Ok(Err(e)) => {
+                    tracing::warn!(file_id = f.id, hook = %hook.name, error = %e, "Processing hook failed");
+                    continue;
+                }
+                Err(_) => {
+                    tracing::warn!(file_id = f.id, hook = %hook.name, "Processing hook timed out");
+                    continue;
+                }
+            };
+            let threat = output.threat.take();
+            if let Err(e) = store(&db, &f, &hook.name, output).await {
+                tracing::warn!(file_id = f.id, hook = %hook.name, error = %e, "Failed to store processing results");
+            }
+            if let Some(threat) = threat {
+                if let Err(e) = quarantine::quarantine(&db, &smtp, &f, &hook.name, &threat).await {
+                    tracing::warn!(file_id = f.id, hook = %hook.name, error = %e, "Failed to quarantine file");
+                }
+            }
+        }
+    });
+}
+
+async fn run_hook(
+    hook: &ProcessingHook,
+    f: &file::Model,
+    mime_type: &str,
+) -> Result<HookOutput, String> {
+    let raw = if let Some((program, args)) = hook.command.split_first() {
+        run_command(program, args, f, mime_type).await?
+    } else if let Some(url) = &hook.webhook {
+        call_webhook(url, f, mime_type).await?
+    } else {
+        return Err("hook has neither a command nor a webhook".to_string());
+    };
+    serde_json::from_slice(&raw).map_err(|e| format!("invalid hook output: {}", e))
+}
+
+async fn run_command(
+    program: &str,
+    args: &[String],
+    f: &file::Model,
+    mime_type: &str,
+) -> Result<Vec<u8>, String> {
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .arg(&f.storage_path)
+        .env("CLOUD_DRIVE_FILE_ID", f.id.to_string())
+        .env("CLOUD_DRIVE_FILE_NAME", &f.name)
+        .env("CLOUD_DRIVE_MIME_TYPE", mime_type)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        // Dropped with the future when the hook times out or says too much
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // Read one byte past the limit to tell a full answer from a cut off one
+    let mut stdout = Vec::new();
+    if let Some(pipe) = child.stdout.take() {
+        pipe.take(MAX_OUTPUT_BYTES as u64 + 1)
+            .read_to_end(&mut stdout)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if stdout.len() > MAX_OUTPUT_BYTES {
+        return Err("command output is too large".to_string());
+    }
+
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("command exited with {}", status));
+    }
+    Ok(stdout)
+}
+
+/// POST the file content to a plain HTTP endpoint, e.g. a processor on the same host
+async fn call_webhook(url: &str, f: &file::Model, mime_type: &str) -> Result<Vec<u8>, String> {
+    let content = tokio::fs::read(&f.storage_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let headers = [
+        (header::CONTENT_TYPE, mime_type.to_string()),
+        (HeaderName::from_static("x-file-id"), f.id.to_string()),
+        (
+            HeaderName::from_static("x-file-name"),
+            percent_encoding::utf8_percent_encode(&f.name, percent_encoding::NON_ALPHANUMERIC)
+                .to_string(),
+        ),
+    ];
+
+    let (status, body) =
+        http_client::send(Method::POST, url, &headers, content, MAX_OUTPUT_BYTES).await?;
+    if !status.is_success() {
+        return Err(format!("webhook answered {}", status));
+    }
+    Ok(body.to_vec())
+}
+
+/// Replace what the hook produced earlier for the file
+/// Skipped when the file changed meanwhile, the run for the new version stores its own results
+async fn store(
+    db: &DatabaseConnection,
+    f: &file::Model,
+    source: &str,
+    output: HookOutput,
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+
+    let current = file::Entity::find_by_id(f.id).one(&txn).await?;
+    if current.is_none_or(|c| c.version != f.version) {
+        return Ok(());
+    }
+
+    file_label::Entity::delete_many()
+        .filter(file_label::Column::FileId.eq(f.id))
+        .filter(file_label::Column::Source.eq(source))
+        .exec(&txn)
+        .await?;
+    file_metadata::Entity::delete_many()
+        .filter(file_metadata::Column::FileId.eq(f.id))
+        .filter(file_metadata::Column::Source.eq(source))
+        .exec(&txn)
+        .await?;
+
+    let now = Utc::now().naive_utc();
+    let mut labels: Vec<String> = output
+        .labels
+        .iter()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty() && l.chars().count() <= MAX_LABEL_LEN)
+        .collect();
+    labels.sort();
+    labels.dedup();
+    for label in labels.into_iter().take(MAX_LABELS) {
+        file_label::ActiveModel {
+            file_id: Set(f.id),
+            user_id: Set(f.user_id),
+            label: Set(label),
+            source: Set(source.to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+
+    for (key, value) in output.metadata.into_iter().take(MAX_METADATA_ENTRIES) {
+        let value = value.to_string();
+        if key.is_empty() || key.chars().count() > MAX_KEY_LEN || value.len() > MAX_VALUE_LEN {
+            continue;
+        }
+        file_metadata::ActiveModel {
+            file_id: Set(f.id),
+            user_id: Set(f.user_id),
+            source: Set(source.to_string()),
+            key: Set(key),
+            value: Set(value),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+
+    txn.commit().await
+}
+
+/// Labels of a file and the metadata each hook produced for it
+pub async fn for_file(
+    db: &DatabaseConnection,
+    file_id: i32,
+) -> Result<(Vec<String>, DerivedMetadata), DbErr> {
+    let mut labels: Vec<String> = file_label::Entity::find()
+        .filter(file_label::Column::FileId.eq(file_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|l| l.label)
+        .collect();
+    labels.sort();
+    labels.dedup();
+
+    let mut metadata = DerivedMetadata::new();
+    for entry in file_metadata::Entity::find()
+        .filter(file_metadata::Column::FileId.eq(file_id))
+        .all(db)
+        .await?
+    {
+        let value = serde_json::from_str(&entry.value).unwrap_or(serde_json::Value::Null);
+        metadata
+            .entry(entry.source)
+            .or_default()
+            .insert(entry.key, value);
+    }
+
+    Ok((labels, metadata))
+}
+
+/// Drop the labels and metadata of a file
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    file_label::Entity::delete_many()
+        .filter(file_label::Column::FileId.eq(file_id))
+        .exec(db)
+        .await?;
+    file_metadata::Entity::delete_many()
+        .filter(file_metadata::Column::FileId.eq(file_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
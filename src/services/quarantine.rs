@@ -0,0 +1,111 @@
+use crate::{
+    config::SmtpConfig,
+    entities::{file, user},
+    handlers::file::delete_file_record,
+    services::{
+        changes, folder_sizes,
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+
+/// Longest threat name kept from a scanner
+const MAX_THREAT_LEN: usize = 255;
+
+/// Hold a file a scanner flagged and tell its owner
+/// Skipped when the file changed since it was scanned, the new content gets its own scan
+pub async fn quarantine(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    scanned: &file::Model,
+    source: &str,
+    threat: &str,
+) -> Result<(), DbErr> {
+    let threat: String = threat.trim().chars().take(MAX_THREAT_LEN).collect();
+    if threat.is_empty() {
+        return Ok(());
+    }
+    let Some(current) = file::Entity::find_by_id(scanned.id).one(db).await? else {
+        return Ok(());
+    };
+    if current.version != scanned.version || current.quarantined_at.is_some() {
+        return Ok(());
+    }
+
+    let mut active: file::ActiveModel = current.into();
+    active.quarantined_at = Set(Some(Utc::now().naive_utc()));
+    active.quarantine_reason = Set(Some(format!("{} (found by {})", threat, source)));
+    let f = active.update(db).await?;
+    tracing::warn!(file_id = f.id, user_id = f.user_id, threat = %threat, hook = %source, "File quarantined");
+
+    let Some(owner) = user::Entity::find_by_id(f.user_id).one(db).await? else {
+        return Ok(());
+    };
+    let notification = Notification {
+        category: NotificationCategory::Security,
+        title: format!("\"{}\" was quarantined", f.name),
+        body: format!(
+            "{} was flagged as {} and can't be downloaded until an administrator reviews it",
+            f.path, threat
+        ),
+        link: None,
+        email: Some(EmailTemplate::FileQuarantined {
+            username: owner.username.clone(),
+            path: f.path.clone(),
+            threat,
+        }),
+    };
+    notifications::dispatch(db, smtp, &owner, notification).await
+}
+
+/// Every quarantined file, oldest first
+pub async fn list(db: &DatabaseConnection) -> Result<Vec<file::Model>, DbErr> {
+    file::Entity::find()
+        .filter(file::Column::QuarantinedAt.is_not_null())
+        .order_by_asc(file::Column::QuarantinedAt)
+        .all(db)
+        .await
+}
+
+/// Quarantined file with the given ID, `None` when it is missing or not quarantined
+pub async fn find(db: &DatabaseConnection, file_id: i32) -> Result<Option<file::Model>, DbErr> {
+    Ok(file::Entity::find_by_id(file_id)
+        .one(db)
+        .await?
+        .filter(|f| f.quarantined_at.is_some()))
+}
+
+/// Make a quarantined file available again
+pub async fn release(db: &DatabaseConnection, f: file::Model) -> Result<file::Model, DbErr> {
+    let mut active: file::ActiveModel = f.into();
+    active.quarantined_at = Set(None);
+    active.quarantine_reason = Set(None);
+    active.update(db).await
+}
+
+/// Remove a quarantined file and its content, unless another record shares the content
+pub async fn delete(db: &DatabaseConnection, f: &file::Model) -> Result<(), DbErr> {
+    delete_file_record(db, f.id).await?;
+    changes::record(db, f, changes::CHANGE_DELETED, None).await;
+
+    // Copies may share the physical file
+    let remaining = file::Entity::find()
+        .filter(file::Column::StoragePath.eq(&f.storage_path))
+        .one(db)
+        .await?;
+    if remaining.is_none() {
+        if let Err(e) = tokio::fs::remove_file(&f.storage_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(file_id = f.id, error = %e, "Failed to delete physical file");
+            }
+        }
+    }
+
+    folder_sizes::adjust(db, f.user_id, &f.parent_path, -f.size_bytes.unwrap_or(0)).await;
+    Ok(())
+}
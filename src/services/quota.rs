@@ -0,0 +1,174 @@
+use crate::{
+    config::{Config, QuotaConfig},
+    entities::{file, user},
+    services::{
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect,
+};
+
+/// Bytes stored in the files of a user's tree
+pub async fn used_bytes(db: &DatabaseConnection, user_id: i32) -> Result<i64, DbErr> {
+    let used: Option<i64> = file::Entity::find()
+        .select_only()
+        .column_as(Expr::col(file::Column::SizeBytes).sum(), "size")
+        .filter(file::Column::UserId.eq(user_id))
+        .filter(file::Column::FileType.eq("file"))
+        .into_tuple()
+        .one(db)
+        .await?
+        .flatten();
+    Ok(used.unwrap_or(0))
+}
+
+/// Largest usage writes are accepted up to, the quota plus its grace overage
+fn hard_limit(config: &QuotaConfig, quota_bytes: u64) -> u64 {
+    quota_bytes.saturating_add(quota_bytes.saturating_mul(config.grace_percent) / 100)
+}
+
+/// Quota of the user's role, `None` when the user is gone or their storage is unlimited
+async fn quota_of(
+    db: &DatabaseConnection,
+    config: &QuotaConfig,
+    user_id: i32,
+) -> Result<Option<(user::Model, u64)>, DbErr> {
+    let Some(owner) = user::Entity::find_by_id(user_id).one(db).await? else {
+        return Ok(None);
+    };
+    Ok(config.limit_for(&owner.role).map(|quota| (owner, quota)))
+}
+
+/// Usage and quota of the user, `None` when their storage is unlimited
+async fn measure(
+    db: &DatabaseConnection,
+    config: &QuotaConfig,
+    user_id: i32,
+) -> Result<Option<(i64, u64)>, DbErr> {
+    match quota_of(db, config, user_id).await? {
+        Some((_, quota)) => Ok(Some((used_bytes(db, user_id).await?, quota))),
+        None => Ok(None),
+    }
+}
+
+/// Bytes the user may still store before writes are rejected, `None` when unlimited
+pub async fn remaining_bytes(
+    db: &DatabaseConnection,
+    config: &QuotaConfig,
+    user_id: i32,
+) -> Result<Option<u64>, DbErr> {
+    Ok(measure(db, config, user_id)
+        .await?
+        .map(|(used, quota)| hard_limit(config, quota).saturating_sub(used.max(0) as u64)))
+}
+
+/// Check that `bytes` more fit in the quota of the tree's owner, grace overage included
+/// Writes are let through when usage cannot be measured
+pub async fn ensure_room(
+    db: &DatabaseConnection,
+    config: &QuotaConfig,
+    user_id: i32,
+    bytes: i64,
+) -> Result<(), String> {
+    if bytes <= 0 {
+        return Ok(());
+    }
+    let (used, quota) = match measure(db, config, user_id).await {
+        Ok(Some(m)) => m,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            tracing::warn!(user_id = user_id, error = %e, "Failed to measure quota usage");
+            return Ok(());
+        }
+    };
+
+    if (used + bytes) as u64 > hard_limit(config, quota) {
+        return Err(format!(
+            "Storage quota exceeded ({} of {} bytes used)",
+            used, quota
+        ));
+    }
+    Ok(())
+}
+
+/// Notify the owner when a write of `grown_bytes` just crossed a warning threshold
+/// Failures are logged, the write itself already succeeded
+pub async fn warn_if_crossed(
+    db: &DatabaseConnection,
+    config: &Config,
+    user_id: i32,
+    grown_bytes: i64,
+) {
+    if grown_bytes <= 0 {
+        return;
+    }
+    if let Err(e) = warn(db, config, user_id, grown_bytes).await {
+        tracing::warn!(user_id = user_id, error = %e, "Failed to send quota warning");
+    }
+}
+
+async fn warn(
+    db: &DatabaseConnection,
+    config: &Config,
+    user_id: i32,
+    grown_bytes: i64,
+) -> Result<(), DbErr> {
+    let Some((owner, quota)) = quota_of(db, &config.quota, user_id).await? else {
+        return Ok(());
+    };
+    let used = used_bytes(db, user_id).await?;
+    let before = used - grown_bytes;
+
+    // Only the highest threshold crossed is announced
+    let threshold = |percent: u64| quota.saturating_mul(percent) / 100;
+    let Some(percent) = config
+        .quota
+        .warn_percents
+        .iter()
+        .copied()
+        .filter(|&p| p > 0 && before < threshold(p) as i64 && used >= threshold(p) as i64)
+        .max()
+    else {
+        return Ok(());
+    };
+
+    let limit = hard_limit(&config.quota, quota);
+    let body = if used as u64 >= quota && limit > quota {
+        format!(
+            "You are over your storage quota, uploads are rejected once your files take more than {} bytes",
+            limit
+        )
+    } else if used as u64 >= quota {
+        "Your storage is full, new uploads are rejected until you free up space".to_string()
+    } else {
+        format!(
+            "Your files take {} of your {} bytes of storage",
+            used, quota
+        )
+    };
+    let notification = Notification {
+        category: NotificationCategory::Quota,
+        title: format!("You have used {}% of your storage", percent),
+        body,
+        link: None,
+        email: Some(EmailTemplate::QuotaWarning {
+            username: owner.username.clone(),
+            percent,
+            used_bytes: used,
+            quota_bytes: quota,
+            hard_limit_bytes: limit,
+        }),
+    };
+    notifications::dispatch(db, &config.smtp, &owner, notification).await?;
+
+    tracing::info!(
+        user_id = user_id,
+        percent = percent,
+        used_bytes = used,
+        quota_bytes = quota,
+        "Quota warning sent"
+    );
+    Ok(())
+}
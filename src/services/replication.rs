@@ -0,0 +1,182 @@
+use crate::{
+    config::Config,
+    entities::file,
+    services::{
+        deduplication,
+        job_scheduler::{self, JobClass},
+    },
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const STATUS_REPLICATED: &str = "replicated";
+pub const STATUS_FAILED: &str = "failed";
+/// Files outside managed storage, e.g. in external mounts, are not replicated
+pub const STATUS_SKIPPED: &str = "skipped";
+
+/// Start the background task mirroring stored files to the replication target
+pub fn spawn_worker(db: DatabaseConnection, config: Config) {
+    if !config.replication.enabled {
+        return;
+    }
+    let Some(target) = config.replication.target.clone().map(PathBuf::from) else {
+        tracing::warn!("Replication is enabled but no target is configured");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.replication.interval_secs));
+
+        loop {
+            interval.tick().await;
+            match replicate_batch(&db, &config, &target).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(files = n, "Replication round finished"),
+                Err(e) => tracing::warn!(error = %e, "Replication round failed"),
+            }
+        }
+    });
+}
+
+/// Files whose current content has no replica yet, never attempted ones first
+pub fn lagging_condition() -> Condition {
+    Condition::any()
+        .add(file::Column::ReplicationStatus.is_null())
+        .add(file::Column::ReplicationStatus.eq(STATUS_FAILED))
+        .add(
+            Condition::all()
+                .add(file::Column::ReplicationStatus.eq(STATUS_REPLICATED))
+                .add(Expr::col(file::Column::UpdatedAt).gt(Expr::col(file::Column::ReplicatedAt))),
+        )
+}
+
+/// Copy one batch of lagging files, returns how many were handled
+async fn replicate_batch(
+    db: &DatabaseConnection,
+    config: &Config,
+    target: &Path,
+) -> Result<usize, DbErr> {
+    let files = file::Entity::find()
+        .filter(file::Column::FileType.eq("file"))
+        .filter(lagging_condition())
+        .order_by_asc(file::Column::ReplicationStatus)
+        .order_by_asc(file::Column::Id)
+        .limit(config.replication.batch_size)
+        .all(db)
+        .await?;
+
+    let volumes = config.get_storage_volumes();
+    for f in &files {
+        let source = PathBuf::from(&f.storage_path);
+        if !volumes.iter().any(|v| source.starts_with(v)) {
+            mark(db, f, STATUS_SKIPPED, None).await?;
+            continue;
+        }
+
+        let known_hash = f.file_hash.clone();
+        let target = target.to_path_buf();
+        let _permit = job_scheduler::acquire(JobClass::Replication).await;
+        let copied = tokio::task::spawn_blocking(move || copy_blob(&source, known_hash, &target))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e.to_string())));
+
+        match copied {
+            Ok(replica) => mark(db, f, STATUS_REPLICATED, Some(replica)).await?,
+            Err(e) => {
+                tracing::warn!(file_id = f.id, path = %f.storage_path, error = %e, "Replication failed");
+                mark(db, f, STATUS_FAILED, None).await?;
+            }
+        }
+    }
+
+    Ok(files.len())
+}
+
+/// Copy a file to `<target>/<hash prefix>/<hash>`, skipped when that replica already exists
+/// Identical content stored for several files is replicated once
+fn copy_blob(source: &Path, hash: Option<String>, target: &Path) -> std::io::Result<String> {
+    let hash = match hash {
+        Some(h) => h,
+        None => deduplication::calculate_hash_from_file(source)?,
+    };
+    let dir = target.join(&hash[..2.min(hash.len())]);
+    let replica = dir.join(&hash);
+
+    let size = std::fs::metadata(source)?.len();
+    let present = std::fs::metadata(&replica).is_ok_and(|m| m.len() == size);
+    if !present {
+        std::fs::create_dir_all(&dir)?;
+        // Copy next to the replica first so a crash never leaves a truncated replica
+        let partial = dir.join(format!("{}.partial", hash));
+        std::fs::copy(source, &partial)?;
+        std::fs::rename(&partial, &replica)?;
+    }
+
+    Ok(replica.to_string_lossy().replace('\\', "/"))
+}
+
+/// Record the outcome for the version that was copied, a concurrent change keeps the file lagging
+/// The replica path and time are only written on success
+async fn mark(
+    db: &DatabaseConnection,
+    f: &file::Model,
+    status: &str,
+    replica_path: Option<String>,
+) -> Result<(), DbErr> {
+    let mut update =
+        file::Entity::update_many().col_expr(file::Column::ReplicationStatus, Expr::value(status));
+    if let Some(path) = replica_path {
+        update = update
+            .col_expr(file::Column::ReplicaPath, Expr::value(path))
+            .col_expr(
+                file::Column::ReplicatedAt,
+                Expr::value(Utc::now().naive_utc()),
+            );
+    }
+
+    update
+        .filter(file::Column::Id.eq(f.id))
+        .filter(file::Column::Version.eq(f.version))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Replicated files whose replica is no longer on the target, at most `limit` of them
+pub async fn missing_replicas(
+    db: &DatabaseConnection,
+    limit: usize,
+) -> Result<(u64, Vec<file::Model>), DbErr> {
+    let replicas: Vec<(i32, Option<String>)> = file::Entity::find()
+        .select_only()
+        .column(file::Column::Id)
+        .column(file::Column::ReplicaPath)
+        .filter(file::Column::ReplicationStatus.eq(STATUS_REPLICATED))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let missing: Vec<i32> = tokio::task::spawn_blocking(move || {
+        replicas
+            .into_iter()
+            .filter(|(_, path)| path.as_deref().is_none_or(|p| !Path::new(p).is_file()))
+            .map(|(id, _)| id)
+            .collect()
+    })
+    .await
+    .map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let count = missing.len() as u64;
+    let files = file::Entity::find()
+        .filter(file::Column::Id.is_in(missing.into_iter().take(limit)))
+        .order_by_asc(file::Column::Id)
+        .all(db)
+        .await?;
+    Ok((count, files))
+}
@@ -0,0 +1,481 @@
+use crate::{
+    config::SearchConfig,
+    entities::{file, file_change, job},
+    services::{events::EventBus, jobs},
+    utils::http_client,
+};
+use axum::{
+    async_trait,
+    http::{header, Method},
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+const INDEX_BATCH_SIZE: u64 = 500;
+const MAX_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+/// What the search engine knows about a file, results are loaded from the database
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchDocument {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+}
+
+impl From<&file::Model> for SearchDocument {
+    fn from(f: &file::Model) -> Self {
+        Self {
+            id: f.id,
+            user_id: f.user_id,
+            name: f.name.clone(),
+        }
+    }
+}
+
+/// External full-text engine ranking files by name
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Prepare the index, called once before anything is indexed
+    async fn setup(&self) -> Result<(), String>;
+    /// Drop the index with its documents and set it up again, e.g. after its analyzers changed
+    async fn reset(&self) -> Result<(), String>;
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), String>;
+    async fn delete(&self, file_ids: &[i32]) -> Result<(), String>;
+    /// IDs of the user's files best matching `text`, best first
+    async fn search(&self, user_id: i32, text: &str, limit: usize) -> Result<Vec<i32>, String>;
+}
+
+/// Backend selected in the config, `None` when searching stays in the database
+pub fn from_config(config: &SearchConfig) -> Result<Option<Arc<dyn SearchBackend>>, String> {
+    let endpoint = || {
+        config
+            .url
+            .as_deref()
+            .map(|u| Endpoint {
+                url: u.trim_end_matches('/').to_string(),
+                api_key: config.api_key.clone(),
+                index: config.index.clone(),
+            })
+            .ok_or_else(|| format!("search.url is required for the {} backend", config.backend))
+    };
+
+    match config.backend.as_str() {
+        "database" => Ok(None),
+        "meilisearch" => Ok(Some(Arc::new(Meilisearch(endpoint()?)))),
+        "elasticsearch" => Ok(Some(Arc::new(Elasticsearch(endpoint()?)))),
+        other => Err(format!("Unknown search backend '{}'", other)),
+    }
+}
+
+/// Start the background task feeding file changes to the search engine
+/// Every file is indexed again at startup, then the changes feed is followed
+pub fn spawn_indexer(db: DatabaseConnection, backend: Arc<dyn SearchBackend>, interval_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        let mut cursor = None;
+
+        loop {
+            interval.tick().await;
+            let result = match cursor {
+                None => reindex(&db, backend.as_ref()).await.map(|c| {
+                    cursor = Some(c);
+                }),
+                Some(since) => sync_changes(&db, backend.as_ref(), since).await.map(|c| {
+                    cursor = Some(c);
+                }),
+            };
+            if let Err(e) = result {
+                tracing::warn!(error = %e, "Search indexing failed");
+            }
+        }
+    });
+}
+
+/// Index every file, returns the changes cursor to follow from
+async fn reindex(db: &DatabaseConnection, backend: &dyn SearchBackend) -> Result<i32, String> {
+    backend.setup().await?;
+    let cursor = latest_change(db).await?;
+    let total = index_all(db, backend, |_| {}).await?;
+
+    tracing::info!(files = total, "Search index rebuilt");
+    Ok(cursor)
+}
+
+/// Record a `search_reindex` job and rebuild the index from the files table in the background
+/// Searches fall short until the job is done, the indexer keeps following the changes feed
+pub async fn start_rebuild(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    backend: Arc<dyn SearchBackend>,
+    user_id: i32,
+) -> Result<job::Model, DbErr> {
+    let total = file::Entity::find().count(db).await?;
+    let job = jobs::create(
+        db,
+        events,
+        user_id,
+        jobs::KIND_SEARCH_REINDEX,
+        jobs::STATUS_RUNNING,
+        total as i64,
+        0,
+    )
+    .await?;
+
+    let db = db.clone();
+    let mut progress = jobs::ProgressReporter::new(events, job.clone());
+    tokio::spawn(async move {
+        let result = async {
+            backend.reset().await?;
+            index_all(&db, backend.as_ref(), |n| progress.advance(n as i64, 0)).await
+        }
+        .await;
+        let result = match result {
+            Ok(total) => {
+                tracing::info!(files = total, "Search index rebuilt");
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Search index rebuild failed");
+                Err(e)
+            }
+        };
+        progress.finish(&db, result).await;
+    });
+
+    Ok(job)
+}
+
+/// Upsert every file in batches, `on_batch` is told how many were sent each time
+async fn index_all(
+    db: &DatabaseConnection,
+    backend: &dyn SearchBackend,
+    mut on_batch: impl FnMut(usize),
+) -> Result<usize, String> {
+    let mut after = 0;
+    let mut total = 0;
+    loop {
+        let files = file::Entity::find()
+            .filter(file::Column::Id.gt(after))
+            .order_by_asc(file::Column::Id)
+            .limit(INDEX_BATCH_SIZE)
+            .all(db)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(last) = files.last() else { break };
+        after = last.id;
+        total += files.len();
+
+        let documents: Vec<SearchDocument> = files.iter().map(SearchDocument::from).collect();
+        backend.upsert(&documents).await?;
+        on_batch(files.len());
+    }
+    Ok(total)
+}
+
+async fn latest_change(db: &DatabaseConnection) -> Result<i32, String> {
+    let latest: Option<i32> = file_change::Entity::find()
+        .select_only()
+        .column(file_change::Column::Id)
+        .order_by_desc(file_change::Column::Id)
+        .into_tuple()
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(latest.unwrap_or(0))
+}
+
+/// Push the files changed after `since`, returns the new cursor
+async fn sync_changes(
+    db: &DatabaseConnection,
+    backend: &dyn SearchBackend,
+    since: i32,
+) -> Result<i32, String> {
+    let entries = file_change::Entity::find()
+        .filter(file_change::Column::Id.gt(since))
+        .order_by_asc(file_change::Column::Id)
+        .limit(INDEX_BATCH_SIZE)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(last) = entries.last() else {
+        return Ok(since);
+    };
+    let cursor = last.id;
+
+    let file_ids: BTreeSet<i32> = entries.iter().map(|c| c.file_id).collect();
+    let files = file::Entity::find()
+        .filter(file::Column::Id.is_in(file_ids.iter().copied()))
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Files no longer in the database were deleted
+    let present: BTreeSet<i32> = files.iter().map(|f| f.id).collect();
+    let gone: Vec<i32> = file_ids.difference(&present).copied().collect();
+
+    if !files.is_empty() {
+        let documents: Vec<SearchDocument> = files.iter().map(SearchDocument::from).collect();
+        backend.upsert(&documents).await?;
+    }
+    backend.delete(&gone).await?;
+
+    Ok(cursor)
+}
+
+struct Endpoint {
+    url: String,
+    api_key: Option<String>,
+    index: String,
+}
+
+impl Endpoint {
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: Vec<u8>,
+        auth_scheme: &str,
+    ) -> Result<Value, String> {
+        let mut headers = vec![(header::CONTENT_TYPE, content_type.to_string())];
+        if let Some(key) = &self.api_key {
+            headers.push((header::AUTHORIZATION, format!("{} {}", auth_scheme, key)));
+        }
+        let url = format!("{}{}", self.url, path);
+        let (status, body) =
+            http_client::send(method, &url, &headers, body, MAX_RESPONSE_BYTES).await?;
+        if !status.is_success() {
+            return Err(format!(
+                "{} answered {}: {}",
+                path,
+                status,
+                String::from_utf8_lossy(&body)
+            ));
+        }
+        if body.is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_slice(&body).map_err(|e| e.to_string())
+    }
+}
+
+/// Meilisearch, typo tolerant out of the box
+struct Meilisearch(Endpoint);
+
+impl Meilisearch {
+    async fn call(&self, method: Method, path: &str, body: Value) -> Result<Value, String> {
+        let path = format!("/indexes/{}{}", self.0.index, path);
+        self.0
+            .request(
+                method,
+                &path,
+                "application/json",
+                body.to_string().into_bytes(),
+                "Bearer",
+            )
+            .await
+    }
+}
+
+#[async_trait]
+impl SearchBackend for Meilisearch {
+    async fn setup(&self) -> Result<(), String> {
+        let settings = json!({
+            "searchableAttributes": ["name"],
+            "filterableAttributes": ["user_id"],
+        });
+        // Creates the index when missing, settings are applied asynchronously
+        self.call(Method::PATCH, "/settings", settings).await?;
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<(), String> {
+        // Tasks run in order, the index is gone before the settings recreate it
+        let path = format!("/indexes/{}", self.0.index);
+        self.0
+            .request(
+                Method::DELETE,
+                &path,
+                "application/json",
+                Vec::new(),
+                "Bearer",
+            )
+            .await?;
+        self.setup().await
+    }
+
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), String> {
+        let body = serde_json::to_value(documents).map_err(|e| e.to_string())?;
+        self.call(Method::POST, "/documents?primaryKey=id", body)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, file_ids: &[i32]) -> Result<(), String> {
+        if file_ids.is_empty() {
+            return Ok(());
+        }
+        self.call(Method::POST, "/documents/delete-batch", json!(file_ids))
+            .await?;
+        Ok(())
+    }
+
+    async fn search(&self, user_id: i32, text: &str, limit: usize) -> Result<Vec<i32>, String> {
+        let query = json!({
+            "q": text,
+            "filter": format!("user_id = {}", user_id),
+            "limit": limit,
+            "attributesToRetrieve": ["id"],
+        });
+        let result = self.call(Method::POST, "/search", query).await?;
+        Ok(result["hits"]
+            .as_array()
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|h| h["id"].as_i64())
+                    .map(|id| id as i32)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// Elasticsearch or OpenSearch, with fuzzy matching on the name
+struct Elasticsearch(Endpoint);
+
+impl Elasticsearch {
+    async fn bulk(&self, lines: Vec<Value>) -> Result<(), String> {
+        let mut body = String::new();
+        for line in lines {
+            body.push_str(&line.to_string());
+            body.push('\n');
+        }
+        let result = self
+            .0
+            .request(
+                Method::POST,
+                "/_bulk",
+                "application/x-ndjson",
+                body.into_bytes(),
+                "ApiKey",
+            )
+            .await?;
+        if result["errors"].as_bool() == Some(true) {
+            return Err("bulk request had failed items".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SearchBackend for Elasticsearch {
+    async fn setup(&self) -> Result<(), String> {
+        let path = format!("/{}", self.0.index);
+        let exists = self
+            .0
+            .request(Method::GET, &path, "application/json", Vec::new(), "ApiKey")
+            .await
+            .is_ok();
+        if !exists {
+            let mappings = json!({
+                "mappings": {
+                    "properties": {
+                        "user_id": { "type": "integer" },
+                        "name": { "type": "text" },
+                    }
+                }
+            });
+            self.0
+                .request(
+                    Method::PUT,
+                    &path,
+                    "application/json",
+                    mappings.to_string().into_bytes(),
+                    "ApiKey",
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn reset(&self) -> Result<(), String> {
+        // Mappings cannot change in place, so the index is created again
+        let path = format!("/{}", self.0.index);
+        let exists = self
+            .0
+            .request(Method::GET, &path, "application/json", Vec::new(), "ApiKey")
+            .await
+            .is_ok();
+        if exists {
+            self.0
+                .request(
+                    Method::DELETE,
+                    &path,
+                    "application/json",
+                    Vec::new(),
+                    "ApiKey",
+                )
+                .await?;
+        }
+        self.setup().await
+    }
+
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), String> {
+        let mut lines = Vec::with_capacity(documents.len() * 2);
+        for doc in documents {
+            lines.push(json!({ "index": { "_index": self.0.index, "_id": doc.id.to_string() } }));
+            lines.push(serde_json::to_value(doc).map_err(|e| e.to_string())?);
+        }
+        self.bulk(lines).await
+    }
+
+    async fn delete(&self, file_ids: &[i32]) -> Result<(), String> {
+        if file_ids.is_empty() {
+            return Ok(());
+        }
+        let lines = file_ids
+            .iter()
+            .map(|id| json!({ "delete": { "_index": self.0.index, "_id": id.to_string() } }))
+            .collect();
+        self.bulk(lines).await
+    }
+
+    async fn search(&self, user_id: i32, text: &str, limit: usize) -> Result<Vec<i32>, String> {
+        let query = json!({
+            "size": limit,
+            "_source": false,
+            "query": {
+                "bool": {
+                    "must": { "match": { "name": { "query": text, "fuzziness": "AUTO" } } },
+                    "filter": { "term": { "user_id": user_id } },
+                }
+            }
+        });
+        let path = format!("/{}/_search", self.0.index);
+        let result = self
+            .0
+            .request(
+                Method::POST,
+                &path,
+                "application/json",
+                query.to_string().into_bytes(),
+                "ApiKey",
+            )
+            .await?;
+        Ok(result["hits"]["hits"]
+            .as_array()
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|h| h["_id"].as_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    constants::ROLE_LIBRARY,
+    entities::{file, user},
+    services::{
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+    AppState,
+};
+use sea_orm::{DbErr, EntityTrait};
+
+/// Human-readable access level of a grant, `None` when it grants nothing
+pub fn access_level(can_read: bool, can_write: bool, can_delete: bool) -> Option<String> {
+    let parts: Vec<&str> = [
+        (can_read, "read"),
+        (can_write, "write"),
+        (can_delete, "delete"),
+    ]
+    .into_iter()
+    .filter_map(|(granted, name)| granted.then_some(name))
+    .collect();
+    match parts.as_slice() {
+        [] => None,
+        [only] => Some(only.to_string()),
+        [first @ .., last] => Some(format!("{} and {}", first.join(", "), last)),
+    }
+}
+
+/// Tell a user about a permission just created for them on a file
+/// Delivered through the channels the recipient chose for shares, failures are only logged
+pub async fn notify_granted(
+    state: &AppState,
+    granted_by: i32,
+    recipient_id: i32,
+    file_id: i32,
+    grant: (bool, bool, bool),
+) {
+    if granted_by == recipient_id {
+        return;
+    }
+    let Some(access) = access_level(grant.0, grant.1, grant.2) else {
+        return;
+    };
+    if let Err(e) = notify(state, granted_by, recipient_id, file_id, access).await {
+        tracing::warn!(user_id = recipient_id, file_id = file_id, error = %e, "Failed to send share notification");
+    }
+}
+
+async fn notify(
+    state: &AppState,
+    granted_by: i32,
+    recipient_id: i32,
+    file_id: i32,
+    access: String,
+) -> Result<(), DbErr> {
+    let Some(recipient) = user::Entity::find_by_id(recipient_id)
+        .one(&state.db)
+        .await?
+        .filter(|u| u.role != ROLE_LIBRARY)
+    else {
+        return Ok(());
+    };
+    let Some(item) = file::Entity::find_by_id(file_id).one(&state.db).await? else {
+        return Ok(());
+    };
+    let shared_by = user::Entity::find_by_id(granted_by)
+        .one(&state.db)
+        .await?
+        .map(|u| u.username)
+        .unwrap_or_else(|| "An administrator".to_string());
+
+    let notification = Notification {
+        category: NotificationCategory::Share,
+        title: format!("{} shared \"{}\" with you", shared_by, item.name),
+        body: format!("You have {} access to {}", access, item.path),
+        link: None,
+        email: Some(EmailTemplate::ShareNotification {
+            username: recipient.username.clone(),
+            shared_by,
+            item_name: item.name,
+            access,
+            link: Some(state.config.server.public_base_url()),
+        }),
+    };
+    notifications::dispatch(&state.db, &state.config.smtp, &recipient, notification).await?;
+
+    tracing::info!(
+        user_id = recipient_id,
+        file_id = file_id,
+        "Share notification sent"
+    );
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use crate::{
+    config::Config,
+    entities::job,
+    models::admin::{StagingAreaUsage, StagingUsage},
+    services::{disk_space, jobs, upload_sessions},
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Generated batch-download archives
+pub const AREA_ARCHIVES: &str = "archives";
+/// Partial chunked uploads
+pub const AREA_UPLOADS: &str = "uploads";
+/// Intermediate files of format conversions
+pub const AREA_CONVERSIONS: &str = "conversions";
+
+const AREAS: [&str; 3] = [AREA_ARCHIVES, AREA_UPLOADS, AREA_CONVERSIONS];
+
+/// Usage above this share of `max_bytes` is logged as a warning
+const WARN_USAGE_PERCENTAGE: f64 = 90.0;
+
+/// Directory of one staging area
+pub fn area_dir(config: &Config, area: &str) -> PathBuf {
+    config.get_staging_dir().join(area)
+}
+
+/// Walk the staging directory and sum up what each area holds
+pub fn usage(config: &Config) -> std::io::Result<StagingUsage> {
+    let root = config.get_staging_dir();
+    let mut areas = Vec::with_capacity(AREAS.len());
+    for area in AREAS {
+        let (used_bytes, file_count) = dir_size(&root.join(area))?;
+        areas.push(StagingAreaUsage {
+            name: area.to_string(),
+            used_bytes,
+            file_count,
+        });
+    }
+
+    let used_bytes = areas.iter().map(|a| a.used_bytes).sum();
+    let max_bytes = config.staging.max_bytes;
+    Ok(StagingUsage {
+        dir: root.to_string_lossy().to_string(),
+        used_bytes,
+        file_count: areas.iter().map(|a| a.file_count).sum(),
+        max_bytes,
+        usage_percentage: if max_bytes > 0 {
+            used_bytes as f64 * 100.0 / max_bytes as f64
+        } else {
+            0.0
+        },
+        disk_available_bytes: disk_space::available_space_blocking(&root),
+        areas,
+    })
+}
+
+/// Check that `bytes` more of temporary data fit within the quota and the free disk space
+pub async fn ensure_space(config: &Config, bytes: u64) -> Result<(), String> {
+    let staging_config = config.clone();
+    let current = match tokio::task::spawn_blocking(move || usage(&staging_config)).await {
+        Ok(Ok(u)) => u,
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Failed to measure staging usage");
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Staging usage task failed");
+            return Ok(());
+        }
+    };
+
+    if current.used_bytes + bytes > config.staging.max_bytes {
+        return Err(format!(
+            "Temporary storage is full ({} of {} bytes used)",
+            current.used_bytes, config.staging.max_bytes
+        ));
+    }
+    disk_space::ensure_free_space(
+        &config.get_staging_dir(),
+        bytes,
+        config.staging.min_free_bytes,
+    )
+    .await
+}
+
+/// Start the background task that removes stale staging artifacts
+pub fn spawn_sweeper(db: DatabaseConnection, config: Config) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.staging.sweep_interval_secs));
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep(&db, &config).await {
+                tracing::warn!(error = %e, "Staging sweep failed");
+            }
+        }
+    });
+}
+
+async fn sweep(db: &DatabaseConnection, config: &Config) -> Result<(), DbErr> {
+    let expired = upload_sessions::remove_expired(db).await?;
+    if expired > 0 {
+        tracing::info!(sessions = expired, "Removed expired upload sessions");
+    }
+
+    // Archives of completed jobs are removed by their TTL, not by age
+    let kept: HashSet<PathBuf> = job::Entity::find()
+        .select_only()
+        .column(job::Column::ResultPath)
+        .filter(job::Column::Status.eq(jobs::STATUS_COMPLETED))
+        .filter(job::Column::ResultPath.is_not_null())
+        .into_tuple::<Option<String>>()
+        .all(db)
+        .await?
+        .into_iter()
+        .flatten()
+        .map(PathBuf::from)
+        .collect();
+
+    let config = config.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let stale_after = Duration::from_secs(config.staging.stale_after_secs);
+        let root = config.get_staging_dir();
+        let mut removed = (0u64, 0u64);
+        for area in AREAS {
+            remove_stale(&root.join(area), stale_after, &kept, &mut removed);
+        }
+        (removed, usage(&config), config.staging.max_bytes)
+    })
+    .await;
+
+    let ((removed_files, removed_bytes), usage, max_bytes) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!(error = %e, "Staging sweep task failed");
+            return Ok(());
+        }
+    };
+
+    if removed_files > 0 {
+        tracing::info!(
+            files = removed_files,
+            bytes = removed_bytes,
+            "Removed stale staging artifacts"
+        );
+    }
+
+    match usage {
+        Ok(u) if u.usage_percentage >= WARN_USAGE_PERCENTAGE => tracing::warn!(
+            used_bytes = u.used_bytes,
+            max_bytes = max_bytes,
+            "Staging directory is almost full"
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "Failed to measure staging usage"),
+    }
+
+    Ok(())
+}
+
+/// Delete files under `dir` not modified within `stale_after`, then empty subdirectories
+fn remove_stale(
+    dir: &Path,
+    stale_after: Duration,
+    kept: &HashSet<PathBuf>,
+    removed: &mut (u64, u64),
+) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        if metadata.is_dir() {
+            remove_stale(&path, stale_after, kept, removed);
+            let _ = std::fs::remove_dir(&path); // only succeeds when empty
+            continue;
+        }
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .unwrap_or_default();
+        let normalized = PathBuf::from(path.to_string_lossy().replace('\\', "/"));
+        if age < stale_after || kept.contains(&normalized) {
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                removed.0 += 1;
+                removed.1 += metadata.len();
+            }
+            Err(e) => tracing::warn!(path = ?path, error = %e, "Failed to remove stale artifact"),
+        }
+    }
+}
+
+/// Total size and number of files below `dir` (0 when it does not exist)
+fn dir_size(dir: &Path) -> std::io::Result<(u64, u64)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e),
+    };
+
+    let mut total = (0, 0);
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            let (bytes, files) = dir_size(&entry.path())?;
+            total.0 += bytes;
+            total.1 += files;
+        } else {
+            total.0 += metadata.len();
+            total.1 += 1;
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,89 @@
+use crate::entities::file_star;
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use std::collections::HashSet;
+
+/// Which of `file_ids` a user starred
+pub async fn starred_among<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    file_ids: &[i32],
+) -> Result<HashSet<i32>, DbErr> {
+    if file_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    Ok(file_star::Entity::find()
+        .select_only()
+        .column(file_star::Column::FileId)
+        .filter(file_star::Column::UserId.eq(user_id))
+        .filter(file_star::Column::FileId.is_in(file_ids.iter().copied()))
+        .into_tuple::<i32>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// Files a user starred, most recently starred first
+pub async fn recent<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    limit: u64,
+) -> Result<Vec<i32>, DbErr> {
+    file_star::Entity::find()
+        .select_only()
+        .column(file_star::Column::FileId)
+        .filter(file_star::Column::UserId.eq(user_id))
+        .order_by_desc(file_star::Column::CreatedAt)
+        .order_by_desc(file_star::Column::Id)
+        .limit(limit)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+/// Star or unstar a file for a user, starring twice keeps the first date
+pub async fn set<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    file_id: i32,
+    starred: bool,
+) -> Result<(), DbErr> {
+    if !starred {
+        file_star::Entity::delete_many()
+            .filter(file_star::Column::UserId.eq(user_id))
+            .filter(file_star::Column::FileId.eq(file_id))
+            .exec(db)
+            .await?;
+        return Ok(());
+    }
+
+    file_star::Entity::insert(file_star::ActiveModel {
+        file_id: Set(file_id),
+        user_id: Set(user_id),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([file_star::Column::FileId, file_star::Column::UserId])
+            .do_nothing()
+            .to_owned(),
+    )
+    .do_nothing()
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Drop every user's star of a file
+pub async fn remove<C: ConnectionTrait>(db: &C, file_id: i32) -> Result<(), DbErr> {
+    file_star::Entity::delete_many()
+        .filter(file_star::Column::FileId.eq(file_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::config::Config;
+use crate::utils::timestamp;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Canary object written to each volume root by the probe
+const CANARY_PREFIX: &str = ".health-canary-";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeHealth {
+    pub volume: String,
+    pub healthy: bool,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+static STATUS: RwLock<Vec<(PathBuf, VolumeHealth)>> = RwLock::new(Vec::new());
+
+/// Probe all storage volumes periodically, after the startup round of `check_all`
+pub fn spawn_prober(config: Config) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(
+            config.storage.health_check_interval_secs,
+        ));
+        // The first round runs at startup
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            check_all(&config).await;
+        }
+    });
+}
+
+/// Write, read back and delete a canary object on every volume and record the outcome
+pub async fn check_all(config: &Config) {
+    let mut results = Vec::new();
+    for volume in config.get_storage_volumes() {
+        let probed = volume.clone();
+        let outcome = tokio::task::spawn_blocking(move || probe(&probed))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e.to_string())));
+
+        let previously_healthy = is_healthy(&volume);
+        match &outcome {
+            Ok(()) if !previously_healthy => {
+                tracing::info!(volume = ?volume, "Storage volume recovered")
+            }
+            Err(e) if previously_healthy => {
+                tracing::error!(volume = ?volume, error = %e, "Storage volume health check failed")
+            }
+            _ => {}
+        }
+
+        results.push((
+            volume.clone(),
+            VolumeHealth {
+                volume: volume.to_string_lossy().replace('\\', "/"),
+                healthy: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+                checked_at: timestamp::now(),
+            },
+        ));
+    }
+
+    if let Ok(mut status) = STATUS.write() {
+        *status = results;
+    }
+}
+
+fn probe(volume: &Path) -> std::io::Result<()> {
+    let canary = volume.join(format!("{}{}", CANARY_PREFIX, uuid::Uuid::new_v4()));
+    let payload = canary.to_string_lossy().into_owned().into_bytes();
+
+    std::fs::write(&canary, &payload)?;
+    let read = std::fs::read(&canary);
+    let removed = std::fs::remove_file(&canary);
+    if read? != payload {
+        return Err(std::io::Error::other("canary read back different content"));
+    }
+    removed
+}
+
+/// Whether the last probe of a volume succeeded, volumes not probed yet count as healthy
+pub fn is_healthy(volume: &Path) -> bool {
+    STATUS
+        .read()
+        .map(|status| {
+            status
+                .iter()
+                .find(|(v, _)| v == volume)
+                .is_none_or(|(_, h)| h.healthy)
+        })
+        .unwrap_or(true)
+}
+
+/// Last probe result of every volume
+pub fn snapshot() -> Vec<VolumeHealth> {
+    STATUS
+        .read()
+        .map(|status| status.iter().map(|(_, h)| h.clone()).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,269 @@
+use crate::{
+    entities::{file, job, user},
+    services::{
+        deduplication,
+        events::EventBus,
+        job_scheduler::{self, JobClass},
+        jobs, user_cache,
+    },
+    utils::file_utils,
+};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use std::path::{Path, PathBuf};
+
+/// A stored file or folder and where it goes
+struct Entry {
+    id: i32,
+    user_id: i32,
+    is_folder: bool,
+    size: i64,
+    version: i32,
+    old_path: String,
+    new_path: PathBuf,
+}
+
+/// Everything a migration will move, collected before it starts
+pub struct MigrationPlan {
+    root: PathBuf,
+    destination: PathBuf,
+    entries: Vec<Entry>,
+    pub total_bytes: i64,
+}
+
+impl MigrationPlan {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Collect the rows stored below `root` (or below one user's directory)
+/// Each keeps its path relative to the storage root under `destination`
+pub async fn plan(
+    db: &DatabaseConnection,
+    root: &Path,
+    destination: &Path,
+    user_id: Option<i32>,
+) -> Result<MigrationPlan, DbErr> {
+    let source = match user_id {
+        Some(id) => file_utils::get_user_storage_path(root, id),
+        None => root.to_path_buf(),
+    };
+
+    let mut query = file::Entity::find();
+    if let Some(id) = user_id {
+        query = query.filter(file::Column::UserId.eq(id));
+    }
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for f in query.all(db).await? {
+        let old = PathBuf::from(&f.storage_path);
+        // Rows already stored elsewhere, e.g. by an earlier migration, stay where they are
+        let relative = match old.strip_prefix(root) {
+            Ok(rel) if old.starts_with(&source) => rel.to_path_buf(),
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+
+        entries.push(Entry {
+            id: f.id,
+            user_id: f.user_id,
+            is_folder: f.file_type == "folder",
+            size: f.size_bytes.unwrap_or(0),
+            version: f.version,
+            old_path: f.storage_path,
+            new_path: destination.join(relative),
+        });
+    }
+
+    if skipped > 0 {
+        tracing::info!(
+            skipped = skipped,
+            "Rows outside the storage root are left in place"
+        );
+    }
+
+    let total_bytes = entries
+        .iter()
+        .filter(|e| !e.is_folder)
+        .map(|e| e.size)
+        .sum();
+
+    Ok(MigrationPlan {
+        root: root.to_path_buf(),
+        destination: destination.to_path_buf(),
+        entries,
+        total_bytes,
+    })
+}
+
+/// Record a `storage_migration` job and run the plan in the background
+pub async fn start(
+    db: &DatabaseConnection,
+    events: &EventBus,
+    user_id: i32,
+    plan: MigrationPlan,
+) -> Result<job::Model, DbErr> {
+    let job = jobs::create(
+        db,
+        events,
+        user_id,
+        jobs::KIND_STORAGE_MIGRATION,
+        jobs::STATUS_RUNNING,
+        plan.len() as i64,
+        plan.total_bytes,
+    )
+    .await?;
+
+    let db = db.clone();
+    let mut progress = jobs::ProgressReporter::new(events, job.clone());
+    tokio::spawn(async move {
+        let result = run(&db, &plan, &mut progress).await;
+        match &result {
+            Ok(()) => tracing::info!(
+                destination = ?plan.destination,
+                files = plan.len(),
+                "Storage migration finished"
+            ),
+            Err(e) => tracing::error!(error = %e, "Storage migration failed"),
+        }
+        progress.finish(&db, result).await;
+    });
+
+    Ok(job)
+}
+
+/// Copy and verify every file, switch the rows over in one transaction, then drop the old copies
+/// A failed run leaves all rows and old files untouched and can simply be started again
+async fn run(
+    db: &DatabaseConnection,
+    plan: &MigrationPlan,
+    progress: &mut jobs::ProgressReporter,
+) -> Result<(), String> {
+    for entry in &plan.entries {
+        let old = PathBuf::from(&entry.old_path);
+        let new = entry.new_path.clone();
+        let copied = if entry.is_folder {
+            tokio::fs::create_dir_all(&new)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", new.display(), e))
+        } else {
+            let _permit = job_scheduler::acquire(JobClass::Hashing).await;
+            tokio::task::spawn_blocking(move || copy_verified(&old, &new))
+                .await
+                .map_err(|e| e.to_string())?
+        };
+        copied?;
+        progress.advance(1, if entry.is_folder { 0 } else { entry.size });
+    }
+
+    let moved = switch_rows(db, plan)
+        .await
+        .map_err(|e| format!("Failed to update storage paths: {}", e))?;
+
+    let mut old_dirs = Vec::new();
+    for (entry, was_moved) in plan.entries.iter().zip(&moved) {
+        let old = PathBuf::from(&entry.old_path);
+        if !*was_moved {
+            // The row changed while copying, it keeps its old file
+            if !entry.is_folder {
+                let _ = tokio::fs::remove_file(&entry.new_path).await;
+            }
+            continue;
+        }
+
+        if entry.is_folder {
+            old_dirs.push(old.clone());
+        } else if let Err(e) = tokio::fs::remove_file(&old).await {
+            tracing::warn!(path = ?old, error = %e, "Failed to remove migrated file");
+        }
+        old_dirs.extend(
+            old.ancestors()
+                .skip(1)
+                .take_while(|p| *p != plan.root && p.starts_with(&plan.root))
+                .map(Path::to_path_buf),
+        );
+    }
+
+    // Deepest directories first, only empty ones can be removed
+    old_dirs.sort();
+    old_dirs.dedup();
+    old_dirs.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
+    for dir in old_dirs {
+        let _ = tokio::fs::remove_dir(&dir).await;
+    }
+
+    Ok(())
+}
+
+/// Copy a file unless an identical copy is already there and compare the hashes of both sides
+fn copy_verified(old: &Path, new: &Path) -> Result<(), String> {
+    let source_hash = deduplication::calculate_hash_from_file(old)
+        .map_err(|e| format!("Failed to read {}: {}", old.display(), e))?;
+
+    // Left over by an earlier, interrupted run
+    if deduplication::calculate_hash_from_file(new).ok().as_ref() == Some(&source_hash) {
+        return Ok(());
+    }
+
+    if let Some(parent) = new.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::copy(old, new).map_err(|e| format!("Failed to copy {}: {}", old.display(), e))?;
+
+    let copied_hash = deduplication::calculate_hash_from_file(new)
+        .map_err(|e| format!("Failed to read {}: {}", new.display(), e))?;
+    if copied_hash != source_hash {
+        let _ = std::fs::remove_file(new);
+        return Err(format!("Hash mismatch after copying {}", old.display()));
+    }
+    Ok(())
+}
+
+/// Point every row at its new location, rows modified meanwhile are left alone
+/// The migrated users get the destination as their volume for new files
+async fn switch_rows(db: &DatabaseConnection, plan: &MigrationPlan) -> Result<Vec<bool>, DbErr> {
+    let txn = db.begin().await?;
+    let mut moved = Vec::with_capacity(plan.entries.len());
+    for entry in &plan.entries {
+        let result = file::Entity::update_many()
+            .col_expr(
+                file::Column::StoragePath,
+                Expr::value(entry.new_path.to_string_lossy().replace('\\', "/")),
+            )
+            .filter(file::Column::Id.eq(entry.id))
+            .filter(file::Column::StoragePath.eq(&entry.old_path))
+            .filter(file::Column::Version.eq(entry.version))
+            .exec(&txn)
+            .await?;
+        moved.push(result.rows_affected == 1);
+    }
+
+    let mut user_ids: Vec<i32> = plan.entries.iter().map(|e| e.user_id).collect();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    user::Entity::update_many()
+        .col_expr(
+            user::Column::StorageRoot,
+            Expr::value(plan.destination.to_string_lossy().replace('\\', "/")),
+        )
+        .filter(user::Column::Id.is_in(user_ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+    for user_id in user_ids {
+        user_cache::invalidate(user_id);
+    }
+    Ok(moved)
+}
@@ -0,0 +1,82 @@
+use crate::entities::{file, file_label};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::collections::BTreeSet;
+
+/// Source of the labels users attach themselves, processing hooks record their own name
+pub const SOURCE_USER: &str = "user";
+/// Tags users can attach to one file
+pub const MAX_TAGS_PER_FILE: usize = 50;
+const MAX_TAG_LEN: usize = 64;
+
+/// Lowercase, trimmed and deduplicated tags
+pub fn normalize(tags: &[String]) -> Result<BTreeSet<String>, String> {
+    let mut normalized = BTreeSet::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!("Tags can have at most {} characters", MAX_TAG_LEN));
+        }
+        normalized.insert(tag);
+    }
+    Ok(normalized)
+}
+
+/// Attach and detach user tags of a file, the tags of processing hooks stay
+/// Returns whether anything changed, `Err` with a reason when the file would get too many tags
+pub async fn apply<C: ConnectionTrait>(
+    db: &C,
+    f: &file::Model,
+    add: &BTreeSet<String>,
+    remove: &BTreeSet<String>,
+) -> Result<Result<bool, String>, DbErr> {
+    let current: BTreeSet<String> = file_label::Entity::find()
+        .filter(file_label::Column::FileId.eq(f.id))
+        .filter(file_label::Column::Source.eq(SOURCE_USER))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|l| l.label)
+        .collect();
+
+    let added: Vec<&String> = add
+        .iter()
+        .filter(|t| !current.contains(*t) && !remove.contains(*t))
+        .collect();
+    let removed: Vec<&String> = remove.iter().filter(|t| current.contains(*t)).collect();
+    if current.len() + added.len() - removed.len() > MAX_TAGS_PER_FILE {
+        return Ok(Err(format!(
+            "A file can have at most {} tags",
+            MAX_TAGS_PER_FILE
+        )));
+    }
+
+    if !removed.is_empty() {
+        file_label::Entity::delete_many()
+            .filter(file_label::Column::FileId.eq(f.id))
+            .filter(file_label::Column::Source.eq(SOURCE_USER))
+            .filter(file_label::Column::Label.is_in(removed.iter().map(|t| t.as_str())))
+            .exec(db)
+            .await?;
+    }
+    let now = Utc::now().naive_utc();
+    for tag in &added {
+        file_label::ActiveModel {
+            file_id: Set(f.id),
+            user_id: Set(f.user_id),
+            label: Set(tag.to_string()),
+            source: Set(SOURCE_USER.to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(Ok(!added.is_empty() || !removed.is_empty()))
+}
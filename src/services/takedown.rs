@@ -0,0 +1,213 @@
+use crate::{
+    config::SmtpConfig,
+    constants::ROLE_ADMIN,
+    entities::{file, takedown, user},
+    services::{
+        mailer::EmailTemplate,
+        notifications::{self, Notification, NotificationCategory},
+    },
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
+
+/// The file is down, the owner may dispute it
+pub const STATUS_ACTIVE: &str = "active";
+/// The owner disputed the takedown, waiting for an administrator
+pub const STATUS_COUNTERED: &str = "countered";
+/// The file was made available again
+pub const STATUS_RESTORED: &str = "restored";
+/// The takedown stands after review
+pub const STATUS_UPHELD: &str = "upheld";
+
+pub const STATUSES: [&str; 4] = [
+    STATUS_ACTIVE,
+    STATUS_COUNTERED,
+    STATUS_RESTORED,
+    STATUS_UPHELD,
+];
+
+/// Whether the takedown still keeps the file down and waits for a decision
+pub fn is_open(t: &takedown::Model) -> bool {
+    t.status == STATUS_ACTIVE || t.status == STATUS_COUNTERED
+}
+
+/// Take a file down and tell its owner
+pub async fn issue(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    f: file::Model,
+    admin_id: i32,
+    reason: String,
+) -> Result<takedown::Model, DbErr> {
+    let now = Utc::now().naive_utc();
+    let txn = db.begin().await?;
+    let record = takedown::ActiveModel {
+        file_id: Set(f.id),
+        owner_id: Set(f.user_id),
+        reason: Set(reason.clone()),
+        issued_by: Set(admin_id),
+        status: Set(STATUS_ACTIVE.to_string()),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let mut active: file::ActiveModel = f.clone().into();
+    active.taken_down = Set(true);
+    active.update(&txn).await?;
+    txn.commit().await?;
+
+    notify_owner(
+        db,
+        smtp,
+        f.user_id,
+        format!("\"{}\" was taken down", f.name),
+        format!(
+            "{} can no longer be downloaded or shared: {}",
+            f.path, reason
+        ),
+        |username| EmailTemplate::Takedown {
+            username,
+            path: f.path.clone(),
+            reason: reason.clone(),
+        },
+    )
+    .await;
+    Ok(record)
+}
+
+/// Record the owner's dispute and let the administrators know
+pub async fn counter(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    t: takedown::Model,
+    statement: String,
+) -> Result<takedown::Model, DbErr> {
+    let mut active: takedown::ActiveModel = t.into();
+    active.status = Set(STATUS_COUNTERED.to_string());
+    active.counter_notice = Set(Some(statement));
+    active.countered_at = Set(Some(Utc::now().naive_utc()));
+    let t = active.update(db).await?;
+
+    let admins = user::Entity::find()
+        .filter(user::Column::Role.eq(ROLE_ADMIN))
+        .all(db)
+        .await?;
+    for admin in &admins {
+        let notification = Notification {
+            category: NotificationCategory::Security,
+            title: "Counter-notice received".to_string(),
+            body: format!("The owner of file {} disputes takedown {}", t.file_id, t.id),
+            link: None,
+            email: None,
+        };
+        if let Err(e) = notifications::dispatch(db, smtp, admin, notification).await {
+            tracing::warn!(user_id = admin.id, error = %e, "Failed to notify administrator");
+        }
+    }
+    Ok(t)
+}
+
+/// Close a takedown, making the file available again when `restore` is set
+pub async fn resolve(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    t: takedown::Model,
+    admin_id: i32,
+    restore: bool,
+    note: Option<String>,
+) -> Result<takedown::Model, DbErr> {
+    let txn = db.begin().await?;
+    let mut active: takedown::ActiveModel = t.into();
+    active.status = Set(if restore {
+        STATUS_RESTORED
+    } else {
+        STATUS_UPHELD
+    }
+    .to_string());
+    active.resolved_by = Set(Some(admin_id));
+    active.resolution_note = Set(note.clone());
+    active.resolved_at = Set(Some(Utc::now().naive_utc()));
+    let t = active.update(&txn).await?;
+
+    let f = file::Entity::find_by_id(t.file_id).one(&txn).await?;
+    if let Some(f) = f.as_ref().filter(|_| restore) {
+        let mut active: file::ActiveModel = f.clone().into();
+        active.taken_down = Set(false);
+        active.update(&txn).await?;
+    }
+    txn.commit().await?;
+
+    let Some(f) = f else {
+        return Ok(t);
+    };
+    let (title, body) = if restore {
+        (
+            format!("\"{}\" was restored", f.name),
+            format!("{} is available again", f.path),
+        )
+    } else {
+        (
+            format!("The takedown of \"{}\" was upheld", f.name),
+            format!("{} stays unavailable after review", f.path),
+        )
+    };
+    notify_owner(db, smtp, t.owner_id, title, body, |username| {
+        EmailTemplate::TakedownResolved {
+            username,
+            path: f.path.clone(),
+            restored: restore,
+            note: note.clone(),
+        }
+    })
+    .await;
+    Ok(t)
+}
+
+/// Takedowns, newest first, optionally only those with `status` or of one owner
+pub async fn list(
+    db: &DatabaseConnection,
+    status: Option<&str>,
+    owner_id: Option<i32>,
+) -> Result<Vec<takedown::Model>, DbErr> {
+    let mut query = takedown::Entity::find().order_by_desc(takedown::Column::Id);
+    if let Some(status) = status {
+        query = query.filter(takedown::Column::Status.eq(status));
+    }
+    if let Some(owner_id) = owner_id {
+        query = query.filter(takedown::Column::OwnerId.eq(owner_id));
+    }
+    query.all(db).await
+}
+
+/// Failures are logged, the takedown itself is already recorded
+async fn notify_owner(
+    db: &DatabaseConnection,
+    smtp: &SmtpConfig,
+    owner_id: i32,
+    title: String,
+    body: String,
+    email: impl FnOnce(String) -> EmailTemplate,
+) {
+    let owner = match user::Entity::find_by_id(owner_id).one(db).await {
+        Ok(Some(u)) => u,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!(user_id = owner_id, error = %e, "Failed to load file owner");
+            return;
+        }
+    };
+    let notification = Notification {
+        category: NotificationCategory::Security,
+        title,
+        body,
+        link: None,
+        email: Some(email(owner.username.clone())),
+    };
+    if let Err(e) = notifications::dispatch(db, smtp, &owner, notification).await {
+        tracing::warn!(user_id = owner_id, error = %e, "Failed to send takedown notification");
+    }
+}
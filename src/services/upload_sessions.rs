@@ -0,0 +1,263 @@
+use crate::{
+    config::Config,
+    entities::{upload_chunk, upload_session},
+    models::file::StartUploadRequest,
+    services::staging,
+};
+use anyhow::Result;
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use sha2::{Digest, Sha256};
+use std::io::{Read, SeekFrom};
+use std::path::PathBuf;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// Sessions live as long as the sweeper keeps their staged bytes
+fn expiry(config: &Config, from: NaiveDateTime) -> NaiveDateTime {
+    from + Duration::seconds(config.staging.stale_after_secs as i64)
+}
+
+/// Open a session with an empty staging file
+pub async fn create(
+    db: &DatabaseConnection,
+    config: &Config,
+    user_id: i32,
+    request: StartUploadRequest,
+    client_modified: Option<NaiveDateTime>,
+) -> Result<upload_session::Model> {
+    let dir = staging::area_dir(config, staging::AREA_UPLOADS).join(user_id.to_string());
+    tokio::fs::create_dir_all(&dir).await?;
+    let staging_path = dir.join(format!("{}.part", uuid::Uuid::new_v4()));
+    tokio::fs::File::create(&staging_path).await?;
+
+    let now = Utc::now().naive_utc();
+    let created = upload_session::ActiveModel {
+        user_id: Set(user_id),
+        path: Set(request.path),
+        file_name: Set(request.file_name),
+        mime_type: Set(request.mime_type),
+        on_conflict: Set(request.on_conflict.unwrap_or_else(|| "rename".to_string())),
+        base_hash: Set(request.base_hash),
+        client_modified: Set(client_modified),
+        total_bytes: Set(request.size),
+        received_bytes: Set(0),
+        staging_path: Set(staging_path.to_string_lossy().to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        expires_at: Set(expiry(config, now)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await;
+
+    match created {
+        Ok(session) => Ok(session),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&staging_path).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// Unexpired session of `user_id`
+pub async fn find(
+    db: &DatabaseConnection,
+    user_id: i32,
+    session_id: i32,
+) -> Result<Option<upload_session::Model>, DbErr> {
+    upload_session::Entity::find_by_id(session_id)
+        .filter(upload_session::Column::UserId.eq(user_id))
+        .filter(upload_session::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .one(db)
+        .await
+}
+
+/// Unexpired sessions of `user_id`, oldest first
+pub async fn list(
+    db: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<upload_session::Model>, DbErr> {
+    upload_session::Entity::find()
+        .filter(upload_session::Column::UserId.eq(user_id))
+        .filter(upload_session::Column::ExpiresAt.gt(Utc::now().naive_utc()))
+        .order_by_asc(upload_session::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Recorded chunks of a session, in file order
+pub async fn chunks(
+    db: &DatabaseConnection,
+    session_id: i32,
+) -> Result<Vec<upload_chunk::Model>, DbErr> {
+    upload_chunk::Entity::find()
+        .filter(upload_chunk::Column::SessionId.eq(session_id))
+        .order_by_asc(upload_chunk::Column::Offset)
+        .all(db)
+        .await
+}
+
+/// Write a chunk at its offset and record its checksum, `replaces` is the same chunk sent earlier
+/// The caller checks that the chunk fits `total_bytes` and overlaps no other chunk
+pub async fn write_chunk(
+    db: &DatabaseConnection,
+    config: &Config,
+    session: upload_session::Model,
+    offset: i64,
+    chunk: &[u8],
+    checksum: String,
+    replaces: Option<upload_chunk::Model>,
+) -> Result<upload_session::Model> {
+    let mut staged = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(&session.staging_path)
+        .await?;
+    staged.seek(SeekFrom::Start(offset as u64)).await?;
+    staged.write_all(chunk).await?;
+    staged.flush().await?;
+
+    let now = Utc::now().naive_utc();
+    match replaces {
+        Some(previous) => {
+            let mut active: upload_chunk::ActiveModel = previous.into();
+            active.checksum = Set(checksum);
+            active.created_at = Set(now);
+            active.update(db).await?;
+        }
+        None => {
+            upload_chunk::ActiveModel {
+                session_id: Set(session.id),
+                offset: Set(offset),
+                size: Set(chunk.len() as i64),
+                checksum: Set(checksum),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(refresh(db, config, session).await?)
+}
+
+/// Hash the staged file in one pass, checking each chunk against the checksum recorded for it
+/// Returns the SHA-256 of the whole file and the chunks whose bytes no longer match
+/// The caller checks that the chunks cover the file without gaps
+pub async fn verify(
+    session: &upload_session::Model,
+    chunks: Vec<upload_chunk::Model>,
+) -> Result<(String, Vec<upload_chunk::Model>)> {
+    let path = PathBuf::from(&session.staging_path);
+    let verified = tokio::task::spawn_blocking(move || {
+        let mut staged = std::io::BufReader::new(std::fs::File::open(path)?);
+        let mut whole = Sha256::new();
+        let mut damaged = Vec::new();
+        for c in chunks {
+            let mut part = Sha256::new();
+            let read = std::io::copy(
+                &mut (&mut staged).take(c.size as u64),
+                &mut Tee(&mut whole, &mut part),
+            )?;
+            if read != c.size as u64 || format!("{:x}", part.finalize()) != c.checksum {
+                damaged.push(c);
+            }
+        }
+        std::io::copy(&mut staged, &mut whole)?;
+        Ok::<_, std::io::Error>((format!("{:x}", whole.finalize()), damaged))
+    })
+    .await??;
+    Ok(verified)
+}
+
+/// Feeds the bytes of a chunk to the hash of the whole file and to its own
+struct Tee<'a>(&'a mut Sha256, &'a mut Sha256);
+
+impl std::io::Write for Tee<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        self.1.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Drop chunks that failed verification so they show up as missing again
+pub async fn drop_chunks(
+    db: &DatabaseConnection,
+    config: &Config,
+    session: upload_session::Model,
+    damaged: &[upload_chunk::Model],
+) -> Result<upload_session::Model, DbErr> {
+    upload_chunk::Entity::delete_many()
+        .filter(upload_chunk::Column::Id.is_in(damaged.iter().map(|c| c.id)))
+        .exec(db)
+        .await?;
+    refresh(db, config, session).await
+}
+
+/// Count the bytes received from the recorded chunks and extend the session
+/// Counting instead of adding keeps the total right when chunks arrive in parallel
+async fn refresh(
+    db: &DatabaseConnection,
+    config: &Config,
+    session: upload_session::Model,
+) -> Result<upload_session::Model, DbErr> {
+    let received = chunks(db, session.id).await?.iter().map(|c| c.size).sum();
+    let now = Utc::now().naive_utc();
+    let mut active: upload_session::ActiveModel = session.into();
+    active.received_bytes = Set(received);
+    active.updated_at = Set(now);
+    active.expires_at = Set(expiry(config, now));
+    active.update(db).await
+}
+
+/// Drop a session and free its staged bytes
+pub async fn remove(db: &DatabaseConnection, session: &upload_session::Model) -> Result<(), DbErr> {
+    upload_chunk::Entity::delete_many()
+        .filter(upload_chunk::Column::SessionId.eq(session.id))
+        .exec(db)
+        .await?;
+    upload_session::Entity::delete_by_id(session.id)
+        .exec(db)
+        .await?;
+    remove_staged(&session.staging_path).await;
+    Ok(())
+}
+
+/// Drop every session of a user, used when the account is purged
+pub async fn remove_for_user(db: &DatabaseConnection, user_id: i32) -> Result<(), DbErr> {
+    let sessions = upload_session::Entity::find()
+        .filter(upload_session::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+    for session in &sessions {
+        remove(db, session).await?;
+    }
+    Ok(())
+}
+
+/// Drop sessions that ran out, returns how many were removed
+pub async fn remove_expired(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let expired = upload_session::Entity::find()
+        .filter(upload_session::Column::ExpiresAt.lte(Utc::now().naive_utc()))
+        .all(db)
+        .await?;
+    for session in &expired {
+        remove(db, session).await?;
+    }
+    Ok(expired.len())
+}
+
+async fn remove_staged(path: &str) {
+    if let Err(e) = tokio::fs::remove_file(PathBuf::from(path)).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(path = %path, error = %e, "Failed to delete staged upload");
+        }
+    }
+}
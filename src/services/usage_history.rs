@@ -0,0 +1,118 @@
+use crate::{
+    config::Config,
+    constants::ROLE_LIBRARY,
+    entities::{file, usage_snapshot, user},
+};
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Snapshots per INSERT, below SQLite's variable limit
+const INSERT_CHUNK: usize = 500;
+
+/// Start the background task taking the daily storage snapshot of every user
+/// Today's snapshot is refreshed on each run, so a day ends with its last measurement
+pub fn spawn_recorder(db: DatabaseConnection, config: Config) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.usage_history.interval_secs));
+
+        loop {
+            interval.tick().await;
+            match record(&db).await {
+                Ok(users) => tracing::debug!(users = users, "Storage usage recorded"),
+                Err(e) => tracing::warn!(error = %e, "Failed to record storage usage"),
+            }
+            if let Err(e) = prune(&db, config.usage_history.retention_days).await {
+                tracing::warn!(error = %e, "Failed to remove old usage snapshots");
+            }
+        }
+    });
+}
+
+/// Store today's usage of every account, returns how many were recorded
+pub async fn record(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let users: Vec<i32> = user::Entity::find()
+        .select_only()
+        .column(user::Column::Id)
+        .filter(user::Column::Role.ne(ROLE_LIBRARY))
+        .into_tuple()
+        .all(db)
+        .await?;
+    if users.is_empty() {
+        return Ok(0);
+    }
+
+    let totals: HashMap<i32, (i64, i64)> = file::Entity::find()
+        .select_only()
+        .column(file::Column::UserId)
+        .column_as(Expr::col(file::Column::SizeBytes).sum(), "size")
+        .column_as(Expr::col(file::Column::Id).count(), "count")
+        .filter(file::Column::FileType.eq("file"))
+        .group_by(file::Column::UserId)
+        .into_tuple::<(i32, Option<i64>, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(user_id, size, count)| (user_id, (size.unwrap_or(0), count)))
+        .collect();
+
+    let now = Utc::now().naive_utc();
+    let rows: Vec<usage_snapshot::ActiveModel> = users
+        .iter()
+        .map(|id| {
+            let (used_bytes, file_count) = totals.get(id).copied().unwrap_or((0, 0));
+            usage_snapshot::ActiveModel {
+                user_id: Set(*id),
+                day: Set(now.date()),
+                used_bytes: Set(used_bytes),
+                file_count: Set(file_count),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    for chunk in rows.chunks(INSERT_CHUNK) {
+        usage_snapshot::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([usage_snapshot::Column::UserId, usage_snapshot::Column::Day])
+                    .update_columns([
+                        usage_snapshot::Column::UsedBytes,
+                        usage_snapshot::Column::FileCount,
+                        usage_snapshot::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+    }
+    Ok(users.len())
+}
+
+async fn prune(db: &DatabaseConnection, retention_days: i64) -> Result<(), DbErr> {
+    let cutoff = Utc::now().date_naive() - ChronoDuration::days(retention_days);
+    usage_snapshot::Entity::delete_many()
+        .filter(usage_snapshot::Column::Day.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Snapshots of the user from `since` on, oldest first
+pub async fn history(
+    db: &DatabaseConnection,
+    user_id: i32,
+    since: NaiveDate,
+) -> Result<Vec<usage_snapshot::Model>, DbErr> {
+    usage_snapshot::Entity::find()
+        .filter(usage_snapshot::Column::UserId.eq(user_id))
+        .filter(usage_snapshot::Column::Day.gte(since))
+        .order_by_asc(usage_snapshot::Column::Day)
+        .all(db)
+        .await
+}
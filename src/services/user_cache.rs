@@ -0,0 +1,39 @@
+use crate::entities::user;
+use moka::sync::Cache;
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Short enough that a missed invalidation only lingers briefly
+const TTL: Duration = Duration::from_secs(30);
+const MAX_ENTRIES: u64 = 10_000;
+
+/// User rows by id, read by the auth middleware on every request
+static CACHE: OnceLock<Cache<i32, user::Model>> = OnceLock::new();
+
+fn cache() -> &'static Cache<i32, user::Model> {
+    CACHE.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(MAX_ENTRIES)
+            .time_to_live(TTL)
+            .build()
+    })
+}
+
+/// Load a user, served from the cache when possible
+pub async fn load(db: &DatabaseConnection, user_id: i32) -> Result<Option<user::Model>, DbErr> {
+    if let Some(user) = cache().get(&user_id) {
+        return Ok(Some(user));
+    }
+
+    let user = user::Entity::find_by_id(user_id).one(db).await?;
+    if let Some(user) = &user {
+        cache().insert(user_id, user.clone());
+    }
+    Ok(user)
+}
+
+/// Forget a cached user, call after any change to their row
+pub fn invalidate(user_id: i32) {
+    cache().invalidate(&user_id);
+}
@@ -0,0 +1,117 @@
+use crate::{
+    config::Config,
+    entities::{file, user},
+    services::{disk_space, storage_health, user_cache},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const PLACEMENT_MOST_FREE_SPACE: &str = "most_free_space";
+pub const PLACEMENT_ROUND_ROBIN: &str = "round_robin";
+pub const PLACEMENT_PER_USER: &str = "per_user";
+
+static NEXT_VOLUME: AtomicUsize = AtomicUsize::new(0);
+
+/// Storage root for a user's files, placed by the configured policy on first use
+/// A user's files all live on one volume so renames and moves never cross disks
+pub async fn user_root(
+    db: &DatabaseConnection,
+    config: &Config,
+    user_id: i32,
+) -> Result<PathBuf, DbErr> {
+    let user = user_cache::load(db, user_id)
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound(format!("User {} not found", user_id)))?;
+
+    if let Some(root) = &user.storage_root {
+        return Ok(PathBuf::from(root));
+    }
+
+    // Users from before volumes existed keep the root their files are already on
+    let root = match existing_root(db, config, user_id).await? {
+        Some(root) => root,
+        None => place(config, user_id).await,
+    };
+
+    user::ActiveModel {
+        id: Set(user_id),
+        storage_root: Set(Some(root.to_string_lossy().replace('\\', "/"))),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+    user_cache::invalidate(user_id);
+
+    tracing::info!(user_id = user_id, root = ?root, "Storage volume assigned");
+    Ok(root)
+}
+
+/// Configured volume one of the user's stored files is on
+/// Files outside the volumes, e.g. in external mounts, are ignored
+async fn existing_root(
+    db: &DatabaseConnection,
+    config: &Config,
+    user_id: i32,
+) -> Result<Option<PathBuf>, DbErr> {
+    let volumes = config.get_storage_volumes();
+    let files = file::Entity::find()
+        .filter(file::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    // storage_path is <root>/<user_id>/<path>
+    Ok(files.iter().find_map(|f| {
+        let relative = f.path.trim_start_matches('/');
+        let storage_path = PathBuf::from(&f.storage_path);
+        if !storage_path.ends_with(relative) {
+            return None;
+        }
+        let depth = relative.split('/').filter(|s| !s.is_empty()).count();
+        let root = storage_path.ancestors().nth(depth + 1)?;
+        volumes.iter().find(|v| v.as_path() == root).cloned()
+    }))
+}
+
+/// Pick a volume for a user that has none yet
+/// With failover on, volumes failing their health check are passed over while any other is up
+async fn place(config: &Config, user_id: i32) -> PathBuf {
+    let usable = |v: &PathBuf| !config.storage.failover || storage_health::is_healthy(v);
+
+    if let Some(pin) = config.storage.pins.iter().find(|p| p.user_id == user_id) {
+        let pinned = PathBuf::from(&pin.volume);
+        if usable(&pinned) {
+            return pinned;
+        }
+        tracing::warn!(user_id = user_id, volume = ?pinned, "Pinned volume is down, failing over");
+    }
+
+    let mut volumes = config.get_storage_volumes();
+    if volumes.iter().any(usable) {
+        volumes.retain(usable);
+    }
+    match config.storage.placement.as_str() {
+        PLACEMENT_PER_USER => volumes[0].clone(),
+        PLACEMENT_ROUND_ROBIN => {
+            volumes[NEXT_VOLUME.fetch_add(1, Ordering::Relaxed) % volumes.len()].clone()
+        }
+        policy => {
+            if policy != PLACEMENT_MOST_FREE_SPACE {
+                tracing::warn!(placement = %policy, "Unknown placement policy, using most free space");
+            }
+            let mut most_free = None;
+            for volume in volumes {
+                let available = disk_space::available_space(&volume).await.unwrap_or(0);
+                if most_free
+                    .as_ref()
+                    .is_none_or(|(best, _)| available >= *best)
+                {
+                    most_free = Some((available, volume));
+                }
+            }
+            most_free.map_or_else(|| config.get_storage_dir(), |(_, volume)| volume)
+        }
+    }
+}
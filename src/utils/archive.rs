@@ -1,96 +1,640 @@
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{Cursor, Write};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use flate2::{write::DeflateEncoder, Compression, Crc};
+use std::io::Write;
 use std::path::Path;
-use zip::write::FileOptions;
-use zip::ZipWriter;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-/// Create a streaming ZIP archive from file paths
-/// Returns the ZIP file as a Vec<u8>
-pub fn create_zip_archive(files: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>> {
-    let buffer = Vec::new();
-    let cursor = Cursor::new(buffer);
-    let mut zip = ZipWriter::new(cursor);
+const CHUNK_SIZE: usize = 64 * 1024;
 
-    let options = FileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
-        .unix_permissions(0o755);
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_END_LOCATOR: u32 = 0x0706_4b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// Sizes follow the data, names are UTF-8
+const ZIP_FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+const ZIP_FLAG_UTF8: u16 = 0x0800;
+const ZIP_VERSION: u16 = 20;
+const ZIP64_VERSION: u16 = 45;
+/// "Made by" Unix, so the permissions in the external attributes are used
+const ZIP_MADE_BY_UNIX: u16 = 3 << 8;
+/// Entries at least this large get ZIP64 sizes, leaving room for deflate overhead
+const ZIP64_ENTRY_THRESHOLD: u64 = 0xFFFF_0000;
 
-    for (file_path, file_content) in files {
-        zip.start_file(file_path, options)?;
-        zip.write_all(&file_content)?;
-    }
+const TAR_BLOCK: usize = 512;
+/// Largest size the 11 octal digits of a ustar header hold
+const TAR_MAX_SIZE: u64 = 0o77_777_777_777;
 
-    let cursor = zip.finish()?;
-    Ok(cursor.into_inner())
+/// Container format of a downloaded archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    Tar,
 }
 
-/// Add a single file to ZIP writer from disk (streaming)
-/// If should_compress is true, uses Deflated compression; otherwise uses Stored
-pub fn add_file_to_zip<W: Write + std::io::Seek>(
-    zip: &mut ZipWriter<W>,
-    file_path: &Path,
-    archive_path: &str,
-    should_compress: bool,
-) -> Result<()> {
-    let compression_method = if should_compress {
-        zip::CompressionMethod::Deflated
-    } else {
-        zip::CompressionMethod::Stored
-    };
+impl ArchiveFormat {
+    /// Format named by a request, ZIP when none is given
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("zip") => Some(ArchiveFormat::Zip),
+            Some("tar") => Some(ArchiveFormat::Tar),
+            _ => None,
+        }
+    }
 
-    let options = FileOptions::default()
-        .compression_method(compression_method)
-        .unix_permissions(0o755);
+    /// Format of an archive file, by its extension
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".tar") {
+            ArchiveFormat::Tar
+        } else {
+            ArchiveFormat::Zip
+        }
+    }
 
-    zip.start_file(archive_path, options)?;
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Tar => "tar",
+        }
+    }
 
-    let mut file = File::open(file_path)?;
-    std::io::copy(&mut file, zip)?;
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::Tar => "application/x-tar",
+        }
+    }
+}
 
-    Ok(())
+/// Central directory record of a ZIP entry written so far
+struct ZipEntry {
+    name: String,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    offset: u64,
+    external_attributes: u32,
 }
 
-/// Create a streaming ZIP from multiple file paths
-/// Each tuple contains (physical_path, archive_path)
-/// If should_compress is true, files will be compressed; otherwise stored as-is
-pub fn create_streaming_zip_from_paths(
-    files: Vec<(String, String)>,
-    should_compress: bool,
-) -> Result<Vec<u8>> {
-    let buffer = Vec::new();
-    let cursor = Cursor::new(buffer);
-    let mut zip = ZipWriter::new(cursor);
+/// Writes a ZIP or TAR archive entry by entry to any `AsyncWrite`
+/// Nothing is seeked or buffered beyond one chunk, so the output can be a socket or pipe
+pub struct ArchiveWriter<W> {
+    writer: W,
+    format: ArchiveFormat,
+    /// Deflate ZIP entries, TAR entries are always stored
+    compress: bool,
+    written: u64,
+    entries: Vec<ZipEntry>,
+}
+
+impl<W: AsyncWrite + Unpin> ArchiveWriter<W> {
+    pub fn new(writer: W, format: ArchiveFormat, compress: bool) -> Self {
+        ArchiveWriter {
+            writer,
+            format,
+            compress,
+            written: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Bytes of archive written so far
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Add the file at `physical_path` as `archive_path`, returning its size
+    pub async fn add_file(
+        &mut self,
+        archive_path: &str,
+        physical_path: &Path,
+        modified: NaiveDateTime,
+    ) -> Result<u64> {
+        let file = match tokio::fs::File::open(physical_path).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow!("File not found: {}", physical_path.display()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return Err(anyhow!("Not a file: {}", physical_path.display()));
+        }
+        self.add_reader(archive_path, file, metadata.len(), modified)
+            .await?;
+        Ok(metadata.len())
+    }
+
+    /// Add `size` bytes read from `reader` as `archive_path`
+    pub async fn add_reader<R: AsyncRead + Unpin>(
+        &mut self,
+        archive_path: &str,
+        reader: R,
+        size: u64,
+        modified: NaiveDateTime,
+    ) -> Result<()> {
+        let name = entry_name(archive_path, false)?;
+        match self.format {
+            ArchiveFormat::Zip => self.zip_file(name, reader, size, modified).await,
+            ArchiveFormat::Tar => self.tar_file(name, reader, size, modified).await,
+        }
+    }
+
+    /// Add a directory entry, so the folder exists even without files in it
+    pub async fn add_dir(&mut self, archive_path: &str, modified: NaiveDateTime) -> Result<()> {
+        let name = entry_name(archive_path, true)?;
+        match self.format {
+            ArchiveFormat::Zip => {
+                let (time, date) = dos_time(modified);
+                let entry = ZipEntry {
+                    name,
+                    flags: ZIP_FLAG_UTF8,
+                    method: 0,
+                    time,
+                    date,
+                    crc: 0,
+                    compressed_size: 0,
+                    size: 0,
+                    offset: self.written,
+                    // Unix drwxr-xr-x plus the MS-DOS directory bit
+                    external_attributes: (0o040755 << 16) | 0x10,
+                };
+                self.write_local_header(&entry, false).await?;
+                self.entries.push(entry);
+                Ok(())
+            }
+            ArchiveFormat::Tar => self.write_tar_header(&name, b'5', 0o755, 0, modified).await,
+        }
+    }
+
+    /// Write the end of the archive, handing the writer back with the archive size
+    pub async fn finish(mut self) -> Result<(W, u64)> {
+        match self.format {
+            ArchiveFormat::Zip => self.write_central_directory().await?,
+            ArchiveFormat::Tar => self.write(&[0u8; TAR_BLOCK * 2]).await?,
+        }
+        self.writer.flush().await?;
+        Ok((self.writer, self.written))
+    }
+
+    async fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.writer.write_all(buf).await?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    async fn zip_file<R: AsyncRead + Unpin>(
+        &mut self,
+        name: String,
+        mut reader: R,
+        size: u64,
+        modified: NaiveDateTime,
+    ) -> Result<()> {
+        let zip64 = size >= ZIP64_ENTRY_THRESHOLD;
+        let (time, date) = dos_time(modified);
+        let mut entry = ZipEntry {
+            name,
+            flags: ZIP_FLAG_UTF8 | ZIP_FLAG_DATA_DESCRIPTOR,
+            method: if self.compress { 8 } else { 0 },
+            time,
+            date,
+            crc: 0,
+            compressed_size: 0,
+            size: 0,
+            offset: self.written,
+            // Unix -rw-r--r--
+            external_attributes: 0o100644 << 16,
+        };
+        self.write_local_header(&entry, zip64).await?;
+
+        // CRC and sizes are only known once the data went out, they follow in the descriptor
+        let start = self.written;
+        let mut crc = Crc::new();
+        let mut encoder = self
+            .compress
+            .then(|| DeflateEncoder::new(Vec::new(), Compression::default()));
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            crc.update(&buf[..n]);
+            match encoder.as_mut() {
+                Some(encoder) => {
+                    encoder.write_all(&buf[..n])?;
+                    let deflated = std::mem::take(encoder.get_mut());
+                    self.write(&deflated).await?;
+                }
+                None => self.write(&buf[..n]).await?,
+            }
+        }
+        if let Some(encoder) = encoder {
+            let deflated = encoder.finish()?;
+            self.write(&deflated).await?;
+        }
+
+        entry.crc = crc.sum();
+        entry.size = crc.amount() as u64;
+        entry.compressed_size = self.written - start;
+        if !zip64 && entry.size.max(entry.compressed_size) >= u32::MAX as u64 {
+            return Err(anyhow!("File grew past the ZIP size limit: {}", entry.name));
+        }
+
+        let mut descriptor = Vec::with_capacity(24);
+        put_u32(&mut descriptor, ZIP_DATA_DESCRIPTOR);
+        put_u32(&mut descriptor, entry.crc);
+        if zip64 {
+            put_u64(&mut descriptor, entry.compressed_size);
+            put_u64(&mut descriptor, entry.size);
+        } else {
+            put_u32(&mut descriptor, entry.compressed_size as u32);
+            put_u32(&mut descriptor, entry.size as u32);
+        }
+        self.write(&descriptor).await?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    async fn write_local_header(&mut self, entry: &ZipEntry, zip64: bool) -> Result<()> {
+        let mut header = Vec::with_capacity(30 + entry.name.len() + 20);
+        put_u32(&mut header, ZIP_LOCAL_HEADER);
+        put_u16(&mut header, if zip64 { ZIP64_VERSION } else { ZIP_VERSION });
+        put_u16(&mut header, entry.flags);
+        put_u16(&mut header, entry.method);
+        put_u16(&mut header, entry.time);
+        put_u16(&mut header, entry.date);
+        put_u32(&mut header, entry.crc);
+        let sizes = if zip64 { u32::MAX } else { 0 };
+        put_u32(&mut header, sizes);
+        put_u32(&mut header, sizes);
+        put_u16(&mut header, entry.name.len() as u16);
+        put_u16(&mut header, if zip64 { 20 } else { 0 });
+        header.extend_from_slice(entry.name.as_bytes());
+        if zip64 {
+            // Sizes are in the data descriptor, the extra field only marks them as 64-bit
+            put_u16(&mut header, 0x0001);
+            put_u16(&mut header, 16);
+            put_u64(&mut header, 0);
+            put_u64(&mut header, 0);
+        }
+        self.write(&header).await
+    }
+
+    async fn write_central_directory(&mut self) -> Result<()> {
+        let start = self.written;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            // Values that do not fit 32 bits move to the ZIP64 extra field, in this order
+            let mut extra = Vec::new();
+            let mut field = |value: u64| {
+                if value >= u32::MAX as u64 {
+                    put_u64(&mut extra, value);
+                    u32::MAX
+                } else {
+                    value as u32
+                }
+            };
+            let size = field(entry.size);
+            let compressed_size = field(entry.compressed_size);
+            let offset = field(entry.offset);
+            let version = if extra.is_empty() {
+                ZIP_VERSION
+            } else {
+                ZIP64_VERSION
+            };
+
+            put_u32(&mut directory, ZIP_CENTRAL_HEADER);
+            put_u16(&mut directory, ZIP_MADE_BY_UNIX | ZIP64_VERSION);
+            put_u16(&mut directory, version);
+            put_u16(&mut directory, entry.flags);
+            put_u16(&mut directory, entry.method);
+            put_u16(&mut directory, entry.time);
+            put_u16(&mut directory, entry.date);
+            put_u32(&mut directory, entry.crc);
+            put_u32(&mut directory, compressed_size);
+            put_u32(&mut directory, size);
+            put_u16(&mut directory, entry.name.len() as u16);
+            put_u16(
+                &mut directory,
+                if extra.is_empty() {
+                    0
+                } else {
+                    extra.len() as u16 + 4
+                },
+            );
+            put_u16(&mut directory, 0); // comment
+            put_u16(&mut directory, 0); // disk
+            put_u16(&mut directory, 0); // internal attributes
+            put_u32(&mut directory, entry.external_attributes);
+            put_u32(&mut directory, offset);
+            directory.extend_from_slice(entry.name.as_bytes());
+            if !extra.is_empty() {
+                put_u16(&mut directory, 0x0001);
+                put_u16(&mut directory, extra.len() as u16);
+                directory.extend_from_slice(&extra);
+            }
+        }
+        let size = directory.len() as u64;
+        let count = self.entries.len() as u64;
+
+        if count >= u16::MAX as u64 || start >= u32::MAX as u64 || size >= u32::MAX as u64 {
+            let end_offset = start + size;
+            put_u32(&mut directory, ZIP64_END_OF_DIRECTORY);
+            put_u64(&mut directory, 44);
+            put_u16(&mut directory, ZIP_MADE_BY_UNIX | ZIP64_VERSION);
+            put_u16(&mut directory, ZIP64_VERSION);
+            put_u32(&mut directory, 0);
+            put_u32(&mut directory, 0);
+            put_u64(&mut directory, count);
+            put_u64(&mut directory, count);
+            put_u64(&mut directory, size);
+            put_u64(&mut directory, start);
+
+            put_u32(&mut directory, ZIP64_END_LOCATOR);
+            put_u32(&mut directory, 0);
+            put_u64(&mut directory, end_offset);
+            put_u32(&mut directory, 1);
+        }
+
+        put_u32(&mut directory, ZIP_END_OF_DIRECTORY);
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, count.min(u16::MAX as u64) as u16);
+        put_u16(&mut directory, count.min(u16::MAX as u64) as u16);
+        put_u32(&mut directory, size.min(u32::MAX as u64) as u32);
+        put_u32(&mut directory, start.min(u32::MAX as u64) as u32);
+        put_u16(&mut directory, 0);
+        self.write(&directory).await
+    }
+
+    async fn tar_file<R: AsyncRead + Unpin>(
+        &mut self,
+        name: String,
+        reader: R,
+        size: u64,
+        modified: NaiveDateTime,
+    ) -> Result<()> {
+        self.write_tar_header(&name, b'0', 0o644, size, modified)
+            .await?;
 
-    for (physical_path, archive_path) in files {
-        let path = Path::new(&physical_path);
-        if !path.exists() {
-            return Err(anyhow!("File not found: {}", physical_path));
+        // The header promised `size` bytes, a file changing underneath would corrupt the archive
+        let mut reader = reader.take(size);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut copied = 0u64;
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            self.write(&buf[..n]).await?;
+            copied += n as u64;
         }
+        if copied != size {
+            return Err(anyhow!("File changed while archiving: {}", name));
+        }
+        self.pad_tar_block(size).await
+    }
+
+    /// ustar header, preceded by a PAX header for names or sizes ustar cannot hold
+    async fn write_tar_header(
+        &mut self,
+        name: &str,
+        kind: u8,
+        mode: u32,
+        size: u64,
+        modified: NaiveDateTime,
+    ) -> Result<()> {
+        let mtime = modified.and_utc().timestamp().max(0) as u64;
+        let long_name = !name.is_ascii() || name.len() > 100;
+        let large = size > TAR_MAX_SIZE;
 
-        if path.is_file() {
-            add_file_to_zip(&mut zip, path, &archive_path, should_compress)?;
+        if long_name || large {
+            let mut records = Vec::new();
+            if long_name {
+                records.extend(pax_record("path", name));
+            }
+            if large {
+                records.extend(pax_record("size", &size.to_string()));
+            }
+            let pax_name = format!("PaxHeader/{}", ascii_name(name, 90));
+            let header = tar_header(&pax_name, b'x', 0o644, records.len() as u64, mtime);
+            self.write(&header).await?;
+            self.write(&records).await?;
+            self.pad_tar_block(records.len() as u64).await?;
         }
+
+        let header = tar_header(
+            &ascii_name(name, 100),
+            kind,
+            mode,
+            if large { 0 } else { size },
+            mtime,
+        );
+        self.write(&header).await
     }
 
-    let cursor = zip.finish()?;
-    Ok(cursor.into_inner())
+    async fn pad_tar_block(&mut self, size: u64) -> Result<()> {
+        let rest = (size % TAR_BLOCK as u64) as usize;
+        if rest > 0 {
+            self.write(&[0u8; TAR_BLOCK][rest..]).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Entry name with forward slashes and no leading slash, directories end with one
+fn entry_name(archive_path: &str, dir: bool) -> Result<String> {
+    let name = archive_path.replace('\\', "/");
+    let name = name.trim_matches('/');
+    if name.is_empty() || name.split('/').any(|part| part == "..") {
+        return Err(anyhow!("Invalid archive path: {}", archive_path));
+    }
+    if name.len() > u16::MAX as usize - 1 {
+        return Err(anyhow!("Archive path too long: {}", archive_path));
+    }
+    Ok(if dir {
+        format!("{}/", name)
+    } else {
+        name.to_string()
+    })
+}
+
+/// ASCII stand-in for a name in the fixed-size ustar field, the PAX path holds the real one
+fn ascii_name(name: &str, max: usize) -> String {
+    let dir = name.ends_with('/');
+    let mut ascii: String = name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+    if ascii.len() > max {
+        ascii.truncate(if dir { max - 1 } else { max });
+        if dir {
+            ascii.push('/');
+        }
+    }
+    ascii
+}
+
+/// "<length> <key>=<value>\n", the length counting its own digits
+fn pax_record(key: &str, value: &str) -> Vec<u8> {
+    let base = key.len() + value.len() + 3;
+    let mut len = base + base.to_string().len();
+    if len.to_string().len() > base.to_string().len() {
+        len += 1;
+    }
+    format!("{} {}={}\n", len, key, value).into_bytes()
+}
+
+fn tar_header(name: &str, kind: u8, mode: u32, size: u64, mtime: u64) -> [u8; TAR_BLOCK] {
+    let mut header = [0u8; TAR_BLOCK];
+    let octal = |field: &mut [u8], value: u64| {
+        let digits = format!("{:0width$o}", value, width = field.len() - 1);
+        field[..digits.len()].copy_from_slice(digits.as_bytes());
+    };
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut header[100..108], mode as u64);
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], mtime);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    let digits = format!("{:06o}\0 ", checksum);
+    header[148..156].copy_from_slice(digits.as_bytes());
+    header
+}
+
+/// MS-DOS time and date, which cannot go before 1980
+fn dos_time(t: NaiveDateTime) -> (u16, u16) {
+    if t.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (t.hour() << 11) | (t.minute() << 5) | (t.second() / 2);
+    let date = (((t.year() - 1980).min(127) as u32) << 9) | (t.month() << 5) | t.day();
+    (time as u16, date as u16)
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_le_bytes());
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{Cursor, Read};
+
+    fn modified() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2024-05-06 07:08:10", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    async fn build(format: ArchiveFormat, compress: bool) -> Vec<u8> {
+        let mut archive = ArchiveWriter::new(Vec::new(), format, compress);
+        let content = b"Hello World, Hello World, Hello World";
+        archive
+            .add_reader(
+                "报告/文档 ü.txt",
+                &content[..],
+                content.len() as u64,
+                modified(),
+            )
+            .await
+            .unwrap();
+        archive.add_dir("报告/空文件夹", modified()).await.unwrap();
+        archive.add_dir("empty", modified()).await.unwrap();
+        let written = archive.bytes_written();
+        let (data, size) = archive.finish().await.unwrap();
+        assert!(size > written);
+        assert_eq!(data.len() as u64, size);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_zip_unicode_names_and_empty_folders() {
+        for compress in [false, true] {
+            let data = build(ArchiveFormat::Zip, compress).await;
+            let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+            assert_eq!(zip.len(), 3);
+
+            let mut file = zip.by_name("报告/文档 ü.txt").unwrap();
+            let mut content = String::new();
+            file.read_to_string(&mut content).unwrap();
+            assert_eq!(content, "Hello World, Hello World, Hello World");
+            let time = file.last_modified();
+            assert_eq!(
+                (
+                    time.year(),
+                    time.month(),
+                    time.day(),
+                    time.hour(),
+                    time.second()
+                ),
+                (2024, 5, 6, 7, 10)
+            );
+            assert_eq!(file.unix_mode(), Some(0o100644));
+            drop(file);
+
+            let dir = zip.by_name("报告/空文件夹/").unwrap();
+            assert!(dir.is_dir());
+            assert_eq!(dir.unix_mode(), Some(0o040755));
+            assert_eq!(dir.last_modified().year(), 2024);
+            drop(dir);
+            assert!(zip.by_name("empty/").unwrap().is_dir());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tar_unicode_names_and_empty_folders() {
+        let data = build(ArchiveFormat::Tar, false).await;
+        assert_eq!(data.len() % TAR_BLOCK, 0);
+
+        // PAX header carrying the UTF-8 name, then the file header and its data
+        assert_eq!(data[156], b'x');
+        let records = String::from_utf8_lossy(&data[TAR_BLOCK..TAR_BLOCK * 2]);
+        let record = "path=报告/文档 ü.txt\n";
+        let expected = format!("{} {}", record.len() + 3, record);
+        assert!(records.starts_with(&expected));
+        assert_eq!(data[TAR_BLOCK * 2 + 156], b'0');
+        assert!(data[TAR_BLOCK * 3..].starts_with(b"Hello World"));
+
+        // The empty folder with a unicode name also gets a PAX path
+        let records = String::from_utf8_lossy(&data[TAR_BLOCK * 5..TAR_BLOCK * 6]);
+        assert!(records.contains("path=报告/空文件夹/\n"));
+        assert_eq!(data[TAR_BLOCK * 6 + 156], b'5');
+        assert!(data[TAR_BLOCK * 7..].starts_with(b"empty/\0"));
+        assert_eq!(data[TAR_BLOCK * 7 + 156], b'5');
+        assert!(data[TAR_BLOCK * 8..].iter().all(|b| *b == 0));
+    }
 
     #[test]
-    fn test_create_zip_archive() {
-        let files = vec![
-            ("test1.txt".to_string(), b"Hello World".to_vec()),
-            ("folder/test2.txt".to_string(), b"Test Content".to_vec()),
-        ];
-
-        let result = create_zip_archive(files);
-        assert!(result.is_ok());
-        let zip_data = result.unwrap();
-        assert!(!zip_data.is_empty());
+    fn test_entry_name_and_format() {
+        assert_eq!(entry_name("/a/b.txt", false).unwrap(), "a/b.txt");
+        assert_eq!(entry_name("a\\dir", true).unwrap(), "a/dir/");
+        assert!(entry_name("a/../b", false).is_err());
+        assert_eq!(ArchiveFormat::parse(None), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::parse(Some("TAR")), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::parse(Some("rar")), None);
     }
 }
@@ -0,0 +1,102 @@
+use crate::AppState;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use std::{convert::Infallible, net::SocketAddr};
+
+/// Maximum stored User-Agent length
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Network details of the client making a request
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+
+        Ok(Self {
+            ip: client_ip(
+                &parts.headers,
+                peer,
+                state.config.server.trust_proxy_headers,
+            ),
+            user_agent: user_agent(&parts.headers),
+        })
+    }
+}
+
+/// Resolve the client IP, honouring `X-Forwarded-For` / `X-Real-IP` only behind a trusted proxy
+/// The proxy appends the address it saw, entries left of it come from the client and are ignored
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trust_proxy: bool,
+) -> Option<String> {
+    if trust_proxy {
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .next_back()
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.split(',').next_back())
+            .or_else(|| headers.get("x-real-ip").and_then(|h| h.to_str().ok()))
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty());
+
+        if let Some(ip) = forwarded {
+            return Some(ip.to_string());
+        }
+    }
+
+    peer.map(|addr| addr.ip().to_string())
+}
+
+fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|ua| ua.chars().take(MAX_USER_AGENT_LENGTH).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_client_ip() {
+        let peer: SocketAddr = "10.0.0.2:5123".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.9, 203.0.113.7"),
+        );
+
+        // The first entry was sent by the client, the proxy appended the real address
+        assert_eq!(
+            client_ip(&headers, Some(peer), true).as_deref(),
+            Some("203.0.113.7")
+        );
+        // Forwarded headers are client-controlled unless a proxy sets them
+        assert_eq!(
+            client_ip(&headers, Some(peer), false).as_deref(),
+            Some("10.0.0.2")
+        );
+        assert_eq!(client_ip(&HeaderMap::new(), None, true), None);
+    }
+}
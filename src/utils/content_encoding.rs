@@ -0,0 +1,137 @@
+use axum::body::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Whether a MIME type is worth compressing, i.e. text-like rather than already compressed media
+pub fn is_compressible(mime_type: &str) -> bool {
+    let mime = mime_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/ecmascript"
+                | "application/x-sh"
+                | "application/x-yaml"
+                | "application/yaml"
+                | "application/toml"
+                | "application/sql"
+                | "application/csv"
+                | "application/x-ndjson"
+                | "image/svg+xml"
+        )
+}
+
+/// Whether an `Accept-Encoding` header allows gzip, honouring `q=0` and the `*` wildcard
+pub fn accepts_gzip(accept_encoding: &str) -> bool {
+    let mut gzip = None;
+    let mut wildcard = None;
+
+    for part in accept_encoding.split(',') {
+        let mut params = part.split(';');
+        let coding = params.next().unwrap_or("").trim().to_ascii_lowercase();
+        let quality = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        match coding.as_str() {
+            "gzip" | "x-gzip" => gzip = Some(quality > 0.0),
+            "*" => wildcard = Some(quality > 0.0),
+            _ => {}
+        }
+    }
+
+    gzip.or(wildcard).unwrap_or(false)
+}
+
+/// Forwards the encoder output to the response body
+struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Stream a file gzip-compressed, the compression runs on a blocking thread
+pub fn gzip_file_stream(path: PathBuf) -> ReceiverStream<io::Result<Bytes>> {
+    let (tx, rx) = mpsc::channel(8);
+
+    tokio::task::spawn_blocking(move || {
+        let errors = tx.clone();
+        let result = (|| {
+            let mut file = std::fs::File::open(&path)?;
+            let mut encoder = GzEncoder::new(ChannelWriter(tx), Compression::default());
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            loop {
+                let n = file.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                encoder.write_all(&buf[..n])?;
+            }
+            encoder.finish()?;
+            Ok::<_, io::Error>(())
+        })();
+
+        if let Err(e) = result {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                tracing::warn!(path = ?path, error = %e, "Compressed download failed");
+                let _ = errors.blocking_send(Err(e));
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_compressible() {
+        assert!(is_compressible("text/plain"));
+        assert!(is_compressible("text/csv; charset=utf-8"));
+        assert!(is_compressible("application/json"));
+        assert!(is_compressible("application/ld+json"));
+        assert!(is_compressible("image/svg+xml"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/zip"));
+        assert!(!is_compressible("application/octet-stream"));
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip("gzip, deflate, br"));
+        assert!(accepts_gzip("br;q=1.0, gzip;q=0.8"));
+        assert!(accepts_gzip("*"));
+        assert!(!accepts_gzip("gzip;q=0"));
+        assert!(!accepts_gzip("gzip;q=0, *"));
+        assert!(!accepts_gzip("identity"));
+        assert!(!accepts_gzip(""));
+    }
+}
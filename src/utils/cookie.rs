@@ -0,0 +1,136 @@
+use crate::config::SessionCookieConfig;
+use axum::http::{header, HeaderMap, HeaderValue};
+
+/// Header the browser UI echoes the CSRF cookie in for mutating requests
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// SameSite values browsers understand
+const SAME_SITE_VALUES: [&str; 3] = ["Strict", "Lax", "None"];
+
+/// Reject settings that would produce cookies browsers ignore or misread
+pub fn validate_config(config: &SessionCookieConfig) -> Result<(), String> {
+    if !SAME_SITE_VALUES
+        .iter()
+        .any(|v| v.eq_ignore_ascii_case(&config.same_site))
+    {
+        return Err(format!(
+            "session_cookie.same_site must be one of {}, got {:?}",
+            SAME_SITE_VALUES.join(", "),
+            config.same_site
+        ));
+    }
+    Ok(())
+}
+
+/// Read a cookie value from the request `Cookie` headers
+pub fn get_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim_matches('"').to_string())
+}
+
+/// Build `Set-Cookie` headers for a new session
+/// The session cookie is HttpOnly; the CSRF cookie must stay readable by scripts
+pub fn session_cookies(
+    config: &SessionCookieConfig,
+    token: &str,
+    csrf: &str,
+    max_age_secs: i64,
+) -> [HeaderValue; 2] {
+    [
+        build_cookie(config, &config.cookie_name, token, max_age_secs, true),
+        build_cookie(config, &config.csrf_cookie_name, csrf, max_age_secs, false),
+    ]
+}
+
+/// Build `Set-Cookie` headers that expire both session cookies
+pub fn clear_session_cookies(config: &SessionCookieConfig) -> [HeaderValue; 2] {
+    [
+        build_cookie(config, &config.cookie_name, "", 0, true),
+        build_cookie(config, &config.csrf_cookie_name, "", 0, false),
+    ]
+}
+
+fn build_cookie(
+    config: &SessionCookieConfig,
+    name: &str,
+    value: &str,
+    max_age_secs: i64,
+    http_only: bool,
+) -> HeaderValue {
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; SameSite={}",
+        name, value, max_age_secs, config.same_site
+    );
+    // Browsers reject SameSite=None cookies without Secure
+    if config.secure || config.same_site.eq_ignore_ascii_case("none") {
+        cookie.push_str("; Secure");
+    }
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+
+    HeaderValue::from_str(&cookie).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SessionCookieConfig {
+        SessionCookieConfig {
+            enabled: true,
+            cookie_name: "cd_session".to_string(),
+            csrf_cookie_name: "cd_csrf".to_string(),
+            same_site: "Strict".to_string(),
+            secure: true,
+        }
+    }
+
+    #[test]
+    fn test_get_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; cd_session=abc.def; cd_csrf=123"),
+        );
+        assert_eq!(
+            get_cookie(&headers, "cd_session").as_deref(),
+            Some("abc.def")
+        );
+        assert_eq!(get_cookie(&headers, "cd_csrf").as_deref(), Some("123"));
+        assert_eq!(get_cookie(&headers, "missing"), None);
+    }
+
+    #[test]
+    fn test_session_cookie_attributes() {
+        let [session, csrf] = session_cookies(&config(), "tok", "xyz", 3600);
+        let session = session.to_str().unwrap();
+        let csrf = csrf.to_str().unwrap();
+
+        assert!(session.starts_with("cd_session=tok;"));
+        assert!(session.contains("HttpOnly"));
+        assert!(session.contains("SameSite=Strict"));
+        assert!(session.contains("Secure"));
+        assert!(csrf.starts_with("cd_csrf=xyz;"));
+        assert!(!csrf.contains("HttpOnly"));
+
+        let [cleared, _] = clear_session_cookies(&config());
+        assert!(cleared.to_str().unwrap().contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn test_validate_same_site() {
+        let mut config = config();
+        assert!(validate_config(&config).is_ok());
+        config.same_site = "lax".to_string();
+        assert!(validate_config(&config).is_ok());
+        config.same_site = "Loose".to_string();
+        assert!(validate_config(&config).is_err());
+    }
+}
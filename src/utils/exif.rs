@@ -0,0 +1,292 @@
+/// Tag in IFD0 pointing to the GPS IFD
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_GPS_LATITUDE_REF: u16 = 0x0001;
+const TAG_GPS_LATITUDE: u16 = 0x0002;
+const TAG_GPS_LONGITUDE_REF: u16 = 0x0003;
+const TAG_GPS_LONGITUDE: u16 = 0x0004;
+/// Tags in IFD1 locating the embedded JPEG thumbnail
+const TAG_THUMBNAIL_OFFSET: u16 = 0x0201;
+const TAG_THUMBNAIL_LENGTH: u16 = 0x0202;
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+/// GPS position of a JPEG or TIFF image as (latitude, longitude) in decimal degrees
+/// `data` only needs to cover the start of the file up to the EXIF block
+pub fn gps_position(data: &[u8]) -> Option<(f64, f64)> {
+    let tiff = if data.starts_with(&[0xff, 0xd8]) {
+        jpeg_exif(data)?
+    } else {
+        data
+    };
+    let tiff = Tiff::new(tiff)?;
+
+    let ifd0 = tiff.u32(4)? as usize;
+    let gps_ifd = tiff.entry(ifd0, TAG_GPS_IFD)?;
+    let gps_ifd = tiff.u32(gps_ifd + 8)? as usize;
+
+    let latitude = tiff.coordinate(gps_ifd, TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, b'S')?;
+    let longitude = tiff.coordinate(gps_ifd, TAG_GPS_LONGITUDE, TAG_GPS_LONGITUDE_REF, b'W')?;
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some((latitude, longitude))
+}
+
+/// JPEG thumbnail a camera embedded in the EXIF data of a JPEG or TIFF image
+/// `data` only needs to cover the start of the file up to the end of the EXIF block
+pub fn thumbnail(data: &[u8]) -> Option<&[u8]> {
+    let data = if data.starts_with(&[0xff, 0xd8]) {
+        jpeg_exif(data)?
+    } else {
+        data
+    };
+    let tiff = Tiff::new(data)?;
+
+    // IFD1 follows IFD0, it describes the thumbnail
+    let ifd0 = tiff.u32(4)? as usize;
+    let count = tiff.u16(ifd0)? as usize;
+    let ifd1 = tiff.u32(ifd0 + 2 + count * 12)? as usize;
+    if ifd1 == 0 {
+        return None;
+    }
+
+    let offset = tiff.number(ifd1, TAG_THUMBNAIL_OFFSET)? as usize;
+    let length = tiff.number(ifd1, TAG_THUMBNAIL_LENGTH)? as usize;
+    let jpeg = data.get(offset..offset.checked_add(length)?)?;
+    jpeg.starts_with(&[0xff, 0xd8]).then_some(jpeg)
+}
+
+/// TIFF data of the APP1 Exif segment, looked up before the image data starts
+fn jpeg_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xff {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Start of scan, no metadata segments follow
+        if marker == 0xda {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xe1 && segment.starts_with(b"Exif\0\0") {
+            return Some(&segment[6..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..4)? {
+            b"II*\0" => true,
+            b"MM\0*" => false,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            little_endian,
+        })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let b = self.data.get(offset..offset + 2)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let b = self.data.get(offset..offset + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    /// Offset of the 12-byte entry for `tag` in the IFD at `ifd`
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&e| self.u16(e) == Some(tag))
+    }
+
+    /// Single SHORT or LONG value of `tag`
+    fn number(&self, ifd: usize, tag: u16) -> Option<u32> {
+        let e = self.entry(ifd, tag)?;
+        if self.u32(e + 4)? != 1 {
+            return None;
+        }
+        match self.u16(e + 2)? {
+            TYPE_SHORT => self.u16(e + 8).map(u32::from),
+            TYPE_LONG => self.u32(e + 8),
+            _ => None,
+        }
+    }
+
+    /// Degrees, minutes and seconds rationals with their N/S or E/W reference
+    fn coordinate(&self, ifd: usize, tag: u16, ref_tag: u16, negative: u8) -> Option<f64> {
+        let e = self.entry(ifd, tag)?;
+        if self.u16(e + 2)? != TYPE_RATIONAL || self.u32(e + 4)? != 3 {
+            return None;
+        }
+        let values = self.u32(e + 8)? as usize;
+        let mut degrees = 0.0;
+        for (i, scale) in [1.0, 60.0, 3600.0].iter().enumerate() {
+            let numerator = self.u32(values + i * 8)? as f64;
+            let denominator = self.u32(values + i * 8 + 4)? as f64;
+            if denominator == 0.0 {
+                return None;
+            }
+            degrees += numerator / denominator / scale;
+        }
+
+        let r = self.entry(ifd, ref_tag)?;
+        if self.u16(r + 2)? != TYPE_ASCII {
+            return None;
+        }
+        // Short ASCII values are stored inline
+        let reference = *self.data.get(r + 8)?;
+        Some(if reference == negative {
+            -degrees
+        } else {
+            degrees
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Big-endian TIFF with IFD0 holding only a GPS IFD pointer
+    fn tiff_with_gps(
+        lat: [(u32, u32); 3],
+        lat_ref: u8,
+        lon: [(u32, u32); 3],
+        lon_ref: u8,
+    ) -> Vec<u8> {
+        let mut t = b"MM\0*".to_vec();
+        t.extend_from_slice(&8u32.to_be_bytes());
+        // IFD0 at 8: one entry, next IFD 0
+        t.extend_from_slice(&1u16.to_be_bytes());
+        t.extend_from_slice(&TAG_GPS_IFD.to_be_bytes());
+        t.extend_from_slice(&4u16.to_be_bytes());
+        t.extend_from_slice(&1u32.to_be_bytes());
+        t.extend_from_slice(&26u32.to_be_bytes());
+        t.extend_from_slice(&0u32.to_be_bytes());
+        // GPS IFD at 26: four entries, rationals after it at 26 + 2 + 48 + 4 = 80
+        t.extend_from_slice(&4u16.to_be_bytes());
+        for (tag, rational_offset, reference) in [
+            (TAG_GPS_LATITUDE_REF, 0, lat_ref),
+            (TAG_GPS_LATITUDE, 80, 0),
+            (TAG_GPS_LONGITUDE_REF, 0, lon_ref),
+            (TAG_GPS_LONGITUDE, 104, 0),
+        ] {
+            t.extend_from_slice(&tag.to_be_bytes());
+            if rational_offset == 0 {
+                t.extend_from_slice(&TYPE_ASCII.to_be_bytes());
+                t.extend_from_slice(&2u32.to_be_bytes());
+                t.extend_from_slice(&[reference, 0, 0, 0]);
+            } else {
+                t.extend_from_slice(&TYPE_RATIONAL.to_be_bytes());
+                t.extend_from_slice(&3u32.to_be_bytes());
+                t.extend_from_slice(&(rational_offset as u32).to_be_bytes());
+            }
+        }
+        t.extend_from_slice(&0u32.to_be_bytes());
+        for (n, d) in lat.iter().chain(lon.iter()) {
+            t.extend_from_slice(&n.to_be_bytes());
+            t.extend_from_slice(&d.to_be_bytes());
+        }
+        t
+    }
+
+    fn jpeg(tiff: &[u8]) -> Vec<u8> {
+        let mut j = vec![0xff, 0xd8];
+        // An unrelated APP0 segment first
+        j.extend_from_slice(&[0xff, 0xe0, 0, 4, 0, 0]);
+        j.extend_from_slice(&[0xff, 0xe1]);
+        j.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        j.extend_from_slice(b"Exif\0\0");
+        j.extend_from_slice(tiff);
+        j.extend_from_slice(&[0xff, 0xda, 0, 2]);
+        j
+    }
+
+    #[test]
+    fn test_gps_position() {
+        let tiff = tiff_with_gps(
+            [(48, 1), (51, 1), (2940, 100)],
+            b'N',
+            [(2, 1), (17, 1), (4020, 100)],
+            b'E',
+        );
+        let (lat, lon) = gps_position(&jpeg(&tiff)).unwrap();
+        assert!((lat - 48.858167).abs() < 1e-5);
+        assert!((lon - 2.294500).abs() < 1e-5);
+
+        let tiff = tiff_with_gps(
+            [(33, 1), (0, 1), (0, 1)],
+            b'S',
+            [(70, 1), (30, 1), (0, 1)],
+            b'W',
+        );
+        assert_eq!(gps_position(&tiff), Some((-33.0, -70.5)));
+
+        // Stripped metadata or no EXIF at all
+        assert_eq!(gps_position(&jpeg(b"MM\0*\0\0\0\x08\0\0\0\0\0\0")), None);
+        assert_eq!(gps_position(&[0xff, 0xd8, 0xff, 0xda, 0, 2]), None);
+        assert_eq!(gps_position(b"\x89PNG"), None);
+    }
+
+    /// Little-endian TIFF with an empty IFD0 and an IFD1 pointing at `thumbnail`
+    fn tiff_with_thumbnail(thumbnail: &[u8]) -> Vec<u8> {
+        let mut t = b"II*\0".to_vec();
+        t.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0 at 8: no entries, IFD1 at 14
+        t.extend_from_slice(&0u16.to_le_bytes());
+        t.extend_from_slice(&14u32.to_le_bytes());
+        // IFD1 at 14: two entries, the thumbnail after it at 14 + 2 + 24 + 4 = 44
+        t.extend_from_slice(&2u16.to_le_bytes());
+        for (tag, kind, value) in [
+            (TAG_THUMBNAIL_OFFSET, TYPE_LONG, 44u32),
+            (TAG_THUMBNAIL_LENGTH, TYPE_SHORT, thumbnail.len() as u32),
+        ] {
+            t.extend_from_slice(&tag.to_le_bytes());
+            t.extend_from_slice(&kind.to_le_bytes());
+            t.extend_from_slice(&1u32.to_le_bytes());
+            t.extend_from_slice(&value.to_le_bytes());
+        }
+        t.extend_from_slice(&0u32.to_le_bytes());
+        t.extend_from_slice(thumbnail);
+        t
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        let jpeg_thumbnail = [0xff, 0xd8, 0xff, 0xd9];
+        let tiff = tiff_with_thumbnail(&jpeg_thumbnail);
+        assert_eq!(thumbnail(&jpeg(&tiff)), Some(&jpeg_thumbnail[..]));
+        assert_eq!(thumbnail(&tiff), Some(&jpeg_thumbnail[..]));
+
+        // Not a JPEG, cut off data or no IFD1
+        assert_eq!(thumbnail(&tiff_with_thumbnail(b"\x89PNG")), None);
+        assert_eq!(thumbnail(&tiff[..tiff.len() - 1]), None);
+        let tiff = tiff_with_gps([(1, 1); 3], b'N', [(1, 1); 3], b'E');
+        assert_eq!(thumbnail(&jpeg(&tiff)), None);
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// Join fields into one CSV line (RFC 4180 quoting), terminated by CRLF
+pub fn csv_row<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+/// Fields a spreadsheet would run as a formula are prefixed with `'` so they stay text
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Parse a date range bound given as "YYYY-MM-DD", "YYYY-MM-DD HH:MM:SS" (UTC) or RFC 3339
+/// A bare date as upper bound covers the whole day
+pub fn parse_time_bound(value: &str, upper: bool) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.naive_utc());
+    }
+    if let Ok(t) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Some(t);
+    }
+    if let Ok(t) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Some(t);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    if upper {
+        date.and_hms_milli_opt(23, 59, 59, 999)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_row() {
+        assert_eq!(csv_row(&["a", "b"]), "a,b\r\n");
+        assert_eq!(
+            csv_row(&["x,y", "say \"hi\"", "line\nbreak"]),
+            "\"x,y\",\"say \"\"hi\"\"\",\"line\nbreak\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_formulas_are_neutralized() {
+        assert_eq!(
+            csv_row(&[
+                "=HYPERLINK(\"http://x\")",
+                "+1",
+                "-1",
+                "@SUM(A1)",
+                "\tcmd",
+                "a=b"
+            ]),
+            "\"'=HYPERLINK(\"\"http://x\"\")\",'+1,'-1,'@SUM(A1),'\tcmd,a=b\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_time_bound() {
+        let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(
+            parse_time_bound("2024-05-01", false),
+            day.and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(
+            parse_time_bound("2024-05-01", true),
+            day.and_hms_milli_opt(23, 59, 59, 999)
+        );
+        assert_eq!(
+            parse_time_bound("2024-05-01 12:30:00", true),
+            day.and_hms_opt(12, 30, 0)
+        );
+        assert_eq!(
+            parse_time_bound("2024-05-01T14:30:00+02:00", false),
+            day.and_hms_opt(12, 30, 0)
+        );
+        assert!(parse_time_bound("yesterday", false).is_none());
+    }
+}
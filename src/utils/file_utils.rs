@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
 
 /// Sanitize and validate path to prevent path traversal attacks
 pub fn sanitize_path(path: &str) -> Result<String> {
@@ -25,7 +26,32 @@ pub fn sanitize_path(path: &str) -> Result<String> {
     // Note: On Windows, PathBuf uses \, so we manually handle it
     let clean_path = path.replace("//", "/");
 
-    Ok(clean_path)
+    Ok(normalize_name(&clean_path))
+}
+
+/// Unicode NFC form of a name or path, so "café" typed on macOS and Linux is the same file
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// List every folder path from the root down to `parent_path`, excluding the root itself
+/// Examples:
+/// - "/" -> []
+/// - "/a/b" -> ["/a", "/a/b"]
+pub fn ancestor_paths(parent_path: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut current = String::new();
+    for segment in parent_path.split('/').filter(|s| !s.is_empty()) {
+        current.push('/');
+        current.push_str(segment);
+        paths.push(current.clone());
+    }
+    paths
+}
+
+/// Number of segments in a path ("/" -> 0, "/a/b" -> 2)
+pub fn path_depth(path: &str) -> usize {
+    path.split('/').filter(|s| !s.is_empty()).count()
 }
 
 /// Split filename into (base_name, extension)
@@ -40,15 +66,28 @@ pub fn split_filename(filename: &str) -> (&str, &str) {
     }
 }
 
+/// Name for an upload that lost a sync conflict, e.g. "report (conflicted copy 2024-05-01 bob).pdf"
+pub fn conflicted_copy_name(filename: &str, date: &str, username: &str) -> String {
+    let (base_name, extension) = split_filename(filename);
+    if extension.is_empty() {
+        format!("{} (conflicted copy {} {})", base_name, date, username)
+    } else {
+        format!(
+            "{} (conflicted copy {} {}).{}",
+            base_name, date, username, extension
+        )
+    }
+}
+
 /// Get user storage root directory
 pub fn get_user_storage_path(storage_root: &Path, user_id: i32) -> PathBuf {
     storage_root.join(user_id.to_string())
 }
 
 /// Ensure user directory exists
-pub fn ensure_user_directory(storage_root: &Path, user_id: i32) -> Result<PathBuf> {
+pub async fn ensure_user_directory(storage_root: &Path, user_id: i32) -> Result<PathBuf> {
     let user_dir = get_user_storage_path(storage_root, user_id);
-    std::fs::create_dir_all(&user_dir)?;
+    tokio::fs::create_dir_all(&user_dir).await?;
     Ok(user_dir)
 }
 
@@ -126,6 +165,46 @@ pub fn format_file_size(bytes: i64) -> String {
     format!("{:.1} {}", size, UNITS[exp])
 }
 
+/// Whether `path` is the root or a system folder, which users cannot create, change or shadow
+pub fn is_protected_path(path: &str) -> bool {
+    path == "/"
+        || crate::constants::SYSTEM_PATHS
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(path))
+}
+
+/// Whether `path` is `root` itself or lies somewhere below it
+pub fn in_subtree(path: &str, root: &str) -> bool {
+    let root = root.trim_end_matches('/');
+    path == root || path.starts_with(&format!("{}/", root))
+}
+
+/// LIKE pattern, escaped with a backslash, matching every path below `folder_path`
+pub fn descendant_pattern(folder_path: &str) -> String {
+    let mut pattern = String::with_capacity(folder_path.len() + 2);
+    for c in folder_path.trim_end_matches('/').chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str("/%");
+    pattern
+}
+
+/// Strong entity tag for one version of a file record
+pub fn file_etag(file_id: i32, version: i32) -> String {
+    format!("\"{}-{}\"", file_id, version)
+}
+
+/// Check an If-Match header value ("*" or a list of entity tags) against the current tag
+pub fn if_match_satisfied(if_match: &str, etag: &str) -> bool {
+    if_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,6 +215,77 @@ mod tests {
         assert_eq!(sanitize_path("valid/path").unwrap(), "/valid/path");
         assert!(sanitize_path("/../etc/passwd").is_err());
         assert!(sanitize_path("/path/../secret").is_err());
+        assert_eq!(sanitize_path("/cafe\u{301}").unwrap(), "/caf\u{e9}");
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("cafe\u{301}.txt"), "caf\u{e9}.txt");
+        assert_eq!(normalize_name("caf\u{e9}.txt"), "caf\u{e9}.txt");
+        assert_eq!(normalize_name("plain.txt"), "plain.txt");
+    }
+
+    #[test]
+    fn test_conflicted_copy_name() {
+        assert_eq!(
+            conflicted_copy_name("report.pdf", "2024-05-01", "bob"),
+            "report (conflicted copy 2024-05-01 bob).pdf"
+        );
+        assert_eq!(
+            conflicted_copy_name("Makefile", "2024-05-01", "bob"),
+            "Makefile (conflicted copy 2024-05-01 bob)"
+        );
+    }
+
+    #[test]
+    fn test_ancestor_paths() {
+        assert!(ancestor_paths("/").is_empty());
+        assert_eq!(ancestor_paths("/docs"), vec!["/docs"]);
+        assert_eq!(ancestor_paths("/docs/2024/"), vec!["/docs", "/docs/2024"]);
+    }
+
+    #[test]
+    fn test_path_depth() {
+        assert_eq!(path_depth("/"), 0);
+        assert_eq!(path_depth("/docs"), 1);
+        assert_eq!(path_depth("/docs/2024/report.pdf"), 3);
+    }
+
+    #[test]
+    fn test_is_protected_path() {
+        assert!(is_protected_path("/"));
+        assert!(is_protected_path("/.trash"));
+        assert!(is_protected_path("/.Trash"));
+        assert!(!is_protected_path("/.trash/old.txt"));
+        assert!(!is_protected_path("/trash"));
+    }
+
+    #[test]
+    fn test_in_subtree() {
+        assert!(in_subtree("/a", "/a"));
+        assert!(in_subtree("/a/b/c", "/a"));
+        assert!(in_subtree("/a/b", "/"));
+        assert!(!in_subtree("/ab", "/a"));
+        assert!(!in_subtree("/", "/a"));
+    }
+
+    #[test]
+    fn test_descendant_pattern() {
+        assert_eq!(descendant_pattern("/docs"), "/docs/%");
+        assert_eq!(descendant_pattern("/docs/"), "/docs/%");
+        assert_eq!(descendant_pattern("/"), "/%");
+        assert_eq!(descendant_pattern("/100%_done"), "/100\\%\\_done/%");
+        assert_eq!(descendant_pattern("/a\\b"), "/a\\\\b/%");
+    }
+
+    #[test]
+    fn test_if_match_satisfied() {
+        let etag = file_etag(7, 3);
+        assert_eq!(etag, "\"7-3\"");
+        assert!(if_match_satisfied("*", &etag));
+        assert!(if_match_satisfied("\"7-2\", \"7-3\"", &etag));
+        assert!(!if_match_satisfied("\"7-2\"", &etag));
+        assert!(!if_match_satisfied("W/\"7-3\"", &etag));
     }
 
     #[test]
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Expires dates are capped at one year ahead, as HTTP/1.1 recommends
+const MAX_EXPIRES_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Whether a MIME pattern (`image/png`, `image/*` or `*`) covers a MIME type
+pub fn mime_matches(pattern: &str, mime_type: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let mime = mime_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime.starts_with(prefix),
+        None => mime == pattern,
+    }
+}
+
+/// Lifetime a `Cache-Control` value allows, `None` when it forbids caching or sets no max-age
+pub fn max_age(cache_control: &str) -> Option<u64> {
+    let directives: Vec<String> = cache_control
+        .split(',')
+        .map(|d| d.trim().to_ascii_lowercase())
+        .collect();
+
+    if directives
+        .iter()
+        .any(|d| d == "no-store" || d == "no-cache")
+    {
+        return None;
+    }
+    directives
+        .iter()
+        .find_map(|d| d.strip_prefix("max-age="))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// `Expires` value matching a `Cache-Control` value, in the past when it may not be cached
+pub fn expires(cache_control: &str, now: DateTime<Utc>) -> String {
+    let at = match max_age(cache_control) {
+        Some(secs) => now + Duration::seconds(secs.min(MAX_EXPIRES_SECS) as i64),
+        None => DateTime::<Utc>::UNIX_EPOCH,
+    };
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_mime_matches() {
+        assert!(mime_matches("image/*", "image/png"));
+        assert!(mime_matches("text/csv", "text/csv; charset=utf-8"));
+        assert!(mime_matches("*", "application/pdf"));
+        assert!(!mime_matches("image/*", "application/pdf"));
+        assert!(!mime_matches("text/csv", "text/plain"));
+    }
+
+    #[test]
+    fn test_expires() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            expires("private, max-age=3600", now),
+            "Mon, 01 Jan 2024 01:00:00 GMT"
+        );
+        assert_eq!(expires("no-store", now), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(max_age("public, max-age=60, no-cache"), None);
+        assert_eq!(max_age("max-age=31536000, immutable"), Some(31536000));
+    }
+}
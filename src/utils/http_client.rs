@@ -0,0 +1,54 @@
+use axum::{
+    body::Bytes,
+    http::{header, HeaderName, Method, Request, StatusCode, Uri},
+};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper_util::rt::TokioIo;
+
+/// Send one request to a plain HTTP endpoint and read at most `max_response` bytes of the answer
+/// Meant for services next to the server, e.g. processing hooks or a search engine
+pub async fn send(
+    method: Method,
+    url: &str,
+    headers: &[(HeaderName, String)],
+    body: Vec<u8>,
+    max_response: usize,
+) -> Result<(StatusCode, Bytes), String> {
+    let uri: Uri = url.parse().map_err(|e| format!("invalid URL: {}", e))?;
+    if uri.scheme_str() != Some("http") {
+        return Err("only http:// URLs are supported".to_string());
+    }
+    let host = uri.host().ok_or("URL has no host")?;
+    let port = uri.port_u16().unwrap_or(80);
+
+    let stream = tokio::net::TcpStream::connect((host, port))
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(|e| e.to_string())?;
+    tokio::spawn(conn);
+
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let mut request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::HOST, uri.authority().map_or(host, |a| a.as_str()));
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let request = request
+        .body(Full::new(Bytes::from(body)))
+        .map_err(|e| e.to_string())?;
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let body = Limited::new(response.into_body(), max_response)
+        .collect()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((status, body.to_bytes()))
+}
@@ -0,0 +1,501 @@
+/// Languages responses can be translated into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Language {
+    /// Value of the `Content-Language` header
+    pub fn tag(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Zh => "zh-CN",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next().unwrap_or("").to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Language::En),
+            "zh" => Some(Language::Zh),
+            _ => None,
+        }
+    }
+}
+
+tokio::task_local! {
+    static LANGUAGE: Language;
+}
+
+/// Run `f` with `language` as the language of every response message it builds
+pub async fn scope<F: std::future::Future>(language: Language, f: F) -> F::Output {
+    LANGUAGE.scope(language, f).await
+}
+
+/// Language of the request being handled, English outside a request
+pub fn current() -> Language {
+    LANGUAGE.try_with(|l| *l).unwrap_or_default()
+}
+
+/// Pick the preferred supported language from an `Accept-Language` header
+/// Falls back to English when nothing matches
+pub fn negotiate(accept_language: &str) -> Language {
+    let mut best: Option<(Language, f32)> = None;
+
+    for part in accept_language.split(',') {
+        let mut params = part.split(';');
+        let tag = params.next().unwrap_or("").trim();
+        let quality = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        if quality <= 0.0 {
+            continue;
+        }
+        if let Some(language) = Language::from_tag(tag) {
+            if best.is_none_or(|(_, q)| quality > q) {
+                best = Some((language, quality));
+            }
+        }
+    }
+
+    best.map(|(language, _)| language).unwrap_or_default()
+}
+
+/// A response message and its translations
+#[derive(Debug)]
+pub struct Message {
+    /// Stable identifier clients can match on instead of the text
+    pub code: &'static str,
+    en: &'static str,
+    zh: &'static str,
+}
+
+impl Message {
+    pub fn text(&self, language: Language) -> &'static str {
+        match language {
+            Language::En => self.en,
+            Language::Zh => self.zh,
+        }
+    }
+}
+
+/// English text, for logs and errors passed along before a response is built
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.en)
+    }
+}
+
+impl std::error::Error for Message {}
+
+/// Message of a response: a catalog entry rendered in the request language, or text sent as is
+#[derive(Debug)]
+pub enum Text {
+    Catalog(&'static Message),
+    Plain(String),
+}
+
+impl Text {
+    /// Code and text of the message in the language of the request being handled
+    pub fn render(self) -> (Option<&'static str>, String) {
+        match self {
+            Text::Catalog(message) => (Some(message.code), message.text(current()).to_string()),
+            Text::Plain(text) => (None, text),
+        }
+    }
+}
+
+impl From<&'static Message> for Text {
+    fn from(message: &'static Message) -> Self {
+        Text::Catalog(message)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Text::Plain(text)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Text::Plain(text.to_string())
+    }
+}
+
+/// Errors raised with a catalog entry keep it, any other error is sent as its text
+impl From<anyhow::Error> for Text {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast_ref::<&'static Message>() {
+            Some(message) => Text::Catalog(message),
+            None => Text::Plain(e.to_string()),
+        }
+    }
+}
+
+macro_rules! messages {
+    ($($name:ident = $code:literal => $en:literal, $zh:literal;)*) => {
+        $(pub static $name: Message = Message { code: $code, en: $en, zh: $zh };)*
+
+        /// Every catalog entry
+        #[cfg(test)]
+        static MESSAGES: &[&Message] = &[$(&$name,)*];
+    };
+}
+
+// Message catalog, handlers respond with these entries and the text follows the request language
+messages! {
+    // Generic
+    COMMON_INTERNAL_ERROR = "common.internal_error" => "Internal server error", "服务器内部错误";
+    COMMON_DATABASE_ERROR = "common.database_error" => "Database error occurred", "数据库错误";
+    COMMON_INVALID_USER_ID = "common.invalid_user_id" => "Invalid user ID", "无效的用户 ID";
+    COMMON_INVALID_REQUEST = "common.invalid_request" => "Invalid request format", "请求格式无效";
+    COMMON_READ_REQUEST_FAILED = "common.read_request_failed" => "Failed to read request", "读取请求失败";
+    COMMON_PERMISSION_CHECK_FAILED = "common.permission_check_failed" => "Permission check failed", "权限检查失败";
+    COMMON_PERMISSION_DENIED = "common.permission_denied" => "Permission denied", "权限不足";
+    COMMON_REQUEST_TIMEOUT = "common.request_timeout" => "Request timed out", "请求超时";
+    COMMON_SERVER_BUSY = "common.server_busy" => "Server is busy, retry later", "服务器繁忙，请稍后重试";
+    COMMON_COMING_SOON_REVOKE_PERMISSION = "common.coming_soon.revoke_permission" => "Revoke permission feature coming soon", "撤销权限功能即将推出";
+    COMMON_COMING_SOON_LIST_PERMISSIONS = "common.coming_soon.list_permissions" => "List permissions feature coming soon", "权限列表功能即将推出";
+
+    // Authentication
+    AUTH_REQUIRED = "auth.required" => "Authentication required", "需要登录";
+    AUTH_MISSING_HEADER = "auth.missing_header" => "Missing authorization header", "缺少授权头";
+    AUTH_INVALID_HEADER = "auth.invalid_header" => "Invalid authorization header format", "授权头格式无效";
+    AUTH_INVALID_TOKEN = "auth.invalid_token" => "Invalid or expired token", "令牌无效或已过期";
+    AUTH_TOKEN_REVOKED = "auth.token_revoked" => "Token has been revoked", "令牌已被撤销";
+    AUTH_INVALID_CSRF = "auth.invalid_csrf" => "Invalid or missing CSRF token", "CSRF 令牌无效或缺失";
+    AUTH_INVALID_CREDENTIALS = "auth.invalid_credentials" => "Invalid username or password", "用户名或密码错误";
+    AUTH_LOGIN_SUCCESS = "auth.login_success" => "Login completed successfully", "登录成功";
+    AUTH_LOGOUT_SUCCESS = "auth.logout_success" => "Logged out successfully", "已退出登录";
+    AUTH_LOGOUT_ALL_SUCCESS = "auth.logout_all_success" => "Logged out of all sessions", "已退出所有会话";
+    AUTH_COOKIE_SESSIONS_DISABLED = "auth.cookie_sessions_disabled" => "Cookie sessions are not enabled", "未启用 Cookie 会话";
+    AUTH_USERNAME_TAKEN = "auth.username_taken" => "Username already exists", "用户名已存在";
+    AUTH_EMAIL_TAKEN = "auth.email_taken" => "Email already exists", "邮箱已被使用";
+
+    // Users
+    USER_NOT_FOUND = "user.not_found" => "User not found", "用户不存在";
+    USER_PROFILE_RETRIEVED = "user.profile_retrieved" => "User profile retrieved", "已获取用户资料";
+    USER_CURRENT_PASSWORD_INCORRECT = "user.current_password_incorrect" => "Current password is incorrect", "当前密码不正确";
+    USER_PASSWORD_CHANGED = "user.password_changed" => "Password changed successfully", "密码修改成功";
+    USER_SECURITY_LOG_RETRIEVED = "user.security_log_retrieved" => "Security log retrieved", "已获取安全日志";
+    USER_SETTINGS_RETRIEVED = "user.settings_retrieved" => "Settings retrieved", "已获取设置";
+    USER_SETTINGS_UPDATED = "user.settings_updated" => "Settings updated", "设置已更新";
+    USER_PROFILE_UPDATED = "user.profile_updated" => "Profile updated", "资料已更新";
+    USER_EMAIL_CHANGE_PENDING = "user.email_change_pending" => "Profile updated, check your new email address to confirm the change", "资料已更新，请查收新邮箱中的邮件以确认更改";
+    USER_EMAIL_VERIFIED = "user.email_verified" => "Email address verified", "邮箱已验证";
+    USER_INVALID_EMAIL_TOKEN = "user.invalid_email_token" => "Invalid or expired verification token", "验证令牌无效或已过期";
+    USER_PASSWORD_INCORRECT = "user.password_incorrect" => "Password is incorrect", "密码不正确";
+    USER_DELETION_SCHEDULED = "user.deletion_scheduled" => "Account deletion scheduled", "已安排删除账户";
+    USER_DELETION_ALREADY_SCHEDULED = "user.deletion_already_scheduled" => "Account deletion is already scheduled", "账户已在等待删除";
+    USER_DELETION_NOT_SCHEDULED = "user.deletion_not_scheduled" => "Account deletion is not scheduled", "账户未安排删除";
+    USER_DELETION_CANCELLED = "user.deletion_cancelled" => "Account deletion cancelled", "已取消删除账户";
+    USER_LAST_ADMIN = "user.last_admin" => "The last administrator account cannot be deleted", "不能删除最后一个管理员账户";
+
+    // Announcements
+    ANNOUNCEMENT_ADMIN_ONLY = "announcement.admin_only" => "Only administrators can manage announcements", "只有管理员可以管理公告";
+    ANNOUNCEMENT_REQUIRED_FIELDS = "announcement.required_fields" => "Announcement title and body are required", "公告标题和内容不能为空";
+    ANNOUNCEMENT_TITLE_TOO_LONG = "announcement.title_too_long" => "Announcement title is too long", "公告标题过长";
+    ANNOUNCEMENT_INVALID_SEVERITY = "announcement.invalid_severity" => "Severity must be info, warning or critical", "级别必须为 info、warning 或 critical";
+    ANNOUNCEMENT_INVALID_WINDOW = "announcement.invalid_window" => "Invalid announcement time window", "公告时间范围无效";
+    ANNOUNCEMENT_PUBLISHED = "announcement.published" => "Announcement published", "公告已发布";
+    ANNOUNCEMENT_LIST_RETRIEVED = "announcement.list_retrieved" => "Announcements retrieved", "已获取公告";
+    ANNOUNCEMENT_NOT_FOUND = "announcement.not_found" => "Announcement not found", "公告不存在";
+    ANNOUNCEMENT_REMOVED = "announcement.removed" => "Announcement removed", "公告已删除";
+
+    // Files
+    FILE_NOT_FOUND = "file.not_found" => "File not found", "文件不存在";
+    FILE_FOLDER_NOT_FOUND = "file.folder_not_found" => "Folder not found", "文件夹不存在";
+    FILE_MODIFIED = "file.modified" => "File has been modified, reload it and retry", "文件已被修改，请刷新后重试";
+    FILE_NAME_CONFLICT = "file.name_conflict" => "A file with this name already exists", "已存在同名文件";
+    FILE_NAME_CONFLICT_DESTINATION = "file.name_conflict_destination" => "A file with this name already exists in destination", "目标位置已存在同名文件";
+    FILE_NAME_HAS_SEPARATOR = "file.name_has_separator" => "File name cannot contain path separators", "文件名不能包含路径分隔符";
+    FILE_PINNED = "file.pinned" => "File pinned", "文件已固定";
+    FILE_UNPINNED = "file.unpinned" => "File unpinned", "文件已取消固定";
+    FILE_STARRED = "file.starred" => "File starred", "文件已加星标";
+    FILE_UNSTARRED = "file.unstarred" => "File unstarred", "文件已取消星标";
+    FILE_BULK_TAG_COMPLETED = "file.bulk_tag_completed" => "Bulk tag completed", "批量标签完成";
+    FILE_BULK_STAR_COMPLETED = "file.bulk_star_completed" => "Bulk star completed", "批量星标完成";
+    FILE_BULK_TAGS_EMPTY = "file.bulk_tags_empty" => "At least one tag to add or remove is required", "至少需要一个要添加或移除的标签";
+    FILE_BULK_TARGET_MISSING = "file.bulk_target_missing" => "Either file_ids or query is required", "需要提供 file_ids 或 query";
+    FILE_BULK_TARGET_BOTH = "file.bulk_target_both" => "Give either file_ids or query, not both", "file_ids 和 query 只能提供其中之一";
+    FILE_HOME_RETRIEVED = "file.home_retrieved" => "Home feed retrieved", "已获取首页动态";
+    FILE_STYLE_FOLDERS_ONLY = "file.style_folders_only" => "Only folders can be styled", "只有文件夹可以设置样式";
+    FILE_STYLE_UPDATED = "file.style_updated" => "Folder style updated", "文件夹样式已更新";
+    CLEANUP_RULES_RETRIEVED = "cleanup.rules_retrieved" => "Cleanup rules retrieved", "已获取清理规则";
+    CLEANUP_RULE_CREATED = "cleanup.rule_created" => "Cleanup rule created", "清理规则已创建";
+    CLEANUP_RULE_UPDATED = "cleanup.rule_updated" => "Cleanup rule updated", "清理规则已更新";
+    CLEANUP_RULE_DELETED = "cleanup.rule_deleted" => "Cleanup rule deleted", "清理规则已删除";
+    CLEANUP_RULE_NOT_FOUND = "cleanup.rule_not_found" => "Cleanup rule not found", "清理规则不存在";
+    CLEANUP_NAME_FILTER_TOO_LONG = "cleanup.name_filter_too_long" => "Name filter is too long", "名称筛选过长";
+    ORGANIZE_RULES_RETRIEVED = "organize.rules_retrieved" => "Organize rules retrieved", "已获取整理规则";
+    ORGANIZE_RULE_CREATED = "organize.rule_created" => "Organize rule created", "整理规则已创建";
+    ORGANIZE_RULE_UPDATED = "organize.rule_updated" => "Organize rule updated", "整理规则已更新";
+    ORGANIZE_RULE_DELETED = "organize.rule_deleted" => "Organize rule deleted", "整理规则已删除";
+    ORGANIZE_RULE_NOT_FOUND = "organize.rule_not_found" => "Organize rule not found", "整理规则不存在";
+    ORGANIZE_PREVIEW_GENERATED = "organize.preview_generated" => "Organize preview generated", "已生成整理预览";
+    ORGANIZE_MIME_PREFIX_TOO_LONG = "organize.mime_prefix_too_long" => "MIME type prefix is too long", "MIME 类型前缀过长";
+    ORGANIZE_INVALID_DESTINATION = "organize.invalid_destination" => "Destination must be an absolute folder path", "目标必须是绝对文件夹路径";
+    ORGANIZE_INVALID_PLACEHOLDER = "organize.invalid_placeholder" => "Destination placeholders are {YYYY}, {MM} and {DD}", "目标路径占位符只能是 {YYYY}、{MM} 和 {DD}";
+    ORGANIZE_SAME_DESTINATION = "organize.same_destination" => "Destination must differ from the folder", "目标不能与文件夹相同";
+    FILE_INVALID_COLOR = "file.invalid_color" => "Color must be a hex color like #3b82f6", "颜色必须是类似 #3b82f6 的十六进制颜色";
+    FILE_LISTED = "file.listed" => "Files retrieved successfully", "已获取文件列表";
+    FILE_TREE_RETRIEVED = "file.tree_retrieved" => "File tree retrieved successfully", "已获取文件树";
+    FILE_STAT_RETRIEVED = "file.stat_retrieved" => "File stat retrieved successfully", "已获取文件信息";
+    FILE_METADATA_UPDATED = "file.metadata_updated" => "File metadata updated", "文件元数据已更新";
+    FILE_FOLDER_CREATED = "file.folder_created" => "Folder created successfully", "文件夹创建成功";
+    FILE_FOLDER_EXISTS = "file.folder_exists" => "Folder already exists", "文件夹已存在";
+    FILE_CREATE_FOLDER_FAILED = "file.create_folder_failed" => "Failed to create folder", "创建文件夹失败";
+    FILE_RENAMED = "file.renamed" => "File renamed successfully", "文件重命名成功";
+    FILE_RENAME_FAILED = "file.rename_failed" => "Failed to rename file", "文件重命名失败";
+    FILE_MOVED = "file.moved" => "File moved successfully", "文件移动成功";
+    FILE_MOVE_FAILED = "file.move_failed" => "Failed to move file", "文件移动失败";
+    FILE_MOVE_ACROSS_MOUNT = "file.move_across_mount" => "Items cannot be moved into or out of a mount, copy them instead", "不能将项目移入或移出挂载点，请改用复制";
+    FILE_COPIED = "file.copied" => "File copied successfully", "文件复制成功";
+    FILE_COPY_FAILED = "file.copy_failed" => "Failed to copy file", "文件复制失败";
+    FILE_SIZE_CALCULATED = "file.size_calculated" => "Size calculated successfully", "大小计算完成";
+    FILE_SIZE_FAILED = "file.size_failed" => "Failed to calculate folder size", "计算文件夹大小失败";
+    FILE_READ_FAILED = "file.read_failed" => "Failed to read file", "读取文件失败";
+    FILE_NO_UPLOAD = "file.no_upload" => "No file uploaded", "未上传文件";
+    FILE_INVALID_ON_CONFLICT = "file.invalid_on_conflict" => "Invalid on_conflict, use rename, overwrite, fail or skip-if-same-hash", "on_conflict 无效，请使用 rename、overwrite、fail 或 skip-if-same-hash";
+    FILE_INVALID_CLIENT_MODIFIED = "file.invalid_client_modified" => "Invalid client_modified", "client_modified 无效";
+    FILE_INVALID_FILE_NAME = "file.invalid_file_name" => "Invalid file name", "文件名无效";
+    FILE_MOVE_INTO_ITSELF = "file.move_into_itself" => "Cannot move a folder into itself or one of its subfolders", "不能将文件夹移动到其自身或其子文件夹中";
+    FILE_COPY_INTO_ITSELF = "file.copy_into_itself" => "Cannot copy a folder into itself or one of its subfolders", "不能将文件夹复制到其自身或其子文件夹中";
+    FILE_NAME_INVALID_CHARS = "file.name_invalid_chars" => "File name contains invalid characters", "文件名包含无效字符";
+    FILE_NAME_TRAILING_DOT_SPACE = "file.name_trailing_dot_space" => "File name cannot end with a dot or space", "文件名不能以点或空格结尾";
+    UPLOAD_PRECHECK_COMPLETED = "upload.precheck_completed" => "Upload pre-check completed", "上传预检已完成";
+    UPLOAD_SESSION_STARTED = "upload.session_started" => "Upload session started", "上传会话已创建";
+    UPLOAD_SESSION_FAILED = "upload.session_failed" => "Failed to start upload session", "创建上传会话失败";
+    UPLOAD_SESSION_NOT_FOUND = "upload.session_not_found" => "Upload session not found", "未找到上传会话";
+    UPLOAD_SESSIONS_RETRIEVED = "upload.sessions_retrieved" => "Upload sessions retrieved", "已获取上传会话";
+    UPLOAD_CHUNK_RECEIVED = "upload.chunk_received" => "Chunk received", "分块已接收";
+    UPLOAD_CHUNK_FAILED = "upload.chunk_failed" => "Failed to store chunk", "保存分块失败";
+    UPLOAD_CHUNK_DUPLICATE = "upload.chunk_duplicate" => "Chunk already received", "分块已接收过";
+    UPLOAD_CHUNK_OVERLAP = "upload.chunk_overlap" => "Chunk overlaps data already received", "分块与已接收的数据重叠";
+    UPLOAD_CHUNK_CHECKSUM = "upload.chunk_checksum" => "Chunk checksum does not match, send it again", "分块校验和不匹配，请重新发送";
+    UPLOAD_VERIFICATION_FAILED = "upload.verification_failed" => "Some chunks failed verification, send the missing ranges again", "部分分块校验失败，请重新发送缺失的范围";
+    UPLOAD_FILE_CHECKSUM = "upload.file_checksum" => "The assembled file does not match the given checksum", "组装后的文件与给定的校验和不匹配";
+    UPLOAD_CHUNK_TOO_LARGE = "upload.chunk_too_large" => "Chunk exceeds the declared file size", "分块超出声明的文件大小";
+    UPLOAD_INCOMPLETE = "upload.incomplete" => "Upload is incomplete", "上传尚未完成";
+    UPLOAD_READ_STAGED_FAILED = "upload.read_staged_failed" => "Failed to read staged upload", "读取暂存的上传数据失败";
+    UPLOAD_CANCELLED = "upload.cancelled" => "Upload cancelled", "上传已取消";
+    UPLOAD_ROLE_LIMIT_EXCEEDED = "upload.role_limit_exceeded" => "Upload exceeds the size allowed for your account", "上传大小超出您的账户限制";
+    FILE_CREATE_DIRECTORY_FAILED = "file.create_directory_failed" => "Failed to create destination directory", "创建目标目录失败";
+    FILE_UNIQUE_NAME_FAILED = "file.unique_name_failed" => "Failed to generate unique filename", "生成唯一文件名失败";
+    FILE_CHANGES_RETRIEVED = "file.changes_retrieved" => "Changes retrieved successfully", "已获取变更记录";
+    FILE_OWN_FILES_ONLY = "file.own_files_only" => "You can only view your own files", "只能查看自己的文件";
+    FILE_INVALID_CHANGES_ORDER = "file.invalid_changes_order" => "Invalid order, use cursor or modified", "order 无效，请使用 cursor 或 modified";
+    FILE_OWN_CHANGES_ONLY = "file.own_changes_only" => "You can only view your own changes", "只能查看自己的变更记录";
+    FILE_ID_OR_PATH_REQUIRED = "file.id_or_path_required" => "Either file_id or path is required", "必须提供 file_id 或 path";
+    FILE_UNSUPPORTED_TREE_FORMAT = "file.unsupported_tree_format" => "Unsupported format, use nested or flat", "不支持的格式，请使用 nested 或 flat";
+    FILE_NO_PERMISSION_CREATE_FOLDER = "file.no_permission_create_folder" => "You don't have permission to create folders here", "您无权在此创建文件夹";
+    FILE_NO_PERMISSION_DELETE = "file.no_permission_delete" => "You don't have permission to delete this file", "您无权删除此文件";
+    FILE_NO_PERMISSION_RENAME = "file.no_permission_rename" => "You don't have permission to rename this file", "您无权重命名此文件";
+    FILE_NO_PERMISSION_MOVE = "file.no_permission_move" => "You don't have permission to move this file", "您无权移动此文件";
+    FILE_NO_PERMISSION_COPY = "file.no_permission_copy" => "You don't have permission to copy this file", "您无权复制此文件";
+    FILE_LEGAL_HOLD = "file.legal_hold" => "This file is under legal hold and cannot be changed", "该文件处于法律保留状态，无法修改";
+    FILE_PROTECTED_PATH = "file.protected_path" => "The root folder and system folders cannot be changed", "根文件夹和系统文件夹不能被修改";
+    FILE_RESERVED_PATH = "file.reserved_path" => "This name is reserved for a system folder", "此名称为系统文件夹保留";
+    FILE_DESTINATION_NOT_FOUND = "file.destination_not_found" => "Destination folder not found", "目标文件夹不存在";
+    FILE_DESTINATION_NOT_FOLDER = "file.destination_not_folder" => "Destination is not a folder", "目标不是文件夹";
+    FILE_NO_PERMISSION_DESTINATION = "file.no_permission_destination" => "You don't have permission to write to the destination folder", "您无权写入目标文件夹";
+    FILE_NO_PERMISSION_UPLOAD = "file.no_permission_upload" => "You don't have permission to upload to this folder", "您无权上传到此文件夹";
+    FILE_NO_PERMISSION_DOWNLOAD = "file.no_permission_download" => "You don't have permission to download this file", "您无权下载此文件";
+    FILE_NO_WRITE_PERMISSION = "file.no_write_permission" => "No write permission for this file", "没有此文件的写入权限";
+
+    // Downloads
+    DOWNLOAD_FOLDER = "download.folder" => "Cannot download a folder", "不能直接下载文件夹";
+    DOWNLOAD_NOT_FOLDER = "download.not_folder" => "Not a folder", "不是文件夹";
+    DOWNLOAD_NO_FILES = "download.no_files" => "No files specified for download", "未指定要下载的文件";
+    DOWNLOAD_NOTHING_FOUND = "download.nothing_found" => "No files found to download", "没有可下载的文件";
+    DOWNLOAD_COLLECT_FAILED = "download.collect_failed" => "Failed to collect files", "收集文件失败";
+    DOWNLOAD_ZIP_FAILED = "download.zip_failed" => "Failed to create ZIP archive", "创建 ZIP 压缩包失败";
+    DOWNLOAD_ARCHIVE_FAILED = "download.archive_failed" => "Failed to create archive", "创建压缩包失败";
+    DOWNLOAD_INVALID_FORMAT = "download.invalid_format" => "Invalid archive format, use zip or tar", "压缩包格式无效，请使用 zip 或 tar";
+    DOWNLOAD_FAILED = "download.failed" => "Failed to process download", "处理下载失败";
+    DOWNLOAD_PREPARING = "download.preparing" => "Batch download is being prepared", "正在准备批量下载";
+    DOWNLOAD_ARCHIVE_EXPIRED = "download.archive_expired" => "The archive has expired", "压缩包已过期";
+    DOWNLOAD_ARCHIVE_NOT_READY = "download.archive_not_ready" => "The archive is not ready yet", "压缩包尚未准备好";
+    DOWNLOAD_ARCHIVE_GONE = "download.archive_gone" => "The archive is no longer available", "压缩包已不可用";
+    DOWNLOAD_TAKEN_DOWN = "download.taken_down" => "This file was taken down", "该文件已被下架";
+    DOWNLOAD_QUARANTINED = "download.quarantined" => "This file is quarantined pending review", "该文件已被隔离，等待审核";
+    DOWNLOAD_URL_CREATED = "download.url_created" => "Download URL created", "下载链接已创建";
+    DOWNLOAD_URL_CREATE_FAILED = "download.url_create_failed" => "Failed to create download URL", "创建下载链接失败";
+    DOWNLOAD_URL_NOT_FOUND = "download.url_not_found" => "Download URL not found", "下载链接不存在";
+    DOWNLOAD_URL_EXPIRED = "download.url_expired" => "This download URL has expired", "此下载链接已过期";
+    DOWNLOAD_URL_USED = "download.url_used" => "This download URL has already been used", "此下载链接已被使用";
+    DOWNLOAD_URL_WRONG_IP = "download.url_wrong_ip" => "This download URL can't be used from your address", "此下载链接不能从您的地址使用";
+    DOWNLOAD_URL_UNAVAILABLE = "download.url_unavailable" => "This download URL is no longer available", "此下载链接已失效";
+    DOWNLOAD_URL_FILE_GONE = "download.url_file_gone" => "The file no longer exists", "文件已不存在";
+    DOWNLOAD_IP_UNKNOWN = "download.ip_unknown" => "Your IP address could not be determined", "无法确定您的 IP 地址";
+
+    // Search
+    SEARCH_CRITERIA_REQUIRED = "search.criteria_required" => "At least one of q, name, label or meta_key is required", "q、name、label 或 meta_key 至少需要提供一个";
+    SEARCH_COMPLETED = "search.completed" => "Search completed", "搜索完成";
+    SEARCH_QUERY_EMPTY = "search.query_empty" => "Search query is empty", "搜索查询为空";
+
+    // Shares
+    SHARE_NOT_FOUND = "share.not_found" => "Share link not found", "分享链接不存在";
+    SHARE_OWNER_ONLY = "share.owner_only" => "Only the owner can share this file", "只有所有者可以分享此文件";
+    SHARE_CREATED = "share.created" => "Share link created", "分享链接已创建";
+    SHARE_CREATE_FAILED = "share.create_failed" => "Failed to create share link", "创建分享链接失败";
+    SHARE_LISTED = "share.listed" => "Share links retrieved", "已获取分享链接";
+    SHARE_REVOKE_OWN_ONLY = "share.revoke_own_only" => "You can only revoke your own share links", "只能撤销自己的分享链接";
+    SHARE_REVOKED = "share.revoked" => "Share link revoked", "分享链接已撤销";
+    SHARE_REVOKE_FAILED = "share.revoke_failed" => "Failed to revoke share link", "撤销分享链接失败";
+    SHARE_UNAVAILABLE = "share.unavailable" => "This share link is no longer available", "此分享链接已失效";
+    SHARE_FILE_GONE = "share.file_gone" => "The shared file no longer exists", "分享的文件已不存在";
+    SHARE_PREVIEW_RETRIEVED = "share.preview_retrieved" => "Share preview retrieved", "已获取分享预览";
+    SHARE_NO_THUMBNAIL = "share.no_thumbnail" => "No thumbnail available", "没有可用的缩略图";
+    SHARE_FOLDER_LISTED = "share.folder_listed" => "Shared folder listed", "已列出分享的文件夹";
+    SHARE_FILE_ID_REQUIRED = "share.file_id_required" => "file_id is required to download from a shared folder", "从分享的文件夹下载需要提供 file_id";
+    SHARE_INVALID_SORT = "share.invalid_sort" => "Invalid sort, use name, size or modified", "无效的排序方式，请使用 name、size 或 modified";
+    SHARE_INVALID_ORDER = "share.invalid_order" => "Invalid order, use asc or desc", "无效的排序顺序，请使用 asc 或 desc";
+    SHARE_INVALID_MAX_DOWNLOADS = "share.invalid_max_downloads" => "max_downloads must be at least 1", "max_downloads 不能小于 1";
+    SHARE_INVALID_EXPIRY = "share.invalid_expiry" => "expires_in_hours must be at least 1", "expires_in_hours 不能小于 1";
+
+    // Permissions
+    PERMISSION_ADMIN_ONLY_GRANT = "permission.admin_only_grant" => "Only administrators can grant permissions", "只有管理员可以授予权限";
+    PERMISSION_ADMIN_ONLY_DEFAULTS = "permission.admin_only_defaults" => "Only administrators can manage folder default permissions", "只有管理员可以管理文件夹默认权限";
+    PERMISSION_ADMIN_ONLY_TEMPLATES = "permission.admin_only_templates" => "Only administrators can manage permission templates", "只有管理员可以管理权限模板";
+    PERMISSION_ADMIN_ONLY_COPY = "permission.admin_only_copy" => "Only administrators can copy permissions", "只有管理员可以复制权限";
+    PERMISSION_DEFAULTS_FOLDERS_ONLY = "permission.defaults_folders_only" => "Default permissions can only be set on folders", "默认权限只能设置在文件夹上";
+    PERMISSION_DEFAULT_NOT_FOUND = "permission.default_not_found" => "Folder default permission not found", "文件夹默认权限不存在";
+    PERMISSION_DEFAULTS_RETRIEVED = "permission.defaults_retrieved" => "Folder default permissions retrieved", "已获取文件夹默认权限";
+    PERMISSION_TEMPLATE_NOT_FOUND = "permission.template_not_found" => "Permission template not found", "权限模板不存在";
+    PERMISSION_TEMPLATE_EXISTS = "permission.template_exists" => "A template with this name already exists", "已存在同名模板";
+    PERMISSION_TEMPLATE_CREATED = "permission.template_created" => "Permission template created", "权限模板已创建";
+    PERMISSION_TEMPLATES_RETRIEVED = "permission.templates_retrieved" => "Permission templates retrieved", "已获取权限模板";
+    PERMISSION_BULK_GRANT_EMPTY = "permission.bulk_grant_empty" => "At least one file and one user are required", "至少需要一个文件和一个用户";
+    PERMISSION_BULK_GRANT_COMPLETED = "permission.bulk_grant_completed" => "Bulk grant completed", "批量授权完成";
+    PERMISSION_COPY_SAME_FILE = "permission.copy_same_file" => "Source and target must be different files", "源文件和目标文件不能相同";
+    PERMISSION_COPY_SOURCE_NOT_FOUND = "permission.copy_source_not_found" => "Source file not found", "源文件不存在";
+    PERMISSION_COPY_TARGET_NOT_FOUND = "permission.copy_target_not_found" => "Target file not found", "目标文件不存在";
+    PERMISSION_COPY_SHARE_TO_FOLDER = "permission.copy_share_to_folder" => "Share links can only be copied to files", "分享链接只能复制到文件";
+    PERMISSION_COPIED = "permission.copied" => "Permissions copied successfully", "权限复制成功";
+
+    // Notifications and jobs
+    NOTIFICATION_LISTED = "notification.listed" => "Notifications retrieved", "已获取通知";
+    NOTIFICATION_READ = "notification.read" => "Notification marked as read", "通知已标记为已读";
+    NOTIFICATION_ALL_READ = "notification.all_read" => "All notifications marked as read", "所有通知已标记为已读";
+    NOTIFICATION_PREFERENCES_RETRIEVED = "notification.preferences_retrieved" => "Notification preferences retrieved", "已获取通知偏好";
+    NOTIFICATION_PREFERENCES_UPDATED = "notification.preferences_updated" => "Notification preferences updated", "通知偏好已更新";
+    JOB_NOT_FOUND = "job.not_found" => "Job not found", "任务不存在";
+    JOB_RETRIEVED = "job.retrieved" => "Job retrieved successfully", "已获取任务";
+
+    // Storage
+    STORAGE_ACCESS_FAILED = "storage.access_failed" => "Failed to access storage directory", "访问存储目录失败";
+    STORAGE_DISK_INFO_UNAVAILABLE = "storage.disk_info_unavailable" => "Disk information not available", "无法获取磁盘信息";
+    STORAGE_INFO_RETRIEVED = "storage.info_retrieved" => "Storage info retrieved", "已获取存储信息";
+    STORAGE_VOLUME_RESOLVE_FAILED = "storage.volume_resolve_failed" => "Failed to resolve storage volume", "解析存储卷失败";
+    STORAGE_VOLUME_UNAVAILABLE = "storage.volume_unavailable" => "Storage volume is unavailable", "存储卷不可用";
+
+    // Administration
+    ADMIN_STATS_ONLY = "admin.stats_only" => "Only administrators can view statistics", "只有管理员可以查看统计信息";
+    ADMIN_STATS_RETRIEVED = "admin.stats_retrieved" => "Statistics retrieved", "已获取统计信息";
+    ADMIN_LOAD_RETRIEVED = "admin.load_retrieved" => "Load statistics retrieved", "已获取负载统计";
+    ADMIN_REPORTS_ONLY = "admin.reports_only" => "Only administrators can view reports", "只有管理员可以查看报表";
+    ADMIN_CONSISTENCY_RETRIEVED = "admin.consistency_retrieved" => "Consistency report retrieved", "已获取一致性报告";
+    ADMIN_REPAIR_ONLY = "admin.repair_only" => "Only administrators can repair storage", "只有管理员可以修复存储";
+    ADMIN_INVALID_REPAIR_ACTION = "admin.invalid_repair_action" => "Invalid repair action, use remove_missing, fix_sizes, create_folders or restore_parents", "修复操作无效，请使用 remove_missing、fix_sizes、create_folders 或 restore_parents";
+    ADMIN_REPAIRS_APPLIED = "admin.repairs_applied" => "Consistency repairs applied", "一致性修复已完成";
+    ADMIN_LEGAL_HOLD_ONLY = "admin.legal_hold_only" => "Only administrators can manage legal holds", "只有管理员可以管理法律保留";
+    ADMIN_LEGAL_HOLDS_RETRIEVED = "admin.legal_holds_retrieved" => "Legal holds retrieved", "已获取法律保留列表";
+    ADMIN_LEGAL_HOLD_PLACED = "admin.legal_hold_placed" => "Legal hold placed", "已设置法律保留";
+    ADMIN_LEGAL_HOLD_LIFTED = "admin.legal_hold_lifted" => "Legal hold lifted", "已解除法律保留";
+    ADMIN_QUARANTINE_ONLY = "admin.quarantine_only" => "Only administrators can review quarantined files", "只有管理员可以审核隔离的文件";
+    ADMIN_QUARANTINE_RETRIEVED = "admin.quarantine_retrieved" => "Quarantined files retrieved", "已获取隔离文件列表";
+    ADMIN_QUARANTINE_NOT_FOUND = "admin.quarantine_not_found" => "Quarantined file not found", "隔离文件不存在";
+    ADMIN_QUARANTINE_RELEASED = "admin.quarantine_released" => "Quarantined file released", "已解除文件隔离";
+    ADMIN_QUARANTINE_DELETED = "admin.quarantine_deleted" => "Quarantined file deleted", "已删除隔离文件";
+    ADMIN_TAKEDOWN_ONLY = "admin.takedown_only" => "Only administrators can manage takedowns", "只有管理员可以管理下架";
+    ADMIN_TAKEDOWN_FOLDER = "admin.takedown_folder" => "Only files can be taken down", "只能下架文件";
+    ADMIN_TAKEDOWN_EXISTS = "admin.takedown_exists" => "This file is already taken down", "该文件已被下架";
+    ADMIN_TAKEDOWN_ISSUED = "admin.takedown_issued" => "File taken down", "文件已下架";
+    ADMIN_TAKEDOWN_INVALID_STATUS = "admin.takedown_invalid_status" => "Invalid status, use active, countered, restored or upheld", "状态无效，请使用 active、countered、restored 或 upheld";
+    ADMIN_TAKEDOWN_CLOSED = "admin.takedown_closed" => "This takedown is already closed", "该下架已结束";
+    ADMIN_TAKEDOWN_RESTORED = "admin.takedown_restored" => "File restored", "文件已恢复";
+    ADMIN_TAKEDOWN_UPHELD = "admin.takedown_upheld" => "Takedown upheld", "已维持下架";
+    TAKEDOWN_NOT_FOUND = "takedown.not_found" => "Takedown not found", "下架记录不存在";
+    TAKEDOWN_RETRIEVED = "takedown.retrieved" => "Takedowns retrieved", "已获取下架记录";
+    TAKEDOWN_COUNTER_NOT_ACTIVE = "takedown.counter_not_active" => "A counter-notice can only be submitted for an active takedown", "只能对生效中的下架提交反通知";
+    TAKEDOWN_COUNTERED = "takedown.countered" => "Counter-notice submitted", "反通知已提交";
+    TAKEDOWN_REASON_REQUIRED = "takedown.reason_required" => "reason is required", "reason 为必填项";
+    TAKEDOWN_STATEMENT_REQUIRED = "takedown.statement_required" => "statement is required", "statement 为必填项";
+    ADMIN_LEGAL_HOLD_MOUNTED = "admin.legal_hold_mounted" => "Files in mounted folders cannot be put under legal hold", "挂载文件夹中的文件不能设置法律保留";
+    ADMIN_AUDIT_ONLY = "admin.audit_only" => "Only administrators can view the audit log", "只有管理员可以查看审计日志";
+    ADMIN_AUDIT_RETRIEVED = "admin.audit_retrieved" => "Audit log retrieved", "已获取审计日志";
+    ADMIN_AUDIT_EXPORT_ONLY = "admin.audit_export_only" => "Only administrators can export the audit log", "只有管理员可以导出审计日志";
+    ADMIN_MOUNTS_ONLY = "admin.mounts_only" => "Only administrators can manage mounts", "只有管理员可以管理挂载点";
+    ADMIN_LIBRARY_ONLY = "admin.library_only" => "Only administrators can manage the Shared Library", "只有管理员可以管理共享库";
+    ADMIN_MIGRATION_ONLY = "admin.migration_only" => "Only administrators can migrate storage", "只有管理员可以迁移存储";
+    ADMIN_MIGRATION_RUNNING = "admin.migration_running" => "A storage migration is already running", "已有存储迁移正在进行";
+    ADMIN_MIGRATION_STARTED = "admin.migration_started" => "Storage migration started", "存储迁移已开始";
+    ADMIN_NOTHING_TO_MIGRATE = "admin.nothing_to_migrate" => "Nothing to migrate", "没有需要迁移的内容";
+    ADMIN_FEATURES_ONLY = "admin.features_only" => "Only administrators can manage features", "只有管理员可以管理功能开关";
+    ADMIN_FEATURE_NOT_FOUND = "admin.feature_not_found" => "Feature not found", "功能不存在";
+    ADMIN_FEATURE_OVERRIDE_SAVED = "admin.feature_override_saved" => "Feature override saved", "功能开关已保存";
+    ADMIN_FEATURE_OVERRIDE_REMOVED = "admin.feature_override_removed" => "Feature override removed", "功能开关已移除";
+    ADMIN_FEATURE_OVERRIDE_NOT_FOUND = "admin.feature_override_not_found" => "Feature override not found", "功能开关不存在";
+    FEATURES_RETRIEVED = "features.retrieved" => "Features retrieved", "已获取功能列表";
+    USER_USAGE_HISTORY_RETRIEVED = "user.usage_history_retrieved" => "Usage history retrieved", "已获取存储用量历史";
+    USER_USAGE_HISTORY_OTHERS = "user.usage_history_others" => "Only administrators can view the usage of other accounts", "只有管理员可以查看其他账户的用量";
+    FEATURES_DISABLED = "features.disabled" => "This feature is not enabled for your account", "您的账户未启用此功能";
+    AUTH_REGISTRATION_DISABLED = "auth.registration_disabled" => "Registration is disabled", "注册已关闭";
+
+    // Library and groups
+    LIBRARY_UNAVAILABLE = "library.unavailable" => "Shared Library is not available", "共享库不可用";
+    LIBRARY_GROUP_NOT_FOUND = "library.group_not_found" => "Group not found", "用户组不存在";
+    LIBRARY_GROUP_NAME_REQUIRED = "library.group_name_required" => "Group name is required", "用户组名称不能为空";
+    LIBRARY_GROUP_CREATED = "library.group_created" => "Group created", "用户组已创建";
+    LIBRARY_GROUP_DELETED = "library.group_deleted" => "Group deleted", "用户组已删除";
+    LIBRARY_ALREADY_MEMBER = "library.already_member" => "User is already a member", "用户已是成员";
+    LIBRARY_MEMBER_ADDED = "library.member_added" => "Member added", "成员已添加";
+    LIBRARY_MEMBER_REMOVED = "library.member_removed" => "Member removed", "成员已移除";
+    LIBRARY_MEMBERSHIP_NOT_FOUND = "library.membership_not_found" => "Membership not found", "成员关系不存在";
+    LIBRARY_GRANT_NOT_FOUND = "library.grant_not_found" => "Library grant not found", "共享库授权不存在";
+    LIBRARY_GRANT_SAVED = "library.grant_saved" => "Library grant saved", "共享库授权已保存";
+    LIBRARY_GRANT_REMOVED = "library.grant_removed" => "Library grant removed", "共享库授权已移除";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("zh-CN,zh;q=0.9,en;q=0.8"), Language::Zh);
+        assert_eq!(negotiate("en-US,en;q=0.9"), Language::En);
+        assert_eq!(negotiate("fr-FR, zh;q=0.5"), Language::Zh);
+        assert_eq!(negotiate("en;q=0.4, zh-TW;q=0.6"), Language::Zh);
+        assert_eq!(negotiate("zh;q=0, en"), Language::En);
+        assert_eq!(negotiate("fr"), Language::En);
+        assert_eq!(negotiate(""), Language::En);
+    }
+
+    #[tokio::test]
+    async fn test_render() {
+        let (code, text) =
+            scope(Language::Zh, async { Text::from(&FILE_NOT_FOUND).render() }).await;
+        assert_eq!(code, Some("file.not_found"));
+        assert_eq!(text, "文件不存在");
+
+        let (code, text) = Text::from("Something nobody translated").render();
+        assert_eq!(code, None);
+        assert_eq!(text, "Something nobody translated");
+
+        let e = anyhow::Error::new(&FILE_NOT_FOUND);
+        assert!(matches!(Text::from(e), Text::Catalog(m) if m.code == "file.not_found"));
+    }
+
+    #[test]
+    fn test_catalog_codes_are_unique() {
+        let codes: std::collections::HashSet<_> = MESSAGES.iter().map(|m| m.code).collect();
+        assert_eq!(codes.len(), MESSAGES.len());
+    }
+}
@@ -0,0 +1,281 @@
+/// Tags read from an audio file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<i32>,
+    pub year: Option<i32>,
+    /// Embedded cover art as (MIME type, image bytes)
+    pub cover: Option<(String, Vec<u8>)>,
+}
+
+/// Size of the ID3v2 tag at the start of `header` (10 bytes), including the header
+pub fn id3v2_len(header: &[u8]) -> Option<usize> {
+    if header.len() < 10 || &header[..3] != b"ID3" {
+        return None;
+    }
+    Some(syncsafe(&header[6..10]) + 10)
+}
+
+/// Parse an ID3v2.2, 2.3 or 2.4 tag from the start of a file
+pub fn parse_id3v2(data: &[u8]) -> Option<AudioTags> {
+    let tag_len = id3v2_len(data)?.min(data.len());
+    let major = data[3];
+    let flags = data[5];
+    if !(2..=4).contains(&major) {
+        return None;
+    }
+    // Unsynchronised tags are rare and not worth undoing here
+    if flags & 0x80 != 0 {
+        return None;
+    }
+
+    let mut pos = 10;
+    if flags & 0x40 != 0 && major >= 3 {
+        let ext = data.get(10..14)?;
+        pos += if major == 4 {
+            syncsafe(ext)
+        } else {
+            u32::from_be_bytes([ext[0], ext[1], ext[2], ext[3]]) as usize + 4
+        };
+    }
+
+    let (id_len, header_len) = if major == 2 { (3, 6) } else { (4, 10) };
+    let mut tags = AudioTags::default();
+    while pos + header_len <= tag_len {
+        let header = &data[pos..pos + header_len];
+        if header[0] == 0 {
+            break; // padding
+        }
+        let id = std::str::from_utf8(&header[..id_len]).ok()?;
+        let size = match major {
+            2 => u32::from_be_bytes([0, header[3], header[4], header[5]]) as usize,
+            3 => u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize,
+            _ => syncsafe(&header[4..8]),
+        };
+        let start = pos + header_len;
+        let end = start.checked_add(size)?;
+        if end > tag_len {
+            break;
+        }
+        let body = &data[start..end];
+
+        match id {
+            "TIT2" | "TT2" => tags.title = text_frame(body),
+            "TPE1" | "TP1" => tags.artist = text_frame(body),
+            "TALB" | "TAL" => tags.album = text_frame(body),
+            "TRCK" | "TRK" => tags.track_number = text_frame(body).and_then(|t| leading_number(&t)),
+            "TYER" | "TYE" | "TDRC" => {
+                tags.year = text_frame(body).and_then(|t| leading_number(&t))
+            }
+            "APIC" if tags.cover.is_none() => tags.cover = apic_frame(body),
+            "PIC" if tags.cover.is_none() => tags.cover = pic_frame(body),
+            _ => {}
+        }
+        pos = end;
+    }
+
+    Some(tags)
+}
+
+/// Parse the 128-byte ID3v1 tag found at the end of a file
+pub fn parse_id3v1(trailer: &[u8]) -> Option<AudioTags> {
+    if trailer.len() != 128 || &trailer[..3] != b"TAG" {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| {
+        let text = latin1(&trailer[range]);
+        let text = text.trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+
+    Some(AudioTags {
+        title: field(3..33),
+        artist: field(33..63),
+        album: field(63..93),
+        year: field(93..97).and_then(|y| leading_number(&y)),
+        // ID3v1.1 keeps the track in the last comment byte after a zero
+        track_number: (trailer[125] == 0 && trailer[126] != 0).then(|| trailer[126] as i32),
+        cover: None,
+    })
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0usize, |acc, b| (acc << 7) | (*b as usize & 0x7f))
+}
+
+fn leading_number(text: &str) -> Option<i32> {
+    let digits: String = text
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Decode text in one of the ID3 encodings, up to the first terminator
+fn decode(encoding: u8, bytes: &[u8]) -> String {
+    match encoding {
+        1 | 2 => {
+            let (big_endian, bytes) = match bytes {
+                [0xfe, 0xff, rest @ ..] => (true, rest),
+                [0xff, 0xfe, rest @ ..] => (false, rest),
+                _ => (encoding == 2, bytes),
+            };
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| {
+                    if big_endian {
+                        u16::from_be_bytes([c[0], c[1]])
+                    } else {
+                        u16::from_le_bytes([c[0], c[1]])
+                    }
+                })
+                .take_while(|&u| u != 0)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        3 => {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        }
+        _ => {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            latin1(&bytes[..end])
+        }
+    }
+}
+
+fn text_frame(body: &[u8]) -> Option<String> {
+    let (&encoding, text) = body.split_first()?;
+    let text = decode(encoding, text).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Length of a terminated string in the given encoding, including the terminator
+fn terminated_len(encoding: u8, bytes: &[u8]) -> Option<usize> {
+    if matches!(encoding, 1 | 2) {
+        bytes
+            .chunks_exact(2)
+            .position(|c| c == [0, 0])
+            .map(|i| i * 2 + 2)
+    } else {
+        bytes.iter().position(|&b| b == 0).map(|i| i + 1)
+    }
+}
+
+/// encoding, MIME type, picture type, description, image
+fn apic_frame(body: &[u8]) -> Option<(String, Vec<u8>)> {
+    let (&encoding, rest) = body.split_first()?;
+    let mime_end = rest.iter().position(|&b| b == 0)?;
+    let mime = latin1(&rest[..mime_end]);
+    let rest = rest.get(mime_end + 2..)?; // terminator and picture type
+    let description_len = terminated_len(encoding, rest)?;
+    let image = rest.get(description_len..)?;
+    cover(mime, image)
+}
+
+/// ID3v2.2 variant with a three-letter image format instead of a MIME type
+fn pic_frame(body: &[u8]) -> Option<(String, Vec<u8>)> {
+    let (&encoding, rest) = body.split_first()?;
+    let format = latin1(rest.get(..3)?).to_ascii_lowercase();
+    let rest = rest.get(4..)?; // format and picture type
+    let description_len = terminated_len(encoding, rest)?;
+    let image = rest.get(description_len..)?;
+    cover(format!("image/{}", format.replace("jpg", "jpeg")), image)
+}
+
+fn cover(mime: String, image: &[u8]) -> Option<(String, Vec<u8>)> {
+    if image.is_empty() {
+        return None;
+    }
+    let mime = match mime.to_ascii_lowercase().as_str() {
+        "" | "image/" => "image/jpeg".to_string(),
+        m if !m.contains('/') => format!("image/{}", m),
+        m => m.to_string(),
+    };
+    Some((mime, image.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(id: &str, body: &[u8]) -> Vec<u8> {
+        let mut f = id.as_bytes().to_vec();
+        f.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        f.extend_from_slice(&[0, 0]);
+        f.extend_from_slice(body);
+        f
+    }
+
+    fn tag(frames: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = frames.concat();
+        let size = body.len() + 16; // with some padding
+        let mut t = b"ID3\x03\x00\x00".to_vec();
+        t.extend_from_slice(&[
+            (size >> 21) as u8 & 0x7f,
+            (size >> 14) as u8 & 0x7f,
+            (size >> 7) as u8 & 0x7f,
+            size as u8 & 0x7f,
+        ]);
+        t.extend_from_slice(&body);
+        t.extend_from_slice(&[0; 16]);
+        t
+    }
+
+    #[test]
+    fn test_parse_id3v2() {
+        let mut utf16_title = vec![1, 0xff, 0xfe];
+        utf16_title.extend("Héllo".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let mut apic = b"\x00image/png\x00\x03cover\x00".to_vec();
+        apic.extend_from_slice(&[0x89, b'P', b'N', b'G']);
+
+        let data = tag(&[
+            frame("TIT2", &utf16_title),
+            frame("TPE1", b"\x03Artist"),
+            frame("TALB", b"\x00Album\x00"),
+            frame("TRCK", b"\x003/12"),
+            frame("TYER", b"\x001999"),
+            frame("APIC", &apic),
+        ]);
+        assert_eq!(id3v2_len(&data), Some(data.len()));
+
+        let tags = parse_id3v2(&data).unwrap();
+        assert_eq!(tags.title.as_deref(), Some("Héllo"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.album.as_deref(), Some("Album"));
+        assert_eq!(tags.track_number, Some(3));
+        assert_eq!(tags.year, Some(1999));
+        assert_eq!(
+            tags.cover,
+            Some(("image/png".to_string(), vec![0x89, b'P', b'N', b'G']))
+        );
+        assert!(parse_id3v2(b"RIFF....WAVE").is_none());
+    }
+
+    #[test]
+    fn test_parse_id3v1() {
+        let mut trailer = vec![0u8; 128];
+        trailer[..3].copy_from_slice(b"TAG");
+        trailer[3..8].copy_from_slice(b"Title");
+        trailer[33..39].copy_from_slice(b"Artist");
+        trailer[93..97].copy_from_slice(b"2004");
+        trailer[126] = 7;
+
+        let tags = parse_id3v1(&trailer).unwrap();
+        assert_eq!(tags.title.as_deref(), Some("Title"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.album, None);
+        assert_eq!(tags.year, Some(2004));
+        assert_eq!(tags.track_number, Some(7));
+    }
+}
@@ -1,6 +1,20 @@
 pub mod archive;
+pub mod client;
+pub mod content_encoding;
+pub mod cookie;
+pub mod exif;
+pub mod export;
 pub mod file_utils;
+pub mod http_cache;
+pub mod http_client;
+pub mod i18n;
+pub mod id3;
+pub mod json_log;
 pub mod jwt;
 pub mod password;
+pub mod range;
 pub mod request_id;
 pub mod response;
+pub mod search_query;
+pub mod timestamp;
+pub mod validation;
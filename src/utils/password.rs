@@ -1,14 +1,134 @@
-use anyhow::Result;
-use bcrypt::{hash, verify, DEFAULT_COST};
+use crate::config::PasswordHashingConfig;
+use anyhow::{anyhow, Result};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
 
-/// Hash password using bcrypt
-pub fn hash_password(password: &str) -> Result<String> {
-    let hashed = hash(password, DEFAULT_COST)?;
+/// Prefixes used by bcrypt hashes created before the switch to Argon2id
+const BCRYPT_PREFIXES: [&str; 4] = ["$2a$", "$2b$", "$2x$", "$2y$"];
+
+fn argon2_hasher(params: &PasswordHashingConfig) -> Result<Argon2<'static>> {
+    let params = Params::new(
+        params.argon2_memory_kib,
+        params.argon2_iterations,
+        params.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| anyhow!("Invalid Argon2 parameters: {}", e))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+fn is_bcrypt_hash(hash: &str) -> bool {
+    BCRYPT_PREFIXES
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
+/// Hash password using Argon2id
+/// Runs on a blocking thread so a burst of logins doesn't stall the async workers
+pub async fn hash_password(password: &str, params: &PasswordHashingConfig) -> Result<String> {
+    let password = password.to_string();
+    let params = params.clone();
+    tokio::task::spawn_blocking(move || hash_password_blocking(&password, &params)).await?
+}
+
+/// Verify password against hash on a blocking thread
+pub async fn verify_password(password: &str, hash: &str) -> Result<bool> {
+    let password = password.to_string();
+    let hash = hash.to_string();
+    tokio::task::spawn_blocking(move || verify_password_blocking(&password, &hash)).await?
+}
+
+fn hash_password_blocking(password: &str, params: &PasswordHashingConfig) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hashed = argon2_hasher(params)?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Failed to hash password: {}", e))?
+        .to_string();
     Ok(hashed)
 }
 
-/// Verify password against hash
-pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    let valid = verify(password, hash)?;
-    Ok(valid)
+/// Accepts both Argon2 hashes and legacy bcrypt hashes
+fn verify_password_blocking(password: &str, hash: &str) -> Result<bool> {
+    if is_bcrypt_hash(hash) {
+        return Ok(bcrypt::verify(password, hash)?);
+    }
+
+    let parsed = PasswordHash::new(hash).map_err(|e| anyhow!("Invalid password hash: {}", e))?;
+
+    // Parameters are read from the hash itself, so older Argon2 hashes still verify
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(anyhow!("Failed to verify password: {}", e)),
+    }
+}
+
+/// Check whether a stored hash should be replaced with one using the current parameters
+/// True for legacy bcrypt hashes and Argon2 hashes with outdated parameters
+pub fn needs_rehash(hash: &str, params: &PasswordHashingConfig) -> bool {
+    if is_bcrypt_hash(hash) {
+        return true;
+    }
+
+    let parsed = match PasswordHash::new(hash) {
+        Ok(p) => p,
+        Err(_) => return true,
+    };
+
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(current) => {
+            current.m_cost() != params.argon2_memory_kib
+                || current.t_cost() != params.argon2_iterations
+                || current.p_cost() != params.argon2_parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> PasswordHashingConfig {
+        // Low cost to keep tests fast
+        PasswordHashingConfig {
+            argon2_memory_kib: 1024,
+            argon2_iterations: 1,
+            argon2_parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_argon2_roundtrip() {
+        let hash = hash_password_blocking("Correct-Horse-7", &params()).unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password_blocking("Correct-Horse-7", &hash).unwrap());
+        assert!(!verify_password_blocking("wrong", &hash).unwrap());
+        assert!(!needs_rehash(&hash, &params()));
+    }
+
+    #[test]
+    fn test_legacy_bcrypt_hash() {
+        let hash = bcrypt::hash("Correct-Horse-7", 4).unwrap();
+        assert!(verify_password_blocking("Correct-Horse-7", &hash).unwrap());
+        assert!(!verify_password_blocking("wrong", &hash).unwrap());
+        assert!(needs_rehash(&hash, &params()));
+    }
+
+    #[test]
+    fn test_needs_rehash_on_param_change() {
+        let hash = hash_password_blocking("Correct-Horse-7", &params()).unwrap();
+        let stronger = PasswordHashingConfig {
+            argon2_iterations: 2,
+            ..params()
+        };
+        assert!(needs_rehash(&hash, &stronger));
+    }
 }
@@ -0,0 +1,94 @@
+/// Parse a single-range `Range` header ("bytes=start-end", "bytes=start-" or "bytes=-suffix")
+/// Returns the inclusive byte range within `size`, or None when it cannot be satisfied
+pub fn parse_byte_range(header: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    // Multiple ranges are not supported
+    if spec.contains(',') || size == 0 {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (size.saturating_sub(suffix), size - 1)
+        }
+        (start, "") => (start.parse().ok()?, size - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(size - 1)),
+    };
+
+    if start > end {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// End of the range `offset..offset + length`, or None unless it lies within `0..=total`
+pub fn end_within(offset: i64, length: i64, total: i64) -> Option<i64> {
+    if offset < 0 || length < 0 {
+        return None;
+    }
+    offset.checked_add(length).filter(|end| *end <= total)
+}
+
+/// Parts of `0..total` none of the `(offset, length)` ranges cover, as `(offset, length)`
+pub fn gaps(ranges: &[(i64, i64)], total: i64) -> Vec<(i64, i64)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable();
+
+    let mut gaps = Vec::new();
+    let mut covered = 0;
+    for (offset, length) in ranges {
+        if offset > covered {
+            gaps.push((covered, offset.min(total) - covered));
+        }
+        covered = covered.max(offset + length);
+        if covered >= total {
+            return gaps;
+        }
+    }
+    if covered < total {
+        gaps.push((covered, total - covered));
+    }
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_byte_range("bytes=500-", 1000), Some((500, 999)));
+        assert_eq!(parse_byte_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=900-2000", 1000), Some((900, 999)));
+        assert_eq!(parse_byte_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_byte_range("bytes=0-1,5-9", 1000), None);
+        assert_eq!(parse_byte_range("items=0-1", 1000), None);
+    }
+
+    #[test]
+    fn test_end_within() {
+        assert_eq!(end_within(0, 10, 10), Some(10));
+        assert_eq!(end_within(4, 0, 10), Some(4));
+        assert_eq!(end_within(4, 7, 10), None);
+        assert_eq!(end_within(-1, 1, 10), None);
+        // Offsets near i64::MAX must not wrap around into the file
+        assert_eq!(end_within(i64::MAX, 1, 10), None);
+        assert_eq!(end_within(i64::MAX - 1, 10, i64::MAX), None);
+    }
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(gaps(&[], 10), vec![(0, 10)]);
+        assert_eq!(gaps(&[(0, 10)], 10), vec![]);
+        assert_eq!(gaps(&[(6, 4), (0, 3)], 10), vec![(3, 3)]);
+        assert_eq!(gaps(&[(2, 2), (2, 2)], 10), vec![(0, 2), (4, 6)]);
+        assert_eq!(gaps(&[(0, 5), (3, 4)], 10), vec![(7, 3)]);
+        assert_eq!(gaps(&[], 0), vec![]);
+    }
+}
@@ -1,14 +1,41 @@
+use crate::error::ErrorCode;
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 
+use super::i18n::Text;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
     pub code: u16,
     pub message: String,
+    /// Stable identifier of the message, unaffected by translation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_code: Option<&'static str>,
+    /// Machine-readable reason of a failed request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+}
+
+/// RFC 7807 body of a failed request, sent to clients that accept `application/problem+json`
+#[derive(Debug, Serialize)]
+pub struct ProblemDetails<T> {
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub error_code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_code: Option<&'static str>,
     pub request_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
@@ -17,17 +44,104 @@ pub struct ApiResponse<T> {
 #[derive(Debug, Serialize)]
 pub struct EmptyData {}
 
+tokio::task_local! {
+    static PROBLEM_DETAILS: bool;
+}
+
+/// Run `f` rendering its error responses as problem details when `enabled`
+pub async fn with_problem_details<F: std::future::Future>(enabled: bool, f: F) -> F::Output {
+    PROBLEM_DETAILS.scope(enabled, f).await
+}
+
+/// Whether an `Accept` header asks for `application/problem+json`
+pub fn accepts_problem_json(accept: &str) -> bool {
+    accept.split(',').any(|part| {
+        let mut params = part.split(';');
+        let media_type = params.next().unwrap_or("").trim();
+        let quality = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        media_type.eq_ignore_ascii_case(PROBLEM_JSON) && quality > 0.0
+    })
+}
+
 pub fn do_json_detail_resp<T: Serialize>(
     status: StatusCode,
     request_id: String,
-    message: impl Into<String>,
+    message: impl Into<Text>,
     data: Option<T>,
 ) -> Response {
+    let error_code = (status.is_client_error() || status.is_server_error())
+        .then(|| ErrorCode::from_status(status));
+    build_resp(status, error_code, request_id, message.into(), data)
+}
+
+pub fn error_resp(status: StatusCode, request_id: String, message: impl Into<Text>) -> Response {
+    do_json_detail_resp::<EmptyData>(status, request_id, message, None)
+}
+
+/// Error response with a more specific code than the one implied by the status
+pub fn error_code_resp(
+    status: StatusCode,
+    error_code: ErrorCode,
+    request_id: String,
+    message: impl Into<Text>,
+) -> Response {
+    error_code_detail_resp::<EmptyData>(status, error_code, request_id, message, None)
+}
+
+/// Error response with a specific code and data describing the failure
+pub fn error_code_detail_resp<T: Serialize>(
+    status: StatusCode,
+    error_code: ErrorCode,
+    request_id: String,
+    message: impl Into<Text>,
+    data: Option<T>,
+) -> Response {
+    build_resp(status, Some(error_code), request_id, message.into(), data)
+}
+
+fn build_resp<T: Serialize>(
+    status: StatusCode,
+    error_code: Option<ErrorCode>,
+    request_id: String,
+    message: Text,
+    data: Option<T>,
+) -> Response {
+    // Catalog messages are translated into the language of the request
+    let (message_code, message) = message.render();
+
+    if let Some(error_code) = error_code {
+        if PROBLEM_DETAILS.try_with(|p| *p).unwrap_or(false) {
+            let mut response = (
+                status,
+                Json(ProblemDetails {
+                    problem_type: "about:blank",
+                    title: status.canonical_reason().unwrap_or("Error"),
+                    status: status.as_u16(),
+                    detail: message,
+                    error_code,
+                    message_code,
+                    request_id,
+                    data,
+                }),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+            return response;
+        }
+    }
+
     (
         status,
         Json(ApiResponse {
             code: status.as_u16(),
-            message: message.into(),
+            message,
+            message_code,
+            error_code,
             request_id,
             data,
         }),
@@ -35,6 +149,18 @@ pub fn do_json_detail_resp<T: Serialize>(
         .into_response()
 }
 
-pub fn error_resp(status: StatusCode, request_id: String, message: impl Into<String>) -> Response {
-    do_json_detail_resp::<EmptyData>(status, request_id, message, None)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_problem_json() {
+        assert!(accepts_problem_json("application/problem+json"));
+        assert!(accepts_problem_json(
+            "application/json, application/problem+json;q=0.5"
+        ));
+        assert!(!accepts_problem_json("application/problem+json;q=0"));
+        assert!(!accepts_problem_json("application/json"));
+        assert!(!accepts_problem_json("*/*"));
+    }
 }
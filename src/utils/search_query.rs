@@ -0,0 +1,226 @@
+use chrono::NaiveDate;
+
+/// Comparison in `size:` and date terms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+/// One filter of a search query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// Bare word or `name:`, part of the file name
+    Name(String),
+    /// `type:`, `file`, `folder`, a MIME class like `image` or a full MIME type
+    Type(String),
+    /// `ext:`, file extension without the dot
+    Extension(String),
+    /// `size:`, in bytes
+    Size(Comparison, i64),
+    /// `modified:`, day of the last change
+    Modified(Comparison, NaiveDate),
+    /// `created:`, day of the upload
+    Created(Comparison, NaiveDate),
+    /// `tag:` or `label:`, label attached by a processing hook
+    Tag(String),
+    /// `meta.<key>:`, user-defined metadata value, `*` for any value
+    Meta(String, Option<String>),
+    /// `in:`, folder the file is somewhere below
+    In(String),
+}
+
+/// Term with its `-` prefix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub negated: bool,
+    pub term: Term,
+}
+
+/// Parse a query like `type:image size:>10MB modified:>2024-01-01 tag:tax`
+/// Terms are separated by spaces, values with spaces go in double quotes
+pub fn parse(query: &str) -> Result<Vec<Filter>, String> {
+    tokenize(query)?
+        .into_iter()
+        .map(|token| {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                _ => (false, token),
+            };
+            Ok(Filter {
+                negated,
+                term: parse_term(&token)?,
+            })
+        })
+        .collect()
+}
+
+fn tokenize(query: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() || quoted {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quote in search query".to_string());
+    }
+    if !current.is_empty() || quoted {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    let Some((field, value)) = token.split_once(':') else {
+        return Ok(Term::Name(token.to_string()));
+    };
+    if value.is_empty() {
+        return Err(format!("Missing value for '{}:'", field));
+    }
+
+    // Metadata keys are case-sensitive, field names are not
+    if field.to_ascii_lowercase().starts_with("meta.") {
+        let key = &field["meta.".len()..];
+        if key.is_empty() {
+            return Err("Missing key in 'meta.:'".to_string());
+        }
+        let value = (value != "*").then(|| value.to_string());
+        return Ok(Term::Meta(key.to_string(), value));
+    }
+
+    let field = field.to_ascii_lowercase();
+    match field.as_str() {
+        "name" => Ok(Term::Name(value.to_string())),
+        "type" => Ok(Term::Type(value.to_ascii_lowercase())),
+        "ext" => Ok(Term::Extension(
+            value.trim_start_matches('.').to_ascii_lowercase(),
+        )),
+        "size" => {
+            let (cmp, size) = comparison(value);
+            Ok(Term::Size(cmp, parse_size(size)?))
+        }
+        "modified" | "created" => {
+            let (cmp, date) = comparison(value);
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date))?;
+            Ok(if field == "modified" {
+                Term::Modified(cmp, date)
+            } else {
+                Term::Created(cmp, date)
+            })
+        }
+        "tag" | "label" => Ok(Term::Tag(value.to_lowercase())),
+        "in" => Ok(Term::In(value.to_string())),
+        _ => Err(format!("Unknown search field '{}'", field)),
+    }
+}
+
+fn comparison(value: &str) -> (Comparison, &str) {
+    for (prefix, cmp) in [
+        (">=", Comparison::Ge),
+        ("<=", Comparison::Le),
+        (">", Comparison::Gt),
+        ("<", Comparison::Lt),
+        ("=", Comparison::Eq),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (cmp, rest);
+        }
+    }
+    (Comparison::Eq, value)
+}
+
+/// `1024`, `10KB`, `1.5GB`, units are powers of 1024
+fn parse_size(value: &str) -> Result<i64, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let split = upper
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(split);
+    let multiplier: f64 = match unit {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1024.0,
+        "M" | "MB" => 1024.0 * 1024.0,
+        "G" | "GB" => 1024.0 * 1024.0 * 1024.0,
+        "T" | "TB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("Invalid size '{}'", value)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}'", value))?;
+    Ok((number * multiplier) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let filters = parse(
+            r#"type:image size:>10MB modified:>2024-01-01 tag:Tax -ext:.PNG "annual report""#,
+        )
+        .unwrap();
+        let terms: Vec<(bool, Term)> = filters.into_iter().map(|f| (f.negated, f.term)).collect();
+        assert_eq!(
+            terms,
+            vec![
+                (false, Term::Type("image".to_string())),
+                (false, Term::Size(Comparison::Gt, 10 * 1024 * 1024)),
+                (false, Term::Modified(Comparison::Gt, date("2024-01-01"))),
+                (false, Term::Tag("tax".to_string())),
+                (true, Term::Extension("png".to_string())),
+                (false, Term::Name("annual report".to_string())),
+            ]
+        );
+
+        let filters =
+            parse(r#"Meta.Project:"Apollo 11" size:<=1.5KB in:/docs created:2023-05-01"#).unwrap();
+        assert_eq!(
+            filters[0].term,
+            Term::Meta("Project".to_string(), Some("Apollo 11".to_string()))
+        );
+        assert_eq!(
+            parse("meta.invoice:*").unwrap()[0].term,
+            Term::Meta("invoice".to_string(), None)
+        );
+        assert_eq!(filters[1].term, Term::Size(Comparison::Le, 1536));
+        assert_eq!(filters[2].term, Term::In("/docs".to_string()));
+        assert_eq!(
+            filters[3].term,
+            Term::Created(Comparison::Eq, date("2023-05-01"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("owner:bob").is_err());
+        assert!(parse("size:>lots").is_err());
+        assert!(parse("modified:yesterday").is_err());
+        assert!(parse(r#"name:"open"#).is_err());
+        assert!(parse("tag:").is_err());
+        assert_eq!(parse("   ").unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{NaiveDateTime, SecondsFormat, Utc};
+use serde::Serializer;
+
+/// Stored times are UTC without an offset, this is how they were sent before RFC 3339
+const LEGACY_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Request header asking for the legacy format, for clients not reading RFC 3339 yet
+pub const FORMAT_HEADER: &str = "x-timestamp-format";
+/// Value of `FORMAT_HEADER` selecting the legacy format
+pub const FORMAT_LEGACY: &str = "legacy";
+
+tokio::task_local! {
+    static LEGACY: bool;
+}
+
+/// Run `f` with every timestamp it renders in the legacy format when `legacy` is set
+pub async fn scope<F: std::future::Future>(legacy: bool, f: F) -> F::Output {
+    LEGACY.scope(legacy, f).await
+}
+
+/// Render a stored UTC time for a response, e.g. `2024-05-01T12:30:00Z`
+pub fn format(t: NaiveDateTime) -> String {
+    if LEGACY.try_with(|l| *l).unwrap_or(false) {
+        return t.format(LEGACY_FORMAT).to_string();
+    }
+    rfc3339(t)
+}
+
+/// Render the current time for a response
+pub fn now() -> String {
+    format(Utc::now().naive_utc())
+}
+
+/// `serialize_with` for stored times of entities sent as is
+pub fn serialize<S: Serializer>(t: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*t))
+}
+
+/// `serialize_with` for optional stored times of entities sent as is
+pub fn serialize_option<S: Serializer>(
+    t: &Option<NaiveDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match t {
+        Some(t) => serialize(t, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn rfc3339(t: NaiveDateTime) -> String {
+    t.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_rfc3339() {
+        let t = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 0, 250)
+            .unwrap();
+        assert_eq!(rfc3339(t), "2024-05-01T12:30:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_format_is_per_request() {
+        let t = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 30, 0)
+            .unwrap();
+        assert_eq!(
+            scope(true, async { format(t) }).await,
+            "2024-05-01 12:30:00"
+        );
+        assert_eq!(
+            scope(false, async { format(t) }).await,
+            "2024-05-01T12:30:00Z"
+        );
+        assert_eq!(format(t), "2024-05-01T12:30:00Z");
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{config::ValidationConfig, utils::i18n};
+use anyhow::{anyhow, Result};
+
+/// Maximum email length per RFC 5321
+const MAX_EMAIL_LENGTH: usize = 254;
+
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+
+const MAX_ICON_LENGTH: usize = 32;
+
+/// Device names Windows reserves with or without an extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Passwords that are rejected regardless of their computed strength
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "password",
+    "password1",
+    "password123",
+    "qwerty",
+    "qwerty123",
+    "qwertyuiop",
+    "abc123",
+    "111111",
+    "000000",
+    "iloveyou",
+    "admin",
+    "admin123",
+    "welcome",
+    "letmein",
+    "monkey",
+    "dragon",
+    "football",
+    "baseball",
+    "sunshine",
+    "princess",
+    "passw0rd",
+    "p@ssw0rd",
+    "1q2w3e4r",
+    "zaq12wsx",
+    "changeme",
+];
+
+/// Validate username length and character set
+pub fn validate_username(username: &str, rules: &ValidationConfig) -> Result<()> {
+    let length = username.chars().count();
+
+    if username.trim().is_empty() {
+        return Err(anyhow!("Username cannot be empty"));
+    }
+
+    if length < rules.username_min_length || length > rules.username_max_length {
+        return Err(anyhow!(
+            "Username must be between {} and {} characters",
+            rules.username_min_length,
+            rules.username_max_length
+        ));
+    }
+
+    let valid_chars = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || rules.username_allowed_symbols.contains(c));
+    if !valid_chars {
+        return Err(anyhow!(
+            "Username may only contain letters, digits and '{}'",
+            rules.username_allowed_symbols
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate email address shape (`local@domain.tld`)
+pub fn validate_email(email: &str, rules: &ValidationConfig) -> Result<()> {
+    if email.trim().is_empty() {
+        return Err(anyhow!("Email cannot be empty"));
+    }
+
+    if !rules.check_email_format {
+        return Ok(());
+    }
+
+    if email.len() > MAX_EMAIL_LENGTH || email.chars().any(|c| c.is_whitespace()) {
+        return Err(anyhow!("Invalid email address"));
+    }
+
+    let (local, domain) = match email.split_once('@') {
+        Some(parts) => parts,
+        None => return Err(anyhow!("Invalid email address")),
+    };
+
+    let domain_valid = !domain.contains('@')
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        });
+
+    if local.is_empty() || !domain_valid {
+        return Err(anyhow!("Invalid email address"));
+    }
+
+    Ok(())
+}
+
+/// Validate a display name: non-blank, bounded and free of control characters
+pub fn validate_display_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Display name cannot be empty"));
+    }
+
+    if name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+        return Err(anyhow!(
+            "Display name must be at most {} characters",
+            MAX_DISPLAY_NAME_LENGTH
+        ));
+    }
+
+    if name.chars().any(|c| c.is_control()) {
+        return Err(anyhow!("Display name contains invalid characters"));
+    }
+
+    Ok(())
+}
+
+/// Validate a BCP 47 style language tag (`en`, `zh-CN`, `zh-Hant-TW`)
+pub fn validate_locale(locale: &str) -> Result<()> {
+    let mut subtags = locale.split(['-', '_']);
+    let primary = subtags.next().unwrap_or("");
+
+    let primary_valid =
+        (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic());
+    let rest_valid = subtags
+        .all(|tag| (2..=8).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    if !primary_valid || !rest_valid {
+        return Err(anyhow!("Invalid locale"));
+    }
+
+    Ok(())
+}
+
+/// Validate an IANA time zone name shape (`UTC`, `Asia/Shanghai`, `America/Argentina/Salta`)
+pub fn validate_timezone(timezone: &str) -> Result<()> {
+    let valid = !timezone.is_empty()
+        && timezone.len() <= 64
+        && timezone.split('/').all(|part| {
+            !part.is_empty()
+                && part.starts_with(|c: char| c.is_ascii_alphabetic())
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        });
+
+    if !valid {
+        return Err(anyhow!("Invalid timezone"));
+    }
+
+    Ok(())
+}
+
+/// Validate a CSS hex color (`#rgb` or `#rrggbb`)
+pub fn validate_hex_color(color: &str) -> Result<()> {
+    let valid = color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+
+    if !valid {
+        return Err(anyhow::Error::new(&i18n::FILE_INVALID_COLOR));
+    }
+
+    Ok(())
+}
+
+/// Validate a folder icon: an emoji or a short icon name without whitespace
+pub fn validate_icon(icon: &str) -> Result<()> {
+    if icon.is_empty() || icon.chars().count() > MAX_ICON_LENGTH {
+        return Err(anyhow!("Icon must be 1 to {} characters", MAX_ICON_LENGTH));
+    }
+
+    if icon.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(anyhow!("Icon contains invalid characters"));
+    }
+
+    Ok(())
+}
+
+/// Validate a file or folder name against the deployment's naming rules
+pub fn validate_filename(name: &str, rules: &ValidationConfig) -> Result<()> {
+    if name.trim().is_empty() || name == "." || name == ".." {
+        return Err(anyhow::Error::new(&i18n::FILE_INVALID_FILE_NAME));
+    }
+
+    if name.contains('/') || name.contains('\\') {
+        return Err(anyhow::Error::new(&i18n::FILE_NAME_HAS_SEPARATOR));
+    }
+
+    if name.chars().any(|c| c.is_control()) {
+        return Err(anyhow::Error::new(&i18n::FILE_NAME_INVALID_CHARS));
+    }
+
+    if name.len() > rules.filename_max_length {
+        return Err(anyhow!(
+            "File name must be at most {} bytes",
+            rules.filename_max_length
+        ));
+    }
+
+    if let Some(c) = name
+        .chars()
+        .find(|c| rules.filename_forbidden_chars.contains(*c))
+    {
+        return Err(anyhow!("File name cannot contain '{}'", c));
+    }
+
+    if rules.filename_windows_compatible {
+        if name.ends_with('.') || name.ends_with(' ') {
+            return Err(anyhow::Error::new(&i18n::FILE_NAME_TRAILING_DOT_SPACE));
+        }
+
+        // `nul.txt` is as unusable as `NUL`
+        let stem = name.split('.').next().unwrap_or(name).trim_end();
+        if WINDOWS_RESERVED_NAMES
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(stem))
+        {
+            return Err(anyhow!("'{}' is a reserved name", name));
+        }
+    }
+
+    if rules
+        .filename_reserved_names
+        .iter()
+        .any(|r| r.to_lowercase() == name.to_lowercase())
+    {
+        return Err(anyhow!("'{}' is a reserved name", name));
+    }
+
+    Ok(())
+}
+
+/// Validate password length and strength
+/// `user_inputs` are values (username, email) that should not make up the password
+pub fn validate_password(
+    password: &str,
+    user_inputs: &[&str],
+    rules: &ValidationConfig,
+) -> Result<()> {
+    let length = password.chars().count();
+
+    if length < rules.password_min_length {
+        return Err(anyhow!(
+            "Password must be at least {} characters",
+            rules.password_min_length
+        ));
+    }
+
+    if length > rules.password_max_length {
+        return Err(anyhow!(
+            "Password must be at most {} characters",
+            rules.password_max_length
+        ));
+    }
+
+    if password_strength_score(password, user_inputs) < rules.password_min_score {
+        return Err(anyhow!(
+            "Password is too weak. Use a longer password mixing letters, digits and symbols"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Estimate password strength on a 0-4 scale (zxcvbn-style)
+///
+/// The estimate is the log10 of brute-force guesses over the character classes
+/// used, where repeated or sequential characters and embedded user inputs
+/// contribute little to the effective length.
+pub fn password_strength_score(password: &str, user_inputs: &[&str]) -> u8 {
+    let lowercase = password.to_lowercase();
+    if COMMON_PASSWORDS.contains(&lowercase.as_str()) {
+        return 0;
+    }
+
+    let chars: Vec<char> = password.chars().collect();
+    let mut effective_length = 0.0_f64;
+    for (i, c) in chars.iter().enumerate() {
+        let predictable = i > 0 && {
+            let prev = chars[i - 1] as i64;
+            let diff = *c as i64 - prev;
+            diff.abs() <= 1
+        };
+        effective_length += if predictable { 0.25 } else { 1.0 };
+    }
+
+    for input in user_inputs {
+        let input = input.to_lowercase();
+        if input.chars().count() >= 3 && lowercase.contains(&input) {
+            effective_length -= input.chars().count() as f64 * 0.75;
+        }
+    }
+
+    let log_guesses = effective_length.max(0.0) * (charset_size(&chars) as f64).log10();
+
+    match log_guesses {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+/// Size of the character pool an attacker would have to search
+fn charset_size(chars: &[char]) -> u32 {
+    let mut size = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        size += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100;
+    }
+    size.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> ValidationConfig {
+        ValidationConfig {
+            username_min_length: 3,
+            username_max_length: 16,
+            username_allowed_symbols: "_-.".to_string(),
+            check_email_format: true,
+            password_min_length: 8,
+            password_max_length: 64,
+            password_min_score: 2,
+            filename_max_length: 32,
+            filename_windows_compatible: true,
+            filename_forbidden_chars: "<>".to_string(),
+            filename_reserved_names: vec!["desktop.ini".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("tomy_331", &rules()).is_ok());
+        assert!(validate_username("ab", &rules()).is_err());
+        assert!(validate_username("has space", &rules()).is_err());
+        assert!(validate_username("semi;colon", &rules()).is_err());
+        assert!(validate_username("a_very_long_username_here", &rules()).is_err());
+    }
+
+    #[test]
+    fn test_validate_email() {
+        assert!(validate_email("user@example.com", &rules()).is_ok());
+        assert!(validate_email("user@localhost", &rules()).is_err());
+        assert!(validate_email("user@@example.com", &rules()).is_err());
+        assert!(validate_email("@example.com", &rules()).is_err());
+        assert!(validate_email("user@example..com", &rules()).is_err());
+        assert!(validate_email("", &rules()).is_err());
+    }
+
+    #[test]
+    fn test_validate_profile_fields() {
+        assert!(validate_display_name("Tomy Tang").is_ok());
+        assert!(validate_display_name("   ").is_err());
+        assert!(validate_display_name("tab\there").is_err());
+        assert!(validate_display_name(&"x".repeat(65)).is_err());
+
+        assert!(validate_locale("en").is_ok());
+        assert!(validate_locale("zh-CN").is_ok());
+        assert!(validate_locale("zh_Hant_TW").is_ok());
+        assert!(validate_locale("e").is_err());
+        assert!(validate_locale("en-").is_err());
+        assert!(validate_locale("en US").is_err());
+
+        assert!(validate_timezone("UTC").is_ok());
+        assert!(validate_timezone("Asia/Shanghai").is_ok());
+        assert!(validate_timezone("America/Argentina/Salta").is_ok());
+        assert!(validate_timezone("Etc/GMT+8").is_ok());
+        assert!(validate_timezone("Asia//Shanghai").is_err());
+        assert!(validate_timezone("../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_validate_folder_style() {
+        assert!(validate_hex_color("#3b82f6").is_ok());
+        assert!(validate_hex_color("#FFF").is_ok());
+        assert!(validate_hex_color("3b82f6").is_err());
+        assert!(validate_hex_color("#3b82f").is_err());
+        assert!(validate_hex_color("#ggghhh").is_err());
+
+        assert!(validate_icon("📷").is_ok());
+        assert!(validate_icon("folder-music").is_ok());
+        assert!(validate_icon("").is_err());
+        assert!(validate_icon("two words").is_err());
+    }
+
+    #[test]
+    fn test_validate_filename() {
+        assert!(validate_filename("report.pdf", &rules()).is_ok());
+        assert!(validate_filename(".bashrc", &rules()).is_ok());
+        assert!(validate_filename("console.log", &rules()).is_ok());
+        assert!(validate_filename("照片 2024.jpg", &rules()).is_ok());
+        assert!(validate_filename("", &rules()).is_err());
+        assert!(validate_filename("..", &rules()).is_err());
+        assert!(validate_filename("a/b", &rules()).is_err());
+        assert!(validate_filename("a\\b", &rules()).is_err());
+        assert!(validate_filename("line\nbreak", &rules()).is_err());
+        assert!(validate_filename(&"x".repeat(33), &rules()).is_err());
+        assert!(validate_filename("a<b", &rules()).is_err());
+        assert!(validate_filename("CON", &rules()).is_err());
+        assert!(validate_filename("nul.txt", &rules()).is_err());
+        assert!(validate_filename("Com1.tar.gz", &rules()).is_err());
+        assert!(validate_filename("notes.", &rules()).is_err());
+        assert!(validate_filename("notes ", &rules()).is_err());
+        assert!(validate_filename("Desktop.INI", &rules()).is_err());
+
+        let permissive = ValidationConfig {
+            filename_windows_compatible: false,
+            ..rules()
+        };
+        assert!(validate_filename("CON", &permissive).is_ok());
+        assert!(validate_filename("notes.", &permissive).is_ok());
+    }
+
+    #[test]
+    fn test_password_strength_score() {
+        assert_eq!(password_strength_score("password", &[]), 0);
+        assert!(password_strength_score("abcdefgh", &[]) < 2);
+        assert!(password_strength_score("Tomy0331.", &[]) >= 3);
+        assert!(
+            password_strength_score("alice2024", &["alice"])
+                < password_strength_score("alice2024", &[])
+        );
+    }
+
+    #[test]
+    fn test_validate_password() {
+        assert!(validate_password("short", &[], &rules()).is_err());
+        assert!(validate_password("12345678", &[], &rules()).is_err());
+        assert!(validate_password("Correct-Horse-7", &["tomy"], &rules()).is_ok());
+    }
+}